@@ -0,0 +1,51 @@
+// Starts `hybrid-transpiler lsp` for C++ files and shows the translation
+// it publishes (hybrid/translation) as a read-only virtual document.
+const vscode = require('vscode');
+const { LanguageClient } = require('vscode-languageclient/node');
+
+const translations = new Map();   // translationUri -> text
+const changed = new vscode.EventEmitter();
+let client;
+
+function activate(context) {
+    const config = vscode.workspace.getConfiguration('hybridTranspiler');
+    const target = config.get('target');
+
+    client = new LanguageClient('hybridTranspiler', 'Hybrid Transpiler',
+        { command: config.get('path'), args: ['lsp'] },
+        {
+            documentSelector: [{ scheme: 'file', language: 'cpp' }],
+            initializationOptions: { target, std: config.get('std') }
+        });
+
+    const scheme = 'hybrid-' + target;
+    context.subscriptions.push(vscode.workspace.registerTextDocumentContentProvider(scheme, {
+        onDidChange: changed.event,
+        provideTextDocumentContent: uri => translations.get(uri.toString()) || ''
+    }));
+
+    context.subscriptions.push(vscode.commands.registerCommand('hybridTranspiler.showTranslation', async () => {
+        const editor = vscode.window.activeTextEditor;
+        if (!editor) return;
+        const result = await client.sendRequest('hybrid/translation',
+            { textDocument: { uri: editor.document.uri.toString() } });
+        translations.set(result.translationUri, result.text);
+        const document = await vscode.workspace.openTextDocument(vscode.Uri.parse(result.translationUri));
+        await vscode.languages.setTextDocumentLanguage(document, result.languageId);
+        await vscode.window.showTextDocument(document, { viewColumn: vscode.ViewColumn.Beside, preview: true });
+    }));
+
+    client.start().then(() => {
+        client.onNotification('hybrid/translation', params => {
+            if (!params.success) return;   // Keep the last good translation
+            translations.set(params.translationUri, params.text);
+            changed.fire(vscode.Uri.parse(params.translationUri));
+        });
+    });
+}
+
+function deactivate() {
+    return client ? client.stop() : undefined;
+}
+
+module.exports = { activate, deactivate };
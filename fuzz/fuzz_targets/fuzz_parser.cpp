@@ -0,0 +1,7 @@
+// libFuzzer target: parser and analysis passes
+#include "fuzz.h"
+
+extern "C" int LLVMFuzzerTestOneInput(const uint8_t* data, size_t size) {
+    hybrid::transpileFuzz(data, size, hybrid::FuzzStages::Parser);
+    return 0;
+}
@@ -0,0 +1,7 @@
+// libFuzzer target: parser, analysis and Rust/Go code generation
+#include "fuzz.h"
+
+extern "C" int LLVMFuzzerTestOneInput(const uint8_t* data, size_t size) {
+    hybrid::transpileFuzz(data, size, hybrid::FuzzStages::EndToEnd);
+    return 0;
+}
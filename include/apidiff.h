@@ -0,0 +1,79 @@
+#ifndef HYBRID_APIDIFF_H
+#define HYBRID_APIDIFF_H
+
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * One item of a public API, under its Rust name
+ */
+struct ApiItem {
+    enum Kind {
+        Type,       // struct, enum, class
+        Function,   // Free function
+        Method,     // Constructors are `new`
+        TraitImpl   // Destructor (Drop), copy constructor (Clone), operators (PartialEq, Add...)
+    };
+
+    Kind kind = Function;
+    std::string owner;          // Type of a method or trait impl
+    std::string name;           // Trait name for a trait impl
+    std::string signature;      // Rust items: fn parse(&mut self, text: &str) -> i32
+    size_t arity = 0;           // Parameters, self not counted
+    bool has_self = false;
+    std::string cpp;            // C++ items: the declaration, Parser::parse(text, strict)
+};
+
+/**
+ * A public C++ item the crate does not match
+ */
+struct ApiMismatch {
+    enum Kind {
+        Missing,    // Neither under its name nor renamed
+        Changed,    // Same name, other signature
+        Renamed     // Found under another name with the same signature
+    };
+
+    Kind kind = Missing;
+    std::string cpp;            // The C++ declaration
+    std::string message;
+};
+
+/**
+ * Result of comparing C++ sources with a generated crate
+ */
+struct ApiDiff {
+    size_t cpp_items = 0;
+    size_t matched = 0;
+    std::vector<ApiMismatch> mismatches;
+};
+
+/**
+ * Public API of C++ sources: public members of classes and structs, free
+ * functions and enums, scanned from the text without the translator's
+ * parser, so what it skips is still seen
+ */
+std::vector<ApiItem> cppApiSurface(const std::string& source);
+
+/**
+ * Public API of Rust code: pub items, pub methods of impl blocks, trait
+ * impls and derives
+ */
+std::vector<ApiItem> rustApiSurface(const std::string& code);
+
+/**
+ * Compare the public API of the C++ sources with that of the crate
+ *
+ * Signatures are compared with a fresh translation of the C++, so a
+ * parameter type changed by hand is found; an item the translation drops
+ * is only compared by name and parameter count.
+ */
+ApiDiff diffApi(const std::vector<std::string>& cpp_sources, const std::vector<std::string>& rust_sources);
+
+const char* apiMismatchKindName(ApiMismatch::Kind kind);
+
+} // namespace hybrid
+
+#endif // HYBRID_APIDIFF_H
@@ -0,0 +1,100 @@
+#ifndef HYBRID_BUILD_DATABASE_H
+#define HYBRID_BUILD_DATABASE_H
+
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Preprocessor environment a file is compiled with
+ *
+ * Macros predefined by the compiler and the target (_WIN32, __linux__,
+ * __cplusplus, _MSC_VER...) come first, then -D and -U in command-line
+ * order, so a later flag wins as it does for the compiler.
+ */
+struct BuildConfiguration {
+    std::vector<std::pair<std::string, std::string>> defines;  // Name, replacement ("1" for -DNAME)
+    std::vector<std::string> undefines;                          // -U, applied after the defines
+    std::vector<std::string> include_paths;                      // -I, -iquote, -isystem; absolute
+    std::string target;                                          // --target triple, empty for the host
+    std::string standard;                                        // -std=c++17 as c++17, empty if not given
+
+    bool empty() const { return defines.empty() && undefines.empty() && include_paths.empty(); }
+};
+
+/**
+ * One entry of compile_commands.json
+ */
+struct CompileCommand {
+    std::string directory;                  // Working directory of the compiler
+    std::string file;                       // Absolute and normalized
+    std::string output;                     // Object file, empty if not given
+    std::vector<std::string> arguments;     // Compiler first; split from "command" if needed
+};
+
+/**
+ * Build database: compile_commands.json as written by CMake
+ * (CMAKE_EXPORT_COMPILE_COMMANDS), Bear or Ninja
+ */
+class BuildDatabase {
+public:
+    /**
+     * @param error Set to the reason when the text is rejected
+     * @return false if the text is not a compilation database
+     */
+    bool parse(const std::string& text, std::string& error);
+
+    const std::vector<CompileCommand>& getCommands() const { return commands_; }
+
+    /**
+     * Commands compiling a file; more than one when it is built for several
+     * targets or configurations
+     */
+    std::vector<const CompileCommand*> commandsFor(const std::string& path) const;
+
+    /**
+     * Configuration of a file
+     *
+     * When the file is compiled more than once, target_config picks the
+     * command: it names the --target triple, a path component of the
+     * output or build directory (build/linux-debug), or a macro the
+     * command defines (-DPLATFORM_LINUX). Without it the first command is
+     * used and `note` says so.
+     * @param error Set when target_config matches none of the commands
+     * @return false if the file has no matching command
+     */
+    bool select(const std::string& path, const std::string& target_config, BuildConfiguration& config,
+                std::string& error, std::string& note) const;
+
+    /**
+     * -D, -U, -I, --target and -std of a command (GCC, Clang and MSVC spellings)
+     */
+    static BuildConfiguration configurationOf(const CompileCommand& command);
+
+    /**
+     * CMake target a command builds, from the CMakeFiles/<target>.dir
+     * directory of its object file; empty for other build systems
+     */
+    static std::string targetOf(const CompileCommand& command);
+
+    /**
+     * Whether a command matches a --target-config name
+     */
+    static bool matchesConfig(const CompileCommand& command, const std::string& target_config);
+
+    /**
+     * A shell command line split into arguments, quotes removed
+     */
+    static std::vector<std::string> splitCommand(const std::string& command);
+
+private:
+    std::vector<CompileCommand> commands_;
+
+    static std::string normalize(const std::string& directory, const std::string& path);
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_BUILD_DATABASE_H
@@ -2,11 +2,25 @@
 #define HYBRID_CODEGEN_H
 
 #include "ir.h"
+#include "mapping_packs.h"
 #include <string>
 #include <sstream>
+#include <set>
 
 namespace hybrid {
 
+/**
+ * Counts collected during code generation (reported by --metrics)
+ */
+struct GenerationStats {
+    size_t functions_translated = 0;
+    size_t functions_stubbed = 0;   // Emitted with a todo!()/panic placeholder body
+    size_t clones_inserted = 0;     // Implicit C++ copies made explicit with .clone()
+    size_t functions_retained = 0;  // Kept in C++ and called through extern "C"
+    size_t memory_issues_fixed = 0;     // C++ memory bugs the translation cannot have
+    size_t memory_issues_preserved = 0; // Kept behind unsafe or in C++
+};
+
 /**
  * Base class for code generators
  */
@@ -21,14 +35,87 @@ public:
      */
     virtual std::string generate(const IR& ir) = 0;
 
+    /**
+     * Enable or disable safe-mode restrictions (enabled by default)
+     */
+    void setSafetyChecks(bool enabled) { safety_checks_ = enabled; }
+
+    /**
+     * Library types to translate through mapping packs (--mapping-pack);
+     * the first pack mapping a type wins
+     */
+    void setMappingPacks(const std::vector<MappingPack>& packs) { mapping_packs_ = packs; }
+
+    /**
+     * Widths of long and unsigned long (LP64 by default, LLP64 for Windows)
+     */
+    void setDataModel(DataModel model) { data_model_ = model; }
+
+    /**
+     * Diagnostics reported by the last generate() call
+     */
+    const std::vector<Diagnostic>& getDiagnostics() const { return diagnostics_; }
+
+    /**
+     * Statistics from the last generate() call
+     */
+    const GenerationStats& getStats() const { return stats_; }
+
+    /**
+     * Rules applied by the last generate() call, in the order they fired
+     */
+    const std::vector<Decision>& getDecisions() const { return decisions_; }
+
+    /**
+     * Enumerator name in the target: NOT_FOUND / kNotFound -> NotFound
+     */
+    static std::string enumeratorName(const std::string& name);
+
 protected:
     std::stringstream output_;
     int indent_level_ = 0;
+    bool safety_checks_ = true;
+    std::vector<Diagnostic> diagnostics_;
+    GenerationStats stats_;
+    std::vector<Decision> decisions_;
+    std::string decision_context_;   // Declaration being generated
+    std::string decision_subject_;   // Field, parameter or return type being converted
+    std::vector<MappingPack> mapping_packs_;
+    DataModel data_model_ = DataModel::LP64;
 
     void indent() { indent_level_++; }
     void dedent() { indent_level_--; }
     void writeLine(const std::string& line);
     void writeIndent();
+    void report(Diagnostic::Severity severity, const std::string& message,
+                const std::string& context = "");
+    void countFunction(bool translated);
+    // Record a rule for the current declaration; the same decision is kept once
+    void decide(const std::string& rule, const std::string& subject, const std::string& outcome,
+                const std::string& reason);
+
+    // Message of an enumerator: NOT_FOUND -> "not found"
+    static std::string enumeratorMessage(const std::string& name);
+
+    // Name of the variadic arguments of a C variadic function: its va_list, or "args"
+    static std::string varargsName(const Function& func);
+
+    // Mapping of a class or template type (boost::optional<int>) in the enabled packs
+    const TypeMapping* findTypeMapping(const std::string& type_name) const;
+    // Pattern with $0, $1, ... replaced by args; reports the mapping's note once
+    std::string applyTypeMapping(const TypeMapping& mapping, const std::string& pattern,
+                                 const std::vector<std::string>& args);
+    static std::string substituteArguments(const std::string& pattern, const std::vector<std::string>& args);
+    // Warning not already reported for this context by this generate() call
+    void reportOnce(const std::string& message, const std::string& context = "");
+
+    // Width and signedness of a C++ integer type under the data model
+    struct IntegerFormat {
+        int bits = 32;
+        bool is_signed = true;
+        bool pointer_sized = false;   // size_t, ptrdiff_t, intptr_t: usize/isize, int/uint
+    };
+    IntegerFormat integerFormat(const std::string& type_name) const;
 };
 
 /**
@@ -38,15 +125,124 @@ class RustCodeGenerator : public CodeGenerator {
 public:
     std::string generate(const IR& ir) override;
 
+    /**
+     * Rust identifier for a C++ name (snake_case, keywords escaped)
+     */
+    static std::string sanitizeName(const std::string& name);
+
+    /**
+     * Trait declaring the methods of a deferred class (--defer): Engine -> EngineMethods
+     */
+    static std::string deferredTraitName(const std::string& class_name);
+
+    /**
+     * One sorted, deduplicated use block for generated Rust: std types and
+     * traits become imports (std::collections::HashMap<K, V> -> HashMap<K, V>,
+     * std::fmt::Result -> fmt::Result) and types written bare get theirs.
+     * Functions keep their path (std::mem::take), and a type stays
+     * qualified where its short name would be ambiguous or shadow a
+     * declaration. Existing use declarations are kept and merged in.
+     */
+    static std::string organizeImports(const std::string& code);
+
+    /**
+     * Derive thiserror::Error for error-code enums instead of writing
+     * Display and Error impls by hand
+     */
+    void setUseThiserror(bool enabled) { use_thiserror_ = enabled; }
+
+    /**
+     * How C variadic functions are translated (slice of VarArg by default)
+     */
+    void setVarargsStrategy(VarargsStrategy strategy) { varargs_ = strategy; }
+
+    /**
+     * Keep custom allocators as Allocator type parameters and generate
+     * Allocator impls for class-specific operator new (nightly allocator_api)
+     */
+    void setAllocatorApi(bool enabled) { allocator_api_ = enabled; }
+
+    /**
+     * How classes pointing at themselves or at each other are translated
+     * (raw pointers, reported, by default)
+     */
+    void setSelfRefStrategy(SelfRefStrategy strategy) { self_ref_ = strategy; }
+
+    /**
+     * How direct libc calls are translated (std equivalents by default)
+     */
+    void setLibcStrategy(LibcStrategy strategy) { libc_ = strategy; }
+
+    /**
+     * Replace the option parsing loop of main with a clap derive struct
+     */
+    void setClap(bool enabled) { clap_ = enabled; }
+
+    /**
+     * Generate a library crate: C++ access control decides visibility, doc
+     * comments are kept, type aliases become pub type items and main is left out
+     */
+    void setLibraryMode(bool enabled) { library_mode_ = enabled; }
+
+    /**
+     * The crate is one of a workspace: protected members are pub, since
+     * derived classes may be in another crate (pub(crate) otherwise)
+     */
+    void setWorkspaceMember(bool enabled) { workspace_member_ = enabled; }
+
+    /**
+     * Derive strum::Display and strum::EnumString for enums with
+     * hand-written string conversions instead of writing the impls
+     */
+    void setStrum(bool enabled) { strum_ = enabled; }
+
 private:
+    bool use_thiserror_ = false;
+    VarargsStrategy varargs_ = VarargsStrategy::Slice;
+    bool allocator_api_ = false;
+    bool uses_allocator_api_ = false;  // Output needs #![feature(allocator_api)]
+    SelfRefStrategy self_ref_ = SelfRefStrategy::Report;
+    LibcStrategy libc_ = LibcStrategy::Std;
+    bool clap_ = false;
+    std::set<std::string> arena_classes_;    // Linked through Id<T> (--self-ref arena)
+    bool pinned_ = false;                    // Class being generated is pinned (--self-ref pin)
+    bool library_mode_ = false;
+    bool workspace_member_ = false;
+    bool strum_ = false;
+    const ClassDecl* member_class_ = nullptr;  // Class whose members are being generated
+
+    // generate() on a deep stack
+    std::string generateSource(const IR& ir);
+
+    std::string memberVisibility(const std::string& member) const;
+    void writeDocComment(const std::string& doc);
+    void generateTypeAlias(const TypeAlias& alias);
+
     void generateClass(const ClassDecl& class_decl);
+    void generateEnum(const EnumDecl& enum_decl);
     void generateFunction(const Function& func);
+    // pub fn name(params) -> Ret, as generateFunction writes it
+    std::string functionSignature(const Function& func, const std::string& visibility);
     void generateVariable(const Variable& var);
     void generateTryCatchAsResult(const Function& func);
 
+    // Classes left for a later migration phase (--defer)
+    void generateDeferredStubs(const ClassDecl& class_decl, const Function* copy_ctor, const Function* destructor);
+    void writeDeferredDoc(const std::string& doc, const std::vector<std::string>& reference);
+    static std::string deferredLocation(const ClassDecl& class_decl, const SourceSpan& span);
+    static std::string cppDeclaration(const ClassDecl& class_decl, const Function& method);
+
+    // Special member functions
+    void generateCloneImpl(const ClassDecl& class_decl, const Function& copy_ctor);
+    void generateDropImpl(const ClassDecl& class_decl, const Function& destructor);
+
     // Inheritance/Trait code generation
     void generateTraitImplementations(const ClassDecl& class_decl);
     void generateTraitForBaseClass(const std::string& base_class_name, const ClassDecl& derived_class);
+    void generateBaseSubobjects(const ClassDecl& class_decl);
+    std::string traitName(const std::string& base_class_name);
+    std::string convertUpcasts(const Function& func, const std::string& body);
+    std::set<std::string> traits_defined_;  // Each base's trait is defined once per file
 
     // Threading code generation
     void generateThreadingCode(const Function& func);
@@ -62,13 +258,104 @@ private:
     void generateAsyncTask(const AsyncTaskInfo& task);
     void generateFuture(const FutureInfo& future);
 
+    // Function body translation
+    std::string translateBody(const Function& func);
+    std::string convertMoveSemantics(const Function& func, const std::string& body);
+    std::string convertCopySemantics(const Function& func, const std::string& body);
+    std::string convertGuards(const Function& func, const std::string& body);
+    std::string convertLayoutQueries(const Function& func, const std::string& body);
+    std::string convertBindings(const Function& func, const std::string& body);
+    std::string convertFormatting(const Function& func, const std::string& body);
+    std::string convertFormatString(const std::string& format, std::string& error);
+    std::string convertLogging(const Function& func, const std::string& body);
+    std::string convertPrintfFormat(const std::string& format, std::string& error);
+    std::string convertTuples(const Function& func, const std::string& body);
+    std::string convertStatusSites(const Function& func, const std::string& body);
+    std::string convertAlgorithms(const Function& func, const std::string& body);
+    std::string convertFunctors(const Function& func, const std::string& body);
+    std::string convertMemberPointers(const Function& func, const std::string& body);
+    std::string convertVarargs(const Function& func, const std::string& body);
+
+    // C variadic functions
+    std::string varArgVariant(const std::shared_ptr<Type>& type, std::string& payload);
+    void generateVarArgEnum(const IR& ir);
+    void generateVarargsMacro(const Function& func, const std::string& class_name);
+    void generateExternDeclaration(const Function& func);
+    std::string convertFfiType(const std::shared_ptr<Type>& type);
+
+    // Self-referential and intrusive classes
+    void generateArenaTypes();
+    std::vector<std::string> arenaNodes(const ClassDecl& class_decl) const;
+    std::string arenaField(const std::string& node);
+    void generateArenaIndex(const ClassDecl& class_decl, const std::string& node);
+    std::string convertGraphSites(const Function& func, const std::string& body);
+
+    // Use after free, double delete, overruns, uninitialized reads found in the C++
+    void reportMemoryIssues(const IR& ir);
+    void reportMemoryIssue(const MemoryIssueInfo& issue, bool retained, const std::string& context);
+
+    // Unsigned wraparound and conversions between integer types
+    std::string convertIntegerSites(const Function& func, const std::string& body);
+    std::string wrappingArithmetic(const std::string& expr, const IntegerSiteInfo& site);
+    std::string integerConversion(const Function& func, const IntegerSiteInfo& site);
+
+    // Direct POSIX/C library calls
+    std::string convertLibcCalls(const Function& func, const std::string& body);
+    std::string convertLibcCall(const Function& func, const LibcCallInfo& call);
+    std::string convertOpenCall(const Function& func, const std::vector<std::string>& args);
+
+    // (T* ptr, size_t len) pairs, std::span parameters and new T[n] buffers as slices
+    std::string convertSlices(const Function& func, const std::string& body);
+
+    // Constructors that can fail midway: fn try_new() -> Result<Self, _>
+    void generateTryNew(const Function& func);
+    std::string constructedValue(const ClassDecl& class_decl);
+    std::string constructionError(const ConstructionSiteInfo& site);
+    std::string convertConstructionSites(const Function& func, const std::string& body);
+
+    // Literals and std::numeric_limits
+    std::string convertLiterals(const Function& func, const std::string& body);
+    std::string numericLiteral(const std::string& literal);
+    std::string stringLiteral(const Function& func, const std::vector<std::string>& pieces);
+    std::string charLiteral(const Function& func, const std::string& prefix, const std::string& content);
+    static std::string decodeEscapes(const std::string& content, bool wide);
+    std::string numericLimit(const Function& func, const NumericLimitInfo& limit);
+
+    // int main(int argc, char** argv) and exit()
+    void generateEntryPoint(const Function& func);
+    std::string convertEntrySites(const Function& func, const std::string& body);
+    std::string entrySite(const Function& func, const EntrySiteInfo& site);
+    void generateCliStruct(const CommandLineInfo& command_line);
+    std::string clapParse(const CommandLineInfo& command_line, const std::string& indentation);
+    std::string cliFieldName(const CliOptionInfo& option);
+    std::string cliValueType(const std::shared_ptr<Type>& type);
+    static bool cliIsVector(const std::shared_ptr<Type>& type);
+
+    // Functions kept in C++ (signal handlers, setjmp/longjmp) and their callbacks
+    void generateRetainedMethod(const ClassDecl& class_decl, const Function& method);
+    void generateRetainedShims(const ClassDecl& class_decl);
+    void generateTrampoline(const ClassDecl& class_decl, const Function& method, const CallbackInfo& callback);
+    static std::shared_ptr<Type> callbackType(const Function& method, const CallbackInfo& callback);
+    std::string callbackClosure(const std::shared_ptr<Type>& type, int context_slot);
+
+    // Placement new, explicit destructor calls and custom allocators
+    std::string convertAllocations(const Function& func, const std::string& body);
+    std::string convertContainerAllocator(const std::shared_ptr<Type>& type, const std::string& container);
+    void generateClassAllocator(const ClassDecl& class_decl);
+
     // Template code generation
-    std::string convertTemplateParametersToRust(const std::vector<TemplateParameter>& params);
+    std::string convertTemplateParametersToRust(const std::vector<TemplateParameter>& params,
+                                                bool with_defaults = false);
+    std::string constGenericType(const TemplateParameter& param);
+    void checkConstGenerics(const ClassDecl& class_decl);
     std::string convertTemplateArgsToRust(const std::vector<TemplateParameter>& params);
+    bool specializedPerInstantiation(const ClassDecl& class_decl, const Function& method);
+    Function selectConstexprBranches(const Function& method, size_t instantiation);
+    void checkConstexprIfs(const ClassDecl& class_decl, const Function& method);
+    void generateInstantiationImpls(const ClassDecl& class_decl, bool after_impl);
 
     std::string convertType(const std::shared_ptr<Type>& type);
     std::string convertSmartPointer(const std::shared_ptr<Type>& type);
-    std::string sanitizeName(const std::string& name);
 };
 
 /**
@@ -79,7 +366,11 @@ public:
     std::string generate(const IR& ir) override;
 
 private:
+    // generate() on a deep stack
+    std::string generateSource(const IR& ir);
+
     void generateClass(const ClassDecl& class_decl);
+    void generateEnum(const EnumDecl& enum_decl);
     void generateFunction(const Function& func, const std::string& receiver_type = "");
     void generateVariable(const Variable& var);
     void generateTryCatchAsError(const Function& func);
@@ -101,6 +392,10 @@ private:
     void generateChannelOperation(const AsyncOperation& op);
     void generateAsyncTask(const AsyncTaskInfo& task);
 
+    // int main(int argc, char** argv) and exit(): os.Args, os.Exit
+    std::string convertEntrySites(const Function& func);
+    std::string entrySite(const EntrySiteInfo& site);
+
     std::string convertType(const std::shared_ptr<Type>& type);
     std::string sanitizeName(const std::string& name);
     std::string capitalize(const std::string& name);
@@ -0,0 +1,41 @@
+#ifndef HYBRID_EXPLAIN_H
+#define HYBRID_EXPLAIN_H
+
+#include "ir.h"
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * What the translation did with one symbol and why (--explain)
+ */
+struct Explanation {
+    std::string symbol;
+    std::string kind;                       // class, enum, method or function
+    std::vector<Decision> decisions;        // In the order the rules fired
+    std::vector<Diagnostic> diagnostics;    // Analysis and generation reports behind them
+
+    /**
+     * One line per rule with its ID, then the reason, then the diagnostics
+     */
+    std::string toString() const;
+};
+
+/**
+ * Decisions and diagnostics of a symbol: a class (with its members), a
+ * Class::method or a function, spelled as for --entry
+ * @return false if the IR has no such symbol
+ */
+bool explainSymbol(const IR& ir, const std::vector<Decision>& decisions, const std::vector<Diagnostic>& diagnostics,
+                   const std::string& symbol, Explanation& explanation);
+
+/**
+ * ID and summary of every rule a decision can cite
+ */
+const std::vector<std::pair<std::string, std::string>>& explainRules();
+
+} // namespace hybrid
+
+#endif // HYBRID_EXPLAIN_H
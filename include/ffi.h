@@ -12,7 +12,7 @@
 #include <string>
 #include <vector>
 #include <memory>
-#include <unordered_map>
+#include <map>
 
 namespace hybrid_transpiler {
 namespace ffi {
@@ -122,9 +122,9 @@ private:
     /**
      * @brief Type mapping tables
      */
-    std::unordered_map<std::string, std::string> cpp_to_c_types_;
-    std::unordered_map<std::string, std::string> cpp_to_rust_types_;
-    std::unordered_map<std::string, std::string> cpp_to_go_types_;
+    std::map<std::string, std::string> cpp_to_c_types_;
+    std::map<std::string, std::string> cpp_to_rust_types_;
+    std::map<std::string, std::string> cpp_to_go_types_;
 
     /**
      * @brief Initialize type mapping tables
@@ -0,0 +1,82 @@
+#ifndef HYBRID_FILE_SYSTEM_H
+#define HYBRID_FILE_SYSTEM_H
+
+#include <string>
+#include <map>
+#include <memory>
+
+namespace hybrid {
+
+/**
+ * File access used by the transpiler
+ *
+ * The core (parser, analysis, code generation) works on strings only; all
+ * reading and writing goes through this interface so the engine can run
+ * where no disk is available (e.g. WebAssembly in the browser).
+ */
+class FileSystem {
+public:
+    virtual ~FileSystem() = default;
+
+    /**
+     * @return false if the file cannot be read
+     */
+    virtual bool readFile(const std::string& path, std::string& content) = 0;
+
+    /**
+     * Write a file, creating missing parent directories
+     * @return false if the file cannot be written
+     */
+    virtual bool writeFile(const std::string& path, const std::string& content) = 0;
+};
+
+/**
+ * The host file system
+ */
+class DiskFileSystem : public FileSystem {
+public:
+    bool readFile(const std::string& path, std::string& content) override;
+    bool writeFile(const std::string& path, const std::string& content) override;
+};
+
+/**
+ * In-memory files keyed by path, for sandboxed and embedded use
+ */
+class MemoryFileSystem : public FileSystem {
+public:
+    void addFile(const std::string& path, const std::string& content) { files_[path] = content; }
+    const std::map<std::string, std::string>& getFiles() const { return files_; }
+
+    bool readFile(const std::string& path, std::string& content) override;
+    bool writeFile(const std::string& path, const std::string& content) override;
+
+private:
+    std::map<std::string, std::string> files_;
+};
+
+/**
+ * Reads from another file system and records every file written
+ *
+ * With write_through, writes also reach the other file system; without,
+ * they stay in memory (a dry run) and later reads see them.
+ */
+class RecordingFileSystem : public FileSystem {
+public:
+    RecordingFileSystem(std::shared_ptr<FileSystem> base, bool write_through)
+        : base_(std::move(base)), write_through_(write_through) {}
+
+    const std::map<std::string, std::string>& getWritten() const { return written_; }
+    void clear() { written_.clear(); }
+
+    bool readFile(const std::string& path, std::string& content) override;
+    bool writeFile(const std::string& path, const std::string& content) override;
+
+private:
+    std::shared_ptr<FileSystem> base_;
+    bool write_through_;
+    std::map<std::string, std::string> written_;
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_FILE_SYSTEM_H
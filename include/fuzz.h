@@ -0,0 +1,33 @@
+#ifndef HYBRID_FUZZ_H
+#define HYBRID_FUZZ_H
+
+#include "transpiler.h"
+#include <cstddef>
+#include <cstdint>
+
+namespace hybrid {
+
+/**
+ * Which pipeline stages a fuzz input exercises
+ */
+enum class FuzzStages {
+    Parser,         // Parsing and analysis passes only
+    EndToEnd        // Parsing, analysis, Rust and Go code generation
+};
+
+/**
+ * Fuzzing entry point
+ *
+ * Runs arbitrary bytes through the pipeline. A std::runtime_error (the
+ * parser's rejection of an input) is treated as rejected input; any other
+ * exception propagates, so libFuzzer reports it as a crash along with
+ * hangs and sanitizer reports.
+ *
+ * @return true if the input was accepted by every stage
+ */
+bool transpileFuzz(const uint8_t* data, size_t size,
+                   FuzzStages stages = FuzzStages::EndToEnd);
+
+} // namespace hybrid
+
+#endif // HYBRID_FUZZ_H
@@ -0,0 +1,79 @@
+#ifndef HYBRID_INSTANTIATION_CACHE_H
+#define HYBRID_INSTANTIATION_CACHE_H
+
+#include <cstddef>
+#include <map>
+#include <mutex>
+#include <string>
+
+namespace hybrid {
+
+/**
+ * `if constexpr` branches selected per template instantiation, shared by
+ * the translation units of a project
+ *
+ * Headers are parsed again by every file including them; with the cache,
+ * each chain of Stack<int> is evaluated once per project. The One
+ * Definition Rule makes (template, arguments) name the same code in every
+ * file, and the chain's conditions are part of the key, so templates that
+ * merely share a name do not collide.
+ */
+class InstantiationCache {
+public:
+    struct Selection {
+        int branch = -1;                    // ConstexprIfInfo::selected entry
+        std::string unresolved_reason;
+    };
+
+    /**
+     * @param instantiation Template and arguments: Stack<int,4>
+     * @param chain Conditions of the chain's branches
+     */
+    bool lookup(const std::string& instantiation, const std::string& chain, Selection& selection) const {
+        std::lock_guard<std::mutex> lock(mutex_);
+        auto entry = entries_.find(instantiation);
+        if (entry != entries_.end()) {
+            auto found = entry->second.find(chain);
+            if (found != entry->second.end()) {
+                selection = found->second;
+                hits_++;
+                return true;
+            }
+        }
+        misses_++;
+        return false;
+    }
+
+    void store(const std::string& instantiation, const std::string& chain, const Selection& selection) {
+        std::lock_guard<std::mutex> lock(mutex_);
+        entries_[instantiation][chain] = selection;
+    }
+
+    /**
+     * Distinct instantiations with at least one chain evaluated
+     */
+    size_t size() const {
+        std::lock_guard<std::mutex> lock(mutex_);
+        return entries_.size();
+    }
+
+    size_t hits() const {
+        std::lock_guard<std::mutex> lock(mutex_);
+        return hits_;
+    }
+
+    size_t misses() const {
+        std::lock_guard<std::mutex> lock(mutex_);
+        return misses_;
+    }
+
+private:
+    mutable std::mutex mutex_;
+    std::map<std::string, std::map<std::string, Selection>> entries_;
+    mutable size_t hits_ = 0;
+    mutable size_t misses_ = 0;
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_INSTANTIATION_CACHE_H
@@ -5,6 +5,7 @@
 #include <vector>
 #include <memory>
 #include <map>
+#include <initializer_list>
 
 namespace hybrid {
 
@@ -18,6 +19,7 @@ enum class TypeKind {
     Float,
     Pointer,
     Reference,
+    RValueReference,
     Array,
     Struct,
     Class,
@@ -34,6 +36,7 @@ enum class TypeKind {
     StdUnorderedSet,
     StdString,
     StdPair,
+    StdTuple,
     StdOptional,
     // Threading types
     StdThread,
@@ -66,12 +69,54 @@ public:
     // For composite types
     std::shared_ptr<Type> element_type;  // For pointers, arrays, references
     std::vector<std::shared_ptr<Type>> template_args;
+    std::string array_size;              // Array bound as written: 8, N, N + 1
+
+    // Function pointers: element_type is the return type. Pointers to
+    // members name their class: int C::* and void (C::*)(int) const
+    std::vector<std::shared_ptr<Type>> parameter_types;
+    std::string member_of;
+
+    // Allocator-aware containers: the allocator argument as written
+    // (std::vector<T, Pool<T>>, std::pmr::vector<T>), null if defaulted
+    std::shared_ptr<Type> allocator;
+
+    // shared_ptr whose pointee reaches another thread: Arc instead of Rc,
+    // around a Mutex if the pointee is mutated without synchronization
+    bool thread_shared = false;
+    bool needs_lock = false;
+
+    // Mutex only ever locked from one thread (--runtime-trace): RefCell<()>
+    bool single_threaded = false;
+
+    // Raw pointer to a buffer allocated with new T[n] and freed with
+    // delete[]: Box<[T]>
+    bool owned_array = false;
 
     // Size information
     size_t size_bytes = 0;
     size_t alignment = 0;
 
     explicit Type(TypeKind k) : kind(k) {}
+
+    // const std::string& or std::string_view: &str in Rust
+    bool isBorrowedString() const {
+        return kind == TypeKind::Reference && is_const && element_type &&
+               (element_type->kind == TypeKind::StdString || element_type->name == "std::string" ||
+                element_type->name == "string");
+    }
+};
+
+/**
+ * Source range of a declaration (1-based lines and columns, end inclusive)
+ */
+class SourceSpan {
+public:
+    int begin_line = 0;     // 0 if unknown (e.g. IR built by hand)
+    int begin_column = 0;
+    int end_line = 0;
+    int end_column = 0;
+
+    bool isKnown() const { return begin_line > 0; }
 };
 
 /**
@@ -84,6 +129,8 @@ public:
     bool is_static = false;
     bool is_const = false;
     std::string initializer;
+    SourceSpan span;
+    std::string doc;        // Doc comment above the declaration, without the comment markers
 };
 
 /**
@@ -95,6 +142,7 @@ public:
     std::shared_ptr<Type> type;
     bool has_default = false;
     std::string default_value;
+    SourceSpan span;
 };
 
 /**
@@ -134,12 +182,13 @@ public:
         Template        // template<typename> class C
     };
 
-    Kind kind;
+    Kind kind = Type;
     std::string name;
     std::string default_value;
 
-    // For non-type parameters
-    std::shared_ptr<Type> param_type;
+    // For non-type parameters (the Type enumerator hides the class here)
+    std::shared_ptr<hybrid::Type> param_type;
+    bool is_array_length = false;   // Used as an array bound (usize in Rust)
 
     // Constraints (C++20 concepts)
     std::vector<std::string> constraints;
@@ -166,6 +215,18 @@ public:
     bool joinable = true;
 };
 
+/**
+ * Value handed to another thread: a std::thread argument, or a variable
+ * used by a lambda run on a std::thread or std::async
+ */
+class ThreadCrossingInfo {
+public:
+    std::string value;            // As written: counter, this
+    std::string thread;           // Thread variable; empty for std::async and temporaries
+    std::shared_ptr<Type> type;   // Declared type, null if not found
+    bool captured = false;        // Used by a lambda rather than passed as an argument
+};
+
 /**
  * Mutex/Lock information
  */
@@ -201,6 +262,491 @@ public:
     std::string scope_body;
 };
 
+/**
+ * Scope-bound RAII guard information (lock guards, scope-exit helpers)
+ */
+class GuardInfo {
+public:
+    enum GuardKind {
+        LockGuard,      // std::lock_guard
+        UniqueLock,     // std::unique_lock
+        SharedLock,     // std::shared_lock
+        ScopedLock,     // std::scoped_lock (one or more mutexes)
+        ScopeExit       // gsl::finally, scope_exit, SCOPE_EXIT
+    };
+
+    GuardKind kind = LockGuard;
+    std::string source_text;             // Original C++ declaration text
+    std::string guard_var_name;          // Empty for anonymous guards (SCOPE_EXIT)
+    std::vector<std::string> mutex_names;
+    bool exclusive_on_shared_mutex = false;  // Lock of a std::shared_mutex in write mode
+    std::string cleanup_body;            // Scope-exit callback body
+    bool unlocked_early = false;         // unique_lock::unlock() / dismiss() called
+    std::vector<std::string> refcell_mutexes;  // Of mutex_names: RefCell borrows (--runtime-trace)
+};
+
+/**
+ * Destructuring information (structured bindings, std::tie)
+ */
+class BindingInfo {
+public:
+    enum BindingKind {
+        Declaration,    // auto [a, b] = expr;
+        Tie,            // std::tie(a, b) = expr;
+        RangeFor        // for (auto& [k, v] : range)
+    };
+
+    BindingKind kind = Declaration;
+    std::string source_text;             // Statement, or the `for (...)` header
+    std::vector<std::string> names;      // Bound names in order ("_" for std::ignore)
+    std::vector<bool> reassigned;        // Per name: assigned again later in the body
+    std::string initializer;             // Destructured expression or range
+    bool by_reference = false;           // auto& / auto&& bound to an lvalue
+    bool is_const = false;
+    bool initializer_is_lvalue = false;  // Named object rather than a temporary
+    bool needs_clone = false;            // By-value copy of a non-Copy lvalue
+    std::string struct_name;             // Destructured class; empty for pairs/tuples
+    std::vector<std::string> field_names;  // Class fields bound to each name
+};
+
+/**
+ * String formatting call (std::format, fmt::format, fmt::print)
+ */
+class FormatInfo {
+public:
+    enum FormatKind {
+        Format,         // std::format / fmt::format -> String
+        Print,          // fmt::print / std::print
+        Println,        // fmt::println / std::println
+        FormatTo        // std::format_to(std::back_inserter(s), ...)
+    };
+
+    FormatKind kind = Format;
+    std::string source_text;             // Whole call expression
+    std::string format_string;           // Literal contents without quotes (empty if not a literal)
+    bool is_literal = false;             // Format string is a string literal
+    std::vector<std::string> arguments;  // Arguments after the format string
+    std::string stream;                  // Print target (stdout, stderr, a FILE* or ostream) or format_to buffer
+};
+
+/**
+ * <algorithm>/<numeric> call over a whole container: std::sort(v.begin(), v.end())
+ */
+class AlgorithmInfo {
+public:
+    std::string source_text;             // Call, including an absorbed `*`, `!= v.end()` or erase
+    std::string algorithm;               // sort, find_if, remove_if, erase_if, ...
+    std::string container;               // Owner of the iterator pair
+    std::vector<std::string> arguments;  // Arguments after the iterator pair
+    std::vector<bool> stored_functors;   // Per argument: names an object with operator()
+    std::string output;                  // transform/copy destination container
+    bool back_inserter = false;          // Destination is std::back_inserter(output)
+    std::string end_comparison;          // "!=" or "==" against the end iterator, empty if none
+    bool dereferenced = false;           // *std::max_element(...)
+};
+
+/**
+ * <cstdarg> use in a variadic function body
+ */
+class VarargSiteInfo {
+public:
+    enum SiteKind {
+        ListDeclaration,  // va_list args;
+        Start,            // va_start(args, fmt);
+        Arg,              // va_arg(args, int)
+        End,              // va_end(args);
+        Copy,             // va_copy(dst, args);
+        Forward           // vprintf(fmt, args): va_list handed to another function
+    };
+
+    SiteKind kind = Arg;
+    std::string source_text;
+    std::string list;                    // va_list variable (Copy: the source)
+    std::string target;                  // Copy: destination; Forward: function called
+    std::shared_ptr<Type> type;          // Arg: type read
+};
+
+/**
+ * Manual object lifetime in a function body
+ */
+class AllocationSiteInfo {
+public:
+    enum SiteKind {
+        PlacementNew,     // new (buffer) Widget(args)
+        DestructorCall    // w->~Widget() / w.~Widget()
+    };
+
+    SiteKind kind = PlacementNew;
+    std::string source_text;
+    std::string buffer;                  // PlacementNew: storage; DestructorCall: object
+    std::shared_ptr<Type> type;          // Type constructed or destroyed
+    std::vector<std::string> arguments;  // PlacementNew: constructor arguments
+    bool through_pointer = false;        // DestructorCall: p->~T() rather than obj.~T()
+    bool is_array = false;               // PlacementNew: new (buffer) T[n]
+};
+
+/**
+ * A (T* ptr, size_t len) parameter pair passed as one slice
+ */
+class SliceParamInfo {
+public:
+    std::string pointer;                 // Becomes &[T] / &mut [T]
+    std::string length;                  // Dropped: ptr.len() in the body
+    std::shared_ptr<Type> length_type;   // As declared: size_t, int, ...
+    size_t position = 0;                 // Of the pointer among the parameters
+    bool is_const = false;               // const T*: a shared slice
+};
+
+/**
+ * Slice-related site in a function body
+ */
+class SliceSiteInfo {
+public:
+    enum SiteKind {
+        SpanMethod,       // values.size(), out.subspan(1, n) on a std::span parameter
+        FusedCall,        // checksum(buf, len) of a function whose pair is fused, total(a) of one taking a span
+        NewArray,         // new int[n] held by a Box<[T]> pointer
+        DeleteArray       // delete[] buffer;
+    };
+
+    SiteKind kind = SpanMethod;
+    std::string source_text;
+    std::string target;                  // SpanMethod: the span; FusedCall: callee; New/DeleteArray: pointer
+    std::string method;                  // SpanMethod: size, empty, data, first, last, subspan
+    std::vector<std::string> arguments;  // Method or call arguments as written; NewArray: the count
+    std::vector<SliceParamInfo> fused;   // FusedCall: the callee's pairs
+    std::map<size_t, bool> borrowed;     // FusedCall: arrays passed to span parameters, by position; true if shared
+    std::shared_ptr<Type> type;          // SpanMethod: the span; NewArray: element type
+    bool is_declaration = false;         // NewArray: int* p = new int[n]; declares the pointer
+};
+
+/**
+ * Self-referential or intrusive structure in a class
+ */
+class SelfReferenceInfo {
+public:
+    enum Kind {
+        ThisEscape,       // this stored outside the object: registry.add(this), peer->owner = this
+        InteriorPointer,  // Field pointing into the object itself: cursor = buffer, self_ = this
+        IntrusiveLink     // Field pointing at another object of the class: Node* next
+    };
+
+    Kind kind = ThisEscape;
+    std::string field;                   // InteriorPointer / IntrusiveLink: the pointer field
+    std::string function;                // ThisEscape / InteriorPointer: where it is stored
+    std::string source_text;
+};
+
+/**
+ * Direct POSIX/C library call: open(path, O_RDONLY), memcpy(dst, src, n)
+ */
+class LibcCallInfo {
+public:
+    std::string source_text;
+    std::string function;                // Library function called
+    std::vector<std::string> arguments;  // As written
+    std::vector<LibcCallInfo> nested;    // Library calls in the arguments: write(fd, s, strlen(s))
+};
+
+/**
+ * Callback parameter of a function kept in C++: Rust closures reach it
+ * through an extern "C" trampoline and a context pointer
+ */
+class CallbackInfo {
+public:
+    std::string parameter;               // The function pointer or std::function parameter
+    std::string context;                 // void* parameter passed back to it; empty if none
+    int context_slot = -1;               // Position of the void* among the callback's parameters, -1 if none
+    bool std_function = false;           // std::function<R(Args)>: the C++ shim wraps the trampoline in a lambda
+    bool stored = false;                 // Kept by the C++ after the call returns: the closure must outlive it
+};
+
+/**
+ * Integer arithmetic or conversion whose C++ semantics Rust does not share
+ */
+class IntegerSiteInfo {
+public:
+    enum SiteKind {
+        Wrapping,       // h = h * 31 + c on an unsigned h: wraps in C++, panics in a Rust debug build
+        Conversion      // int n = v.size(), static_cast<int>(n), return count: from one integer type to another
+    };
+
+    SiteKind kind = Wrapping;
+    std::string source_text;             // Statement without ';', or the cast
+    std::string target;                  // Assigned variable; empty for casts and returns
+    std::string op;                      // Wrapping: =, +=, -= or *=
+    std::string expression;              // Right-hand side or cast operand
+    std::shared_ptr<Type> type;          // Wrapping: the unsigned type; Conversion: the destination
+    std::shared_ptr<Type> from;          // Conversion: the source type
+    std::map<std::string, std::shared_ptr<Type>> operands;  // Wrapping: variables of another integer type
+    bool explicit_cast = false;          // static_cast<T>(...)
+};
+
+/**
+ * Classic C++ memory bug in a function body; the code generator reports
+ * whether the translation fixes it or keeps it behind unsafe
+ */
+class MemoryIssueInfo {
+public:
+    enum IssueKind {
+        UseAfterFree,       // *p, p->next or p[0] after delete p
+        DoubleDelete,       // delete p twice with no assignment in between
+        BufferOverrun,      // data[4], or i <= 4 indexing data, on int data[4]
+        UninitializedRead   // int total; total += x;
+    };
+
+    IssueKind kind = UseAfterFree;
+    std::string variable;                // The pointer, array or local
+    std::string source_text;             // Statement with the bug, without ';'
+    std::string cause;                   // The earlier delete, the array or local declaration
+    std::string loop;                    // BufferOverrun: header of the loop running past the end, if any
+    std::shared_ptr<Type> type;          // Of the variable, null if unknown
+};
+
+/**
+ * Use of the arguments or the exit status in main, or exit() anywhere
+ */
+class EntrySiteInfo {
+public:
+    enum SiteKind {
+        Argument,       // argv[1]
+        ArgumentUse,    // argv passed on, *argv, argv + 1: a char** Rust does not have
+        Return,         // return 1; in main
+        Exit,           // exit(1), std::exit(EXIT_FAILURE)
+        CommandLine     // getopt or argv loop described by Function::command_line
+    };
+
+    SiteKind kind = Argument;
+    std::string source_text;
+    std::string expression;              // Index, returned value, exit status or the loop
+    std::vector<EntrySiteInfo> nested;   // Uses of argv in the expression
+};
+
+/**
+ * Option handled by a command-line loop: -v, --output FILE
+ */
+class CliOptionInfo {
+public:
+    char short_name = 0;                 // 'o' for -o, 0 if long only
+    std::string long_name;               // "output" for --output
+    bool takes_value = false;            // optarg, argv[++i]
+    bool counted = false;                // ++verbosity for each -v
+    bool repeated = false;               // includes.push_back(optarg)
+    std::string target;                  // Variable set, empty if only action
+    std::shared_ptr<Type> type;          // Declared type of target
+    std::string action;                  // Other statements, as written
+};
+
+/**
+ * Hand-rolled argv loop or getopt loop in main (--clap)
+ */
+class CommandLineInfo {
+public:
+    enum Style {
+        ArgvLoop,       // for (int i = 1; i < argc; ++i) if (strcmp(argv[i], "-v") == 0) ...
+        Getopt,         // while ((c = getopt(argc, argv, "vo:")) != -1) switch (c) ...
+        GetoptLong      // getopt_long with a struct option table
+    };
+
+    Style style = ArgvLoop;
+    std::string source_text;             // The whole loop
+    std::vector<CliOptionInfo> options;
+    std::string positional;              // Variable the other arguments go to
+    std::shared_ptr<Type> positional_type;
+    bool uses_optind = false;            // optind read after the loop
+};
+
+/**
+ * std::numeric_limits<T> member
+ */
+class NumericLimitInfo {
+public:
+    std::string source_text;             // std::numeric_limits<float>::max()
+    std::shared_ptr<Type> type;          // T
+    std::string member;                  // max, lowest, epsilon, digits, ...
+};
+
+/**
+ * Use of linked nodes in a class that holds them (list, tree, graph)
+ */
+class GraphSiteInfo {
+public:
+    enum SiteKind {
+        Declaration,    // Node* cur
+        NewNode,        // new Node(args)
+        DeleteNode,     // delete head;
+        NodeAccess      // cur->next->value
+    };
+
+    SiteKind kind = NodeAccess;
+    std::string source_text;
+    std::string node_class;              // Class of the linked nodes
+    std::string pointer;                 // Declaration: declared name; Delete/Access: pointer dereferenced first
+    bool pointer_is_field = false;       // pointer is a field of the holding class
+    std::vector<std::string> members;    // DeleteNode / NodeAccess: p->a->b
+    std::vector<std::string> arguments;  // NewNode: constructor arguments
+};
+
+/**
+ * Derived-to-base conversion in a class with several bases
+ */
+class UpcastInfo {
+public:
+    std::string source_text;
+    std::string object;                  // this, *this, or a variable of the derived type
+    std::string base;                    // Base class converted to
+    std::string name;                    // Declaration: Shape* s = this; empty for casts
+    bool to_pointer = false;             // Base* rather than Base&
+    bool is_const = false;               // const Base* / const Base&
+    bool is_polymorphic = false;         // Base has virtual methods: trait object
+};
+
+/**
+ * Pointer-to-member expression or declaration
+ */
+class MemberPointerInfo {
+public:
+    enum SiteKind {
+        AddressOf,      // &Machine::start
+        Call,           // (obj.*handler)(args) / (this->*handler)(args)
+        Access,         // obj.*field / p->*field
+        Declaration     // void (Machine::*handler)() / int Machine::*field
+    };
+
+    SiteKind kind = AddressOf;
+    std::string source_text;             // Expression, or declarator without initializer
+    std::string class_name;              // AddressOf: class named
+    std::string member;                  // AddressOf: member named
+    bool is_data_member = false;         // AddressOf: a field rather than a method
+    bool is_virtual = false;             // AddressOf: C++ dispatches calls virtually
+    bool is_overloaded = false;          // AddressOf: several methods share the name
+    std::string object;                  // Call/Access: object (`this` for this->*)
+    bool through_pointer = false;        // ->* rather than .*
+    std::string pointer;                 // Call/Access: pointer-to-member expression
+    std::vector<std::string> arguments;  // Call arguments
+    std::string name;                    // Declaration: declared variable
+    std::shared_ptr<Type> type;          // Declaration: declared type
+};
+
+/**
+ * Use of a function object (a class with operator())
+ */
+class FunctorUseInfo {
+public:
+    enum UseKind {
+        Construct,      // std::sort(b, e, ByLength{}) -> closure
+        Call            // cmp_(a, b) -> cmp_.call(a, b)
+    };
+
+    UseKind kind = Construct;
+    std::string source_text;             // Construction expression, or `name(` of a call
+    std::string functor;                 // Functor class
+    std::vector<std::string> arguments;  // Constructor arguments
+    std::vector<std::string> parameters;   // operator() parameter names
+    bool has_constructor = false;        // Built with new(); otherwise a struct literal
+    std::vector<std::string> field_names;  // Fields set by aggregate initialization
+};
+
+/**
+ * `if constexpr` chain, resolved per instantiation of the enclosing template
+ */
+class ConstexprIfInfo {
+public:
+    struct Branch {
+        std::string condition;           // Empty for the final else
+        std::string body;                // Statements between the braces
+    };
+
+    static constexpr int UNRESOLVED = -2;
+
+    std::string source_text;             // Whole if constexpr ... else ... chain
+    std::vector<Branch> branches;
+    std::vector<int> selected;           // Per instantiation: branch taken, -1 if none, or UNRESOLVED
+    std::string unresolved_reason;       // Why a condition could not be evaluated
+};
+
+/**
+ * Logging statement mapped to a log crate macro
+ */
+class LogInfo {
+public:
+    enum LogStyle {
+        Stream,         // LOG(INFO) << "x = " << x;
+        Printf,         // LOG_ERROR("%d items", n);
+        Fmt             // spdlog::info("{} items", n);
+    };
+
+    LogStyle style = Fmt;
+    std::string source_text;             // Whole statement including ';'
+    std::string macro;                   // LOG, spdlog::info, LOG_ERROR, ...
+    std::string level;                   // log crate level: error, warn, info, debug, trace
+    bool fatal = false;                  // LOG(FATAL): aborts after logging
+    std::string format_string;           // Literal contents (Stream: pieces joined with {})
+    bool is_literal = false;             // Format string is a string literal
+    std::vector<std::string> arguments;  // Arguments after the format string
+    std::string unsupported;             // Stream manipulator preventing translation
+};
+
+/**
+ * Error-code site in a function returning a status enum
+ */
+class StatusSiteInfo {
+public:
+    enum SiteKind {
+        ReturnSuccess,  // return OK;
+        ReturnError,    // return Status::INVALID;
+        Propagate,      // if (s != OK) return s;  RETURN_IF_ERROR(f());
+        Check           // s == OK / s != OK
+    };
+
+    SiteKind kind = ReturnSuccess;
+    std::string source_text;
+    std::string expression;      // Propagated or checked status expression
+    std::string value;           // Returned error enumerator
+    bool is_success_check = true;  // Check: `== OK` (false for `!= OK`)
+};
+
+/**
+ * Point in a constructor where construction can stop early, or where the
+ * partially built object is used before such a point
+ */
+class ConstructionSiteInfo {
+public:
+    enum SiteKind {
+        Throw,           // throw std::runtime_error("...");
+        FallibleMember,  // Member whose constructor can fail: conn_(host, port)
+        ThisEscape,      // registry.add(this); before a later failure
+        MethodCall       // validate(); on the partial object before a later failure
+    };
+
+    SiteKind kind = Throw;
+    std::string source_text;
+    std::string exception_type;          // Throw: thrown type; FallibleMember: member's class
+    std::string arguments;               // Exception or member constructor arguments, as written
+    std::string member;                  // FallibleMember: field being constructed
+    std::vector<std::string> initialized;  // Fields set before this point, in order
+    std::vector<std::string> leaked;     // Of those, fields only the destructor releases
+};
+
+/**
+ * Layout query information (sizeof, alignof, offsetof)
+ */
+class LayoutQueryInfo {
+public:
+    enum QueryKind {
+        SizeOf,         // sizeof(T) / sizeof expr
+        AlignOf,        // alignof(T)
+        OffsetOf,       // offsetof(T, member)
+        ArrayLength     // sizeof(arr) / sizeof(arr[0])
+    };
+
+    QueryKind kind = SizeOf;
+    std::string source_text;             // Original C++ expression text
+    std::shared_ptr<Type> type;          // Type operand (null for expression operands)
+    std::string expression;              // Expression operand (sizeof expr, array name)
+    std::string member_name;             // offsetof member designator
+};
+
 /**
  * Atomic operation information
  */
@@ -221,6 +767,49 @@ public:
     std::vector<std::string> wait_conditions;
 };
 
+/**
+ * Move operation information (std::move / std::exchange)
+ */
+class MoveInfo {
+public:
+    enum MoveKind {
+        Move,       // std::move(expr)
+        Exchange    // std::exchange(expr, new_value)
+    };
+
+    MoveKind kind = Move;
+    std::string source_text;     // Original C++ expression text
+    std::string expression;      // The object being moved from
+    std::string new_value;       // Replacement value for std::exchange
+    bool used_after_move = false;      // Source relies on the moved-from state
+    bool moves_out_of_borrow = false;  // Moves out of a field or reference
+    bool in_initializer = false;       // Found in the member initializer list, not the body
+    size_t offset = 0;                 // Offset of source_text in the initializer list or body
+};
+
+/**
+ * Copy semantics of a type in the target language
+ */
+enum class CopySemantics {
+    Copy,       // Bitwise copyable (#[derive(Clone, Copy)])
+    Clone,      // Copyable via explicit clone()
+    MoveOnly    // Not copyable (deleted copy constructor, unique ownership)
+};
+
+/**
+ * Implicit copy site information (assignment or pass-by-value)
+ */
+class CopyInfo {
+public:
+    std::string source_text;         // Original C++ statement/call text
+    std::string expression;          // The expression being copied
+    size_t expression_offset = 0;    // Offset of expression within source_text
+    bool needs_clone = false;        // false if the type is Copy or the value is moved
+    bool needs_deref = false;        // Copy type read through a reference parameter
+    bool to_owned = false;           // &str copied into an owned String: .to_string()
+    bool needs_borrow = false;       // Owned String passed to a &str parameter: &expr
+};
+
 /**
  * Async/Coroutine operation types
  */
@@ -290,6 +879,8 @@ public:
     std::shared_ptr<Type> return_type;
     std::vector<Parameter> parameters;
     std::string body;
+    SourceSpan span;
+    std::string doc;        // Doc comment above the declaration, without the comment markers
 
     bool is_const = false;
     bool is_static = false;
@@ -297,10 +888,97 @@ public:
     bool is_pure_virtual = false;
     bool is_constructor = false;
     bool is_destructor = false;
+    bool is_move_constructor = false;
+    bool is_copy_constructor = false;
+    bool is_deleted = false;     // = delete
+    bool is_defaulted = false;   // = default
+
+    // Constructor member initializer list (raw text after ':')
+    std::string member_initializers;
 
     // Ownership analysis results
     std::vector<std::string> moved_params;
     std::vector<std::string> borrowed_params;
+    std::vector<MoveInfo> moves;
+    std::vector<CopyInfo> copies;
+
+    // Layout queries (sizeof/alignof/offsetof)
+    std::vector<LayoutQueryInfo> layout_queries;
+
+    // Structured bindings and std::tie
+    std::vector<BindingInfo> bindings;
+    std::vector<std::string> tuple_variables;  // std::pair/std::tuple parameters, fields and locals
+
+    // std::format / fmt library calls
+    std::vector<FormatInfo> formats;
+
+    // glog/spdlog/project logging statements
+    std::vector<LogInfo> logs;
+
+    // Standard algorithms over whole containers
+    std::vector<AlgorithmInfo> algorithms;
+
+    // Pointers to members: &C::m, obj.*p, (this->*p)(...)
+    std::vector<MemberPointerInfo> member_pointers;
+
+    // C variadic functions: f(const char* fmt, ...)
+    bool is_variadic = false;
+    std::vector<VarargSiteInfo> vararg_sites;
+
+    // int main(int argc, char** argv): fn main() -> ExitCode
+    bool is_entry_point = false;
+    std::vector<EntrySiteInfo> entry_sites;
+    std::vector<CommandLineInfo> command_lines;  // Option parsing loops a clap struct can replace
+
+    // Signal handlers, setjmp/longjmp: kept in C++ behind extern "C"
+    std::string retained_reason;         // Why, empty if translated
+    std::vector<CallbackInfo> callbacks; // Callback parameters of a retained function
+
+    // Placement new and explicit destructor calls
+    std::vector<AllocationSiteInfo> allocations;
+
+    // Conversions to one of several bases: trait objects or accessors
+    std::vector<UpcastInfo> upcasts;
+
+    // Node allocation and traversal in a class holding linked nodes
+    std::vector<GraphSiteInfo> graph_sites;
+
+    // open/read/malloc/socket... called directly
+    std::vector<LibcCallInfo> libc_calls;
+
+    // (T* ptr, size_t len) pairs taken as slices, span methods, new T[n] buffers
+    std::vector<SliceParamInfo> slice_params;
+    std::vector<SliceSiteInfo> slice_sites;
+
+    // Unsigned arithmetic relying on wraparound, conversions between integer types
+    std::vector<IntegerSiteInfo> integer_sites;
+
+    // std::numeric_limits<T>::max(), ::epsilon(), ::digits
+    std::vector<NumericLimitInfo> numeric_limits;
+
+    // Use after free, double delete, fixed-array overruns, uninitialized reads
+    std::vector<MemoryIssueInfo> memory_issues;
+
+    // Function objects constructed as arguments or called
+    std::vector<FunctorUseInfo> functor_uses;
+
+    // if constexpr chains (see ClassDecl::instantiations)
+    std::vector<ConstexprIfInfo> constexpr_ifs;
+
+    // Error-code returns: Status f(...) -> Result<T, Status>
+    std::string status_enum;             // Error-code enum returned, empty if none
+    std::string status_out_param;        // Out-parameter that becomes the Ok value
+    std::vector<std::string> status_params;  // Error-code parameters, passed as Result<(), Status>
+    std::vector<StatusSiteInfo> status_sites;
+
+    // Borrowed string parameters kept in std::string_view fields: the
+    // &str needs a lifetime tied to the struct
+    std::map<std::string, std::string> stored_views;    // Parameter -> field
+
+    // Constructors that can fail midway: fn try_new() -> Result<Self, _>
+    bool is_fallible_constructor = false;
+    std::vector<ConstructionSiteInfo> construction_sites;
+    std::map<std::string, std::string> initializer_arguments;  // Member or base -> arguments in the list
 
     // Exception handling
     ExceptionSpec exception_spec;
@@ -315,8 +993,10 @@ public:
     // Threading information
     std::vector<ThreadInfo> threads_created;
     std::vector<LockInfo> lock_scopes;
+    std::vector<GuardInfo> guards;
     std::vector<AtomicInfo> atomic_operations;
     std::vector<ConditionVariableInfo> condition_variables;
+    std::vector<ThreadCrossingInfo> thread_crossings;
     bool uses_threading = false;
 
     // Async/Coroutine information
@@ -326,6 +1006,48 @@ public:
     bool is_async = false;  // Async function (returns Future/Task)
 };
 
+/**
+ * Enumeration representation
+ */
+class EnumDecl {
+public:
+    struct Enumerator {
+        std::string name;
+        std::string value;   // Explicit initializer, empty if implicit
+    };
+
+    std::string name;
+    bool is_scoped = false;              // enum class
+    std::string underlying_type;         // `: uint8_t`, empty if unspecified
+    std::vector<Enumerator> enumerators;
+    SourceSpan span;
+    std::string doc;
+
+    // Error-code enum: returned as a status with a success enumerator
+    bool is_error_code = false;
+    std::string success_value;
+
+    // Hand-written string conversions: impl Display and impl FromStr
+    std::string to_string_function;                     // const char* toString(Color c), a switch over c
+    std::map<std::string, std::string> display_texts;   // Enumerator -> text its case returns
+    bool has_display_default = false;                   // default: covers the enumerators without a case
+    std::string display_default;
+    std::string from_string_function;                   // Color parseColor(const std::string& s)
+    std::vector<std::pair<std::string, std::string>> parse_texts;  // Text -> enumerator, in order
+    std::string parse_fallback;                         // Enumerator returned when no text matches; empty: Err
+};
+
+/**
+ * Type alias at namespace scope: using Name = Target; or typedef Target Name;
+ */
+class TypeAlias {
+public:
+    std::string name;
+    std::shared_ptr<Type> type;
+    SourceSpan span;
+    std::string doc;
+};
+
 /**
  * Class/Struct representation
  */
@@ -333,16 +1055,60 @@ class ClassDecl {
 public:
     std::string name;
     bool is_struct = false;
+    SourceSpan span;
+    std::string doc;
 
     std::vector<Variable> fields;
     std::vector<Function> methods;
     std::vector<std::string> base_classes;
+    std::vector<std::string> virtual_bases;    // Inherited with `virtual`
+
+    // Multiple inheritance: each base declared in the input becomes an
+    // embedded field (see InheritanceAnalyzer)
+    struct BaseSubobject {
+        std::string name;
+        bool is_polymorphic = false;            // Has virtual methods: also a trait
+        std::vector<Function> virtual_methods;  // Trait interface, as declared in the base
+    };
+    std::vector<BaseSubobject> base_subobjects;
+
+    // Pointers to itself or to objects of the same class (see SelfReferenceAnalyzer)
+    std::vector<SelfReferenceInfo> self_references;
+
+    // Classes with intrusive links this class holds raw pointers to (list -> node)
+    std::vector<std::string> graph_nodes;
+
+    // Copy semantics (conservative default until analyzed)
+    CopySemantics copy_semantics = CopySemantics::MoveOnly;
+
+    // Field offsets are observed (offsetof): keep C layout
+    bool needs_c_layout = false;
+
+    // Only purpose is operator(): comparator, hasher, predicate
+    bool is_functor = false;
+
+    // Class-specific operator new / operator delete (methods named
+    // "operator new", "operator delete[]", ...)
+    bool has_class_allocator = false;
+
+    // Left for a later migration phase (--defer): the struct, a trait of
+    // its methods and todo!() bodies are generated instead of a translation
+    bool is_deferred = false;
+    std::string source_path;        // Deferred: file declaring the class, named by the stubs
+    int source_line_offset = 0;     // Deferred: lines of the parsed source before that file
 
     // Template information
     bool is_template = false;
     std::vector<TemplateParameter> template_parameters;
     TemplateSpecialization specialization;
 
+    // Instantiations named in the source: Buffer<int, 4>
+    struct Instantiation {
+        std::vector<std::string> arguments;          // As written, defaults filled in
+        std::vector<std::shared_ptr<Type>> types;    // Type arguments (null for values)
+    };
+    std::vector<Instantiation> instantiations;
+
     // Access control
     struct AccessSection {
         enum Level { Public, Protected, Private };
@@ -357,6 +1123,123 @@ public:
     bool thread_safe = false;
 };
 
+/**
+ * Diagnostic reported during analysis or code generation
+ */
+class Diagnostic {
+public:
+    enum Severity { Note, Warning, Error };
+
+    Severity severity = Warning;
+    std::string message;
+    std::string context;    // Enclosing class/function, if known
+
+    /**
+     * Format as "warning: in 'context': message"
+     */
+    std::string toString() const {
+        std::string label = severity == Error ? "error" : severity == Warning ? "warning" : "note";
+        return label + ": " + (context.empty() ? "" : "in '" + context + "': ") + message;
+    }
+};
+
+/**
+ * Translation rule applied by code generation, kept for --explain
+ */
+class Decision {
+public:
+    std::string rule;       // Rule ID: OWN-RC, COPY-CLONE... (explainRules() lists them)
+    std::string context;    // Class, Class::method or function being generated
+    std::string subject;    // What the rule applied to: field cache (std::shared_ptr<Entry>)
+    std::string outcome;    // What was generated for it
+    std::string reason;     // Why the rule fired
+};
+
+/**
+ * C++ language standard the input is written against (--std)
+ */
+enum class CppStandard {
+    Cxx11,
+    Cxx14,
+    Cxx17,
+    Cxx20
+};
+
+/**
+ * Command-line spelling of a standard ("c++17")
+ */
+inline std::string standardName(CppStandard standard) {
+    switch (standard) {
+        case CppStandard::Cxx11: return "c++11";
+        case CppStandard::Cxx14: return "c++14";
+        case CppStandard::Cxx17: return "c++17";
+        case CppStandard::Cxx20: return "c++20";
+    }
+    return "c++20";
+}
+
+/**
+ * Parse "c++11", "c++14", "c++17" or "c++20"
+ * @return false for any other spelling
+ */
+inline bool parseStandardName(const std::string& name, CppStandard& standard) {
+    for (CppStandard candidate : {CppStandard::Cxx11, CppStandard::Cxx14,
+                                  CppStandard::Cxx17, CppStandard::Cxx20}) {
+        if (name == standardName(candidate)) {
+            standard = candidate;
+            return true;
+        }
+    }
+    return false;
+}
+
+/**
+ * Logging macro or function name -> log crate level (--log-macro NAME=level)
+ */
+using LogMacroTable = std::map<std::string, std::string>;
+
+/**
+ * True for the log crate levels: error, warn, info, debug, trace
+ */
+inline bool isLogLevel(const std::string& level) {
+    return level == "error" || level == "warn" || level == "info" ||
+           level == "debug" || level == "trace";
+}
+
+/**
+ * Translation of C variadic functions (--varargs)
+ */
+enum class VarargsStrategy {
+    Slice,      // Trailing &[VarArg] parameter; va_arg reads the next element
+    Macro,      // Slice, plus a macro_rules! front-end taking the arguments as written
+    Ffi         // Kept in C++ and declared in an extern "C" block
+};
+
+/**
+ * Widths of long and unsigned long (--data-model)
+ */
+enum class DataModel {
+    LP64,   // Linux, macOS: long is 64 bits
+    LLP64   // Windows: long is 32 bits
+};
+
+/**
+ * Translation of direct POSIX/C library calls (--libc)
+ */
+enum class LibcStrategy {
+    Std,    // std::fs / std::io / std::net / std::ptr where they fit, the libc crate otherwise
+    Crate   // Every call through the libc crate, as in C
+};
+
+/**
+ * Translation of self-referential and intrusive classes (--self-ref)
+ */
+enum class SelfRefStrategy {
+    Report,     // Raw pointers as written; each pattern is reported
+    Arena,      // Intrusive links become generational Id<T> into an Arena<T>
+    Pin         // Pinned in place: Pin<Box<Self>> constructors, PhantomPinned
+};
+
 /**
  * Intermediate Representation
  * Contains parsed and analyzed C++ code in a language-neutral format
@@ -366,12 +1249,36 @@ public:
     IR() = default;
 
     void addClass(const ClassDecl& class_decl);
+    void addEnum(const EnumDecl& enum_decl);
+    void addTypeAlias(const TypeAlias& alias);
     void addFunction(const Function& func);
     void addGlobalVariable(const Variable& var);
+    void addDiagnostic(const Diagnostic& diagnostic);
 
     const std::vector<ClassDecl>& getClasses() const { return classes_; }
     const std::vector<Function>& getFunctions() const { return functions_; }
+    const std::vector<EnumDecl>& getEnums() const { return enums_; }
+    const std::vector<TypeAlias>& getTypeAliases() const { return type_aliases_; }
+
+    // Mutable access for analysis passes
+    std::vector<ClassDecl>& getClasses() { return classes_; }
+    std::vector<EnumDecl>& getEnums() { return enums_; }
+    std::vector<Function>& getFunctions() { return functions_; }
     const std::vector<Variable>& getGlobalVariables() const { return global_vars_; }
+    const std::vector<Diagnostic>& getDiagnostics() const { return diagnostics_; }
+
+    // Standard the source was parsed as
+    CppStandard getStandard() const { return standard_; }
+    void setStandard(CppStandard standard) { standard_ = standard; }
+
+    // Class lookup (nullptr if not found)
+    const ClassDecl* findClass(const std::string& name) const;
+
+    // Enum lookup (nullptr if not found)
+    const EnumDecl* findEnum(const std::string& name) const;
+
+    // Type alias lookup (nullptr if not found)
+    const TypeAlias* findTypeAlias(const std::string& name) const;
 
     // Type lookup
     std::shared_ptr<Type> findType(const std::string& name) const;
@@ -379,9 +1286,13 @@ public:
 
 private:
     std::vector<ClassDecl> classes_;
+    std::vector<EnumDecl> enums_;
+    std::vector<TypeAlias> type_aliases_;
     std::vector<Function> functions_;
     std::vector<Variable> global_vars_;
+    std::vector<Diagnostic> diagnostics_;
     std::map<std::string, std::shared_ptr<Type>> type_registry_;
+    CppStandard standard_ = CppStandard::Cxx20;
 };
 
 } // namespace hybrid
@@ -0,0 +1,37 @@
+#ifndef HYBRID_IR_JSON_H
+#define HYBRID_IR_JSON_H
+
+#include "ir.h"
+#include "json.h"
+#include <string>
+
+namespace hybrid {
+
+/**
+ * Structured dumps of what the frontend understood (--emit=ast-json/ir-json)
+ *
+ * Both documents start with `"format"` ("hybrid-ast" or "hybrid-ir") and
+ * `"version"`; the version is bumped whenever a member is removed or
+ * changes meaning. Spans are omitted for declarations without a known
+ * source location.
+ */
+
+/**
+ * Declarations as written: names, type spellings, qualifiers and spans
+ */
+JsonValue astToJson(const IR& ir);
+
+/**
+ * Declarations after analysis: resolved type trees, copy semantics,
+ * layout requirements, move/copy/guard sites and diagnostics
+ */
+JsonValue irToJson(const IR& ir);
+
+/**
+ * Stable lowercase name of a type kind (e.g. "std_vector")
+ */
+std::string typeKindName(TypeKind kind);
+
+} // namespace hybrid
+
+#endif // HYBRID_IR_JSON_H
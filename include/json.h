@@ -0,0 +1,107 @@
+#ifndef HYBRID_JSON_H
+#define HYBRID_JSON_H
+
+#include <string>
+#include <vector>
+#include <utility>
+#include <cstddef>
+
+namespace hybrid {
+
+/**
+ * Minimal JSON value
+ *
+ * Used for the language server protocol and machine-readable output.
+ * Object members keep their insertion order, so serialized output is
+ * deterministic.
+ */
+class JsonValue {
+public:
+    enum Kind { Null, Bool, Number, String, Array, Object };
+
+    JsonValue() = default;
+    JsonValue(std::nullptr_t) {}
+    JsonValue(bool value) : kind_(Bool), bool_(value) {}
+    JsonValue(int value) : kind_(Number), number_(value) {}
+    JsonValue(unsigned int value) : kind_(Number), number_(value) {}
+    JsonValue(long value) : kind_(Number), number_(static_cast<double>(value)) {}
+    JsonValue(long long value) : kind_(Number), number_(static_cast<double>(value)) {}
+    JsonValue(unsigned long value) : kind_(Number), number_(static_cast<double>(value)) {}
+    JsonValue(unsigned long long value) : kind_(Number), number_(static_cast<double>(value)) {}
+    JsonValue(double value) : kind_(Number), number_(value) {}
+    JsonValue(const char* value) : kind_(String), string_(value) {}
+    JsonValue(const std::string& value) : kind_(String), string_(value) {}
+
+    static JsonValue array();
+    static JsonValue object();
+
+    Kind kind() const { return kind_; }
+    bool isNull() const { return kind_ == Null; }
+    bool isBool() const { return kind_ == Bool; }
+    bool isNumber() const { return kind_ == Number; }
+    bool isString() const { return kind_ == String; }
+    bool isArray() const { return kind_ == Array; }
+    bool isObject() const { return kind_ == Object; }
+
+    /**
+     * Value accessors; a value of another kind yields false/0/""/empty
+     */
+    bool asBool() const { return kind_ == Bool && bool_; }
+    double asNumber() const { return kind_ == Number ? number_ : 0; }
+    int asInt() const { return static_cast<int>(asNumber()); }
+    const std::string& asString() const;
+    const std::vector<JsonValue>& asArray() const;
+
+    /**
+     * Object members, in insertion order
+     */
+    const std::vector<std::pair<std::string, JsonValue>>& members() const;
+
+    bool has(const std::string& key) const;
+
+    /**
+     * Member lookup; Null if missing or not an object
+     */
+    const JsonValue& operator[](const std::string& key) const;
+
+    /**
+     * Set an object member (replacing an existing one); turns Null into an object
+     * @return *this, for chaining
+     */
+    JsonValue& set(const std::string& key, const JsonValue& value);
+
+    /**
+     * Append an array element; turns Null into an array
+     */
+    JsonValue& push(const JsonValue& value);
+
+    /**
+     * Number of array elements or object members
+     */
+    size_t size() const;
+
+    /**
+     * @param indent Spaces per nesting level; negative for compact output
+     */
+    std::string serialize(int indent = -1) const;
+
+    /**
+     * Parse JSON text
+     * @throws std::runtime_error on malformed input
+     */
+    static JsonValue parse(const std::string& text);
+
+private:
+    Kind kind_ = Null;
+    bool bool_ = false;
+    double number_ = 0;
+    std::string string_;
+    std::vector<JsonValue> elements_;
+    std::vector<std::pair<std::string, JsonValue>> members_;
+
+    void write(std::string& out, int indent, int depth) const;
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_JSON_H
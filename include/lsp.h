@@ -0,0 +1,76 @@
+#ifndef HYBRID_LSP_H
+#define HYBRID_LSP_H
+
+#include "json.h"
+#include "transpiler.h"
+#include <iosfwd>
+#include <map>
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Minimal language server for live translation preview
+ *
+ * Speaks JSON-RPC over stdin/stdout with Content-Length framing. For every
+ * open C++ document it keeps the current translation and publishes:
+ *   - textDocument/publishDiagnostics: transpiler warnings and regions the
+ *     transpiler does not translate
+ *   - hybrid/translation: the generated code as a virtual document
+ *     (also available on request with the same method name)
+ *
+ * The target language defaults to setTarget() and can be overridden by
+ * the client with `initializationOptions.target` ("rust" or "go").
+ */
+class LspServer {
+public:
+    LspServer(std::istream& in, std::ostream& out);
+
+    /**
+     * Serve until `exit` or end of input
+     * @return 0 if `shutdown` was requested before `exit`, 1 otherwise
+     */
+    int run();
+
+    /**
+     * Handle one incoming message
+     * @return Responses and notifications to send, in order
+     */
+    std::vector<JsonValue> handleMessage(const JsonValue& message);
+
+    void setTarget(TargetLanguage target) { options_.target = target; }
+
+    bool exitRequested() const { return exit_requested_; }
+
+    /**
+     * URI of the virtual document holding the translation of `uri`
+     */
+    std::string translationUri(const std::string& uri) const;
+
+private:
+    struct Document {
+        std::string text;
+        SnippetResult translation;
+    };
+
+    std::istream& in_;
+    std::ostream& out_;
+    TranspilerOptions options_;
+    std::map<std::string, Document> documents_;
+    bool shutdown_requested_ = false;
+    bool exit_requested_ = false;
+
+    // Sets `error` instead of `content` when a message is dropped
+    bool readMessage(std::string& content, std::string& error);
+    void writeMessage(const JsonValue& message);
+
+    JsonValue initializeResult() const;
+    void update(const std::string& uri, const std::string& text, std::vector<JsonValue>& out);
+    JsonValue translationParams(const std::string& uri) const;
+    JsonValue diagnostics(const std::string& uri) const;
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_LSP_H
@@ -0,0 +1,49 @@
+#ifndef HYBRID_MAPPING_PACKS_H
+#define HYBRID_MAPPING_PACKS_H
+
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Library type translated by a mapping pack: boost::optional<T> -> Option<T>
+ *
+ * In the Rust and Go patterns $0, $1, ... stand for the translated
+ * template arguments.
+ */
+struct TypeMapping {
+    std::string cpp;    // Class or template as written, without arguments: boost::optional, QString
+    std::string rust;   // Rust type: Option<$0>
+    std::string go;     // Go type; empty keeps the C++ name
+    std::string note;   // Behavior the translation loses, reported where the type is used
+};
+
+/**
+ * Set of type mappings for one library, enabled with --mapping-pack
+ */
+struct MappingPack {
+    std::string name;
+    std::string description;
+    std::vector<TypeMapping> types;
+};
+
+/**
+ * Packs shipped with the transpiler (boost, qt)
+ * @return nullptr if there is no pack of that name
+ */
+const MappingPack* findBuiltinMappingPack(const std::string& name);
+
+std::vector<std::string> builtinMappingPackNames();
+
+/**
+ * Read a pack from JSON:
+ * {"name": "...", "types": [{"cpp": "...", "rust": "...", "go": "...", "note": "..."}]}
+ * @param error Set to the reason when the pack is rejected
+ * @return false if the text is not a valid pack
+ */
+bool parseMappingPack(const std::string& text, MappingPack& pack, std::string& error);
+
+} // namespace hybrid
+
+#endif // HYBRID_MAPPING_PACKS_H
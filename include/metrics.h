@@ -0,0 +1,81 @@
+#ifndef HYBRID_METRICS_H
+#define HYBRID_METRICS_H
+
+#include "codegen.h"
+#include "json.h"
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Limits on the generated code, per 1000 lines of output (--max-unsafe,
+ * --max-clones); negative for no limit
+ */
+struct QualityBudget {
+    double max_unsafe = -1;         // unsafe blocks
+    double max_clones = -1;         // .clone() calls
+};
+
+/**
+ * Statistics of one transpiler run (--metrics)
+ *
+ * Written as JSON so migration progress can be tracked across runs.
+ * Counts of unsafe blocks, Rc/Arc/RefCell types, clone and unwrap calls
+ * are taken from the generated code, ignoring `//` comments.
+ */
+struct TranslationMetrics {
+    bool success = false;
+    std::string input_path;
+    std::string output_path;
+    std::string target;             // "rust" or "go"
+
+    size_t lines_in = 0;
+    size_t lines_out = 0;
+    size_t classes = 0;
+    GenerationStats generation;
+    size_t unsafe_blocks = 0;       // `unsafe { ... }` blocks
+    size_t rc_introduced = 0;       // `Rc<...>` types
+    size_t arc_introduced = 0;      // `Arc<...>` types
+    size_t refcell_introduced = 0;  // `RefCell<...>` types
+    size_t clone_calls = 0;         // `.clone()` calls, inserted or translated
+    size_t unwrap_calls = 0;        // `.unwrap()` and `.expect(...)` calls
+
+    // Wall time per phase in milliseconds, in execution order
+    std::vector<std::pair<std::string, double>> phase_ms;
+    bool record_timings = true;     // false with --deterministic: reports are byte-identical
+
+    /**
+     * Add one generated file to the output counts
+     */
+    void countOutput(const std::string& code);
+
+    /**
+     * Occurrences per 1000 lines of output
+     */
+    double perKloc(size_t count) const;
+
+    /**
+     * 100 for output without unsafe blocks, Rc/RefCell, unwraps or clones,
+     * lower the denser they are
+     */
+    double idiomaticityScore() const;
+
+    /**
+     * @param error Set to the first budget exceeded
+     * @return false if the output is over a budget
+     */
+    bool withinBudget(const QualityBudget& budget, std::string& error) const;
+
+    JsonValue toJson() const;
+};
+
+/**
+ * Number of lines in text (a final line without newline counts)
+ */
+size_t countLines(const std::string& text);
+
+} // namespace hybrid
+
+#endif // HYBRID_METRICS_H
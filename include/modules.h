@@ -0,0 +1,193 @@
+#ifndef HYBRID_MODULES_H
+#define HYBRID_MODULES_H
+
+#include "ir.h"
+#include "codegen.h"
+#include <functional>
+#include <string>
+#include <vector>
+#include <map>
+#include <set>
+
+namespace hybrid {
+
+class FileSystem;
+class Preprocessor;
+
+/**
+ * Include graph of a translation unit
+ *
+ * Follows quoted #include directives (angle-bracket system headers are
+ * skipped) starting from a root source file.
+ */
+class IncludeGraph {
+public:
+    struct Node {
+        std::string path;
+        std::string source;
+        std::vector<std::string> includes;   // Resolved paths of included files
+    };
+
+    /**
+     * Build the graph by reading the root file and everything it includes
+     * @return false if the root file cannot be read
+     */
+    bool build(const std::string& root_path, FileSystem& fs);
+
+    /**
+     * Add a file with already loaded source; includes are resolved
+     * against files added or read so far
+     */
+    void addFile(const std::string& path, const std::string& source);
+
+    /**
+     * Preprocess every file for a build configuration before it is added:
+     * only #includes in taken #if groups are followed, and files not next
+     * to their includer are searched in include_paths (-I). Node sources
+     * are the preprocessed text
+     */
+    void setPreprocessor(Preprocessor* preprocessor, std::vector<std::string> include_paths) {
+        preprocessor_ = preprocessor;
+        include_paths_ = std::move(include_paths);
+    }
+
+    const std::vector<Node>& getNodes() const { return nodes_; }
+    const Node* findNode(const std::string& path) const;
+
+    /**
+     * Files included in dependency order (included files before includers)
+     */
+    std::vector<std::string> topologicalOrder() const;
+
+    /**
+     * True if `from` includes `to` directly or transitively
+     */
+    bool reaches(const std::string& from, const std::string& to) const;
+
+    /**
+     * Quoted includes that could not be found
+     */
+    const std::vector<std::string>& getUnresolved() const { return unresolved_; }
+
+private:
+    std::vector<Node> nodes_;
+    std::vector<std::string> unresolved_;
+    FileSystem* fs_ = nullptr;      // Set by build() to read included files
+    Preprocessor* preprocessor_ = nullptr;
+    std::vector<std::string> include_paths_;
+
+    std::string locate(const std::string& including_file, const std::string& include);
+    static std::vector<std::string> findIncludes(const std::string& source);
+    static std::string resolve(const std::string& including_file, const std::string& include);
+};
+
+/**
+ * A generated Rust source file
+ */
+struct ModuleFile {
+    std::string path;       // Relative to the output directory
+    std::string content;
+};
+
+/**
+ * Module Splitter
+ *
+ * Assigns every declaration to the file that defines it, finds which
+ * declarations each including file actually uses, and splits headers
+ * whose declarations are used by different consumers into submodules.
+ * Only declarations used outside their header are re-exported.
+ *
+ * With crates assigned to the files, every crate gets its own module tree
+ * (<crate>/src/lib.rs); declarations from another crate are imported by
+ * crate name, and only modules other crates use are pub.
+ *
+ * The file defining main becomes main.rs, the root of a binary crate,
+ * which declares the other modules of its crate.
+ */
+class ModuleSplitter {
+public:
+    /**
+     * @param configure Sets the options of the code generator of each
+     *        module, the same ones a single-file translation gets
+     */
+    explicit ModuleSplitter(std::function<void(RustCodeGenerator&)> configure = nullptr)
+        : configure_(std::move(configure)) {}
+
+    /**
+     * @param graph Include graph of the input
+     * @param ir Analyzed IR of the whole translation unit
+     * @param provenance Class, enum, free function and (library mode) type
+     *        alias names declared by each file
+     */
+    std::vector<ModuleFile> split(const IncludeGraph& graph, const IR& ir,
+                                  const std::map<std::string, std::vector<std::string>>& provenance);
+
+    /**
+     * Split into workspace crates: file path -> crate name. Crates that
+     * use each other are merged, since Cargo rejects dependency cycles
+     */
+    void setCrates(std::map<std::string, std::string> crate_of) { crates_ = std::move(crate_of); }
+
+    /**
+     * Crates of the last split() and the crates each one uses, after merging
+     */
+    const std::map<std::string, std::set<std::string>>& getCrateDependencies() const { return crate_dependencies_; }
+
+    /**
+     * Rust module name for a source path: include/net-utils.h -> net_utils
+     */
+    static std::string moduleName(const std::string& path);
+
+    /**
+     * Crate name for a directory or build target: src/net-io -> src_net_io.
+     * Names of std crates and of the dependencies generated code uses
+     * (log, libc, ...) get a _cpp suffix
+     */
+    static std::string crateName(const std::string& text);
+
+    /**
+     * Diagnostics reported while generating the modules
+     */
+    const std::vector<Diagnostic>& getDiagnostics() const { return diagnostics_; }
+
+    /**
+     * Code generation statistics summed over all modules
+     */
+    const GenerationStats& getStats() const { return stats_; }
+
+private:
+    struct Group {
+        std::string name;
+        std::set<std::string> consumers;     // Files using the group's declarations
+        std::vector<std::string> declarations;
+    };
+
+    std::function<void(RustCodeGenerator&)> configure_;
+    std::set<std::string> allocator_api_crates_;         // Crates whose modules need #![feature(allocator_api)]
+    std::vector<Diagnostic> diagnostics_;
+    GenerationStats stats_;
+    std::map<std::string, std::string> owner_;           // Declaration -> defining file
+    std::map<std::string, std::set<std::string>> uses_;  // File -> declarations it names
+    std::map<std::string, std::string> crates_;          // File -> crate as set; empty for one crate
+    std::map<std::string, std::string> crate_of_;        // File -> crate after merging cycles
+    std::map<std::string, std::set<std::string>> crate_dependencies_;
+    std::string entry_file_;                             // File defining main: the root of its crate
+
+    std::string crateOf(const std::string& path) const;
+    std::string moduleOf(const std::string& path) const;
+    void mergeCrateCycles();
+
+    // Rust items a declaration is imported and re-exported as
+    static std::vector<std::string> rustItems(const IR& ir, const std::string& name);
+    std::vector<Group> groupDeclarations(const IncludeGraph& graph, const std::string& path,
+                                         const std::vector<std::string>& declarations);
+    std::string generateModule(const IR& ir, const std::vector<std::string>& declarations,
+                               const std::set<std::string>& names_used,
+                               const std::map<std::string, std::string>& sibling_of,
+                               const std::string& self_path);
+    static std::set<std::string> identifiers(const std::string& source);
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_MODULES_H
@@ -2,6 +2,9 @@
 #define HYBRID_PARSER_H
 
 #include "ir.h"
+#include "instantiation_cache.h"
+#include "pipeline.h"
+#include "runtime_trace.h"
 #include <string>
 
 namespace hybrid {
@@ -17,17 +20,28 @@ public:
      * Parse C++ source file
      *
      * @param filename Path to C++ source file
+     * @param standard Language standard; features from later standards are rejected
+     * @param log_macros Additional logging macros and their log crate levels
      * @return Intermediate representation of the parsed code
      */
-    static IR parseFile(const std::string& filename);
+    static IR parseFile(const std::string& filename, CppStandard standard = CppStandard::Cxx20,
+                        const LogMacroTable& log_macros = {});
 
     /**
      * Parse C++ source string
      *
      * @param source C++ source code as string
+     * @param standard Language standard; features from later standards are rejected
+     * @param log_macros Additional logging macros and their log crate levels
+     * @param trace Threads and mutexes observed at run time, refining Rc/Arc and Mutex choices
+     * @param instantiation_cache Template instantiations already analyzed by other files of the project
+     * @param pipeline Passes to run up to code generation (Pipeline::standard() if null)
      * @return Intermediate representation of the parsed code
      */
-    static IR parseString(const std::string& source);
+    static IR parseString(const std::string& source, CppStandard standard = CppStandard::Cxx20,
+                          const LogMacroTable& log_macros = {}, const RuntimeTrace& trace = {},
+                          InstantiationCache* instantiation_cache = nullptr,
+                          const Pipeline* pipeline = nullptr);
 };
 
 } // namespace hybrid
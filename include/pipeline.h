@@ -0,0 +1,278 @@
+#ifndef HYBRID_PIPELINE_H
+#define HYBRID_PIPELINE_H
+
+#include "ir.h"
+#include <algorithm>
+#include <functional>
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Stages of a translation, in the order they run
+ */
+enum class PassStage {
+    Parse,      // Declarations and bodies into the IR, each class analyzed alone
+    Resolve,    // Types: pImpl owners, layouts, constexpr, bases, members
+    Ownership,  // Copies, borrows, allocations, Rc/Arc
+    Idiom,      // Library calls and patterns with a Rust counterpart
+    Codegen     // The target source
+};
+
+inline const char* passStageName(PassStage stage) {
+    switch (stage) {
+        case PassStage::Parse: return "parse";
+        case PassStage::Resolve: return "resolve";
+        case PassStage::Ownership: return "ownership";
+        case PassStage::Idiom: return "idiom";
+        case PassStage::Codegen: return "codegen";
+    }
+    return "";
+}
+
+/**
+ * One step of the pipeline
+ *
+ * Built-in passes are run by the parser and the code generator; custom
+ * passes bring their own function, which may change the IR in any way
+ * the passes after it understand.
+ */
+class Pass {
+public:
+    std::string name;
+    PassStage stage = PassStage::Idiom;
+    std::string description;
+    std::function<void(IR&)> run;   // Empty for built-in passes
+    bool enabled = true;
+
+    bool isBuiltin() const { return !run; }
+};
+
+/**
+ * The passes of a translation, in order
+ *
+ * `parse` comes first and `codegen` last; both always run. The analysis
+ * passes in between can be disabled, custom passes can be placed before
+ * or after any of them, and the IR can be handed to a sink after a pass
+ * to see what it changed.
+ */
+class Pipeline {
+public:
+    /**
+     * The built-in passes, grouped by stage
+     */
+    static const Pipeline& standard() {
+        static const Pipeline pipeline = [] {
+            Pipeline p;
+            p.add("parse", PassStage::Parse, "Classes, type aliases and main into the IR");
+            p.add("moves", PassStage::Parse, "std::move, std::exchange and move constructors");
+            p.add("guards", PassStage::Parse, "lock_guard and scope-exit guards");
+            p.add("format", PassStage::Parse, "std::format and fmt calls");
+            p.add("varargs", PassStage::Parse, "va_list and va_arg in variadic functions");
+            p.add("numeric", PassStage::Parse, "std::numeric_limits members");
+            // First: the others see the fields and methods of a pImpl class in its owner
+            p.add("pimpl", PassStage::Resolve, "pImpl classes merged into their owner");
+            p.add("layout", PassStage::Resolve, "sizeof/alignof/offsetof as std::mem queries");
+            p.add("constexpr", PassStage::Resolve, "if constexpr chains resolved per instantiation");
+            p.add("member-pointers", PassStage::Resolve, "Pointers to members (&C::m, obj.*p)");
+            p.add("inheritance", PassStage::Resolve, "Several bases as embedded fields and traits");
+            // Passes after it leave functions kept in C++ alone
+            p.add("retention", PassStage::Resolve, "Signal handlers and setjmp kept in C++");
+            p.add("copies", PassStage::Ownership, "Copy vs Clone and implicit copies");
+            p.add("self-references", PassStage::Ownership, "Objects pointing at themselves or each other");
+            p.add("allocations", PassStage::Ownership, "Placement new and class-specific operator new");
+            p.add("memory-safety", PassStage::Ownership, "Use after free, double delete, overruns");
+            p.add("slices", PassStage::Ownership, "(T*, size_t) pairs, std::span and new T[n] buffers");
+            // Last of its stage: Rc becomes Arc once ownership of every type is settled
+            p.add("thread-safety", PassStage::Ownership, "shared_ptr types reaching other threads");
+            p.add("functors", PassStage::Idiom, "Function objects and their use sites");
+            p.add("algorithms", PassStage::Idiom, "<algorithm> and <numeric> calls over containers");
+            p.add("integers", PassStage::Idiom, "Wrapping arithmetic and integer conversions");
+            p.add("libc", PassStage::Idiom, "Direct POSIX/C library calls");
+            p.add("logging", PassStage::Idiom, "glog, spdlog and logging macros");
+            p.add("status", PassStage::Idiom, "Error-code enums returned as status values");
+            p.add("construction", PassStage::Idiom, "Constructors that can fail");
+            p.add("enum-strings", PassStage::Idiom, "Hand-written enum/string conversions");
+            p.add("bindings", PassStage::Idiom, "Structured bindings, std::tie and pairs");
+            p.add("command-line", PassStage::Idiom, "Option parsing loops of main");
+            p.add("entry-point", PassStage::Idiom, "Exit status of main and exit() calls");
+            p.add("codegen", PassStage::Codegen, "Rust or Go source from the IR");
+            return p;
+        }();
+        return pipeline;
+    }
+
+    const std::vector<Pass>& passes() const { return passes_; }
+
+    const Pass* find(const std::string& name) const {
+        auto found = std::find_if(passes_.begin(), passes_.end(),
+                                  [&name](const Pass& pass) { return pass.name == name; });
+        return found == passes_.end() ? nullptr : &*found;
+    }
+
+    bool isEnabled(const std::string& name) const {
+        const Pass* pass = find(name);
+        return pass && pass->enabled;
+    }
+
+    /**
+     * Skip a pass; what it would have recorded is left out of the IR
+     * @return false if there is no such pass or it cannot be skipped
+     */
+    bool disable(const std::string& name, std::string& error) {
+        Pass* pass = lookup(name, error);
+        if (!pass) return false;
+        if (pass->name == "parse" || pass->name == "codegen") {
+            error = "the '" + name + "' pass cannot be disabled";
+            return false;
+        }
+        pass->enabled = false;
+        return true;
+    }
+
+    /**
+     * Add a custom pass before another; it joins that pass's stage
+     */
+    bool insertBefore(const std::string& anchor, const Pass& pass, std::string& error) {
+        return insert(anchor, pass, 0, error);
+    }
+
+    /**
+     * Add a custom pass after another; it joins that pass's stage
+     */
+    bool insertAfter(const std::string& anchor, const Pass& pass, std::string& error) {
+        return insert(anchor, pass, 1, error);
+    }
+
+    /**
+     * Move a custom pass before another pass; built-in passes keep their order
+     */
+    bool moveBefore(const std::string& name, const std::string& anchor, std::string& error) {
+        return move(name, anchor, 0, error);
+    }
+
+    bool moveAfter(const std::string& name, const std::string& anchor, std::string& error) {
+        return move(name, anchor, 1, error);
+    }
+
+    /**
+     * Hand the IR to the dump sink after a pass, or after every pass ("all")
+     */
+    bool dumpAfter(const std::string& name, std::string& error) {
+        if (name == "all") {
+            dump_all_ = true;
+            return true;
+        }
+        Pass* pass = lookup(name, error);
+        if (!pass) return false;
+        if (pass->stage == PassStage::Codegen) {
+            error = "the 'codegen' pass does not change the IR";
+            return false;
+        }
+        dump_after_.push_back(name);
+        return true;
+    }
+
+    bool dumps() const { return dump_all_ || !dump_after_.empty(); }
+
+    bool dumpsAfter(const std::string& name) const {
+        return dump_all_ || std::find(dump_after_.begin(), dump_after_.end(), name) != dump_after_.end();
+    }
+
+    /**
+     * @param sink Called with the position of the pass in passes() and the IR it left
+     */
+    void setDumpSink(std::function<void(size_t, const Pass&, const IR&)> sink) { dump_sink_ = std::move(sink); }
+
+    /**
+     * Called by the parser when a pass has run
+     */
+    void finished(size_t index, const IR& ir) const {
+        const Pass& pass = passes_[index];
+        if (dump_sink_ && pass.stage != PassStage::Codegen && dumpsAfter(pass.name)) {
+            dump_sink_(index, pass, ir);
+        }
+    }
+
+private:
+    std::vector<Pass> passes_;
+    std::vector<std::string> dump_after_;
+    bool dump_all_ = false;
+    std::function<void(size_t, const Pass&, const IR&)> dump_sink_;
+
+    void add(const std::string& name, PassStage stage, const std::string& description) {
+        Pass pass;
+        pass.name = name;
+        pass.stage = stage;
+        pass.description = description;
+        passes_.push_back(pass);
+    }
+
+    Pass* lookup(const std::string& name, std::string& error) {
+        auto found = std::find_if(passes_.begin(), passes_.end(),
+                                  [&name](const Pass& pass) { return pass.name == name; });
+        if (found == passes_.end()) {
+            error = "unknown pass '" + name + "'";
+            return nullptr;
+        }
+        return &*found;
+    }
+
+    /**
+     * Index a custom pass may take next to an anchor: after parse has
+     * built the IR and before codegen reads it
+     */
+    bool position(const std::string& anchor, size_t after, size_t& index, std::string& error) {
+        Pass* pass = lookup(anchor, error);
+        if (!pass) return false;
+        if ((pass->name == "parse" && !after) || (pass->name == "codegen" && after)) {
+            error = "custom passes run after 'parse' and before 'codegen'";
+            return false;
+        }
+        index = static_cast<size_t>(pass - passes_.data()) + after;
+        return true;
+    }
+
+    bool insert(const std::string& anchor, Pass pass, size_t after, std::string& error) {
+        size_t index = 0;
+        if (!pass.run) {
+            error = "the custom pass '" + pass.name + "' has no function to run";
+            return false;
+        }
+        if (find(pass.name)) {
+            error = "a pass named '" + pass.name + "' already exists";
+            return false;
+        }
+        if (!position(anchor, after, index, error)) return false;
+        // Joins the stage of its neighbour on the side of the anchor
+        pass.stage = passes_[after ? index - 1 : index].stage;
+        passes_.insert(passes_.begin() + static_cast<std::ptrdiff_t>(index), pass);
+        return true;
+    }
+
+    bool move(const std::string& name, const std::string& anchor, size_t after, std::string& error) {
+        Pass* pass = lookup(name, error);
+        if (!pass) return false;
+        if (pass->isBuiltin()) {
+            error = "built-in passes keep their order; '" + name + "' cannot be moved";
+            return false;
+        }
+        if (name == anchor) return true;
+        std::vector<Pass> before = passes_;
+        Pass moved = *pass;
+        passes_.erase(passes_.begin() + (pass - passes_.data()));
+        size_t index = 0;
+        if (!position(anchor, after, index, error)) {
+            passes_ = before;
+            return false;
+        }
+        moved.stage = passes_[after ? index - 1 : index].stage;
+        passes_.insert(passes_.begin() + static_cast<std::ptrdiff_t>(index), moved);
+        return true;
+    }
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_PIPELINE_H
@@ -0,0 +1,55 @@
+#ifndef HYBRID_PREPROCESSOR_H
+#define HYBRID_PREPROCESSOR_H
+
+#include "build_database.h"
+#include <functional>
+#include <map>
+#include <set>
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Conditional compilation for one build configuration
+ *
+ * Keeps the lines of the #if/#ifdef/#elif/#else groups that are taken
+ * and blanks the others, and the conditional directives themselves, so
+ * line numbers do not move. #define and #undef on taken lines update the
+ * macros for the rest of the file and for the files it includes after
+ * them. Macros are not expanded in the code; that is left to the parser
+ * as before.
+ */
+class Preprocessor {
+public:
+    explicit Preprocessor(const BuildConfiguration& config);
+
+    /**
+     * Called where a taken #include appears, so an included file sees the
+     * macros defined before it and its own #defines reach the includer
+     */
+    using IncludeHandler = std::function<void(const std::string& include, bool angled)>;
+
+    std::string process(const std::string& source, const IncludeHandler& on_include = nullptr);
+
+    bool isDefined(const std::string& name) const { return macros_.count(name) > 0; }
+
+    /**
+     * Conditions using what cannot be evaluated here (__has_include,
+     * function-like macros): assumed false, once per condition
+     */
+    const std::vector<std::string>& getNotes() const { return notes_; }
+
+private:
+    std::map<std::string, std::string> macros_;     // Object-like macros and their replacement
+    std::set<std::string> function_macros_;         // #define F(x) ...: defined, not evaluated
+    std::vector<std::string> notes_;
+
+    long evaluate(const std::string& condition);
+    std::vector<std::string> expand(const std::vector<std::string>& tokens, int depth);
+    static std::vector<std::string> tokenize(const std::string& text);
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_PREPROCESSOR_H
@@ -0,0 +1,60 @@
+#ifndef HYBRID_REGIONS_H
+#define HYBRID_REGIONS_H
+
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Generated regions of an output file (--update)
+ *
+ * Every top-level item (a struct, a function, the file header) is
+ * written between two marker comments; the begin marker names the item
+ * and records a hash of it as generated. Impl and trait blocks are split
+ * into their head, each member and the closing brace:
+ *
+ *   // hybrid-transpiler: begin 9f1c2e4a7b30d865 impl Parser
+ *   impl Parser {
+ *   // hybrid-transpiler: end
+ *       // hybrid-transpiler: begin 53b0e1f2c47a9d18 impl Parser: pub fn parse
+ *       ...
+ *       // hybrid-transpiler: end
+ *   // hybrid-transpiler: begin 08dfd407b57ea6e6 impl Parser: end
+ *   }
+ *   // hybrid-transpiler: end
+ *
+ * The hash is the common base of a three-way merge: a region whose text
+ * no longer matches it was edited by hand, an item whose new translation
+ * no longer matches it changed in the C++. Text outside the markers is
+ * never touched.
+ */
+struct RegionMerge {
+    bool success = false;
+    std::string content;                    // The file to write
+    std::string error;                      // Set when success is false
+    std::vector<std::string> conflicts;     // Hand-edited items the C++ changed or no longer declares
+    std::vector<std::string> overwritten;   // Hand-edited items replaced anyway (force)
+
+    size_t retranslated = 0;                // Changed in the C++, not edited: replaced
+    size_t unchanged = 0;
+    size_t kept = 0;                        // Edited by hand, not changed in the C++: left alone
+    size_t added = 0;
+    size_t removed = 0;
+};
+
+/**
+ * Merge a new translation into the file written by a previous run (an
+ * empty existing file gives the translation with its markers)
+ *
+ * Items whose translation changed are replaced, new items are inserted
+ * after the item that precedes them, items the C++ no longer declares are
+ * removed. A hand-edited region the new translation would replace or
+ * remove is a conflict, and nothing is merged, unless force is set; so is
+ * an existing file without markers.
+ */
+RegionMerge mergeRegions(const std::string& existing, const std::string& generated, bool force);
+
+} // namespace hybrid
+
+#endif // HYBRID_REGIONS_H
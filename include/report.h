@@ -0,0 +1,44 @@
+#ifndef HYBRID_REPORT_H
+#define HYBRID_REPORT_H
+
+#include "ir.h"
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * An unsafe item of the generated code, listed by --report so each one
+ * can be reviewed
+ */
+struct UnsafeSite {
+    std::string file;       // Output file
+    size_t line = 0;        // From 1
+    std::string kind;       // block, fn, impl or extern
+    std::string code;       // The line, trimmed
+};
+
+/**
+ * Unsafe blocks, functions, impls and extern blocks of generated Rust,
+ * ignoring `//` comments as the metrics do
+ */
+std::vector<UnsafeSite> auditUnsafe(const std::string& file, const std::string& code);
+
+/**
+ * C++ declaration whose Rust translation has another name
+ */
+struct RenamedSymbol {
+    std::string kind;       // class, enum, enumerator, function, method or field
+    std::string cpp;        // Point::getX
+    std::string rust;       // point::get_x
+};
+
+/**
+ * Every class, enum, enumerator, function, method and field of the IR
+ * that the Rust generator renames, in declaration order
+ */
+std::vector<RenamedSymbol> renamedSymbols(const IR& ir);
+
+} // namespace hybrid
+
+#endif // HYBRID_REPORT_H
@@ -0,0 +1,50 @@
+#ifndef HYBRID_RUNTIME_TRACE_H
+#define HYBRID_RUNTIME_TRACE_H
+
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * What an instrumented C++ build observed at run time (--runtime-trace)
+ *
+ * The static analysis only sees threads it can follow in the source, and
+ * assumes every shared pointee is written concurrently. A trace tells it
+ * which shared_ptr pointees actually reached several threads, whether they
+ * were written while shared, and which mutexes were ever locked from more
+ * than one thread.
+ */
+struct RuntimeTrace {
+    struct SharedPointee {
+        std::string type;                   // Pointee as written: Cache, net::Session
+        int threads = 0;                    // Distinct threads that held a shared_ptr to it
+        bool written_while_shared = true;   // Modified while held by more than one thread
+    };
+
+    struct MutexUse {
+        std::string name;                   // Class::member
+        int threads = 0;                    // Distinct threads that locked it
+        long contended = 0;                 // Locks that had to wait
+    };
+
+    std::vector<SharedPointee> shared_ptrs;
+    std::vector<MutexUse> mutexes;
+
+    bool empty() const { return shared_ptrs.empty() && mutexes.empty(); }
+};
+
+/**
+ * Read a trace from JSON:
+ * {"shared_ptrs": [{"type": "Cache", "threads": 3, "written_while_shared": false}],
+ *  "mutexes": [{"name": "Counter::mutex_", "threads": 1, "contended": 0}]}
+ *
+ * A missing written_while_shared counts as written.
+ * @param error Set to the reason when the trace is rejected
+ * @return false if the text is not a valid trace
+ */
+bool parseRuntimeTrace(const std::string& text, RuntimeTrace& trace, std::string& error);
+
+} // namespace hybrid
+
+#endif // HYBRID_RUNTIME_TRACE_H
@@ -0,0 +1,68 @@
+#ifndef HYBRID_SNAPSHOT_H
+#define HYBRID_SNAPSHOT_H
+
+#include "transpiler.h"
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Result of checking one fixture against its snapshot
+ */
+struct SnapshotResult {
+    enum Status {
+        Passed,     // Output matches the snapshot
+        Failed,     // Output differs (see diff)
+        Blessed,    // Snapshot written in bless mode
+        Missing     // No snapshot exists for the fixture
+    };
+
+    std::string fixture;        // Path of the .cpp fixture
+    std::string snapshot;       // Path of the expected .rs/.go file
+    Status status = Passed;
+    std::string diff;           // Line diff (expected -> actual) when failed
+};
+
+/**
+ * Snapshot (golden file) test harness
+ *
+ * Fixture convention: every `<name>.cpp` in a fixture directory has the
+ * expected output next to it as `<name>.rs` and/or `<name>.go`. Outputs are
+ * compared after normalization (line endings, trailing whitespace, runs of
+ * blank lines). In bless mode, mismatching or missing snapshots are
+ * (re)written from the current output instead of failing.
+ */
+class SnapshotHarness {
+public:
+    explicit SnapshotHarness(bool bless = false) : bless_(bless) {}
+
+    /**
+     * Check every fixture in a directory (sorted by name)
+     */
+    std::vector<SnapshotResult> runDirectory(const std::string& directory);
+
+    /**
+     * Check a single fixture against its snapshot for one target
+     */
+    SnapshotResult runFixture(const std::string& cpp_path, TargetLanguage target);
+
+    /**
+     * Transpile C++ source in memory
+     */
+    static std::string transpileSource(const std::string& source, TargetLanguage target);
+
+    static std::string normalize(const std::string& code);
+
+    /**
+     * Line diff of two normalized texts, `-` expected / `+` actual
+     */
+    static std::string diff(const std::string& expected, const std::string& actual);
+
+private:
+    bool bless_;
+};
+
+} // namespace hybrid
+
+#endif // HYBRID_SNAPSHOT_H
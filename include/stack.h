@@ -0,0 +1,30 @@
+#ifndef HYBRID_STACK_H
+#define HYBRID_STACK_H
+
+#include <cstddef>
+#include <functional>
+
+namespace hybrid {
+
+/**
+ * Stack given to parsing and code generation
+ *
+ * The regex-based passes recurse once per character they match, and type
+ * and template arguments recurse once per nesting level, so the default
+ * 8 MB main thread stack limits how deep input can nest. Pages are only
+ * committed as they are touched.
+ */
+constexpr size_t DEEP_STACK_SIZE = size_t(1) << 30;
+
+/**
+ * Run a task on a thread with a stack of the given size and wait for it
+ *
+ * Exceptions thrown by the task are rethrown in the caller. Calls made
+ * while already on such a thread, and platforms without POSIX threads
+ * (WebAssembly, Windows), run the task directly.
+ */
+void runWithStack(const std::function<void()>& task, size_t stack_size = DEEP_STACK_SIZE);
+
+} // namespace hybrid
+
+#endif // HYBRID_STACK_H
@@ -0,0 +1,68 @@
+#ifndef HYBRID_SYMBOLS_H
+#define HYBRID_SYMBOLS_H
+
+#include "ir.h"
+#include <string>
+#include <vector>
+#include <map>
+#include <set>
+
+namespace hybrid {
+
+/**
+ * Whole-program symbol database
+ *
+ * Symbols are classes (`Name`), methods (`Class::method`, destructors as
+ * `Class::~Class`) and free functions (`name`). Each symbol records the
+ * symbols it references through types, base classes and calls.
+ */
+class SymbolDatabase {
+public:
+    explicit SymbolDatabase(const IR& ir);
+
+    bool contains(const std::string& symbol) const;
+
+    /**
+     * All symbols, in declaration order
+     */
+    const std::vector<std::string>& getSymbols() const { return symbols_; }
+
+    /**
+     * Symbols directly referenced by `symbol`
+     */
+    const std::set<std::string>& referencesOf(const std::string& symbol) const;
+
+    /**
+     * Symbols transitively referenced from the roots (roots included).
+     * A class name as root also roots all of its methods.
+     */
+    std::set<std::string> reachableFrom(const std::vector<std::string>& roots) const;
+
+    static std::string methodSymbol(const ClassDecl& class_decl, const Function& method);
+
+private:
+    std::vector<std::string> symbols_;
+    std::set<std::string> classes_;
+    std::map<std::string, std::set<std::string>> references_;
+    std::map<std::string, std::vector<std::string>> methods_of_;   // Class -> method symbols
+    std::map<std::string, std::vector<std::string>> by_name_;      // Callable name -> symbols
+
+    void addSymbol(const std::string& symbol);
+    void addTypeReferences(const std::string& from, const std::shared_ptr<Type>& type);
+    void addBodyReferences(const std::string& from, const std::string& owner_class,
+                           const std::string& body);
+};
+
+/**
+ * Remove classes, methods and functions not reachable from the entry points
+ * @param ir IR to prune in place
+ * @param entry_points Symbols to keep (see SymbolDatabase)
+ * @param unknown Receives entry points that name no symbol
+ * @return Number of declarations removed
+ */
+size_t pruneUnused(IR& ir, const std::vector<std::string>& entry_points,
+                   std::vector<std::string>& unknown);
+
+} // namespace hybrid
+
+#endif // HYBRID_SYMBOLS_H
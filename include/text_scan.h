@@ -0,0 +1,122 @@
+#ifndef HYBRID_TEXT_SCAN_H
+#define HYBRID_TEXT_SCAN_H
+
+#include <algorithm>
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Scanning helpers for source text, shared by the analysis passes
+ */
+
+/**
+ * Index of the quote closing the string or character literal starting
+ * at quote (text.size() if it is not closed)
+ */
+inline size_t skipLiteral(const std::string& text, size_t quote) {
+    size_t i = quote + 1;
+    while (i < text.size() && text[i] != text[quote]) {
+        i += text[i] == '\\' ? 2 : 1;
+    }
+    return std::min(i, text.size());
+}
+
+/**
+ * Split an argument list at top-level commas, ignoring commas inside
+ * literals and inside the brackets given (template argument lists need
+ * "<([" and ">)]")
+ */
+inline std::vector<std::string> splitArguments(const std::string& args_str, const std::string& opening = "({[",
+                                               const std::string& closing = ")}]") {
+    auto trim = [](const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return std::string();
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    };
+
+    std::vector<std::string> args;
+    std::string current;
+    int depth = 0;
+
+    for (size_t i = 0; i < args_str.size(); ++i) {
+        char c = args_str[i];
+        if (c == '"' || c == '\'') {
+            size_t end = std::min(skipLiteral(args_str, i), args_str.size() - 1);
+            current += args_str.substr(i, end - i + 1);
+            i = end;
+            continue;
+        }
+
+        if (opening.find(c) != std::string::npos) depth++;
+        else if (closing.find(c) != std::string::npos) depth--;
+
+        if (c == ',' && depth == 0) {
+            args.push_back(trim(current));
+            current.clear();
+        } else {
+            current += c;
+        }
+    }
+    if (!trim(current).empty()) {
+        args.push_back(trim(current));
+    }
+    return args;
+}
+
+/**
+ * Index of the close_char matching the open_char at open (npos if it is
+ * not closed); brackets inside literals are not counted unless
+ * skip_literals is false, as for Rust text where ' starts a lifetime
+ */
+inline size_t findClosing(const std::string& text, size_t open, char open_char, char close_char,
+                          bool skip_literals = true) {
+    if (open == std::string::npos) return std::string::npos;
+    int depth = 0;
+    for (size_t i = open; i < text.size(); ++i) {
+        char c = text[i];
+        if (skip_literals && (c == '"' || c == '\'')) {
+            i = skipLiteral(text, i);
+        } else if (c == open_char) {
+            depth++;
+        } else if (c == close_char && --depth == 0) {
+            return i;
+        }
+    }
+    return std::string::npos;
+}
+
+inline size_t findClosingParen(const std::string& text, size_t open) {
+    return findClosing(text, open, '(', ')');
+}
+
+/**
+ * Order sites found by several scans of a body by their position in it
+ *
+ * Code generators rewrite a body with a single cursor moving forward, so
+ * every list of sites recorded on a function is kept in source order;
+ * sites at the same position keep the order they were found in.
+ */
+template <typename Site>
+void sortBySourcePosition(std::vector<std::pair<size_t, Site>>& found) {
+    using Entry = std::pair<size_t, Site>;
+    std::stable_sort(found.begin(), found.end(), [](const Entry& a, const Entry& b) { return a.first < b.first; });
+}
+
+/**
+ * Append found sites to a function's list in source order
+ */
+template <typename Site>
+void appendInSourceOrder(std::vector<std::pair<size_t, Site>>& found, std::vector<Site>& sites) {
+    sortBySourcePosition(found);
+    for (auto& entry : found) {
+        sites.push_back(std::move(entry.second));
+    }
+}
+
+} // namespace hybrid
+
+#endif // HYBRID_TEXT_SCAN_H
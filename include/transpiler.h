@@ -1,15 +1,33 @@
 #ifndef HYBRID_TRANSPILER_H
 #define HYBRID_TRANSPILER_H
 
+#include "ir.h"
+#include "instantiation_cache.h"
+#include "metrics.h"
+#include "pipeline.h"
+#include "mapping_packs.h"
+#include "report.h"
+#include "runtime_trace.h"
+#include <atomic>
+#include <functional>
 #include <string>
+#include <map>
+#include <set>
 #include <memory>
 #include <vector>
 
 namespace hybrid {
 
 // Forward declarations
-class IR;
+class BuildDatabase;
 class CodeGenerator;
+class FileSystem;
+class IncludeGraph;
+class RecordingFileSystem;
+struct BuildConfiguration;
+struct Explanation;
+struct FileReport;
+struct ModuleFile;
 
 /**
  * Target language for transpilation
@@ -19,17 +37,66 @@ enum class TargetLanguage {
     Go
 };
 
+/**
+ * What the transpiler writes to the output path
+ */
+enum class EmitKind {
+    Code,       // Generated Rust/Go source
+    AstJson,    // Declarations as parsed (--emit=ast-json)
+    IrJson      // Analyzed IR (--emit=ir-json)
+};
+
+/**
+ * How a module tree is partitioned into workspace crates (--split-crates-by)
+ */
+enum class CrateSplit {
+    None,
+    Directory,  // One crate per directory of the input files
+    Target      // One crate per CMake target of the compilation database
+};
+
 /**
  * Transpilation options
  */
 struct TranspilerOptions {
     TargetLanguage target = TargetLanguage::Rust;
+    CppStandard standard = CppStandard::Cxx20;
     int optimization_level = 0;
     bool enable_safety_checks = true;
+    bool use_thiserror = false;     // Derive thiserror::Error for error-code enums
+    VarargsStrategy varargs = VarargsStrategy::Slice;  // C variadic functions (--varargs)
+    bool allocator_api = false;     // Custom allocators as nightly Allocator parameters
+    SelfRefStrategy self_ref = SelfRefStrategy::Report;  // Self-referential classes (--self-ref)
+    LibcStrategy libc = LibcStrategy::Std;  // Direct POSIX/C library calls (--libc)
+    bool clap = false;              // Option parsing loop of main as a clap derive struct
+    bool strum = false;             // strum derives for hand-written enum string conversions
+    DataModel data_model = DataModel::LP64;  // Width of long (--data-model)
+    std::vector<MappingPack> mapping_packs;  // Library types (--mapping-pack), first match wins
+    RuntimeTrace runtime_trace;     // Threads observed at run time (--runtime-trace)
+    std::shared_ptr<InstantiationCache> instantiation_cache;  // Shared by the files of a project; per run if null
+    Pipeline pipeline = Pipeline::standard();  // Passes to run (--disable-pass) and the IR dumps (--dump-ir-after)
+    std::string dump_ir_dir;        // Where the IR dumps of the pipeline are written (--dump-ir-dir)
+    std::string compile_commands;   // compile_commands.json, or the build directory holding it
+    std::string target_config;      // Which command to use for files compiled more than once
     bool preserve_comments = true;
     bool generate_tests = false;
     bool verbose = false;           // Verbose output
     bool quiet = false;             // Minimal output
+    bool split_modules = false;     // Emit a module tree (output_path is a directory)
+    bool library = false;           // Emit a library crate from headers (output_path is the crate directory)
+    CrateSplit split_crates = CrateSplit::None;  // Emit a Cargo workspace (output_path is its directory)
+    bool prune_unused = false;      // Skip declarations unreachable from entry_points
+    EmitKind emit = EmitKind::Code;
+    std::string metrics_path;       // Write a TranslationMetrics JSON report here if set
+    QualityBudget budget;           // Fail the run when the output is over it (--max-unsafe, --max-clones)
+    std::string report_path;        // Write a versioned project report (docs/report.schema.json) here if set
+    bool deterministic = false;     // Check that a second run writes the same files; no timings in metrics
+    bool update = false;            // Merge into the existing output, keeping hand-edited regions
+    bool force = false;             // With update: overwrite hand-edited regions the C++ changed
+    std::vector<std::string> entry_points;
+    std::vector<std::string> deferred_classes;  // Stubbed for a later migration phase (--defer)
+    std::string explain;            // Record the decisions about this symbol instead of writing output
+    LogMacroTable log_macros;       // Extra logging macros (--log-macro NAME=level)
     std::string output_path;
 };
 
@@ -38,7 +105,12 @@ struct TranspilerOptions {
  */
 class Transpiler {
 public:
-    explicit Transpiler(const TranspilerOptions& options);
+    /**
+     * @param options Transpilation options
+     * @param fs Where input is read and output written (the disk if null)
+     */
+    explicit Transpiler(const TranspilerOptions& options,
+                        std::shared_ptr<FileSystem> fs = nullptr);
     ~Transpiler();
 
     /**
@@ -60,16 +132,183 @@ public:
      */
     const std::string& getLastError() const { return last_error_; }
 
+    /**
+     * Get warnings and notes reported by analysis and code generation
+     */
+    const std::vector<Diagnostic>& getDiagnostics() const { return diagnostics_; }
+
+    /**
+     * Statistics of the last transpile() call
+     */
+    const TranslationMetrics& getMetrics() const { return metrics_; }
+
+    /**
+     * The last transpile() call as one file of a project report: status,
+     * diagnostics, metrics, unsafe code and renamed symbols
+     */
+    FileReport getFileReport() const;
+
+    /**
+     * Decisions of the last transpile() call about a class, Class::method
+     * or function, with the diagnostics behind them
+     * @return false if the input has no such symbol
+     */
+    bool explain(const std::string& symbol, Explanation& explanation) const;
+
 private:
     TranspilerOptions options_;
+    std::shared_ptr<FileSystem> fs_;
+    std::shared_ptr<RecordingFileSystem> recorded_;  // What a run writes, with --deterministic
     std::unique_ptr<IR> ir_;
     std::unique_ptr<CodeGenerator> codegen_;
     std::string last_error_;
+    std::vector<Diagnostic> diagnostics_;
+    std::vector<Decision> decisions_;
+    TranslationMetrics metrics_;
+    std::vector<UnsafeSite> unsafe_sites_;
 
     bool parseSourceFile(const std::string& input_path);
+    bool parse(const std::string& source, IR& ir);
+    bool loadBuildDatabase(BuildDatabase& database);
+    bool loadBuildConfiguration(const std::string& input_path, BuildConfiguration& config, bool& found);
+    bool transpileModules(const std::string& input_path);
+    bool assignCrates(const IncludeGraph& graph, std::map<std::string, std::string>& crate_of,
+                      std::map<std::string, std::string>& crate_sources);
+    bool splitModules(const IncludeGraph& graph, const std::map<std::string, std::vector<std::string>>& provenance,
+                      const std::map<std::string, std::string>& crate_of, std::vector<ModuleFile>& files,
+                      std::map<std::string, std::set<std::string>>& crate_dependencies);
+    bool generateLibraryRoot(std::vector<ModuleFile>& files);
+    void addCrateDocs(std::string& lib, const std::string& input_path) const;
+    bool writeManifest(const std::string& input_path, const std::vector<ModuleFile>& files);
+    bool writeWorkspace(const std::map<std::string, std::set<std::string>>& crate_dependencies,
+                        const std::map<std::string, std::string>& crate_sources, const std::vector<ModuleFile>& files);
+    bool writeManifestFile(const std::string& path, const std::string& manifest);
+    bool pruneUnusedDeclarations();
+    bool deferClasses(const std::vector<std::pair<int, std::string>>& files);
     bool generateCode(const std::string& output_path);
+    bool writeOutput(const std::string& path, const std::string& code);
+    bool transpileAgain(const std::string& input_path, std::map<std::string, std::string>& written,
+                        std::string& error) const;
+    bool checkDeterministic(const std::map<std::string, std::string>& again);
+};
+
+/**
+ * Result of transpiling an in-memory snippet
+ */
+struct SnippetResult {
+    bool success = false;
+    std::string code;                       // Generated Rust/Go source
+    std::string error;                      // Set when success is false
+    std::vector<Diagnostic> diagnostics;
 };
 
+/**
+ * Transpile C++ source held in memory, without touching the disk
+ *
+ * Used by embedders such as the WebAssembly playground. Module splitting
+ * and library crates are not available for snippets.
+ */
+SnippetResult transpileSnippet(const std::string& source,
+                               const TranspilerOptions& options = TranspilerOptions());
+
+/**
+ * Output path used when -o is not given: the input with the target's
+ * extension (point.cpp -> point.rs), or point_rs / point_crate for
+ * module trees and crates
+ */
+std::string defaultOutputPath(const std::string& input_path, const TranspilerOptions& options);
+
+/**
+ * Stops transpileProject() from another thread (a GUI's cancel button);
+ * the file being translated is finished first
+ */
+class CancellationToken {
+public:
+    void cancel() { cancelled_ = true; }
+    bool isCancelled() const { return cancelled_; }
+
+private:
+    std::atomic<bool> cancelled_{false};
+};
+
+/**
+ * Progress of transpileProject(), reported before and after each file
+ */
+struct ProjectProgress {
+    enum Stage {
+        Started,
+        Finished
+    };
+
+    Stage stage = Started;
+    std::string input_path;
+    size_t index = 0;           // Position of the file, from 0
+    size_t total = 0;           // Files in the project
+    bool success = false;       // Finished: whether the file was translated
+};
+
+using ProgressCallback = std::function<void(const ProjectProgress& progress)>;
+
+/**
+ * Outcome of one file of a project
+ */
+struct FileReport {
+    enum Status {
+        Translated,
+        Failed,
+        Skipped     // Cancelled before it was started
+    };
+
+    Status status = Skipped;
+    std::string input_path;
+    std::string output_path;
+    std::string error;                      // Set when Failed
+    std::vector<Diagnostic> diagnostics;
+    TranslationMetrics metrics;
+    std::vector<UnsafeSite> unsafe_sites;   // Rust output only
+    std::vector<RenamedSymbol> renames;     // Rust output only
+
+    JsonValue toJson() const;
+};
+
+/**
+ * Outcome of transpileProject(), one entry per input in order
+ */
+struct ProjectReport {
+    std::vector<FileReport> files;
+    size_t translated = 0;
+    size_t failed = 0;
+    size_t skipped = 0;
+    bool cancelled = false;
+    std::string error;          // Set when the report_path file could not be written
+
+    bool success() const { return failed == 0 && !cancelled && error.empty(); }
+
+    /**
+     * The --report document: format "hybrid-report", described by
+     * docs/report.schema.json
+     */
+    JsonValue toJson() const;
+};
+
+/**
+ * Transpile several files for an embedder (GUI, CI job): nothing is
+ * printed, a failing file does not stop the others, and the result is a
+ * report instead of a log
+ *
+ * Each file is written to options.output_path as a directory, keeping the
+ * relative path of the input (src/net/socket.cpp -> <dir>/src/net/socket.rs;
+ * absolute inputs by file name), or next to the input when output_path is
+ * empty. options.metrics_path is ignored: each file's metrics are in the
+ * report, which is written to options.report_path if set.
+ * @param progress Called on the calling thread before and after each file
+ * @param cancel Checked before each file; the rest are Skipped once it is set
+ * @param fs Where input is read and output written (the disk if null)
+ */
+ProjectReport transpileProject(const std::vector<std::string>& input_paths, const TranspilerOptions& options,
+                               const ProgressCallback& progress = nullptr, const CancellationToken* cancel = nullptr,
+                               std::shared_ptr<FileSystem> fs = nullptr);
+
 } // namespace hybrid
 
 #endif // HYBRID_TRANSPILER_H
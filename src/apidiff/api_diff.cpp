@@ -0,0 +1,746 @@
+#include "apidiff.h"
+#include "codegen.h"
+#include "parser.h"
+#include "text_scan.h"
+#include <regex>
+#include <map>
+#include <set>
+#include <cctype>
+
+namespace hybrid {
+
+namespace {
+
+/**
+ * Comments, string and character literals and preprocessor lines as
+ * spaces, so braces and semicolons in them are not seen; offsets and
+ * newlines are kept
+ */
+std::string blankNonCode(const std::string& code, bool cpp) {
+    std::string result = code;
+    bool line_start = true;
+    for (size_t i = 0; i < result.size(); ++i) {
+        char c = result[i];
+        if (cpp && line_start && c == '#') {
+            // A directive, continued lines included
+            for (; i < result.size() && result[i] != '\n'; ++i) {
+                if (result[i] == '\\' && i + 1 < result.size() && result[i + 1] == '\n') result[i++] = ' ';
+                result[i] = ' ';
+            }
+            line_start = true;
+            continue;
+        }
+        if (c == '\n') {
+            line_start = true;
+            continue;
+        }
+        if (!std::isspace(static_cast<unsigned char>(c))) line_start = false;
+
+        if (c == '/' && i + 1 < result.size() && result[i + 1] == '/') {
+            for (; i < result.size() && result[i] != '\n'; ++i) result[i] = ' ';
+            line_start = true;
+        } else if (c == '/' && i + 1 < result.size() && result[i + 1] == '*') {
+            size_t end = result.find("*/", i + 2);
+            end = end == std::string::npos ? result.size() : end + 2;
+            for (; i < end; ++i) {
+                if (result[i] != '\n') result[i] = ' ';
+            }
+            --i;
+        } else if (c == '"' || (c == '\'' && (cpp || (i + 2 < result.size() &&
+                                                      (result[i + 2] == '\'' || result[i + 1] == '\\'))))) {
+            // In Rust, 'a is a lifetime
+            for (++i; i < result.size() && result[i] != c && result[i] != '\n'; ++i) {
+                if (result[i] == '\\') result[i++] = ' ';
+                if (i < result.size()) result[i] = ' ';
+            }
+        }
+    }
+    return result;
+}
+
+std::string trim(const std::string& str) {
+    size_t start = str.find_first_not_of(" \t\n\r");
+    if (start == std::string::npos) return "";
+    size_t end = str.find_last_not_of(" \t\n\r");
+    return str.substr(start, end - start + 1);
+}
+
+// Whitespace runs as one space, none inside brackets: fn parse(&self, text: &str) -> i32
+std::string normalize(const std::string& text) {
+    std::string spaced = std::regex_replace(trim(text), std::regex(R"(\s+)"), " ");
+    spaced = std::regex_replace(spaced, std::regex(R"(\s*([(\[<])\s*)"), "$1");
+    spaced = std::regex_replace(spaced, std::regex(R"(\s*([)\]>,]))"), "$1");
+    spaced = std::regex_replace(spaced, std::regex(R"(,(\S))"), ", $1");
+    spaced = std::regex_replace(spaced, std::regex(R"(\)->)"), ") ->");
+    return std::regex_replace(spaced, std::regex(R"(->\()"), "-> (");
+}
+
+/**
+ * Parameters of a list, split at top-level commas
+ */
+std::vector<std::string> splitParameters(const std::string& list) {
+    std::vector<std::string> parts;
+    std::string current;
+    int depth = 0;
+    for (char c : list) {
+        if (c == '<' || c == '(' || c == '[' || c == '{') depth++;
+        else if (c == '>' || c == ')' || c == ']' || c == '}') depth--;
+        if (c == ',' && depth == 0) {
+            parts.push_back(trim(current));
+            current.clear();
+            continue;
+        }
+        current += c;
+    }
+    if (!trim(current).empty()) parts.push_back(trim(current));
+    return parts;
+}
+
+// const std::vector<Token>& -> Token's container: vector; Parser* -> Parser
+std::string baseTypeName(const std::string& type) {
+    std::string base = std::regex_replace(type, std::regex(R"(<.*>)"), "");
+    base = std::regex_replace(base, std::regex(R"(\b(?:const|volatile|struct|class|mut|dyn)\b|[&*'])"), " ");
+    base = trim(base);
+    size_t qualifier = base.rfind("::");
+    if (qualifier != std::string::npos) base = base.substr(qualifier + 2);
+    size_t space = base.find_last_of(" \t");
+    return trim(space == std::string::npos ? base : base.substr(space + 1));
+}
+
+// ---------------------------------------------------------------------
+// C++ surface
+// ---------------------------------------------------------------------
+
+/**
+ * Trait a C++ operator is implemented through in Rust (empty if none).
+ * operands counts the explicit parameters plus one for a free operator
+ */
+std::string operatorTrait(const std::string& symbol, size_t operands) {
+    static const std::map<std::string, std::string> binary = {
+        {"==", "PartialEq"}, {"!=", "PartialEq"}, {"<", "PartialOrd"}, {">", "PartialOrd"},
+        {"<=", "PartialOrd"}, {">=", "PartialOrd"}, {"+", "Add"}, {"-", "Sub"}, {"*", "Mul"},
+        {"/", "Div"}, {"%", "Rem"}, {"&", "BitAnd"}, {"|", "BitOr"}, {"^", "BitXor"}, {"<<", "Shl"},
+        {">>", "Shr"}, {"+=", "AddAssign"}, {"-=", "SubAssign"}, {"*=", "MulAssign"}, {"/=", "DivAssign"},
+        {"%=", "RemAssign"}, {"[]", "Index"}};
+    static const std::map<std::string, std::string> unary = {{"-", "Neg"}, {"!", "Not"}, {"~", "Not"}, {"*", "Deref"}};
+    if (operands <= 1) {
+        auto found = unary.find(symbol);
+        return found == unary.end() ? "" : found->second;
+    }
+    auto found = binary.find(symbol);
+    return found == binary.end() ? "" : found->second;
+}
+
+class CppScanner {
+public:
+    explicit CppScanner(const std::string& source) : text_(blankNonCode(source, true)) {}
+
+    std::vector<ApiItem> scan() {
+        scanScope(0, text_.size(), "", true);
+        return items_;
+    }
+
+private:
+    std::string text_;
+    std::vector<ApiItem> items_;
+
+    void scanScope(size_t begin, size_t end, const std::string& owner, bool is_public) {
+        static const std::regex namespace_head(R"(^(?:inline\s+)?namespace\b|^extern\s*"\s*"$)");
+        static const std::regex enum_head(R"(\benum\s+(?:(?:class|struct)\s+)?([A-Za-z_]\w*))");
+        static const std::regex class_head(R"(^(?:template\s*<[\s\S]*>\s*)?(class|struct|union)\s+(?:alignas\s*\([^)]*\)\s*)?([A-Za-z_]\w*))");
+        static const std::regex init_list(R"(\)\s*(?:const\s*)?(?:noexcept\s*)?:[^:])");
+
+        size_t head_start = begin;
+        for (size_t i = begin; i < end; ++i) {
+            char c = text_[i];
+            if (c != ';' && c != '{') continue;
+            std::string head = text_.substr(head_start, i - head_start);
+            is_public = accessAfterLabels(head, is_public);
+
+            if (c == ';') {
+                if (is_public) declaration(head, owner);
+                head_start = i + 1;
+                continue;
+            }
+
+            // Literals are blanked already, so brackets are matched as they are
+            size_t close = findClosing(text_, i, '{', '}', false);
+            if (close == std::string::npos || close > end) return;
+
+            // Braces of a member initializer: Parser() : depth_{0} {
+            size_t last = head.find_last_not_of(" \t\n\r");
+            if (std::regex_search(head, init_list) && last != std::string::npos &&
+                (std::isalnum(static_cast<unsigned char>(head[last])) || head[last] == '_' || head[last] == '>')) {
+                i = close;
+                continue;
+            }
+
+            std::smatch match;
+            std::string trimmed = trim(head);
+            if (std::regex_search(trimmed, namespace_head)) {
+                scanScope(i + 1, close, "", true);
+            } else if (std::regex_search(trimmed, match, enum_head) && trimmed.find('(') == std::string::npos) {
+                if (is_public) addType("enum", match[1].str());
+                close = skipDeclarators(close, end);
+            } else if (std::regex_search(trimmed, match, class_head)) {
+                std::string name = match[2].str();
+                if (is_public) {
+                    addType(match[1].str(), name);
+                    scanScope(i + 1, close, name, match[1].str() != "class");
+                }
+                close = skipDeclarators(close, end);
+            } else if (is_public) {
+                declaration(head, owner);
+            }
+            i = close;
+            head_start = close + 1;
+        }
+    }
+
+    // struct Point { ... } origin; ends at the ';'
+    size_t skipDeclarators(size_t close, size_t end) const {
+        size_t semicolon = text_.find(';', close);
+        return semicolon == std::string::npos || semicolon > end ? close : semicolon;
+    }
+
+    // public: / private: labels in a head set the access of what follows; the head is what is left
+    static bool accessAfterLabels(std::string& head, bool is_public) {
+        static const std::regex label(R"(\b(public|protected|private)\s*:(?!:))");
+        size_t after = 0;
+        for (auto it = std::sregex_iterator(head.begin(), head.end(), label); it != std::sregex_iterator(); ++it) {
+            is_public = (*it)[1].str() == "public";
+            after = static_cast<size_t>(it->position(0) + it->length(0));
+        }
+        head = head.substr(after);
+        return is_public;
+    }
+
+    void addType(const std::string& keyword, const std::string& name) {
+        ApiItem item;
+        item.kind = ApiItem::Type;
+        item.name = RustCodeGenerator::sanitizeName(name);
+        item.cpp = keyword + " " + name;
+        items_.push_back(item);
+    }
+
+    /**
+     * A function or method declaration or definition; anything else
+     * (fields, using, friend declarations, macros) is not API or not seen
+     */
+    void declaration(std::string head, const std::string& owner) {
+        static const std::regex skipped(R"(^\s*(?:typedef|using|friend|static_assert|template\s*<[^>]*>\s*friend)\b)");
+        static const std::regex template_prefix(R"(^\s*template\s*<)");
+        static const std::regex specifiers(R"(^\s*(?:(inline|static|virtual|explicit|constexpr|consteval|extern|\[\[[^\]]*\]\])\s+))");
+        static const std::set<std::string> keywords = {"if", "while", "for", "switch", "return", "sizeof", "decltype",
+                                                       "alignas", "catch", "static_assert", "noexcept", "throw"};
+        head = trim(head);
+        if (head.empty() || std::regex_search(head, skipped)) return;
+
+        // template <typename T> and specifiers before the declaration
+        if (std::regex_search(head, template_prefix)) {
+            size_t close = findClosing(head, head.find('<'), '<', '>', false);
+            if (close == std::string::npos) return;
+            head = trim(head.substr(close + 1));
+        }
+        bool is_static = false;
+        std::smatch specifier;
+        while (std::regex_search(head, specifier, specifiers)) {
+            is_static = is_static || specifier[1].str() == "static";
+            head = head.substr(static_cast<size_t>(specifier.length(0)));
+        }
+
+        std::string name;
+        size_t name_start = 0;
+        size_t open = parameterList(head, name, name_start);
+        if (open == std::string::npos || keywords.count(name)) return;
+        size_t close = findClosing(head, open, '(', ')', false);
+        if (close == std::string::npos) return;
+        std::string return_type = trim(head.substr(0, name_start));
+        std::string rest = head.substr(close + 1);
+        if (std::regex_search(rest, std::regex(R"(=\s*delete\b)"))) return;
+        bool is_const = std::regex_search(rest, std::regex(R"(^\s*const\b)"));
+
+        // Parser::parse(...) { }: defined out of line, declared in the class
+        if (return_type.size() >= 2 && return_type.compare(return_type.size() - 2, 2, "::") == 0) return;
+
+        std::vector<std::string> params;
+        std::vector<std::string> shown;
+        for (const auto& param : splitParameters(head.substr(open + 1, close - open - 1))) {
+            if (param == "void" || param == "...") {
+                if (param == "...") shown.push_back(param);
+                continue;
+            }
+            // const std::string& text = "": the type, and the name if there is one
+            std::string declarator = trim(param.substr(0, param.find('=')));
+            std::smatch named;
+            static const std::regex parameter_name(R"(^.*[\w>*&\]]\s*[*&\s]\s*([A-Za-z_]\w*)\s*(?:\[[^\]]*\])?$)");
+            bool has_name = std::regex_search(declarator, named, parameter_name);
+            params.push_back(has_name ? trim(declarator.substr(0, static_cast<size_t>(named.position(1)))) : declarator);
+            shown.push_back(has_name ? named[1].str() : declarator);
+        }
+
+        ApiItem item;
+        item.owner = owner.empty() ? "" : RustCodeGenerator::sanitizeName(owner);
+        item.arity = params.size();
+        item.cpp = (owner.empty() ? "" : owner + "::") + name + "(";
+        for (size_t i = 0; i < shown.size(); ++i) item.cpp += (i > 0 ? ", " : "") + shown[i];
+        item.cpp += std::string(")") + (is_const ? " const" : "");
+
+        if (name.compare(0, 8, "operator") == 0) {
+            std::string symbol = trim(name.substr(8));
+            if (symbol == "()") {
+                item.kind = ApiItem::Method;
+                item.name = "call";
+                item.has_self = true;
+            } else if (symbol.compare(0, 3, "new") == 0 || symbol.compare(0, 6, "delete") == 0) {
+                return;  // Class allocation functions: --allocator-api
+            } else if (symbol == "=") {
+                if (!params.empty() && params[0].find("&&") != std::string::npos) return;  // Moves are implicit
+                item.kind = ApiItem::TraitImpl;
+                item.name = "Clone";
+            } else {
+                std::string trait;
+                if (owner.empty()) {
+                    // bool operator==(const A&, const A&); std::ostream& operator<<(std::ostream&, const A&)
+                    if (params.empty()) return;
+                    bool stream = symbol == "<<" && params[0].find("ostream") != std::string::npos;
+                    trait = stream ? "Display" : operatorTrait(symbol, params.size());
+                    item.owner = RustCodeGenerator::sanitizeName(baseTypeName(params[stream ? params.size() - 1 : 0]));
+                } else {
+                    trait = operatorTrait(symbol, params.size() + 1);
+                }
+                item.kind = trait.empty() ? ApiItem::Method : ApiItem::TraitImpl;
+                item.name = trait.empty() ? name : trait;
+                item.has_self = true;
+            }
+        } else if (!owner.empty() && name == owner) {
+            std::regex self_reference("^(?:const\\s+" + owner + "|" + owner + "\\s+const)\\s*&$");
+            if (params.size() == 1 && params[0].find("&&") != std::string::npos) return;
+            if (params.size() == 1 && std::regex_match(params[0], self_reference)) {
+                item.kind = ApiItem::TraitImpl;
+                item.name = "Clone";
+            } else {
+                item.kind = ApiItem::Method;
+                item.name = "new";
+            }
+        } else if (!owner.empty() && name == "~" + owner) {
+            item.kind = ApiItem::TraitImpl;
+            item.name = "Drop";
+        } else {
+            // No return type: a macro call, not a declaration
+            if (return_type.empty() || name[0] == '~' || (owner.empty() && name == "main")) return;
+            item.kind = owner.empty() ? ApiItem::Function : ApiItem::Method;
+            item.name = RustCodeGenerator::sanitizeName(name);
+            item.has_self = !owner.empty() && !is_static;
+        }
+        items_.push_back(item);
+    }
+
+    /**
+     * The '(' opening the parameters: the first at template depth 0, or the
+     * one after the operator symbol. Sets name (and where it starts) to what
+     * precedes it
+     */
+    static size_t parameterList(const std::string& head, std::string& name, size_t& name_start) {
+        static const std::regex operator_name(R"(\boperator\s*(\(\s*\)|\[\s*\]|[^\w\s(]+|new\s*(?:\[\s*\])?|delete\s*(?:\[\s*\])?)\s*\()");
+        std::smatch match;
+        if (std::regex_search(head, match, operator_name)) {
+            name = "operator" + std::regex_replace(match[1].str(), std::regex(R"(\s+)"), "");
+            name_start = static_cast<size_t>(match.position(0));
+            return static_cast<size_t>(match.position(0) + match.length(0)) - 1;
+        }
+
+        int depth = 0;
+        for (size_t i = 0; i < head.size(); ++i) {
+            char c = head[i];
+            if (c == '<') depth++;
+            else if (c == '>') depth--;
+            else if (c == '(' && depth == 0) {
+                size_t end = head.find_last_not_of(" \t\n\r", i == 0 ? 0 : i - 1);
+                if (i == 0 || end == std::string::npos) return std::string::npos;
+                size_t start = end + 1;
+                while (start > 0 && (std::isalnum(static_cast<unsigned char>(head[start - 1])) || head[start - 1] == '_')) start--;
+                if (start > 0 && head[start - 1] == '~') start--;
+                if (start > end) return std::string::npos;
+                name = head.substr(start, end + 1 - start);
+                name_start = start;
+                return i;
+            }
+        }
+        return std::string::npos;
+    }
+};
+
+// ---------------------------------------------------------------------
+// Rust surface
+// ---------------------------------------------------------------------
+
+class RustScanner {
+public:
+    explicit RustScanner(const std::string& code) : text_(blankNonCode(code, false)) {}
+
+    std::vector<ApiItem> scan() {
+        scanScope(0, text_.size(), "", false);
+        return items_;
+    }
+
+private:
+    std::string text_;
+    std::vector<ApiItem> items_;
+
+    void scanScope(size_t begin, size_t end, const std::string& owner, bool trait_impl) {
+        static const std::regex module_head(R"(^(?:pub(?:\([^)]*\))?\s+)?mod\s+\w+$)");
+        static const std::regex type_head(R"(^pub(?:\([^)]*\))?\s+(struct|enum|union|trait|type)\s+([A-Za-z_]\w*))");
+
+        size_t head_start = begin;
+        for (size_t i = begin; i < end; ++i) {
+            char c = text_[i];
+            if (c == '[' ) {
+                // #[derive(...)] and array types hold no item boundaries
+                size_t close = findClosing(text_, i, '[', ']', false);
+                if (close != std::string::npos && close < end) i = close;
+                continue;
+            }
+            if (c != ';' && c != '{') continue;
+
+            std::vector<std::string> derives;
+            std::string head = stripAttributes(text_.substr(head_start, i - head_start), derives);
+            size_t close = i;
+            if (c == '{') {
+                close = findClosing(text_, i, '{', '}', false);
+                if (close == std::string::npos || close > end) return;
+            }
+
+            std::smatch match;
+            if (std::regex_search(head, module_head) && c == '{') {
+                scanScope(i + 1, close, "", false);
+            } else if (head.compare(0, 4, "impl") == 0 && (head.size() == 4 || !std::isalnum(static_cast<unsigned char>(head[4])))) {
+                std::string trait;
+                std::string type = implTarget(head, trait);
+                if (!trait.empty()) {
+                    addItem(ApiItem::TraitImpl, type, baseTypeName(trait), "");
+                }
+                if (c == '{') scanScope(i + 1, close, type, !trait.empty());
+            } else if (std::regex_search(head, match, type_head)) {
+                std::string name = match[2].str();
+                addItem(ApiItem::Type, "", name, "");
+                for (const auto& derive : derives) addItem(ApiItem::TraitImpl, name, derive, "");
+            } else if (std::regex_search(head, match, std::regex(R"(^(pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern\s*"\s*")\s+)*fn\s+([A-Za-z_]\w*))"))) {
+                if (match[1].matched && !trait_impl) {
+                    addItem(owner.empty() ? ApiItem::Function : ApiItem::Method, owner, match[2].str(),
+                            head.substr(static_cast<size_t>(match.length(1))));
+                }
+            }
+            i = close;
+            head_start = close + 1;
+        }
+    }
+
+    static std::string stripAttributes(std::string head, std::vector<std::string>& derives) {
+        head = trim(head);
+        while (head.compare(0, 2, "#[") == 0 || head.compare(0, 3, "#![") == 0) {
+            size_t open = head.find('[');
+            size_t close = findClosing(head, open, '[', ']', false);
+            if (close == std::string::npos) break;
+            std::smatch derive;
+            std::string attribute = head.substr(open + 1, close - open - 1);
+            if (std::regex_match(attribute, derive, std::regex(R"(\s*derive\s*\(([\s\S]*)\)\s*)"))) {
+                for (const auto& name : splitParameters(derive[1].str())) derives.push_back(baseTypeName(name));
+            }
+            head = trim(head.substr(close + 1));
+        }
+        return head;
+    }
+
+    // impl<T> Trait for Type<T> where ...: Type, and Trait in trait
+    static std::string implTarget(const std::string& head, std::string& trait) {
+        std::string rest = trim(head.substr(4));
+        if (!rest.empty() && rest[0] == '<') {
+            size_t close = findClosing(rest, 0, '<', '>', false);
+            rest = close == std::string::npos ? "" : trim(rest.substr(close + 1));
+        }
+        size_t where = rest.find(" where ");
+        if (where != std::string::npos) rest = rest.substr(0, where);
+        int depth = 0;
+        for (size_t i = 0; i + 5 <= rest.size(); ++i) {
+            if (rest[i] == '<') depth++;
+            else if (rest[i] == '>') depth--;
+            else if (depth == 0 && rest.compare(i, 5, " for ") == 0) {
+                trait = trim(rest.substr(0, i));
+                return baseTypeName(rest.substr(i + 5));
+            }
+        }
+        return baseTypeName(rest);
+    }
+
+    void addItem(ApiItem::Kind kind, const std::string& owner, const std::string& name, const std::string& signature) {
+        ApiItem item;
+        item.kind = kind;
+        item.owner = owner;
+        item.name = name;
+        if (!signature.empty()) {
+            item.signature = normalize(signature);
+            size_t open = item.signature.find('(');
+            size_t close = open == std::string::npos ? open : findClosing(item.signature, open, '(', ')', false);
+            if (close != std::string::npos) {
+                for (const auto& param : splitParameters(item.signature.substr(open + 1, close - open - 1))) {
+                    if (std::regex_match(param, std::regex(R"((?:&(?:'\w+\s*)?)?(?:mut\s+)?self\b.*)"))) {
+                        item.has_self = true;
+                    } else {
+                        item.arity++;
+                    }
+                }
+            }
+        }
+        items_.push_back(item);
+    }
+};
+
+// ---------------------------------------------------------------------
+// Comparison
+// ---------------------------------------------------------------------
+
+// fn parse(&self) -> i32 -> fn (&self) -> i32
+std::string withoutName(const std::string& signature) {
+    return std::regex_replace(signature, std::regex(R"(\bfn\s+\w+)"), "fn ");
+}
+
+class ApiComparison {
+public:
+    ApiComparison(const std::vector<ApiItem>& cpp, const std::vector<ApiItem>& translated,
+                  const std::vector<ApiItem>& crate)
+        : cpp_(cpp), translated_(translated), crate_(crate), claimed_(crate.size(), false),
+          translated_claimed_(translated.size(), false) {}
+
+    ApiDiff run() {
+        ApiDiff diff;
+        diff.cpp_items = cpp_.size();
+        std::vector<bool> done(cpp_.size(), false);
+        std::vector<std::vector<ApiMismatch>> found(cpp_.size());
+
+        // Types first: methods of a renamed type are looked up under its new name
+        std::set<std::string> missing_types;
+        for (size_t i = 0; i < cpp_.size(); ++i) {
+            if (cpp_[i].kind != ApiItem::Type) continue;
+            done[i] = true;
+            const ApiItem& item = cpp_[i];
+            long crate_index = find(crate_, claimed_, ApiItem::Type, "", item.name, 0, false);
+            if (crate_index >= 0) {
+                claimed_[static_cast<size_t>(crate_index)] = true;
+                owners_[item.name] = item.name;
+                continue;
+            }
+            std::string renamed = renamedType(item);
+            if (!renamed.empty()) {
+                owners_[item.name] = renamed;
+                found[i].push_back({ApiMismatch::Renamed, item.cpp,
+                                    "is " + renamed + " in the crate (translated as " + item.name + ")"});
+                continue;
+            }
+            missing_types.insert(item.name);
+            bool translated = find(translated_, translated_claimed_, ApiItem::Type, "", item.name, 0, false) >= 0;
+            size_t members = 0;
+            for (const auto& member : cpp_) members += member.owner == item.name ? 1 : 0;
+            std::string with_members = members > 0 ? " (with its " + std::to_string(members) + " public members)" : "";
+            found[i].push_back({ApiMismatch::Missing, item.cpp,
+                                (translated ? "no " + item.name + " in the crate" : "not translated, and not in the crate") +
+                                    with_members});
+        }
+
+        // Same name first, so a rename cannot take an item another declaration matches
+        std::vector<long> translated_of(cpp_.size(), -1);
+        for (size_t i = 0; i < cpp_.size(); ++i) {
+            if (done[i]) continue;
+            const ApiItem& item = cpp_[i];
+            if (missing_types.count(item.owner)) {
+                done[i] = true;  // Reported with its type
+                diff.cpp_items--;
+                continue;
+            }
+            std::string owner = ownerInCrate(item.owner);
+            if (item.kind == ApiItem::TraitImpl) {
+                done[i] = true;
+                bool has_impl = false;
+                for (const auto& candidate : crate_) {
+                    has_impl = has_impl || (candidate.kind == ApiItem::TraitImpl && candidate.owner == owner &&
+                                            candidate.name == item.name);
+                }
+                if (!has_impl) {
+                    found[i].push_back({ApiMismatch::Missing, item.cpp,
+                                        "no impl " + item.name + " for " + owner + " in the crate"});
+                }
+                continue;
+            }
+
+            translated_of[i] = find(translated_, translated_claimed_, item.kind, item.owner, item.name, item.arity, true);
+            if (translated_of[i] >= 0) translated_claimed_[static_cast<size_t>(translated_of[i])] = true;
+            long crate_index = find(crate_, claimed_, item.kind, owner, item.name, item.arity, true);
+            if (crate_index < 0) continue;
+            done[i] = true;
+            claimed_[static_cast<size_t>(crate_index)] = true;
+            const ApiItem& actual = crate_[static_cast<size_t>(crate_index)];
+            if (translated_of[i] >= 0) {
+                const ApiItem& expected = translated_[static_cast<size_t>(translated_of[i])];
+                if (expected.signature != actual.signature) {
+                    found[i].push_back({ApiMismatch::Changed, item.cpp,
+                                        expected.signature + " in the translation, " + actual.signature + " in the crate"});
+                }
+            } else if (actual.arity != item.arity) {
+                found[i].push_back({ApiMismatch::Changed, item.cpp,
+                                    std::to_string(item.arity) + " parameters in the C++, " +
+                                        std::to_string(actual.arity) + " in the crate (" + actual.signature + ")"});
+            }
+        }
+
+        // Not found by name: renamed, or missing
+        for (size_t i = 0; i < cpp_.size(); ++i) {
+            if (done[i]) continue;
+            const ApiItem& item = cpp_[i];
+            const ApiItem* expected = translated_of[i] >= 0 ? &translated_[static_cast<size_t>(translated_of[i])] : nullptr;
+            long renamed = renamedFunction(item, expected);
+            if (renamed >= 0) {
+                claimed_[static_cast<size_t>(renamed)] = true;
+                found[i].push_back({ApiMismatch::Renamed, item.cpp,
+                                    "is " + crate_[static_cast<size_t>(renamed)].name + " in the crate (" +
+                                        crate_[static_cast<size_t>(renamed)].signature + ")"});
+            } else {
+                found[i].push_back({ApiMismatch::Missing, item.cpp,
+                                    expected ? "not in the crate (translated as " + expected->signature + ")"
+                                             : "not translated, and not in the crate"});
+            }
+        }
+
+        for (size_t i = 0; i < cpp_.size(); ++i) {
+            if (found[i].empty() && !missing_types.count(cpp_[i].owner)) diff.matched++;
+            diff.mismatches.insert(diff.mismatches.end(), found[i].begin(), found[i].end());
+        }
+        return diff;
+    }
+
+private:
+    const std::vector<ApiItem>& cpp_;
+    const std::vector<ApiItem>& translated_;
+    const std::vector<ApiItem>& crate_;
+    std::vector<bool> claimed_;
+    std::vector<bool> translated_claimed_;
+    std::map<std::string, std::string> owners_;   // C++ type (Rust name) -> type in the crate
+
+    std::string ownerInCrate(const std::string& owner) const {
+        auto found = owners_.find(owner);
+        return found == owners_.end() ? owner : found->second;
+    }
+
+    // Unclaimed item of that kind, owner and name; one with the same parameter count is preferred
+    static long find(const std::vector<ApiItem>& items, const std::vector<bool>& claimed, ApiItem::Kind kind,
+                     const std::string& owner, const std::string& name, size_t arity, bool by_arity) {
+        long first = -1;
+        for (size_t i = 0; i < items.size(); ++i) {
+            const ApiItem& item = items[i];
+            if (claimed[i] || item.kind != kind || item.owner != owner || item.name != name) continue;
+            if (!by_arity || item.arity == arity) return static_cast<long>(i);
+            if (first < 0) first = static_cast<long>(i);
+        }
+        return first;
+    }
+
+    // Anything the translation writes under the same name is a translation, not a rename
+    bool translatedAs(const ApiItem& item, const std::string& owner) const {
+        for (const auto& translated : translated_) {
+            if (translated.kind == item.kind && translated.owner == owner && translated.name == item.name) return true;
+        }
+        return false;
+    }
+
+    /**
+     * A crate type no C++ class accounts for, with the most method names
+     * in common with the class
+     */
+    std::string renamedType(const ApiItem& type) {
+        std::set<std::string> methods;
+        for (const auto& item : cpp_) {
+            if (item.kind == ApiItem::Method && item.owner == type.name) methods.insert(item.name);
+        }
+        long best = -1;
+        size_t best_common = 0;
+        for (size_t i = 0; i < crate_.size(); ++i) {
+            const ApiItem& candidate = crate_[i];
+            if (claimed_[i] || candidate.kind != ApiItem::Type || translatedAs(candidate, "")) continue;
+            size_t common = 0;
+            for (const auto& item : crate_) {
+                if (item.kind == ApiItem::Method && item.owner == candidate.name && methods.count(item.name)) common++;
+            }
+            if (common > best_common) {
+                best = static_cast<long>(i);
+                best_common = common;
+            }
+        }
+        if (best < 0) return "";
+        claimed_[static_cast<size_t>(best)] = true;
+        return crate_[static_cast<size_t>(best)].name;
+    }
+
+    /**
+     * An unclaimed function of the same owner the translation does not
+     * write, with the translated signature or else the same parameters
+     */
+    long renamedFunction(const ApiItem& item, const ApiItem* expected) const {
+        std::string owner = ownerInCrate(item.owner);
+        for (size_t i = 0; i < crate_.size(); ++i) {
+            const ApiItem& candidate = crate_[i];
+            if (claimed_[i] || candidate.kind != item.kind || candidate.owner != owner) continue;
+            if (translatedAs(candidate, item.owner)) continue;
+            bool same = expected ? withoutName(candidate.signature) == withoutName(expected->signature)
+                                 : candidate.arity == item.arity && candidate.has_self == item.has_self;
+            if (same) return static_cast<long>(i);
+        }
+        return -1;
+    }
+};
+
+} // namespace
+
+std::vector<ApiItem> cppApiSurface(const std::string& source) {
+    return CppScanner(source).scan();
+}
+
+std::vector<ApiItem> rustApiSurface(const std::string& code) {
+    return RustScanner(code).scan();
+}
+
+ApiDiff diffApi(const std::vector<std::string>& cpp_sources, const std::vector<std::string>& rust_sources) {
+    std::vector<ApiItem> cpp;
+    std::string translation_unit;
+    for (const auto& source : cpp_sources) {
+        std::vector<ApiItem> items = cppApiSurface(source);
+        cpp.insert(cpp.end(), items.begin(), items.end());
+        translation_unit += source + "\n";
+    }
+
+    // The translation the crate started from, for its signatures
+    std::vector<ApiItem> translated;
+    try {
+        RustCodeGenerator generator;
+        translated = rustApiSurface(generator.generate(Parser::parseString(translation_unit)));
+    } catch (const std::exception&) {
+        // Compared by name and parameter count only
+    }
+
+    std::vector<ApiItem> crate;
+    for (const auto& code : rust_sources) {
+        std::vector<ApiItem> items = rustApiSurface(code);
+        crate.insert(crate.end(), items.begin(), items.end());
+    }
+
+    return ApiComparison(cpp, translated, crate).run();
+}
+
+const char* apiMismatchKindName(ApiMismatch::Kind kind) {
+    switch (kind) {
+        case ApiMismatch::Missing: return "missing";
+        case ApiMismatch::Changed: return "changed";
+        case ApiMismatch::Renamed: return "renamed";
+    }
+    return "missing";
+}
+
+} // namespace hybrid
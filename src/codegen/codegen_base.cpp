@@ -1,4 +1,7 @@
 #include "codegen.h"
+#include <algorithm>
+#include <cctype>
+#include <map>
 
 namespace hybrid {
 
@@ -16,4 +19,148 @@ void CodeGenerator::writeIndent() {
     }
 }
 
+void CodeGenerator::report(Diagnostic::Severity severity, const std::string& message,
+                           const std::string& context) {
+    Diagnostic diagnostic;
+    diagnostic.severity = severity;
+    diagnostic.message = message;
+    diagnostic.context = context;
+    diagnostics_.push_back(diagnostic);
+}
+
+void CodeGenerator::decide(const std::string& rule, const std::string& subject, const std::string& outcome,
+                           const std::string& reason) {
+    Decision decision;
+    decision.rule = rule;
+    decision.context = decision_context_;
+    decision.subject = decision_subject_.empty() ? subject : decision_subject_ + " (" + subject + ")";
+    decision.outcome = outcome;
+    decision.reason = reason;
+    bool recorded = std::any_of(decisions_.begin(), decisions_.end(), [&decision](const Decision& d) {
+        return d.rule == decision.rule && d.context == decision.context && d.subject == decision.subject;
+    });
+    if (!recorded) decisions_.push_back(decision);
+}
+
+void CodeGenerator::countFunction(bool translated) {
+    if (translated) {
+        stats_.functions_translated++;
+    } else {
+        stats_.functions_stubbed++;
+    }
+}
+
+std::string CodeGenerator::enumeratorName(const std::string& name) {
+    std::string base = name;
+    if (base.size() > 1 && base[0] == 'k' && std::isupper(static_cast<unsigned char>(base[1]))) {
+        base = base.substr(1);  // kNotFound
+    }
+
+    bool screaming = std::none_of(base.begin(), base.end(),
+                                  [](char c) { return std::islower(static_cast<unsigned char>(c)); });
+
+    std::string result;
+    bool word_start = true;
+    for (char c : base) {
+        if (c == '_') {
+            word_start = true;
+            continue;
+        }
+        char mapped = word_start ? std::toupper(static_cast<unsigned char>(c))
+                                 : screaming ? std::tolower(static_cast<unsigned char>(c)) : c;
+        result += mapped;
+        word_start = false;
+    }
+
+    if (result.empty()) return name;
+    if (std::isdigit(static_cast<unsigned char>(result[0]))) result = "V" + result;
+    return result;
+}
+
+std::string CodeGenerator::varargsName(const Function& func) {
+    for (const auto& site : func.vararg_sites) {
+        if (site.kind == VarargSiteInfo::ListDeclaration) return site.list;
+    }
+    return "args";
+}
+
+std::string CodeGenerator::enumeratorMessage(const std::string& name) {
+    // Word breaks before capitals, keeping acronyms together: HttpError, IOError
+    std::string camel = enumeratorName(name);
+    auto is_upper = [&](size_t i) { return std::isupper(static_cast<unsigned char>(camel[i])) != 0; };
+
+    std::string words;
+    for (size_t i = 0; i < camel.size(); ++i) {
+        bool breaks = i > 0 && is_upper(i) &&
+                      (!is_upper(i - 1) || (i + 1 < camel.size() && !is_upper(i + 1)));
+        if (breaks) words += ' ';
+        words += std::tolower(static_cast<unsigned char>(camel[i]));
+    }
+    return words;
+}
+
+const TypeMapping* CodeGenerator::findTypeMapping(const std::string& type_name) const {
+    std::string name = type_name.substr(0, type_name.find('<'));
+    while (!name.empty() && name.back() == ' ') name.pop_back();
+    if (name.compare(0, 2, "::") == 0) name = name.substr(2);
+
+    for (const auto& pack : mapping_packs_) {
+        for (const auto& mapping : pack.types) {
+            if (mapping.cpp == name) return &mapping;
+        }
+    }
+    return nullptr;
+}
+
+std::string CodeGenerator::applyTypeMapping(const TypeMapping& mapping, const std::string& pattern,
+                                            const std::vector<std::string>& args) {
+    if (!mapping.note.empty()) reportOnce(mapping.note, mapping.cpp);
+    return substituteArguments(pattern, args);
+}
+
+std::string CodeGenerator::substituteArguments(const std::string& pattern, const std::vector<std::string>& args) {
+    std::string result;
+    for (size_t i = 0; i < pattern.size(); ++i) {
+        if (pattern[i] == '$' && i + 1 < pattern.size() && std::isdigit(static_cast<unsigned char>(pattern[i + 1]))) {
+            size_t index = static_cast<size_t>(pattern[++i] - '0');
+            result += index < args.size() ? args[index] : "()";
+        } else {
+            result += pattern[i];
+        }
+    }
+    return result;
+}
+
+CodeGenerator::IntegerFormat CodeGenerator::integerFormat(const std::string& type_name) const {
+    static const std::map<std::string, IntegerFormat> formats = {
+        {"char", {8, false, false}},          {"signed char", {8, true, false}},
+        {"unsigned char", {8, false, false}}, {"short", {16, true, false}},
+        {"unsigned short", {16, false, false}}, {"int", {32, true, false}},
+        {"unsigned int", {32, false, false}}, {"long long", {64, true, false}},
+        {"unsigned long long", {64, false, false}},
+        {"int8_t", {8, true, false}},         {"uint8_t", {8, false, false}},
+        {"int16_t", {16, true, false}},       {"uint16_t", {16, false, false}},
+        {"int32_t", {32, true, false}},       {"uint32_t", {32, false, false}},
+        {"int64_t", {64, true, false}},       {"uint64_t", {64, false, false}},
+        {"intmax_t", {64, true, false}},      {"uintmax_t", {64, false, false}},
+        {"size_t", {64, false, true}},        {"uintptr_t", {64, false, true}},
+        {"ssize_t", {64, true, true}},        {"ptrdiff_t", {64, true, true}},
+        {"intptr_t", {64, true, true}},
+    };
+    if (type_name == "long" || type_name == "unsigned long") {
+        IntegerFormat format;
+        format.bits = data_model_ == DataModel::LLP64 ? 32 : 64;
+        format.is_signed = type_name == "long";
+        return format;
+    }
+    auto format = formats.find(type_name);
+    return format != formats.end() ? format->second : IntegerFormat();
+}
+
+void CodeGenerator::reportOnce(const std::string& message, const std::string& context) {
+    bool reported = std::any_of(diagnostics_.begin(), diagnostics_.end(),
+                                [&](const Diagnostic& d) { return d.message == message && d.context == context; });
+    if (!reported) report(Diagnostic::Warning, message, context);
+}
+
 } // namespace hybrid
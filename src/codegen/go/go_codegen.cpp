@@ -1,12 +1,24 @@
 #include "codegen.h"
+#include "stack.h"
 #include <algorithm>
 #include <cctype>
+#include <regex>
 
 namespace hybrid {
 
 std::string GoCodeGenerator::generate(const IR& ir) {
+    // Type trees and the regex passes recurse with the nesting of the input
+    std::string code;
+    runWithStack([&] { code = generateSource(ir); });
+    return code;
+}
+
+std::string GoCodeGenerator::generateSource(const IR& ir) {
     output_.str("");
     output_.clear();
+    indent_level_ = 0;
+    diagnostics_.clear();
+    stats_ = GenerationStats();
 
     // Generate file header
     writeLine("// Auto-generated Go code from C++ source");
@@ -16,25 +28,44 @@ std::string GoCodeGenerator::generate(const IR& ir) {
     writeLine("");
 
     // Generate imports if needed
-    bool needs_imports = false;
+    std::vector<std::string> imports;
+    bool needs_os = false;
+    for (const auto& func : ir.getFunctions()) {
+        needs_os = needs_os || func.is_entry_point || !func.entry_sites.empty();
+    }
     for (const auto& class_decl : ir.getClasses()) {
-        for (const auto& field : class_decl.fields) {
-            if (field.type->name.find("sync") != std::string::npos) {
-                needs_imports = true;
-                break;
-            }
+        for (const auto& method : class_decl.methods) {
+            needs_os = needs_os || !method.entry_sites.empty();
+        }
+    }
+    if (needs_os) imports.push_back("os");
+    for (const auto& class_decl : ir.getClasses()) {
+        bool needs_sync = std::any_of(class_decl.fields.begin(), class_decl.fields.end(), [](const Variable& field) {
+            return field.type->name.find("sync") != std::string::npos;
+        });
+        if (needs_sync) {
+            imports.push_back("sync");
+            break;
         }
     }
 
-    if (needs_imports) {
+    if (!imports.empty()) {
         writeLine("import (");
         indent();
-        writeLine("\"sync\"");
+        for (const auto& import : imports) {
+            writeLine("\"" + import + "\"");
+        }
         dedent();
         writeLine(")");
         writeLine("");
     }
 
+    // Generate enums (referenced by the classes below)
+    for (const auto& enum_decl : ir.getEnums()) {
+        generateEnum(enum_decl);
+        writeLine("");
+    }
+
     // Generate classes/structs
     for (const auto& class_decl : ir.getClasses()) {
         generateClass(class_decl);
@@ -55,12 +86,68 @@ std::string GoCodeGenerator::generate(const IR& ir) {
     return output_.str();
 }
 
+void GoCodeGenerator::generateEnum(const EnumDecl& enum_decl) {
+    std::string name = capitalize(sanitizeName(enum_decl.name));
+    writeLine("type " + name + " int");
+    writeLine("");
+
+    bool explicit_values = false;
+    for (const auto& enumerator : enum_decl.enumerators) {
+        if (!enumerator.value.empty()) explicit_values = true;
+    }
+
+    writeLine("const (");
+    indent();
+    std::string previous;
+    for (size_t i = 0; i < enum_decl.enumerators.size(); ++i) {
+        const auto& enumerator = enum_decl.enumerators[i];
+        std::string constant = name + enumeratorName(enumerator.name);
+
+        if (!explicit_values) {
+            writeLine(i == 0 ? constant + " " + name + " = iota" : constant);
+        } else if (!enumerator.value.empty()) {
+            writeLine(constant + " " + name + " = " + enumerator.value);
+        } else {
+            // C++ continues counting from the previous enumerator
+            writeLine(constant + " " + name + " = " + (previous.empty() ? "0" : previous + " + 1"));
+        }
+        previous = constant;
+    }
+    dedent();
+    writeLine(")");
+
+    if (!enum_decl.is_error_code) {
+        return;
+    }
+
+    // Error codes satisfy the error interface
+    writeLine("");
+    writeLine("func (s " + name + ") Error() string {");
+    indent();
+    writeLine("switch s {");
+    for (const auto& enumerator : enum_decl.enumerators) {
+        writeLine("case " + name + enumeratorName(enumerator.name) + ":");
+        indent();
+        writeLine("return \"" + enumeratorMessage(enumerator.name) + "\"");
+        dedent();
+    }
+    writeLine("}");
+    writeLine("return \"unknown status\"");
+    dedent();
+    writeLine("}");
+}
+
 void GoCodeGenerator::generateClass(const ClassDecl& class_decl) {
     // Generate struct definition
     std::string struct_name = capitalize(sanitizeName(class_decl.name));
     writeLine("type " + struct_name + " struct {");
     indent();
 
+    // Embedded bases: their fields and methods are promoted, as in C++
+    for (const auto& base : class_decl.base_subobjects) {
+        writeLine(capitalize(sanitizeName(base.name)));
+    }
+
     for (const auto& field : class_decl.fields) {
         std::string field_name = capitalize(sanitizeName(field.name));
         std::string field_type = convertType(field.type);
@@ -77,7 +164,7 @@ void GoCodeGenerator::generateClass(const ClassDecl& class_decl) {
         // Find constructor or generate New function
         bool has_constructor = false;
         for (const auto& method : class_decl.methods) {
-            if (method.is_constructor) {
+            if (method.is_constructor && !method.is_move_constructor) {
                 has_constructor = true;
 
                 std::stringstream sig;
@@ -113,9 +200,16 @@ void GoCodeGenerator::generateClass(const ClassDecl& class_decl) {
             }
         }
 
-        // Generate methods
+        if (class_decl.has_class_allocator) {
+            report(Diagnostic::Warning,
+                   "'" + class_decl.name + "' defines its own operator new/delete; Go allocates it on the "
+                   "garbage-collected heap",
+                   class_decl.name);
+        }
+
+        // Generate methods (allocation functions have no Go counterpart)
         for (const auto& method : class_decl.methods) {
-            if (!method.is_constructor && !method.is_destructor) {
+            if (!method.is_constructor && !method.is_destructor && method.name.compare(0, 9, "operator ") != 0) {
                 generateFunction(method, struct_name);
                 writeLine("");
             }
@@ -136,6 +230,23 @@ void GoCodeGenerator::generateFunction(const Function& func, const std::string&
         return;
     }
 
+    // int main(int argc, char** argv): func main() reads os.Args
+    if (func.is_entry_point) {
+        countFunction(!func.body.empty());
+        writeLine("func main() {");
+        indent();
+        if (!func.parameters.empty()) {
+            const std::string& argc = func.parameters[0].name;
+            if (!argc.empty() && std::regex_search(func.body, std::regex("\\b" + argc + "\\b"))) {
+                writeLine(sanitizeName(argc) + " := len(os.Args)");
+            }
+        }
+        writeLine(convertEntrySites(func));
+        dedent();
+        writeLine("}");
+        return;
+    }
+
     std::stringstream sig;
 
     sig << "func ";
@@ -164,6 +275,11 @@ void GoCodeGenerator::generateFunction(const Function& func, const std::string&
         }
     }
 
+    // C variadic: Go variadic parameter (cgo cannot call variadic C functions)
+    if (func.is_variadic) {
+        sig << (func.parameters.empty() ? "" : ", ") << sanitizeName(varargsName(func)) << " ...interface{}";
+    }
+
     sig << ")";
 
     // Return type - add error if function may throw
@@ -181,6 +297,18 @@ void GoCodeGenerator::generateFunction(const Function& func, const std::string&
     writeLine(sig.str() + " {");
     indent();
 
+    // Signal handlers, setjmp/longjmp: left to the C++ build (callable through cgo)
+    if (!func.retained_reason.empty()) {
+        stats_.functions_retained++;
+        writeLine("// Kept in C++: " + func.retained_reason);
+        writeLine("panic(\"" + func.name + " is kept in C++\")");
+        dedent();
+        writeLine("}");
+        return;
+    }
+
+    countFunction(func.uses_threading || !func.try_catch_blocks.empty() || !func.body.empty());
+
     // Function body with threading conversion
     if (func.uses_threading) {
         generateThreadingCode(func);
@@ -195,7 +323,7 @@ void GoCodeGenerator::generateFunction(const Function& func, const std::string&
             writeLine("// Function may throw - return error on failure");
         }
         writeLine("// TODO: Implement function body");
-        writeLine(func.body);
+        writeLine(convertEntrySites(func));
 
         if (func.may_throw && func.return_type->kind != TypeKind::Void) {
             writeLine("// return result, nil");
@@ -275,6 +403,53 @@ void GoCodeGenerator::generateVariable(const Variable& var) {
     writeLine(line.str());
 }
 
+std::string GoCodeGenerator::convertEntrySites(const Function& func) {
+    std::string result = func.body;
+    size_t cursor = 0;
+
+    for (const auto& site : func.entry_sites) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+        std::string replacement = entrySite(site);
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string GoCodeGenerator::entrySite(const EntrySiteInfo& site) {
+    std::string expression = site.expression;
+    size_t cursor = 0;
+    for (const auto& nested : site.nested) {
+        size_t pos = expression.find(nested.source_text, cursor);
+        if (pos == std::string::npos) continue;
+        std::string replacement = entrySite(nested);
+        expression.replace(pos, nested.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+    if (expression == "EXIT_SUCCESS") expression = "0";
+    if (expression == "EXIT_FAILURE") expression = "1";
+
+    switch (site.kind) {
+        case EntrySiteInfo::Argument:
+            return "os.Args[" + expression + "]";
+        case EntrySiteInfo::ArgumentUse:
+            return "os.Args";
+        case EntrySiteInfo::Return:
+            // func main() returns nothing: the status goes to os.Exit
+            return expression.empty() || expression == "0" ? "return;" : "os.Exit(" + expression + ");";
+        case EntrySiteInfo::Exit:
+            return "os.Exit(" + expression + ")";
+        case EntrySiteInfo::CommandLine:
+            // An option parsing loop stays as written, with os.Args in it
+            return expression;
+    }
+    return site.source_text;
+}
+
 std::string GoCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
     if (!type) return "interface{}";
 
@@ -285,18 +460,12 @@ std::string GoCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
         case TypeKind::Bool:
             return "bool";
 
-        case TypeKind::Integer:
-            // Map C++ integer types to Go
-            if (type->name == "int" || type->name == "int32_t") return "int32";
-            if (type->name == "long" || type->name == "int64_t") return "int64";
-            if (type->name == "short" || type->name == "int16_t") return "int16";
-            if (type->name == "char" || type->name == "int8_t") return "int8";
-            if (type->name == "unsigned int" || type->name == "uint32_t") return "uint32";
-            if (type->name == "unsigned long" || type->name == "uint64_t") return "uint64";
-            if (type->name == "unsigned short" || type->name == "uint16_t") return "uint16";
-            if (type->name == "unsigned char" || type->name == "uint8_t") return "uint8";
-            if (type->name == "size_t") return "uint";
-            return "int32"; // default
+        case TypeKind::Integer: {
+            // Map C++ integer types to Go; long follows the data model
+            IntegerFormat format = integerFormat(type->name);
+            if (format.pointer_sized) return format.is_signed ? "int" : "uint";
+            return (format.is_signed ? "int" : "uint") + std::to_string(format.bits);
+        }
 
         case TypeKind::Float:
             if (type->name == "float") return "float32";
@@ -304,6 +473,11 @@ std::string GoCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
             return "float64"; // default
 
         case TypeKind::Pointer:
+            // Pointer to data member: accessor taking the object
+            if (!type->member_of.empty()) {
+                return "func(*" + capitalize(sanitizeName(type->member_of)) + ") *" +
+                       convertType(type->element_type);
+            }
             // Smart pointers become slices or direct values in Go
             if (type->name.find("unique_ptr") != std::string::npos) {
                 return "*" + convertType(type->element_type);
@@ -318,9 +492,16 @@ std::string GoCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
             return "*" + convertType(type->element_type);
 
         case TypeKind::Reference:
-            // References become pointers in Go
+            // std::span is already a slice; other references become pointers in Go
+            if (type->element_type && type->element_type->kind == TypeKind::Array) {
+                return convertType(type->element_type);
+            }
             return "*" + convertType(type->element_type);
 
+        case TypeKind::RValueReference:
+            // Moved values are passed by value in Go
+            return convertType(type->element_type);
+
         case TypeKind::Array:
             return "[]" + convertType(type->element_type);
 
@@ -360,6 +541,16 @@ std::string GoCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
             }
             return "struct { First interface{}; Second interface{} }";
 
+        case TypeKind::StdTuple: {
+            // Anonymous struct with positional field names
+            std::string fields;
+            for (size_t i = 0; i < type->template_args.size(); ++i) {
+                if (i > 0) fields += "; ";
+                fields += "F" + std::to_string(i) + " " + convertType(type->template_args[i]);
+            }
+            return "struct { " + fields + " }";
+        }
+
         case TypeKind::StdOptional:
             // Go uses pointer for optional
             if (!type->template_args.empty()) {
@@ -421,14 +612,51 @@ std::string GoCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
 
         case TypeKind::Struct:
         case TypeKind::Class:
+        case TypeKind::Enum:
+            // va_list parameter: the caller's variadic arguments
+            if (type->name == "va_list") return "[]interface{}";
+            // Library types of the enabled mapping packs
+            if (const TypeMapping* mapping = findTypeMapping(type->name)) {
+                if (!mapping->go.empty()) {
+                    std::vector<std::string> args;
+                    for (const auto& arg : type->template_args) args.push_back(convertType(arg));
+                    return applyTypeMapping(*mapping, mapping->go, args);
+                }
+            }
             return capitalize(sanitizeName(type->name));
 
+        case TypeKind::Function: {
+            // Pointer to member function: a method expression, (*C).Method
+            std::vector<std::string> params;
+            if (!type->member_of.empty()) {
+                params.push_back("*" + capitalize(sanitizeName(type->member_of)));
+            }
+            for (const auto& param : type->parameter_types) {
+                params.push_back(convertType(param));
+            }
+            std::string result = "func(";
+            for (size_t i = 0; i < params.size(); ++i) {
+                result += (i > 0 ? ", " : "") + params[i];
+            }
+            result += ")";
+            std::string ret = convertType(type->element_type);
+            return ret.empty() ? result : result + " " + ret;
+        }
+
+        case TypeKind::Template:
+            return type->name;
+
         default:
             return "interface{} /* Unknown type: " + type->name + " */";
     }
 }
 
 std::string GoCodeGenerator::sanitizeName(const std::string& name) {
+    // Functors get a Call method
+    if (name == "operator()") {
+        return "call";
+    }
+
     std::string result = name;
 
     // Handle Go keywords
@@ -733,6 +961,8 @@ void GoCodeGenerator::generateAsyncFunction(const Function& func) {
     writeLine("defer close(resultChan)");
     writeLine("");
 
+    countFunction(func.coroutine_info.is_coroutine || !func.async_tasks.empty() || !func.body.empty());
+
     // Generate coroutine body
     if (func.coroutine_info.is_coroutine) {
         generateCoroutineAsGoroutine(func);
@@ -0,0 +1,111 @@
+#include "mapping_packs.h"
+#include "json.h"
+#include <stdexcept>
+
+namespace hybrid {
+
+namespace {
+
+// Boost types with a std or Rust standard library equivalent
+MappingPack boostPack() {
+    MappingPack pack;
+    pack.name = "boost";
+    pack.description = "Boost smart pointers, optional, containers and filesystem";
+    pack.types = {
+        {"boost::shared_ptr", "Rc<$0>", "*$0", ""},
+        {"boost::weak_ptr", "Weak<$0>", "*$0", ""},
+        {"boost::scoped_ptr", "Box<$0>", "*$0", ""},
+        {"boost::intrusive_ptr", "Rc<$0>", "*$0",
+         "boost::intrusive_ptr keeps its count in the object; Rc counts separately"},
+        {"boost::optional", "Option<$0>", "*$0", ""},
+        {"boost::any", "Box<dyn std::any::Any>", "interface{}", ""},
+        {"boost::array", "[$0; $1]", "[$1]$0", ""},
+        {"boost::unordered_map", "std::collections::HashMap<$0, $1>", "map[$0]$1", ""},
+        {"boost::unordered_set", "std::collections::HashSet<$0>", "map[$0]bool", ""},
+        {"boost::container::vector", "Vec<$0>", "[]$0", ""},
+        {"boost::container::flat_map", "std::collections::BTreeMap<$0, $1>", "map[$0]$1",
+         "boost::container::flat_map is a sorted vector; BTreeMap has the same order, not the same layout"},
+        {"boost::container::small_vector", "Vec<$0>", "[]$0",
+         "boost::container::small_vector stores its first elements inline; Vec always allocates"},
+        {"boost::filesystem::path", "std::path::PathBuf", "string", ""},
+        {"boost::mutex", "std::sync::Mutex<()>", "sync.Mutex", ""},
+        {"boost::thread", "std::thread::JoinHandle<()>", "chan struct{}", ""},
+    };
+    return pack;
+}
+
+// Qt value types; QObject classes and signals are out of scope
+MappingPack qtPack() {
+    MappingPack pack;
+    pack.name = "qt";
+    pack.description = "Qt strings and containers";
+    pack.types = {
+        {"QString", "String", "string", "QString is UTF-16; String indices are UTF-8 byte offsets"},
+        {"QStringList", "Vec<String>", "[]string", ""},
+        {"QByteArray", "Vec<u8>", "[]byte", ""},
+        {"QVector", "Vec<$0>", "[]$0",
+         "Qt containers share their data until written (implicit sharing); clone() copies eagerly"},
+        {"QList", "Vec<$0>", "[]$0",
+         "Qt containers share their data until written (implicit sharing); clone() copies eagerly"},
+        {"QMap", "std::collections::BTreeMap<$0, $1>", "map[$0]$1", ""},
+        {"QHash", "std::collections::HashMap<$0, $1>", "map[$0]$1", ""},
+        {"QSet", "std::collections::HashSet<$0>", "map[$0]bool", ""},
+        {"QPair", "($0, $1)", "struct { First $0; Second $1 }", ""},
+        {"QSharedPointer", "Rc<$0>", "*$0", ""},
+        {"QScopedPointer", "Box<$0>", "*$0", ""},
+    };
+    return pack;
+}
+
+const std::vector<MappingPack>& builtinPacks() {
+    static const std::vector<MappingPack> packs = {boostPack(), qtPack()};
+    return packs;
+}
+
+} // namespace
+
+const MappingPack* findBuiltinMappingPack(const std::string& name) {
+    for (const auto& pack : builtinPacks()) {
+        if (pack.name == name) return &pack;
+    }
+    return nullptr;
+}
+
+std::vector<std::string> builtinMappingPackNames() {
+    std::vector<std::string> names;
+    for (const auto& pack : builtinPacks()) names.push_back(pack.name);
+    return names;
+}
+
+bool parseMappingPack(const std::string& text, MappingPack& pack, std::string& error) {
+    JsonValue root;
+    try {
+        root = JsonValue::parse(text);
+    } catch (const std::runtime_error& e) {
+        error = e.what();
+        return false;
+    }
+    if (!root.isObject() || !root["name"].isString() || !root["types"].isArray()) {
+        error = "a mapping pack needs a \"name\" string and a \"types\" array";
+        return false;
+    }
+
+    pack = MappingPack();
+    pack.name = root["name"].asString();
+    pack.description = root["description"].asString();
+    for (const auto& entry : root["types"].asArray()) {
+        if (!entry["cpp"].isString() || !entry["rust"].isString()) {
+            error = "every entry of \"types\" needs \"cpp\" and \"rust\" strings";
+            return false;
+        }
+        TypeMapping mapping;
+        mapping.cpp = entry["cpp"].asString();
+        mapping.rust = entry["rust"].asString();
+        mapping.go = entry["go"].asString();
+        mapping.note = entry["note"].asString();
+        pack.types.push_back(mapping);
+    }
+    return true;
+}
+
+} // namespace hybrid
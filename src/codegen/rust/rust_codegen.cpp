@@ -1,28 +1,111 @@
 #include "codegen.h"
+#include "stack.h"
 #include <algorithm>
 #include <cctype>
+#include <cstdio>
+#include <cstdlib>
+#include <map>
+#include <regex>
+#include <set>
+#include <sstream>
 
 namespace hybrid {
 
+namespace {
+
+/**
+ * Decision context or subject for the rest of a scope
+ */
+class ScopedValue {
+public:
+    ScopedValue(std::string& target, const std::string& value) : target_(target), saved_(target) {
+        target_ = value;
+    }
+    ~ScopedValue() { target_ = saved_; }
+
+private:
+    std::string& target_;
+    std::string saved_;
+};
+
+} // namespace
+
 std::string RustCodeGenerator::generate(const IR& ir) {
+    // Type trees and the regex passes recurse with the nesting of the input
+    std::string code;
+    runWithStack([&] { code = generateSource(ir); });
+    return code;
+}
+
+std::string RustCodeGenerator::generateSource(const IR& ir) {
     output_.str("");
     output_.clear();
+    indent_level_ = 0;
+    diagnostics_.clear();
+    decisions_.clear();
+    stats_ = GenerationStats();
+    uses_allocator_api_ = false;
+    traits_defined_.clear();
 
     // Generate file header
     writeLine("// Auto-generated Rust code from C++ source");
     writeLine("// Generated by Hybrid Transpiler");
     writeLine("");
 
+    // Argument type of C variadic functions translated to Rust
+    generateVarArgEnum(ir);
+
+    // Generational arena for classes linking to each other (--self-ref arena)
+    arena_classes_.clear();
+    if (self_ref_ == SelfRefStrategy::Arena) {
+        for (const auto& class_decl : ir.getClasses()) {
+            for (const auto& reference : class_decl.self_references) {
+                if (reference.kind == SelfReferenceInfo::IntrusiveLink) arena_classes_.insert(class_decl.name);
+            }
+        }
+        if (!arena_classes_.empty()) generateArenaTypes();
+    }
+
+    // Decided by the translation of each variable, so arena ids are known first
+    reportMemoryIssues(ir);
+
+    // Generate enums (referenced by the classes below)
+    for (const auto& enum_decl : ir.getEnums()) {
+        generateEnum(enum_decl);
+        writeLine("");
+    }
+
     // Generate classes/structs
     for (const auto& class_decl : ir.getClasses()) {
         generateClass(class_decl);
         writeLine("");
     }
 
+    // Library crates export the instantiations their aliases name: pub type IntStack = Stack<i32>;
+    if (library_mode_) {
+        for (const auto& alias : ir.getTypeAliases()) {
+            generateTypeAlias(alias);
+            writeLine("");
+        }
+    }
+
     // Generate standalone functions
     for (const auto& func : ir.getFunctions()) {
+        if (library_mode_ && func.is_entry_point) {
+            report(Diagnostic::Note, "main is not part of a library crate; it was left out", func.name);
+            continue;
+        }
+        if (!func.retained_reason.empty() || (func.is_variadic && varargs_ == VarargsStrategy::Ffi)) {
+            generateExternDeclaration(func);
+            writeLine("");
+            continue;
+        }
         generateFunction(func);
         writeLine("");
+        if (func.is_variadic && varargs_ == VarargsStrategy::Macro) {
+            generateVarargsMacro(func, "");
+            writeLine("");
+        }
     }
 
     // Generate global variables (as constants or static)
@@ -30,36 +113,323 @@ std::string RustCodeGenerator::generate(const IR& ir) {
         generateVariable(var);
     }
 
-    return output_.str();
+    std::string code = organizeImports(output_.str());
+    if (uses_allocator_api_) {
+        // Crate-level attribute: goes right after the file header
+        code.insert(code.find("\n\n") + 2, "#![feature(allocator_api)]\n\n");
+    }
+    return code;
+}
+
+void RustCodeGenerator::generateEnum(const EnumDecl& enum_decl) {
+    std::string name = sanitizeName(enum_decl.name);
+    std::string derives = "Debug, Clone, Copy, PartialEq, Eq";
+    bool thiserror = enum_decl.is_error_code && use_thiserror_;
+
+    // Error-code enum: the success value becomes Ok(..), the rest are errors
+    std::vector<EnumDecl::Enumerator> variants;
+    for (const auto& enumerator : enum_decl.enumerators) {
+        if (!enum_decl.is_error_code || enumerator.name != enum_decl.success_value) {
+            variants.push_back(enumerator);
+        }
+    }
+
+    // Text of each variant: what toString returns for it, else for error
+    // codes a message from its name
+    auto displayText = [&enum_decl](const std::string& enumerator) {
+        auto text = enum_decl.display_texts.find(enumerator);
+        if (text != enum_decl.display_texts.end()) return text->second;
+        if (enum_decl.has_display_default) return enum_decl.display_default;
+        return enum_decl.is_error_code ? enumeratorMessage(enumerator) : enumerator;
+    };
+    // Literal text in a format string
+    auto formatText = [](const std::string& text) {
+        std::string escaped;
+        for (char c : text) {
+            escaped += c;
+            if (c == '{' || c == '}') escaped += c;
+        }
+        return escaped;
+    };
+    auto parseTexts = [&enum_decl](const std::string& enumerator) {
+        std::vector<std::string> texts;
+        for (const auto& text : enum_decl.parse_texts) {
+            if (text.second == enumerator && std::find(texts.begin(), texts.end(), text.first) == texts.end()) {
+                texts.push_back(text.first);
+            }
+        }
+        return texts;
+    };
+
+    // toString and the parse function: impl Display and impl FromStr, or
+    // strum derives. EnumString parses to_string too, and the variant name
+    // when a variant has no text: only derived when that changes nothing
+    bool display = !enum_decl.to_string_function.empty() && !enum_decl.is_error_code;
+    bool parse = !enum_decl.from_string_function.empty();
+    bool strum_display = strum_ && display;
+    bool strum_parse = strum_ && parse && enum_decl.parse_fallback.empty();
+    for (const auto& variant : variants) {
+        std::vector<std::string> texts = parseTexts(variant.name);
+        if (texts.empty() ||
+            (strum_display && std::find(texts.begin(), texts.end(), displayText(variant.name)) == texts.end())) {
+            strum_parse = false;
+        }
+    }
+    if (strum_display) derives += ", strum::Display";
+    if (strum_parse) derives += ", strum::EnumString";
+
+    writeDocComment(enum_decl.doc);
+    writeLine("#[derive(" + derives + (thiserror ? ", thiserror::Error" : "") + ")]");
+    if (!enum_decl.underlying_type.empty()) {
+        auto repr = std::make_shared<Type>(TypeKind::Integer);
+        repr->name = enum_decl.underlying_type;
+        writeLine("#[repr(" + convertType(repr) + ")]");
+    }
+    writeLine("pub enum " + name + " {");
+    indent();
+
+    std::regex integer_literal(R"((-?(?:0[xX][0-9a-fA-F]+|[0-9]+))[uUlL]*)");
+    for (const auto& variant : variants) {
+        if (thiserror) {
+            writeLine("#[error(\"" + formatText(displayText(variant.name)) + "\")]");
+        }
+        std::vector<std::string> strum_texts;
+        if (strum_display) strum_texts.push_back("to_string = \"" + displayText(variant.name) + "\"");
+        if (strum_parse) {
+            for (const auto& text : parseTexts(variant.name)) {
+                if (!strum_display || text != displayText(variant.name)) {
+                    strum_texts.push_back("serialize = \"" + text + "\"");
+                }
+            }
+        }
+        if (!strum_texts.empty()) {
+            std::string attribute;
+            for (const auto& text : strum_texts) attribute += (attribute.empty() ? "" : ", ") + text;
+            writeLine("#[strum(" + attribute + ")]");
+        }
+
+        std::string line = enumeratorName(variant.name);
+        std::smatch literal;
+        if (variant.value.empty()) {
+            line += ",";
+        } else if (std::regex_match(variant.value, literal, integer_literal)) {
+            line += " = " + literal[1].str() + ",";
+        } else {
+            // Discriminants computed from other enumerators are left for review
+            line += ", // = " + variant.value;
+        }
+        writeLine(line);
+    }
+
+    dedent();
+    writeLine("}");
+
+    if (display && !strum_display) {
+        writeLine("");
+        writeLine("impl std::fmt::Display for " + name + " {");
+        indent();
+        writeLine("fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {");
+        indent();
+        writeLine("f.write_str(match self {");
+        indent();
+        for (const auto& variant : variants) {
+            writeLine(name + "::" + enumeratorName(variant.name) + " => \"" + displayText(variant.name) + "\",");
+        }
+        dedent();
+        writeLine("})");
+        dedent();
+        writeLine("}");
+        dedent();
+        writeLine("}");
+    }
+
+    if (parse && !strum_parse) {
+        writeLine("");
+        writeLine("impl std::str::FromStr for " + name + " {");
+        indent();
+        writeLine("type Err = String;");
+        writeLine("");
+        writeLine("fn from_str(s: &str) -> Result<Self, Self::Err> {");
+        indent();
+        writeLine("match s {");
+        indent();
+        std::vector<std::string> matched;
+        for (const auto& text : enum_decl.parse_texts) {
+            bool is_variant = std::any_of(variants.begin(), variants.end(),
+                                          [&text](const EnumDecl::Enumerator& variant) {
+                                              return variant.name == text.second;
+                                          });
+            if (!is_variant || std::find(matched.begin(), matched.end(), text.first) != matched.end()) continue;
+            matched.push_back(text.first);
+            writeLine("\"" + text.first + "\" => Ok(" + name + "::" + enumeratorName(text.second) + "),");
+        }
+        if (enum_decl.parse_fallback.empty()) {
+            writeLine("_ => Err(format!(\"unknown " + name + ": {}\", s)),");
+        } else {
+            writeLine("_ => Ok(" + name + "::" + enumeratorName(enum_decl.parse_fallback) + "),");
+        }
+        dedent();
+        writeLine("}");
+        dedent();
+        writeLine("}");
+        dedent();
+        writeLine("}");
+    }
+
+    if (!enum_decl.is_error_code || thiserror) {
+        return;
+    }
+
+    writeLine("");
+    writeLine("impl std::fmt::Display for " + name + " {");
+    indent();
+    writeLine("fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {");
+    indent();
+    writeLine("match self {");
+    indent();
+    for (const auto& variant : variants) {
+        writeLine(name + "::" + enumeratorName(variant.name) + " => write!(f, \"" +
+                  formatText(displayText(variant.name)) + "\"),");
+    }
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("impl std::error::Error for " + name + " {}");
 }
 
 void RustCodeGenerator::generateClass(const ClassDecl& class_decl) {
+    member_class_ = &class_decl;
+    ScopedValue context(decision_context_, class_decl.name);
+    if (class_decl.is_deferred) {
+        writeDeferredDoc(class_decl.doc,
+                         {"Not translated yet: `" + class_decl.name + "` (" +
+                              deferredLocation(class_decl, class_decl.span) + ") is deferred to a later migration",
+                          "phase. Its methods are stubs in `impl " + deferredTraitName(class_decl.name) + " for " +
+                              sanitizeName(class_decl.name) + "` that end in todo!()."});
+    } else {
+        writeDocComment(class_decl.doc);
+    }
+
+    // Derive Copy/Clone according to the analyzed copy semantics
+    const Function* copy_ctor = nullptr;
+    const Function* destructor = nullptr;
+    for (const auto& method : class_decl.methods) {
+        if (method.is_copy_constructor && !method.is_deleted && !method.is_defaulted) {
+            copy_ctor = &method;
+        }
+        if (method.is_destructor && !method.is_defaulted) {
+            destructor = &method;
+        }
+    }
+
+    // Pinned objects are never copied: copies would point at the original
+    pinned_ = self_ref_ == SelfRefStrategy::Pin && !class_decl.self_references.empty();
+    // Nor are node holders: the arena owns the nodes
+    std::vector<std::string> arenas = arenaNodes(class_decl);
+    if (pinned_ || !arenas.empty()) {
+        // No derives
+        decide("COPY-NONE", class_decl.name, "no Clone", pinned_ ? "pinned objects are never copied (--self-ref pin)"
+                                                                  : "the arena owns the nodes (--self-ref arena)");
+    } else if (class_decl.copy_semantics == CopySemantics::Copy) {
+        writeLine("#[derive(Clone, Copy)]");
+        decide("COPY-DERIVE", class_decl.name, "#[derive(Clone, Copy)]",
+               "every field is trivially copyable and there is no user-defined copy constructor or destructor");
+    } else if (class_decl.copy_semantics == CopySemantics::Clone && !copy_ctor) {
+        writeLine("#[derive(Clone)]");
+        decide("CLONE-DERIVE", class_decl.name, "#[derive(Clone)]",
+               "copyable, but a field owns resources, so copies are explicit clone() calls");
+    } else if (class_decl.copy_semantics == CopySemantics::Clone) {
+        decide("CLONE-IMPL", class_decl.name, "impl Clone", "the user-defined copy constructor becomes clone()");
+    } else {
+        decide("COPY-NONE", class_decl.name, "no Clone",
+               "the copy constructor is deleted or a field is move-only (unique_ptr, mutex...)");
+    }
+    if (self_ref_ == SelfRefStrategy::Arena) {
+        for (const auto& reference : class_decl.self_references) {
+            if (reference.kind == SelfReferenceInfo::IntrusiveLink) continue;
+            report(Diagnostic::Warning,
+                   "'" + reference.source_text + "' is not a link between objects; --self-ref arena keeps it as "
+                   "a raw pointer (--self-ref pin keeps the object in place)",
+                   reference.function);
+        }
+    }
+
+    // offsetof() results are only meaningful with C field order
+    if (class_decl.needs_c_layout) {
+        writeLine("#[repr(C)]");
+    }
+
+    size_t call_operators = std::count_if(class_decl.methods.begin(), class_decl.methods.end(),
+                                          [](const Function& method) { return method.name == "operator()"; });
+    if (call_operators > 1) {
+        report(Diagnostic::Warning,
+               "'" + class_decl.name + "' overloads operator(); Rust has no overloading, so its " +
+               std::to_string(call_operators) + " call methods must be renamed",
+               class_decl.name);
+    }
+
     // Generate struct definition with generics
     std::string struct_decl = "pub struct " + sanitizeName(class_decl.name);
 
     // Add generic parameters if template
     if (class_decl.is_template && !class_decl.template_parameters.empty()) {
-        struct_decl += convertTemplateParametersToRust(class_decl.template_parameters);
+        checkConstGenerics(class_decl);
+        struct_decl += convertTemplateParametersToRust(class_decl.template_parameters, true);
     }
 
     struct_decl += " {";
     writeLine(struct_decl);
     indent();
 
+    // Multiple inheritance: base subobjects first, in declaration order
+    for (const auto& base : class_decl.base_subobjects) {
+        writeLine("pub " + sanitizeName(base.name) + ": " + sanitizeName(base.name) + ",");
+    }
+
     for (const auto& field : class_decl.fields) {
-        std::string visibility = "pub "; // Simplified - would need proper access control
+        std::string visibility = memberVisibility(field.name);
         std::string field_name = sanitizeName(field.name);
+        ScopedValue subject(decision_subject_, "field " + field.name);
         std::string field_type = convertType(field.type);
+        if (field.type && field.type->isBorrowedString()) {
+            report(Diagnostic::Warning,
+                   "field '" + field.name + "' borrows a string it does not own: '" + class_decl.name +
+                   "' needs a lifetime parameter ('" + field_name + ": &'a str'), or the field must own a String",
+                   class_decl.name);
+        }
 
+        writeDocComment(field.doc);
         writeLine(visibility + field_name + ": " + field_type + ",");
     }
+    if (pinned_) {
+        writeLine("_pin: std::marker::PhantomPinned,");
+    }
+    for (const auto& node : arenas) {
+        writeLine("pub " + arenaField(node) + ": Arena<" + sanitizeName(node) + ">,");
+    }
 
     dedent();
     writeLine("}");
     writeLine("");
 
+    if (class_decl.is_deferred) {
+        generateDeferredStubs(class_decl, copy_ctor, destructor);
+        pinned_ = false;
+        member_class_ = nullptr;
+        return;
+    }
+
+    // Methods specialized per instantiation get their own impl blocks below
+    bool has_generic_methods = std::any_of(
+        class_decl.methods.begin(), class_decl.methods.end(),
+        [&](const Function& method) { return !specializedPerInstantiation(class_decl, method); });
+
     // Generate impl block for methods
-    if (!class_decl.methods.empty()) {
+    if (has_generic_methods) {
         std::string impl_decl = "impl";
 
         // Add generic parameters
@@ -79,7 +449,29 @@ void RustCodeGenerator::generateClass(const ClassDecl& class_decl) {
         indent();
 
         for (const auto& method : class_decl.methods) {
-            generateFunction(method);
+            // Deleted functions and destructors have no inherent method
+            if (method.is_deleted || method.is_destructor) {
+                continue;
+            }
+            if (method.is_copy_constructor) {
+                writeLine(copy_ctor ? "// Copy constructor: see impl Clone"
+                                    : "// Copy constructor: derived Clone");
+                writeLine("");
+                continue;
+            }
+            if (specializedPerInstantiation(class_decl, method)) {
+                continue;
+            }
+            if (!method.retained_reason.empty()) {
+                generateRetainedMethod(class_decl, method);
+                writeLine("");
+                continue;
+            }
+            if (class_decl.has_class_allocator && method.name.compare(0, 9, "operator ") == 0) {
+                continue;  // See generateClassAllocator
+            }
+            checkConstexprIfs(class_decl, method);
+            generateFunction(class_decl.is_template ? method : selectConstexprBranches(method, 0));
             writeLine("");
         }
 
@@ -87,79 +479,88 @@ void RustCodeGenerator::generateClass(const ClassDecl& class_decl) {
         writeLine("}");
     }
 
+    generateRetainedShims(class_decl);
+    generateInstantiationImpls(class_decl, has_generic_methods);
+
+    for (const auto& node : arenas) {
+        writeLine("");
+        generateArenaIndex(class_decl, node);
+    }
+
+    if (class_decl.has_class_allocator) {
+        generateClassAllocator(class_decl);
+    }
+
+    if (copy_ctor) {
+        writeLine("");
+        generateCloneImpl(class_decl, *copy_ctor);
+    }
+
+    if (destructor) {
+        writeLine("");
+        generateDropImpl(class_decl, *destructor);
+    }
+
     // Generate trait implementations for base classes
-    if (!class_decl.base_classes.empty()) {
+    if (!class_decl.base_subobjects.empty()) {
+        writeLine("");
+        generateBaseSubobjects(class_decl);
+    } else if (!class_decl.base_classes.empty()) {
         writeLine("");
         generateTraitImplementations(class_decl);
     }
+
+    // Front-ends taking variadic arguments as written
+    if (varargs_ == VarargsStrategy::Macro) {
+        for (const auto& method : class_decl.methods) {
+            if (method.is_variadic) {
+                writeLine("");
+                generateVarargsMacro(method, class_decl.name);
+            }
+        }
+    }
+
+    pinned_ = false;
+    member_class_ = nullptr;
 }
 
 void RustCodeGenerator::generateFunction(const Function& func) {
+    ScopedValue context(decision_context_, (member_class_ ? member_class_->name + "::" : "") + func.name);
+    writeDocComment(func.doc);
+
     // If function is async or coroutine, use async generation
     if (func.is_async || func.coroutine_info.is_coroutine) {
         generateAsyncFunction(func);
         return;
     }
 
-    std::stringstream sig;
-
-    // Constructor becomes 'new' in Rust
-    if (func.is_constructor) {
-        sig << "pub fn new";
-    } else {
-        sig << "pub fn " << sanitizeName(func.name);
+    // int main(int argc, char** argv): fn main() -> ExitCode
+    if (func.is_entry_point) {
+        generateEntryPoint(func);
+        return;
     }
 
-    // Add generic parameters for template functions
-    if (func.is_template && !func.template_parameters.empty()) {
-        sig << convertTemplateParametersToRust(func.template_parameters);
+    // Move constructors have no Rust equivalent: values are moved by memcpy
+    if (func.is_move_constructor) {
+        writeLine("// Move constructor elided: Rust moves values implicitly");
+        return;
     }
 
-    sig << "(";
-
-    // Add self parameter for methods
-    if (!func.is_static && !func.is_constructor) {
-        if (func.is_const) {
-            sig << "&self";
-        } else {
-            sig << "&mut self";
-        }
-
-        if (!func.parameters.empty()) {
-            sig << ", ";
-        }
+    // Constructors that can fail midway build the fields as locals first
+    if (func.is_fallible_constructor && member_class_) {
+        generateTryNew(func);
+        return;
     }
 
-    // Add parameters
-    for (size_t i = 0; i < func.parameters.size(); ++i) {
-        const auto& param = func.parameters[i];
-        sig << sanitizeName(param.name) << ": " << convertType(param.type);
-
-        if (i < func.parameters.size() - 1) {
-            sig << ", ";
-        }
-    }
+    countFunction(func.uses_threading || !func.try_catch_blocks.empty() || !func.body.empty());
 
-    sig << ")";
+    writeLine(functionSignature(func, memberVisibility(func.name)) + " {");
+    indent();
 
-    // Return type - wrap in Result if function may throw
-    if (func.is_constructor) {
-        sig << " -> Self";
-    } else if (func.may_throw) {
-        // Function may throw - convert to Result type
-        if (func.return_type->kind != TypeKind::Void) {
-            sig << " -> Result<" << convertType(func.return_type)
-                << ", Box<dyn std::error::Error>>";
-        } else {
-            sig << " -> Result<(), Box<dyn std::error::Error>>";
-        }
-    } else if (func.return_type->kind != TypeKind::Void) {
-        sig << " -> " << convertType(func.return_type);
+    if (pinned_ && func.is_constructor) {
+        writeLine("// Box::pin the value first, then point its fields at the pinned address");
     }
 
-    writeLine(sig.str() + " {");
-    indent();
-
     // Function body with threading conversion
     if (func.uses_threading) {
         generateThreadingCode(func);
@@ -174,7 +575,7 @@ void RustCodeGenerator::generateFunction(const Function& func) {
             writeLine("// Function may throw - wrap result in Ok()");
         }
         writeLine("// TODO: Implement function body");
-        writeLine(func.body);
+        writeLine(translateBody(func));
 
         if (func.may_throw && func.return_type->kind != TypeKind::Void) {
             writeLine("// Ok(result)");
@@ -193,49 +594,527 @@ void RustCodeGenerator::generateFunction(const Function& func) {
     writeLine("}");
 }
 
-void RustCodeGenerator::generateTryCatchAsResult(const Function& func) {
-    // Convert C++ try-catch to Rust Result pattern
-    writeLine("// Converted from C++ try-catch block");
+std::string RustCodeGenerator::functionSignature(const Function& func, const std::string& visibility) {
+    std::stringstream sig;
 
-    for (const auto& block : func.try_catch_blocks) {
-        // Generate the try body as a closure or inline code
-        writeLine("(|| -> Result<_, Box<dyn std::error::Error>> {");
-        indent();
+    // Placement new and explicit destructor calls: the caller vouches for the storage
+    sig << visibility << (func.allocations.empty() ? "fn " : "unsafe fn ");
 
-        // Generate try block body
-        writeLine("// Try block:");
-        if (!block.try_body.empty()) {
-            writeLine(block.try_body);
+    // Constructor becomes 'new' in Rust, 'try_new' if it can fail
+    if (func.is_constructor) {
+        sig << (func.is_fallible_constructor ? "try_new" : "new");
+    } else {
+        sig << sanitizeName(func.name);
+    }
+
+    // Add generic parameters for template functions
+    if (func.is_template && !func.template_parameters.empty()) {
+        sig << convertTemplateParametersToRust(func.template_parameters);
+    }
+
+    sig << "(";
+
+    // Parameters (a status out-parameter becomes the Ok value instead)
+    std::shared_ptr<Type> ok_type;
+    std::vector<std::string> params;
+    for (const auto& param : func.parameters) {
+        if (!func.status_out_param.empty() && param.name == func.status_out_param) {
+            ok_type = param.type->element_type;
+            continue;
+        }
+        // (T* ptr, size_t len): one slice, which carries its own length
+        auto pair = std::find_if(func.slice_params.begin(), func.slice_params.end(), [&param](const SliceParamInfo& p) {
+            return p.pointer == param.name || p.length == param.name;
+        });
+        if (pair != func.slice_params.end() && pair->length == param.name) continue;
+        ScopedValue subject(decision_subject_, "parameter " + param.name);
+        if (pair != func.slice_params.end()) {
+            std::string slice = (pair->is_const ? "&[" : "&mut [") + convertType(param.type->element_type) + "]";
+            decide("SLICE-FUSE", param.type->name + ", " + pair->length, slice,
+                   "the pointer and length always travel together; the slice carries its length");
+            params.push_back(sanitizeName(param.name) + ": " + slice);
+            continue;
+        }
+        auto stored = func.stored_views.find(param.name);
+        if (stored != func.stored_views.end()) {
+            report(Diagnostic::Warning,
+                   "'" + param.name + "' is kept in the field '" + stored->second + "': the &str needs a lifetime "
+                   "tied to the struct ('" + sanitizeName(param.name) + ": &'a str' on a struct with <'a>), or "
+                   "the field must own a String",
+                   func.name);
+        }
+        if (std::find(func.status_params.begin(), func.status_params.end(), param.name) != func.status_params.end()) {
+            decide("ERR-STATUS-PARAM", param.type->name, "Result<(), " + convertType(param.type) + ">",
+                   "the error-code enum has no success variant; the status is passed as the caller's Result");
+            params.push_back(sanitizeName(param.name) + ": Result<(), " + convertType(param.type) + ">");
+            continue;
         }
+        params.push_back(sanitizeName(param.name) + ": " + convertType(param.type));
+    }
+    if (func.is_variadic) {
+        // Only free functions can be called through the C ABI
+        if (varargs_ == VarargsStrategy::Ffi) {
+            report(Diagnostic::Warning,
+                   "variadic method '" + func.name + "' cannot be called through the C ABI; "
+                   "its arguments are passed as a &[VarArg] slice",
+                   func.name);
+        }
+        params.push_back(sanitizeName(varargsName(func)) + ": &[VarArg]");
+    }
 
-        // If try succeeds, return Ok
-        if (func.return_type->kind != TypeKind::Void) {
-            writeLine("Ok(result)  // TODO: actual return value");
+    // Add self parameter for methods
+    if (!func.is_static && !func.is_constructor) {
+        if (func.is_const) {
+            sig << "&self";
+        } else if (pinned_) {
+            sig << "self: std::pin::Pin<&mut Self>";
         } else {
-            writeLine("Ok(())");
+            sig << "&mut self";
         }
 
-        dedent();
-        writeLine("})()");
-
-        // Generate match for error handling (equivalent to catch)
-        if (!block.catch_clauses.empty()) {
-            writeLine(".or_else(|err| {");
-            indent();
-
-            for (const auto& catch_clause : block.catch_clauses) {
-                writeLine("// Catch " + catch_clause.exception_type);
+        if (!params.empty()) {
+            sig << ", ";
+        }
+    }
 
-                if (catch_clause.exception_type == "...") {
-                    writeLine("// Catch-all handler");
-                } else {
-                    writeLine("// Handle specific exception type: " + catch_clause.exception_type);
-                }
+    for (size_t i = 0; i < params.size(); ++i) {
+        sig << (i > 0 ? ", " : "") << params[i];
+    }
 
-                if (!catch_clause.handler_body.empty()) {
-                    writeLine(catch_clause.handler_body);
-                }
-            }
+    sig << ")";
+
+    // Return type - wrap in Result if function may throw
+    if (!func.status_enum.empty()) {
+        decide("ERR-STATUS", "returned " + func.status_enum, "Result<_, " + sanitizeName(func.status_enum) + ">",
+               "the function returns an error-code enum: failures become Err, propagated with ?");
+    } else if (func.may_throw && !func.is_constructor) {
+        decide("ERR-RESULT", "exceptions", "Result<_, Box<dyn std::error::Error>>",
+               "the function throws, or calls a function that may throw");
+    } else if (func.is_fallible_constructor) {
+        decide("CTOR-TRY-NEW", "constructor", "fn try_new() -> Result<Self, Box<dyn std::error::Error>>",
+               "the constructor can fail after some members are built; the fields are locals until Ok(Self)");
+    }
+    {
+        ScopedValue subject(decision_subject_, "return type");
+        std::string self_type = pinned_ ? "std::pin::Pin<Box<Self>>" : "Self";
+        if (func.is_fallible_constructor) {
+            sig << " -> Result<" << self_type << ", Box<dyn std::error::Error>>";
+        } else if (func.is_constructor) {
+            sig << " -> " << self_type;
+        } else if (!func.status_enum.empty()) {
+            // Error-code status: Result<T, Status>
+            sig << " -> Result<" << (ok_type ? convertType(ok_type) : "()") << ", "
+                << sanitizeName(func.status_enum) << ">";
+        } else if (func.may_throw) {
+            // Function may throw - convert to Result type
+            if (func.return_type->kind != TypeKind::Void) {
+                sig << " -> Result<" << convertType(func.return_type)
+                    << ", Box<dyn std::error::Error>>";
+            } else {
+                sig << " -> Result<(), Box<dyn std::error::Error>>";
+            }
+        } else if (func.return_type->kind != TypeKind::Void) {
+            sig << " -> " << convertType(func.return_type);
+        }
+    }
+
+    return sig.str();
+}
+
+std::string RustCodeGenerator::deferredTraitName(const std::string& class_name) {
+    std::string name = sanitizeName(class_name);
+    if (!name.empty()) name[0] = static_cast<char>(std::toupper(static_cast<unsigned char>(name[0])));
+    return name + "Methods";
+}
+
+std::string RustCodeGenerator::deferredLocation(const ClassDecl& class_decl, const SourceSpan& span) {
+    int line = span.begin_line - class_decl.source_line_offset;
+    if (class_decl.source_path.empty()) return span.isKnown() ? "line " + std::to_string(line) : "the C++ source";
+    return class_decl.source_path + (span.isKnown() ? ":" + std::to_string(line) : "");
+}
+
+void RustCodeGenerator::writeDeferredDoc(const std::string& doc, const std::vector<std::string>& reference) {
+    // Written in application output too: they are what the stubs are filled in from
+    std::stringstream lines(doc);
+    std::string line;
+    while (std::getline(lines, line)) {
+        writeLine(line.empty() ? "///" : "/// " + line);
+    }
+    if (!doc.empty()) writeLine("///");
+    for (const auto& text : reference) {
+        writeLine("/// " + text);
+    }
+}
+
+std::string RustCodeGenerator::cppDeclaration(const ClassDecl& class_decl, const Function& method) {
+    auto spelling = [](const std::shared_ptr<Type>& type) {
+        return type ? (type->is_const ? "const " : "") + type->name : std::string();
+    };
+    std::string text = method.is_static ? "static " : "";
+    if (method.is_virtual) text += "virtual ";
+    if (!method.is_constructor) text += spelling(method.return_type) + " ";
+    text += (method.is_constructor ? class_decl.name : method.name) + "(";
+    for (size_t i = 0; i < method.parameters.size(); ++i) {
+        text += (i > 0 ? ", " : "") + spelling(method.parameters[i].type) + " " + method.parameters[i].name;
+    }
+    text += ")";
+    if (method.is_const) text += " const";
+    return text;
+}
+
+void RustCodeGenerator::generateDeferredStubs(const ClassDecl& class_decl, const Function* copy_ctor,
+                                              const Function* destructor) {
+    std::string struct_name = sanitizeName(class_decl.name);
+    std::string trait_name = deferredTraitName(class_decl.name);
+    std::string generics;
+    std::string arguments;
+    if (class_decl.is_template && !class_decl.template_parameters.empty()) {
+        generics = convertTemplateParametersToRust(class_decl.template_parameters);
+        arguments = convertTemplateArgsToRust(class_decl.template_parameters);
+    }
+    decide("DEFER-STUB", class_decl.name, "trait " + trait_name + " with todo!() bodies",
+           "--defer leaves the class for a later migration phase; its signatures compile now");
+
+    // Virtual methods are already stubbed in the impl of the base's trait
+    bool base_trait = class_decl.base_subobjects.empty() && !class_decl.base_classes.empty();
+    std::vector<const Function*> associated;   // Constructors and static methods: called on the type
+    std::vector<const Function*> methods;
+    for (const auto& method : class_decl.methods) {
+        if (method.is_deleted || method.is_destructor || method.is_copy_constructor || method.is_move_constructor) {
+            continue;
+        }
+        if (base_trait && method.is_virtual) continue;
+        (method.is_constructor || method.is_static ? associated : methods).push_back(&method);
+    }
+
+    // One signature per stub, shared by the trait and its impl
+    auto stub = [&](const Function& method, const std::string& signature) {
+        countFunction(false);
+        writeLine(signature + " {");
+        indent();
+        writeLine("todo!(\"translate " + class_decl.name + "::" + method.name + " (" +
+                  deferredLocation(class_decl, method.span) + ")\")");
+        dedent();
+        writeLine("}");
+    };
+    auto declarationDoc = [&](const Function& method) {
+        writeDeferredDoc(method.doc, {"C++: `" + cppDeclaration(class_decl, method) + "` (" +
+                                          deferredLocation(class_decl, method.span) + ")"});
+    };
+
+    // Parameters are unused until the bodies are written
+    if (!associated.empty()) {
+        writeLine("#[allow(unused_variables)]");
+        writeLine("impl" + generics + " " + struct_name + arguments + " {");
+        indent();
+        for (size_t i = 0; i < associated.size(); ++i) {
+            if (i > 0) writeLine("");
+            ScopedValue context(decision_context_, class_decl.name + "::" + associated[i]->name);
+            std::string signature = functionSignature(*associated[i], memberVisibility(associated[i]->name));
+            declarationDoc(*associated[i]);
+            stub(*associated[i], signature);
+        }
+        dedent();
+        writeLine("}");
+        writeLine("");
+    }
+
+    std::vector<std::string> signatures;
+    for (const Function* method : methods) {
+        ScopedValue context(decision_context_, class_decl.name + "::" + method->name);
+        signatures.push_back(functionSignature(*method, ""));
+    }
+    writeDeferredDoc("", {"Methods of C++ `" + class_decl.name + "`, to be translated"});
+    writeLine("pub trait " + trait_name + generics + " {");
+    indent();
+    for (size_t i = 0; i < methods.size(); ++i) {
+        if (i > 0) writeLine("");
+        declarationDoc(*methods[i]);
+        writeLine(signatures[i] + ";");
+    }
+    dedent();
+    writeLine("}");
+    writeLine("");
+
+    writeLine("#[allow(unused_variables)]");
+    writeLine("impl" + generics + " " + trait_name + arguments + " for " + struct_name + arguments + " {");
+    indent();
+    for (size_t i = 0; i < methods.size(); ++i) {
+        if (i > 0) writeLine("");
+        stub(*methods[i], signatures[i]);
+    }
+    dedent();
+    writeLine("}");
+
+    if (copy_ctor) {
+        writeLine("");
+        writeLine("impl" + generics + " Clone for " + struct_name + arguments + " {");
+        indent();
+        stub(*copy_ctor, "fn clone(&self) -> Self");
+        dedent();
+        writeLine("}");
+    }
+    if (destructor) {
+        report(Diagnostic::Note,
+               "deferred class '" + class_decl.name + "' has no Drop impl yet: dropping it does not run ~" +
+                   class_decl.name + "()",
+               class_decl.name);
+    }
+
+    if (base_trait) {
+        writeLine("");
+        generateTraitImplementations(class_decl);
+    } else if (!class_decl.base_subobjects.empty()) {
+        writeLine("");
+        generateBaseSubobjects(class_decl);
+    }
+}
+
+void RustCodeGenerator::generateCloneImpl(const ClassDecl& class_decl, const Function& copy_ctor) {
+    // User-provided copy constructor becomes a manual Clone implementation
+    // (always completed by hand: the body ends in todo!())
+    countFunction(false);
+    writeLine("impl Clone for " + sanitizeName(class_decl.name) + " {");
+    indent();
+    writeLine("fn clone(&self) -> Self {");
+    indent();
+    writeLine("// Converted from C++ copy constructor");
+    if (!copy_ctor.member_initializers.empty()) {
+        writeLine("// Member initializers: " + copy_ctor.member_initializers);
+    }
+    if (!copy_ctor.body.empty()) {
+        writeLine(translateBody(copy_ctor));
+    }
+    writeLine("todo!()");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+}
+
+void RustCodeGenerator::generateDropImpl(const ClassDecl& class_decl, const Function& destructor) {
+    // C++ destructor becomes Drop implementation (RAII)
+    countFunction(true);
+    writeLine("impl Drop for " + sanitizeName(class_decl.name) + " {");
+    indent();
+    writeLine("fn drop(&mut self) {");
+    indent();
+    if (!destructor.body.empty()) {
+        writeLine(translateBody(destructor));
+    }
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+}
+
+void RustCodeGenerator::generateTryNew(const Function& func) {
+    const ClassDecl& class_decl = *member_class_;
+    countFunction(true);
+    writeLine(functionSignature(func, memberVisibility(func.name)) + " {");
+    indent();
+
+    // Members are built in declaration order, each a local until Ok(Self)
+    auto local = [&](const std::string& name, const std::string& value) {
+        bool changed = std::regex_search(func.body, std::regex("\\b" + name + "\\b"));
+        writeLine((changed ? "let mut " : "let ") + sanitizeName(name) + " = " + value + ";");
+    };
+    for (const auto& base : class_decl.base_subobjects) {
+        auto init = func.initializer_arguments.find(base.name);
+        local(base.name, init == func.initializer_arguments.end()
+                             ? "Default::default()"
+                             : sanitizeName(base.name) + "::new(" + init->second + ")");
+    }
+    for (const auto& field : class_decl.fields) {
+        if (field.is_static) continue;
+        ScopedValue subject(decision_subject_, "field " + field.name);
+        std::string type = convertType(field.type);
+        auto init = func.initializer_arguments.find(field.name);
+        auto member = std::find_if(func.construction_sites.begin(), func.construction_sites.end(),
+                                   [&field](const ConstructionSiteInfo& site) {
+                                       return site.kind == ConstructionSiteInfo::FallibleMember &&
+                                              site.member == field.name;
+                                   });
+        bool is_class = field.type && field.type->kind == TypeKind::Class &&
+                        field.type->name.compare(0, 5, "std::") != 0;
+        std::string value;
+        if (member != func.construction_sites.end()) {
+            value = type + "::try_new(" + member->arguments + ")?";
+        } else if (init != func.initializer_arguments.end() && !init->second.empty()) {
+            value = is_class ? type + "::new(" + init->second + ")" : init->second;
+        } else if (init == func.initializer_arguments.end() && !field.initializer.empty()) {
+            value = field.initializer;
+        } else if (type.compare(0, 5, "*mut ") == 0) {
+            value = "std::ptr::null_mut()";
+        } else if (type.compare(0, 7, "*const ") == 0) {
+            value = "std::ptr::null()";
+        } else {
+            value = "Default::default()";
+        }
+        local(field.name, value);
+    }
+
+    std::string name = sanitizeName(class_decl.name);
+    writeLine("// On Err the fields built so far are dropped; Drop for " + name +
+              " does not run, as ~" + class_decl.name + " does not in C++");
+    if (!func.body.empty()) {
+        writeLine(translateBody(func));
+    }
+    writeLine("Ok(" + constructedValue(class_decl) + ")");
+    dedent();
+    writeLine("}");
+
+    // What a partial object could do in C++ that try_new cannot
+    std::set<std::string> reported;
+    for (const auto& site : func.construction_sites) {
+        switch (site.kind) {
+            case ConstructionSiteInfo::ThisEscape:
+                report(Diagnostic::Warning,
+                       "'" + site.source_text + "' hands out `this` before the constructor can fail; try_new has "
+                       "no object until it returns Ok, so register the value after construction (in C++ the "
+                       "pointer dangles when the constructor throws)",
+                       func.name);
+                break;
+            case ConstructionSiteInfo::MethodCall:
+                report(Diagnostic::Warning,
+                       "'" + site.source_text + "' calls " + site.member + "() on the partially built object; "
+                       "try_new has no self before Ok(Self), so call it on the fields or after construction",
+                       func.name);
+                break;
+            default:
+                for (const auto& field_name : site.leaked) {
+                    if (!reported.insert(field_name).second) continue;
+                    auto field = std::find_if(class_decl.fields.begin(), class_decl.fields.end(),
+                                              [&field_name](const Variable& f) { return f.name == field_name; });
+                    std::string type = field == class_decl.fields.end() ? "" : convertType(field->type);
+                    bool owned = type.compare(0, 4, "Box<") == 0 || type.compare(0, 4, "Vec<") == 0 ||
+                                 type == "String" || type.compare(0, 8, "std::fs:") == 0 ||
+                                 type.compare(0, 9, "std::net:") == 0;
+                    std::string failure = site.kind == ConstructionSiteInfo::Throw
+                                              ? "at '" + site.source_text + "'"
+                                              : "constructing '" + site.member + "'";
+                    if (owned) {
+                        report(Diagnostic::Note,
+                               "'" + field_name + "' is released by ~" + class_decl.name + ", which does not run "
+                               "when the constructor fails " + failure + ": C++ leaks it, the " + type +
+                               " is dropped on the Err path",
+                               func.name);
+                    } else {
+                        report(Diagnostic::Warning,
+                               "'" + field_name + "' is released by ~" + class_decl.name + ", which does not run "
+                               "when the constructor fails " + failure + ": it leaks in C++ and in try_new; "
+                               "give it an owning type whose Drop releases it",
+                               func.name);
+                    }
+                }
+                break;
+        }
+    }
+}
+
+std::string RustCodeGenerator::constructedValue(const ClassDecl& class_decl) {
+    std::vector<std::string> fields;
+    for (const auto& base : class_decl.base_subobjects) {
+        fields.push_back(sanitizeName(base.name));
+    }
+    for (const auto& field : class_decl.fields) {
+        if (!field.is_static) fields.push_back(sanitizeName(field.name));
+    }
+    if (pinned_) {
+        fields.push_back("_pin: std::marker::PhantomPinned");
+    }
+    for (const auto& node : arenaNodes(class_decl)) {
+        fields.push_back(arenaField(node) + ": Arena::new()");
+    }
+
+    std::string value = "Self {";
+    for (size_t i = 0; i < fields.size(); ++i) {
+        value += (i > 0 ? ", " : " ") + fields[i];
+    }
+    value += fields.empty() ? "}" : " }";
+    return pinned_ ? "Box::pin(" + value + ")" : value;
+}
+
+std::string RustCodeGenerator::constructionError(const ConstructionSiteInfo& site) {
+    // Standard exceptions carry a message: Box<dyn Error> converts from it
+    if (site.exception_type.compare(0, 5, "std::") == 0) {
+        if (site.arguments.empty()) {
+            return "\"" + site.exception_type.substr(5) + "\".into()";
+        }
+        static const std::regex simple(R"("(?:[^"\\]|\\.)*"|[\w.]+)");
+        bool plain = std::regex_match(site.arguments, simple);
+        return (plain ? site.arguments : "(" + site.arguments + ")") + ".into()";
+    }
+    return "Box::new(" + sanitizeName(site.exception_type) + "::new(" + site.arguments + "))";
+}
+
+std::string RustCodeGenerator::convertConstructionSites(const Function& func, const std::string& body) {
+    if (!func.is_fallible_constructor || !member_class_) {
+        return body;
+    }
+
+    std::string result = body;
+    size_t cursor = 0;
+    for (const auto& site : func.construction_sites) {
+        if (site.kind != ConstructionSiteInfo::Throw) continue;
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+        std::string replacement = "return Err(" + constructionError(site) + ");";
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    // The fields are locals: this->x is x, and an early return completes the object
+    result = std::regex_replace(result, std::regex(R"(\bthis->)"), "");
+    return std::regex_replace(result, std::regex(R"(\breturn\s*;)"),
+                              "return Ok(" + constructedValue(*member_class_) + ");");
+}
+
+void RustCodeGenerator::generateTryCatchAsResult(const Function& func) {
+    // Convert C++ try-catch to Rust Result pattern
+    writeLine("// Converted from C++ try-catch block");
+
+    for (const auto& block : func.try_catch_blocks) {
+        // Generate the try body as a closure or inline code
+        writeLine("(|| -> Result<_, Box<dyn std::error::Error>> {");
+        indent();
+
+        // Generate try block body
+        writeLine("// Try block:");
+        if (!block.try_body.empty()) {
+            writeLine(block.try_body);
+        }
+
+        // If try succeeds, return Ok
+        if (func.return_type->kind != TypeKind::Void) {
+            writeLine("Ok(result)  // TODO: actual return value");
+        } else {
+            writeLine("Ok(())");
+        }
+
+        dedent();
+        writeLine("})()");
+
+        // Generate match for error handling (equivalent to catch)
+        if (!block.catch_clauses.empty()) {
+            writeLine(".or_else(|err| {");
+            indent();
+
+            for (const auto& catch_clause : block.catch_clauses) {
+                writeLine("// Catch " + catch_clause.exception_type);
+
+                if (catch_clause.exception_type == "...") {
+                    writeLine("// Catch-all handler");
+                } else {
+                    writeLine("// Handle specific exception type: " + catch_clause.exception_type);
+                }
+
+                if (!catch_clause.handler_body.empty()) {
+                    writeLine(catch_clause.handler_body);
+                }
+            }
 
             if (func.return_type->kind != TypeKind::Void) {
                 writeLine("Ok(default_value)  // TODO: error recovery value");
@@ -249,6 +1128,58 @@ void RustCodeGenerator::generateTryCatchAsResult(const Function& func) {
     }
 }
 
+void RustCodeGenerator::generateTypeAlias(const TypeAlias& alias) {
+    std::string target = convertType(alias.type);
+
+    // Instantiation of a class template: Stack<int> is stack<i32>, Buffer<int, 4> is buffer<i32, 4>
+    const auto& type = alias.type;
+    size_t open = type ? type->name.find('<') : std::string::npos;
+    if (type && type->kind == TypeKind::Class && open != std::string::npos && !findTypeMapping(type->name)) {
+        std::string base = type->name.substr(0, open);
+        size_t scope = base.rfind("::");
+        target = sanitizeName(scope == std::string::npos ? base : base.substr(scope + 2)) + "<";
+        for (size_t i = 0; i < type->template_args.size(); ++i) {
+            const auto& arg = type->template_args[i];
+            bool value = arg->kind == TypeKind::Class && !arg->name.empty() &&
+                         !std::isalpha(static_cast<unsigned char>(arg->name[0])) && arg->name[0] != '_';
+            target += (i > 0 ? ", " : "") + (value ? arg->name : convertType(arg));
+        }
+        target += ">";
+    }
+
+    writeDocComment(alias.doc);
+    writeLine("pub type " + sanitizeName(alias.name) + " = " + target + ";");
+}
+
+/**
+ * Visibility of a member of the class being generated: everything is pub,
+ * except in library mode, where private members are private to the module
+ * and protected ones visible to the crate, or the workspace (derived
+ * classes may be elsewhere)
+ */
+std::string RustCodeGenerator::memberVisibility(const std::string& member) const {
+    if (!library_mode_ || !member_class_) return "pub ";
+    for (const auto& section : member_class_->access_sections) {
+        if (std::find(section.members.begin(), section.members.end(), member) == section.members.end()) continue;
+        switch (section.level) {
+            case ClassDecl::AccessSection::Public: return "pub ";
+            case ClassDecl::AccessSection::Protected: return workspace_member_ ? "pub " : "pub(crate) ";
+            case ClassDecl::AccessSection::Private: return "";
+        }
+    }
+    return "pub ";
+}
+
+void RustCodeGenerator::writeDocComment(const std::string& doc) {
+    // Only library crates keep them: the application output stays as it was
+    if (!library_mode_ || doc.empty()) return;
+    std::stringstream lines(doc);
+    std::string line;
+    while (std::getline(lines, line)) {
+        writeLine(line.empty() ? "///" : "/// " + line);
+    }
+}
+
 void RustCodeGenerator::generateVariable(const Variable& var) {
     std::string modifier = var.is_const ? "const" : "static";
     std::string name = sanitizeName(var.name).c_str();
@@ -270,6 +1201,19 @@ void RustCodeGenerator::generateVariable(const Variable& var) {
 std::string RustCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
     if (!type) return "()";
 
+    if (type->allocator) {
+        auto global = std::make_shared<Type>(*type);
+        global->allocator = nullptr;
+        return convertContainerAllocator(type, convertType(global));
+    }
+
+    // Never locked from a second thread at run time: a borrow flag is enough
+    if (type->single_threaded) {
+        decide("LOCK-REFCELL", type->name, "std::cell::RefCell<()>",
+               "--runtime-trace only saw this mutex locked from one thread");
+        return "std::cell::RefCell<()>";
+    }
+
     switch (type->kind) {
         case TypeKind::Void:
             return "()";
@@ -277,35 +1221,86 @@ std::string RustCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
         case TypeKind::Bool:
             return "bool";
 
-        case TypeKind::Integer:
-            // Map C++ integer types to Rust
-            if (type->name == "int" || type->name == "int32_t") return "i32";
-            if (type->name == "long" || type->name == "int64_t") return "i64";
-            if (type->name == "short" || type->name == "int16_t") return "i16";
-            if (type->name == "char" || type->name == "int8_t") return "i8";
-            if (type->name == "unsigned int" || type->name == "uint32_t") return "u32";
-            if (type->name == "unsigned long" || type->name == "uint64_t") return "u64";
-            if (type->name == "unsigned short" || type->name == "uint16_t") return "u16";
-            if (type->name == "unsigned char" || type->name == "uint8_t") return "u8";
-            if (type->name == "size_t") return "usize";
-            return "i32"; // default
+        case TypeKind::Integer: {
+            // Map C++ integer types to Rust; long follows the data model
+            IntegerFormat format = integerFormat(type->name);
+            if (format.pointer_sized) return format.is_signed ? "isize" : "usize";
+            return (format.is_signed ? "i" : "u") + std::to_string(format.bits);
+        }
 
         case TypeKind::Float:
             if (type->name == "float") return "f32";
             if (type->name == "double") return "f64";
+            if (type->name == "long double") {
+                reportOnce("long double becomes f64; the 80-bit and 128-bit C++ formats keep more precision");
+            }
             return "f64"; // default
 
         case TypeKind::Pointer:
+            // Pointer to data member: accessor taking the object
+            if (!type->member_of.empty()) {
+                return "fn(&mut " + sanitizeName(type->member_of) + ") -> &mut " +
+                       convertType(type->element_type);
+            }
+            // Links between arena-allocated objects: generational ids
+            if (type->element_type && arena_classes_.count(type->element_type->name) &&
+                type->name.find("_ptr<") == std::string::npos) {
+                decide("SELF-ARENA", type->name, "Option<" + type->element_type->name + "Id>",
+                       "intrusive link between " + type->element_type->name + " objects (--self-ref arena)");
+                return "Option<" + type->element_type->name + "Id>";
+            }
             return convertSmartPointer(type);
 
+        case TypeKind::Function: {
+            // Pointer to member function: the object becomes the first parameter
+            std::vector<std::string> params;
+            if (!type->member_of.empty()) {
+                params.push_back((type->is_const ? "&" : "&mut ") + sanitizeName(type->member_of));
+            }
+            for (const auto& param : type->parameter_types) {
+                params.push_back(convertType(param));
+            }
+            // std::function<R(Args)> holds a callable of any kind, captures
+            // included; function pointers are spelled with (*)
+            bool closure = type->name.find('*') == std::string::npos;
+            std::string result = closure ? "Box<dyn Fn(" : "fn(";
+            for (size_t i = 0; i < params.size(); ++i) {
+                result += (i > 0 ? ", " : "") + params[i];
+            }
+            result += ")";
+            if (type->element_type && type->element_type->kind != TypeKind::Void) {
+                result += " -> " + convertType(type->element_type);
+            }
+            return closure ? result + ">" : result;
+        }
+
         case TypeKind::Reference:
+            // Read-only strings: &String, literals and slices all coerce to &str
+            if (type->isBorrowedString()) {
+                decide("STR-BORROW", type->name, "&str",
+                       "a read-only string: callers pass &String, literals or slices without a copy");
+                return "&str";
+            }
             if (type->is_const) {
                 return "&" + convertType(type->element_type);
             } else {
                 return "&mut " + convertType(type->element_type);
             }
 
+        case TypeKind::RValueReference:
+            // T&& parameters take ownership: pass by value
+            return convertType(type->element_type);
+
         case TypeKind::Array:
+            // Bound as written: a literal, constant or const generic parameter
+            if (!type->array_size.empty()) {
+                return "[" + convertType(type->element_type) + "; " + type->array_size + "]";
+            }
+            // Unknown bound (e.g. unique_ptr<T[]>): unsized slice
+            if (!type->element_type || type->element_type->size_bytes == 0 ||
+                type->size_bytes == 0) {
+                return "[" + convertType(type->element_type) + "]";
+            }
             return "[" + convertType(type->element_type) + "; " +
                    std::to_string(type->size_bytes / type->element_type->size_bytes) + "]";
 
@@ -366,6 +1361,16 @@ std::string RustCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
             }
             return "((), ())";
 
+        case TypeKind::StdTuple: {
+            std::string tuple = "(";
+            for (size_t i = 0; i < type->template_args.size(); ++i) {
+                if (i > 0) tuple += ", ";
+                tuple += convertType(type->template_args[i]);
+            }
+            // A one-element tuple needs a trailing comma
+            return tuple + (type->template_args.size() == 1 ? ",)" : ")");
+        }
+
         case TypeKind::StdOptional:
             if (!type->template_args.empty()) {
                 return "Option<" + convertType(type->template_args[0]) + ">";
@@ -447,8 +1452,23 @@ std::string RustCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
 
         case TypeKind::Struct:
         case TypeKind::Class:
+        case TypeKind::Enum:
+            // va_list parameter: the caller's VarArg iterator
+            if (type->name == "va_list") return "&mut std::slice::Iter<'_, VarArg>";
+            // Library types of the enabled mapping packs
+            if (const TypeMapping* mapping = findTypeMapping(type->name)) {
+                std::vector<std::string> args;
+                for (const auto& arg : type->template_args) args.push_back(convertType(arg));
+                std::string mapped = applyTypeMapping(*mapping, mapping->rust, args);
+                decide("MAP-PACK", type->name, mapped, "mapped by the " + mapping->cpp + " entry of a --mapping-pack");
+                return mapped;
+            }
             return sanitizeName(type->name);
 
+        case TypeKind::Template:
+            // Generic parameter of the enclosing struct
+            return type->name;
+
         default:
             return "/* Unknown type: " + type->name + " */";
     }
@@ -456,25 +1476,56 @@ std::string RustCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
 
 std::string RustCodeGenerator::convertSmartPointer(const std::shared_ptr<Type>& type) {
     // Check if this is a smart pointer pattern
+    std::string result;
     if (type->name.find("unique_ptr") != std::string::npos) {
-        return "Box<" + convertType(type->element_type) + ">";
+        result = "Box<" + convertType(type->element_type) + ">";
+        decide("OWN-BOX", type->name, result, "unique_ptr has a single owner");
+        return result;
     }
     if (type->name.find("shared_ptr") != std::string::npos) {
-        return "Rc<" + convertType(type->element_type) + ">";
+        // Reaches another thread: Rc is not Send
+        if (type->thread_shared && type->needs_lock) {
+            result = "std::sync::Arc<std::sync::Mutex<" + convertType(type->element_type) + ">>";
+            decide("OWN-ARC-MUTEX", type->name, result,
+                   "the pointee reaches another thread and is written without synchronization of its own");
+            return result;
+        }
+        if (type->thread_shared) {
+            result = "std::sync::Arc<" + convertType(type->element_type) + ">";
+            decide("OWN-ARC", type->name, result,
+                   "the pointee reaches another thread; it is const, synchronized, or never written while shared");
+            return result;
+        }
+        result = "Rc<" + convertType(type->element_type) + ">";
+        decide("OWN-RC", type->name, result,
+               "shared ownership, and no shared_ptr to this type was seen reaching another thread");
+        return result;
     }
     if (type->name.find("weak_ptr") != std::string::npos) {
-        return "Weak<" + convertType(type->element_type) + ">";
+        result = "Weak<" + convertType(type->element_type) + ">";
+        decide("OWN-WEAK", type->name, result, "non-owning observer of a shared_ptr");
+        return result;
     }
 
-    // Raw pointer - use unsafe raw pointer in Rust
-    if (type->is_const) {
-        return "*const " + convertType(type->element_type);
-    } else {
-        return "*mut " + convertType(type->element_type);
+    // Buffer from new T[n], released with delete[]: an owned slice
+    if (type->owned_array) {
+        result = "Box<[" + convertType(type->element_type) + "]>";
+        decide("SLICE-BOX", type->name, result, "allocated with new[] and released with delete[]; the box frees it when dropped");
+        return result;
     }
+
+    // Raw pointer - use unsafe raw pointer in Rust
+    result = (type->is_const ? "*const " : "*mut ") + convertType(type->element_type);
+    decide("PTR-RAW", type->name, result, "a raw pointer says nothing about ownership or lifetime");
+    return result;
 }
 
 std::string RustCodeGenerator::sanitizeName(const std::string& name) {
+    // Rust cannot overload the call operator on stable: functors get a call method
+    if (name == "operator()") {
+        return "call";
+    }
+
     // Convert C++ naming conventions to Rust snake_case
     std::string result;
     bool prev_upper = false;
@@ -508,67 +1559,2462 @@ std::string RustCodeGenerator::sanitizeName(const std::string& name) {
     return result;
 }
 
-std::string RustCodeGenerator::convertTemplateParametersToRust(const std::vector<TemplateParameter>& params) {
-    if (params.empty()) {
-        return "";
-    }
+std::string RustCodeGenerator::translateBody(const Function& func) {
+    std::string body = func.body;
 
-    std::stringstream ss;
-    ss << "<";
+    // Structured bindings and std::tie: tuple/struct destructuring
+    // (first, so later passes still find the initializer expressions)
+    body = convertBindings(func, body);
 
-    bool first = true;
-    for (const auto& param : params) {
-        if (!first) ss << ", ";
-        first = false;
+    // glog/spdlog/logging macros: log::info!, log::error!, ...
+    body = convertLogging(func, body);
 
-        if (param.kind == TemplateParameter::Type) {
-            ss << param.name;
+    // std::format / fmt: format!, print!, println!
+    body = convertFormatting(func, body);
 
-            // Add trait bounds if constraints exist
-            if (!param.constraints.empty()) {
-                ss << ": ";
-                for (size_t i = 0; i < param.constraints.size(); ++i) {
-                    if (i > 0) ss << " + ";
-                    ss << param.constraints[i];
-                }
-            }
-        } else if (param.kind == TemplateParameter::NonType) {
-            // Rust const generics
-            ss << "const " << param.name << ": ";
-            if (param.param_type) {
-                ss << convertType(param.param_type);
-            } else {
-                ss << "usize";
-            }
-        }
-    }
+    // Error-code statuses: Ok/Err returns and ? propagation
+    body = convertStatusSites(func, body);
 
-    ss << ">";
-    return ss.str();
-}
+    // Implicit copies: explicit clone() for non-Copy types
+    body = convertCopySemantics(func, body);
 
-std::string RustCodeGenerator::convertTemplateArgsToRust(const std::vector<TemplateParameter>& params) {
-    if (params.empty()) {
-        return "";
-    }
+    // Move semantics: std::move / std::exchange
+    body = convertMoveSemantics(func, body);
 
-    std::stringstream ss;
-    ss << "<";
+    // Scope-bound RAII guards: lock_guard, unique_lock, scope_exit
+    body = convertGuards(func, body);
 
-    bool first = true;
-    for (const auto& param : params) {
-        if (!first) ss << ", ";
-        first = false;
+    // Layout queries: sizeof / alignof / offsetof
+    body = convertLayoutQueries(func, body);
 
-        if (param.kind == TemplateParameter::Type) {
-            ss << param.name;
-        } else if (param.kind == TemplateParameter::NonType) {
-            ss << param.name;
-        }
-    }
+    // std::make_pair / std::get / .first: tuple expressions
+    body = convertTuples(func, body);
+
+    // <algorithm>/<numeric>: container methods and iterator adapters
+    // (before functors, so predicates constructed in place become closures)
+    body = convertAlgorithms(func, body);
+
+    // Function objects: closures at argument sites, .call() on stored ones
+    body = convertFunctors(func, body);
+
+    // Pointers to members: fn pointers taking the object, accessor closures
+    body = convertMemberPointers(func, body);
+
+    // <cstdarg>: va_arg reads the next element of the VarArg slice
+    body = convertVarargs(func, body);
+
+    // Placement new / p->~T(): ptr::write and ptr::drop_in_place
+    body = convertAllocations(func, body);
+
+    // Conversions to one of several bases: trait objects or accessors
+    body = convertUpcasts(func, body);
+
+    // Linked nodes of a list/tree: ids into the arena of the holder
+    body = convertGraphSites(func, body);
+
+    // argv[i], the exit status of main, exit() (before integer conversions:
+    // return n; in main is an exit status)
+    body = convertEntrySites(func, body);
+
+    // Unsigned wraparound and integer conversions (before libc: int n = strlen(s))
+    body = convertIntegerSites(func, body);
+
+    // open/read/memcpy/socket...: std::fs, std::io, std::net or the libc crate
+    body = convertLibcCalls(func, body);
+
+    // (T* ptr, size_t len) pairs, std::span methods, new T[n] buffers
+    body = convertSlices(func, body);
+
+    // Fallible constructors: throw becomes return Err, fields are locals
+    body = convertConstructionSites(func, body);
+
+    // Numeric, character and string literals, numeric_limits
+    // (last: every earlier pass finds its sites as written in C++)
+    body = convertLiterals(func, body);
+
+    return body;
+}
+
+std::string RustCodeGenerator::convertAlgorithms(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& call : func.algorithms) {
+        size_t pos = result.find(call.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        // Predicates that are not plain names are evaluated once, as in C++
+        std::vector<std::string> bindings;
+        auto apply = [&](size_t index, const std::string& name, const std::string& args) {
+            const std::string& callable = call.arguments[index];
+            if (call.stored_functors[index]) {
+                return callable + ".call(" + args + ")";
+            }
+            if (std::regex_match(callable, std::regex(R"([a-zA-Z_][\w:]*)"))) {
+                return callable + "(" + args + ")";
+            }
+            std::string binding = "let " + name + " = " + callable + ";";
+            if (std::find(bindings.begin(), bindings.end(), binding) == bindings.end()) {
+                bindings.push_back(binding);
+            }
+            return name + "(" + args + ")";
+        };
+        // Strict weak ordering -> std::cmp::Ordering
+        auto ordering = [&]() -> std::string {
+            const std::string& comparator = call.arguments[0];
+            if (std::regex_match(comparator, std::regex(R"(std::greater\s*<[^<>]*>\s*(?:\(\s*\)|\{\s*\}))"))) {
+                return "|a, b| b.cmp(a)";
+            }
+            if (std::regex_match(comparator, std::regex(R"(std::less\s*<[^<>]*>\s*(?:\(\s*\)|\{\s*\}))"))) {
+                return "|a, b| a.cmp(b)";
+            }
+            return "|a, b| if " + apply(0, "less", "a, b") + " { std::cmp::Ordering::Less } else if " +
+                   apply(0, "less", "b, a") + " { std::cmp::Ordering::Greater } else { std::cmp::Ordering::Equal }";
+        };
+
+        const std::string& c = call.container;
+        const std::string& algorithm = call.algorithm;
+        std::string replacement;
+
+        if (algorithm == "sort" || algorithm == "stable_sort") {
+            // std::sort makes no stability guarantee; std::stable_sort matches slice::sort
+            std::string method = algorithm == "sort" ? "sort_unstable" : "sort";
+            replacement = call.arguments.empty() ? c + "." + method + "()"
+                                                 : c + "." + method + "_by(" + ordering() + ")";
+        } else if (algorithm == "reverse") {
+            replacement = c + ".reverse()";
+        } else if (algorithm == "fill") {
+            replacement = c + ".fill(" + call.arguments[0] + ")";
+        } else if (algorithm == "find" || algorithm == "find_if") {
+            std::string any = algorithm == "find" ? c + ".contains(&" + call.arguments[0] + ")"
+                                                  : c + ".iter().any(|x| " + apply(0, "pred", "x") + ")";
+            if (call.end_comparison == "!=") {
+                replacement = any;
+            } else if (call.end_comparison == "==") {
+                replacement = "!" + any;
+            } else {
+                replacement = c + ".iter().position(|x| " +
+                              (algorithm == "find" ? "*x == " + call.arguments[0] : apply(0, "pred", "x")) + ")";
+                report(Diagnostic::Warning,
+                       "iterator returned by 'std::" + algorithm + "' over '" + c +
+                       "' becomes an Option<usize> index",
+                       func.name);
+            }
+        } else if (algorithm == "count") {
+            replacement = c + ".iter().filter(|&x| *x == " + call.arguments[0] + ").count()";
+        } else if (algorithm == "count_if") {
+            replacement = c + ".iter().filter(|&x| " + apply(0, "pred", "x") + ").count()";
+        } else if (algorithm == "any_of" || algorithm == "all_of" || algorithm == "none_of") {
+            replacement = std::string(algorithm == "none_of" ? "!" : "") + c + ".iter()." +
+                          (algorithm == "all_of" ? "all" : "any") + "(|x| " + apply(0, "pred", "x") + ")";
+        } else if (algorithm == "accumulate") {
+            std::string step = call.arguments.size() > 1 ? apply(1, "op", "acc, x") : "acc + x";
+            replacement = c + ".iter().fold(" + call.arguments[0] + ", |acc, x| " + step + ")";
+        } else if (algorithm == "transform") {
+            std::string mapped = apply(0, "f", "x");
+            if (call.back_inserter) {
+                replacement = call.output + ".extend(" + c + ".iter().map(|x| " + mapped + "))";
+            } else if (call.output == c) {
+                replacement = c + ".iter_mut().for_each(|x| *x = " + mapped + ")";
+            } else {
+                replacement = call.output + ".iter_mut().zip(" + c + ".iter()).for_each(|(dst, x)| *dst = " +
+                              mapped + ")";
+            }
+        } else if (algorithm == "copy") {
+            replacement = call.back_inserter ? call.output + ".extend(" + c + ".iter().cloned())"
+                                             : call.output + "[.." + c + ".len()].clone_from_slice(&" + c + ")";
+        } else if (algorithm == "copy_if") {
+            replacement = call.output + ".extend(" + c + ".iter().filter(|&x| " + apply(0, "pred", "x") +
+                          ").cloned())";
+        } else if (algorithm == "min_element" || algorithm == "max_element") {
+            std::string method = algorithm == "min_element" ? "min" : "max";
+            replacement = c + ".iter()." + method + (call.arguments.empty() ? "()" : "_by(" + ordering() + ")");
+            if (call.dereferenced) {
+                replacement = "*" + replacement + ".unwrap()";
+            } else {
+                report(Diagnostic::Warning,
+                       "iterator returned by 'std::" + algorithm + "' over '" + c +
+                       "' becomes an Option<&T>",
+                       func.name);
+            }
+        } else if (algorithm == "remove_if" || algorithm == "erase_if") {
+            replacement = c + ".retain(|x| !" + apply(0, "pred", "x") + ")";
+        } else if (algorithm == "remove" || algorithm == "erase") {
+            replacement = c + ".retain(|x| *x != " + call.arguments[0] + ")";
+        } else if (algorithm == "unique") {
+            replacement = c + ".dedup()";
+        } else {
+            continue;
+        }
+
+        if (!bindings.empty()) {
+            std::string block = "{ ";
+            for (const auto& binding : bindings) {
+                block += binding + " ";
+            }
+            replacement = block + replacement + " }";
+        }
+
+        result.replace(pos, call.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertFunctors(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& use : func.functor_uses) {
+        size_t pos = result.find(use.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        std::string replacement;
+        if (use.kind == FunctorUseInfo::Call) {
+            // cmp_(a, b) -> cmp_.call(a, b)
+            replacement = use.source_text.substr(0, use.source_text.size() - 1);
+            replacement.erase(replacement.find_last_not_of(" \t\r\n") + 1);
+            replacement += ".call(";
+        } else {
+            std::string params;
+            for (size_t i = 0; i < use.parameters.size(); ++i) {
+                params += (i > 0 ? ", " : "") + sanitizeName(use.parameters[i]);
+            }
+
+            std::string name = sanitizeName(use.functor);
+            std::string value;
+            if (use.has_constructor) {
+                value = name + "::new(";
+                for (size_t i = 0; i < use.arguments.size(); ++i) {
+                    value += (i > 0 ? ", " : "") + use.arguments[i];
+                }
+                value += ")";
+            } else {
+                value = name + " {";
+                for (size_t i = 0; i < use.field_names.size(); ++i) {
+                    value += (i > 0 ? ", " : " ") + sanitizeName(use.field_names[i]) + ": " + use.arguments[i];
+                }
+                value += use.field_names.empty() ? "}" : " }";
+            }
+
+            // Stateless functors are built inside the closure; others are moved into it
+            if (use.arguments.empty()) {
+                replacement = "|" + params + "| " + value + ".call(" + params + ")";
+            } else {
+                replacement = "{ let functor = " + value + "; move |" + params + "| functor.call(" +
+                              params + ") }";
+            }
+        }
+
+        result.replace(pos, use.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertMemberPointers(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    // The object a pointer to member is applied to, as a receiver argument
+    auto receiver = [&func](const MemberPointerInfo& site) -> std::string {
+        if (site.object == "this") return "self";
+        if (site.through_pointer) return "&mut *" + site.object;
+        // Reference parameters are already `&mut T` in Rust
+        for (const auto& param : func.parameters) {
+            if (param.name == site.object && param.type && param.type->kind == TypeKind::Reference) {
+                return site.object;
+            }
+        }
+        return "&mut " + site.object;
+    };
+
+    for (const auto& site : func.member_pointers) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        std::string replacement;
+        switch (site.kind) {
+            case MemberPointerInfo::AddressOf:
+                if (site.is_data_member) {
+                    // Non-capturing closures coerce to fn(&mut C) -> &mut T
+                    replacement = "|object: &mut " + sanitizeName(site.class_name) + "| &mut object." +
+                                  sanitizeName(site.member);
+                } else {
+                    replacement = sanitizeName(site.class_name) + "::" + sanitizeName(site.member);
+                    if (site.is_virtual) {
+                        report(Diagnostic::Warning,
+                               "'&" + site.class_name + "::" + site.member +
+                               "' names a virtual method; the fn pointer calls it without dynamic dispatch",
+                               func.name);
+                    }
+                    if (site.is_overloaded) {
+                        report(Diagnostic::Warning,
+                               "'&" + site.class_name + "::" + site.member +
+                               "' names an overloaded method; Rust has no overloading, so the pointer "
+                               "needs the renamed overload",
+                               func.name);
+                    }
+                }
+                break;
+
+            case MemberPointerInfo::Call:
+                replacement = site.pointer + "(" + receiver(site);
+                for (const auto& arg : site.arguments) {
+                    replacement += ", " + arg;
+                }
+                replacement += ")";
+                break;
+
+            case MemberPointerInfo::Access:
+                replacement = "(*" + site.pointer + "(" + receiver(site) + "))";
+                break;
+
+            case MemberPointerInfo::Declaration:
+                replacement = "let " + sanitizeName(site.name) + ": " + convertType(site.type);
+                break;
+        }
+
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertVarargs(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& site : func.vararg_sites) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        std::string list = sanitizeName(site.list);
+        std::string replacement;
+        switch (site.kind) {
+            case VarargSiteInfo::ListDeclaration:
+            case VarargSiteInfo::End:
+                // The iterator is declared by va_start and dropped at scope end
+                replacement = "";
+                break;
+
+            case VarargSiteInfo::Start:
+                replacement = "let mut " + list + " = " + list + ".iter();";
+                break;
+
+            case VarargSiteInfo::Copy:
+                replacement = "let mut " + sanitizeName(site.target) + " = " + list + ".clone();";
+                break;
+
+            case VarargSiteInfo::Arg: {
+                std::string payload;
+                std::string variant = varArgVariant(site.type, payload);
+                if (variant.empty()) {
+                    report(Diagnostic::Warning,
+                           "va_arg of type '" + (site.type ? site.type->name : "") +
+                           "' has no VarArg variant and is left as written",
+                           func.name);
+                    cursor = pos + site.source_text.size();
+                    continue;
+                }
+                std::string accessor = variant;
+                std::transform(accessor.begin(), accessor.end(), accessor.begin(), ::tolower);
+                replacement = list + ".next().unwrap().as_" + accessor + "()";
+                break;
+            }
+
+            case VarargSiteInfo::Forward: {
+                bool from_parameter = std::any_of(func.parameters.begin(), func.parameters.end(),
+                                                  [&](const Parameter& param) { return param.name == site.list; });
+                if (site.target.size() > 1 && site.target[0] == 'v' &&
+                    site.target.find("printf") != std::string::npos) {
+                    report(Diagnostic::Warning,
+                           "'" + site.target + "' takes a C va_list; format the VarArg arguments in Rust instead",
+                           func.name);
+                    cursor = pos + site.source_text.size();
+                    continue;
+                }
+                // Another translated function taking a va_list: lend it the iterator
+                size_t open = site.source_text.find('(');
+                replacement = site.source_text.substr(0, open) +
+                              std::regex_replace(site.source_text.substr(open),
+                                                 std::regex("\\b" + site.list + "\\b"),
+                                                 from_parameter ? list : "&mut " + list);
+                break;
+            }
+        }
+
+        size_t length = site.source_text.size();
+        if (replacement.empty()) {
+            // Drop the whole line of a removed statement
+            size_t line_start = result.find_last_not_of(" \t", pos == 0 ? 0 : pos - 1);
+            size_t line_end = result.find_first_not_of(" \t", pos + length);
+            if (pos > 0 && line_start != std::string::npos && result[line_start] == '\n' &&
+                line_end != std::string::npos && result[line_end] == '\n') {
+                length = line_end + 1 - (line_start + 1);
+                pos = line_start + 1;
+            }
+        }
+
+        result.replace(pos, length, replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::varArgVariant(const std::shared_ptr<Type>& type, std::string& payload) {
+    if (!type) return "";
+
+    // const char*: owned string, read back as &str
+    if (type->kind == TypeKind::Pointer && type->element_type &&
+        type->element_type->kind == TypeKind::Integer && type->element_type->name == "char") {
+        payload = "String";
+        return "Str";
+    }
+
+    std::string rust_type = convertType(type);
+    if (!std::regex_match(rust_type, std::regex(R"([a-zA-Z_]\w*)"))) return "";
+    payload = rust_type;
+    std::string variant = rust_type;
+    variant[0] = std::toupper(static_cast<unsigned char>(variant[0]));
+    return variant;
+}
+
+void RustCodeGenerator::generateVarArgEnum(const IR& ir) {
+    std::vector<const Function*> functions;
+    for (const auto& class_decl : ir.getClasses()) {
+        for (const auto& method : class_decl.methods) functions.push_back(&method);
+    }
+    for (const auto& func : ir.getFunctions()) {
+        // Variadic functions kept in C++ take no VarArg slice
+        if (!(func.is_variadic && varargs_ == VarargsStrategy::Ffi)) functions.push_back(&func);
+    }
+
+    bool needed = false;
+    std::vector<std::pair<std::string, std::string>> variants;  // Variant, payload
+    for (const Function* func : functions) {
+        bool takes_list = std::any_of(func->parameters.begin(), func->parameters.end(),
+                                      [](const Parameter& param) { return param.type && param.type->name == "va_list"; });
+        if (!func->is_variadic && !takes_list) continue;
+        needed = true;
+        for (const auto& site : func->vararg_sites) {
+            if (site.kind != VarargSiteInfo::Arg) continue;
+            std::string payload;
+            std::string variant = varArgVariant(site.type, payload);
+            if (variant.empty()) continue;
+            bool seen = std::any_of(variants.begin(), variants.end(),
+                                    [&](const auto& entry) { return entry.first == variant; });
+            if (!seen) variants.emplace_back(variant, payload);
+        }
+    }
+    if (!needed) return;
+
+    writeLine("/// Argument of a translated C variadic function");
+    writeLine("#[derive(Debug, Clone, PartialEq)]");
+    writeLine("pub enum VarArg {");
+    indent();
+    for (const auto& variant : variants) {
+        writeLine(variant.first + "(" + variant.second + "),");
+    }
+    dedent();
+    writeLine("}");
+    writeLine("");
+
+    // va_arg(args, T): the caller passed a T, as in C
+    writeLine("impl VarArg {");
+    indent();
+    for (size_t i = 0; i < variants.size(); ++i) {
+        const auto& variant = variants[i];
+        std::string accessor = variant.first;
+        std::transform(accessor.begin(), accessor.end(), accessor.begin(), ::tolower);
+        bool text = variant.first == "Str";
+        writeLine("pub fn as_" + accessor + "(&self) -> " + (text ? "&str" : variant.second) + " {");
+        indent();
+        writeLine("match self {");
+        indent();
+        writeLine("VarArg::" + variant.first + "(value) => " + (text ? "value" : "value.clone()") + ",");
+        writeLine("other => panic!(\"expected a " + (text ? "string" : variant.second) +
+                  " argument, got {:?}\", other),");
+        dedent();
+        writeLine("}");
+        dedent();
+        writeLine("}");
+        if (i + 1 < variants.size()) writeLine("");
+    }
+    dedent();
+    writeLine("}");
+    writeLine("");
+
+    // Conversions used by the macro front-ends
+    if (varargs_ == VarargsStrategy::Macro) {
+        for (const auto& variant : variants) {
+            bool text = variant.first == "Str";
+            writeLine(std::string("impl From<") + (text ? "&str" : variant.second) + "> for VarArg {");
+            indent();
+            writeLine(std::string("fn from(value: ") + (text ? "&str" : variant.second) + ") -> Self {");
+            indent();
+            writeLine("VarArg::" + variant.first + (text ? "(value.to_string())" : "(value)"));
+            dedent();
+            writeLine("}");
+            dedent();
+            writeLine("}");
+            writeLine("");
+        }
+    }
+}
+
+void RustCodeGenerator::generateArenaTypes() {
+    writeLine("/// Generational index into an Arena<T>; stale once its slot is reused");
+    writeLine("pub struct Id<T> {");
+    indent();
+    writeLine("index: u32,");
+    writeLine("generation: u32,");
+    writeLine("marker: std::marker::PhantomData<fn() -> T>,");
+    dedent();
+    writeLine("}");
+    writeLine("");
+
+    // Derives would require T: Copy
+    writeLine("impl<T> Clone for Id<T> {");
+    indent();
+    writeLine("fn clone(&self) -> Self {");
+    indent();
+    writeLine("*self");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("impl<T> Copy for Id<T> {}");
+    writeLine("");
+    writeLine("impl<T> PartialEq for Id<T> {");
+    indent();
+    writeLine("fn eq(&self, other: &Self) -> bool {");
+    indent();
+    writeLine("self.index == other.index && self.generation == other.generation");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("impl<T> Eq for Id<T> {}");
+    writeLine("");
+
+    writeLine("/// Owns objects that point at each other; links are Ids, not references");
+    writeLine("pub struct Arena<T> {");
+    indent();
+    writeLine("slots: Vec<(u32, Option<T>)>,");
+    writeLine("free: Vec<u32>,");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("impl<T> Arena<T> {");
+    indent();
+    writeLine("pub fn new() -> Self {");
+    indent();
+    writeLine("Arena { slots: Vec::new(), free: Vec::new() }");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("pub fn insert(&mut self, value: T) -> Id<T> {");
+    indent();
+    writeLine("let index = match self.free.pop() {");
+    indent();
+    writeLine("Some(index) => {");
+    indent();
+    writeLine("self.slots[index as usize].1 = Some(value);");
+    writeLine("index");
+    dedent();
+    writeLine("}");
+    writeLine("None => {");
+    indent();
+    writeLine("self.slots.push((0, Some(value)));");
+    writeLine("(self.slots.len() - 1) as u32");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("};");
+    writeLine("Id { index, generation: self.slots[index as usize].0, marker: std::marker::PhantomData }");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("pub fn get(&self, id: Id<T>) -> Option<&T> {");
+    indent();
+    writeLine("match self.slots.get(id.index as usize) {");
+    indent();
+    writeLine("Some((generation, value)) if *generation == id.generation => value.as_ref(),");
+    writeLine("_ => None,");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("pub fn get_mut(&mut self, id: Id<T>) -> Option<&mut T> {");
+    indent();
+    writeLine("match self.slots.get_mut(id.index as usize) {");
+    indent();
+    writeLine("Some((generation, value)) if *generation == id.generation => value.as_mut(),");
+    writeLine("_ => None,");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("/// Frees the slot; every Id to it becomes stale");
+    writeLine("pub fn remove(&mut self, id: Id<T>) -> Option<T> {");
+    indent();
+    writeLine("let slot = self.slots.get_mut(id.index as usize)?;");
+    writeLine("if slot.0 != id.generation || slot.1.is_none() {");
+    indent();
+    writeLine("return None;");
+    dedent();
+    writeLine("}");
+    writeLine("slot.0 += 1;");
+    writeLine("self.free.push(id.index);");
+    writeLine("slot.1.take()");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+
+    // Dereferencing a removed object panics instead of reading freed memory
+    writeLine("impl<T> std::ops::Index<Id<T>> for Arena<T> {");
+    indent();
+    writeLine("type Output = T;");
+    writeLine("");
+    writeLine("fn index(&self, id: Id<T>) -> &T {");
+    indent();
+    writeLine("self.get(id).expect(\"stale Id: the object was removed\")");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("impl<T> std::ops::IndexMut<Id<T>> for Arena<T> {");
+    indent();
+    writeLine("fn index_mut(&mut self, id: Id<T>) -> &mut T {");
+    indent();
+    writeLine("self.get_mut(id).expect(\"stale Id: the object was removed\")");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+
+    for (const auto& name : arena_classes_) {
+        writeLine("pub type " + name + "Id = Id<" + sanitizeName(name) + ">;");
+    }
+    writeLine("");
+}
+
+std::vector<std::string> RustCodeGenerator::arenaNodes(const ClassDecl& class_decl) const {
+    std::vector<std::string> nodes;
+    for (const auto& node : class_decl.graph_nodes) {
+        if (arena_classes_.count(node)) nodes.push_back(node);
+    }
+    return nodes;
+}
+
+std::string RustCodeGenerator::arenaField(const std::string& node) {
+    return sanitizeName(node) + "s";
+}
+
+void RustCodeGenerator::generateArenaIndex(const ClassDecl& class_decl, const std::string& node) {
+    std::string generics;
+    std::string args;
+    if (class_decl.is_template && !class_decl.template_parameters.empty()) {
+        generics = convertTemplateParametersToRust(class_decl.template_parameters);
+        args = convertTemplateArgsToRust(class_decl.template_parameters);
+    }
+    std::string holder = sanitizeName(class_decl.name) + args;
+    std::string id = node + "Id";
+    std::string element = sanitizeName(node);
+
+    writeLine("// " + class_decl.name + " owns its " + node + " objects: " + id + " indexes them");
+    writeLine("impl" + generics + " std::ops::Index<" + id + "> for " + holder + " {");
+    indent();
+    writeLine("type Output = " + element + ";");
+    writeLine("");
+    writeLine("fn index(&self, id: " + id + ") -> &" + element + " {");
+    indent();
+    writeLine("&self." + arenaField(node) + "[id]");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+    writeLine("");
+    writeLine("impl" + generics + " std::ops::IndexMut<" + id + "> for " + holder + " {");
+    indent();
+    writeLine("fn index_mut(&mut self, id: " + id + ") -> &mut " + element + " {");
+    indent();
+    writeLine("&mut self." + arenaField(node) + "[id]");
+    dedent();
+    writeLine("}");
+    dedent();
+    writeLine("}");
+}
+
+std::string RustCodeGenerator::convertGraphSites(const Function& func, const std::string& body) {
+    if (self_ref_ != SelfRefStrategy::Arena) {
+        return body;
+    }
+
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& site : func.graph_sites) {
+        if (!arena_classes_.count(site.node_class)) {
+            continue;
+        }
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        std::string arena = "self." + arenaField(site.node_class);
+        std::string pointer = (site.pointer_is_field ? "self." : "") + sanitizeName(site.pointer);
+        std::string replacement;
+        switch (site.kind) {
+            case GraphSiteInfo::Declaration:
+                replacement = "let mut " + sanitizeName(site.pointer) + ": Option<" + site.node_class + "Id>";
+                break;
+            case GraphSiteInfo::NewNode: {
+                std::string args;
+                for (size_t i = 0; i < site.arguments.size(); ++i) {
+                    if (i > 0) args += ", ";
+                    args += site.arguments[i];
+                }
+                replacement = "Some(" + arena + ".insert(" + sanitizeName(site.node_class) + "::new(" + args + ")))";
+                break;
+            }
+            case GraphSiteInfo::DeleteNode:
+            case GraphSiteInfo::NodeAccess: {
+                // Each -> reads the node the id names; null ids panic
+                std::string expression = pointer;
+                for (const auto& member : site.members) {
+                    expression = arena + "[" + expression + ".unwrap()]." + sanitizeName(member);
+                }
+                replacement = site.kind == GraphSiteInfo::DeleteNode
+                                  ? "if let Some(id) = " + expression + " { " + arena + ".remove(id); }"
+                                  : expression;
+                break;
+            }
+        }
+
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+void RustCodeGenerator::generateVarargsMacro(const Function& func, const std::string& class_name) {
+    std::string name = sanitizeName(func.name);
+    std::string macro_name = class_name.empty() ? name : sanitizeName(class_name) + "_" + name;
+
+    std::string pattern;
+    std::string call = class_name.empty() ? name + "(" : "$object." + name + "(";
+    if (!class_name.empty()) pattern = "$object:expr";
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        std::string param = func.parameters[i].name.empty() ? "arg" + std::to_string(i)
+                                                            : sanitizeName(func.parameters[i].name);
+        pattern += (pattern.empty() ? "$" : ", $") + param + ":expr";
+        call += "$" + param + ", ";
+    }
+    pattern += " $(, $arg:expr)* $(,)?";
+    call += "&[$(VarArg::from($arg)),*])";
+
+    writeLine("/// " + (class_name.empty() ? name : class_name + "::" + func.name) +
+              " with its variadic arguments as written");
+    writeLine("macro_rules! " + macro_name + " {");
+    indent();
+    writeLine("(" + pattern + ") => {");
+    indent();
+    writeLine(call);
+    dedent();
+    writeLine("};");
+    dedent();
+    writeLine("}");
+}
+
+void RustCodeGenerator::generateExternDeclaration(const Function& func) {
+    stats_.functions_retained++;
+    ScopedValue context(decision_context_, func.name);
+    decide("FFI-RETAIN", func.name, "extern \"C\" declaration",
+           func.retained_reason.empty() ? "--varargs ffi keeps C variadic functions in C++" : func.retained_reason);
+    std::vector<std::string> params;
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        const auto& param = func.parameters[i];
+        std::string name = param.name.empty() ? "arg" + std::to_string(i) : sanitizeName(param.name);
+        params.push_back(name + ": " + convertFfiType(param.type));
+    }
+    if (func.is_variadic) params.push_back("...");
+
+    std::string sig = "pub fn " + sanitizeName(func.name) + "(";
+    for (size_t i = 0; i < params.size(); ++i) {
+        sig += (i > 0 ? ", " : "") + params[i];
+    }
+    sig += ")";
+    if (func.return_type && func.return_type->kind != TypeKind::Void) {
+        sig += " -> " + convertFfiType(func.return_type);
+    }
+
+    if (!func.retained_reason.empty()) {
+        // Explained by the analysis diagnostic
+        writeLine("// Kept in C++ (declared extern \"C\" there): " + func.retained_reason);
+    } else {
+        writeLine("// Variadic: kept in C++ (declared extern \"C\" there) and called through the C ABI");
+        report(Diagnostic::Note,
+               "'" + func.name + "' is kept in C++; it must have extern \"C\" linkage", func.name);
+    }
+    writeLine("extern \"C\" {");
+    indent();
+    writeLine(sig + ";");
+    dedent();
+    writeLine("}");
+}
+
+void RustCodeGenerator::generateEntryPoint(const Function& func) {
+    countFunction(!func.body.empty());
+
+    // --clap: the option parsing loop becomes a derive struct
+    const CommandLineInfo* command_line = clap_ && !func.command_lines.empty() ? &func.command_lines[0] : nullptr;
+    std::string rest = func.body;
+    if (command_line) {
+        generateCliStruct(*command_line);
+        writeLine("");
+        rest.erase(rest.find(command_line->source_text), command_line->source_text.size());
+    }
+
+    writeLine("fn main() -> std::process::ExitCode {");
+    indent();
+
+    // argc and argv are both read from the one Vec
+    if (func.parameters.size() >= 2) {
+        const std::string& argc = func.parameters[0].name;
+        const std::string& argv = func.parameters[1].name;
+        bool uses_argc = !argc.empty() && std::regex_search(rest, std::regex("\\b" + argc + "\\b"));
+        if (uses_argc || std::regex_search(rest, std::regex("\\b" + argv + "\\b"))) {
+            writeLine("let args: Vec<String> = std::env::args().collect();");
+        }
+        if (uses_argc) {
+            writeLine("let " + sanitizeName(argc) + " = args.len() as i32;");
+        }
+    }
+    if (func.parameters.size() > 2) {
+        report(Diagnostic::Warning,
+               "main's environment parameter '" + func.parameters[2].name +
+                   "' is dropped; read the environment with std::env::vars()",
+               func.name);
+    }
+
+    writeLine(translateBody(func));
+    // Falling off the end of main returns 0
+    static const std::regex final_return(R"(\breturn\b[^;]*;\s*$)");
+    if (!std::regex_search(func.body, final_return)) {
+        writeLine("std::process::ExitCode::SUCCESS");
+    }
+
+    dedent();
+    writeLine("}");
+}
+
+std::string RustCodeGenerator::convertEntrySites(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& site : func.entry_sites) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+        std::string replacement = entrySite(func, site);
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::entrySite(const Function& func, const EntrySiteInfo& site) {
+    // Nested uses of argv first: return run(argv);
+    std::string expression = site.expression;
+    size_t cursor = 0;
+    for (const auto& nested : site.nested) {
+        size_t pos = expression.find(nested.source_text, cursor);
+        if (pos == std::string::npos) continue;
+        std::string replacement = entrySite(func, nested);
+        expression.replace(pos, nested.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+    // A name or a call needs no parentheses before `as`
+    std::string callee = expression;
+    if (!callee.empty() && callee.back() == ')') {
+        int depth = 0;
+        for (size_t i = callee.size(); i-- > 0;) {
+            if (callee[i] == ')') depth++;
+            else if (callee[i] == '(' && --depth == 0) {
+                callee = callee.substr(0, i);
+                break;
+            }
+        }
+    }
+    bool simple = std::regex_match(callee, std::regex(R"([\w.:]+)"));
+
+    switch (site.kind) {
+        case EntrySiteInfo::Argument:
+            // Strings, not char*: args[1].as_str() where argv[1] was read
+            if (std::regex_match(expression, std::regex(R"(\d+)"))) return "args[" + expression + "].as_str()";
+            return "args[" + (simple ? expression : "(" + expression + ")") + " as usize].as_str()";
+        case EntrySiteInfo::ArgumentUse:
+            reportOnce("argv is used as a char**; it becomes &args, a &[String], and what receives it must "
+                       "take &[String]",
+                       func.name);
+            return "&args";
+        case EntrySiteInfo::Return:
+            if (expression == "0" || expression == "EXIT_SUCCESS") return "return std::process::ExitCode::SUCCESS;";
+            if (expression == "1" || expression == "EXIT_FAILURE") return "return std::process::ExitCode::FAILURE;";
+            // Exit statuses are 8 bits wide, as the C++ program's were
+            return "return std::process::ExitCode::from(" + (simple ? expression : "(" + expression + ")") +
+                   " as u8);";
+        case EntrySiteInfo::Exit:
+            if (expression == "EXIT_SUCCESS") expression = "0";
+            if (expression == "EXIT_FAILURE") expression = "1";
+            return "std::process::exit(" + expression + ")";
+        case EntrySiteInfo::CommandLine:
+            if (!clap_) {
+                report(Diagnostic::Note, "the option parsing loop of main can become a clap derive struct (--clap)",
+                       func.name);
+                return expression;
+            }
+            if (site.source_text == func.command_lines[0].source_text) {
+                size_t line_start = func.body.rfind('\n', func.body.find(site.source_text));
+                size_t first = line_start == std::string::npos ? 0 : line_start + 1;
+                return clapParse(func.command_lines[0],
+                                 func.body.substr(first, func.body.find_first_not_of(" \t", first) - first));
+            }
+            return expression;
+    }
+    return site.source_text;
+}
+
+void RustCodeGenerator::generateCliStruct(const CommandLineInfo& command_line) {
+    static const char* styles[] = {"an argv loop", "a getopt loop", "a getopt_long loop"};
+    writeLine(std::string("/// Command line of main, parsed by clap (was ") + styles[command_line.style] + ")");
+    writeLine("#[derive(clap::Parser)]");
+    writeLine("struct Cli {");
+    indent();
+
+    for (const auto& option : command_line.options) {
+        std::string field = cliFieldName(option);
+        std::vector<std::string> attributes;
+        if (option.short_name) attributes.push_back(std::string("short = '") + option.short_name + "'");
+        if (!option.long_name.empty()) {
+            // clap's default long name is the field name in kebab case
+            std::string kebab = field;
+            std::replace(kebab.begin(), kebab.end(), '_', '-');
+            attributes.push_back(option.long_name == kebab ? "long" : "long = \"" + option.long_name + "\"");
+        }
+
+        std::string type;
+        if (option.counted) {
+            attributes.push_back("action = clap::ArgAction::Count");
+            type = "u8";
+        } else if (!option.takes_value) {
+            type = "bool";
+        } else if (option.repeated) {
+            type = cliIsVector(option.type) ? cliValueType(option.type) : "Vec<String>";
+        } else {
+            type = "Option<" + cliValueType(option.type) + ">";
+        }
+
+        std::string list;
+        for (const auto& attribute : attributes) list += (list.empty() ? "" : ", ") + attribute;
+        writeLine("#[arg(" + list + ")]");
+        writeLine(field + ": " + type + ",");
+    }
+
+    // The arguments that are not options
+    if (!command_line.positional.empty()) {
+        const auto& type = command_line.positional_type;
+        writeLine(sanitizeName(command_line.positional) + ": " +
+                  (cliIsVector(type) ? cliValueType(type) : "Option<" + cliValueType(type) + ">") + ",");
+    }
+
+    dedent();
+    writeLine("}");
+}
+
+std::string RustCodeGenerator::clapParse(const CommandLineInfo& command_line, const std::string& indentation) {
+    reportOnce("the command line is parsed by clap; add clap with the derive feature to the crate's dependencies",
+               "main");
+    std::vector<std::string> lines = {"let cli = <Cli as clap::Parser>::parse();"};
+    for (const auto& option : command_line.options) {
+        std::string field = "cli." + cliFieldName(option);
+        std::string target = sanitizeName(option.target);
+        std::string action = option.action.empty() ? "" : " " + option.action;
+        if (option.counted) {
+            std::string rust_type = option.type ? convertType(option.type) : "u8";
+            lines.push_back(target + " += " + field + (rust_type == "u8" ? "" : " as " + rust_type) + ";");
+        } else if (!option.takes_value) {
+            // The C++ default stands unless the flag is given
+            std::string set = option.target.empty()
+                                  ? ""
+                                  : " " + target + " = " +
+                                        (option.type && option.type->kind != TypeKind::Bool ? "1" : "true") + ";";
+            lines.push_back("if " + field + " {" + set + action + " }");
+        } else if (option.repeated) {
+            lines.push_back(target + ".extend(" + field + ");");
+        } else {
+            std::string set = option.target.empty() ? "" : " " + target + " = value;";
+            lines.push_back("if let Some(value) = " + field + " {" + set + action + " }");
+        }
+    }
+    if (!command_line.positional.empty()) {
+        std::string target = sanitizeName(command_line.positional);
+        const auto& type = command_line.positional_type;
+        lines.push_back(cliIsVector(type)
+                            ? target + ".extend(cli." + target + ");"
+                            : "if let Some(value) = cli." + target + " { " + target + " = value; }");
+    }
+    if (command_line.uses_optind) {
+        report(Diagnostic::Warning,
+               "optind is not set by clap; the arguments after the options need a positional field in Cli", "main");
+    }
+
+    std::string result;
+    for (const auto& line : lines) result += (result.empty() ? "" : "\n" + indentation) + line;
+    return result;
+}
+
+std::string RustCodeGenerator::cliFieldName(const CliOptionInfo& option) {
+    std::string name = !option.long_name.empty() ? option.long_name : option.target;
+    if (name.empty()) name = std::string("flag_") + option.short_name;
+    std::replace(name.begin(), name.end(), '-', '_');
+    return sanitizeName(name);
+}
+
+std::string RustCodeGenerator::cliValueType(const std::shared_ptr<Type>& type) {
+    // clap parses into the declared type; C strings are read as String
+    if (!type || type->kind == TypeKind::Pointer || type->kind == TypeKind::StdString || type->name == "std::string" ||
+        type->name == "string") {
+        return "String";
+    }
+    if (cliIsVector(type)) {
+        return "Vec<" + cliValueType(type->template_args.empty() ? nullptr : type->template_args[0]) + ">";
+    }
+    return convertType(type);
+}
+
+bool RustCodeGenerator::cliIsVector(const std::shared_ptr<Type>& type) {
+    return type && (type->kind == TypeKind::StdVector || type->name.compare(0, 12, "std::vector<") == 0);
+}
+
+std::string RustCodeGenerator::convertFfiType(const std::shared_ptr<Type>& type) {
+    // In C prototypes char is c_char, signed or unsigned as the target's C compiler has it
+    auto is_char = [](const std::shared_ptr<Type>& t) {
+        return t && t->kind == TypeKind::Integer && t->name == "char";
+    };
+    if (is_char(type)) return "std::ffi::c_char";
+    if (type && type->kind == TypeKind::Pointer && type->name.find("_ptr<") == std::string::npos &&
+        type->member_of.empty() && is_char(type->element_type)) {
+        return std::string(type->is_const ? "*const " : "*mut ") + "std::ffi::c_char";
+    }
+    if (type && type->kind == TypeKind::Pointer && type->element_type &&
+        type->element_type->kind == TypeKind::Void) {
+        return std::string(type->is_const ? "*const " : "*mut ") + "std::ffi::c_void";
+    }
+    // C function pointers use the C calling convention
+    if (type && type->kind == TypeKind::Function && type->member_of.empty() &&
+        type->name.find('*') != std::string::npos) {
+        std::string result = "extern \"C\" fn(";
+        for (size_t i = 0; i < type->parameter_types.size(); ++i) {
+            result += (i > 0 ? ", " : "") + convertFfiType(type->parameter_types[i]);
+        }
+        result += ")";
+        if (type->element_type && type->element_type->kind != TypeKind::Void) {
+            result += " -> " + convertFfiType(type->element_type);
+        }
+        return result;
+    }
+    return convertType(type);
+}
+
+std::shared_ptr<Type> RustCodeGenerator::callbackType(const Function& method, const CallbackInfo& callback) {
+    for (const auto& param : method.parameters) {
+        if (param.name != callback.parameter) continue;
+        // const std::function<void(int)>& callback
+        return param.type && param.type->kind == TypeKind::Reference ? param.type->element_type : param.type;
+    }
+    return nullptr;
+}
+
+std::string RustCodeGenerator::callbackClosure(const std::shared_ptr<Type>& type, int context_slot) {
+    std::string closure = "dyn FnMut(";
+    bool first = true;
+    for (size_t i = 0; i < type->parameter_types.size(); ++i) {
+        if (static_cast<int>(i) == context_slot) continue;
+        closure += (first ? "" : ", ") + convertType(type->parameter_types[i]);
+        first = false;
+    }
+    closure += ")";
+    if (type->element_type && type->element_type->kind != TypeKind::Void) {
+        closure += " -> " + convertType(type->element_type);
+    }
+    return closure;
+}
+
+void RustCodeGenerator::generateRetainedMethod(const ClassDecl& class_decl, const Function& method) {
+    stats_.functions_retained++;
+    ScopedValue context(decision_context_, class_decl.name + "::" + method.name);
+    decide("FFI-RETAIN", method.name, "extern \"C\" shim", method.retained_reason);
+
+    auto callbackOf = [&method](const std::string& name) -> const CallbackInfo* {
+        for (const auto& callback : method.callbacks) {
+            if (callback.parameter == name && (callback.std_function || !callback.context.empty())) {
+                return &callback;
+            }
+        }
+        return nullptr;
+    };
+
+    std::vector<std::string> params;
+    std::vector<std::string> args;
+    std::vector<std::string> notes;
+    if (!method.is_static) {
+        params.push_back(method.is_const ? "&self" : "&mut self");
+        args.push_back("self");
+    }
+    for (size_t i = 0; i < method.parameters.size(); ++i) {
+        const auto& param = method.parameters[i];
+        std::string name = param.name.empty() ? "arg" + std::to_string(i) : sanitizeName(param.name);
+        std::string ffi_type = convertFfiType(param.type);
+        std::string rust_type = convertType(param.type);
+
+        // The context pointer of a callback carries the closure
+        const CallbackInfo* context_of = nullptr;
+        for (const auto& callback : method.callbacks) {
+            if (!param.name.empty() && callback.context == param.name) context_of = &callback;
+        }
+        if (context_of) {
+            std::string closure = callbackClosure(callbackType(method, *context_of), context_of->context_slot);
+            std::string callback_name = sanitizeName(context_of->parameter);
+            args.push_back(context_of->stored ? "Box::into_raw(Box::new(" + callback_name + ")).cast()"
+                                              : "(&mut " + callback_name + " as *mut &mut " + closure + ").cast()");
+            continue;
+        }
+
+        if (const CallbackInfo* callback = callbackOf(param.name)) {
+            std::string closure = callbackClosure(callbackType(method, *callback), callback->context_slot);
+            std::string trampoline = sanitizeName(class_decl.name) + "_" + sanitizeName(method.name) + "_" + name +
+                                     "_trampoline";
+            params.push_back(callback->stored ? name + ": Box<" + closure + ">" : "mut " + name + ": &mut " + closure);
+            args.push_back(trampoline);
+            if (callback->std_function) {
+                // The C++ shim takes the trampoline and its context instead of the std::function
+                args.push_back(callback->stored ? "Box::into_raw(Box::new(" + name + ")).cast()"
+                                                : "(&mut " + name + " as *mut &mut " + closure + ").cast()");
+            }
+            notes.push_back(callback->stored
+                                ? "'" + param.name + "' is kept by the C++ after the call: the closure is boxed, "
+                                  "passed through " + trampoline + " and never freed"
+                                : "'" + param.name + "' is called through " + trampoline + " during the call only: "
+                                  "the closure is borrowed and the C++ must not keep it");
+            continue;
+        }
+        if (param.type && param.type->kind == TypeKind::Function && param.type->member_of.empty()) {
+            // No context to carry a closure: a C function with the C calling convention
+            params.push_back(name + ": " + ffi_type);
+            args.push_back(name);
+            notes.push_back("'" + param.name + "' has no context pointer: pass an extern \"C\" fn, "
+                            "which captures nothing");
+            continue;
+        }
+        if (ffi_type == "*const std::ffi::c_char") {
+            // Callers pass a NUL-terminated string: CString::new(s)?.as_c_str()
+            params.push_back(name + ": &std::ffi::CStr");
+            args.push_back(name + ".as_ptr()");
+        } else {
+            params.push_back(name + ": " + rust_type);
+            args.push_back(ffi_type == rust_type ? name
+                           : ffi_type == "std::ffi::c_char" ? name + " as std::ffi::c_char" : name + ".cast()");
+        }
+    }
+
+    std::string sig = memberVisibility(method.name) + "fn " + sanitizeName(method.name) + "(";
+    for (size_t i = 0; i < params.size(); ++i) {
+        sig += (i > 0 ? ", " : "") + params[i];
+    }
+    sig += ")";
+    std::string returned;
+    if (method.return_type && method.return_type->kind != TypeKind::Void) {
+        std::string rust_type = convertType(method.return_type);
+        std::string ffi_type = convertFfiType(method.return_type);
+        sig += " -> " + rust_type;
+        if (ffi_type != rust_type) returned = ffi_type == "std::ffi::c_char" ? " as u8" : ".cast()";
+    }
+
+    std::string call = sanitizeName(class_decl.name) + "_" + sanitizeName(method.name) + "(";
+    for (size_t i = 0; i < args.size(); ++i) {
+        call += (i > 0 ? ", " : "") + args[i];
+    }
+    call += ")";
+
+    writeLine("// Kept in C++: " + method.retained_reason);
+    for (const auto& note : notes) {
+        writeLine("// " + note);
+    }
+    writeLine(sig + " {");
+    indent();
+    writeLine("unsafe { " + call + " }" + returned);
+    dedent();
+    writeLine("}");
+}
+
+void RustCodeGenerator::generateTrampoline(const ClassDecl& class_decl, const Function& method,
+                                           const CallbackInfo& callback) {
+    auto type = callbackType(method, callback);
+    std::string closure = callbackClosure(type, callback.context_slot);
+    std::string holder = callback.stored ? "Box<" + closure + ">" : "&mut " + closure;
+    std::string name = sanitizeName(class_decl.name) + "_" + sanitizeName(method.name) + "_" +
+                       sanitizeName(callback.parameter) + "_trampoline";
+
+    // The context comes first for a std::function, where the C++ shim makes the call
+    std::vector<std::string> params;
+    std::vector<std::string> args;
+    if (callback.std_function) params.push_back("context: *mut std::ffi::c_void");
+    for (size_t i = 0; i < type->parameter_types.size(); ++i) {
+        if (static_cast<int>(i) == callback.context_slot) {
+            params.push_back("context: *mut std::ffi::c_void");
+            continue;
+        }
+        std::string arg = "arg" + std::to_string(i);
+        std::string ffi_type = convertFfiType(type->parameter_types[i]);
+        std::string rust_type = convertType(type->parameter_types[i]);
+        params.push_back(arg + ": " + ffi_type);
+        args.push_back(ffi_type == rust_type ? arg
+                       : ffi_type == "std::ffi::c_char" ? arg + " as " + rust_type : arg + ".cast()");
+    }
+
+    std::string sig = "extern \"C\" fn " + name + "(";
+    for (size_t i = 0; i < params.size(); ++i) {
+        sig += (i > 0 ? ", " : "") + params[i];
+    }
+    sig += ")";
+    std::string returned;
+    if (type->element_type && type->element_type->kind != TypeKind::Void) {
+        std::string ffi_type = convertFfiType(type->element_type);
+        std::string rust_type = convertType(type->element_type);
+        sig += " -> " + ffi_type;
+        if (ffi_type != rust_type) returned = ffi_type == "std::ffi::c_char" ? " as std::ffi::c_char" : ".cast()";
+    }
+
+    std::string call = "callback(";
+    for (size_t i = 0; i < args.size(); ++i) {
+        call += (i > 0 ? ", " : "") + args[i];
+    }
+    call += ")";
+
+    writeLine("");
+    writeLine("/// Calls the closure passed to " + sanitizeName(class_decl.name) + "::" + sanitizeName(method.name) +
+              " as '" + callback.parameter + "'");
+    writeLine(sig + " {");
+    indent();
+    writeLine(callback.stored
+                  ? "// context is the leaked Box of that call: it is never freed, so it is valid here"
+                  : "// context points at the closure borrowed by that call, which is still running");
+    writeLine("let callback = unsafe { &mut *context.cast::<" + holder + ">() };");
+    writeLine(call + returned);
+    dedent();
+    writeLine("}");
+}
+
+void RustCodeGenerator::generateRetainedShims(const ClassDecl& class_decl) {
+    std::vector<const Function*> retained;
+    for (const auto& method : class_decl.methods) {
+        if (!method.retained_reason.empty()) retained.push_back(&method);
+    }
+    if (retained.empty()) return;
+
+    // std::function parameters, which the shims replace with a trampoline and its context
+    auto stdFunction = [](const Function* method, const std::string& name) -> const CallbackInfo* {
+        for (const auto& callback : method->callbacks) {
+            if (callback.parameter == name && callback.std_function) return &callback;
+        }
+        return nullptr;
+    };
+
+    // The C++ side forwards each call to the original method
+    writeLine("");
+    writeLine("// Provided by the C++ build:");
+    for (const Function* method : retained) {
+        std::string params = method->is_static ? "" : class_decl.name + (method->is_const ? " const" : "") + "* self";
+        std::string args;
+        for (size_t i = 0; i < method->parameters.size(); ++i) {
+            const auto& param = method->parameters[i];
+            std::string name = param.name.empty() ? "arg" + std::to_string(i) : param.name;
+            std::string type_name = param.type ? param.type->name : "";
+            std::string arg = name;
+            if (const CallbackInfo* callback = stdFunction(method, param.name)) {
+                // A std::function cannot cross extern "C": a lambda around the trampoline rebuilds it
+                auto type = callbackType(*method, *callback);
+                std::string ret = type->element_type ? type->element_type->name : "void";
+                std::string fn_params = "void*";
+                std::string lambda_params;
+                std::string lambda_args = name + "_context";
+                for (size_t j = 0; j < type->parameter_types.size(); ++j) {
+                    std::string arg_name = "a" + std::to_string(j);
+                    fn_params += ", " + type->parameter_types[j]->name;
+                    lambda_params += (j > 0 ? ", " : "") + type->parameter_types[j]->name + " " + arg_name;
+                    lambda_args += ", " + arg_name;
+                }
+                type_name = ret + " (*" + name + ")(" + fn_params + "), void*";
+                name += "_context";
+                arg = "[" + param.name + ", " + name + "](" + lambda_params + ") { " +
+                      (ret == "void" ? "" : "return ") + param.name + "(" + lambda_args + "); }";
+            } else if (param.type && param.type->kind == TypeKind::Function && type_name.find("(*)") != std::string::npos) {
+                // void (*)(int) handler is spelled void (*handler)(int)
+                type_name.replace(type_name.find("(*)"), 3, "(*" + name + ")");
+                name.clear();
+            }
+            params += (params.empty() ? "" : ", ") + type_name + (name.empty() ? "" : " " + name);
+            args += (i > 0 ? ", " : "") + arg;
+        }
+        std::string target = method->is_static ? class_decl.name + "::" + method->name : "self->" + method->name;
+        std::string ret = method->return_type ? method->return_type->name : "void";
+        writeLine("//   extern \"C\" " + ret + " " + sanitizeName(class_decl.name) + "_" + sanitizeName(method->name) +
+                  "(" + params + ") { " + (ret == "void" ? "" : "return ") + target + "(" + args + "); }");
+    }
+    writeLine("extern \"C\" {");
+    indent();
+    for (const Function* method : retained) {
+        std::vector<std::string> params;
+        if (!method->is_static) {
+            params.push_back(std::string("this: ") + (method->is_const ? "*const " : "*mut ") +
+                             sanitizeName(class_decl.name));
+        }
+        for (size_t i = 0; i < method->parameters.size(); ++i) {
+            const auto& param = method->parameters[i];
+            std::string name = param.name.empty() ? "arg" + std::to_string(i) : sanitizeName(param.name);
+            if (const CallbackInfo* callback = stdFunction(method, param.name)) {
+                auto type = callbackType(*method, *callback);
+                std::string fn = "extern \"C\" fn(*mut std::ffi::c_void";
+                for (const auto& arg_type : type->parameter_types) fn += ", " + convertFfiType(arg_type);
+                fn += ")";
+                if (type->element_type && type->element_type->kind != TypeKind::Void) {
+                    fn += " -> " + convertFfiType(type->element_type);
+                }
+                params.push_back(name + ": " + fn);
+                params.push_back(name + "_context: *mut std::ffi::c_void");
+                continue;
+            }
+            params.push_back(name + ": " + convertFfiType(param.type));
+        }
+        std::string decl = "fn " + sanitizeName(class_decl.name) + "_" + sanitizeName(method->name) + "(";
+        for (size_t i = 0; i < params.size(); ++i) {
+            decl += (i > 0 ? ", " : "") + params[i];
+        }
+        decl += ")";
+        if (method->return_type && method->return_type->kind != TypeKind::Void) {
+            decl += " -> " + convertFfiType(method->return_type);
+        }
+        writeLine(decl + ";");
+    }
+    dedent();
+    writeLine("}");
+
+    // Trampolines: C function pointers that call the Rust closure behind the context
+    for (const Function* method : retained) {
+        for (const auto& callback : method->callbacks) {
+            if (callback.std_function || !callback.context.empty()) {
+                generateTrampoline(class_decl, *method, callback);
+            }
+        }
+    }
+}
+
+std::string RustCodeGenerator::convertAllocations(const Function& func, const std::string& body) {
+    if (func.allocations.empty()) {
+        return body;
+    }
+    report(Diagnostic::Warning,
+           "'" + func.name + "' manages object lifetime by hand (placement new or an explicit destructor "
+           "call); it becomes an unsafe fn whose callers must guarantee the storage is valid, large enough "
+           "and aligned",
+           func.name);
+
+    std::string result = body;
+    size_t cursor = 0;
+    static const std::regex simple_expression(R"(^[\w.]+$)");
+
+    for (const auto& site : func.allocations) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        std::string replacement;
+        if (site.kind == AllocationSiteInfo::DestructorCall) {
+            std::string object = site.buffer == "this" ? "self"
+                                 : site.through_pointer ? site.buffer : "&mut " + site.buffer;
+            replacement = "unsafe { std::ptr::drop_in_place(" + object + ") }";
+        } else if (site.is_array) {
+            report(Diagnostic::Warning,
+                   "array placement new '" + site.source_text + "' is not translated; write its elements "
+                   "with std::ptr::write in a loop",
+                   func.name);
+            cursor = pos + site.source_text.size();
+            continue;
+        } else {
+            std::string type = convertType(site.type);
+            std::string value;
+            bool is_scalar = site.type && (site.type->kind == TypeKind::Integer ||
+                                           site.type->kind == TypeKind::Float ||
+                                           site.type->kind == TypeKind::Bool ||
+                                           site.type->kind == TypeKind::Pointer);
+            if (is_scalar) {
+                value = site.arguments.empty() ? "Default::default()" : site.arguments[0];
+            } else {
+                value = type + "::new(";
+                for (size_t i = 0; i < site.arguments.size(); ++i) {
+                    value += (i > 0 ? ", " : "") + site.arguments[i];
+                }
+                value += ")";
+            }
+            std::string buffer = std::regex_match(site.buffer, simple_expression) ? site.buffer
+                                                                                   : "(" + site.buffer + ")";
+            // Placement new evaluates to the constructed object's address
+            replacement = "{ let slot = " + buffer + " as *mut " + type + "; unsafe { slot.write(" + value +
+                          ") }; slot }";
+        }
+
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertContainerAllocator(const std::shared_ptr<Type>& type,
+                                                         const std::string& container) {
+    // Each spelling is converted wherever it appears; say it once
+    auto warn = [this, &type](const std::string& message) {
+        bool reported = std::any_of(diagnostics_.begin(), diagnostics_.end(),
+                                    [&](const Diagnostic& d) { return d.message == message; });
+        if (!reported) report(Diagnostic::Warning, message, type->name);
+    };
+
+    if (type->allocator->name == "std::pmr::polymorphic_allocator") {
+        warn("'" + type->name + "' takes its memory resource at run time; Rust collections have no "
+             "equivalent, so it becomes '" + container + "' on the global allocator");
+        return container;
+    }
+
+    // std's HashMap and HashSet take no allocator parameter, even on nightly
+    bool has_parameter = type->kind != TypeKind::StdUnorderedMap && type->kind != TypeKind::StdUnorderedSet;
+    if (!allocator_api_ || !has_parameter) {
+        warn("allocator '" + type->allocator->name + "' of '" + type->name + "' is dropped; '" + container +
+             "' uses the global allocator" + (has_parameter ? " (--allocator-api keeps it)" : ""));
+        return container;
+    }
+
+    uses_allocator_api_ = true;
+    return container.substr(0, container.size() - 1) + ", " + convertType(type->allocator) + ">";
+}
+
+void RustCodeGenerator::generateClassAllocator(const ClassDecl& class_decl) {
+    const Function* allocate = nullptr;
+    const Function* deallocate = nullptr;
+    for (const auto& method : class_decl.methods) {
+        if (!allocate && method.name.compare(0, 12, "operator new") == 0) allocate = &method;
+        if (!deallocate && method.name.compare(0, 15, "operator delete") == 0) deallocate = &method;
+    }
+
+    std::string name = sanitizeName(class_decl.name);
+    if (!allocator_api_) {
+        report(Diagnostic::Warning,
+               "'" + class_decl.name + "' defines its own operator new/delete; Box<" + name +
+               "> uses the global allocator (--allocator-api generates " + name + "_allocator)",
+               class_decl.name);
+        return;
+    }
+
+    uses_allocator_api_ = true;
+    writeLine("");
+    writeLine("/// " + class_decl.name + "::operator new/delete; allocate with Box::new_in(value, " + name +
+              "_allocator)");
+    writeLine("pub struct " + name + "_allocator;");
+    writeLine("");
+    writeLine("unsafe impl std::alloc::Allocator for " + name + "_allocator {");
+    indent();
+
+    countFunction(false);
+    writeLine("fn allocate(&self, layout: std::alloc::Layout) -> "
+              "Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {");
+    indent();
+    if (allocate) {
+        writeLine("// Converted from C++ " + allocate->name);
+        if (!allocate->parameters.empty() && !allocate->parameters[0].name.empty()) {
+            writeLine("let " + sanitizeName(allocate->parameters[0].name) + " = layout.size();");
+        }
+        if (!allocate->body.empty()) {
+            writeLine(translateBody(*allocate));
+        }
+    }
+    writeLine("todo!()");
+    dedent();
+    writeLine("}");
+    writeLine("");
+
+    countFunction(false);
+    writeLine("unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {");
+    indent();
+    if (deallocate) {
+        writeLine("// Converted from C++ " + deallocate->name);
+        if (!deallocate->parameters.empty() && !deallocate->parameters[0].name.empty()) {
+            writeLine("let " + sanitizeName(deallocate->parameters[0].name) + " = ptr.as_ptr();");
+        }
+        if (!deallocate->body.empty()) {
+            writeLine(translateBody(*deallocate));
+        }
+    }
+    writeLine("todo!()");
+    dedent();
+    writeLine("}");
+
+    dedent();
+    writeLine("}");
+}
+
+std::string RustCodeGenerator::convertMoveSemantics(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& move : func.moves) {
+        // Moves in the member initializer list are not part of the body
+        if (move.in_initializer) {
+            continue;
+        }
+
+        // Reference parameters are already `&mut T` in Rust
+        std::string place = "&mut " + move.expression;
+        for (const auto& param : func.parameters) {
+            if (param.name == move.expression && param.type &&
+                param.type->kind == TypeKind::Reference) {
+                place = move.expression;
+            }
+        }
+
+        size_t pos = result.find(move.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        std::string replacement;
+        if (move.kind == MoveInfo::Exchange) {
+            replacement = "std::mem::replace(" + place + ", " + move.new_value + ")";
+            decide("MOVE-REPLACE", move.source_text, replacement, "std::exchange leaves the new value behind");
+        } else if (move.used_after_move || move.moves_out_of_borrow) {
+            // Source relies on the moved-from state (or moves out of a borrow):
+            // leave a default value behind
+            replacement = "std::mem::take(" + place + ")";
+            decide("MOVE-TAKE", move.source_text, replacement,
+                   move.used_after_move ? "'" + move.expression + "' is used after the move"
+                                        : "'" + move.expression + "' is moved out of a borrow");
+        } else {
+            // Plain by-value move
+            replacement = move.expression;
+            decide("MOVE-PLAIN", move.source_text, replacement, "'" + move.expression + "' is not used again");
+        }
+
+        result.replace(pos, move.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertCopySemantics(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (size_t i = 0; i < func.copies.size();) {
+        // Arguments of one call share its text, at increasing offsets
+        size_t end = i + 1;
+        while (end < func.copies.size() && func.copies[end].source_text == func.copies[i].source_text &&
+               func.copies[end].expression_offset > func.copies[end - 1].expression_offset) {
+            end++;
+        }
+        const std::string& source_text = func.copies[i].source_text;
+        size_t pos = result.find(source_text, cursor);
+        if (pos == std::string::npos) {
+            i = end;
+            continue;
+        }
+
+        // Last argument first, so earlier offsets stay valid
+        std::string rewritten = source_text;
+        for (size_t k = end; k-- > i;) {
+            const CopyInfo& copy = func.copies[k];
+            if (copy.to_owned) {
+                rewritten.insert(copy.expression_offset + copy.expression.size(), ".to_string()");
+                stats_.clones_inserted++;
+                decide("STR-OWN", copy.source_text, copy.expression + ".to_string()",
+                       "'" + copy.expression + "' is a &str copied into an owned String");
+            } else if (copy.needs_borrow) {
+                rewritten.insert(copy.expression_offset, "&");
+                decide("STR-COERCE", copy.source_text, "&" + copy.expression,
+                       "the String is borrowed for a &str parameter (deref coercion), not copied");
+            } else if (copy.needs_clone) {
+                rewritten.insert(copy.expression_offset + copy.expression.size(), ".clone()");
+                stats_.clones_inserted++;
+                decide("COPY-CLONE", copy.source_text, copy.expression + ".clone()",
+                       "'" + copy.expression + "' is copied by value and its type is not Copy");
+            } else if (copy.needs_deref) {
+                rewritten.insert(copy.expression_offset, "*");
+                decide("COPY-DEREF", copy.source_text, "*" + copy.expression,
+                       "a Copy value read through a reference parameter");
+            }
+        }
+
+        result.replace(pos, source_text.size(), rewritten);
+        cursor = pos + rewritten.size();
+        i = end;
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertGuards(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& guard : func.guards) {
+        size_t pos = result.find(guard.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        // The guard must stay bound to a named variable until the end of the
+        // enclosing block: `let _ = ...` would drop it immediately
+        std::string binding = guard.guard_var_name.empty() ? "_guard" : guard.guard_var_name;
+        if (!guard.unlocked_early && binding[0] != '_') {
+            binding = "_" + binding;
+        }
+
+        // A mutex turned into a RefCell (--runtime-trace) is borrowed instead
+        auto refcell = [&guard](const std::string& mutex) {
+            return std::find(guard.refcell_mutexes.begin(), guard.refcell_mutexes.end(), mutex) !=
+                   guard.refcell_mutexes.end();
+        };
+
+        std::stringstream ss;
+        switch (guard.kind) {
+            case GuardInfo::LockGuard:
+            case GuardInfo::UniqueLock: {
+                std::string mutex = guard.mutex_names.empty() ? "mutex" : guard.mutex_names[0];
+                std::string acquire = guard.exclusive_on_shared_mutex ? ".write()" : ".lock()";
+                ss << "let " << (guard.kind == GuardInfo::UniqueLock ? "mut " : "") << binding << " = " << mutex
+                   << (refcell(mutex) ? ".borrow_mut();" : acquire + ".unwrap();");
+                break;
+            }
+
+            case GuardInfo::SharedLock: {
+                std::string mutex = guard.mutex_names.empty() ? "rwlock" : guard.mutex_names[0];
+                ss << "let " << binding << " = " << mutex << (refcell(mutex) ? ".borrow();" : ".read().unwrap();");
+                break;
+            }
+
+            case GuardInfo::ScopedLock:
+                // One guard per mutex, acquired (and released in reverse) in order
+                for (size_t i = 0; i < guard.mutex_names.size(); ++i) {
+                    if (i > 0) ss << " ";
+                    ss << "let " << binding;
+                    if (guard.mutex_names.size() > 1) ss << "_" << i;
+                    ss << " = " << guard.mutex_names[i]
+                       << (refcell(guard.mutex_names[i]) ? ".borrow_mut();" : ".lock().unwrap();");
+                }
+                break;
+
+            case GuardInfo::ScopeExit:
+                ss << "let " << binding << " = scopeguard::guard((), |_| { "
+                   << guard.cleanup_body << " });";
+                break;
+        }
+
+        std::string replacement = ss.str();
+        result.replace(pos, guard.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+
+        // Early release: unlock() drops the guard, dismiss() defuses the scope guard
+        if (guard.unlocked_early) {
+            std::regex release_pattern("\\b" + guard.guard_var_name + R"(\.(unlock|dismiss|release)\s*\(\s*\))");
+            std::string release = guard.kind == GuardInfo::ScopeExit
+                ? "scopeguard::ScopeGuard::into_inner(" + guard.guard_var_name + ")"
+                : "drop(" + guard.guard_var_name + ")";
+            result = std::regex_replace(result, release_pattern, release);
+        }
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertLayoutQueries(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& query : func.layout_queries) {
+        size_t pos = result.find(query.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        // sizeof(T&) is the size of T
+        std::shared_ptr<Type> type = query.type;
+        if (type && (type->kind == TypeKind::Reference ||
+                     type->kind == TypeKind::RValueReference)) {
+            type = type->element_type;
+        }
+
+        std::string replacement;
+        switch (query.kind) {
+            case LayoutQueryInfo::SizeOf:
+                replacement = type ? "std::mem::size_of::<" + convertType(type) + ">()"
+                                   : "std::mem::size_of_val(&" + query.expression + ")";
+                break;
+
+            case LayoutQueryInfo::AlignOf:
+                replacement = type ? "std::mem::align_of::<" + convertType(type) + ">()"
+                                   : "std::mem::align_of_val(&" + query.expression + ")";
+                break;
+
+            case LayoutQueryInfo::ArrayLength:
+                replacement = query.expression + ".len()";
+                break;
+
+            case LayoutQueryInfo::OffsetOf:
+                replacement = "std::mem::offset_of!(" + convertType(type) + ", " +
+                              query.member_name + ")";
+                if (safety_checks_) {
+                    report(Diagnostic::Warning,
+                           "offsetof(" + (type ? type->name : "?") + ", " + query.member_name +
+                           ") translated to offset_of!; pointer arithmetic using the offset "
+                           "requires unsafe code and must be ported by hand",
+                           func.name);
+                }
+                break;
+        }
+
+        result.replace(pos, query.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertBindings(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& binding : func.bindings) {
+        size_t pos = result.find(binding.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        // Bindings holding `&mut` are assigned through a dereference
+        bool mutable_ref = binding.by_reference && !binding.is_const &&
+                           binding.kind != BindingInfo::Tie;
+        bool iterate_by_ref = binding.kind == BindingInfo::RangeFor && !binding.by_reference &&
+                              binding.initializer_is_lvalue &&
+                              std::find(binding.reassigned.begin(), binding.reassigned.end(),
+                                        true) == binding.reassigned.end();
+
+        std::vector<std::string> names;
+        for (size_t i = 0; i < binding.names.size(); ++i) {
+            bool is_mut = binding.kind != BindingInfo::Tie && !binding.by_reference &&
+                          !iterate_by_ref && i < binding.reassigned.size() &&
+                          binding.reassigned[i];
+            std::string name = (is_mut ? "mut " : "") + sanitizeName(binding.names[i]);
+            if (!binding.struct_name.empty() && binding.field_names[i] != binding.names[i]) {
+                name = sanitizeName(binding.field_names[i]) + ": " + name;
+            }
+            names.push_back(name);
+        }
+
+        std::stringstream pattern;
+        if (binding.struct_name.empty()) {
+            pattern << "(";
+            for (size_t i = 0; i < names.size(); ++i) {
+                pattern << (i > 0 ? ", " : "") << names[i];
+            }
+            pattern << (names.size() == 1 ? ",)" : ")");
+        } else {
+            pattern << sanitizeName(binding.struct_name) << " {";
+            for (size_t i = 0; i < names.size(); ++i) {
+                pattern << (i > 0 ? ", " : " ") << names[i];
+            }
+            pattern << " }";
+        }
+
+        std::string source = binding.initializer;
+        if (binding.by_reference) {
+            source = (binding.is_const ? "&" : "&mut ") + source;
+        } else if (iterate_by_ref) {
+            source = "&" + source;  // Copies are only read
+        } else if (binding.needs_clone ||
+                   (binding.kind == BindingInfo::RangeFor && binding.initializer_is_lvalue)) {
+            source += ".clone()";
+        }
+
+        std::string replacement;
+        switch (binding.kind) {
+            case BindingInfo::Declaration:
+                replacement = "let " + pattern.str() + " = " + source + ";";
+                break;
+            case BindingInfo::Tie:
+                replacement = pattern.str() + " = " + source + ";";
+                break;
+            case BindingInfo::RangeFor:
+                replacement = "for " + pattern.str() + " in " + source;
+                break;
+        }
+
+        result.replace(pos, binding.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+
+        if (!mutable_ref) {
+            continue;
+        }
+
+        // Scope of the bindings: the loop body, or the rest of the enclosing block
+        size_t scope_start = cursor;
+        if (binding.kind == BindingInfo::RangeFor) {
+            scope_start = result.find('{', cursor) + 1;
+        }
+        size_t scope_end = result.size();
+        int depth = 0;
+        for (size_t i = scope_start; i < result.size(); ++i) {
+            if (result[i] == '{') {
+                depth++;
+            } else if (result[i] == '}' && depth-- == 0) {
+                scope_end = i;
+                break;
+            }
+        }
+
+        std::string scope = result.substr(scope_start, scope_end - scope_start);
+        for (size_t i = 0; i < binding.names.size(); ++i) {
+            if (i >= binding.reassigned.size() || !binding.reassigned[i]) continue;
+            std::regex assign_pattern("(^|[^\\w.>*])" + binding.names[i] +
+                                      R"((\s*(?:[-+*/%&|^]|<<|>>)?=(?!=)))");
+            scope = std::regex_replace(scope, assign_pattern, "$1*" + binding.names[i] + "$2");
+        }
+        result.replace(scope_start, scope_end - scope_start, scope);
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertFormatting(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& call : func.formats) {
+        size_t pos = result.find(call.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+        // Arguments are kept verbatim, so nested calls are found after this one
+        cursor = pos + 1;
+
+        if (!call.is_literal) {
+            report(Diagnostic::Warning,
+                   "format string of '" + call.source_text.substr(0, call.source_text.find('(')) +
+                   "' is not a string literal; Rust formatting macros need a literal",
+                   func.name);
+            continue;
+        }
+
+        std::string error;
+        std::string format = convertFormatString(call.format_string, error);
+        if (!error.empty()) {
+            report(Diagnostic::Warning, "format string \"" + call.format_string + "\": " + error, func.name);
+            continue;
+        }
+
+        // fmt::arg("name", value) and "name"_a = value become named arguments
+        std::string args;
+        std::regex arg_pattern(R"re(fmt::arg\s*\(\s*"(\w+)"\s*,\s*([\s\S]*)\))re");
+        std::regex udl_pattern(R"re("(\w+)"_a\s*=\s*([\s\S]*))re");
+        for (const auto& arg : call.arguments) {
+            std::smatch named;
+            if (std::regex_match(arg, named, arg_pattern) || std::regex_match(arg, named, udl_pattern)) {
+                args += ", " + named[1].str() + " = " + named[2].str();
+            } else {
+                args += ", " + arg;
+            }
+        }
+
+        // A trailing newline selects the ...ln! form
+        bool newline = call.kind == FormatInfo::Println;
+        if (call.kind == FormatInfo::Print && format.size() >= 2 &&
+            format.compare(format.size() - 2, 2, "\\n") == 0) {
+            format.erase(format.size() - 2);
+            newline = true;
+        }
+
+        std::string replacement;
+        std::string macro_args = "\"" + format + "\"" + args;
+        if (call.kind == FormatInfo::Format) {
+            replacement = "format!(" + macro_args + ")";
+        } else if (call.kind == FormatInfo::FormatTo) {
+            replacement = call.stream + ".push_str(&format!(" + macro_args + "))";
+        } else if (call.stream.empty() || call.stream == "stdout") {
+            replacement = std::string(newline ? "println!(" : "print!(") + macro_args + ")";
+        } else if (call.stream == "stderr") {
+            replacement = std::string(newline ? "eprintln!(" : "eprint!(") + macro_args + ")";
+        } else {
+            // FILE* or std::ostream: any io::Write (std::io::Write must be in scope)
+            replacement = std::string(newline ? "writeln!(" : "write!(") + call.stream + ", " +
+                          macro_args + ").unwrap()";
+        }
+
+        result.replace(pos, call.source_text.size(), replacement);
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertFormatString(const std::string& format, std::string& error) {
+    std::string result;
+    size_t i = 0;
+
+    while (i < format.size()) {
+        char c = format[i];
+        if ((c == '{' || c == '}') && i + 1 < format.size() && format[i + 1] == c) {
+            result += format.substr(i, 2);  // {{ and }} escape the same way
+            i += 2;
+            continue;
+        }
+        if (c == '}') {
+            error = "unmatched '}'";
+            return "";
+        }
+        if (c != '{') {
+            result += c;
+            ++i;
+            continue;
+        }
+
+        size_t close = format.find('}', i);
+        size_t nested = format.find('{', i + 1);
+        if (close == std::string::npos) {
+            error = "unmatched '{'";
+            return "";
+        }
+        if (nested < close) {
+            error = "dynamic width or precision has no direct Rust equivalent";
+            return "";
+        }
+
+        std::string field = format.substr(i + 1, close - i - 1);
+        size_t colon = field.find(':');
+        std::string arg_id = field.substr(0, colon);
+        result += "{" + arg_id;
+
+        if (colon != std::string::npos) {
+            // [[fill]align][sign][#][0][width][.precision][L][type]
+            std::smatch spec;
+            std::string spec_text = field.substr(colon + 1);
+            std::regex spec_pattern(R"(((?:.?[<>^])?)([+\- ]?)(#?0?\d*)(\.\d+)?(L?)([a-zA-Z?]?))");
+            if (!std::regex_match(spec_text, spec, spec_pattern)) {
+                error = "unsupported format specification '" + spec_text + "'";
+                return "";
+            }
+
+            std::string sign = spec[2].str();
+            if (sign == " ") {
+                error = "the ' ' sign option has no Rust equivalent";
+                return "";
+            }
+            if (sign == "-") sign.clear();  // Default in both languages
+
+            std::string precision = spec[4].str();
+            std::string type = spec[6].str();
+            if (type == "a" || type == "A") {
+                error = "hexadecimal floating point ('" + type + "') has no Rust equivalent";
+                return "";
+            }
+            if ((type == "f" || type == "F") && precision.empty()) {
+                precision = ".6";  // fmt's fixed notation defaults to six digits
+            }
+            if (type == "B") type = "b";
+            if (type != "x" && type != "X" && type != "o" && type != "b" &&
+                type != "e" && type != "E" && type != "p" && type != "?") {
+                type.clear();  // d, s, c, f, g: Display
+            }
+
+            std::string rust_spec = spec[1].str() + sign + spec[3].str() + precision + type;
+            if (!rust_spec.empty()) {
+                result += ":" + rust_spec;
+            }
+        }
+
+        result += "}";
+        i = close + 1;
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertLogging(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& log : func.logs) {
+        size_t pos = result.find(log.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+        // Arguments are kept verbatim, so nested calls are found after this one
+        cursor = pos + 1;
+
+        if (!log.unsupported.empty()) {
+            report(Diagnostic::Warning,
+                   "stream manipulator '" + log.unsupported + "' in " + log.macro + "(...) has no log! equivalent",
+                   func.name);
+            continue;
+        }
+        if (!log.is_literal) {
+            report(Diagnostic::Warning,
+                   "format string of '" + log.macro + "' is not a string literal; log! macros need a literal",
+                   func.name);
+            continue;
+        }
+
+        std::string error;
+        std::string format = log.format_string;
+        if (log.style == LogInfo::Printf) {
+            format = convertPrintfFormat(format, error);
+        } else if (log.style == LogInfo::Fmt) {
+            format = convertFormatString(format, error);
+        }
+        if (!error.empty()) {
+            report(Diagnostic::Warning, "format string \"" + log.format_string + "\": " + error, func.name);
+            continue;
+        }
+
+        std::string args;
+        for (const auto& arg : log.arguments) {
+            // printf wrappers take C strings; a String displays as is
+            std::string value = arg;
+            if (log.style == LogInfo::Printf && value.size() > 8 &&
+                value.compare(value.size() - 8, 8, ".c_str()") == 0) {
+                value.erase(value.size() - 8);
+            }
+            args += ", " + value;
+        }
+
+        std::string replacement = "log::" + log.level + "!(\"" + format + "\"" + args + ");";
+        if (log.fatal) {
+            replacement += " std::process::abort();";
+        }
+
+        result.replace(pos, log.source_text.size(), replacement);
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertPrintfFormat(const std::string& format, std::string& error) {
+    std::regex spec_pattern(R"(%([-+ #0]*)(\d+|\*)?(?:\.(\d+|\*))?(?:hh|h|ll|l|z|j|t|L)?([diouxXeEfFgGcspaA%]))");
+    std::string result;
+    size_t last = 0;
+
+    for (auto it = std::sregex_iterator(format.begin(), format.end(), spec_pattern);
+         it != std::sregex_iterator(); ++it) {
+        const std::smatch& spec = *it;
+        for (char c : format.substr(last, spec.position(0) - last)) {
+            result += c;
+            if (c == '{' || c == '}') result += c;
+        }
+        last = spec.position(0) + spec.length(0);
+
+        std::string flags = spec[1].str();
+        std::string width = spec[2].str();
+        std::string precision = spec[3].str();
+        std::string type = spec[4].str();
+
+        if (type == "%") {
+            result += "%";
+            continue;
+        }
+        if (width == "*" || precision == "*") {
+            error = "dynamic width or precision has no direct Rust equivalent";
+            return "";
+        }
+        if (flags.find(' ') != std::string::npos) {
+            error = "the ' ' flag has no Rust equivalent";
+            return "";
+        }
+        if (type == "a" || type == "A") {
+            error = "hexadecimal floating point ('%" + type + "') has no Rust equivalent";
+            return "";
+        }
+
+        // [align][sign][#][0][width][.precision][type]
+        std::string rust_spec;
+        bool left = flags.find('-') != std::string::npos;
+        if (left) rust_spec += "<";
+        if (flags.find('+') != std::string::npos) rust_spec += "+";
+        if (flags.find('#') != std::string::npos) rust_spec += "#";
+        if (!left && flags.find('0') != std::string::npos) rust_spec += "0";
+        rust_spec += width;
+
+        if ((type == "f" || type == "F") && precision.empty()) {
+            precision = "6";  // printf's default for %f
+        }
+        if (!precision.empty()) rust_spec += "." + precision;
+        if (type == "x" || type == "X" || type == "o" || type == "e" || type == "E" || type == "p") {
+            rust_spec += type;
+        }
+
+        result += rust_spec.empty() ? "{}" : "{:" + rust_spec + "}";
+    }
+
+    for (char c : format.substr(last)) {
+        result += c;
+        if (c == '{' || c == '}') result += c;
+    }
+    return result;
+}
+
+std::string RustCodeGenerator::convertStatusSites(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    // The out-parameter is a local holding the Ok value
+    std::string out = sanitizeName(func.status_out_param);
+    std::string ok_value = func.status_out_param.empty() ? "()" : out;
+
+    for (const auto& site : func.status_sites) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        std::string replacement;
+        switch (site.kind) {
+            case StatusSiteInfo::ReturnSuccess:
+                replacement = "return Ok(" + ok_value + ");";
+                break;
+            case StatusSiteInfo::ReturnError:
+                replacement = "return Err(" + sanitizeName(func.status_enum) + "::" +
+                              enumeratorName(site.value) + ");";
+                break;
+            case StatusSiteInfo::Propagate:
+                replacement = site.expression + "?;";
+                break;
+            case StatusSiteInfo::Check:
+                replacement = site.expression + (site.is_success_check ? ".is_ok()" : ".is_err()");
+                break;
+        }
+
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    if (func.status_out_param.empty()) {
+        return result;
+    }
+
+    // *out = value / out->field: the local is assigned directly
+    const auto& param = func.parameters.back();
+    result = std::regex_replace(result, std::regex("\\*" + func.status_out_param + "\\b"), out);
+    result = std::regex_replace(result, std::regex("\\b" + func.status_out_param + "\\s*->"), out + ".");
+    return "let mut " + out + ": " + convertType(param.type->element_type) + " = Default::default();\n" +
+           result;
+}
+
+std::string RustCodeGenerator::convertTuples(const Function& func, const std::string& body) {
+    std::string result = body;
+
+    // std::make_pair(a, b) / std::make_tuple(a, b, c) -> (a, b) / (a, b, c)
+    std::regex make_pattern(R"(std::make_(pair|tuple)\s*\()");
+    std::smatch match;
+    size_t cursor = 0;
+    while (std::regex_search(result.cbegin() + cursor, result.cend(), match, make_pattern)) {
+        size_t pos = cursor + match.position(0);
+        size_t open = pos + match.length(0) - 1;
+
+        int depth = 0;
+        size_t commas = 0;
+        size_t close = std::string::npos;
+        for (size_t i = open; i < result.size(); ++i) {
+            char c = result[i];
+            if (c == '(' || c == '[' || c == '{') depth++;
+            else if (c == ')' || c == ']' || c == '}') depth--;
+            else if (c == ',' && depth == 1) commas++;
+            if (depth == 0) {
+                close = i;
+                break;
+            }
+        }
+        if (close == std::string::npos) {
+            break;
+        }
+
+        // A one-element tuple needs a trailing comma
+        if (commas == 0 && close > open + 1) {
+            result.insert(close, ",");
+        }
+        result.erase(pos, open - pos);
+        cursor = pos + 1;
+    }
+
+    // std::get<N>(t) -> t.N
+    result = std::regex_replace(
+        result, std::regex(R"(std::get\s*<\s*(\d+)\s*>\s*\(\s*([a-zA-Z_][\w.]*)\s*\))"), "$2.$1");
+
+    // p.first / p.second -> p.0 / p.1
+    for (const auto& name : func.tuple_variables) {
+        result = std::regex_replace(result, std::regex("\\b" + name + R"(\s*\.\s*first\b)"), name + ".0");
+        result = std::regex_replace(result, std::regex("\\b" + name + R"(\s*\.\s*second\b)"), name + ".1");
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertTemplateParametersToRust(const std::vector<TemplateParameter>& params,
+                                                               bool with_defaults) {
+    if (params.empty()) {
+        return "";
+    }
+
+    std::stringstream ss;
+    ss << "<";
+
+    bool first = true;
+    for (const auto& param : params) {
+        if (param.kind == TemplateParameter::NonType && constGenericType(param).empty()) {
+            continue;  // Reported by checkConstGenerics
+        }
+        if (!first) ss << ", ";
+        first = false;
+
+        if (param.kind == TemplateParameter::Type) {
+            ss << param.name;
+
+            // Add trait bounds if constraints exist
+            if (!param.constraints.empty()) {
+                ss << ": ";
+                for (size_t i = 0; i < param.constraints.size(); ++i) {
+                    if (i > 0) ss << " + ";
+                    ss << param.constraints[i];
+                }
+            }
+        } else if (param.kind == TemplateParameter::NonType) {
+            // Rust const generics
+            ss << "const " << param.name << ": " << constGenericType(param);
+            if (with_defaults && !param.default_value.empty()) {
+                ss << " = " << param.default_value;
+            }
+        }
+    }
+
+    ss << ">";
+    return first ? "" : ss.str();
+}
+
+std::string RustCodeGenerator::constGenericType(const TemplateParameter& param) {
+    const auto& type = param.param_type;
+    if (!type || type->name == "auto" || param.is_array_length) {
+        return "usize";  // Array lengths must be usize
+    }
+    if (type->kind == TypeKind::Integer || type->kind == TypeKind::Bool) {
+        return convertType(type);
+    }
+    return "";  // Floats, pointers, enums and classes are not const generic types
+}
+
+void RustCodeGenerator::checkConstGenerics(const ClassDecl& class_decl) {
+    std::vector<std::string> const_params;
+    for (const auto& param : class_decl.template_parameters) {
+        if (param.kind != TemplateParameter::NonType) continue;
+        if (constGenericType(param).empty()) {
+            report(Diagnostic::Warning,
+                   "non-type template parameter '" + param.name + "' of type '" +
+                   (param.param_type ? param.param_type->name : "") +
+                   "' has no Rust const generic equivalent; use an integer, bool or char",
+                   class_decl.name);
+        }
+        const_params.push_back(param.name);
+    }
+
+    // Stable Rust only allows a const parameter on its own as an array length
+    for (const auto& field : class_decl.fields) {
+        for (auto type = field.type; type && type->kind == TypeKind::Array; type = type->element_type) {
+            for (const auto& name : const_params) {
+                if (type->array_size != name &&
+                    std::regex_search(type->array_size, std::regex("\\b" + name + "\\b"))) {
+                    report(Diagnostic::Warning,
+                           "array length '" + type->array_size + "' of field '" + field.name +
+                           "' computes with const parameter '" + name +
+                           "'; this requires the unstable generic_const_exprs feature",
+                           class_decl.name);
+                }
+            }
+        }
+    }
+}
+
+/**
+ * A method is emitted once per instantiation when every if constexpr chain
+ * in it resolves for every instantiation
+ */
+bool RustCodeGenerator::specializedPerInstantiation(const ClassDecl& class_decl, const Function& method) {
+    if (!class_decl.is_template || class_decl.instantiations.empty() || method.constexpr_ifs.empty()) {
+        return false;
+    }
+    for (const auto& chain : method.constexpr_ifs) {
+        if (std::count(chain.selected.begin(), chain.selected.end(), ConstexprIfInfo::UNRESOLVED) > 0) {
+            return false;
+        }
+    }
+    return true;
+}
+
+/**
+ * Copy of a method with each resolved chain replaced by the branch taken
+ */
+Function RustCodeGenerator::selectConstexprBranches(const Function& method, size_t instantiation) {
+    Function result = method;
+    result.constexpr_ifs.clear();
+    size_t cursor = 0;
+
+    for (const auto& chain : method.constexpr_ifs) {
+        if (instantiation >= chain.selected.size() ||
+            chain.selected[instantiation] == ConstexprIfInfo::UNRESOLVED) {
+            result.constexpr_ifs.push_back(chain);
+            continue;
+        }
+        size_t pos = result.body.find(chain.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        int selected = chain.selected[instantiation];
+        std::string replacement = selected >= 0 ? "{" + chain.branches[selected].body + "}" : "";
+        result.body.replace(pos, chain.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+    return result;
+}
+
+void RustCodeGenerator::checkConstexprIfs(const ClassDecl& class_decl, const Function& method) {
+    for (const auto& chain : method.constexpr_ifs) {
+        bool resolved = !chain.selected.empty() &&
+                        std::count(chain.selected.begin(), chain.selected.end(), ConstexprIfInfo::UNRESOLVED) == 0;
+        // Resolved chains of a non-template class are pruned in place
+        if (resolved && !class_decl.is_template) {
+            continue;
+        }
+        report(Diagnostic::Warning,
+               "'if constexpr (" + chain.branches.front().condition + ")' is translated as a runtime if" +
+               (chain.unresolved_reason.empty() ? "" : " (" + chain.unresolved_reason + ")") +
+               "; it only compiles if every branch type-checks for every instantiation",
+               class_decl.name + "::" + method.name);
+    }
+}
+
+/**
+ * impl buffer<i32, 4> { ... } with the branches taken by that instantiation
+ */
+void RustCodeGenerator::generateInstantiationImpls(const ClassDecl& class_decl, bool after_impl) {
+    std::vector<const Function*> methods;
+    for (const auto& method : class_decl.methods) {
+        if (specializedPerInstantiation(class_decl, method)) {
+            methods.push_back(&method);
+        }
+    }
+    if (methods.empty()) {
+        return;
+    }
+
+    for (size_t i = 0; i < class_decl.instantiations.size(); ++i) {
+        const auto& instantiation = class_decl.instantiations[i];
+        std::vector<std::string> args;
+        for (size_t k = 0; k < instantiation.arguments.size(); ++k) {
+            const auto& param = class_decl.template_parameters[k];
+            if (param.kind == TemplateParameter::NonType && constGenericType(param).empty()) {
+                continue;  // Not a Rust generic parameter
+            }
+            args.push_back(instantiation.types[k] ? convertType(instantiation.types[k])
+                                                  : instantiation.arguments[k]);
+        }
+
+        std::string impl_decl = "impl " + sanitizeName(class_decl.name);
+        if (!args.empty()) {
+            impl_decl += "<";
+            for (size_t k = 0; k < args.size(); ++k) {
+                impl_decl += (k > 0 ? ", " : "") + args[k];
+            }
+            impl_decl += ">";
+        }
+
+        if (i > 0 || after_impl) {
+            writeLine("");
+        }
+        writeLine(impl_decl + " {");
+        indent();
+        for (const auto* method : methods) {
+            generateFunction(selectConstexprBranches(*method, i));
+            writeLine("");
+        }
+        dedent();
+        writeLine("}");
+    }
+}
+
+std::string RustCodeGenerator::convertTemplateArgsToRust(const std::vector<TemplateParameter>& params) {
+    if (params.empty()) {
+        return "";
+    }
+
+    std::stringstream ss;
+    ss << "<";
+
+    bool first = true;
+    for (const auto& param : params) {
+        if (param.kind == TemplateParameter::NonType && constGenericType(param).empty()) {
+            continue;  // Reported by checkConstGenerics
+        }
+        if (!first) ss << ", ";
+        first = false;
+
+        if (param.kind == TemplateParameter::Type) {
+            ss << param.name;
+        } else if (param.kind == TemplateParameter::NonType) {
+            ss << param.name;
+        }
+    }
 
     ss << ">";
-    return ss.str();
+    return first ? "" : ss.str();
 }
 
 void RustCodeGenerator::generateThreadingCode(const Function& func) {
@@ -792,7 +4238,7 @@ void RustCodeGenerator::generateAsyncFunction(const Function& func) {
     std::stringstream sig;
 
     // Generate async function signature
-    sig << "pub async fn " << sanitizeName(func.name);
+    sig << memberVisibility(func.name) << "async fn " << sanitizeName(func.name);
 
     // Add generic parameters for template functions
     if (func.is_template && !func.template_parameters.empty()) {
@@ -834,6 +4280,9 @@ void RustCodeGenerator::generateAsyncFunction(const Function& func) {
     writeLine(sig.str() + " {");
     indent();
 
+    countFunction(func.coroutine_info.is_coroutine || !func.async_tasks.empty() ||
+                  !func.futures.empty() || !func.body.empty());
+
     // Generate coroutine body
     if (func.coroutine_info.is_coroutine) {
         generateCoroutineBody(func);
@@ -849,7 +4298,7 @@ void RustCodeGenerator::generateAsyncFunction(const Function& func) {
         }
     } else if (!func.body.empty()) {
         writeLine("// Async function body:");
-        writeLine(func.body);
+        writeLine(translateBody(func));
     } else {
         writeLine("todo!()");
     }
@@ -924,196 +4373,1356 @@ void RustCodeGenerator::generateAsyncTask(const AsyncTaskInfo& task) {
         writeLine(ss.str());
         indent();
 
-        // Call the async function
-        ss.str("");
-        ss << sanitizeName(task.async_function_name) << "(";
-        for (size_t i = 0; i < task.arguments.size(); ++i) {
-            ss << task.arguments[i];
-            if (i < task.arguments.size() - 1) {
-                ss << ", ";
+        // Call the async function
+        ss.str("");
+        ss << sanitizeName(task.async_function_name) << "(";
+        for (size_t i = 0; i < task.arguments.size(); ++i) {
+            ss << task.arguments[i];
+            if (i < task.arguments.size() - 1) {
+                ss << ", ";
+            }
+        }
+        ss << ").await";
+        writeLine(ss.str());
+
+        dedent();
+        writeLine("});");
+
+        if (!task.detached) {
+            writeLine("");
+            writeLine("// Await task completion");
+            writeLine(sanitizeName(task.task_var_name) + ".await.unwrap();");
+        }
+    } else {
+        // Detached task
+        ss << "tokio::spawn(async move {";
+        writeLine(ss.str());
+        indent();
+
+        ss.str("");
+        ss << sanitizeName(task.async_function_name) << "(";
+        for (size_t i = 0; i < task.arguments.size(); ++i) {
+            ss << task.arguments[i];
+            if (i < task.arguments.size() - 1) {
+                ss << ", ";
+            }
+        }
+        ss << ").await";
+        writeLine(ss.str());
+
+        dedent();
+        writeLine("});");
+    }
+
+    writeLine("");
+}
+
+void RustCodeGenerator::generateFuture(const FutureInfo& future) {
+    std::stringstream ss;
+
+    writeLine("// Future: " + future.future_var_name);
+
+    if (!future.promise_var_name.empty()) {
+        // Create a oneshot channel (promise/future pair)
+        ss << "let (" << sanitizeName(future.promise_var_name)
+           << ", " << sanitizeName(future.future_var_name)
+           << ") = futures::channel::oneshot::channel::<"
+           << convertType(future.value_type) << ">();";
+        writeLine(ss.str());
+    } else {
+        // Just a future variable
+        ss << "// Future variable: " << future.future_var_name;
+        writeLine(ss.str());
+        ss.str("");
+        ss << "// Use .await to get the value";
+        writeLine(ss.str());
+    }
+
+    writeLine("");
+}
+
+void RustCodeGenerator::generateTraitImplementations(const ClassDecl& class_decl) {
+    writeLine("// Trait implementations for inherited base classes");
+    writeLine("");
+
+    for (const auto& base_class_name : class_decl.base_classes) {
+        generateTraitForBaseClass(base_class_name, class_decl);
+        writeLine("");
+    }
+}
+
+std::string RustCodeGenerator::traitName(const std::string& base_class_name) {
+    std::string trait_name = sanitizeName(base_class_name);
+    // Capitalize first letter for trait name (Rust convention)
+    if (!trait_name.empty()) {
+        trait_name[0] = std::toupper(trait_name[0]);
+    }
+    return trait_name;
+}
+
+void RustCodeGenerator::generateTraitForBaseClass(const std::string& base_class_name, const ClassDecl& derived_class) {
+    // Generate trait definition based on base class name
+    std::string trait_name = traitName(base_class_name);
+    bool has_virtual_methods = std::any_of(
+        derived_class.methods.begin(), derived_class.methods.end(),
+        [](const Function& method) { return method.is_virtual && !method.is_constructor && !method.is_destructor; });
+
+    // Defined once; later classes deriving from the same base only implement it
+    if (traits_defined_.insert(trait_name).second) {
+        writeLine("// Trait representing C++ base class: " + base_class_name);
+        writeLine("pub trait " + trait_name + " {");
+        indent();
+
+        // Extract public virtual methods to define trait interface
+        for (const auto& method : derived_class.methods) {
+            if (method.is_virtual && !method.is_constructor && !method.is_destructor) {
+                // Generate trait method signature
+                std::stringstream sig;
+                sig << "fn " << sanitizeName(method.name) << "(";
+
+                // Add self parameter
+                if (method.is_const) {
+                    sig << "&self";
+                } else {
+                    sig << "&mut self";
+                }
+
+                // Add other parameters
+                for (size_t i = 0; i < method.parameters.size(); ++i) {
+                    const auto& param = method.parameters[i];
+                    sig << ", " << sanitizeName(param.name) << ": " << convertType(param.type);
+                }
+
+                sig << ")";
+
+                // Return type
+                if (method.return_type && method.return_type->kind != TypeKind::Void) {
+                    sig << " -> " << convertType(method.return_type);
+                }
+
+                sig << ";";
+                writeLine(sig.str());
+            }
+        }
+
+        // If no virtual methods found, add a placeholder
+        if (!has_virtual_methods) {
+            writeLine("// No virtual methods found in derived class");
+            writeLine("// Add base class methods here as needed");
+        }
+
+        dedent();
+        writeLine("}");
+        writeLine("");
+    }
+
+    // Generate trait implementation for the derived class
+    std::string struct_name = sanitizeName(derived_class.name);
+    writeLine("impl " + trait_name + " for " + struct_name + " {");
+    indent();
+
+    // Implement virtual methods
+    for (const auto& method : derived_class.methods) {
+        if (method.is_virtual && !method.is_constructor && !method.is_destructor) {
+            std::stringstream sig;
+            sig << "fn " << sanitizeName(method.name) << "(";
+
+            // Add self parameter
+            if (method.is_const) {
+                sig << "&self";
+            } else {
+                sig << "&mut self";
+            }
+
+            // Add other parameters
+            for (size_t i = 0; i < method.parameters.size(); ++i) {
+                const auto& param = method.parameters[i];
+                sig << ", " << sanitizeName(param.name) << ": " << convertType(param.type);
+            }
+
+            sig << ")";
+
+            // Return type
+            if (method.return_type && method.return_type->kind != TypeKind::Void) {
+                sig << " -> " << convertType(method.return_type);
+            }
+
+            writeLine(sig.str() + " {");
+            indent();
+
+            if (derived_class.is_deferred) {
+                countFunction(false);
+                writeLine("todo!(\"translate " + derived_class.name + "::" + method.name + " (" +
+                          deferredLocation(derived_class, method.span) + ")\")");
+            } else if (!method.body.empty()) {
+                writeLine("// Method body:");
+                writeLine(method.body);
+            } else {
+                writeLine("// TODO: Implement method");
+                writeLine("todo!()");
+            }
+
+            dedent();
+            writeLine("}");
+            writeLine("");
+        }
+    }
+
+    // If no virtual methods, add placeholder
+    if (!has_virtual_methods) {
+        writeLine("// No methods to implement");
+    }
+
+    dedent();
+    writeLine("}");
+}
+
+void RustCodeGenerator::generateBaseSubobjects(const ClassDecl& class_decl) {
+    std::string struct_name = sanitizeName(class_decl.name);
+
+    // Upcasts to a base borrow its embedded field
+    writeLine("// Base subobjects of " + class_decl.name);
+    writeLine("impl " + struct_name + " {");
+    indent();
+    for (size_t i = 0; i < class_decl.base_subobjects.size(); ++i) {
+        std::string field = sanitizeName(class_decl.base_subobjects[i].name);
+        if (i > 0) writeLine("");
+        writeLine("pub fn as_" + field + "(&self) -> &" + field + " {");
+        indent();
+        writeLine("&self." + field);
+        dedent();
+        writeLine("}");
+        writeLine("");
+        writeLine("pub fn as_" + field + "_mut(&mut self) -> &mut " + field + " {");
+        indent();
+        writeLine("&mut self." + field);
+        dedent();
+        writeLine("}");
+    }
+    dedent();
+    writeLine("}");
+
+    auto signature = [this](const Function& method) {
+        std::string sig = "fn " + sanitizeName(method.name) + (method.is_const ? "(&self" : "(&mut self");
+        for (const auto& param : method.parameters) {
+            sig += ", " + sanitizeName(param.name) + ": " + convertType(param.type);
+        }
+        sig += ")";
+        if (method.return_type && method.return_type->kind != TypeKind::Void) {
+            sig += " -> " + convertType(method.return_type);
+        }
+        return sig;
+    };
+
+    // Polymorphic bases: one trait each, with the base's virtual methods
+    for (const auto& base : class_decl.base_subobjects) {
+        if (!base.is_polymorphic) continue;
+        std::string trait_name = traitName(base.name);
+
+        writeLine("");
+        if (traits_defined_.insert(trait_name).second) {
+            writeLine("// Trait representing C++ base class: " + base.name);
+            writeLine("pub trait " + trait_name + " {");
+            indent();
+            for (const auto& method : base.virtual_methods) {
+                writeLine(signature(method) + ";");
+            }
+            dedent();
+            writeLine("}");
+            writeLine("");
+        }
+
+        writeLine("impl " + trait_name + " for " + struct_name + " {");
+        indent();
+        for (size_t i = 0; i < base.virtual_methods.size(); ++i) {
+            const Function& method = base.virtual_methods[i];
+            bool overridden = std::any_of(class_decl.methods.begin(), class_decl.methods.end(),
+                                          [&](const Function& m) { return m.name == method.name && !m.is_constructor; });
+            std::string args;
+            for (const auto& param : method.parameters) {
+                args += ", " + sanitizeName(param.name);
+            }
+
+            if (i > 0) writeLine("");
+            writeLine(signature(method) + " {");
+            indent();
+            if (overridden) {
+                writeLine(struct_name + "::" + sanitizeName(method.name) + "(self" + args + ")");
+            } else {
+                // Inherited unchanged: forward to the embedded base
+                writeLine("self." + sanitizeName(base.name) + "." + sanitizeName(method.name) + "(" +
+                          (args.empty() ? "" : args.substr(2)) + ")");
+            }
+            dedent();
+            writeLine("}");
+        }
+        dedent();
+        writeLine("}");
+    }
+
+    // Bases not declared in the input get a trait as for single inheritance
+    for (const auto& base_name : class_decl.base_classes) {
+        bool embedded = std::any_of(class_decl.base_subobjects.begin(), class_decl.base_subobjects.end(),
+                                    [&](const ClassDecl::BaseSubobject& base) { return base.name == base_name; });
+        if (!embedded) {
+            writeLine("");
+            generateTraitForBaseClass(base_name, class_decl);
+        }
+    }
+}
+
+std::string RustCodeGenerator::convertUpcasts(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& site : func.upcasts) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        bool is_this = site.object == "this";
+        bool is_mut = !site.is_const && !(is_this && func.is_const);
+        const Parameter* param = nullptr;
+        for (const auto& candidate : func.parameters) {
+            if (candidate.name == site.object) param = &candidate;
+        }
+
+        // The derived object as a borrow
+        std::string borrow;
+        if (is_this) {
+            // `as` would move self: reborrow it
+            borrow = is_mut && site.name.empty() && site.is_polymorphic ? "&mut *self" : "self";
+        } else if (param && param->type && param->type->kind == TypeKind::Reference) {
+            borrow = sanitizeName(site.object);
+        } else if (param && param->type && param->type->kind == TypeKind::Pointer) {
+            borrow = std::string("unsafe { ") + (is_mut ? "&mut *" : "&*") + sanitizeName(site.object) + " }";
+        } else {
+            borrow = (is_mut ? "&mut " : "&") + sanitizeName(site.object);
+        }
+
+        std::string replacement;
+        if (site.is_polymorphic) {
+            std::string target = (is_mut ? "&mut dyn " : "&dyn ") + traitName(site.base);
+            replacement = site.name.empty() ? "(" + borrow + " as " + target + ")"
+                                            : "let " + sanitizeName(site.name) + ": " + target + " = " + borrow;
+        } else {
+            std::string receiver = borrow[0] == '&' || borrow[0] == 'u' ? "(" + borrow + ")" : borrow;
+            std::string call = receiver + ".as_" + sanitizeName(site.base) + (is_mut ? "_mut()" : "()");
+            replacement = site.name.empty() ? call : "let " + sanitizeName(site.name) + " = " + call;
+        }
+
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertLibcCalls(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& call : func.libc_calls) {
+        size_t pos = result.find(call.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+        std::string replacement = convertLibcCall(func, call);
+        result.replace(pos, call.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
+
+    return result;
+}
+
+std::string RustCodeGenerator::convertLibcCall(const Function& func, const LibcCallInfo& call) {
+    struct StdForm {
+        const char* pattern;  // Empty: no std equivalent, the libc crate
+        const char* note;
+    };
+    static const char* heap_note = "malloc/free go through the libc crate; Box or Vec would own the memory";
+    static const char* sockaddr_note = "sockaddr structures become std::net::SocketAddr; the addresses passed to "
+                                       "connect and bind must be rewritten";
+    static const std::map<std::string, StdForm> std_forms = {
+        {"read", {"std::io::Read::read(&mut $0, &mut $1[..$2 as usize])", ""}},
+        {"write", {"std::io::Write::write(&mut $0, &$1[..$2 as usize])", ""}},
+        {"close", {"drop($0)", ""}},
+        {"lseek", {"", ""}},
+        {"unlink", {"std::fs::remove_file($0)", ""}},
+        {"mkdir", {"std::fs::create_dir($0)", ""}},
+        {"rename", {"std::fs::rename($0, $1)", ""}},
+        {"malloc", {"", heap_note}},
+        {"calloc", {"", heap_note}},
+        {"realloc", {"", heap_note}},
+        {"free", {"", heap_note}},
+        {"memcpy", {"unsafe { std::ptr::copy_nonoverlapping($1 as *const u8, $0 as *mut u8, $2 as usize) }", ""}},
+        {"memmove", {"unsafe { std::ptr::copy($1 as *const u8, $0 as *mut u8, $2 as usize) }", ""}},
+        {"memset", {"unsafe { std::ptr::write_bytes($0 as *mut u8, $1 as u8, $2 as usize) }", ""}},
+        {"memcmp", {"", ""}},
+        {"strlen", {"unsafe { std::ffi::CStr::from_ptr($0.cast()) }.to_bytes().len()", ""}},
+        {"strcmp", {"", ""}},
+        {"strcpy", {"", ""}},
+        {"socket", {"", "std::net::TcpStream::connect and TcpListener::bind create their own socket; "
+                        "the descriptor from socket() only serves libc calls"}},
+        {"connect", {"std::net::TcpStream::connect($1)", sockaddr_note}},
+        {"bind", {"std::net::TcpListener::bind($1)", sockaddr_note}},
+        {"listen", {"Ok::<(), std::io::Error>(())", "TcpListener::bind already listens; the backlog of listen() "
+                                                    "is not configurable"}},
+        {"accept", {"$0.accept()", ""}},
+        {"send", {"std::io::Write::write(&mut $0, &$1[..$2 as usize])", ""}},
+        {"recv", {"std::io::Read::read(&mut $0, &mut $1[..$2 as usize])", ""}},
+        {"getpid", {"std::process::id()", ""}},
+        {"sleep", {"std::thread::sleep(std::time::Duration::from_secs($0 as u64))", ""}},
+        {"usleep", {"std::thread::sleep(std::time::Duration::from_micros($0 as u64))", ""}},
+    };
+    // Calls whose result stood for a file descriptor in C
+    static const std::set<std::string> descriptor_calls = {"open", "read", "write", "close", "connect", "bind",
+                                                           "accept", "send", "recv"};
+
+    // Arguments first: calls nested in them
+    std::vector<std::string> args;
+    for (const auto& arg : call.arguments) {
+        std::string converted = arg;
+        size_t cursor = 0;
+        for (const auto& nested : call.nested) {
+            size_t pos = converted.find(nested.source_text, cursor);
+            if (pos == std::string::npos) continue;
+            std::string replacement = convertLibcCall(func, nested);
+            converted.replace(pos, nested.source_text.size(), replacement);
+            cursor = pos + replacement.size();
+        }
+        args.push_back(converted);
+    }
+
+    if (libc_ == LibcStrategy::Std) {
+        std::string std_call;
+        if (call.function == "open") {
+            std_call = convertOpenCall(func, args);
+        } else {
+            auto form = std_forms.find(call.function);
+            if (form != std_forms.end() && form->second.note[0] != '\0') {
+                reportOnce(form->second.note, func.name);
+            }
+            if (form != std_forms.end() && form->second.pattern[0] != '\0') {
+                std_call = substituteArguments(form->second.pattern, args);
             }
         }
-        ss << ").await";
-        writeLine(ss.str());
+        if (!std_call.empty()) {
+            if (descriptor_calls.count(call.function)) {
+                reportOnce("file descriptors become std::fs::File and std::net values returning io::Result; "
+                           "their int declarations and '< 0' checks must be rewritten", func.name);
+            }
+            return std_call;
+        }
+    }
 
-        dedent();
-        writeLine("});");
+    // As in C, through the libc crate: constants too
+    static const std::regex constant(
+        R"((^|[^:\w])((?:O|AF|PF|SOCK|SOL|SO|IPPROTO|INADDR|SEEK|MSG|PROT|MAP)_[A-Z0-9_]+|S_I[RWX]\w*)\b)");
+    std::string joined;
+    for (size_t i = 0; i < args.size(); ++i) {
+        if (i > 0) joined += ", ";
+        // A string literal is NUL-terminated in C: a C string literal in Rust
+        static const std::regex string_literal(R"("(?:[^"\\]|\\.)*")");
+        joined += std::regex_match(args[i], string_literal) ? "c" + args[i] + ".as_ptr()"
+                                                            : std::regex_replace(args[i], constant, "$1libc::$2");
+    }
+    reportOnce("direct libc calls use the libc crate; add libc to the crate's dependencies");
+    return "unsafe { libc::" + call.function + "(" + joined + ") }";
+}
 
-        if (!task.detached) {
-            writeLine("");
-            writeLine("// Await task completion");
-            writeLine(sanitizeName(task.task_var_name) + ".await.unwrap();");
+std::string RustCodeGenerator::convertOpenCall(const Function& func, const std::vector<std::string>& args) {
+    static const std::map<std::string, std::string> options = {
+        {"O_RDONLY", ".read(true)"},
+        {"O_WRONLY", ".write(true)"},
+        {"O_RDWR", ".read(true).write(true)"},
+        {"O_CREAT", ".create(true)"},
+        {"O_TRUNC", ".truncate(true)"},
+        {"O_APPEND", ".append(true)"},
+        {"O_EXCL", ".create_new(true)"},
+        {"O_CLOEXEC", ""},  // std opens with close-on-exec
+    };
+    if (args.size() < 2) return "";
+
+    // Flags must be constants: open(path, flags) with computed flags stays libc
+    std::string chain;
+    bool access_mode = false;
+    std::stringstream flags(args[1]);
+    std::string flag;
+    while (std::getline(flags, flag, '|')) {
+        flag.erase(std::remove_if(flag.begin(), flag.end(), [](char c) { return std::isspace(static_cast<unsigned char>(c)); }),
+                   flag.end());
+        auto option = options.find(flag);
+        if (option == options.end()) {
+            reportOnce("open flag '" + flag + "' has no std::fs::OpenOptions equivalent; the call goes through "
+                       "the libc crate", func.name);
+            return "";
         }
-    } else {
-        // Detached task
-        ss << "tokio::spawn(async move {";
-        writeLine(ss.str());
-        indent();
+        access_mode = access_mode || flag == "O_RDONLY" || flag == "O_WRONLY" || flag == "O_RDWR";
+        chain += option->second;
+    }
+    if (!access_mode) chain = ".read(true)" + chain;  // O_RDONLY is 0
+    if (args.size() > 2) {
+        reportOnce("the mode argument of open is dropped; set it with std::os::unix::fs::OpenOptionsExt::mode",
+                   func.name);
+    }
+    return "std::fs::OpenOptions::new()" + chain + ".open(" + args[0] + ")";
+}
 
-        ss.str("");
-        ss << sanitizeName(task.async_function_name) << "(";
-        for (size_t i = 0; i < task.arguments.size(); ++i) {
-            ss << task.arguments[i];
-            if (i < task.arguments.size() - 1) {
-                ss << ", ";
+void RustCodeGenerator::reportMemoryIssues(const IR& ir) {
+    for (const auto& class_decl : ir.getClasses()) {
+        for (const auto& method : class_decl.methods) {
+            for (const auto& issue : method.memory_issues) {
+                reportMemoryIssue(issue, !method.retained_reason.empty(), class_decl.name + "::" + method.name);
             }
         }
-        ss << ").await";
-        writeLine(ss.str());
-
-        dedent();
-        writeLine("});");
+    }
+    for (const auto& func : ir.getFunctions()) {
+        bool retained = !func.retained_reason.empty() || (func.is_variadic && varargs_ == VarargsStrategy::Ffi);
+        for (const auto& issue : func.memory_issues) reportMemoryIssue(issue, retained, func.name);
     }
 
-    writeLine("");
+    size_t found = stats_.memory_issues_fixed + stats_.memory_issues_preserved;
+    if (found == 0) return;
+    report(Diagnostic::Note,
+           std::to_string(found) + " memory-safety bug" + (found > 1 ? "s" : "") + " found in the C++: the " +
+               "translation fixes " + std::to_string(stats_.memory_issues_fixed) + ", " +
+               std::to_string(stats_.memory_issues_preserved) + " preserved");
 }
 
-void RustCodeGenerator::generateFuture(const FutureInfo& future) {
-    std::stringstream ss;
+void RustCodeGenerator::reportMemoryIssue(const MemoryIssueInfo& issue, bool retained, const std::string& context) {
+    std::string bug;
+    switch (issue.kind) {
+        case MemoryIssueInfo::UseAfterFree:
+            bug = "use after free: '" + issue.source_text + "' uses '" + issue.variable + "' after '" + issue.cause +
+                  "'";
+            break;
+        case MemoryIssueInfo::DoubleDelete:
+            bug = "double delete: '" + issue.source_text + "' frees '" + issue.variable + "' again after '" +
+                  issue.cause + "'";
+            break;
+        case MemoryIssueInfo::BufferOverrun:
+            bug = "buffer overrun: '" + issue.source_text + "' indexes past the end of '" + issue.cause + "'";
+            if (!issue.loop.empty()) bug += " in '" + issue.loop + "'";
+            break;
+        case MemoryIssueInfo::UninitializedRead:
+            bug = "uninitialized read: '" + issue.source_text + "' reads '" + issue.variable +
+                  "' before it is assigned ('" + issue.cause + "')";
+            break;
+    }
 
-    writeLine("// Future: " + future.future_var_name);
+    // Links between arena-allocated nodes are generational ids
+    bool arena = issue.type && issue.type->kind == TypeKind::Pointer && issue.type->element_type &&
+                 arena_classes_.count(issue.type->element_type->name);
+    std::string outcome;
+    bool fixed = !retained;
+    if (retained) {
+        outcome = "preserved: the function is kept in C++";
+    } else if (issue.kind == MemoryIssueInfo::BufferOverrun) {
+        outcome = "fixed: Rust arrays are bounds-checked, so the overrun panics";
+    } else if (issue.kind == MemoryIssueInfo::UninitializedRead) {
+        outcome = "fixed: rustc rejects reading a binding before it is assigned (E0381)";
+    } else if (arena && issue.kind == MemoryIssueInfo::UseAfterFree) {
+        outcome = "fixed: the arena id is stale once removed, so the access panics";
+    } else if (arena) {
+        outcome = "fixed: removing a stale arena id does nothing";
+    } else {
+        std::string pointer = issue.type && issue.type->kind == TypeKind::Pointer ? convertType(issue.type)
+                                                                                   : "a raw pointer";
+        outcome = "preserved behind unsafe: '" + issue.variable + "' stays " + pointer;
+        fixed = false;
+    }
 
-    if (!future.promise_var_name.empty()) {
-        // Create a oneshot channel (promise/future pair)
-        ss << "let (" << sanitizeName(future.promise_var_name)
-           << ", " << sanitizeName(future.future_var_name)
-           << ") = futures::channel::oneshot::channel::<"
-           << convertType(future.value_type) << ">();";
-        writeLine(ss.str());
+    if (fixed) {
+        stats_.memory_issues_fixed++;
     } else {
-        // Just a future variable
-        ss << "// Future variable: " << future.future_var_name;
-        writeLine(ss.str());
-        ss.str("");
-        ss << "// Use .await to get the value";
-        writeLine(ss.str());
+        stats_.memory_issues_preserved++;
+    }
+    report(Diagnostic::Warning, bug + "; " + outcome, context);
+}
+
+std::string RustCodeGenerator::convertIntegerSites(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
+
+    for (const auto& site : func.integer_sites) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+
+        std::string replacement;
+        if (site.kind == IntegerSiteInfo::Wrapping) {
+            // h *= 31 -> h = h.wrapping_mul(31)
+            std::string expression = wrappingArithmetic(site.expression, site);
+            if (site.op == "=") {
+                replacement = site.target + " = " + expression;
+            } else {
+                static const std::map<char, std::string> methods = {{'+', "add"}, {'-', "sub"}, {'*', "mul"}};
+                replacement = site.target + " = " + site.target + ".wrapping_" + methods.at(site.op[0]) + "(" +
+                              expression + ")";
+            }
+        } else {
+            std::string converted = integerConversion(func, site);
+            replacement = site.explicit_cast
+                              ? converted
+                              : site.source_text.substr(0, site.source_text.size() - site.expression.size()) +
+                                    converted;
+        }
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
     }
 
-    writeLine("");
+    return result;
 }
 
-void RustCodeGenerator::generateTraitImplementations(const ClassDecl& class_decl) {
-    writeLine("// Trait implementations for inherited base classes");
-    writeLine("");
+std::string RustCodeGenerator::wrappingArithmetic(const std::string& expr, const IntegerSiteInfo& site) {
+    std::string target = convertType(site.type);
+    auto trim = [](const std::string& text) {
+        size_t start = text.find_first_not_of(" \t\n");
+        size_t end = text.find_last_not_of(" \t\n");
+        return start == std::string::npos ? std::string() : text.substr(start, end - start + 1);
+    };
 
-    for (const auto& base_class_name : class_decl.base_classes) {
-        generateTraitForBaseClass(base_class_name, class_decl);
-        writeLine("");
+    // Top-level operands and operators: a binary operator follows an operand
+    std::vector<std::string> operands;
+    std::vector<char> operators;
+    std::string current;
+    int depth = 0;
+    bool after_operand = false;
+    for (size_t i = 0; i < expr.size(); ++i) {
+        char c = expr[i];
+        if (c == '(' || c == '[') depth++;
+        else if (c == ')' || c == ']') depth--;
+        bool arrow = c == '-' && i + 1 < expr.size() && expr[i + 1] == '>';
+        if (depth == 0 && after_operand && !arrow && (c == '+' || c == '-' || c == '*' || c == '/' || c == '%')) {
+            operands.push_back(trim(current));
+            operators.push_back(c);
+            current.clear();
+            after_operand = false;
+            continue;
+        }
+        if (!std::isspace(static_cast<unsigned char>(c))) after_operand = true;
+        current += c;
+        if (arrow) current += expr[++i];
+    }
+    operands.push_back(trim(current));
+
+    for (auto& operand : operands) {
+        if (operand.size() > 2 && operand.front() == '(' && operand.back() == ')') {
+            operand = "(" + wrappingArithmetic(operand.substr(1, operand.size() - 2), site) + ")";
+        } else if (site.operands.count(operand)) {
+            // C++ converts the other operand to the unsigned type, modulo 2^N like `as`
+            operand = "(" + operand + " as " + target + ")";
+        }
+    }
+    if (operators.empty()) return operands[0];
+
+    // An untyped literal cannot receive a method call: 31u32.wrapping_mul(h)
+    auto receiver = [&target](const std::string& operand) {
+        bool literal = !operand.empty() && std::all_of(operand.begin(), operand.end(), [](char c) {
+            return std::isxdigit(static_cast<unsigned char>(c)) || c == 'x' || c == 'X';
+        }) && std::isdigit(static_cast<unsigned char>(operand[0]));
+        return literal ? operand + target : operand;
+    };
+
+    // * / % bind tighter than + -
+    std::vector<std::string> terms;
+    std::vector<char> additive;
+    std::string term = operands[0];
+    for (size_t i = 0; i < operators.size(); ++i) {
+        const std::string& rhs = operands[i + 1];
+        if (operators[i] == '*') {
+            term = receiver(term) + ".wrapping_mul(" + rhs + ")";
+        } else if (operators[i] == '/' || operators[i] == '%') {
+            term = "(" + term + " " + operators[i] + " " + rhs + ")";
+        } else {
+            terms.push_back(term);
+            additive.push_back(operators[i]);
+            term = rhs;
+        }
+    }
+    terms.push_back(term);
+
+    std::string result = terms[0];
+    for (size_t i = 0; i < additive.size(); ++i) {
+        result = receiver(result) + (additive[i] == '+' ? ".wrapping_add(" : ".wrapping_sub(") + terms[i + 1] + ")";
     }
+    return result;
 }
 
-void RustCodeGenerator::generateTraitForBaseClass(const std::string& base_class_name, const ClassDecl& derived_class) {
-    // Generate trait definition based on base class name
-    std::string trait_name = sanitizeName(base_class_name);
-    // Capitalize first letter for trait name (Rust convention)
-    if (!trait_name.empty()) {
-        trait_name[0] = std::toupper(trait_name[0]);
+std::string RustCodeGenerator::integerConversion(const Function& func, const IntegerSiteInfo& site) {
+    std::string to = convertType(site.type);
+    std::string from = convertType(site.from);
+    if (to == from) return site.expression;  // long and int under LLP64
+
+    IntegerFormat source = integerFormat(site.from->name);
+    IntegerFormat target = integerFormat(site.type->name);
+    bool lossless = source.is_signed == target.is_signed ? target.bits >= source.bits
+                                                         : !source.is_signed && target.bits > source.bits;
+    // From exists between fixed-width types, and into usize/isize from 8 and 16 bits only
+    bool has_from = !source.pointer_sized && (!target.pointer_sized || source.bits == 8 ||
+                                              (source.bits == 16 && source.is_signed == target.is_signed));
+    if (lossless && has_from) return to + "::from(" + site.expression + ")";
+
+    if (!lossless) {
+        std::string spelled = site.explicit_cast ? "static_cast<" + site.type->name + ">(" + site.expression + ")"
+                                                 : "'" + site.expression + "'";
+        if (site.explicit_cast) {
+            report(Diagnostic::Note,
+                   spelled + " narrows " + site.from->name + " to " + site.type->name + "; " + to +
+                       "::try_from panics where the cast truncated (write 'as " + to + "' to keep the truncation)",
+                   func.name);
+        } else {
+            report(Diagnostic::Warning,
+                   spelled + " (" + site.from->name + ") converts implicitly to " + site.type->name +
+                       ", which may not hold it; " + to + "::try_from panics where C++ truncated",
+                   func.name);
+        }
     }
+    return to + "::try_from(" + site.expression + ").expect(\"" + site.from->name + " value out of range for " +
+           to + "\")";
+}
 
-    writeLine("// Trait representing C++ base class: " + base_class_name);
-    writeLine("pub trait " + trait_name + " {");
-    indent();
+std::string RustCodeGenerator::convertSlices(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
 
-    // Extract public virtual methods to define trait interface
-    bool has_virtual_methods = false;
-    for (const auto& method : derived_class.methods) {
-        if (method.is_virtual && !method.is_constructor && !method.is_destructor) {
-            has_virtual_methods = true;
+    // Index or bound of a slice: a literal as written, anything else as usize
+    auto index = [](const std::string& expr) {
+        if (std::regex_match(expr, std::regex(R"(\d+)"))) return expr;
+        bool simple = std::regex_match(expr, std::regex(R"([a-zA-Z_][\w.]*)"));
+        return (simple ? expr : "(" + expr + ")") + " as usize";
+    };
 
-            // Generate trait method signature
-            std::stringstream sig;
-            sig << "fn " << sanitizeName(method.name) << "(";
+    for (const auto& site : func.slice_sites) {
+        size_t pos = result.find(site.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
 
-            // Add self parameter
-            if (method.is_const) {
-                sig << "&self";
-            } else {
-                sig << "&mut self";
+        std::string target = sanitizeName(site.target);
+        std::string replacement;
+        switch (site.kind) {
+            case SliceSiteInfo::SpanMethod: {
+                std::string borrow = site.type && site.type->is_const ? "&" : "&mut ";
+                const auto& args = site.arguments;
+                if (site.method == "size") replacement = target + ".len()";
+                else if (site.method == "size_bytes") replacement = "std::mem::size_of_val(" + target + ")";
+                else if (site.method == "empty") replacement = target + ".is_empty()";
+                else if (site.method == "data") replacement = target + (borrow == "&" ? ".as_ptr()" : ".as_mut_ptr()");
+                else if (site.method == "front") replacement = target + "[0]";
+                else if (site.method == "back") replacement = target + "[" + target + ".len() - 1]";
+                else if (site.method == "first" && args.size() == 1) {
+                    replacement = borrow + target + "[.." + index(args[0]) + "]";
+                } else if (site.method == "last" && args.size() == 1) {
+                    replacement = borrow + target + "[" + target + ".len() - " + index(args[0]) + "..]";
+                } else if (site.method == "subspan" && args.size() == 1) {
+                    replacement = borrow + target + "[" + index(args[0]) + "..]";
+                } else if (site.method == "subspan" && args.size() == 2) {
+                    replacement = borrow + target + "[" + index(args[0]) + ".." + index(args[0]) + " + " +
+                                  index(args[1]) + "]";
+                } else {
+                    cursor = pos + site.source_text.size();
+                    continue;
+                }
+                break;
             }
 
-            // Add other parameters
-            for (size_t i = 0; i < method.parameters.size(); ++i) {
-                const auto& param = method.parameters[i];
-                sig << ", " << sanitizeName(param.name) << ": " << convertType(param.type);
+            case SliceSiteInfo::FusedCall: {
+                std::vector<std::string> args;
+                bool converted = true;
+                for (size_t i = 0; i < site.arguments.size(); ++i) {
+                    auto array = site.borrowed.find(i);
+                    if (array != site.borrowed.end()) {
+                        // An array or vector lends itself to a span parameter as a borrow
+                        args.push_back((array->second ? "&" : "&mut ") + sanitizeName(site.arguments[i]));
+                        continue;
+                    }
+                    auto pair = std::find_if(site.fused.begin(), site.fused.end(),
+                                             [i](const SliceParamInfo& p) { return p.position == i; });
+                    if (pair == site.fused.end() || i + 1 >= site.arguments.size()) {
+                        args.push_back(site.arguments[i]);
+                        continue;
+                    }
+                    std::string borrow = pair->is_const ? "&" : "&mut ";
+                    const std::string& pointer = site.arguments[i];
+                    const std::string& length = site.arguments[++i];
+                    std::smatch data;
+                    bool forwarded = std::any_of(func.slice_params.begin(), func.slice_params.end(),
+                                                 [&](const SliceParamInfo& p) {
+                                                     return p.pointer == pointer && p.length == length;
+                                                 });
+                    auto raw = std::find_if(func.parameters.begin(), func.parameters.end(), [&](const Parameter& p) {
+                        return p.name == pointer && p.type && p.type->kind == TypeKind::Pointer &&
+                               p.type->name.find("_ptr<") == std::string::npos;
+                    });
+                    if (forwarded) {
+                        // The caller's own pair: hand its slice on
+                        args.push_back(sanitizeName(pointer));
+                    } else if (raw != func.parameters.end()) {
+                        // A raw pointer parameter of the caller: the caller vouches for the length
+                        args.push_back(std::string("unsafe { std::slice::") +
+                                       (pair->is_const ? "from_raw_parts(" : "from_raw_parts_mut(") +
+                                       sanitizeName(pointer) + ", " + index(length) + ") }");
+                    } else if (std::regex_match(pointer, data, std::regex(R"(([a-zA-Z_][\w.]*)\.data\(\))"))) {
+                        // v.data(), v.size(): the whole vector, array or span
+                        std::string owner = data[1].str();
+                        bool span = std::any_of(func.parameters.begin(), func.parameters.end(), [&](const Parameter& p) {
+                            return p.name == owner && p.type && p.type->kind == TypeKind::Reference &&
+                                   p.type->element_type && p.type->element_type->kind == TypeKind::Array;
+                        });
+                        if (length != owner + ".size()") {
+                            args.push_back(borrow + owner + "[.." + index(length) + "]");
+                        } else {
+                            args.push_back(span ? sanitizeName(owner) : borrow + owner);
+                        }
+                    } else if (std::regex_match(pointer, std::regex(R"([a-zA-Z_][\w.]*)"))) {
+                        args.push_back(borrow + pointer + "[.." + index(length) + "]");
+                    } else {
+                        report(Diagnostic::Warning,
+                               "'" + pointer + "' is passed where '" + site.target +
+                               "' takes a slice; the call is left as written",
+                               func.name);
+                        converted = false;
+                        break;
+                    }
+                }
+                if (!converted) {
+                    cursor = pos + site.source_text.size();
+                    continue;
+                }
+                replacement = site.source_text.substr(0, site.source_text.find('(') + 1);
+                for (size_t i = 0; i < args.size(); ++i) replacement += (i > 0 ? ", " : "") + args[i];
+                replacement += ")";
+                break;
             }
 
-            sig << ")";
-
-            // Return type
-            if (method.return_type && method.return_type->kind != TypeKind::Void) {
-                sig << " -> " << convertType(method.return_type);
+            case SliceSiteInfo::NewArray: {
+                std::string buffer = "vec![Default::default(); " + index(site.arguments[0]) + "].into_boxed_slice()";
+                replacement = site.is_declaration
+                    ? "let mut " + target + ": Box<[" + convertType(site.type) + "]> = " + buffer + ";"
+                    : buffer;
+                break;
             }
 
-            sig << ";";
-            writeLine(sig.str());
+            case SliceSiteInfo::DeleteArray:
+                // The box frees the buffer when it is dropped or reassigned
+                replacement = "";
+                break;
         }
+
+        result.replace(pos, site.source_text.size(), replacement);
+        cursor = pos + replacement.size();
     }
 
-    // If no virtual methods found, add a placeholder
-    if (!has_virtual_methods) {
-        writeLine("// No virtual methods found in derived class");
-        writeLine("// Add base class methods here as needed");
+    // The dropped length of a fused pair is the length of its slice
+    for (const auto& pair : func.slice_params) {
+        std::string len = sanitizeName(pair.pointer) + ".len()";
+        std::string length_type = convertType(pair.length_type);
+        std::string length = length_type == "usize" ? len : "(" + len + " as " + length_type + ")";
+
+        std::string rewritten;
+        for (size_t i = 0; i < result.size();) {
+            char c = result[i];
+            if (c == '"' || c == '\'') {
+                size_t end = i + 1;
+                while (end < result.size() && result[end] != c) end += result[end] == '\\' ? 2 : 1;
+                end = std::min(end + 1, result.size());
+                rewritten += result.substr(i, end - i);
+                i = end;
+                continue;
+            }
+            if (std::isalpha(static_cast<unsigned char>(c)) || c == '_') {
+                size_t end = i;
+                while (end < result.size() && (std::isalnum(static_cast<unsigned char>(result[end])) || result[end] == '_')) {
+                    ++end;
+                }
+                // v.n and p->n are members; the n of ..n is not
+                bool member = i > 0 && ((result[i - 1] == '.' && (i < 2 || result[i - 2] != '.')) ||
+                                        (i > 1 && result[i - 1] == '>' && result[i - 2] == '-'));
+                if (member || end - i != pair.length.size() || result.compare(i, end - i, pair.length) != 0) {
+                    rewritten += result.substr(i, end - i);
+                } else if (result.compare(end, 9, " as usize") == 0) {
+                    // n as usize, as an earlier pass spelled an index
+                    rewritten += len;
+                    end += 9;
+                } else {
+                    rewritten += length;
+                }
+                i = end;
+                continue;
+            }
+            rewritten += c;
+            ++i;
+        }
+        result = rewritten;
     }
 
-    dedent();
-    writeLine("}");
-    writeLine("");
+    return result;
+}
 
-    // Generate trait implementation for the derived class
-    std::string struct_name = sanitizeName(derived_class.name);
-    writeLine("impl " + trait_name + " for " + struct_name + " {");
-    indent();
+std::string RustCodeGenerator::convertLiterals(const Function& func, const std::string& body) {
+    std::string result = body;
+    size_t cursor = 0;
 
-    // Implement virtual methods
-    for (const auto& method : derived_class.methods) {
-        if (method.is_virtual && !method.is_constructor && !method.is_destructor) {
-            std::stringstream sig;
-            sig << "fn " << sanitizeName(method.name) << "(";
+    for (const auto& limit : func.numeric_limits) {
+        size_t pos = result.find(limit.source_text, cursor);
+        if (pos == std::string::npos) {
+            continue;
+        }
+        std::string replacement = numericLimit(func, limit);
+        if (replacement.empty()) {
+            cursor = pos + limit.source_text.size();
+            continue;
+        }
+        result.replace(pos, limit.source_text.size(), replacement);
+        cursor = pos + replacement.size();
+    }
 
-            // Add self parameter
-            if (method.is_const) {
-                sig << "&self";
-            } else {
-                sig << "&mut self";
+    // Literals outside comments
+    auto is_word = [](char c) { return std::isalnum(static_cast<unsigned char>(c)) || c == '_'; };
+    auto is_digit = [](char c) { return std::isdigit(static_cast<unsigned char>(c)) != 0; };
+    // Encoding prefix ending at pos: u8, u, U, L, R (raw), c (C string of the libc pass)
+    auto prefix_before = [&](const std::string& text, size_t pos) {
+        static const std::regex prefix(R"((?:^|[^\w])(u8R|uR|UR|LR|u8|R|u|U|L|c)$)");
+        std::smatch match;
+        std::string head = text.substr(pos >= 4 ? pos - 4 : 0, pos >= 4 ? 4 : pos);
+        return std::regex_search(head, match, prefix) ? match[1].str() : std::string();
+    };
+    // End of the string literal whose opening quote is at open
+    auto string_end = [&](size_t open, bool raw) {
+        if (raw) {
+            size_t paren = result.find('(', open);
+            if (paren == std::string::npos) return result.size();
+            std::string close = ")" + result.substr(open + 1, paren - open - 1) + "\"";
+            size_t found = result.find(close, paren);
+            return found == std::string::npos ? result.size() : found + close.size();
+        }
+        size_t end = open + 1;
+        for (; end < result.size() && result[end] != '"'; ++end) {
+            if (result[end] == '\\') ++end;
+        }
+        return std::min(end + 1, result.size());
+    };
+    std::string converted;
+    size_t i = 0;
+    while (i < result.size()) {
+        char c = result[i];
+        char next = i + 1 < result.size() ? result[i + 1] : '\0';
+        size_t end = i + 1;
+        if (c == '"') {
+            // "a" "b" is one literal in C++
+            std::string prefix = prefix_before(converted, converted.size());
+            converted.erase(converted.size() - prefix.size());
+            std::vector<std::string> pieces;
+            size_t open = i;
+            while (true) {
+                end = string_end(open, prefix.find('R') != std::string::npos);
+                pieces.push_back(prefix + result.substr(open, end - open));
+                size_t following = result.find_first_not_of(" \t\r\n", end);
+                if (following == std::string::npos) break;
+                size_t quote = result.find('"', following);
+                if (quote == std::string::npos || quote - following > 3) break;
+                prefix = result.substr(following, quote - following);
+                if (prefix_before(prefix, prefix.size()) != prefix || prefix == "c") break;
+                open = quote;
             }
-
-            // Add other parameters
-            for (size_t i = 0; i < method.parameters.size(); ++i) {
-                const auto& param = method.parameters[i];
-                sig << ", " << sanitizeName(param.name) << ": " << convertType(param.type);
+            converted += stringLiteral(func, pieces);
+            i = end;
+            continue;
+        } else if (c == '\'') {
+            // 'a', '\n', 'e' followed by a combining byte; anything else is a lifetime or a label
+            size_t close = std::string::npos;
+            if (next == '\\') {
+                close = result.find('\'', i + 3);
+            } else if (next != '\0' && next != '\'') {
+                size_t length = 1;
+                while (i + 1 + length < result.size() && (static_cast<unsigned char>(result[i + 1 + length]) & 0xC0) == 0x80) {
+                    length++;
+                }
+                if (i + 1 + length < result.size() && result[i + 1 + length] == '\'') close = i + 1 + length;
+                // 'ab': multicharacter
+                for (size_t k = i + 2; close == std::string::npos && k < result.size() && k <= i + 5; ++k) {
+                    if (result[k] == '\'') close = k;
+                    else if (!std::isalnum(static_cast<unsigned char>(result[k]))) break;
+                }
             }
+            if (close != std::string::npos) {
+                std::string prefix = prefix_before(converted, converted.size());
+                if (prefix.find('R') != std::string::npos || prefix == "c") prefix.clear();
+                converted.erase(converted.size() - prefix.size());
+                converted += charLiteral(func, prefix, result.substr(i + 1, close - i - 1));
+                i = close + 1;
+                continue;
+            }
+        } else if (c == '/' && next == '/') {
+            end = result.find('\n', i);
+            if (end == std::string::npos) end = result.size();
+        } else if (c == '/' && next == '*') {
+            end = result.find("*/", i + 2);
+            end = end == std::string::npos ? result.size() : end + 2;
+        } else if (is_digit(c) || (c == '.' && is_digit(next))) {
+            // pair.0, x1 and 31u32 are not C++ literals
+            char before = converted.empty() ? ' ' : converted.back();
+            bool attached = is_word(before) || before == '.' || (c == '.' && (before == ')' || before == ']'));
+
+            bool hex = c == '0' && (next == 'x' || next == 'X');
+            bool binary = c == '0' && (next == 'b' || next == 'B');
+            end = hex || binary ? i + 2 : i;
+            auto digits = [&]() {
+                while (end < result.size() &&
+                       ((hex ? std::isxdigit(static_cast<unsigned char>(result[end])) : is_digit(result[end])) ||
+                        (result[end] == '\'' && end + 1 < result.size() &&
+                         std::isxdigit(static_cast<unsigned char>(result[end + 1]))))) {
+                    ++end;
+                }
+            };
+            digits();
+            // 5.f and 5. are floats; 0..n, 1.max(2) and x.0.1 are not
+            if (end < result.size() && result[end] == '.' && !binary) {
+                char after = end + 1 < result.size() ? result[end + 1] : '\0';
+                bool suffix = std::string("fFlL").find(after) != std::string::npos && after != '\0' &&
+                              !(end + 2 < result.size() && is_word(result[end + 2]));
+                bool exponent = std::string(hex ? "pP" : "eE").find(after) != std::string::npos && after != '\0';
+                if ((hex ? std::isxdigit(static_cast<unsigned char>(after)) : is_digit(after)) || suffix || exponent ||
+                    (after != '.' && !is_word(after))) {
+                    ++end;
+                    digits();
+                }
+            }
+            if (end < result.size() && std::string(hex ? "pP" : "eE").find(result[end]) != std::string::npos &&
+                !binary) {
+                size_t sign = end + 1 < result.size() && (result[end + 1] == '+' || result[end + 1] == '-')
+                                  ? end + 2 : end + 1;
+                if (sign < result.size() && is_digit(result[sign])) {
+                    end = sign;
+                    while (end < result.size() && is_digit(result[end])) ++end;
+                }
+            }
+            while (end < result.size() && is_word(result[end])) ++end;
 
-            sig << ")";
+            std::string token = result.substr(i, end - i);
+            converted += attached ? token : numericLiteral(token);
+            i = end;
+            continue;
+        }
+        converted += result.substr(i, end - i);
+        i = end;
+    }
 
-            // Return type
-            if (method.return_type && method.return_type->kind != TypeKind::Void) {
-                sig << " -> " << convertType(method.return_type);
+    return converted;
+}
+
+std::string RustCodeGenerator::decodeEscapes(const std::string& content, bool wide) {
+    auto utf8 = [](unsigned long code) {
+        std::string bytes;
+        if (code < 0x80) {
+            bytes += static_cast<char>(code);
+        } else if (code < 0x800) {
+            bytes += static_cast<char>(0xC0 | (code >> 6));
+            bytes += static_cast<char>(0x80 | (code & 0x3F));
+        } else if (code < 0x10000) {
+            bytes += static_cast<char>(0xE0 | (code >> 12));
+            bytes += static_cast<char>(0x80 | ((code >> 6) & 0x3F));
+            bytes += static_cast<char>(0x80 | (code & 0x3F));
+        } else {
+            bytes += static_cast<char>(0xF0 | (code >> 18));
+            bytes += static_cast<char>(0x80 | ((code >> 12) & 0x3F));
+            bytes += static_cast<char>(0x80 | ((code >> 6) & 0x3F));
+            bytes += static_cast<char>(0x80 | (code & 0x3F));
+        }
+        return bytes;
+    };
+    static const std::map<char, char> simple = {{'n', '\n'}, {'t', '\t'}, {'r', '\r'}, {'a', '\a'},
+                                                 {'b', '\b'}, {'f', '\f'}, {'v', '\v'}, {'e', '\x1b'},
+                                                 {'\\', '\\'}, {'\'', '\''}, {'"', '"'}, {'?', '?'}};
+
+    std::string bytes;
+    for (size_t i = 0; i < content.size(); ++i) {
+        if (content[i] != '\\' || i + 1 == content.size()) {
+            bytes += content[i];
+            continue;
+        }
+        char c = content[++i];
+        auto escape = simple.find(c);
+        if (escape != simple.end()) {
+            bytes += escape->second;
+        } else if (c >= '0' && c <= '7') {
+            // Up to three octal digits
+            unsigned long value = 0;
+            size_t digits = 0;
+            for (; digits < 3 && i < content.size() && content[i] >= '0' && content[i] <= '7'; ++digits, ++i) {
+                value = value * 8 + static_cast<unsigned long>(content[i] - '0');
             }
+            --i;
+            bytes += wide ? utf8(value) : std::string(1, static_cast<char>(value));
+        } else if (c == 'x' || c == 'u' || c == 'U') {
+            // \x takes every hex digit; \u four, \U eight
+            size_t limit = c == 'x' ? std::string::npos : c == 'u' ? 4 : 8;
+            size_t start = i + 1;
+            size_t stop = start;
+            while (stop < content.size() && stop - start < limit &&
+                   std::isxdigit(static_cast<unsigned char>(content[stop]))) {
+                ++stop;
+            }
+            unsigned long value = std::strtoul(content.substr(start, stop - start).c_str(), nullptr, 16);
+            bytes += c == 'x' && !wide ? std::string(1, static_cast<char>(value)) : utf8(value);
+            i = stop - 1;
+        } else {
+            bytes += c;
+        }
+    }
+    return bytes;
+}
 
-            writeLine(sig.str() + " {");
-            indent();
+std::string RustCodeGenerator::stringLiteral(const Function& func, const std::vector<std::string>& pieces) {
+    std::string bytes;
+    std::string written;
+    bool raw = pieces.size() == 1;
+    bool wide = false;
+    bool c_string = false;
+    for (const auto& piece : pieces) {
+        size_t quote = piece.find('"');
+        std::string prefix = piece.substr(0, quote);
+        written += (written.empty() ? "" : " ") + piece;
+        wide = wide || (prefix.find_first_of("uUL") != std::string::npos && prefix.compare(0, 2, "u8") != 0);
+        c_string = c_string || prefix == "c";
+        if (prefix.find('R') != std::string::npos) {
+            // R"delim(...)delim"
+            size_t paren = piece.find('(', quote);
+            size_t delimiter = paren - quote - 1;
+            bytes += piece.substr(paren + 1, piece.size() - paren - 1 - delimiter - 2);
+        } else {
+            raw = false;
+            bytes += decodeEscapes(piece.substr(quote + 1, piece.size() - quote - 2), wide);
+        }
+    }
 
-            if (!method.body.empty()) {
-                writeLine("// Method body:");
-                writeLine(method.body);
-            } else {
-                writeLine("// TODO: Implement method");
-                writeLine("todo!()");
-            }
+    // Valid UTF-8 can be a &str
+    bool utf8 = true;
+    for (size_t i = 0; i < bytes.size() && utf8;) {
+        unsigned char lead = static_cast<unsigned char>(bytes[i]);
+        size_t length = lead < 0x80 ? 1 : (lead >> 5) == 0x6 ? 2 : (lead >> 4) == 0xE ? 3 : (lead >> 3) == 0x1E ? 4 : 0;
+        if (length == 0 || i + length > bytes.size()) utf8 = false;
+        for (size_t k = 1; utf8 && k < length; ++k) {
+            if ((static_cast<unsigned char>(bytes[i + k]) & 0xC0) != 0x80) utf8 = false;
+        }
+        i += length;
+    }
+    if (wide) {
+        reportOnce("wide string literals become UTF-8 &str; use encode_utf16() or chars() where the wchar_t, "
+                   "char16_t or char32_t units matter", func.name);
+    }
+    if (bytes.find('\0') != std::string::npos && !c_string) {
+        report(Diagnostic::Warning,
+               written + " contains \\0: C++ reads it as a C string and stops there; Rust keeps what follows",
+               func.name);
+    }
+    if (!utf8 && !c_string) {
+        report(Diagnostic::Warning,
+               written + " is not valid UTF-8 and becomes a byte string (&[u8; N]); String and &str "
+                         "cannot hold it",
+               func.name);
+    }
 
-            dedent();
-            writeLine("}");
-            writeLine("");
+    if (raw && utf8) {
+        // Enough # that no "### inside ends the raw string
+        std::string hashes = "#";
+        while (bytes.find("\"" + hashes) != std::string::npos) hashes += "#";
+        return "r" + hashes + "\"" + bytes + "\"" + hashes;
+    }
+
+    std::string result = c_string ? "c\"" : utf8 ? "\"" : "b\"";
+    for (char c : bytes) {
+        unsigned char byte = static_cast<unsigned char>(c);
+        if (c == '"' || c == '\\') result += std::string("\\") + c;
+        else if (c == '\n') result += "\\n";
+        else if (c == '\t') result += "\\t";
+        else if (c == '\r') result += "\\r";
+        else if (c == '\0') result += "\\0";
+        else if (byte < 0x20 || byte == 0x7F || (byte >= 0x80 && !utf8)) {
+            static const char* hex = "0123456789abcdef";
+            result += std::string("\\x") + hex[byte >> 4] + hex[byte & 0xF];
+        } else {
+            result += c;
         }
     }
+    return result + "\"";
+}
 
-    // If no virtual methods, add placeholder
-    if (!has_virtual_methods) {
-        writeLine("// No methods to implement");
+std::string RustCodeGenerator::charLiteral(const Function& func, const std::string& prefix,
+                                           const std::string& content) {
+    std::string written = prefix + "'" + content + "'";
+    bool wide = !prefix.empty() && prefix != "u8";
+    std::string bytes = decodeEscapes(content, wide);
+    static const char* hex = "0123456789abcdef";
+
+    if (!wide && bytes.size() == 1) {
+        // A char is one byte: b'a' is a u8, like the char it initializes
+        char c = bytes[0];
+        unsigned char byte = static_cast<unsigned char>(c);
+        if (c == '\'' || c == '\\') return std::string("b'\\") + c + "'";
+        if (c == '\n') return "b'\\n'";
+        if (c == '\t') return "b'\\t'";
+        if (c == '\r') return "b'\\r'";
+        if (c == '\0') return "b'\\0'";
+        if (byte < 0x20 || byte >= 0x7F) return std::string("b'\\x") + hex[byte >> 4] + hex[byte & 0xF] + "'";
+        return "b'" + bytes + "'";
     }
 
-    dedent();
-    writeLine("}");
+    // One code point: a Rust char
+    size_t code_points = 0;
+    for (char c : bytes) {
+        if ((static_cast<unsigned char>(c) & 0xC0) != 0x80) code_points++;
+    }
+    if (code_points != 1) {
+        report(Diagnostic::Warning,
+               written + " is a multicharacter literal; its int value is implementation-defined. Left as written",
+               func.name);
+        return written;
+    }
+    std::string rust_char = "'" + bytes + "'";
+    unsigned char first = static_cast<unsigned char>(bytes[0]);
+    if (bytes == "'" || bytes == "\\") {
+        rust_char = "'\\" + bytes + "'";
+    } else if (first < 0x20 || first == 0x7F) {
+        rust_char = std::string("'\\u{") + hex[first >> 4] + hex[first & 0xF] + "}'";
+    }
+    if (!wide) {
+        report(Diagnostic::Warning,
+               written + " does not fit in a char (UTF-8 takes " + std::to_string(bytes.size()) +
+                   " bytes); it becomes the Rust char " + rust_char,
+               func.name);
+        return rust_char;
+    }
+    return prefix == "u" ? "(" + rust_char + " as u16)" : rust_char;
+}
+
+std::string RustCodeGenerator::numericLiteral(const std::string& literal) {
+    static const std::regex hex_pattern(R"(0[xX]([0-9a-fA-F']*)(?:(\.)([0-9a-fA-F']*))?(?:[pP]([+-]?\d+))?(\w*))");
+    static const std::regex binary_pattern(R"(0[bB]([01']+)(\w*))");
+    static const std::regex decimal_pattern(R"((\d[\d']*)?(?:(\.)([\d']*))?(?:[eE]([+-]?\d+))?(\w*))");
+    std::smatch match;
+    bool hex = std::regex_match(literal, match, hex_pattern);
+    if (!hex && !std::regex_match(literal, match, binary_pattern) &&
+        !std::regex_match(literal, match, decimal_pattern)) {
+        return literal;
+    }
+
+    bool binary = !hex && literal.size() > 1 && (literal[1] == 'b' || literal[1] == 'B');
+    std::string digits = match[1].str();
+    std::string suffix = match[binary ? 2 : 5].str();
+    bool floating = !binary && (match[2].matched || match[4].matched);
+    std::replace(digits.begin(), digits.end(), '\'', '_');
+    std::string lower = suffix;
+    std::transform(lower.begin(), lower.end(), lower.begin(), ::tolower);
+
+    if (floating) {
+        if (!lower.empty() && lower != "f" && lower != "l") return literal;  // 1.5_km, 2.0f64
+        std::string rust_suffix = lower == "f" ? "f32" : lower == "l" ? "f64" : "";
+        if (lower == "l") {
+            reportOnce("long double becomes f64; the 80-bit and 128-bit C++ formats keep more precision");
+        }
+        if (hex) {
+            // No hex float literals in Rust: the shortest decimal that reads back exactly
+            std::string text = literal.substr(0, literal.size() - suffix.size());
+            text.erase(std::remove(text.begin(), text.end(), '\''), text.end());
+            bool single = lower == "f";
+            double value = single ? std::strtof(text.c_str(), nullptr) : std::strtod(text.c_str(), nullptr);
+            char buffer[40];
+            std::snprintf(buffer, sizeof(buffer), single ? "%.6g" : "%.15g", value);
+            if ((single ? std::strtof(buffer, nullptr) : std::strtod(buffer, nullptr)) != value) {
+                std::snprintf(buffer, sizeof(buffer), single ? "%.9g" : "%.17g", value);
+            }
+            std::string decimal = buffer;
+            if (decimal.find_first_of(".en") == std::string::npos) decimal += ".0";  // inf/nan stay as written
+            if (decimal.find_first_of("in") != std::string::npos) return literal;
+            return decimal + rust_suffix;
+        }
+        std::string fraction = match[3].str();
+        std::replace(fraction.begin(), fraction.end(), '\'', '_');
+        std::string result = digits.empty() ? "0" : digits;
+        if (match[2].matched) result += "." + (fraction.empty() ? "0" : fraction);
+        if (match[4].matched) result += "e" + match[4].str();
+        return result + rust_suffix;
+    }
+
+    // 0755 is octal in C++ and decimal in Rust
+    std::string prefix = hex ? literal.substr(0, 2) : binary ? literal.substr(0, 2) : "";
+    if (!hex && !binary && digits.size() > 1 && digits[0] == '0') {
+        if (digits.find_first_of("89") != std::string::npos) return literal;
+        prefix = "0o";
+        digits = digits.substr(digits[1] == '_' ? 2 : 1);
+    }
+    if (lower.empty()) return prefix + digits;
+
+    static const std::map<std::string, std::string> suffixes = {
+        {"u", "unsigned int"}, {"l", "long"}, {"ul", "unsigned long"}, {"lu", "unsigned long"},
+        {"ll", "long long"}, {"ull", "unsigned long long"}, {"llu", "unsigned long long"},
+        {"z", "ssize_t"}, {"uz", "size_t"}, {"zu", "size_t"}};
+    auto cpp_type = suffixes.find(lower);
+    if (cpp_type == suffixes.end()) return literal;  // 10_km, 31u32
+
+    // A value too large for the suffixed type has the next larger type, as in C++
+    IntegerFormat format = integerFormat(cpp_type->second);
+    std::string plain = digits;
+    plain.erase(std::remove(plain.begin(), plain.end(), '_'), plain.end());
+    int base = hex ? 16 : binary ? 2 : prefix == "0o" ? 8 : 10;
+    unsigned long long value = std::strtoull(plain.c_str(), nullptr, base);
+    auto type = std::make_shared<Type>(TypeKind::Integer);
+    type->name = cpp_type->second;
+    if (format.bits < 64 && value >> (format.is_signed ? format.bits - 1 : format.bits) != 0) {
+        type->name = format.is_signed ? "long long" : "unsigned long long";
+    }
+    return prefix + digits + convertType(type);
+}
+
+std::string RustCodeGenerator::numericLimit(const Function& func, const NumericLimitInfo& limit) {
+    std::string type = convertType(limit.type);
+    const std::string& member = limit.member;
+    std::map<std::string, std::string> members;
+
+    if (limit.type->kind == TypeKind::Integer) {
+        IntegerFormat format = integerFormat(limit.type->name);
+        int digits = format.is_signed ? format.bits - 1 : format.bits;
+        members = {{"max", type + "::MAX"},
+                   {"min", type + "::MIN"},
+                   {"lowest", type + "::MIN"},
+                   {"digits", format.is_signed ? "(" + type + "::BITS - 1) as i32" : type + "::BITS as i32"},
+                   {"digits10", std::to_string(digits * 30103 / 100000)},
+                   {"radix", "2"},
+                   {"is_signed", format.is_signed ? "true" : "false"},
+                   {"is_integer", "true"}};
+    } else if (limit.type->kind == TypeKind::Float) {
+        // min() is the smallest positive normal value, not the most negative one
+        members = {{"max", type + "::MAX"},
+                   {"min", type + "::MIN_POSITIVE"},
+                   {"lowest", type + "::MIN"},
+                   {"epsilon", type + "::EPSILON"},
+                   {"infinity", type + "::INFINITY"},
+                   {"quiet_NaN", type + "::NAN"},
+                   {"denorm_min", type + "::from_bits(1)"},
+                   {"digits", type + "::MANTISSA_DIGITS as i32"},
+                   {"digits10", type + "::DIGITS as i32"},
+                   {"max_exponent", type + "::MAX_EXP"},
+                   {"min_exponent", type + "::MIN_EXP"},
+                   {"radix", type + "::RADIX as i32"},
+                   {"is_signed", "true"},
+                   {"is_integer", "false"},
+                   {"has_infinity", "true"},
+                   {"has_quiet_NaN", "true"}};
+    }
+
+    auto found = members.find(member);
+    if (found == members.end()) {
+        report(Diagnostic::Warning,
+               "std::numeric_limits<" + limit.type->name + ">::" + member + " has no Rust equivalent; left as written",
+               func.name);
+        return "";
+    }
+    return found->second;
 }
 
 } // namespace hybrid
@@ -0,0 +1,431 @@
+#include "codegen.h"
+#include <algorithm>
+#include <cctype>
+#include <map>
+#include <set>
+#include <vector>
+
+namespace hybrid {
+
+namespace {
+
+bool isIdentifierStart(char c) {
+    return std::isalpha(static_cast<unsigned char>(c)) || c == '_';
+}
+
+bool isIdentifierChar(char c) {
+    return std::isalnum(static_cast<unsigned char>(c)) || c == '_';
+}
+
+bool isUpper(const std::string& name) {
+    return !name.empty() && std::isupper(static_cast<unsigned char>(name[0]));
+}
+
+// MAX, PI: constants, which keep their path like functions
+bool isConstant(const std::string& name) {
+    return isUpper(name) && std::none_of(name.begin(), name.end(), [](char c) {
+        return std::islower(static_cast<unsigned char>(c));
+    });
+}
+
+/**
+ * std items the generator writes without a path (Rc<T>, Duration::from_secs)
+ */
+const std::map<std::string, std::string>& barePaths() {
+    static const std::map<std::string, std::string> paths = {
+        {"Arc", "std::sync::Arc"},
+        {"BTreeMap", "std::collections::BTreeMap"},
+        {"BTreeSet", "std::collections::BTreeSet"},
+        {"Cell", "std::cell::Cell"},
+        {"Duration", "std::time::Duration"},
+        {"HashMap", "std::collections::HashMap"},
+        {"HashSet", "std::collections::HashSet"},
+        {"Instant", "std::time::Instant"},
+        {"Mutex", "std::sync::Mutex"},
+        {"PhantomData", "std::marker::PhantomData"},
+        {"Rc", "std::rc::Rc"},
+        {"RefCell", "std::cell::RefCell"},
+        {"RwLock", "std::sync::RwLock"},
+        {"VecDeque", "std::collections::VecDeque"},
+        {"Weak", "std::rc::Weak"},
+    };
+    return paths;
+}
+
+/**
+ * Names an import must not shadow: the prelude and the primitive types
+ */
+const std::set<std::string>& reservedNames() {
+    static const std::set<std::string> names = {
+        "AsMut", "AsRef", "Box", "Clone", "Copy", "Default", "DoubleEndedIterator", "Drop", "Eq", "Err",
+        "ExactSizeIterator", "Extend", "Fn", "FnMut", "FnOnce", "From", "FromIterator", "Into", "IntoIterator",
+        "Iterator", "None", "Ok", "Option", "Ord", "PartialEq", "PartialOrd", "Result", "Send", "Sized", "Some",
+        "String", "Sync", "ToOwned", "ToString", "TryFrom", "TryInto", "Unpin", "Vec",
+        "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str",
+        "u8", "u16", "u32", "u64", "u128", "usize",
+    };
+    return names;
+}
+
+/**
+ * The code with comments and string and character literals blanked out,
+ * so paths are only looked for in code; offsets are unchanged
+ */
+std::string codeOnly(const std::string& code) {
+    std::string text = code;
+    auto blank = [&text](size_t from, size_t to) {
+        for (size_t i = from; i < to && i < text.size(); ++i) {
+            if (text[i] != '\n') text[i] = ' ';
+        }
+    };
+
+    size_t i = 0;
+    while (i < code.size()) {
+        char c = code[i];
+        if (code.compare(i, 2, "//") == 0) {
+            size_t end = code.find('\n', i);
+            if (end == std::string::npos) end = code.size();
+            blank(i, end);
+            i = end;
+        } else if (code.compare(i, 2, "/*") == 0) {
+            // Block comments nest in Rust
+            size_t j = i + 2;
+            for (int depth = 1; j < code.size() && depth > 0;) {
+                if (code.compare(j, 2, "/*") == 0) { depth++; j += 2; }
+                else if (code.compare(j, 2, "*/") == 0) { depth--; j += 2; }
+                else j++;
+            }
+            blank(i, j);
+            i = j;
+        } else if (c == 'r' && (i == 0 || !isIdentifierChar(code[i - 1])) && i + 1 < code.size() &&
+                   (code[i + 1] == '"' || code[i + 1] == '#')) {
+            // Raw string: r"..." or r#"..."#
+            size_t j = i + 1;
+            size_t hashes = 0;
+            while (j < code.size() && code[j] == '#') { hashes++; j++; }
+            if (j >= code.size() || code[j] != '"') { i++; continue; }
+            std::string closing = "\"" + std::string(hashes, '#');
+            size_t end = code.find(closing, j + 1);
+            end = end == std::string::npos ? code.size() : end + closing.size();
+            blank(i, end);
+            i = end;
+        } else if (c == '"') {
+            size_t j = i + 1;
+            while (j < code.size() && code[j] != '"') j += code[j] == '\\' ? 2 : 1;
+            blank(i, j + 1);
+            i = j + 1;
+        } else if (c == '\'') {
+            // A character literal, or the quote of a lifetime ('a, '_)
+            size_t j = i + 1;
+            if (j < code.size() && code[j] == '\\') {
+                j += 2;
+                while (j < code.size() && code[j] != '\'') j++;
+                blank(i, j + 1);
+                i = j + 1;
+                continue;
+            }
+            size_t width = 1;
+            if (j < code.size()) {
+                unsigned char lead = static_cast<unsigned char>(code[j]);
+                width = lead >= 0xF0 ? 4 : lead >= 0xE0 ? 3 : lead >= 0xC0 ? 2 : 1;
+            }
+            if (j + width < code.size() && code[j + width] == '\'') {
+                blank(i, j + width + 1);
+                i = j + width + 1;
+            } else {
+                i++;
+            }
+        } else {
+            i++;
+        }
+    }
+    return text;
+}
+
+/**
+ * A std type or trait in the code: std::collections::HashMap in
+ * std::collections::HashMap<K, V>
+ */
+struct PathUse {
+    size_t pos = 0;
+    size_t length = 0;
+    std::string path;         // The item: std::process::ExitCode, not ::SUCCESS after it
+    std::string module;       // std::process
+    std::string name;         // ExitCode
+    bool by_module = false;   // Written module::name, as fmt::Result is
+};
+
+// Where a path is rendered from, and the path to import for it
+struct Rendering {
+    std::string text;
+    std::string import;
+};
+
+std::string parentOf(const std::string& path) {
+    size_t pos = path.rfind("::");
+    return pos == std::string::npos ? "" : path.substr(0, pos);
+}
+
+std::string lastOf(const std::string& path) {
+    size_t pos = path.rfind("::");
+    return pos == std::string::npos ? path : path.substr(pos + 2);
+}
+
+/**
+ * Paths named by a use declaration: use std::sync::{self, Arc}; names
+ * std::sync and std::sync::Arc
+ */
+std::vector<std::string> usePaths(const std::string& declaration) {
+    std::string body = declaration.substr(4);   // After "use "
+    body = body.substr(0, body.find(';'));
+    size_t open = body.find("::{");
+    if (open == std::string::npos) return {body};
+
+    std::vector<std::string> paths;
+    std::string prefix = body.substr(0, open);
+    std::string items = body.substr(open + 3, body.rfind('}') - open - 3);
+    size_t start = 0;
+    while (start <= items.size()) {
+        size_t comma = items.find(',', start);
+        if (comma == std::string::npos) comma = items.size();
+        std::string item = items.substr(start, comma - start);
+        size_t first = item.find_first_not_of(' ');
+        if (first != std::string::npos) {
+            item = item.substr(first, item.find_last_not_of(' ') - first + 1);
+            paths.push_back(item == "self" ? prefix : prefix + "::" + item);
+        }
+        start = comma + 1;
+    }
+    return paths;
+}
+
+// Name an imported path binds: the last segment, or the alias of `as`
+std::string boundName(const std::string& path) {
+    size_t alias = path.find(" as ");
+    return alias == std::string::npos ? lastOf(path) : path.substr(alias + 4);
+}
+
+// std, then other crates, then this crate
+int importGroup(const std::string& path) {
+    std::string root = path.substr(0, path.find("::"));
+    if (root == "std" || root == "core" || root == "alloc") return 0;
+    if (root == "crate" || root == "super" || root == "self") return 2;
+    return 1;
+}
+
+// Order of the items of a use list: self, modules, then types
+bool itemBefore(const std::string& a, const std::string& b) {
+    auto rank = [](const std::string& item) { return item == "self" ? 0 : isUpper(item) ? 2 : 1; };
+    if (rank(a) != rank(b)) return rank(a) < rank(b);
+    return a < b;
+}
+
+/**
+ * One use declaration per parent module, alphabetized, in groups
+ */
+std::string useBlock(const std::set<std::string>& imports) {
+    std::map<std::string, std::vector<std::string>> by_parent;
+    for (const auto& path : imports) {
+        std::string plain = path.substr(0, path.find(" as "));
+        std::string item = path.substr(parentOf(plain).size() + (parentOf(plain).empty() ? 0 : 2));
+        by_parent[parentOf(plain)].push_back(item);
+    }
+    // A module imported next to its items: use std::sync::{self, Arc};
+    for (auto& entry : by_parent) {
+        if (entry.first.empty()) continue;
+        std::string parent = parentOf(entry.first);
+        auto outer = by_parent.find(parent);
+        if (outer == by_parent.end()) continue;
+        auto self = std::find(outer->second.begin(), outer->second.end(), lastOf(entry.first));
+        if (self != outer->second.end() && &entry != &*outer) {
+            outer->second.erase(self);
+            entry.second.push_back("self");
+        }
+    }
+
+    std::vector<std::set<std::string>> groups(3);
+    for (auto& entry : by_parent) {
+        if (entry.second.empty()) continue;
+        std::sort(entry.second.begin(), entry.second.end(), itemBefore);
+        int group = importGroup(entry.first.empty() ? entry.second.front() : entry.first);
+        // Modules of a crate root get a line each: use std::fmt; use std::io;
+        if (entry.first.empty() || parentOf(entry.first).empty()) {
+            for (const auto& item : entry.second) {
+                groups[group].insert("use " + (entry.first.empty() ? item : entry.first + "::" + item) + ";\n");
+            }
+        } else if (entry.second.size() == 1) {
+            groups[group].insert(entry.second.front() == "self"
+                                     ? "use " + entry.first + ";\n"
+                                     : "use " + entry.first + "::" + entry.second.front() + ";\n");
+        } else {
+            std::string items;
+            for (const auto& item : entry.second) {
+                if (!items.empty()) items += ", ";
+                items += item;
+            }
+            groups[group].insert("use " + entry.first + "::{" + items + "};\n");
+        }
+    }
+
+    std::string block;
+    for (const auto& group : groups) {
+        if (group.empty()) continue;
+        if (!block.empty()) block += "\n";
+        for (const auto& line : group) block += line;
+    }
+    return block;
+}
+
+} // namespace
+
+std::string RustCodeGenerator::organizeImports(const std::string& code) {
+    // Imports already written (the crate paths of split modules) are kept
+    // and merged: a trait import is used without its name appearing
+    std::set<std::string> imports;
+    std::string body;
+    size_t start = 0;
+    bool after_uses = false;
+    while (start < code.size()) {
+        size_t end = code.find('\n', start);
+        end = end == std::string::npos ? code.size() : end + 1;
+        std::string line = code.substr(start, end - start);
+        start = end;
+        if (line.compare(0, 4, "use ") == 0 && line.find(';') != std::string::npos) {
+            for (const auto& path : usePaths(line)) imports.insert(path);
+            after_uses = true;
+            continue;
+        }
+        if (after_uses && line == "\n") {
+            after_uses = false;
+            continue;
+        }
+        after_uses = false;
+        body += line;
+    }
+
+    std::map<std::string, std::string> bound;   // Name -> path it refers to
+    for (const auto& path : imports) bound[boundName(path)] = path;
+
+    // Names declared in the file, and uppercase names used without a path
+    // the table does not know: an import of either name would shadow it
+    std::string text = codeOnly(body);
+    std::set<std::string> declared;
+    std::set<std::string> bare;
+    std::vector<PathUse> uses;
+    size_t i = 0;
+    std::string previous;
+    while (i < text.size()) {
+        if (!isIdentifierStart(text[i]) || (i > 0 && isIdentifierChar(text[i - 1]))) {
+            i++;
+            continue;
+        }
+        bool qualified = i >= 2 && text.compare(i - 2, 2, "::") == 0;
+        bool member = i >= 1 && text[i - 1] == '.';
+        std::vector<std::string> segments;
+        size_t pos = i;
+        while (true) {
+            size_t end = pos;
+            while (end < text.size() && isIdentifierChar(text[end])) end++;
+            segments.push_back(text.substr(pos, end - pos));
+            pos = end;
+            if (text.compare(pos, 2, "::") != 0 || pos + 2 >= text.size() || !isIdentifierStart(text[pos + 2])) break;
+            pos += 2;
+        }
+
+        if (previous == "struct" || previous == "enum" || previous == "trait" || previous == "type" ||
+            previous == "union" || previous == "mod") {
+            declared.insert(segments.front());
+        }
+        previous = segments.size() == 1 ? segments.front() : "";
+
+        if (segments.front() == "std" && !qualified && !member && segments.size() >= 3) {
+            // The item ends at the first type: std::process::ExitCode::SUCCESS.
+            // Functions and constants keep their path (std::mem::take)
+            size_t item = 0;
+            for (size_t k = 1; k < segments.size(); ++k) {
+                if (isUpper(segments[k])) { item = k; break; }
+            }
+            if (item >= 2 && !isConstant(segments[item])) {
+                PathUse use;
+                use.pos = i;
+                for (size_t k = 0; k <= item; ++k) {
+                    if (k > 0) use.path += "::";
+                    if (k == item) use.module = use.path.substr(0, use.path.size() - 2);
+                    use.path += segments[k];
+                    use.length = use.path.size();
+                }
+                use.name = segments[item];
+                use.by_module = use.module == "std::fmt";
+                uses.push_back(use);
+            }
+        } else if (!qualified && !member && isUpper(segments.front())) {
+            bare.insert(segments.front());
+        }
+        i = pos;
+    }
+
+    // Types written bare get their import, unless the file declares them
+    for (const auto& name : bare) {
+        auto known = barePaths().find(name);
+        if (known == barePaths().end() || declared.count(name) || bound.count(name)) continue;
+        bound[name] = known->second;
+        imports.insert(known->second);
+    }
+
+    // A name wanted by two items binds neither: rc::Weak and sync::Weak
+    std::map<std::string, std::set<std::string>> claims;
+    for (const auto& use : uses) {
+        claims[use.by_module ? lastOf(use.module) : use.name].insert(use.by_module ? use.module : use.path);
+    }
+    auto available = [&](const std::string& name, const std::string& path) {
+        if (reservedNames().count(name) || declared.count(name)) return false;
+        auto existing = bound.find(name);
+        if (existing != bound.end()) return existing->second == path;
+        if (bare.count(name)) return false;
+        auto claim = claims.find(name);
+        return claim == claims.end() || claim->second.size() <= 1;
+    };
+
+    std::map<std::string, Rendering> renderings;
+    std::vector<std::string> paths;
+    for (const auto& use : uses) paths.push_back(use.path);
+    std::sort(paths.begin(), paths.end());
+    paths.erase(std::unique(paths.begin(), paths.end()), paths.end());
+    for (const auto& path : paths) {
+        const PathUse& use = *std::find_if(uses.begin(), uses.end(),
+                                           [&path](const PathUse& u) { return u.path == path; });
+        std::string module_name = lastOf(use.module);
+        Rendering rendering{use.path, ""};
+        if (!use.by_module && available(use.name, use.path)) {
+            rendering = {use.name, use.path};
+        } else if (use.module != "std" && available(module_name, use.module)) {
+            rendering = {module_name + "::" + use.name, use.module};
+        }
+        if (!rendering.import.empty()) {
+            bound[boundName(rendering.import)] = rendering.import;
+            imports.insert(rendering.import);
+        }
+        renderings[path] = rendering;
+    }
+
+    for (auto use = uses.rbegin(); use != uses.rend(); ++use) {
+        body.replace(use->pos, use->length, renderings[use->path].text);
+    }
+    if (imports.empty()) return body;
+
+    // The block goes after the file header and crate attributes
+    size_t insert_at = 0;
+    while (insert_at < body.size()) {
+        size_t end = body.find('\n', insert_at);
+        end = end == std::string::npos ? body.size() : end + 1;
+        std::string line = body.substr(insert_at, end - insert_at);
+        bool header = (line.compare(0, 2, "//") == 0 && line.compare(0, 3, "///") != 0) ||
+                      line.compare(0, 3, "#![") == 0 || line == "\n";
+        if (!header) break;
+        insert_at = end;
+    }
+    body.insert(insert_at, useBlock(imports) + "\n");
+    return body;
+}
+
+} // namespace hybrid
@@ -0,0 +1,147 @@
+#include "explain.h"
+#include <algorithm>
+#include <set>
+
+namespace hybrid {
+
+namespace {
+
+/**
+ * shared_ptr<Entry> in "field cache (std::shared_ptr<Entry>)", as the
+ * thread-safety notes spell it
+ */
+std::string sharedPointee(const std::string& subject) {
+    size_t start = subject.find("shared_ptr<");
+    if (start == std::string::npos) return "";
+    int depth = 0;
+    for (size_t i = start; i < subject.size(); ++i) {
+        if (subject[i] == '<') depth++;
+        else if (subject[i] == '>' && --depth == 0) return subject.substr(start, i - start + 1);
+    }
+    return "";
+}
+
+} // namespace
+
+const std::vector<std::pair<std::string, std::string>>& explainRules() {
+    static const std::vector<std::pair<std::string, std::string>> rules = {
+        {"OWN-BOX", "std::unique_ptr<T> becomes Box<T>"},
+        {"OWN-RC", "std::shared_ptr<T> kept on one thread becomes Rc<T>"},
+        {"OWN-ARC", "std::shared_ptr<T> reaching another thread becomes Arc<T>"},
+        {"OWN-ARC-MUTEX", "std::shared_ptr<T> reaching another thread with unsynchronized writes becomes "
+                          "Arc<Mutex<T>>"},
+        {"OWN-WEAK", "std::weak_ptr<T> becomes Weak<T>"},
+        {"PTR-RAW", "a raw pointer stays *const T or *mut T"},
+        {"STR-BORROW", "a const std::string& or std::string_view becomes &str"},
+        {"STR-COERCE", "an owned String passed to a &str parameter is borrowed with &"},
+        {"STR-OWN", "a &str copied into an owned String becomes .to_string()"},
+        {"SLICE-FUSE", "a (T* ptr, size_t len) parameter pair becomes one &[T] or &mut [T]"},
+        {"SLICE-BOX", "a pointer to a buffer from new T[n] released with delete[] becomes Box<[T]>"},
+        {"SELF-ARENA", "an intrusive link becomes a generational Id into an Arena (--self-ref arena)"},
+        {"LOCK-REFCELL", "a mutex only ever locked from one thread becomes RefCell<()> (--runtime-trace)"},
+        {"MAP-PACK", "a library type is translated by a mapping pack (--mapping-pack)"},
+        {"COPY-DERIVE", "a trivially copyable class derives Clone and Copy"},
+        {"CLONE-DERIVE", "a copyable class owning resources derives Clone"},
+        {"CLONE-IMPL", "a user-defined copy constructor becomes impl Clone"},
+        {"COPY-NONE", "a class that cannot be copied gets no Clone"},
+        {"COPY-CLONE", "an implicit copy of a non-Copy value becomes .clone()"},
+        {"COPY-DEREF", "a Copy value read through a reference parameter is dereferenced"},
+        {"MOVE-PLAIN", "std::move of a value not used again becomes a plain move"},
+        {"MOVE-TAKE", "std::move of a value used afterwards becomes std::mem::take"},
+        {"MOVE-REPLACE", "std::exchange becomes std::mem::replace"},
+        {"ERR-RESULT", "a function that may throw returns Result<T, Box<dyn Error>>"},
+        {"ERR-STATUS", "a function returning an error-code enum returns Result<T, Status>"},
+        {"ERR-STATUS-PARAM", "an error-code enum parameter is passed as Result<(), Status>"},
+        {"CTOR-TRY-NEW", "a constructor that can fail midway becomes fn try_new() -> Result<Self, _>"},
+        {"FFI-RETAIN", "a function is kept in C++ and called through extern \"C\""},
+        {"DEFER-STUB", "a deferred class gets a trait of its methods with todo!() bodies (--defer)"},
+    };
+    return rules;
+}
+
+bool explainSymbol(const IR& ir, const std::vector<Decision>& decisions, const std::vector<Diagnostic>& diagnostics,
+                   const std::string& symbol, Explanation& explanation) {
+    explanation = Explanation();
+    explanation.symbol = symbol;
+
+    // Contexts belonging to the symbol: decisions name Class::method,
+    // analysis diagnostics the bare method name
+    std::set<std::string> contexts = {symbol};
+    size_t scope = symbol.rfind("::");
+    std::string owner = scope == std::string::npos ? "" : symbol.substr(0, scope);
+    std::string member = scope == std::string::npos ? symbol : symbol.substr(scope + 2);
+    const ClassDecl* class_decl = ir.findClass(owner.empty() ? symbol : owner);
+
+    if (owner.empty() && class_decl) {
+        explanation.kind = "class";
+        for (const auto& method : class_decl->methods) {
+            contexts.insert(symbol + "::" + method.name);
+            contexts.insert(method.name);
+        }
+    } else if (class_decl && std::any_of(class_decl->methods.begin(), class_decl->methods.end(),
+                                         [&member](const Function& method) { return method.name == member; })) {
+        explanation.kind = "method";
+        contexts.insert(member);
+    } else if (owner.empty()) {
+        for (const auto& enum_decl : ir.getEnums()) {
+            if (enum_decl.name != symbol) continue;
+            explanation.kind = "enum";
+            if (!enum_decl.to_string_function.empty()) contexts.insert(enum_decl.to_string_function);
+            if (!enum_decl.from_string_function.empty()) contexts.insert(enum_decl.from_string_function);
+        }
+        for (const auto& func : ir.getFunctions()) {
+            if (func.name == symbol) explanation.kind = "function";
+        }
+    }
+    if (explanation.kind.empty()) return false;
+
+    // Why Arc: the note of the analysis that saw the pointee cross threads
+    std::set<std::string> shared;
+    for (const auto& decision : decisions) {
+        if (!contexts.count(decision.context)) continue;
+        explanation.decisions.push_back(decision);
+        if (decision.rule == "OWN-ARC" || decision.rule == "OWN-ARC-MUTEX") {
+            std::string pointee = sharedPointee(decision.subject);
+            if (!pointee.empty()) shared.insert(pointee);
+        }
+    }
+
+    std::set<std::string> seen;
+    for (const auto& diagnostic : diagnostics) {
+        bool related = contexts.count(diagnostic.context) > 0;
+        for (const auto& pointee : shared) {
+            related = related || diagnostic.message.find(pointee) != std::string::npos;
+        }
+        if (related && seen.insert(diagnostic.toString()).second) explanation.diagnostics.push_back(diagnostic);
+    }
+    return true;
+}
+
+std::string Explanation::toString() const {
+    std::string text = "Decisions for " + symbol + " (" + kind + "):\n";
+    if (decisions.empty()) {
+        text += "  no translation rule fired: the declaration has a direct Rust equivalent\n";
+    }
+
+    // Grouped by declaration, in the order each was generated
+    std::vector<std::string> order;
+    for (const auto& decision : decisions) {
+        if (std::find(order.begin(), order.end(), decision.context) == order.end()) order.push_back(decision.context);
+    }
+    for (const auto& context : order) {
+        text += "  " + context + "\n";
+        for (const auto& decision : decisions) {
+            if (decision.context != context) continue;
+            text += "    [" + decision.rule + "] " + decision.subject + " -> " + decision.outcome + "\n";
+            text += "        " + decision.reason + "\n";
+        }
+    }
+
+    if (!diagnostics.empty()) {
+        text += "Reported:\n";
+        for (const auto& diagnostic : diagnostics) text += "  " + diagnostic.toString() + "\n";
+    }
+    return text;
+}
+
+} // namespace hybrid
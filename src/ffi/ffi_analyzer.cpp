@@ -44,7 +44,7 @@ void FFIAnalyzer::initializeTypeMappings() {
     cpp_to_rust_types_ = {
         {"void", "()"},
         {"bool", "bool"},
-        {"char", "i8"},
+        {"char", "std::ffi::c_char"},
         {"unsigned char", "u8"},
         {"short", "i16"},
         {"unsigned short", "u16"},
@@ -65,8 +65,8 @@ void FFIAnalyzer::initializeTypeMappings() {
         {"uint32_t", "u32"},
         {"uint64_t", "u64"},
         {"size_t", "usize"},
-        {"const char*", "*const i8"},
-        {"char*", "*mut i8"},
+        {"const char*", "*const std::ffi::c_char"},
+        {"char*", "*mut std::ffi::c_char"},
     };
 
     // C++ to Go (cgo) type mappings
@@ -0,0 +1,33 @@
+#include "fuzz.h"
+#include "parser.h"
+#include "codegen.h"
+#include <stdexcept>
+#include <string>
+
+namespace hybrid {
+
+bool transpileFuzz(const uint8_t* data, size_t size, FuzzStages stages) {
+    std::string source(reinterpret_cast<const char*>(data), size);
+
+    try {
+        IR ir = Parser::parseString(source);
+        if (stages == FuzzStages::Parser) {
+            return true;
+        }
+
+        RustCodeGenerator rust_gen;
+        rust_gen.generate(ir);
+
+        GoCodeGenerator go_gen;
+        go_gen.generate(ir);
+
+        return true;
+    }
+    catch (const std::runtime_error&) {
+        // Deliberate rejection (overlong declarations, unbalanced input);
+        // logic errors such as std::out_of_range propagate as crashes
+        return false;
+    }
+}
+
+} // namespace hybrid
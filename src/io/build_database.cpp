@@ -0,0 +1,284 @@
+#include "build_database.h"
+#include "json.h"
+#include <filesystem>
+#include <regex>
+#include <stdexcept>
+
+namespace hybrid {
+
+namespace {
+
+/**
+ * Macros the compiler defines for a target triple (the host when empty)
+ */
+void addPlatformDefines(const std::string& target, bool msvc, BuildConfiguration& config) {
+    auto has = [&target](const char* part) { return target.find(part) != std::string::npos; };
+    auto define = [&config](const std::string& name, const std::string& value) {
+        config.defines.emplace_back(name, value);
+    };
+
+    bool windows = has("windows") || has("win32") || has("mingw") || (target.empty() && msvc);
+    bool apple = has("apple") || has("darwin") || has("macos") || has("ios");
+    bool linux_os = has("linux");
+    if (target.empty() && !msvc) {
+#if defined(_WIN32)
+        windows = true;
+#elif defined(__APPLE__)
+        apple = true;
+#else
+        linux_os = true;
+#endif
+    }
+
+    bool wide = target.empty() ? sizeof(void*) == 8 : has("x86_64") || has("amd64") || has("aarch64") || has("arm64");
+    if (windows) {
+        define("_WIN32", "1");
+        if (wide) define("_WIN64", "1");
+    } else if (apple) {
+        define("__APPLE__", "1");
+        define("__MACH__", "1");
+        define("__unix__", "1");
+    } else if (linux_os) {
+        define("__linux__", "1");
+        define("__unix__", "1");
+        if (has("android")) define("__ANDROID__", "1");
+    } else if (has("freebsd")) {
+        define("__FreeBSD__", "1");
+        define("__unix__", "1");
+    }
+
+    if (has("x86_64") || has("amd64")) {
+        define(msvc ? "_M_X64" : "__x86_64__", "1");
+    } else if (has("aarch64") || has("arm64")) {
+        define(msvc ? "_M_ARM64" : "__aarch64__", "1");
+    } else if (has("i386") || has("i686")) {
+        define(msvc ? "_M_IX86" : "__i386__", "1");
+    } else if (has("arm")) {
+        define(msvc ? "_M_ARM" : "__arm__", "1");
+    }
+}
+
+/**
+ * __cplusplus for -std=c++17, gnu++20, c++2a...
+ */
+std::string cplusplusValue(const std::string& standard) {
+    static const std::vector<std::pair<std::string, std::string>> values = {
+        {"98", "199711L"}, {"03", "199711L"}, {"11", "201103L"}, {"0x", "201103L"}, {"14", "201402L"},
+        {"1y", "201402L"}, {"17", "201703L"}, {"1z", "201703L"}, {"20", "202002L"}, {"2a", "202002L"},
+        {"23", "202302L"}, {"2b", "202302L"}, {"latest", "202302L"},
+    };
+    for (const auto& value : values) {
+        if (standard.size() >= value.first.size() &&
+            standard.compare(standard.size() - value.first.size(), value.first.size(), value.first) == 0) {
+            return value.second;
+        }
+    }
+    return "";
+}
+
+} // namespace
+
+bool BuildDatabase::parse(const std::string& text, std::string& error) {
+    JsonValue root;
+    try {
+        root = JsonValue::parse(text);
+    } catch (const std::runtime_error& e) {
+        error = e.what();
+        return false;
+    }
+    if (!root.isArray()) {
+        error = "a compilation database is an array of {\"directory\", \"file\", \"command\" or \"arguments\"}";
+        return false;
+    }
+
+    commands_.clear();
+    for (const auto& entry : root.asArray()) {
+        if (!entry["directory"].isString() || !entry["file"].isString() ||
+            !(entry["command"].isString() || entry["arguments"].isArray())) {
+            error = "every entry needs \"directory\" and \"file\" strings and a \"command\" or \"arguments\"";
+            return false;
+        }
+        CompileCommand command;
+        command.directory = entry["directory"].asString();
+        command.file = normalize(command.directory, entry["file"].asString());
+        if (entry["output"].isString()) command.output = normalize(command.directory, entry["output"].asString());
+        if (entry["arguments"].isArray()) {
+            for (const auto& argument : entry["arguments"].asArray()) {
+                command.arguments.push_back(argument.asString());
+            }
+        } else {
+            command.arguments = splitCommand(entry["command"].asString());
+        }
+        commands_.push_back(command);
+    }
+    return true;
+}
+
+std::vector<const CompileCommand*> BuildDatabase::commandsFor(const std::string& path) const {
+    std::vector<const CompileCommand*> result;
+    std::string file = normalize("", path);
+    for (const auto& command : commands_) {
+        if (command.file == file) result.push_back(&command);
+    }
+    return result;
+}
+
+bool BuildDatabase::select(const std::string& path, const std::string& target_config, BuildConfiguration& config,
+                           std::string& error, std::string& note) const {
+    std::vector<const CompileCommand*> commands = commandsFor(path);
+    if (commands.empty()) {
+        error = "'" + path + "' is not in the compilation database";
+        return false;
+    }
+
+    const CompileCommand* chosen = nullptr;
+    if (target_config.empty()) {
+        chosen = commands[0];
+        if (commands.size() > 1) {
+            note = "'" + path + "' is compiled " + std::to_string(commands.size()) + " times in the compilation " +
+                   "database; using the command in " + chosen->directory + " (choose one with --target-config)";
+        }
+    } else {
+        for (const CompileCommand* command : commands) {
+            if (matchesConfig(*command, target_config)) {
+                chosen = command;
+                break;
+            }
+        }
+        if (!chosen) {
+            error = "no command compiling '" + path + "' matches --target-config " + target_config +
+                    " (compiled in:";
+            for (const CompileCommand* command : commands) error += " " + command->directory;
+            error += ")";
+            return false;
+        }
+    }
+    config = configurationOf(*chosen);
+    return true;
+}
+
+bool BuildDatabase::matchesConfig(const CompileCommand& command, const std::string& target_config) {
+    BuildConfiguration config = configurationOf(command);
+    if (config.target == target_config) return true;
+    for (const auto& path : {command.output, command.directory}) {
+        for (const auto& component : std::filesystem::path(path)) {
+            if (component.string() == target_config) return true;
+        }
+    }
+    // Only macros the command line defines, not those of the platform
+    for (size_t i = 0; i < command.arguments.size(); ++i) {
+        const std::string& argument = command.arguments[i];
+        std::string define;
+        if ((argument == "-D" || argument == "/D") && i + 1 < command.arguments.size()) {
+            define = command.arguments[i + 1];
+        } else if (argument.size() > 2 && (argument.compare(0, 2, "-D") == 0 || argument.compare(0, 2, "/D") == 0)) {
+            define = argument.substr(2);
+        }
+        if (!define.empty() && define.substr(0, define.find('=')) == target_config) return true;
+    }
+    return false;
+}
+
+BuildConfiguration BuildDatabase::configurationOf(const CompileCommand& command) {
+    BuildConfiguration config;
+    const auto& args = command.arguments;
+    std::string compiler = args.empty() ? "" : std::filesystem::path(args[0]).filename().string();
+    bool msvc = compiler == "cl" || compiler == "cl.exe" || compiler.find("clang-cl") != std::string::npos;
+
+    // -DX, -Idir: the value attached or in the next argument; --target=t,
+    // -std=c++17, /std:c++17: after = or :, or in the next argument
+    auto value = [&args](size_t& i, const std::string& flag) -> std::string {
+        const std::string& argument = args[i];
+        if (argument == flag) return i + 1 < args.size() ? args[++i] : "";
+        if (argument.size() <= flag.size() || argument.compare(0, flag.size(), flag) != 0) return "";
+        if (flag.size() == 2) return argument.substr(2);
+        char separator = argument[flag.size()];
+        return separator == '=' || separator == ':' ? argument.substr(flag.size() + 1) : "";
+    };
+    std::vector<std::pair<std::string, std::string>> command_defines;
+    for (size_t i = 1; i < args.size(); ++i) {
+        std::string found;
+        if (!(found = value(i, "--target")).empty() || !(found = value(i, "-target")).empty()) {
+            config.target = found;
+        } else if (!(found = value(i, "-std")).empty() || (msvc && !(found = value(i, "/std")).empty())) {
+            config.standard = found;
+        } else if (!(found = value(i, "-D")).empty() || (msvc && !(found = value(i, "/D")).empty())) {
+            size_t equals = found.find('=');
+            command_defines.emplace_back(found.substr(0, equals),
+                                         equals == std::string::npos ? "1" : found.substr(equals + 1));
+        } else if (!(found = value(i, "-U")).empty() || (msvc && !(found = value(i, "/U")).empty())) {
+            config.undefines.push_back(found);
+        } else if (!(found = value(i, "-iquote")).empty() || !(found = value(i, "-isystem")).empty() ||
+                   !(found = value(i, "-I")).empty() || (msvc && !(found = value(i, "/I")).empty())) {
+            config.include_paths.push_back(normalize(command.directory, found));
+        }
+    }
+
+    // Compiler, then platform, then the command line
+    if (msvc) {
+        config.defines.emplace_back("_MSC_VER", "1930");
+    } else {
+        config.defines.emplace_back("__GNUC__", "4");
+        if (compiler.find("clang") != std::string::npos) config.defines.emplace_back("__clang__", "1");
+    }
+    addPlatformDefines(config.target, msvc, config);
+    std::string cplusplus = cplusplusValue(config.standard.empty() ? (msvc ? "14" : "17") : config.standard);
+    if (!cplusplus.empty()) config.defines.emplace_back("__cplusplus", cplusplus);
+    config.defines.insert(config.defines.end(), command_defines.begin(), command_defines.end());
+    return config;
+}
+
+std::vector<std::string> BuildDatabase::splitCommand(const std::string& command) {
+    std::vector<std::string> arguments;
+    std::string current;
+    bool in_argument = false;
+    char quote = 0;
+    for (size_t i = 0; i < command.size(); ++i) {
+        char c = command[i];
+        if (quote) {
+            if (c == quote) {
+                quote = 0;
+            } else if (c == '\\' && quote == '"' && i + 1 < command.size() &&
+                       (command[i + 1] == '"' || command[i + 1] == '\\')) {
+                current += command[++i];
+            } else {
+                current += c;
+            }
+        } else if (c == '"' || c == '\'') {
+            quote = c;
+            in_argument = true;
+        } else if (c == '\\' && i + 1 < command.size()) {
+            current += command[++i];
+            in_argument = true;
+        } else if (c == ' ' || c == '\t' || c == '\n') {
+            if (in_argument) arguments.push_back(current);
+            current.clear();
+            in_argument = false;
+        } else {
+            current += c;
+            in_argument = true;
+        }
+    }
+    if (in_argument) arguments.push_back(current);
+    return arguments;
+}
+
+std::string BuildDatabase::targetOf(const CompileCommand& command) {
+    static const std::regex object_dir(R"(CMakeFiles[/\\]([^/\\]+)\.dir[/\\])");
+    std::smatch match;
+    if (std::regex_search(command.output, match, object_dir)) return match[1].str();
+    for (const auto& argument : command.arguments) {
+        if (std::regex_search(argument, match, object_dir)) return match[1].str();
+    }
+    return "";
+}
+
+std::string BuildDatabase::normalize(const std::string& directory, const std::string& path) {
+    std::filesystem::path result(path);
+    if (result.is_relative()) {
+        result = directory.empty() ? std::filesystem::absolute(result) : std::filesystem::path(directory) / result;
+    }
+    return result.lexically_normal().string();
+}
+
+} // namespace hybrid
@@ -0,0 +1,67 @@
+#include "file_system.h"
+#include <fstream>
+#include <sstream>
+#include <filesystem>
+
+namespace hybrid {
+
+bool DiskFileSystem::readFile(const std::string& path, std::string& content) {
+    std::ifstream file(path);
+    if (!file.is_open()) {
+        return false;
+    }
+
+    std::stringstream buffer;
+    buffer << file.rdbuf();
+    content = buffer.str();
+    return true;
+}
+
+bool DiskFileSystem::writeFile(const std::string& path, const std::string& content) {
+    std::filesystem::path parent = std::filesystem::path(path).parent_path();
+    if (!parent.empty()) {
+        std::error_code ec;
+        std::filesystem::create_directories(parent, ec);
+    }
+
+    std::ofstream file(path);
+    if (!file.is_open()) {
+        return false;
+    }
+
+    file << content;
+    return static_cast<bool>(file);
+}
+
+bool MemoryFileSystem::readFile(const std::string& path, std::string& content) {
+    auto it = files_.find(path);
+    if (it == files_.end()) {
+        return false;
+    }
+    content = it->second;
+    return true;
+}
+
+bool MemoryFileSystem::writeFile(const std::string& path, const std::string& content) {
+    files_[path] = content;
+    return true;
+}
+
+bool RecordingFileSystem::readFile(const std::string& path, std::string& content) {
+    auto it = written_.find(path);
+    if (!write_through_ && it != written_.end()) {
+        content = it->second;
+        return true;
+    }
+    return base_->readFile(path, content);
+}
+
+bool RecordingFileSystem::writeFile(const std::string& path, const std::string& content) {
+    if (write_through_ && !base_->writeFile(path, content)) {
+        return false;
+    }
+    written_[path] = content;
+    return true;
+}
+
+} // namespace hybrid
@@ -0,0 +1,354 @@
+#include "preprocessor.h"
+#include <algorithm>
+#include <cctype>
+#include <cstdlib>
+#include <cstring>
+#include <sstream>
+
+namespace hybrid {
+
+namespace {
+
+/**
+ * Directive text without comments: #if FOO // for bar
+ */
+std::string stripComments(const std::string& text) {
+    std::string result;
+    for (size_t i = 0; i < text.size(); ++i) {
+        if (text.compare(i, 2, "//") == 0) break;
+        if (text.compare(i, 2, "/*") == 0) {
+            size_t end = text.find("*/", i + 2);
+            if (end == std::string::npos) break;
+            i = end + 1;
+            result += ' ';
+            continue;
+        }
+        result += text[i];
+    }
+    return result;
+}
+
+std::string trim(const std::string& text) {
+    size_t begin = text.find_first_not_of(" \t\r\n");
+    if (begin == std::string::npos) return "";
+    size_t end = text.find_last_not_of(" \t\r\n");
+    return text.substr(begin, end - begin + 1);
+}
+
+/**
+ * Recursive descent over the tokens of an #if condition, C precedence
+ */
+class ConditionParser {
+public:
+    explicit ConditionParser(const std::vector<std::string>& tokens) : tokens_(tokens) {}
+
+    long parse() { return conditional(); }
+
+private:
+    const std::vector<std::string>& tokens_;
+    size_t pos_ = 0;
+
+    const std::string& peek() const {
+        static const std::string end;
+        return pos_ < tokens_.size() ? tokens_[pos_] : end;
+    }
+
+    bool accept(const std::string& token) {
+        if (peek() != token) return false;
+        pos_++;
+        return true;
+    }
+
+    long conditional() {
+        long condition = binary(1);
+        if (!accept("?")) return condition;
+        long then_value = conditional();
+        accept(":");
+        long else_value = conditional();
+        return condition ? then_value : else_value;
+    }
+
+    static int precedence(const std::string& op) {
+        static const std::map<std::string, int> levels = {
+            {"||", 1}, {"&&", 2}, {"|", 3}, {"^", 4}, {"&", 5}, {"==", 6}, {"!=", 6}, {"<", 7}, {">", 7},
+            {"<=", 7}, {">=", 7}, {"<<", 8}, {">>", 8}, {"+", 9}, {"-", 9}, {"*", 10}, {"/", 10}, {"%", 10},
+        };
+        auto level = levels.find(op);
+        return level == levels.end() ? 0 : level->second;
+    }
+
+    long binary(int min_level) {
+        long left = unary();
+        while (precedence(peek()) >= min_level) {
+            std::string op = tokens_[pos_++];
+            long right = binary(precedence(op) + 1);
+            if (op == "||") left = left || right;
+            else if (op == "&&") left = left && right;
+            else if (op == "|") left |= right;
+            else if (op == "^") left ^= right;
+            else if (op == "&") left &= right;
+            else if (op == "==") left = left == right;
+            else if (op == "!=") left = left != right;
+            else if (op == "<") left = left < right;
+            else if (op == ">") left = left > right;
+            else if (op == "<=") left = left <= right;
+            else if (op == ">=") left = left >= right;
+            else if (op == "<<") left <<= right;
+            else if (op == ">>") left >>= right;
+            else if (op == "+") left += right;
+            else if (op == "-") left -= right;
+            else if (op == "*") left *= right;
+            else if (op == "/") left = right ? left / right : 0;
+            else if (op == "%") left = right ? left % right : 0;
+        }
+        return left;
+    }
+
+    long unary() {
+        if (accept("!")) return !unary();
+        if (accept("~")) return ~unary();
+        if (accept("-")) return -unary();
+        if (accept("+")) return unary();
+        if (accept("(")) {
+            long value = conditional();
+            accept(")");
+            return value;
+        }
+        if (pos_ >= tokens_.size()) return 0;
+        const std::string& token = tokens_[pos_++];
+        if (token.size() >= 3 && token[0] == '\'') {
+            return token[1] == '\\' ? 0 : static_cast<unsigned char>(token[1]);
+        }
+        // 201703L, 0x10u: the suffix does not change the value
+        std::string digits = token;
+        while (!digits.empty() && std::strchr("uUlL", digits.back())) digits.pop_back();
+        return std::strtol(digits.c_str(), nullptr, 0);
+    }
+};
+
+} // namespace
+
+Preprocessor::Preprocessor(const BuildConfiguration& config) {
+    for (const auto& define : config.defines) {
+        macros_[define.first] = define.second;
+    }
+    for (const auto& name : config.undefines) {
+        macros_.erase(name);
+    }
+}
+
+std::string Preprocessor::process(const std::string& source, const IncludeHandler& on_include) {
+    struct Group {
+        bool enclosing_active;  // The lines around the #if are kept
+        bool taken;             // A branch was chosen already
+        bool active;            // Lines of the current branch are kept
+    };
+    std::vector<Group> groups;
+    auto active = [&groups]() { return groups.empty() || groups.back().active; };
+
+    std::vector<std::string> lines;
+    std::istringstream input(source);
+    std::string line;
+    while (std::getline(input, line)) lines.push_back(line);
+    bool trailing_newline = !source.empty() && source.back() == '\n';
+
+    std::string output;
+    bool in_comment = false;
+    for (size_t i = 0; i < lines.size(); ++i) {
+        // A directive continues on the next line after a backslash
+        size_t last = i;
+        std::string logical = lines[i];
+        while (!logical.empty() && logical.back() == '\\' && last + 1 < lines.size()) {
+            logical.pop_back();
+            logical += lines[++last];
+        }
+
+        size_t hash = logical.find_first_not_of(" \t");
+        bool directive = !in_comment && hash != std::string::npos && logical[hash] == '#';
+
+        // Block comments may hide a '#' at the start of a line
+        for (size_t j = 0; j < logical.size(); ++j) {
+            if (in_comment) {
+                if (logical.compare(j, 2, "*/") == 0) {
+                    in_comment = false;
+                    j++;
+                }
+            } else if (logical[j] == '"' || logical[j] == '\'') {
+                char quote = logical[j];
+                for (++j; j < logical.size() && logical[j] != quote; ++j) {
+                    if (logical[j] == '\\') ++j;
+                }
+            } else if (logical.compare(j, 2, "//") == 0) {
+                break;
+            } else if (logical.compare(j, 2, "/*") == 0) {
+                in_comment = true;
+                j++;
+            }
+        }
+
+        bool keep = active();
+        if (directive) {
+            std::string text = trim(stripComments(logical.substr(hash + 1)));
+            size_t name_end = 0;
+            while (name_end < text.size() && std::isalpha(static_cast<unsigned char>(text[name_end]))) name_end++;
+            std::string name = text.substr(0, name_end);
+            std::string rest = trim(text.substr(name_end));
+
+            if (name == "if" || name == "ifdef" || name == "ifndef") {
+                bool enclosing = active();
+                bool condition = false;
+                if (enclosing) {
+                    if (name == "if") condition = evaluate(rest) != 0;
+                    else condition = isDefined(trim(rest.substr(0, rest.find_first_of(" \t")))) == (name == "ifdef");
+                }
+                groups.push_back({enclosing, condition, enclosing && condition});
+                keep = false;
+            } else if ((name == "elif" || name == "elifdef" || name == "elifndef") && !groups.empty()) {
+                Group& group = groups.back();
+                group.active = false;
+                if (group.enclosing_active && !group.taken) {
+                    bool condition = name == "elif" ? evaluate(rest) != 0 : isDefined(rest) == (name == "elifdef");
+                    group.active = condition;
+                    group.taken = condition;
+                }
+                keep = false;
+            } else if (name == "else" && !groups.empty()) {
+                Group& group = groups.back();
+                group.active = group.enclosing_active && !group.taken;
+                group.taken = true;
+                keep = false;
+            } else if (name == "endif" && !groups.empty()) {
+                groups.pop_back();
+                keep = false;
+            } else if (keep && name == "define") {
+                size_t macro_end = 0;
+                while (macro_end < rest.size() &&
+                       (std::isalnum(static_cast<unsigned char>(rest[macro_end])) || rest[macro_end] == '_')) {
+                    macro_end++;
+                }
+                std::string macro = rest.substr(0, macro_end);
+                if (macro_end < rest.size() && rest[macro_end] == '(') {
+                    function_macros_.insert(macro);
+                    macros_[macro] = "";
+                } else {
+                    macros_[macro] = trim(rest.substr(macro_end));
+                }
+            } else if (keep && name == "undef") {
+                macros_.erase(rest);
+                function_macros_.erase(rest);
+            } else if (keep && name == "include" && on_include && rest.size() > 2) {
+                char close = rest[0] == '"' ? '"' : rest[0] == '<' ? '>' : 0;
+                size_t end = close ? rest.find(close, 1) : std::string::npos;
+                if (end != std::string::npos) on_include(rest.substr(1, end - 1), close == '>');
+            }
+        }
+
+        for (size_t j = i; j <= last; ++j) {
+            if (keep) output += lines[j];
+            if (j + 1 < lines.size() || trailing_newline) output += '\n';
+        }
+        i = last;
+    }
+    return output;
+}
+
+long Preprocessor::evaluate(const std::string& condition) {
+    size_t notes_before = notes_.size();
+    std::vector<std::string> tokens = expand(tokenize(condition), 0);
+    if (notes_.size() > notes_before) {
+        // One note per condition, naming what was assumed
+        std::string assumed;
+        for (size_t i = notes_before; i < notes_.size(); ++i) assumed += (assumed.empty() ? "" : ", ") + notes_[i];
+        notes_.resize(notes_before);
+        notes_.push_back(assumed + " cannot be evaluated without a compiler; it counts as false in #if " +
+                         condition);
+    }
+    return ConditionParser(tokens).parse();
+}
+
+std::vector<std::string> Preprocessor::expand(const std::vector<std::string>& tokens, int depth) {
+    std::vector<std::string> result;
+    for (size_t i = 0; i < tokens.size(); ++i) {
+        const std::string& token = tokens[i];
+        bool identifier = std::isalpha(static_cast<unsigned char>(token[0])) || token[0] == '_';
+        if (!identifier) {
+            result.push_back(token);
+            continue;
+        }
+
+        // defined NAME, defined(NAME)
+        if (token == "defined") {
+            size_t name = i + 1;
+            bool parenthesized = name < tokens.size() && tokens[name] == "(";
+            if (parenthesized) name++;
+            result.push_back(name < tokens.size() && isDefined(tokens[name]) ? "1" : "0");
+            i = parenthesized ? name + 1 : name;
+            continue;
+        }
+
+        // __has_include(<x>), __has_cpp_attribute(...), function-like macros
+        if (i + 1 < tokens.size() && tokens[i + 1] == "(") {
+            int parens = 0;
+            size_t end = i + 1;
+            for (; end < tokens.size(); ++end) {
+                if (tokens[end] == "(") parens++;
+                else if (tokens[end] == ")" && --parens == 0) break;
+            }
+            std::string call = token + "(";
+            for (size_t j = i + 2; j < end && j < tokens.size(); ++j) call += tokens[j];
+            notes_.push_back(call + ")");
+            result.push_back("0");
+            i = end;
+            continue;
+        }
+
+        auto macro = macros_.find(token);
+        if (token == "true") {
+            result.push_back("1");
+        } else if (macro == macros_.end() || function_macros_.count(token)) {
+            // Unknown identifiers are 0, as for the compiler
+            result.push_back("0");
+        } else if (depth < 32) {
+            std::vector<std::string> replacement = expand(tokenize(macro->second), depth + 1);
+            if (replacement.empty()) replacement.push_back("0");
+            result.push_back("(");
+            result.insert(result.end(), replacement.begin(), replacement.end());
+            result.push_back(")");
+        } else {
+            result.push_back("0");
+        }
+    }
+    return result;
+}
+
+std::vector<std::string> Preprocessor::tokenize(const std::string& text) {
+    std::vector<std::string> tokens;
+    for (size_t i = 0; i < text.size();) {
+        char c = text[i];
+        if (std::isspace(static_cast<unsigned char>(c))) {
+            i++;
+        } else if (std::isalnum(static_cast<unsigned char>(c)) || c == '_') {
+            size_t start = i;
+            while (i < text.size() && (std::isalnum(static_cast<unsigned char>(text[i])) || text[i] == '_')) i++;
+            tokens.push_back(text.substr(start, i - start));
+        } else if (c == '\'' || c == '"') {
+            size_t start = i;
+            for (++i; i < text.size() && text[i] != c; ++i) {
+                if (text[i] == '\\') ++i;
+            }
+            i = std::min(i + 1, text.size());
+            tokens.push_back(text.substr(start, i - start));
+        } else {
+            static const std::vector<std::string> two_char = {"||", "&&", "==", "!=", "<=", ">=", "<<", ">>"};
+            std::string op(1, c);
+            for (const auto& candidate : two_char) {
+                if (text.compare(i, 2, candidate) == 0) op = candidate;
+            }
+            tokens.push_back(op);
+            i += op.size();
+        }
+    }
+    return tokens;
+}
+
+} // namespace hybrid
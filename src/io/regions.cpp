@@ -0,0 +1,356 @@
+#include "regions.h"
+#include <cstdint>
+#include <map>
+#include <regex>
+
+namespace hybrid {
+
+namespace {
+
+const std::string kBegin = "// hybrid-transpiler: begin ";
+const std::string kEnd = "// hybrid-transpiler: end";
+
+struct Segment {
+    bool region = false;
+    std::string name;
+    std::string hash;       // Of the text as generated
+    std::string text;       // Lines, each ending in a newline
+    std::string indent;     // Of the markers: members of an impl block are indented
+    std::string before;     // Blank lines in front of a generated item
+};
+
+struct Chunk {
+    std::vector<std::string> lines;
+    std::string before;     // Blank lines in front of it
+};
+
+// FNV-1a: stable across platforms and standard libraries
+std::string hashText(const std::string& text) {
+    uint64_t hash = 14695981039346656037ull;
+    for (unsigned char c : text) {
+        hash ^= c;
+        hash *= 1099511628211ull;
+    }
+    static const char digits[] = "0123456789abcdef";
+    std::string hex(16, '0');
+    for (int i = 15; i >= 0; --i) {
+        hex[static_cast<size_t>(i)] = digits[hash & 0xf];
+        hash >>= 4;
+    }
+    return hex;
+}
+
+std::vector<std::string> lines(const std::string& text) {
+    std::vector<std::string> result;
+    size_t start = 0;
+    while (start < text.size()) {
+        size_t end = text.find('\n', start);
+        if (end == std::string::npos) end = text.size();
+        result.push_back(text.substr(start, end - start));
+        start = end + 1;
+    }
+    return result;
+}
+
+std::string trim(const std::string& str) {
+    size_t start = str.find_first_not_of(" \t\r");
+    if (start == std::string::npos) return "";
+    size_t end = str.find_last_not_of(" \t\r");
+    return str.substr(start, end - start + 1);
+}
+
+// Brace depth change of a line, ignoring strings, character literals and // comments
+int braceDelta(const std::string& line) {
+    int delta = 0;
+    for (size_t i = 0; i < line.size(); ++i) {
+        char c = line[i];
+        if (c == '/' && i + 1 < line.size() && line[i + 1] == '/') break;
+        if (c == '"') {
+            for (++i; i < line.size() && line[i] != '"'; ++i) {
+                if (line[i] == '\\') ++i;
+            }
+        } else if (c == '\'' && i + 2 < line.size() && (line[i + 2] == '\'' || line[i + 1] == '\\')) {
+            // '{' and '\'' but not the lifetime in &'a str
+            size_t close = line.find('\'', i + 2);
+            if (close != std::string::npos) i = close;
+        } else if (c == '{') {
+            delta++;
+        } else if (c == '}') {
+            delta--;
+        }
+    }
+    return delta;
+}
+
+/**
+ * What a region is called: its first line that is not a comment or an
+ * attribute, up to the parameters or the body (pub fn parse, impl Parser)
+ */
+std::string itemName(const std::vector<std::string>& item) {
+    std::string name;
+    for (const auto& line : item) {
+        std::string text = trim(line);
+        if (text.compare(0, 2, "//") == 0 || text.compare(0, 1, "#") == 0) continue;
+        name = text;
+        break;
+    }
+    // Only comments: the file header
+    if (name.empty()) {
+        name = trim(item.front());
+        if (name.compare(0, 2, "//") == 0) name = trim(name.substr(2));
+    }
+    for (const char* stop : {"(", "{", " = ", ";", " where "}) {
+        size_t pos = name.find(stop);
+        if (pos != std::string::npos && pos > 0) name = name.substr(0, pos);
+    }
+    return trim(name);
+}
+
+/**
+ * Lines split into items at blank lines outside braces
+ * @param trailing Set to the blank lines after the last item
+ */
+std::vector<Chunk> splitChunks(const std::vector<std::string>& text, std::string& trailing) {
+    std::vector<Chunk> chunks;
+    Chunk current;
+    std::string blanks;
+    int depth = 0;
+    for (const auto& line : text) {
+        if (depth <= 0 && trim(line).empty()) {
+            if (!current.lines.empty()) chunks.push_back(current);
+            current = Chunk();
+            blanks += "\n";
+            continue;
+        }
+        if (current.lines.empty()) {
+            current.before = blanks;
+            blanks.clear();
+        }
+        current.lines.push_back(line);
+        depth += braceDelta(line);
+    }
+    if (!current.lines.empty()) chunks.push_back(current);
+    trailing = blanks;
+    return chunks;
+}
+
+/**
+ * Generated code split into top-level items; impl and trait blocks are
+ * split further into their head, each member and the closing brace, so
+ * that a hand edit to one method does not hold back the others
+ */
+std::vector<Segment> splitItems(const std::string& generated) {
+    static const std::regex block_pattern(R"((?:pub(?:\([^)]*\))?\s+)?(?:unsafe\s+)?(?:impl\b|trait\s).*)");
+    std::vector<Segment> items;
+    std::map<std::string, int> seen;
+
+    auto add = [&](const std::vector<std::string>& text, std::string name, const std::string& before) {
+        // Two items of the same name (impl blocks of one type) are told apart by number
+        int count = ++seen[name];
+        if (count > 1) name += " #" + std::to_string(count);
+        Segment item;
+        item.region = true;
+        item.name = name;
+        item.before = before;
+        item.indent = text.front().substr(0, text.front().find_first_not_of(" \t"));
+        for (const auto& line : text) item.text += line + "\n";
+        item.hash = hashText(item.text);
+        items.push_back(item);
+        return name;
+    };
+
+    std::string trailing;
+    for (const auto& chunk : splitChunks(lines(generated), trailing)) {
+        const std::vector<std::string>& text = chunk.lines;
+        std::string name = itemName(text);
+
+        // The head ends on the line opening the block
+        size_t head = 0;
+        int depth = braceDelta(text[0]);
+        while (depth <= 0 && head + 1 < text.size()) depth += braceDelta(text[++head]);
+        if (!std::regex_match(name, block_pattern) || depth != 1 || head + 2 >= text.size() ||
+            trim(text.back()) != "}") {
+            add(text, name, chunk.before);
+            continue;
+        }
+
+        std::string block = add({text.begin(), text.begin() + static_cast<std::ptrdiff_t>(head) + 1}, name,
+                                chunk.before);
+        std::string body_trailing;
+        std::vector<std::string> body(text.begin() + static_cast<std::ptrdiff_t>(head) + 1, text.end() - 1);
+        for (const auto& member : splitChunks(body, body_trailing)) {
+            add(member.lines, block + ": " + itemName(member.lines), member.before);
+        }
+        add({text.back()}, block + ": end", body_trailing);
+    }
+    return items;
+}
+
+/**
+ * A previously written file as regions and the text between them
+ */
+bool parseRegions(const std::string& existing, std::vector<Segment>& segments, std::string& error) {
+    Segment* open = nullptr;
+    size_t line_number = 0;
+    for (const auto& line : lines(existing)) {
+        line_number++;
+        std::string text = trim(line);
+        if (text.compare(0, kBegin.size(), kBegin) == 0) {
+            if (open) {
+                error = "region '" + open->name + "' has no end marker before line " + std::to_string(line_number);
+                return false;
+            }
+            std::string rest = text.substr(kBegin.size());
+            size_t space = rest.find(' ');
+            Segment region;
+            region.region = true;
+            region.indent = line.substr(0, line.find_first_not_of(" \t"));
+            region.hash = rest.substr(0, space);
+            region.name = space == std::string::npos ? "" : trim(rest.substr(space + 1));
+            segments.push_back(region);
+            open = &segments.back();
+        } else if (text == kEnd) {
+            if (!open) {
+                error = "end marker without a begin marker at line " + std::to_string(line_number);
+                return false;
+            }
+            open = nullptr;
+        } else if (open) {
+            open->text += line + "\n";
+        } else {
+            if (segments.empty() || segments.back().region) segments.emplace_back();
+            segments.back().text += line + "\n";
+        }
+    }
+    if (open) {
+        error = "region '" + open->name + "' has no end marker";
+        return false;
+    }
+    return true;
+}
+
+std::string render(const std::vector<Segment>& segments) {
+    std::string content;
+    for (const auto& segment : segments) {
+        if (segment.region) {
+            content += segment.indent + kBegin + segment.hash + " " + segment.name + "\n" + segment.text +
+                       segment.indent + kEnd + "\n";
+        } else {
+            content += segment.text;
+        }
+    }
+    return content;
+}
+
+bool isBlank(const Segment& segment) {
+    return !segment.region && trim(segment.text).empty();
+}
+
+} // namespace
+
+RegionMerge mergeRegions(const std::string& existing, const std::string& generated, bool force) {
+    RegionMerge merge;
+    std::vector<Segment> items = splitItems(generated);
+
+    std::vector<Segment> segments;
+    std::string error;
+    if (!parseRegions(existing, segments, error)) {
+        merge.error = error;
+        return merge;
+    }
+
+    // Written by hand, or by a run without --update: nothing tells generated text from edits
+    bool marked = false;
+    for (const auto& segment : segments) marked = marked || segment.region;
+    if (!marked && existing.find_first_not_of(" \t\r\n") != std::string::npos) {
+        if (!force) {
+            merge.error = "it has no region markers, so hand edits cannot be told apart; --force overwrites it";
+            return merge;
+        }
+        merge.overwritten.push_back("the whole file");
+        segments.clear();
+    }
+
+    std::map<std::string, const Segment*> by_name;
+    for (const auto& item : items) by_name[item.name] = &item;
+
+    std::vector<Segment> merged;
+    bool drop_blank = false;
+    for (const auto& segment : segments) {
+        if (!segment.region) {
+            if (!(drop_blank && isBlank(segment))) merged.push_back(segment);
+            drop_blank = false;
+            continue;
+        }
+        drop_blank = false;
+        bool edited = hashText(segment.text) != segment.hash;
+        auto found = by_name.find(segment.name);
+
+        if (found == by_name.end()) {
+            // The C++ no longer declares it
+            if (edited && !force) {
+                merge.conflicts.push_back(segment.name + " (no longer in the C++)");
+                merged.push_back(segment);
+                continue;
+            }
+            if (edited) merge.overwritten.push_back(segment.name);
+            merge.removed++;
+            drop_blank = true;
+            continue;
+        }
+
+        const Segment& item = *found->second;
+        if (item.hash == segment.hash) {
+            // The C++ did not change: the region stays as it is, edits included
+            if (edited) {
+                merge.kept++;
+            } else {
+                merge.unchanged++;
+            }
+            merged.push_back(segment);
+        } else if (!edited || force) {
+            if (edited) merge.overwritten.push_back(segment.name);
+            merge.retranslated++;
+            merged.push_back(item);
+        } else {
+            merge.conflicts.push_back(segment.name);
+            merged.push_back(segment);
+        }
+    }
+
+    if (!merge.conflicts.empty()) {
+        merge.error = std::to_string(merge.conflicts.size()) + " hand-edited item" +
+                      (merge.conflicts.size() > 1 ? "s" : "") + " would be overwritten";
+        return merge;
+    }
+
+    // New items go after the item they follow in the translation, the first one first
+    std::string previous;
+    for (const auto& item : items) {
+        bool present = false;
+        size_t at = 0;
+        for (size_t i = 0; i < merged.size(); ++i) {
+            if (!merged[i].region) continue;
+            if (merged[i].name == item.name) present = true;
+            if (merged[i].name == previous) at = i + 1;
+        }
+        previous = item.name;
+        if (present) continue;
+
+        Segment blank;
+        blank.text = item.before;
+        std::vector<Segment> inserted;
+        if (at > 0 && !blank.text.empty()) inserted.push_back(blank);
+        inserted.push_back(item);
+        blank.text = "\n";
+        if (at == 0 && !merged.empty()) inserted.push_back(blank);
+        merged.insert(merged.begin() + static_cast<std::ptrdiff_t>(at), inserted.begin(), inserted.end());
+        merge.added++;
+    }
+
+    merge.content = render(merged);
+    merge.success = true;
+    return merge;
+}
+
+} // namespace hybrid
@@ -0,0 +1,46 @@
+#include "runtime_trace.h"
+#include "json.h"
+#include <stdexcept>
+
+namespace hybrid {
+
+bool parseRuntimeTrace(const std::string& text, RuntimeTrace& trace, std::string& error) {
+    JsonValue root;
+    try {
+        root = JsonValue::parse(text);
+    } catch (const std::runtime_error& e) {
+        error = e.what();
+        return false;
+    }
+    if (!root.isObject() || !(root["shared_ptrs"].isArray() || root["mutexes"].isArray())) {
+        error = "a runtime trace needs a \"shared_ptrs\" or a \"mutexes\" array";
+        return false;
+    }
+
+    trace = RuntimeTrace();
+    for (const auto& entry : root["shared_ptrs"].asArray()) {
+        if (!entry["type"].isString() || !entry["threads"].isNumber()) {
+            error = "every entry of \"shared_ptrs\" needs a \"type\" string and a \"threads\" count";
+            return false;
+        }
+        RuntimeTrace::SharedPointee pointee;
+        pointee.type = entry["type"].asString();
+        pointee.threads = entry["threads"].asInt();
+        pointee.written_while_shared = !entry.has("written_while_shared") || entry["written_while_shared"].asBool();
+        trace.shared_ptrs.push_back(pointee);
+    }
+    for (const auto& entry : root["mutexes"].asArray()) {
+        if (!entry["name"].isString() || !entry["threads"].isNumber()) {
+            error = "every entry of \"mutexes\" needs a \"name\" string and a \"threads\" count";
+            return false;
+        }
+        RuntimeTrace::MutexUse mutex;
+        mutex.name = entry["name"].asString();
+        mutex.threads = entry["threads"].asInt();
+        mutex.contended = static_cast<long>(entry["contended"].asNumber());
+        trace.mutexes.push_back(mutex);
+    }
+    return true;
+}
+
+} // namespace hybrid
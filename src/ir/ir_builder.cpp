@@ -11,6 +11,18 @@ void IR::addClass(const ClassDecl& class_decl) {
     registerType(class_decl.name, type);
 }
 
+void IR::addEnum(const EnumDecl& enum_decl) {
+    enums_.push_back(enum_decl);
+
+    auto type = std::make_shared<Type>(TypeKind::Enum);
+    type->name = enum_decl.name;
+    registerType(enum_decl.name, type);
+}
+
+void IR::addTypeAlias(const TypeAlias& alias) {
+    type_aliases_.push_back(alias);
+}
+
 void IR::addFunction(const Function& func) {
     functions_.push_back(func);
 }
@@ -19,6 +31,37 @@ void IR::addGlobalVariable(const Variable& var) {
     global_vars_.push_back(var);
 }
 
+void IR::addDiagnostic(const Diagnostic& diagnostic) {
+    diagnostics_.push_back(diagnostic);
+}
+
+const ClassDecl* IR::findClass(const std::string& name) const {
+    for (const auto& class_decl : classes_) {
+        if (class_decl.name == name) {
+            return &class_decl;
+        }
+    }
+    return nullptr;
+}
+
+const EnumDecl* IR::findEnum(const std::string& name) const {
+    for (const auto& enum_decl : enums_) {
+        if (enum_decl.name == name) {
+            return &enum_decl;
+        }
+    }
+    return nullptr;
+}
+
+const TypeAlias* IR::findTypeAlias(const std::string& name) const {
+    for (const auto& alias : type_aliases_) {
+        if (alias.name == name) {
+            return &alias;
+        }
+    }
+    return nullptr;
+}
+
 std::shared_ptr<Type> IR::findType(const std::string& name) const {
     auto it = type_registry_.find(name);
     if (it != type_registry_.end()) {
@@ -0,0 +1,829 @@
+#include "ir_json.h"
+#include <algorithm>
+
+namespace hybrid {
+
+namespace {
+
+const int FORMAT_VERSION = 1;
+
+JsonValue spanToJson(const SourceSpan& span) {
+    return JsonValue::object()
+        .set("begin", JsonValue::object().set("line", span.begin_line).set("column", span.begin_column))
+        .set("end", JsonValue::object().set("line", span.end_line).set("column", span.end_column));
+}
+
+void setSpan(JsonValue& node, const SourceSpan& span) {
+    if (span.isKnown()) {
+        node.set("span", spanToJson(span));
+    }
+}
+
+std::string spelling(const std::shared_ptr<Type>& type) {
+    if (!type) return "";
+    return (type->is_const ? "const " : "") + type->name;
+}
+
+std::string accessName(ClassDecl::AccessSection::Level level) {
+    switch (level) {
+        case ClassDecl::AccessSection::Public:    return "public";
+        case ClassDecl::AccessSection::Protected: return "protected";
+        case ClassDecl::AccessSection::Private:   return "private";
+    }
+    return "private";
+}
+
+std::string copySemanticsName(CopySemantics semantics) {
+    switch (semantics) {
+        case CopySemantics::Copy:     return "copy";
+        case CopySemantics::Clone:    return "clone";
+        case CopySemantics::MoveOnly: return "move_only";
+    }
+    return "move_only";
+}
+
+std::string severityName(Diagnostic::Severity severity) {
+    switch (severity) {
+        case Diagnostic::Note:    return "note";
+        case Diagnostic::Warning: return "warning";
+        case Diagnostic::Error:   return "error";
+    }
+    return "warning";
+}
+
+std::string guardKindName(GuardInfo::GuardKind kind) {
+    switch (kind) {
+        case GuardInfo::LockGuard:  return "lock_guard";
+        case GuardInfo::UniqueLock: return "unique_lock";
+        case GuardInfo::SharedLock: return "shared_lock";
+        case GuardInfo::ScopedLock: return "scoped_lock";
+        case GuardInfo::ScopeExit:  return "scope_exit";
+    }
+    return "lock_guard";
+}
+
+std::string layoutQueryName(LayoutQueryInfo::QueryKind kind) {
+    switch (kind) {
+        case LayoutQueryInfo::SizeOf:      return "sizeof";
+        case LayoutQueryInfo::AlignOf:     return "alignof";
+        case LayoutQueryInfo::OffsetOf:    return "offsetof";
+        case LayoutQueryInfo::ArrayLength: return "array_length";
+    }
+    return "sizeof";
+}
+
+std::string bindingKindName(BindingInfo::BindingKind kind) {
+    switch (kind) {
+        case BindingInfo::Declaration: return "declaration";
+        case BindingInfo::Tie:         return "tie";
+        case BindingInfo::RangeFor:    return "range_for";
+    }
+    return "declaration";
+}
+
+std::string formatKindName(FormatInfo::FormatKind kind) {
+    switch (kind) {
+        case FormatInfo::Format:   return "format";
+        case FormatInfo::Print:    return "print";
+        case FormatInfo::Println:  return "println";
+        case FormatInfo::FormatTo: return "format_to";
+    }
+    return "format";
+}
+
+std::string logStyleName(LogInfo::LogStyle style) {
+    switch (style) {
+        case LogInfo::Stream: return "stream";
+        case LogInfo::Printf: return "printf";
+        case LogInfo::Fmt:    return "fmt";
+    }
+    return "fmt";
+}
+
+std::string statusSiteKindName(StatusSiteInfo::SiteKind kind) {
+    switch (kind) {
+        case StatusSiteInfo::ReturnSuccess: return "return_success";
+        case StatusSiteInfo::ReturnError:   return "return_error";
+        case StatusSiteInfo::Propagate:     return "propagate";
+        case StatusSiteInfo::Check:         return "check";
+    }
+    return "check";
+}
+
+std::string functorUseKindName(FunctorUseInfo::UseKind kind) {
+    switch (kind) {
+        case FunctorUseInfo::Construct: return "construct";
+        case FunctorUseInfo::Call:      return "call";
+    }
+    return "call";
+}
+
+std::string memberPointerKindName(MemberPointerInfo::SiteKind kind) {
+    switch (kind) {
+        case MemberPointerInfo::AddressOf:   return "address_of";
+        case MemberPointerInfo::Call:        return "call";
+        case MemberPointerInfo::Access:      return "access";
+        case MemberPointerInfo::Declaration: return "declaration";
+    }
+    return "address_of";
+}
+
+std::string varargSiteKindName(VarargSiteInfo::SiteKind kind) {
+    switch (kind) {
+        case VarargSiteInfo::ListDeclaration: return "list_declaration";
+        case VarargSiteInfo::Start:           return "start";
+        case VarargSiteInfo::Arg:             return "arg";
+        case VarargSiteInfo::End:             return "end";
+        case VarargSiteInfo::Copy:            return "copy";
+        case VarargSiteInfo::Forward:         return "forward";
+    }
+    return "arg";
+}
+
+std::string sliceSiteKindName(SliceSiteInfo::SiteKind kind) {
+    switch (kind) {
+        case SliceSiteInfo::SpanMethod:  return "span_method";
+        case SliceSiteInfo::FusedCall:   return "fused_call";
+        case SliceSiteInfo::NewArray:    return "new_array";
+        case SliceSiteInfo::DeleteArray: return "delete_array";
+    }
+    return "span_method";
+}
+
+std::string allocationSiteKindName(AllocationSiteInfo::SiteKind kind) {
+    switch (kind) {
+        case AllocationSiteInfo::PlacementNew:   return "placement_new";
+        case AllocationSiteInfo::DestructorCall: return "destructor_call";
+    }
+    return "placement_new";
+}
+
+std::string selfReferenceKindName(SelfReferenceInfo::Kind kind) {
+    switch (kind) {
+        case SelfReferenceInfo::ThisEscape:      return "this_escape";
+        case SelfReferenceInfo::InteriorPointer: return "interior_pointer";
+        case SelfReferenceInfo::IntrusiveLink:   return "intrusive_link";
+    }
+    return "this_escape";
+}
+
+std::string graphSiteKindName(GraphSiteInfo::SiteKind kind) {
+    switch (kind) {
+        case GraphSiteInfo::Declaration: return "declaration";
+        case GraphSiteInfo::NewNode:     return "new_node";
+        case GraphSiteInfo::DeleteNode:  return "delete_node";
+        case GraphSiteInfo::NodeAccess:  return "node_access";
+    }
+    return "node_access";
+}
+
+std::string entrySiteKindName(EntrySiteInfo::SiteKind kind) {
+    switch (kind) {
+        case EntrySiteInfo::Argument:    return "argument";
+        case EntrySiteInfo::ArgumentUse: return "argument_use";
+        case EntrySiteInfo::Return:      return "return";
+        case EntrySiteInfo::Exit:        return "exit";
+        case EntrySiteInfo::CommandLine: return "command_line";
+    }
+    return "exit";
+}
+
+std::string templateParameterKindName(TemplateParameter::Kind kind) {
+    switch (kind) {
+        case TemplateParameter::Type:     return "type";
+        case TemplateParameter::NonType:  return "non_type";
+        case TemplateParameter::Template: return "template";
+    }
+    return "type";
+}
+
+// ---------------------------------------------------------------------
+// AST view
+// ---------------------------------------------------------------------
+
+JsonValue astTemplateParameters(const std::vector<TemplateParameter>& params) {
+    JsonValue list = JsonValue::array();
+    for (const auto& param : params) {
+        JsonValue node = JsonValue::object()
+            .set("kind", templateParameterKindName(param.kind))
+            .set("name", param.name);
+        if (param.param_type) node.set("type", spelling(param.param_type));
+        if (!param.default_value.empty()) node.set("default", param.default_value);
+        if (param.is_array_length) node.set("array_length", true);
+        list.push(node);
+    }
+    return list;
+}
+
+JsonValue astFunction(const Function& func) {
+    JsonValue node = JsonValue::object().set("name", func.name);
+    setSpan(node, func.span);
+
+    if (func.is_constructor) node.set("constructor", true);
+    if (func.is_destructor) node.set("destructor", true);
+    if (func.return_type) node.set("return_type", spelling(func.return_type));
+
+    JsonValue params = JsonValue::array();
+    for (const auto& param : func.parameters) {
+        JsonValue param_node = JsonValue::object()
+            .set("name", param.name)
+            .set("type", spelling(param.type));
+        if (param.has_default) param_node.set("default", param.default_value);
+        setSpan(param_node, param.span);
+        params.push(param_node);
+    }
+    node.set("parameters", params);
+
+    JsonValue qualifiers = JsonValue::array();
+    if (func.is_static) qualifiers.push("static");
+    if (func.is_virtual) qualifiers.push("virtual");
+    if (func.is_const) qualifiers.push("const");
+    if (func.exception_spec.is_noexcept) qualifiers.push("noexcept");
+    if (func.is_pure_virtual) qualifiers.push("= 0");
+    if (func.is_deleted) qualifiers.push("= delete");
+    if (func.is_defaulted) qualifiers.push("= default");
+    node.set("qualifiers", qualifiers);
+
+    if (func.is_template) node.set("template_parameters", astTemplateParameters(func.template_parameters));
+    if (!func.member_initializers.empty()) node.set("member_initializers", func.member_initializers);
+    node.set("has_body", !func.body.empty());
+    return node;
+}
+
+JsonValue enumNode(const EnumDecl& enum_decl) {
+    JsonValue node = JsonValue::object()
+        .set("name", enum_decl.name)
+        .set("scoped", enum_decl.is_scoped);
+    if (!enum_decl.underlying_type.empty()) node.set("underlying_type", enum_decl.underlying_type);
+    setSpan(node, enum_decl.span);
+
+    JsonValue enumerators = JsonValue::array();
+    for (const auto& enumerator : enum_decl.enumerators) {
+        JsonValue enumerator_node = JsonValue::object().set("name", enumerator.name);
+        if (!enumerator.value.empty()) enumerator_node.set("value", enumerator.value);
+        enumerators.push(enumerator_node);
+    }
+    node.set("enumerators", enumerators);
+    return node;
+}
+
+JsonValue astClass(const ClassDecl& class_decl) {
+    JsonValue node = JsonValue::object()
+        .set("name", class_decl.name)
+        .set("kind", class_decl.is_struct ? "struct" : "class");
+    setSpan(node, class_decl.span);
+
+    JsonValue bases = JsonValue::array();
+    for (const auto& base : class_decl.base_classes) bases.push(base);
+    node.set("bases", bases);
+
+    JsonValue self_references = JsonValue::array();
+    for (const auto& reference : class_decl.self_references) {
+        JsonValue reference_node = JsonValue::object().set("kind", selfReferenceKindName(reference.kind));
+        if (!reference.field.empty()) reference_node.set("field", reference.field);
+        if (!reference.function.empty()) reference_node.set("function", reference.function);
+        self_references.push(reference_node);
+    }
+    node.set("self_references", self_references);
+    if (!class_decl.graph_nodes.empty()) {
+        JsonValue graph_nodes = JsonValue::array();
+        for (const auto& name : class_decl.graph_nodes) graph_nodes.push(name);
+        node.set("graph_nodes", graph_nodes);
+    }
+
+    if (class_decl.is_template) {
+        node.set("template_parameters", astTemplateParameters(class_decl.template_parameters));
+    }
+
+    JsonValue fields = JsonValue::array();
+    for (const auto& field : class_decl.fields) {
+        JsonValue field_node = JsonValue::object()
+            .set("name", field.name)
+            .set("type", spelling(field.type));
+        if (field.is_static) field_node.set("static", true);
+        if (!field.initializer.empty()) field_node.set("initializer", field.initializer);
+        setSpan(field_node, field.span);
+        fields.push(field_node);
+    }
+    node.set("fields", fields);
+
+    JsonValue methods = JsonValue::array();
+    for (const auto& method : class_decl.methods) methods.push(astFunction(method));
+    node.set("methods", methods);
+
+    JsonValue sections = JsonValue::array();
+    for (const auto& section : class_decl.access_sections) {
+        JsonValue members = JsonValue::array();
+        for (const auto& member : section.members) members.push(member);
+        sections.push(JsonValue::object().set("access", accessName(section.level)).set("members", members));
+    }
+    node.set("access_sections", sections);
+    return node;
+}
+
+// ---------------------------------------------------------------------
+// IR view
+// ---------------------------------------------------------------------
+
+JsonValue irType(const IR& ir, const std::shared_ptr<Type>& type) {
+    if (!type) return JsonValue();
+
+    JsonValue node = JsonValue::object()
+        .set("kind", typeKindName(type->kind))
+        .set("name", type->name);
+    if (type->is_const) node.set("const", true);
+
+    if (type->kind == TypeKind::Class || type->kind == TypeKind::Struct) {
+        // Whether the name refers to a class translated in this run
+        node.set("declared", ir.findClass(type->name) != nullptr);
+    }
+    if (type->element_type) node.set("element", irType(ir, type->element_type));
+    if (!type->array_size.empty()) node.set("length", type->array_size);
+    if (!type->member_of.empty()) node.set("member_of", type->member_of);
+    if (type->kind == TypeKind::Function) {
+        JsonValue params = JsonValue::array();
+        for (const auto& param : type->parameter_types) params.push(irType(ir, param));
+        node.set("parameters", params);
+    }
+    if (!type->template_args.empty()) {
+        JsonValue args = JsonValue::array();
+        for (const auto& arg : type->template_args) args.push(irType(ir, arg));
+        node.set("template_args", args);
+    }
+    if (type->allocator) node.set("allocator", irType(ir, type->allocator));
+    if (type->thread_shared) node.set("thread_shared", true);
+    if (type->needs_lock) node.set("needs_lock", true);
+    if (type->owned_array) node.set("owned_array", true);
+    if (type->size_bytes > 0) node.set("size_bytes", type->size_bytes);
+    if (type->alignment > 0) node.set("alignment", type->alignment);
+    return node;
+}
+
+JsonValue irFunction(const IR& ir, const Function& func) {
+    JsonValue node = JsonValue::object().set("name", func.name);
+    setSpan(node, func.span);
+    node.set("return_type", irType(ir, func.return_type));
+
+    JsonValue params = JsonValue::array();
+    for (const auto& param : func.parameters) {
+        JsonValue param_node = JsonValue::object()
+            .set("name", param.name)
+            .set("type", irType(ir, param.type));
+        setSpan(param_node, param.span);
+        params.push(param_node);
+    }
+    node.set("parameters", params);
+
+    JsonValue moved = JsonValue::array();
+    for (const auto& name : func.moved_params) moved.push(name);
+    node.set("moved_params", moved);
+
+    JsonValue borrowed = JsonValue::array();
+    for (const auto& name : func.borrowed_params) borrowed.push(name);
+    node.set("borrowed_params", borrowed);
+
+    JsonValue moves = JsonValue::array();
+    for (const auto& move : func.moves) {
+        moves.push(JsonValue::object()
+            .set("kind", move.kind == MoveInfo::Exchange ? "exchange" : "move")
+            .set("expression", move.expression)
+            .set("used_after_move", move.used_after_move)
+            .set("moves_out_of_borrow", move.moves_out_of_borrow));
+    }
+    node.set("moves", moves);
+
+    JsonValue copies = JsonValue::array();
+    for (const auto& copy : func.copies) {
+        copies.push(JsonValue::object()
+            .set("expression", copy.expression)
+            .set("needs_clone", copy.needs_clone)
+            .set("needs_deref", copy.needs_deref));
+    }
+    node.set("copies", copies);
+
+    JsonValue guards = JsonValue::array();
+    for (const auto& guard : func.guards) {
+        JsonValue mutexes = JsonValue::array();
+        for (const auto& mutex : guard.mutex_names) mutexes.push(mutex);
+        guards.push(JsonValue::object()
+            .set("kind", guardKindName(guard.kind))
+            .set("variable", guard.guard_var_name)
+            .set("mutexes", mutexes)
+            .set("unlocked_early", guard.unlocked_early));
+    }
+    node.set("guards", guards);
+
+    JsonValue queries = JsonValue::array();
+    for (const auto& query : func.layout_queries) {
+        JsonValue query_node = JsonValue::object().set("kind", layoutQueryName(query.kind));
+        if (query.type) query_node.set("type", irType(ir, query.type));
+        if (!query.expression.empty()) query_node.set("expression", query.expression);
+        if (!query.member_name.empty()) query_node.set("member", query.member_name);
+        queries.push(query_node);
+    }
+    node.set("layout_queries", queries);
+
+    JsonValue bindings = JsonValue::array();
+    for (const auto& binding : func.bindings) {
+        JsonValue names = JsonValue::array();
+        for (const auto& name : binding.names) names.push(name);
+        JsonValue binding_node = JsonValue::object()
+            .set("kind", bindingKindName(binding.kind))
+            .set("names", names)
+            .set("initializer", binding.initializer)
+            .set("by_reference", binding.by_reference);
+        if (!binding.struct_name.empty()) binding_node.set("struct", binding.struct_name);
+        bindings.push(binding_node);
+    }
+    node.set("bindings", bindings);
+
+    JsonValue formats = JsonValue::array();
+    for (const auto& call : func.formats) {
+        JsonValue format_node = JsonValue::object().set("kind", formatKindName(call.kind));
+        if (call.is_literal) format_node.set("format", call.format_string);
+        if (!call.stream.empty()) format_node.set("stream", call.stream);
+        format_node.set("arguments", call.arguments.size());
+        formats.push(format_node);
+    }
+    node.set("formats", formats);
+
+    JsonValue logs = JsonValue::array();
+    for (const auto& log : func.logs) {
+        JsonValue log_node = JsonValue::object()
+            .set("macro", log.macro)
+            .set("style", logStyleName(log.style))
+            .set("level", log.level);
+        if (log.fatal) log_node.set("fatal", true);
+        if (log.is_literal) log_node.set("format", log.format_string);
+        log_node.set("arguments", log.arguments.size());
+        logs.push(log_node);
+    }
+    node.set("logs", logs);
+
+    if (!func.status_enum.empty()) {
+        node.set("status_enum", func.status_enum);
+        if (!func.status_out_param.empty()) node.set("status_out_param", func.status_out_param);
+    }
+    if (!func.status_params.empty()) {
+        JsonValue params = JsonValue::array();
+        for (const auto& param : func.status_params) params.push(param);
+        node.set("status_params", params);
+    }
+    JsonValue status_sites = JsonValue::array();
+    for (const auto& site : func.status_sites) {
+        JsonValue site_node = JsonValue::object().set("kind", statusSiteKindName(site.kind));
+        if (!site.expression.empty()) site_node.set("expression", site.expression);
+        if (!site.value.empty()) site_node.set("value", site.value);
+        if (site.kind == StatusSiteInfo::Check) site_node.set("success_check", site.is_success_check);
+        status_sites.push(site_node);
+    }
+    node.set("status_sites", status_sites);
+
+    JsonValue algorithms = JsonValue::array();
+    for (const auto& call : func.algorithms) {
+        JsonValue algorithm_node = JsonValue::object()
+            .set("algorithm", call.algorithm)
+            .set("container", call.container)
+            .set("arguments", call.arguments.size());
+        if (!call.output.empty()) algorithm_node.set("output", call.output);
+        if (!call.end_comparison.empty()) algorithm_node.set("end_comparison", call.end_comparison);
+        algorithms.push(algorithm_node);
+    }
+    node.set("algorithms", algorithms);
+
+    JsonValue member_pointers = JsonValue::array();
+    for (const auto& site : func.member_pointers) {
+        JsonValue site_node = JsonValue::object().set("kind", memberPointerKindName(site.kind));
+        if (site.kind == MemberPointerInfo::AddressOf) {
+            site_node.set("class", site.class_name)
+                .set("member", site.member)
+                .set("data_member", site.is_data_member);
+        } else if (site.kind == MemberPointerInfo::Declaration) {
+            site_node.set("name", site.name).set("type", irType(ir, site.type));
+        } else {
+            site_node.set("object", site.object).set("pointer", site.pointer);
+        }
+        member_pointers.push(site_node);
+    }
+    node.set("member_pointers", member_pointers);
+
+    if (func.is_variadic) node.set("variadic", true);
+    if (func.is_entry_point) node.set("entry_point", true);
+    if (!func.retained_reason.empty()) node.set("retained", func.retained_reason);
+    JsonValue vararg_sites = JsonValue::array();
+    for (const auto& site : func.vararg_sites) {
+        JsonValue site_node = JsonValue::object()
+            .set("kind", varargSiteKindName(site.kind))
+            .set("list", site.list);
+        if (!site.target.empty()) site_node.set("target", site.target);
+        if (site.type) site_node.set("type", irType(ir, site.type));
+        vararg_sites.push(site_node);
+    }
+    node.set("vararg_sites", vararg_sites);
+
+    JsonValue allocations = JsonValue::array();
+    for (const auto& site : func.allocations) {
+        JsonValue site_node = JsonValue::object()
+            .set("kind", allocationSiteKindName(site.kind))
+            .set("buffer", site.buffer)
+            .set("type", irType(ir, site.type));
+        if (site.kind == AllocationSiteInfo::PlacementNew) site_node.set("arguments", site.arguments.size());
+        allocations.push(site_node);
+    }
+    node.set("allocations", allocations);
+
+    JsonValue upcasts = JsonValue::array();
+    for (const auto& site : func.upcasts) {
+        JsonValue site_node = JsonValue::object()
+            .set("object", site.object)
+            .set("base", site.base)
+            .set("polymorphic", site.is_polymorphic);
+        if (!site.name.empty()) site_node.set("name", site.name);
+        upcasts.push(site_node);
+    }
+    node.set("upcasts", upcasts);
+
+    JsonValue graph_sites = JsonValue::array();
+    for (const auto& site : func.graph_sites) {
+        JsonValue members = JsonValue::array();
+        for (const auto& member : site.members) members.push(member);
+        graph_sites.push(JsonValue::object()
+            .set("kind", graphSiteKindName(site.kind))
+            .set("node", site.node_class)
+            .set("pointer", site.pointer)
+            .set("members", members));
+    }
+    node.set("graph_sites", graph_sites);
+
+    JsonValue libc_calls = JsonValue::array();
+    for (const auto& call : func.libc_calls) {
+        libc_calls.push(JsonValue::object()
+            .set("function", call.function)
+            .set("arguments", call.arguments.size())
+            .set("nested", call.nested.size()));
+    }
+    node.set("libc_calls", libc_calls);
+
+    JsonValue slice_params = JsonValue::array();
+    for (const auto& pair : func.slice_params) {
+        slice_params.push(JsonValue::object()
+            .set("pointer", pair.pointer)
+            .set("length", pair.length)
+            .set("const", pair.is_const));
+    }
+    node.set("slice_params", slice_params);
+
+    JsonValue slice_sites = JsonValue::array();
+    for (const auto& site : func.slice_sites) {
+        JsonValue site_node = JsonValue::object()
+            .set("kind", sliceSiteKindName(site.kind))
+            .set("target", site.target);
+        if (!site.method.empty()) site_node.set("method", site.method);
+        if (site.type) site_node.set("type", irType(ir, site.type));
+        slice_sites.push(site_node);
+    }
+    node.set("slice_sites", slice_sites);
+
+    JsonValue integer_sites = JsonValue::array();
+    for (const auto& site : func.integer_sites) {
+        JsonValue site_node = JsonValue::object()
+            .set("kind", site.kind == IntegerSiteInfo::Wrapping ? "wrapping" : "conversion")
+            .set("type", irType(ir, site.type));
+        if (site.kind == IntegerSiteInfo::Wrapping) {
+            site_node.set("target", site.target).set("op", site.op);
+        } else {
+            site_node.set("from", irType(ir, site.from)).set("explicit", site.explicit_cast);
+        }
+        integer_sites.push(site_node);
+    }
+    node.set("integer_sites", integer_sites);
+
+    JsonValue numeric_limits = JsonValue::array();
+    for (const auto& limit : func.numeric_limits) {
+        numeric_limits.push(JsonValue::object()
+            .set("type", irType(ir, limit.type))
+            .set("member", limit.member));
+    }
+    node.set("numeric_limits", numeric_limits);
+
+    JsonValue entry_sites = JsonValue::array();
+    for (const auto& site : func.entry_sites) {
+        entry_sites.push(JsonValue::object()
+            .set("kind", entrySiteKindName(site.kind))
+            .set("expression", site.expression)
+            .set("nested", site.nested.size()));
+    }
+    node.set("entry_sites", entry_sites);
+
+    JsonValue command_lines = JsonValue::array();
+    for (const auto& command_line : func.command_lines) {
+        static const char* styles[] = {"argv_loop", "getopt", "getopt_long"};
+        JsonValue options = JsonValue::array();
+        for (const auto& option : command_line.options) {
+            JsonValue option_node = JsonValue::object()
+                .set("short", option.short_name ? std::string(1, option.short_name) : "")
+                .set("long", option.long_name)
+                .set("takes_value", option.takes_value)
+                .set("target", option.target);
+            if (option.type) option_node.set("type", irType(ir, option.type));
+            if (option.counted) option_node.set("counted", true);
+            if (option.repeated) option_node.set("repeated", true);
+            options.push(option_node);
+        }
+        JsonValue command_line_node = JsonValue::object()
+            .set("style", styles[command_line.style])
+            .set("options", options);
+        if (!command_line.positional.empty()) command_line_node.set("positional", command_line.positional);
+        command_lines.push(command_line_node);
+    }
+    node.set("command_lines", command_lines);
+
+    JsonValue thread_crossings = JsonValue::array();
+    for (const auto& crossing : func.thread_crossings) {
+        JsonValue crossing_node = JsonValue::object()
+            .set("value", crossing.value)
+            .set("type", irType(ir, crossing.type))
+            .set("captured", crossing.captured);
+        if (!crossing.thread.empty()) crossing_node.set("thread", crossing.thread);
+        thread_crossings.push(crossing_node);
+    }
+    node.set("thread_crossings", thread_crossings);
+
+    JsonValue functor_uses = JsonValue::array();
+    for (const auto& use : func.functor_uses) {
+        JsonValue use_node = JsonValue::object()
+            .set("kind", functorUseKindName(use.kind))
+            .set("functor", use.functor);
+        if (use.kind == FunctorUseInfo::Construct) use_node.set("arguments", use.arguments.size());
+        functor_uses.push(use_node);
+    }
+    node.set("functor_uses", functor_uses);
+
+    JsonValue constexpr_ifs = JsonValue::array();
+    for (const auto& chain : func.constexpr_ifs) {
+        JsonValue conditions = JsonValue::array();
+        for (const auto& branch : chain.branches) conditions.push(branch.condition);
+        JsonValue selected = JsonValue::array();
+        for (int branch : chain.selected) {
+            if (branch == ConstexprIfInfo::UNRESOLVED) selected.push(JsonValue());
+            else selected.push(branch);
+        }
+        JsonValue chain_node = JsonValue::object()
+            .set("conditions", conditions)
+            .set("selected", selected);
+        if (!chain.unresolved_reason.empty()) chain_node.set("unresolved", chain.unresolved_reason);
+        constexpr_ifs.push(chain_node);
+    }
+    node.set("constexpr_ifs", constexpr_ifs);
+
+    node.set("may_throw", func.may_throw);
+    node.set("uses_threading", func.uses_threading);
+    node.set("is_async", func.is_async || func.coroutine_info.is_coroutine);
+    return node;
+}
+
+JsonValue irClass(const IR& ir, const ClassDecl& class_decl) {
+    JsonValue node = JsonValue::object().set("name", class_decl.name);
+    setSpan(node, class_decl.span);
+    node.set("copy_semantics", copySemanticsName(class_decl.copy_semantics));
+    node.set("needs_c_layout", class_decl.needs_c_layout);
+    node.set("functor", class_decl.is_functor);
+    if (class_decl.has_class_allocator) node.set("class_allocator", true);
+    node.set("thread_safe", class_decl.thread_safe);
+
+    JsonValue bases = JsonValue::array();
+    for (const auto& base : class_decl.base_classes) {
+        JsonValue base_node = JsonValue::object().set("name", base).set("declared", ir.findClass(base) != nullptr);
+        if (std::find(class_decl.virtual_bases.begin(), class_decl.virtual_bases.end(), base) !=
+            class_decl.virtual_bases.end()) {
+            base_node.set("virtual", true);
+        }
+        for (const auto& subobject : class_decl.base_subobjects) {
+            if (subobject.name == base) base_node.set("embedded", true).set("polymorphic", subobject.is_polymorphic);
+        }
+        bases.push(base_node);
+    }
+    node.set("bases", bases);
+
+    if (class_decl.is_template) {
+        JsonValue instantiations = JsonValue::array();
+        for (const auto& instantiation : class_decl.instantiations) {
+            JsonValue arguments = JsonValue::array();
+            for (const auto& arg : instantiation.arguments) arguments.push(arg);
+            instantiations.push(arguments);
+        }
+        node.set("instantiations", instantiations);
+    }
+
+    JsonValue fields = JsonValue::array();
+    for (const auto& field : class_decl.fields) {
+        JsonValue field_node = JsonValue::object()
+            .set("name", field.name)
+            .set("type", irType(ir, field.type));
+        setSpan(field_node, field.span);
+        fields.push(field_node);
+    }
+    node.set("fields", fields);
+
+    JsonValue methods = JsonValue::array();
+    for (const auto& method : class_decl.methods) methods.push(irFunction(ir, method));
+    node.set("methods", methods);
+    return node;
+}
+
+} // namespace
+
+std::string typeKindName(TypeKind kind) {
+    switch (kind) {
+        case TypeKind::Void:                 return "void";
+        case TypeKind::Bool:                 return "bool";
+        case TypeKind::Integer:              return "integer";
+        case TypeKind::Float:                return "float";
+        case TypeKind::Pointer:              return "pointer";
+        case TypeKind::Reference:            return "reference";
+        case TypeKind::RValueReference:      return "rvalue_reference";
+        case TypeKind::Array:                return "array";
+        case TypeKind::Struct:               return "struct";
+        case TypeKind::Class:                return "class";
+        case TypeKind::Enum:                 return "enum";
+        case TypeKind::Function:             return "function";
+        case TypeKind::Template:             return "template";
+        case TypeKind::StdVector:            return "std_vector";
+        case TypeKind::StdList:              return "std_list";
+        case TypeKind::StdDeque:             return "std_deque";
+        case TypeKind::StdMap:               return "std_map";
+        case TypeKind::StdUnorderedMap:      return "std_unordered_map";
+        case TypeKind::StdSet:               return "std_set";
+        case TypeKind::StdUnorderedSet:      return "std_unordered_set";
+        case TypeKind::StdString:            return "std_string";
+        case TypeKind::StdPair:              return "std_pair";
+        case TypeKind::StdTuple:             return "std_tuple";
+        case TypeKind::StdOptional:          return "std_optional";
+        case TypeKind::StdThread:            return "std_thread";
+        case TypeKind::StdMutex:             return "std_mutex";
+        case TypeKind::StdRecursiveMutex:    return "std_recursive_mutex";
+        case TypeKind::StdSharedMutex:       return "std_shared_mutex";
+        case TypeKind::StdConditionVariable: return "std_condition_variable";
+        case TypeKind::StdAtomic:            return "std_atomic";
+        case TypeKind::StdLockGuard:         return "std_lock_guard";
+        case TypeKind::StdUniqueLock:        return "std_unique_lock";
+        case TypeKind::StdSharedLock:        return "std_shared_lock";
+        case TypeKind::StdFuture:            return "std_future";
+        case TypeKind::StdPromise:           return "std_promise";
+        case TypeKind::StdAsync:             return "std_async";
+        case TypeKind::Coroutine:            return "coroutine";
+        case TypeKind::Task:                 return "task";
+    }
+    return "unknown";
+}
+
+JsonValue astToJson(const IR& ir) {
+    JsonValue enums = JsonValue::array();
+    for (const auto& enum_decl : ir.getEnums()) enums.push(enumNode(enum_decl));
+
+    JsonValue classes = JsonValue::array();
+    for (const auto& class_decl : ir.getClasses()) classes.push(astClass(class_decl));
+
+    JsonValue functions = JsonValue::array();
+    for (const auto& func : ir.getFunctions()) functions.push(astFunction(func));
+
+    return JsonValue::object()
+        .set("format", "hybrid-ast")
+        .set("version", FORMAT_VERSION)
+        .set("enums", enums)
+        .set("classes", classes)
+        .set("functions", functions);
+}
+
+JsonValue irToJson(const IR& ir) {
+    JsonValue enums = JsonValue::array();
+    for (const auto& enum_decl : ir.getEnums()) {
+        JsonValue enum_json = enumNode(enum_decl).set("error_code", enum_decl.is_error_code);
+        if (enum_decl.is_error_code) enum_json.set("success", enum_decl.success_value);
+        enums.push(enum_json);
+    }
+
+    JsonValue classes = JsonValue::array();
+    for (const auto& class_decl : ir.getClasses()) classes.push(irClass(ir, class_decl));
+
+    JsonValue functions = JsonValue::array();
+    for (const auto& func : ir.getFunctions()) functions.push(irFunction(ir, func));
+
+    JsonValue diagnostics = JsonValue::array();
+    for (const auto& diagnostic : ir.getDiagnostics()) {
+        diagnostics.push(JsonValue::object()
+            .set("severity", severityName(diagnostic.severity))
+            .set("message", diagnostic.message)
+            .set("context", diagnostic.context));
+    }
+
+    return JsonValue::object()
+        .set("format", "hybrid-ir")
+        .set("version", FORMAT_VERSION)
+        .set("enums", enums)
+        .set("classes", classes)
+        .set("functions", functions)
+        .set("diagnostics", diagnostics);
+}
+
+} // namespace hybrid
@@ -0,0 +1,204 @@
+#include "symbols.h"
+#include <regex>
+#include <algorithm>
+
+namespace hybrid {
+
+SymbolDatabase::SymbolDatabase(const IR& ir) {
+    for (const auto& class_decl : ir.getClasses()) {
+        addSymbol(class_decl.name);
+        classes_.insert(class_decl.name);
+        for (const auto& method : class_decl.methods) {
+            std::string symbol = methodSymbol(class_decl, method);
+            addSymbol(symbol);
+            methods_of_[class_decl.name].push_back(symbol);
+            by_name_[method.name].push_back(symbol);
+        }
+    }
+
+    for (const auto& func : ir.getFunctions()) {
+        addSymbol(func.name);
+        by_name_[func.name].push_back(func.name);
+    }
+
+    for (const auto& class_decl : ir.getClasses()) {
+        const std::string& name = class_decl.name;
+
+        for (const auto& base : class_decl.base_classes) {
+            references_[name].insert(base);
+        }
+        for (const auto& field : class_decl.fields) {
+            addTypeReferences(name, field.type);
+        }
+
+        for (const auto& method : class_decl.methods) {
+            std::string symbol = methodSymbol(class_decl, method);
+            references_[symbol].insert(name);
+
+            // Special members are part of the type; virtual methods and
+            // overrides may be reached through dynamic dispatch
+            bool overrides = false;
+            for (const auto& base : class_decl.base_classes) {
+                const ClassDecl* base_decl = ir.findClass(base);
+                if (!base_decl) continue;
+                for (const auto& base_method : base_decl->methods) {
+                    overrides = overrides || base_method.name == method.name;
+                }
+            }
+            if (method.is_constructor || method.is_destructor || method.is_virtual ||
+                method.is_pure_virtual || overrides) {
+                references_[name].insert(symbol);
+            }
+
+            addTypeReferences(symbol, method.return_type);
+            for (const auto& param : method.parameters) {
+                addTypeReferences(symbol, param.type);
+            }
+            addBodyReferences(symbol, name, method.member_initializers + " " + method.body);
+        }
+    }
+
+    for (const auto& func : ir.getFunctions()) {
+        addTypeReferences(func.name, func.return_type);
+        for (const auto& param : func.parameters) {
+            addTypeReferences(func.name, param.type);
+        }
+        addBodyReferences(func.name, "", func.body);
+    }
+}
+
+bool SymbolDatabase::contains(const std::string& symbol) const {
+    return references_.count(symbol) > 0;
+}
+
+const std::set<std::string>& SymbolDatabase::referencesOf(const std::string& symbol) const {
+    static const std::set<std::string> none;
+    auto it = references_.find(symbol);
+    return it != references_.end() ? it->second : none;
+}
+
+std::set<std::string> SymbolDatabase::reachableFrom(const std::vector<std::string>& roots) const {
+    std::set<std::string> reachable;
+    std::vector<std::string> pending;
+
+    for (const auto& root : roots) {
+        pending.push_back(root);
+        auto methods = methods_of_.find(root);
+        if (methods != methods_of_.end()) {
+            pending.insert(pending.end(), methods->second.begin(), methods->second.end());
+        }
+    }
+
+    while (!pending.empty()) {
+        std::string symbol = pending.back();
+        pending.pop_back();
+        if (!contains(symbol) || !reachable.insert(symbol).second) {
+            continue;
+        }
+        for (const auto& ref : referencesOf(symbol)) {
+            pending.push_back(ref);
+        }
+    }
+
+    return reachable;
+}
+
+std::string SymbolDatabase::methodSymbol(const ClassDecl& class_decl, const Function& method) {
+    return class_decl.name + "::" + (method.is_destructor ? "~" : "") + method.name;
+}
+
+void SymbolDatabase::addSymbol(const std::string& symbol) {
+    if (references_.emplace(symbol, std::set<std::string>()).second) {
+        symbols_.push_back(symbol);
+    }
+}
+
+void SymbolDatabase::addTypeReferences(const std::string& from, const std::shared_ptr<Type>& type) {
+    if (!type) return;
+
+    // Type names may be qualified or templated: std::vector<Point>
+    static const std::regex id_pattern(R"([A-Za-z_]\w*)");
+    for (auto it = std::sregex_iterator(type->name.begin(), type->name.end(), id_pattern);
+         it != std::sregex_iterator(); ++it) {
+        if (classes_.count(it->str()) && it->str() != from) {
+            references_[from].insert(it->str());
+        }
+    }
+
+    addTypeReferences(from, type->element_type);
+    for (const auto& arg : type->template_args) {
+        addTypeReferences(from, arg);
+    }
+}
+
+void SymbolDatabase::addBodyReferences(const std::string& from, const std::string& owner_class,
+                                       const std::string& body) {
+    static const std::regex use_pattern(R"(([A-Za-z_]\w*)\s*(\()?)");
+
+    for (auto it = std::sregex_iterator(body.begin(), body.end(), use_pattern);
+         it != std::sregex_iterator(); ++it) {
+        std::string name = (*it)[1].str();
+        bool is_call = (*it)[2].matched;
+
+        // Type names (declarations, casts, constructor calls)
+        if (classes_.count(name)) {
+            references_[from].insert(name);
+        }
+        if (!is_call) continue;
+
+        // Unqualified call inside a class resolves to its own method first;
+        // calls through objects conservatively reach every method of that name
+        std::string own = owner_class + "::" + name;
+        if (!owner_class.empty() && contains(own)) {
+            references_[from].insert(own);
+            continue;
+        }
+        auto candidates = by_name_.find(name);
+        if (candidates != by_name_.end()) {
+            references_[from].insert(candidates->second.begin(), candidates->second.end());
+        }
+    }
+
+    references_[from].erase(from);
+}
+
+size_t pruneUnused(IR& ir, const std::vector<std::string>& entry_points,
+                   std::vector<std::string>& unknown) {
+    SymbolDatabase symbols(ir);
+
+    for (const auto& entry : entry_points) {
+        if (!symbols.contains(entry)) {
+            unknown.push_back(entry);
+        }
+    }
+
+    std::set<std::string> keep = symbols.reachableFrom(entry_points);
+    size_t removed = 0;
+
+    auto& classes = ir.getClasses();
+    for (auto& class_decl : classes) {
+        auto& methods = class_decl.methods;
+        size_t before = methods.size();
+        methods.erase(std::remove_if(methods.begin(), methods.end(), [&](const Function& method) {
+            return !keep.count(SymbolDatabase::methodSymbol(class_decl, method));
+        }), methods.end());
+        removed += before - methods.size();
+    }
+
+    size_t classes_before = classes.size();
+    classes.erase(std::remove_if(classes.begin(), classes.end(), [&](const ClassDecl& class_decl) {
+        return !keep.count(class_decl.name);
+    }), classes.end());
+    removed += classes_before - classes.size();
+
+    auto& functions = ir.getFunctions();
+    size_t functions_before = functions.size();
+    functions.erase(std::remove_if(functions.begin(), functions.end(), [&](const Function& func) {
+        return !keep.count(func.name);
+    }), functions.end());
+    removed += functions_before - functions.size();
+
+    return removed;
+}
+
+} // namespace hybrid
@@ -0,0 +1,495 @@
+#include "lsp.h"
+#include "parser.h"
+#include <istream>
+#include <ostream>
+#include <regex>
+#include <algorithm>
+#include <cctype>
+#include <cstdlib>
+
+namespace hybrid {
+
+namespace {
+
+// JSON-RPC error codes
+const int PARSE_ERROR = -32700;
+const int INVALID_REQUEST = -32600;
+const int METHOD_NOT_FOUND = -32601;
+const int INVALID_PARAMS = -32602;
+
+// Largest message body read into memory
+const long MAX_CONTENT_LENGTH = 64L * 1024 * 1024;
+
+// LSP DiagnosticSeverity
+const int SEVERITY_ERROR = 1;
+const int SEVERITY_WARNING = 2;
+const int SEVERITY_INFORMATION = 3;
+
+JsonValue response(const JsonValue& id, const JsonValue& result) {
+    return JsonValue::object().set("jsonrpc", "2.0").set("id", id).set("result", result);
+}
+
+JsonValue errorResponse(const JsonValue& id, int code, const std::string& message) {
+    JsonValue error = JsonValue::object().set("code", code).set("message", message);
+    return JsonValue::object().set("jsonrpc", "2.0").set("id", id).set("error", error);
+}
+
+JsonValue notification(const std::string& method, const JsonValue& params) {
+    return JsonValue::object().set("jsonrpc", "2.0").set("method", method).set("params", params);
+}
+
+/**
+ * LSP position of a byte offset: `character` counts UTF-16 code units
+ */
+JsonValue position(const std::string& text, size_t offset) {
+    size_t line = 0;
+    size_t character = 0;
+    for (size_t i = 0; i < offset && i < text.size(); ++i) {
+        unsigned char c = static_cast<unsigned char>(text[i]);
+        if (c == '\n') {
+            ++line;
+            character = 0;
+        } else if ((c & 0xC0) != 0x80) {
+            // One unit per character, two (a surrogate pair) beyond the BMP
+            character += c >= 0xF0 ? 2 : 1;
+        }
+    }
+    return JsonValue::object().set("line", line).set("character", character);
+}
+
+JsonValue range(const std::string& text, size_t begin, size_t end) {
+    return JsonValue::object().set("start", position(text, begin)).set("end", position(text, end));
+}
+
+/**
+ * Range of the whole given line (0-based), clamped to the document
+ */
+JsonValue lineRange(const std::string& text, size_t line) {
+    size_t begin = 0;
+    for (size_t i = 0; i < line; ++i) {
+        size_t next = text.find('\n', begin);
+        if (next == std::string::npos) break;
+        begin = next + 1;
+    }
+    size_t end = text.find('\n', begin);
+    return range(text, begin, end == std::string::npos ? text.size() : end);
+}
+
+JsonValue diagnostic(const JsonValue& range, int severity, const std::string& message) {
+    return JsonValue::object()
+        .set("range", range)
+        .set("severity", severity)
+        .set("source", "hybrid-transpiler")
+        .set("message", message);
+}
+
+/**
+ * A top-level declaration that has a body
+ */
+struct Definition {
+    size_t begin;
+    size_t end;             // One past the closing '}'
+    std::string header;     // Source before the opening '{'
+};
+
+/**
+ * Find top-level definitions, looking through namespace and extern "C" blocks
+ */
+std::vector<Definition> topLevelDefinitions(const std::string& text) {
+    std::vector<Definition> definitions;
+    size_t decl_start = std::string::npos;
+    size_t header_end = std::string::npos;
+    int depth = 0;              // Braces opened inside the current declaration
+    int transparent = 0;        // Enclosing namespace/extern blocks
+    bool line_start = true;
+
+    for (size_t i = 0; i < text.size(); ++i) {
+        char c = text[i];
+
+        // Comments, literals and preprocessor lines
+        if (text.compare(i, 2, "//") == 0) {
+            i = std::min(text.find('\n', i), text.size()) - 1;
+            continue;
+        }
+        if (text.compare(i, 2, "/*") == 0) {
+            size_t close = text.find("*/", i + 2);
+            i = close == std::string::npos ? text.size() : close + 1;
+            continue;
+        }
+        if (c == '"' || c == '\'') {
+            size_t j = i + 1;
+            while (j < text.size() && text[j] != c && text[j] != '\n') {
+                j += text[j] == '\\' ? 2 : 1;
+            }
+            i = std::min(j, text.size());
+            line_start = false;
+            continue;
+        }
+        if (c == '\n') {
+            line_start = true;
+            continue;
+        }
+        if (c == '#' && line_start && decl_start == std::string::npos) {
+            i = std::min(text.find('\n', i), text.size()) - 1;
+            continue;
+        }
+        if (std::isspace(static_cast<unsigned char>(c))) {
+            continue;
+        }
+        line_start = false;
+
+        if (decl_start == std::string::npos) {
+            if (c == '}' && transparent > 0) {
+                --transparent;
+                continue;
+            }
+            if (c == ';' || c == '}') continue;
+            decl_start = i;
+        }
+
+        if (c == '{') {
+            if (depth == 0) {
+                std::string header = text.substr(decl_start, i - decl_start);
+                static const std::regex scope_pattern(R"(^(?:inline\s+)?namespace\b|^extern\s*"C")");
+                if (std::regex_search(header.substr(0, 64), scope_pattern) &&
+                    header.find('(') == std::string::npos) {
+                    ++transparent;
+                    decl_start = std::string::npos;
+                    continue;
+                }
+                header_end = i;
+            }
+            ++depth;
+        } else if (c == '}' && depth > 0) {
+            if (--depth == 0) {
+                definitions.push_back({decl_start, i + 1, text.substr(decl_start, header_end - decl_start)});
+                decl_start = std::string::npos;
+            }
+        } else if (c == ';' && depth == 0) {
+            decl_start = std::string::npos;   // Declaration without a body
+        }
+    }
+
+    return definitions;
+}
+
+/**
+ * Describe a definition the transpiler leaves out, or "" if it is translated
+ */
+std::string untranslatedReason(const Definition& definition, const IR& ir) {
+    std::string header = std::regex_replace(definition.header, std::regex(R"(\s+)"), " ");
+
+    static const std::regex type_pattern(
+        R"(^(?:template\s*<[^>]*>\s*)?(?:typedef\s+)?(class|struct|union|enum)\s+(?:(?:class|struct)\s+)?(?:alignas\s*\([^)]*\)\s*)?([A-Za-z_]\w*))");
+    static const std::regex function_pattern(R"(((?:[A-Za-z_]\w*\s*::\s*)*~?[A-Za-z_]\w*|operator\s*[^\s(]+)\s*\()");
+    static const std::regex variable_pattern(R"(([A-Za-z_]\w*)\s*(?:\[[^\]]*\]\s*)*=)");
+
+    std::smatch match;
+    if (std::regex_search(header, match, type_pattern)) {
+        std::string kind = match[1].str();
+        std::string name = match[2].str();
+        return ir.findClass(name) ? "" : kind + " '" + name + "' is not translated";
+    }
+
+    // `int table[] = { ... }`, `auto f = [] { ... }`
+    size_t equals = header.find('=');
+    if (equals != std::string::npos && equals < header.find('(') &&
+        header.find("operator") == std::string::npos) {
+        std::string declarator = header.substr(0, equals + 1);
+        if (std::regex_search(declarator, match, variable_pattern)) {
+            return "variable '" + match[1].str() + "' is not translated";
+        }
+        return "";
+    }
+
+    if (std::regex_search(header, match, function_pattern)) {
+        std::string name = std::regex_replace(match[1].str(), std::regex(R"(\s+)"), "");
+        size_t scope = name.rfind("::");
+
+        if (scope == std::string::npos) {
+            for (const auto& func : ir.getFunctions()) {
+                if (func.name == name) return "";
+            }
+        } else {
+            // Out-of-class member definition: translated if the class carries the body
+            std::string class_name = name.substr(0, scope);
+            class_name = class_name.substr(class_name.rfind("::") == std::string::npos
+                                           ? 0 : class_name.rfind("::") + 2);
+            std::string method_name = name.substr(scope + 2);
+            const ClassDecl* class_decl = ir.findClass(class_name);
+            if (class_decl) {
+                for (const auto& method : class_decl->methods) {
+                    if (method.name == method_name && !method.body.empty()) return "";
+                }
+            }
+        }
+        return "function '" + name + "' is not translated";
+    }
+
+    return "";
+}
+
+/**
+ * Line of the first whole-word occurrence of the innermost name in a
+ * diagnostic context such as "Class::method"
+ */
+size_t contextLine(const std::string& text, const std::string& context) {
+    std::string name = context.substr(context.rfind("::") == std::string::npos ? 0 : context.rfind("::") + 2);
+    if (name.empty()) return 0;
+
+    size_t from = 0;
+    size_t scope = context.find("::");
+    if (scope != std::string::npos) {
+        size_t class_pos = text.find("class " + context.substr(0, scope));
+        from = class_pos == std::string::npos ? 0 : class_pos;
+    }
+
+    for (size_t pos = text.find(name, from); pos != std::string::npos; pos = text.find(name, pos + 1)) {
+        bool starts = pos == 0 || !(std::isalnum(static_cast<unsigned char>(text[pos - 1])) || text[pos - 1] == '_');
+        size_t after = pos + name.size();
+        bool ends = after >= text.size() ||
+                    !(std::isalnum(static_cast<unsigned char>(text[after])) || text[after] == '_');
+        if (starts && ends) {
+            size_t line = 0;
+            for (size_t i = 0; i < pos; ++i) {
+                if (text[i] == '\n') ++line;
+            }
+            return line;
+        }
+    }
+    return 0;
+}
+
+} // namespace
+
+LspServer::LspServer(std::istream& in, std::ostream& out) : in_(in), out_(out) {}
+
+int LspServer::run() {
+    std::string content;
+    std::string error;
+    while (!exit_requested_ && readMessage(content, error)) {
+        if (!error.empty()) {
+            writeMessage(errorResponse(JsonValue(), PARSE_ERROR, error));
+            continue;
+        }
+
+        JsonValue message;
+        try {
+            message = JsonValue::parse(content);
+        }
+        catch (const std::exception& e) {
+            writeMessage(errorResponse(JsonValue(), PARSE_ERROR, e.what()));
+            continue;
+        }
+
+        for (const auto& reply : handleMessage(message)) {
+            writeMessage(reply);
+        }
+    }
+    return shutdown_requested_ ? 0 : 1;
+}
+
+std::vector<JsonValue> LspServer::handleMessage(const JsonValue& message) {
+    std::vector<JsonValue> out;
+    const std::string& method = message["method"].asString();
+    const JsonValue& params = message["params"];
+    const JsonValue& id = message["id"];
+    bool is_request = message.has("id");
+
+    if (method.empty()) {
+        if (is_request) {
+            out.push_back(errorResponse(id, INVALID_REQUEST, "Missing method"));
+        }
+        return out;  // Responses from the client are not used
+    }
+
+    if (shutdown_requested_ && method != "exit") {
+        if (is_request) {
+            out.push_back(errorResponse(id, INVALID_REQUEST, "Server is shutting down"));
+        }
+        return out;
+    }
+
+    if (method == "initialize") {
+        const std::string& target = params["initializationOptions"]["target"].asString();
+        if (target == "go") {
+            options_.target = TargetLanguage::Go;
+        } else if (target == "rust") {
+            options_.target = TargetLanguage::Rust;
+        }
+        parseStandardName(params["initializationOptions"]["std"].asString(), options_.standard);
+        out.push_back(response(id, initializeResult()));
+    } else if (method == "shutdown") {
+        shutdown_requested_ = true;
+        out.push_back(response(id, JsonValue()));
+    } else if (method == "exit") {
+        exit_requested_ = true;
+    } else if (method == "textDocument/didOpen") {
+        const JsonValue& document = params["textDocument"];
+        update(document["uri"].asString(), document["text"].asString(), out);
+    } else if (method == "textDocument/didChange") {
+        // Full document sync: the last change holds the whole text
+        const auto& changes = params["contentChanges"].asArray();
+        if (!changes.empty()) {
+            update(params["textDocument"]["uri"].asString(), changes.back()["text"].asString(), out);
+        }
+    } else if (method == "textDocument/didClose") {
+        std::string uri = params["textDocument"]["uri"].asString();
+        documents_.erase(uri);
+        out.push_back(notification("textDocument/publishDiagnostics",
+            JsonValue::object().set("uri", uri).set("diagnostics", JsonValue::array())));
+    } else if (method == "hybrid/translation" && is_request) {
+        std::string uri = params["textDocument"]["uri"].asString();
+        if (!documents_.count(uri)) {
+            out.push_back(errorResponse(id, INVALID_PARAMS, "Document is not open: " + uri));
+        } else {
+            out.push_back(response(id, translationParams(uri)));
+        }
+    } else if (is_request) {
+        out.push_back(errorResponse(id, METHOD_NOT_FOUND, "Unknown method: " + method));
+    }
+
+    return out;
+}
+
+std::string LspServer::translationUri(const std::string& uri) const {
+    std::string path = uri.compare(0, 7, "file://") == 0 ? uri.substr(7) : uri;
+
+    size_t slash = path.rfind('/');
+    size_t dot = path.rfind('.');
+    if (dot != std::string::npos && (slash == std::string::npos || dot > slash)) {
+        path = path.substr(0, dot);
+    }
+
+    bool go = options_.target == TargetLanguage::Go;
+    return std::string(go ? "hybrid-go:" : "hybrid-rust:") + path + (go ? ".go" : ".rs");
+}
+
+bool LspServer::readMessage(std::string& content, std::string& error) {
+    error.clear();
+    long length = -1;
+    std::string line;
+
+    while (std::getline(in_, line)) {
+        if (!line.empty() && line.back() == '\r') {
+            line.pop_back();
+        }
+        if (line.empty()) {
+            if (length >= 0) break;
+            continue;  // Stray blank line between messages
+        }
+
+        static const std::string header = "content-length:";
+        std::string lower;
+        for (char c : line.substr(0, header.size())) {
+            lower += static_cast<char>(std::tolower(static_cast<unsigned char>(c)));
+        }
+        if (lower == header) {
+            length = std::strtol(line.c_str() + header.size(), nullptr, 10);
+        }
+    }
+
+    if (length < 0 || !in_) {
+        return false;
+    }
+
+    if (length > MAX_CONTENT_LENGTH) {
+        // Skip the body without buffering it
+        in_.ignore(length);
+        error = "Content-Length " + std::to_string(length) + " exceeds the limit of " +
+                std::to_string(MAX_CONTENT_LENGTH) + " bytes";
+        return true;
+    }
+
+    content.assign(static_cast<size_t>(length), '\0');
+    in_.read(&content[0], length);
+    return in_.gcount() == length;
+}
+
+void LspServer::writeMessage(const JsonValue& message) {
+    std::string content = message.serialize();
+    out_ << "Content-Length: " << content.size() << "\r\n\r\n" << content;
+    out_.flush();
+}
+
+JsonValue LspServer::initializeResult() const {
+    JsonValue capabilities = JsonValue::object()
+        .set("textDocumentSync", 1);  // Full
+
+    JsonValue server_info = JsonValue::object()
+        .set("name", "hybrid-transpiler")
+        .set("version", "0.1.0");
+
+    return JsonValue::object()
+        .set("capabilities", capabilities)
+        .set("serverInfo", server_info);
+}
+
+void LspServer::update(const std::string& uri, const std::string& text, std::vector<JsonValue>& out) {
+    Document& document = documents_[uri];
+    document.text = text;
+    document.translation = transpileSnippet(text, options_);
+
+    out.push_back(notification("textDocument/publishDiagnostics",
+        JsonValue::object().set("uri", uri).set("diagnostics", diagnostics(uri))));
+    out.push_back(notification("hybrid/translation", translationParams(uri)));
+}
+
+JsonValue LspServer::translationParams(const std::string& uri) const {
+    const Document& document = documents_.at(uri);
+    return JsonValue::object()
+        .set("uri", uri)
+        .set("translationUri", translationUri(uri))
+        .set("languageId", options_.target == TargetLanguage::Go ? "go" : "rust")
+        .set("success", document.translation.success)
+        .set("text", document.translation.success ? document.translation.code : "");
+}
+
+JsonValue LspServer::diagnostics(const std::string& uri) const {
+    const Document& document = documents_.at(uri);
+    const std::string& text = document.text;
+    JsonValue result = JsonValue::array();
+
+    if (!document.translation.success) {
+        // Parser errors name the offending line when they can
+        std::smatch match;
+        size_t line = 0;
+        static const std::regex line_pattern(R"(line (\d+))");
+        if (std::regex_search(document.translation.error, match, line_pattern)) {
+            line = std::stoul(match[1].str()) - 1;
+        }
+        result.push(diagnostic(lineRange(text, line), SEVERITY_ERROR, document.translation.error));
+        return result;
+    }
+
+    for (const auto& reported : document.translation.diagnostics) {
+        int severity = reported.severity == Diagnostic::Error ? SEVERITY_ERROR
+                     : reported.severity == Diagnostic::Warning ? SEVERITY_WARNING
+                     : SEVERITY_INFORMATION;
+        result.push(diagnostic(lineRange(text, contextLine(text, reported.context)),
+                               severity, reported.message));
+    }
+
+    // Definitions the parser does not pick up never reach the output
+    IR ir;
+    try {
+        ir = Parser::parseString(text, options_.standard, options_.log_macros);
+    }
+    catch (const std::exception&) {
+        return result;
+    }
+
+    for (const auto& definition : topLevelDefinitions(text)) {
+        std::string reason = untranslatedReason(definition, ir);
+        if (reason.empty()) continue;
+
+        size_t line_end = text.find('\n', definition.begin);
+        size_t end = std::min(line_end == std::string::npos ? text.size() : line_end, definition.end);
+        result.push(diagnostic(range(text, definition.begin, end), SEVERITY_WARNING,
+                               reason + "; it is omitted from the output"));
+    }
+
+    return result;
+}
+
+} // namespace hybrid
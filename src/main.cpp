@@ -1,8 +1,15 @@
 #include "transpiler.h"
+#include "explain.h"
+#include "lsp.h"
+#include "apidiff.h"
 #include <iostream>
 #include <string>
 #include <vector>
 #include <fstream>
+#include <sstream>
+#include <filesystem>
+#include <algorithm>
+#include <iomanip>
 
 void printUsage(const char* program_name) {
     std::cout << "Hybrid Transpiler - Convert C++ code to modern, safe languages (Rust/Go)\n\n";
@@ -15,18 +22,90 @@ void printUsage(const char* program_name) {
     std::cout << "  • Threading → Safe concurrency\n";
     std::cout << "  • Async/Coroutines → async/await\n\n";
 
-    std::cout << "Usage: " << program_name << " [options]\n\n";
+    std::cout << "Usage: " << program_name << " [options]\n";
+    std::cout << "       " << program_name << " lsp [--target <lang>]\n";
+    std::cout << "       " << program_name << " apidiff <C++ file or dir>... <Rust file or dir>\n\n";
+
+    std::cout << "Commands:\n";
+    std::cout << "  lsp                     Run a language server on stdin/stdout that\n";
+    std::cout << "                          publishes the live translation of open C++ files\n";
+    std::cout << "  apidiff                 Compare the public API of C++ headers with that of\n";
+    std::cout << "                          the generated crate (missing, changed, renamed items)\n\n";
 
     std::cout << "Options:\n";
     std::cout << "  -i, --input <file>      Input C++ source file (required)\n";
     std::cout << "  -o, --output <file>     Output file path (auto-generated if omitted)\n";
     std::cout << "  -t, --target <lang>     Target language: rust, go [default: rust]\n";
+    std::cout << "  --std <standard>        C++ standard of the input: c++11, c++14, c++17, c++20\n";
+    std::cout << "                          [default: c++20] (also --std=<standard>)\n";
     std::cout << "  -O, --opt-level <N>     Optimization level 0-3 [default: 0]\n";
     std::cout << "                          0 = readable, 1 = balanced,\n";
     std::cout << "                          2 = optimized, 3 = aggressive\n";
     std::cout << "  --no-safety-checks      Disable safety checks\n";
     std::cout << "  --no-comments           Don't preserve comments\n";
     std::cout << "  --gen-tests             Generate test cases\n";
+    std::cout << "  --thiserror             Derive thiserror::Error for error-code enums\n";
+    std::cout << "  --varargs <strategy>    C variadic functions: slice (&[VarArg] argument),\n";
+    std::cout << "                          macro (slice plus macro_rules! front-end),\n";
+    std::cout << "                          ffi (kept in C++, extern \"C\") [default: slice]\n";
+    std::cout << "  --allocator-api         Keep custom allocators as Allocator type parameters\n";
+    std::cout << "                          (nightly #![feature(allocator_api)])\n";
+    std::cout << "  --self-ref <strategy>   Classes pointing at themselves or each other:\n";
+    std::cout << "                          report (raw pointers), pin (Pin<Box<Self>>),\n";
+    std::cout << "                          arena (Arena<T> with generational Id<T>) [default: report]\n";
+    std::cout << "  --data-model <model>    Width of long: lp64 (64 bits, Linux/macOS),\n";
+    std::cout << "                          llp64 (32 bits, Windows) [default: lp64]\n";
+    std::cout << "  --libc <strategy>       Direct libc calls (open, read, memcpy, socket...):\n";
+    std::cout << "                          std (std::fs/io/net where they fit), crate (libc crate)\n";
+    std::cout << "                          [default: std]\n";
+    std::cout << "  --clap                  Replace the getopt or argv loop of main with a clap\n";
+    std::cout << "                          derive struct\n";
+    std::cout << "  --strum                 Enums with toString/parse functions derive strum::Display\n";
+    std::cout << "                          and strum::EnumString instead of hand-written impls\n";
+    std::cout << "  --log-macro <NAME=lvl>  Map a logging macro to log::<lvl>! (repeatable);\n";
+    std::cout << "                          lvl: error, warn, info, debug, trace\n";
+    std::cout << "  --mapping-pack <pack>   Translate library types with a mapping pack (repeatable):\n";
+    std::cout << "                          boost, qt, or a JSON pack file\n";
+    std::cout << "  --runtime-trace <file>  Threads observed by an instrumented build (JSON): Rc or\n";
+    std::cout << "                          Arc, Mutex or RefCell where the source is not conclusive\n";
+    std::cout << "  --compile-commands <p>  compile_commands.json (or its build directory): each file\n";
+    std::cout << "                          is preprocessed with its own -D, -U, -I and --target\n";
+    std::cout << "  --target-config <name>  For files compiled more than once: the command whose\n";
+    std::cout << "                          --target, build directory or -D macro is <name>\n";
+    std::cout << "  --split-modules         Follow #include \"...\" and emit a Rust module tree;\n";
+    std::cout << "                          -o names the output directory\n";
+    std::cout << "  --library               Header-only library: emit a crate (Cargo.toml, src/lib.rs)\n";
+    std::cout << "                          with C++ access control and doc comments; main is\n";
+    std::cout << "                          left out; -o names the crate directory\n";
+    std::cout << "  --split-crates-by <by>  Emit a Cargo workspace with one crate per dir (directory)\n";
+    std::cout << "                          or target (CMake target, needs --compile-commands);\n";
+    std::cout << "                          -o names the workspace directory\n";
+    std::cout << "  --prune-unused          Skip declarations not reachable from --entry symbols\n";
+    std::cout << "  --entry <symbol>        Entry point for --prune-unused (repeatable):\n";
+    std::cout << "                          Class, Class::method or function\n";
+    std::cout << "  --defer <class>         Leave a class for a later migration phase (repeatable):\n";
+    std::cout << "                          its struct, a trait of its methods and todo!() bodies\n";
+    std::cout << "  --disable-pass <pass>   Skip an analysis pass (repeatable)\n";
+    std::cout << "  --list-passes           Print the passes of the pipeline, in order\n";
+    std::cout << "  --dump-ir-after <pass>  Write the IR (JSON) after a pass, or after every pass\n";
+    std::cout << "                          with 'all' (repeatable)\n";
+    std::cout << "  --dump-ir-dir <dir>     Where --dump-ir-after writes <position>-<pass>.json\n";
+    std::cout << "  --explain <symbol>      Print the rules applied to a class, Class::method or\n";
+    std::cout << "                          function, and why, instead of writing output\n";
+    std::cout << "  --emit <kind>           What to write: code, ast-json, ir-json [default: code]\n";
+    std::cout << "                          (also --emit=<kind>)\n";
+    std::cout << "  --metrics <file>        Write translation statistics (JSON) to <file>\n";
+    std::cout << "  --report <file>         Write diagnostics, metrics, unsafe code and renamed\n";
+    std::cout << "                          symbols as one versioned JSON report to <file>\n";
+    std::cout << "  --max-unsafe <n>        Fail when the output has more than n unsafe blocks\n";
+    std::cout << "                          per 1000 lines (the output is still written)\n";
+    std::cout << "  --max-clones <n>        Fail when the output has more than n .clone() calls\n";
+    std::cout << "                          per 1000 lines\n";
+    std::cout << "  --deterministic         Fail unless a second parse, analysis and generation\n";
+    std::cout << "                          write the same output; no timings in --metrics\n";
+    std::cout << "  --update                Merge into the existing output: retranslate the items\n";
+    std::cout << "                          whose C++ changed, keep hand-edited ones\n";
+    std::cout << "  --force                 With --update, overwrite hand-edited items anyway\n";
     std::cout << "  --verbose               Enable verbose output\n";
     std::cout << "  --quiet                 Minimal output (errors only)\n";
     std::cout << "  -h, --help              Show this help message\n";
@@ -35,6 +114,8 @@ void printUsage(const char* program_name) {
     std::cout << "Examples:\n";
     std::cout << "  # Basic conversion to Rust\n";
     std::cout << "  " << program_name << " -i example.cpp -o example.rs\n\n";
+    std::cout << "  # Reject features newer than C++14\n";
+    std::cout << "  " << program_name << " -i legacy.cpp --std=c++14\n\n";
     std::cout << "  # Convert to Go with optimization\n";
     std::cout << "  " << program_name << " -i myclass.cpp -t go -O2\n\n";
     std::cout << "  # Auto-generate output filename\n";
@@ -46,6 +127,60 @@ void printUsage(const char* program_name) {
     std::cout << "  " << program_name << " -i example.cpp --quiet\n\n";
     std::cout << "  # Generate with test cases\n";
     std::cout << "  " << program_name << " -i vector.cpp --gen-tests\n\n";
+    std::cout << "  # Error-code enums as thiserror types\n";
+    std::cout << "  " << program_name << " -i codec.cpp --thiserror\n\n";
+    std::cout << "  # Keep printf-style functions in C++ and call them through FFI\n";
+    std::cout << "  " << program_name << " -i logging.cpp --varargs ffi\n\n";
+    std::cout << "  # Pool-allocated containers on nightly Rust\n";
+    std::cout << "  " << program_name << " -i arena.cpp --allocator-api\n\n";
+    std::cout << "  # Linked lists and graphs through generational ids\n";
+    std::cout << "  " << program_name << " -i graph.cpp --self-ref arena\n\n";
+    std::cout << "  # Code written for Windows, where long is 32 bits\n";
+    std::cout << "  " << program_name << " -i codec.cpp --data-model llp64\n\n";
+    std::cout << "  # Keep POSIX calls as they are, through the libc crate\n";
+    std::cout << "  " << program_name << " -i daemon.cpp --libc crate\n\n";
+    std::cout << "  # A command-line tool with a clap argument parser\n";
+    std::cout << "  " << program_name << " -i tool.cpp --clap\n\n";
+    std::cout << "  # Enum string conversions as strum derives\n";
+    std::cout << "  " << program_name << " -i colors.cpp --strum\n\n";
+    std::cout << "  # Map a project logging macro defined in an unseen header\n";
+    std::cout << "  " << program_name << " -i server.cpp --log-macro TRACE_MSG=trace\n\n";
+    std::cout << "  # Qt strings and containers as String and Vec\n";
+    std::cout << "  " << program_name << " -i editor.cpp --mapping-pack qt\n\n";
+    std::cout << "  # Drop the locks a profiling run showed were never shared\n";
+    std::cout << "  " << program_name << " -i cache.cpp --runtime-trace cache_trace.json\n\n";
+    std::cout << "  # Translate the Windows build of a cross-platform file\n";
+    std::cout << "  " << program_name << " -i src/fs.cpp --compile-commands build --target-config windows-x64\n\n";
+    std::cout << "  # Only translate what Client::connect needs\n";
+    std::cout << "  " << program_name << " -i lib.cpp --prune-unused --entry Client::connect\n\n";
+    std::cout << "  # Split included headers into a module tree\n";
+    std::cout << "  " << program_name << " -i app.cpp --split-modules -o app_rs\n\n";
+    std::cout << "  # Publish a header-only library as a crate\n";
+    std::cout << "  " << program_name << " -i include/geometry.hpp --library -o geometry\n\n";
+    std::cout << "  # One crate per CMake target, with path dependencies between them\n";
+    std::cout << "  " << program_name << " -i src/main.cpp --compile-commands build --split-crates-by target -o app\n\n";
+    std::cout << "  # Retranslate after a C++ change without losing hand edits\n";
+    std::cout << "  " << program_name << " -i engine.cpp -o engine.rs --update\n\n";
+    std::cout << "  # Translate the rest now, stub Renderer so callers compile\n";
+    std::cout << "  " << program_name << " -i engine.cpp --defer Renderer\n\n";
+    std::cout << "  # Why Cache::lookup returns an Arc and clones its key\n";
+    std::cout << "  " << program_name << " -i cache.cpp --explain Cache::lookup\n\n";
+    std::cout << "  # What the copy analysis changes in the IR, and the output without it\n";
+    std::cout << "  " << program_name << " -i engine.cpp --dump-ir-after copies --dump-ir-dir ir\n";
+    std::cout << "  " << program_name << " -i engine.cpp --disable-pass copies\n\n";
+    std::cout << "  # Record translation statistics for progress tracking\n";
+    std::cout << "  " << program_name << " -i engine.cpp --metrics engine.metrics.json\n\n";
+    std::cout << "  # Translation-quality budget for CI: no unsafe, at most 20 clones per 1000 lines\n";
+    std::cout << "  " << program_name << " -i engine.cpp --max-unsafe 0 --max-clones 20\n\n";
+    std::cout << "  # Machine-readable report for a dashboard (docs/report.schema.json)\n";
+    std::cout << "  " << program_name << " -i engine.cpp --report engine.report.json\n\n";
+    std::cout << "  # Dump the analyzed IR as JSON\n";
+    std::cout << "  " << program_name << " -i point.cpp --emit=ir-json\n";
+    std::cout << "  # Output: point.ir.json\n\n";
+    std::cout << "  # Check that the crate still covers the public API of the headers\n";
+    std::cout << "  " << program_name << " apidiff include/ engine_rs/\n\n";
+    std::cout << "  # Language server for editor integration\n";
+    std::cout << "  " << program_name << " lsp\n\n";
 
     std::cout << "Supported C++ Features:\n";
     std::cout << "  • Classes, methods, constructors\n";
@@ -70,15 +205,110 @@ void printVersion() {
     std::cout << "License: MIT\n";
 }
 
+int runLanguageServer(int argc, char* argv[]) {
+    std::string target = "rust";
+    for (int i = 2; i < argc; ++i) {
+        std::string arg = argv[i];
+        if ((arg == "-t" || arg == "--target") && i + 1 < argc) {
+            target = argv[++i];
+        } else {
+            std::cerr << "Error: Unknown lsp option '" << arg << "'\n";
+            std::cerr << "Usage: " << argv[0] << " lsp [--target rust|go]\n";
+            return 1;
+        }
+    }
+
+    if (target != "rust" && target != "go") {
+        std::cerr << "Error: Unknown target language '" << target << "'\n";
+        std::cerr << "Supported languages: rust, go\n";
+        return 1;
+    }
+
+    // stdout carries the protocol; nothing else may be printed there
+    hybrid::LspServer server(std::cin, std::cout);
+    server.setTarget(target == "go" ? hybrid::TargetLanguage::Go : hybrid::TargetLanguage::Rust);
+    return server.run();
+}
+
+/**
+ * Contents of a file, or of the files with one of the extensions under a
+ * directory (in path order)
+ */
+bool readSources(const std::string& path, const std::vector<std::string>& extensions,
+                 std::vector<std::string>& sources) {
+    std::vector<std::string> paths;
+    std::error_code ec;
+    if (std::filesystem::is_directory(path, ec)) {
+        for (const auto& entry : std::filesystem::recursive_directory_iterator(path, ec)) {
+            std::string extension = entry.path().extension().string();
+            if (entry.is_regular_file() &&
+                std::find(extensions.begin(), extensions.end(), extension) != extensions.end()) {
+                paths.push_back(entry.path().string());
+            }
+        }
+        std::sort(paths.begin(), paths.end());
+    } else {
+        paths.push_back(path);
+    }
+
+    for (const auto& file_path : paths) {
+        std::ifstream file(file_path);
+        if (!file.is_open()) {
+            std::cerr << "Error: Cannot open " << file_path << "\n";
+            return false;
+        }
+        std::stringstream buffer;
+        buffer << file.rdbuf();
+        sources.push_back(buffer.str());
+    }
+    return true;
+}
+
+int runApiDiff(int argc, char* argv[]) {
+    if (argc < 4) {
+        std::cerr << "Error: apidiff requires C++ sources and a Rust crate\n";
+        std::cerr << "Usage: " << argv[0] << " apidiff <C++ file or dir>... <Rust file or dir>\n";
+        return 1;
+    }
+
+    std::vector<std::string> cpp_sources;
+    for (int i = 2; i < argc - 1; ++i) {
+        if (!readSources(argv[i], {".h", ".hh", ".hpp", ".hxx", ".cpp", ".cc", ".cxx"}, cpp_sources)) return 1;
+    }
+    std::vector<std::string> rust_sources;
+    if (!readSources(argv[argc - 1], {".rs"}, rust_sources)) return 1;
+
+    hybrid::ApiDiff diff = hybrid::diffApi(cpp_sources, rust_sources);
+    for (const auto& mismatch : diff.mismatches) {
+        std::cout << hybrid::apiMismatchKindName(mismatch.kind) << ": " << mismatch.cpp << ": "
+                  << mismatch.message << "\n";
+    }
+    std::cout << diff.matched << " of " << diff.cpp_items << " public C++ items match the crate";
+    if (!diff.mismatches.empty()) {
+        std::cout << ", " << diff.mismatches.size() << " mismatch" << (diff.mismatches.size() > 1 ? "es" : "");
+    }
+    std::cout << "\n";
+    return diff.mismatches.empty() ? 0 : 1;
+}
+
 int main(int argc, char* argv[]) {
     if (argc < 2) {
         printUsage(argv[0]);
         return 1;
     }
 
+    if (std::string(argv[1]) == "lsp") {
+        return runLanguageServer(argc, argv);
+    }
+
+    if (std::string(argv[1]) == "apidiff") {
+        return runApiDiff(argc, argv);
+    }
+
     hybrid::TranspilerOptions options;
     std::string input_file;
     std::vector<std::string> input_files;
+    bool list_passes = false;
 
     // Parse command line arguments
     for (int i = 1; i < argc; ++i) {
@@ -133,6 +363,22 @@ int main(int argc, char* argv[]) {
                 std::cerr << "See '" << argv[0] << " --help' for more information.\n";
                 return 1;
             }
+        } else if (arg == "--std" || arg.find("--std=") == 0) {
+            std::string standard;
+            if (arg != "--std") {
+                standard = arg.substr(6);
+            } else if (i + 1 < argc) {
+                standard = argv[++i];
+            }
+
+            if (!hybrid::parseStandardName(standard, options.standard)) {
+                std::cerr << "Error: Unknown C++ standard '" << standard << "'\n";
+                std::cerr << "Supported standards: c++11, c++14, c++17, c++20\n";
+                if (standard.find("gnu++") == 0) {
+                    std::cerr << "Did you mean 'c++" << standard.substr(5) << "'?\n";
+                }
+                return 1;
+            }
         } else if (arg == "-O" || arg == "--opt-level") {
             if (i + 1 < argc) {
                 try {
@@ -164,6 +410,249 @@ int main(int argc, char* argv[]) {
             options.preserve_comments = false;
         } else if (arg == "--gen-tests") {
             options.generate_tests = true;
+        } else if (arg == "--thiserror") {
+            options.use_thiserror = true;
+        } else if (arg == "--varargs") {
+            std::string strategy = i + 1 < argc ? argv[++i] : "";
+            if (strategy == "slice") {
+                options.varargs = hybrid::VarargsStrategy::Slice;
+            } else if (strategy == "macro") {
+                options.varargs = hybrid::VarargsStrategy::Macro;
+            } else if (strategy == "ffi") {
+                options.varargs = hybrid::VarargsStrategy::Ffi;
+            } else {
+                std::cerr << "Error: Unknown varargs strategy '" << strategy << "'\n";
+                std::cerr << "Supported strategies: slice, macro, ffi\n";
+                return 1;
+            }
+        } else if (arg == "--allocator-api") {
+            options.allocator_api = true;
+        } else if (arg == "--self-ref") {
+            std::string strategy = i + 1 < argc ? argv[++i] : "";
+            if (strategy == "report") {
+                options.self_ref = hybrid::SelfRefStrategy::Report;
+            } else if (strategy == "pin") {
+                options.self_ref = hybrid::SelfRefStrategy::Pin;
+            } else if (strategy == "arena") {
+                options.self_ref = hybrid::SelfRefStrategy::Arena;
+            } else {
+                std::cerr << "Error: Unknown self-ref strategy '" << strategy << "'\n";
+                std::cerr << "Supported strategies: report, pin, arena\n";
+                return 1;
+            }
+        } else if (arg == "--data-model") {
+            std::string model = i + 1 < argc ? argv[++i] : "";
+            if (model == "lp64") {
+                options.data_model = hybrid::DataModel::LP64;
+            } else if (model == "llp64") {
+                options.data_model = hybrid::DataModel::LLP64;
+            } else {
+                std::cerr << "Error: Unknown data model '" << model << "'\n";
+                std::cerr << "Supported models: lp64, llp64\n";
+                return 1;
+            }
+        } else if (arg == "--libc") {
+            std::string strategy = i + 1 < argc ? argv[++i] : "";
+            if (strategy == "std") {
+                options.libc = hybrid::LibcStrategy::Std;
+            } else if (strategy == "crate") {
+                options.libc = hybrid::LibcStrategy::Crate;
+            } else {
+                std::cerr << "Error: Unknown libc strategy '" << strategy << "'\n";
+                std::cerr << "Supported strategies: std, crate\n";
+                return 1;
+            }
+        } else if (arg == "--clap") {
+            options.clap = true;
+        } else if (arg == "--strum") {
+            options.strum = true;
+        } else if (arg == "--split-modules") {
+            options.split_modules = true;
+        } else if (arg == "--library") {
+            options.library = true;
+        } else if (arg == "--split-crates-by") {
+            std::string by = i + 1 < argc ? argv[++i] : "";
+            if (by == "dir") {
+                options.split_crates = hybrid::CrateSplit::Directory;
+            } else if (by == "target") {
+                options.split_crates = hybrid::CrateSplit::Target;
+            } else {
+                std::cerr << "Error: Unknown crate split '" << by << "'\n";
+                std::cerr << "Supported splits: dir, target\n";
+                return 1;
+            }
+        } else if (arg == "--prune-unused") {
+            options.prune_unused = true;
+        } else if (arg == "--entry") {
+            if (i + 1 < argc) {
+                options.entry_points.push_back(argv[++i]);
+            } else {
+                std::cerr << "Error: --entry requires a symbol name\n";
+                std::cerr << "Usage: " << argv[0] << " --prune-unused --entry <Class|Class::method|function>\n";
+                return 1;
+            }
+        } else if (arg == "--defer") {
+            if (i + 1 < argc) {
+                options.deferred_classes.push_back(argv[++i]);
+            } else {
+                std::cerr << "Error: --defer requires a class name\n";
+                std::cerr << "Usage: " << argv[0] << " --defer <Class>\n";
+                return 1;
+            }
+        } else if (arg == "--disable-pass") {
+            std::string name = i + 1 < argc ? argv[++i] : "";
+            std::string error;
+            if (!options.pipeline.disable(name, error)) {
+                std::cerr << "Error: Invalid --disable-pass '" << name << "': " << error << "\n";
+                std::cerr << "Run '" << argv[0] << " --list-passes' for the passes\n";
+                return 1;
+            }
+        } else if (arg == "--dump-ir-after") {
+            std::string name = i + 1 < argc ? argv[++i] : "";
+            std::string error;
+            if (!options.pipeline.dumpAfter(name, error)) {
+                std::cerr << "Error: Invalid --dump-ir-after '" << name << "': " << error << "\n";
+                std::cerr << "Usage: " << argv[0] << " --dump-ir-after <pass|all> --dump-ir-dir <dir>\n";
+                return 1;
+            }
+        } else if (arg == "--dump-ir-dir") {
+            if (i + 1 < argc) {
+                options.dump_ir_dir = argv[++i];
+            } else {
+                std::cerr << "Error: --dump-ir-dir requires a directory\n";
+                std::cerr << "Usage: " << argv[0] << " --dump-ir-after <pass|all> --dump-ir-dir <dir>\n";
+                return 1;
+            }
+        } else if (arg == "--list-passes") {
+            list_passes = true;
+        } else if (arg == "--log-macro") {
+            std::string entry = i + 1 < argc ? argv[++i] : "";
+            size_t equals = entry.find('=');
+            std::string level = equals == std::string::npos ? "" : entry.substr(equals + 1);
+            if (equals == 0 || !hybrid::isLogLevel(level)) {
+                std::cerr << "Error: Invalid --log-macro '" << entry << "'\n";
+                std::cerr << "Usage: " << argv[0] << " --log-macro <NAME>=<error|warn|info|debug|trace>\n";
+                return 1;
+            }
+            options.log_macros[entry.substr(0, equals)] = level;
+        } else if (arg == "--mapping-pack") {
+            std::string name = i + 1 < argc ? argv[++i] : "";
+            const hybrid::MappingPack* builtin = hybrid::findBuiltinMappingPack(name);
+            std::ifstream file(builtin ? "" : name);
+            if (builtin) {
+                options.mapping_packs.push_back(*builtin);
+            } else if (!name.empty() && file) {
+                std::stringstream text;
+                text << file.rdbuf();
+                hybrid::MappingPack pack;
+                std::string error;
+                if (!hybrid::parseMappingPack(text.str(), pack, error)) {
+                    std::cerr << "Error: Invalid mapping pack '" << name << "': " << error << "\n";
+                    return 1;
+                }
+                options.mapping_packs.push_back(pack);
+            } else {
+                std::string names;
+                for (const auto& pack_name : hybrid::builtinMappingPackNames()) {
+                    names += (names.empty() ? "" : ", ") + pack_name;
+                }
+                std::cerr << "Error: Unknown mapping pack '" << name << "'\n";
+                std::cerr << "Built-in packs: " << names << " (or the path of a JSON pack file)\n";
+                return 1;
+            }
+        } else if (arg == "--runtime-trace") {
+            std::string path = i + 1 < argc ? argv[++i] : "";
+            std::ifstream file(path);
+            if (path.empty() || !file) {
+                std::cerr << "Error: Cannot read runtime trace '" << path << "'\n";
+                return 1;
+            }
+            std::stringstream text;
+            text << file.rdbuf();
+            std::string error;
+            if (!hybrid::parseRuntimeTrace(text.str(), options.runtime_trace, error)) {
+                std::cerr << "Error: Invalid runtime trace '" << path << "': " << error << "\n";
+                return 1;
+            }
+        } else if (arg == "--explain") {
+            if (i + 1 < argc) {
+                options.explain = argv[++i];
+            } else {
+                std::cerr << "Error: --explain requires a symbol name\n";
+                std::cerr << "Usage: " << argv[0] << " --explain <Class|Class::method|function>\n";
+                return 1;
+            }
+        } else if (arg == "--compile-commands") {
+            if (i + 1 < argc) {
+                options.compile_commands = argv[++i];
+            } else {
+                std::cerr << "Error: --compile-commands requires a file or build directory\n";
+                std::cerr << "Usage: " << argv[0] << " --compile-commands <build/compile_commands.json>\n";
+                return 1;
+            }
+        } else if (arg == "--target-config") {
+            if (i + 1 < argc) {
+                options.target_config = argv[++i];
+            } else {
+                std::cerr << "Error: --target-config requires a configuration name\n";
+                std::cerr << "Usage: " << argv[0] << " --target-config <name>\n";
+                return 1;
+            }
+        } else if (arg == "--emit" || arg.find("--emit=") == 0) {
+            std::string kind;
+            if (arg != "--emit") {
+                kind = arg.substr(7);
+            } else if (i + 1 < argc) {
+                kind = argv[++i];
+            }
+
+            if (kind == "code") {
+                options.emit = hybrid::EmitKind::Code;
+            } else if (kind == "ast-json") {
+                options.emit = hybrid::EmitKind::AstJson;
+            } else if (kind == "ir-json") {
+                options.emit = hybrid::EmitKind::IrJson;
+            } else {
+                std::cerr << "Error: Unknown emit kind '" << kind << "'\n";
+                std::cerr << "Supported kinds: code, ast-json, ir-json\n";
+                return 1;
+            }
+        } else if (arg == "--deterministic") {
+            options.deterministic = true;
+        } else if (arg == "--update") {
+            options.update = true;
+        } else if (arg == "--force") {
+            options.force = true;
+        } else if (arg == "--metrics") {
+            if (i + 1 < argc) {
+                options.metrics_path = argv[++i];
+            } else {
+                std::cerr << "Error: --metrics requires a file path\n";
+                std::cerr << "Usage: " << argv[0] << " --metrics <file.json>\n";
+                return 1;
+            }
+        } else if (arg == "--report") {
+            if (i + 1 < argc) {
+                options.report_path = argv[++i];
+            } else {
+                std::cerr << "Error: --report requires a file path\n";
+                std::cerr << "Usage: " << argv[0] << " --report <file.json>\n";
+                return 1;
+            }
+        } else if (arg == "--max-unsafe" || arg == "--max-clones") {
+            double& limit = arg == "--max-unsafe" ? options.budget.max_unsafe : options.budget.max_clones;
+            std::string value = i + 1 < argc ? argv[++i] : "";
+            size_t parsed = 0;
+            try {
+                limit = std::stod(value, &parsed);
+            } catch (const std::exception&) {
+                parsed = 0;
+            }
+            if (parsed == 0 || parsed != value.size() || limit < 0) {
+                std::cerr << "Error: Invalid " << arg << " '" << value << "'\n";
+                std::cerr << "Usage: " << argv[0] << " " << arg << " <count per 1000 lines of output>\n";
+                return 1;
+            }
         } else if (arg == "--verbose") {
             options.verbose = true;
         } else if (arg == "--quiet") {
@@ -187,6 +676,15 @@ int main(int argc, char* argv[]) {
         }
     }
 
+    if (list_passes) {
+        for (const auto& pass : options.pipeline.passes()) {
+            std::string name = pass.name + (pass.enabled ? "" : " (disabled)");
+            std::cout << std::left << std::setw(10) << hybrid::passStageName(pass.stage) << std::setw(28) << name
+                      << pass.description << "\n";
+        }
+        return 0;
+    }
+
     // Validate inputs
     if (input_file.empty()) {
         std::cerr << "Error: No input file specified\n";
@@ -196,6 +694,67 @@ int main(int argc, char* argv[]) {
         return 1;
     }
 
+    if (options.prune_unused && options.entry_points.empty()) {
+        std::cerr << "Error: --prune-unused requires at least one --entry symbol\n";
+        std::cerr << "Example: " << argv[0] << " -i lib.cpp --prune-unused --entry Client::connect\n";
+        return 1;
+    }
+
+    if (options.dump_ir_dir.empty() == options.pipeline.dumps()) {
+        std::cerr << "Error: --dump-ir-after and --dump-ir-dir go together\n";
+        std::cerr << "Usage: " << argv[0] << " --dump-ir-after <pass|all> --dump-ir-dir <dir>\n";
+        return 1;
+    }
+
+    if (options.force && !options.update) {
+        std::cerr << "Error: --force only applies to --update\n";
+        return 1;
+    }
+
+    if (!options.explain.empty() &&
+        (options.target != hybrid::TargetLanguage::Rust || options.split_modules || options.library ||
+         options.split_crates != hybrid::CrateSplit::None || options.update ||
+         options.emit != hybrid::EmitKind::Code)) {
+        std::cerr << "Error: --explain works on a single Rust translation (no -t go, --split-modules, --library, "
+                     "--split-crates-by, --update or --emit)\n";
+        return 1;
+    }
+
+    if (!options.deferred_classes.empty() && options.target != hybrid::TargetLanguage::Rust) {
+        std::cerr << "Error: --defer generates Rust trait and impl stubs; it does not apply to -t go\n";
+        return 1;
+    }
+
+    if (!options.target_config.empty() && options.compile_commands.empty()) {
+        std::cerr << "Error: --target-config selects a command of --compile-commands\n";
+        return 1;
+    }
+
+    if (options.update && options.emit != hybrid::EmitKind::Code) {
+        std::cerr << "Error: --update merges generated code and cannot be combined with --emit=ast-json or ir-json\n";
+        return 1;
+    }
+
+    if (options.split_modules && options.emit != hybrid::EmitKind::Code) {
+        std::cerr << "Error: --emit=ast-json and --emit=ir-json cannot be combined with --split-modules\n";
+        return 1;
+    }
+
+    if (options.library && options.emit != hybrid::EmitKind::Code) {
+        std::cerr << "Error: --emit=ast-json and --emit=ir-json cannot be combined with --library\n";
+        return 1;
+    }
+
+    if (options.split_crates != hybrid::CrateSplit::None && options.emit != hybrid::EmitKind::Code) {
+        std::cerr << "Error: --emit=ast-json and --emit=ir-json cannot be combined with --split-crates-by\n";
+        return 1;
+    }
+
+    if (options.split_crates == hybrid::CrateSplit::Target && options.compile_commands.empty()) {
+        std::cerr << "Error: --split-crates-by target reads the CMake targets from --compile-commands\n";
+        return 1;
+    }
+
     // Check if input file exists
     std::ifstream test_file(input_file);
     if (!test_file.good()) {
@@ -207,13 +766,7 @@ int main(int argc, char* argv[]) {
 
     // Auto-generate output filename if not specified
     if (options.output_path.empty()) {
-        std::string extension = (options.target == hybrid::TargetLanguage::Rust) ? ".rs" : ".go";
-        size_t dot_pos = input_file.find_last_of('.');
-        if (dot_pos != std::string::npos) {
-            options.output_path = input_file.substr(0, dot_pos) + extension;
-        } else {
-            options.output_path = input_file + extension;
-        }
+        options.output_path = hybrid::defaultOutputPath(input_file, options);
 
         if (options.verbose) {
             std::cout << "Auto-generated output path: " << options.output_path << "\n";
@@ -226,6 +779,7 @@ int main(int argc, char* argv[]) {
         std::cout << "  Input:  " << input_file << "\n";
         std::cout << "  Output: " << options.output_path << "\n";
         std::cout << "  Target: " << (options.target == hybrid::TargetLanguage::Rust ? "Rust" : "Go") << "\n";
+        std::cout << "  Standard: " << hybrid::standardName(options.standard) << "\n";
         std::cout << "  Optimization level: " << options.optimization_level << "\n";
         std::cout << "  Safety checks: " << (options.enable_safety_checks ? "enabled" : "disabled") << "\n";
         std::cout << "  Preserve comments: " << (options.preserve_comments ? "yes" : "no") << "\n";
@@ -233,7 +787,10 @@ int main(int argc, char* argv[]) {
     }
 
     // Create transpiler and run
-    if (!options.quiet) {
+    if (!options.quiet && options.emit != hybrid::EmitKind::Code) {
+        std::cout << "Dumping " << input_file << " as "
+                  << (options.emit == hybrid::EmitKind::AstJson ? "AST" : "IR") << " JSON...\n";
+    } else if (!options.quiet && options.explain.empty()) {
         std::cout << "Transpiling " << input_file << " to "
                   << (options.target == hybrid::TargetLanguage::Rust ? "Rust" : "Go")
                   << "...\n";
@@ -241,7 +798,30 @@ int main(int argc, char* argv[]) {
 
     hybrid::Transpiler transpiler(options);
 
-    if (!transpiler.transpile(input_file)) {
+    bool success = transpiler.transpile(input_file);
+
+    // The diagnostics behind the decisions are part of the explanation
+    if (success && !options.explain.empty()) {
+        hybrid::Explanation explanation;
+        if (!transpiler.explain(options.explain, explanation)) {
+            std::cerr << "Error: No class, method or function named '" << options.explain << "' in " << input_file
+                      << "\n";
+            return 1;
+        }
+        std::cout << explanation.toString();
+        return 0;
+    }
+
+    // Report diagnostics (warnings are suppressed in quiet mode)
+    for (const auto& diagnostic : transpiler.getDiagnostics()) {
+        if (options.quiet && diagnostic.severity != hybrid::Diagnostic::Error) {
+            continue;
+        }
+
+        std::cerr << diagnostic.toString() << "\n";
+    }
+
+    if (!success) {
         std::cerr << "Error: Transpilation failed\n";
         std::cerr << transpiler.getLastError() << "\n";
         return 1;
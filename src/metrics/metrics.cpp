@@ -0,0 +1,161 @@
+#include "metrics.h"
+#include <algorithm>
+#include <cctype>
+#include <cmath>
+#include <sstream>
+
+namespace hybrid {
+
+namespace {
+
+bool isIdentifierChar(char c) {
+    return std::isalnum(static_cast<unsigned char>(c)) || c == '_';
+}
+
+/**
+ * Occurrences of `word` as a whole identifier that are followed
+ * (after optional spaces) by `next`
+ */
+size_t countToken(const std::string& line, const std::string& word, char next) {
+    size_t count = 0;
+    size_t pos = line.find(word);
+    while (pos != std::string::npos) {
+        size_t after = pos + word.size();
+        bool starts_word = pos == 0 || !isIdentifierChar(line[pos - 1]);
+        while (after < line.size() && line[after] == ' ') {
+            ++after;
+        }
+        if (starts_word && after < line.size() && line[after] == next) {
+            ++count;
+        }
+        pos = line.find(word, pos + word.size());
+    }
+    return count;
+}
+
+/**
+ * Occurrences of `.method(`
+ */
+size_t countCall(const std::string& line, const std::string& method) {
+    size_t count = 0;
+    std::string call = "." + method + "(";
+    for (size_t pos = line.find(call); pos != std::string::npos; pos = line.find(call, pos + call.size())) {
+        ++count;
+    }
+    return count;
+}
+
+// Penalty per occurrence in 1000 lines: unsafe gives up the compiler's
+// checks, Rc/RefCell move borrow checking to run time, unwraps and clones
+// are often only noise
+const double UNSAFE_WEIGHT = 5;
+const double SHARED_WEIGHT = 2;
+const double UNWRAP_WEIGHT = 1;
+const double CLONE_WEIGHT = 1;
+
+std::string formatDensity(double value) {
+    std::ostringstream out;
+    out << std::round(value * 100) / 100;
+    return out.str();
+}
+
+} // namespace
+
+size_t countLines(const std::string& text) {
+    size_t lines = 0;
+    for (char c : text) {
+        if (c == '\n') ++lines;
+    }
+    if (!text.empty() && text.back() != '\n') ++lines;
+    return lines;
+}
+
+void TranslationMetrics::countOutput(const std::string& code) {
+    lines_out += countLines(code);
+
+    size_t start = 0;
+    while (start < code.size()) {
+        size_t end = code.find('\n', start);
+        if (end == std::string::npos) end = code.size();
+
+        std::string line = code.substr(start, end - start);
+        size_t comment = line.find("//");
+        if (comment != std::string::npos) {
+            line.erase(comment);
+        }
+
+        unsafe_blocks += countToken(line, "unsafe", '{');
+        rc_introduced += countToken(line, "Rc", '<');
+        arc_introduced += countToken(line, "Arc", '<');
+        refcell_introduced += countToken(line, "RefCell", '<');
+        clone_calls += countCall(line, "clone");
+        unwrap_calls += countCall(line, "unwrap") + countCall(line, "expect");
+        start = end + 1;
+    }
+}
+
+double TranslationMetrics::perKloc(size_t count) const {
+    return lines_out == 0 ? 0 : count * 1000.0 / lines_out;
+}
+
+double TranslationMetrics::idiomaticityScore() const {
+    double penalty = UNSAFE_WEIGHT * perKloc(unsafe_blocks) +
+                     SHARED_WEIGHT * perKloc(rc_introduced + refcell_introduced) +
+                     UNWRAP_WEIGHT * perKloc(unwrap_calls) + CLONE_WEIGHT * perKloc(clone_calls);
+    return std::max(0.0, 100 - penalty / 10);
+}
+
+bool TranslationMetrics::withinBudget(const QualityBudget& budget, std::string& error) const {
+    auto check = [&](double limit, size_t count, const std::string& what, const std::string& option) {
+        if (limit < 0 || perKloc(count) <= limit) return true;
+        error = std::to_string(count) + " " + what + (count == 1 ? "" : "s") + " (" +
+                formatDensity(perKloc(count)) + " per 1000 lines), over " + option + " " + formatDensity(limit);
+        return false;
+    };
+    return check(budget.max_unsafe, unsafe_blocks, "unsafe block", "--max-unsafe") &&
+           check(budget.max_clones, clone_calls, ".clone() call", "--max-clones");
+}
+
+JsonValue TranslationMetrics::toJson() const {
+    // Microsecond resolution is plenty and keeps the report readable
+    auto rounded = [](double ms) { return std::round(ms * 1000) / 1000; };
+    auto density = [](double value) { return std::round(value * 100) / 100; };
+
+    JsonValue phases = JsonValue::object();
+    double total = 0;
+    for (const auto& phase : phase_ms) {
+        phases.set(phase.first, rounded(phase.second));
+        total += phase.second;
+    }
+    phases.set("total", rounded(total));
+
+    return JsonValue::object()
+        .set("success", success)
+        .set("input", input_path)
+        .set("output", output_path)
+        .set("target", target)
+        .set("lines_in", lines_in)
+        .set("lines_out", lines_out)
+        .set("classes", classes)
+        .set("functions_translated", generation.functions_translated)
+        .set("functions_stubbed", generation.functions_stubbed)
+        .set("functions_retained", generation.functions_retained)
+        .set("unsafe_blocks", unsafe_blocks)
+        .set("clones_inserted", generation.clones_inserted)
+        .set("memory_issues_fixed", generation.memory_issues_fixed)
+        .set("memory_issues_preserved", generation.memory_issues_preserved)
+        .set("rc_introduced", rc_introduced)
+        .set("arc_introduced", arc_introduced)
+        .set("refcell_introduced", refcell_introduced)
+        .set("clone_calls", clone_calls)
+        .set("unwrap_calls", unwrap_calls)
+        .set("idiomaticity", JsonValue::object()
+                                 .set("score", density(idiomaticityScore()))
+                                 .set("unsafe_per_kloc", density(perKloc(unsafe_blocks)))
+                                 .set("rc_refcell_per_kloc", density(perKloc(rc_introduced + refcell_introduced)))
+                                 .set("unwraps_per_kloc", density(perKloc(unwrap_calls)))
+                                 .set("clones_per_kloc", density(perKloc(clone_calls))))
+        .set("phase_ms", record_timings ? phases : JsonValue::object());
+}
+
+} // namespace hybrid
@@ -0,0 +1,155 @@
+#include "modules.h"
+#include "file_system.h"
+#include "preprocessor.h"
+#include <sstream>
+#include <filesystem>
+#include <functional>
+
+namespace hybrid {
+
+bool IncludeGraph::build(const std::string& root_path, FileSystem& fs) {
+    std::string source;
+    if (!fs.readFile(root_path, source)) {
+        return false;
+    }
+
+    fs_ = &fs;
+    addFile(root_path, source);
+    return true;
+}
+
+void IncludeGraph::addFile(const std::string& path, const std::string& source) {
+    if (findNode(path)) {
+        return;
+    }
+
+    Node node;
+    node.path = path;
+    node.source = source;
+    nodes_.push_back(node);
+    size_t index = nodes_.size() - 1;
+
+    // nodes_ grows while included files are added: index, not a reference
+    auto follow = [&](const std::string& include) {
+        std::string resolved = locate(path, include);
+        if (!resolved.empty()) {
+            nodes_[index].includes.push_back(resolved);
+        } else {
+            unresolved_.push_back(include);
+        }
+    };
+
+    if (preprocessor_) {
+        // Included files are added where they appear, so they see the
+        // macros defined before them and the rest of the file sees theirs
+        std::string processed = preprocessor_->process(source, [&](const std::string& include, bool angled) {
+            if (!angled) follow(include);
+        });
+        nodes_[index].source = processed;
+    } else {
+        for (const auto& include : findIncludes(source)) {
+            follow(include);
+        }
+    }
+}
+
+std::string IncludeGraph::locate(const std::string& including_file, const std::string& include) {
+    std::vector<std::string> candidates = {resolve(including_file, include)};
+    for (const auto& directory : include_paths_) {
+        candidates.push_back((std::filesystem::path(directory) / include).lexically_normal().string());
+    }
+
+    for (const auto& candidate : candidates) {
+        std::string included_source;
+        if (findNode(candidate)) {
+            return candidate;
+        }
+        if (fs_ && fs_->readFile(candidate, included_source)) {
+            addFile(candidate, included_source);
+            return candidate;
+        }
+    }
+    return "";
+}
+
+const IncludeGraph::Node* IncludeGraph::findNode(const std::string& path) const {
+    for (const auto& node : nodes_) {
+        if (node.path == path) {
+            return &node;
+        }
+    }
+    return nullptr;
+}
+
+std::vector<std::string> IncludeGraph::topologicalOrder() const {
+    std::vector<std::string> order;
+    std::set<std::string> visited;
+
+    std::function<void(const Node&)> visit = [&](const Node& node) {
+        if (!visited.insert(node.path).second) {
+            return;
+        }
+        for (const auto& include : node.includes) {
+            visit(*findNode(include));
+        }
+        order.push_back(node.path);
+    };
+
+    for (const auto& node : nodes_) {
+        visit(node);
+    }
+    return order;
+}
+
+bool IncludeGraph::reaches(const std::string& from, const std::string& to) const {
+    std::set<std::string> visited;
+    std::vector<std::string> pending = {from};
+
+    while (!pending.empty()) {
+        std::string current = pending.back();
+        pending.pop_back();
+        if (!visited.insert(current).second) {
+            continue;
+        }
+
+        const Node* node = findNode(current);
+        if (!node) continue;
+
+        for (const auto& include : node->includes) {
+            if (include == to) {
+                return true;
+            }
+            pending.push_back(include);
+        }
+    }
+    return false;
+}
+
+std::vector<std::string> IncludeGraph::findIncludes(const std::string& source) {
+    std::vector<std::string> includes;
+    std::istringstream lines(source);
+    std::string line;
+
+    while (std::getline(lines, line)) {
+        size_t hash = line.find_first_not_of(" \t");
+        if (hash == std::string::npos || line[hash] != '#') continue;
+
+        size_t directive = line.find_first_not_of(" \t", hash + 1);
+        if (directive == std::string::npos || line.compare(directive, 7, "include") != 0) continue;
+
+        // Only quoted includes belong to the project; <...> are system headers
+        size_t open = line.find('"', directive + 7);
+        size_t close = open == std::string::npos ? open : line.find('"', open + 1);
+        if (close != std::string::npos) {
+            includes.push_back(line.substr(open + 1, close - open - 1));
+        }
+    }
+    return includes;
+}
+
+std::string IncludeGraph::resolve(const std::string& including_file, const std::string& include) {
+    std::filesystem::path base = std::filesystem::path(including_file).parent_path();
+    return (base / include).lexically_normal().string();
+}
+
+} // namespace hybrid
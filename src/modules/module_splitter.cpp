@@ -0,0 +1,467 @@
+#include "modules.h"
+#include "codegen.h"
+#include <regex>
+#include <algorithm>
+#include <filesystem>
+#include <cctype>
+
+namespace hybrid {
+
+namespace {
+
+/**
+ * Identifiers mentioned by a class: field/parameter types, bases and bodies
+ */
+void collectTypeNames(const std::shared_ptr<Type>& type, std::string& text) {
+    if (!type) return;
+    text += " " + type->name;
+    collectTypeNames(type->element_type, text);
+    for (const auto& arg : type->template_args) {
+        collectTypeNames(arg, text);
+    }
+}
+
+std::string referencedText(const ClassDecl& class_decl) {
+    std::string text;
+    for (const auto& base : class_decl.base_classes) {
+        text += " " + base;
+    }
+    for (const auto& field : class_decl.fields) {
+        collectTypeNames(field.type, text);
+    }
+    for (const auto& method : class_decl.methods) {
+        collectTypeNames(method.return_type, text);
+        for (const auto& param : method.parameters) {
+            collectTypeNames(param.type, text);
+        }
+        text += " " + method.member_initializers + " " + method.body;
+    }
+    return text;
+}
+
+} // namespace
+
+std::vector<ModuleFile> ModuleSplitter::split(
+    const IncludeGraph& graph, const IR& ir,
+    const std::map<std::string, std::vector<std::string>>& provenance) {
+
+    owner_.clear();
+    uses_.clear();
+    diagnostics_.clear();
+    stats_ = GenerationStats();
+    allocator_api_crates_.clear();
+    crate_of_ = crates_;
+    crate_dependencies_.clear();
+    entry_file_.clear();
+
+    std::vector<std::string> order = graph.topologicalOrder();
+
+    // The first file (in dependency order) that declares a name owns it
+    std::map<std::string, std::vector<std::string>> declared;
+    for (const auto& path : order) {
+        auto it = provenance.find(path);
+        if (it == provenance.end()) continue;
+
+        for (const auto& name : it->second) {
+            if (owner_.emplace(name, path).second) {
+                declared[path].push_back(name);
+            }
+        }
+    }
+
+    // Declarations each file names from the files it includes; main is
+    // called by no one
+    std::set<std::string> entry_points;
+    for (const auto& func : ir.getFunctions()) {
+        if (!func.is_entry_point) continue;
+        entry_points.insert(func.name);
+        auto owner = owner_.find(func.name);
+        if (owner != owner_.end()) entry_file_ = owner->second;
+    }
+    for (const auto& path : order) {
+        std::set<std::string> ids = identifiers(graph.findNode(path)->source);
+        for (const auto& entry : owner_) {
+            if (entry.second != path && ids.count(entry.first) && !entry_points.count(entry.first) &&
+                graph.reaches(path, entry.second)) {
+                uses_[path].insert(entry.first);
+            }
+        }
+    }
+
+    // Modules another crate uses are the crate's public surface
+    std::set<std::string> public_files;
+    if (!crate_of_.empty()) {
+        mergeCrateCycles();
+        for (const auto& entry : uses_) {
+            for (const auto& name : entry.second) {
+                const std::string& owner = owner_.at(name);
+                if (crateOf(owner) != crateOf(entry.first)) public_files.insert(owner);
+            }
+        }
+    }
+
+    std::vector<ModuleFile> files;
+    std::map<std::string, std::string> libs;  // Crate -> lib.rs
+    std::string entry_root;                   // main.rs, without its module declarations
+
+    for (const auto& path : order) {
+        std::string module = moduleOf(path);
+        std::string crate = crateOf(path);
+        std::string prefix = crate.empty() ? "" : crate + "/src/";
+        std::string& lib = libs[crate];
+        if (lib.empty()) lib = "// Auto-generated module tree\n\n";
+        const std::vector<std::string>& declarations = declared[path];
+
+        // The file defining main is the root of a binary crate
+        if (path == entry_file_) {
+            entry_root = generateModule(ir, declarations, uses_[path], {}, path);
+            continue;
+        }
+
+        // The root file's crate is the top of the workspace and exports
+        // everything; the modules of a binary are its own
+        bool binary = !entry_file_.empty() && crate == crateOf(entry_file_);
+        bool visible = public_files.count(path) || (!binary && (crate.empty() || crate == crateOf(order.back())));
+        lib += (visible ? "pub mod " : "mod ") + module + ";\n";
+
+        std::vector<Group> groups = groupDeclarations(graph, path, declarations);
+
+        if (groups.size() <= 1) {
+            std::set<std::string> names_used = uses_[path];
+            files.push_back({prefix + module + ".rs",
+                             generateModule(ir, declarations, names_used, {}, path)});
+            continue;
+        }
+
+        // Split: one private submodule per group, re-export what consumers use
+        std::map<std::string, std::string> sibling_of;
+        for (const auto& group : groups) {
+            for (const auto& name : group.declarations) {
+                sibling_of[name] = group.name;
+            }
+        }
+
+        std::string index = "// Auto-generated module index for " +
+                            std::filesystem::path(path).filename().string() + "\n\n";
+        for (const auto& group : groups) {
+            index += "mod " + group.name + ";\n";
+        }
+
+        index += "\n";
+        for (const auto& group : groups) {
+            if (group.consumers.empty()) continue;
+
+            std::vector<std::string> exported;
+            for (const auto& name : group.declarations) {
+                std::vector<std::string> items = rustItems(ir, name);
+                exported.insert(exported.end(), items.begin(), items.end());
+            }
+            std::string exports;
+            for (const auto& item : exported) {
+                if (!exports.empty()) exports += ", ";
+                exports += item;
+            }
+            index += exported.size() == 1
+                ? "pub use " + group.name + "::" + exports + ";\n"
+                : "pub use " + group.name + "::{" + exports + "};\n";
+        }
+        files.push_back({prefix + module + "/mod.rs", index});
+
+        for (const auto& group : groups) {
+            std::set<std::string> names_used;
+            for (const auto& name : group.declarations) {
+                const ClassDecl* class_decl = ir.findClass(name);
+                if (class_decl) {
+                    std::set<std::string> ids = identifiers(referencedText(*class_decl));
+                    names_used.insert(ids.begin(), ids.end());
+                }
+            }
+            files.push_back({prefix + module + "/" + group.name + ".rs",
+                             generateModule(ir, group.declarations, names_used, sibling_of, path)});
+        }
+    }
+
+    for (auto& entry : libs) {
+        std::string root = "lib.rs";
+        if (!entry_file_.empty() && entry.first == crateOf(entry_file_)) {
+            // main.rs declares the modules of the crate after its header
+            std::string modules = entry.second.substr(entry.second.find("\n\n") + 2);
+            size_t header_end = entry_root.find("\n\n");
+            entry.second = entry_root.insert(header_end == std::string::npos ? 0 : header_end + 2,
+                                             modules.empty() ? "" : modules + "\n");
+            root = "main.rs";
+        }
+        if (allocator_api_crates_.count(entry.first)) {
+            // Crate-level attributes are only honored in the crate root
+            entry.second.insert(entry.second.find("\n\n") + 2, "#![feature(allocator_api)]\n\n");
+        }
+        files.push_back({(entry.first.empty() ? "" : entry.first + "/src/") + root, entry.second});
+    }
+    return files;
+}
+
+std::string ModuleSplitter::crateOf(const std::string& path) const {
+    auto it = crate_of_.find(path);
+    return it == crate_of_.end() ? "" : it->second;
+}
+
+void ModuleSplitter::mergeCrateCycles() {
+    while (true) {
+        crate_dependencies_.clear();
+        for (const auto& entry : crate_of_) {
+            crate_dependencies_[entry.second];
+        }
+        for (const auto& entry : uses_) {
+            for (const auto& name : entry.second) {
+                std::string from = crateOf(entry.first);
+                std::string to = crateOf(owner_.at(name));
+                if (from != to) crate_dependencies_[from].insert(to);
+            }
+        }
+
+        // Crates reachable from each crate
+        std::map<std::string, std::set<std::string>> reachable;
+        for (const auto& entry : crate_dependencies_) {
+            std::vector<std::string> stack(entry.second.begin(), entry.second.end());
+            std::set<std::string>& seen = reachable[entry.first];
+            while (!stack.empty()) {
+                std::string crate = stack.back();
+                stack.pop_back();
+                if (!seen.insert(crate).second) continue;
+                const auto& next = crate_dependencies_[crate];
+                stack.insert(stack.end(), next.begin(), next.end());
+            }
+        }
+
+        // The first crate (by name) on a cycle takes in the others on it
+        std::string into;
+        std::set<std::string> cycle;
+        for (const auto& entry : reachable) {
+            if (!entry.second.count(entry.first)) continue;
+            into = entry.first;
+            for (const auto& other : entry.second) {
+                if (other != into && reachable[other].count(into)) cycle.insert(other);
+            }
+            break;
+        }
+        if (into.empty()) return;
+
+        std::string merged = into;
+        for (const auto& crate : cycle) {
+            merged += ", " + crate;
+        }
+        for (auto& entry : crate_of_) {
+            if (cycle.count(entry.second)) entry.second = into;
+        }
+        Diagnostic diagnostic;
+        diagnostic.severity = Diagnostic::Note;
+        diagnostic.message = "crates " + merged + " use each other; merged into " + into +
+                             " (Cargo does not allow dependency cycles)";
+        diagnostics_.push_back(diagnostic);
+    }
+}
+
+std::vector<ModuleSplitter::Group> ModuleSplitter::groupDeclarations(
+    const IncludeGraph& graph, const std::string& path,
+    const std::vector<std::string>& declarations) {
+
+    std::vector<Group> groups;
+
+    // Declarations used by the same set of consumers belong together
+    for (const auto& name : declarations) {
+        std::set<std::string> consumers;
+        for (const auto& node : graph.getNodes()) {
+            if (uses_[node.path].count(name)) {
+                consumers.insert(node.path);
+            }
+        }
+
+        auto it = std::find_if(groups.begin(), groups.end(),
+                               [&](const Group& g) { return g.consumers == consumers; });
+        if (it == groups.end()) {
+            Group group;
+            group.consumers = consumers;
+            groups.push_back(group);
+            it = groups.end() - 1;
+        }
+        it->declarations.push_back(name);
+    }
+
+    // Name groups after their consumers
+    std::set<std::string> taken;
+    for (auto& group : groups) {
+        std::string name;
+        if (group.consumers.empty()) {
+            name = "detail";
+        } else if (group.consumers.size() > 3) {
+            name = "common";
+        } else {
+            for (const auto& consumer : group.consumers) {
+                if (!name.empty()) name += "_";
+                name += moduleName(consumer);
+            }
+        }
+
+        if (name == moduleOf(path)) {
+            name += "_decls";
+        }
+
+        std::string unique = name;
+        for (int n = 2; taken.count(unique); ++n) {
+            unique = name + "_" + std::to_string(n);
+        }
+        taken.insert(unique);
+        group.name = unique;
+    }
+
+    return groups;
+}
+
+std::string ModuleSplitter::generateModule(const IR& ir,
+                                           const std::vector<std::string>& declarations,
+                                           const std::set<std::string>& names_used,
+                                           const std::map<std::string, std::string>& sibling_of,
+                                           const std::string& self_path) {
+    // Imports, grouped by module path
+    std::map<std::string, std::vector<std::string>> imports;
+    for (const auto& name : names_used) {
+        if (std::find(declarations.begin(), declarations.end(), name) != declarations.end()) {
+            continue;
+        }
+
+        auto sibling = sibling_of.find(name);
+        if (sibling != sibling_of.end()) {
+            imports["super::" + sibling->second].push_back(name);
+            continue;
+        }
+
+        auto owner = owner_.find(name);
+        if (owner != owner_.end() && owner->second != self_path) {
+            std::string crate = crateOf(owner->second);
+            std::string module = owner->second == entry_file_ ? "" : "::" + moduleOf(owner->second);
+            imports[(crate == crateOf(self_path) ? "crate" : crate) + module].push_back(name);
+        }
+    }
+
+    std::string use_block;
+    for (const auto& entry : imports) {
+        std::vector<std::string> items;
+        for (const auto& name : entry.second) {
+            std::vector<std::string> named = rustItems(ir, name);
+            items.insert(items.end(), named.begin(), named.end());
+        }
+        std::string names;
+        for (const auto& item : items) {
+            if (!names.empty()) names += ", ";
+            names += item;
+        }
+        use_block += items.size() == 1
+            ? "use " + entry.first + "::" + names + ";\n"
+            : "use " + entry.first + "::{" + names + "};\n";
+    }
+
+    IR module_ir;
+    for (const auto& name : declarations) {
+        const ClassDecl* class_decl = ir.findClass(name);
+        if (class_decl) {
+            module_ir.addClass(*class_decl);
+        }
+        const EnumDecl* enum_decl = ir.findEnum(name);
+        if (enum_decl) {
+            module_ir.addEnum(*enum_decl);
+        }
+        const TypeAlias* alias = ir.findTypeAlias(name);
+        if (alias) {
+            module_ir.addTypeAlias(*alias);
+        }
+        for (const auto& func : ir.getFunctions()) {
+            if (func.name == name) module_ir.addFunction(func);
+        }
+    }
+
+    RustCodeGenerator codegen;
+    if (configure_) configure_(codegen);
+    codegen.setWorkspaceMember(!crate_of_.empty());
+    std::string code = codegen.generate(module_ir);
+    const std::string feature = "#![feature(allocator_api)]\n\n";
+    size_t feature_pos = code.find(feature);
+    if (feature_pos != std::string::npos) {
+        code.erase(feature_pos, feature.size());
+        allocator_api_crates_.insert(crateOf(self_path));
+    }
+    diagnostics_.insert(diagnostics_.end(), codegen.getDiagnostics().begin(),
+                        codegen.getDiagnostics().end());
+    stats_.functions_translated += codegen.getStats().functions_translated;
+    stats_.functions_stubbed += codegen.getStats().functions_stubbed;
+    stats_.functions_retained += codegen.getStats().functions_retained;
+    stats_.clones_inserted += codegen.getStats().clones_inserted;
+    stats_.memory_issues_fixed += codegen.getStats().memory_issues_fixed;
+    stats_.memory_issues_preserved += codegen.getStats().memory_issues_preserved;
+
+    // Imports go right after the generated file header, merged with the std ones
+    if (!use_block.empty()) {
+        size_t header_end = code.find("\n\n");
+        size_t insert_at = header_end == std::string::npos ? 0 : header_end + 2;
+        code.insert(insert_at, use_block + "\n");
+        code = RustCodeGenerator::organizeImports(code);
+    }
+
+    return code;
+}
+
+std::vector<std::string> ModuleSplitter::rustItems(const IR& ir, const std::string& name) {
+    // Calling the methods of a deferred class needs its trait in scope
+    const ClassDecl* class_decl = ir.findClass(name);
+    if (class_decl && class_decl->is_deferred) {
+        return {RustCodeGenerator::sanitizeName(name), RustCodeGenerator::deferredTraitName(name)};
+    }
+    return {RustCodeGenerator::sanitizeName(name)};
+}
+
+std::string ModuleSplitter::moduleOf(const std::string& path) const {
+    // src/main.rs is the root of a binary crate, never a module
+    std::string module = moduleName(path);
+    return module == "main" ? "main_decls" : module;
+}
+
+std::string ModuleSplitter::moduleName(const std::string& path) {
+    std::string stem = std::filesystem::path(path).stem().string();
+    std::string name;
+    for (char c : stem) {
+        name += std::isalnum(static_cast<unsigned char>(c)) ? static_cast<char>(std::tolower(c)) : '_';
+    }
+    if (name.empty() || std::isdigit(static_cast<unsigned char>(name[0]))) {
+        name = "m_" + name;
+    }
+    return RustCodeGenerator::sanitizeName(name);
+}
+
+std::string ModuleSplitter::crateName(const std::string& text) {
+    static const std::set<std::string> taken = {"std", "core", "alloc", "proc_macro", "test", "crate", "self",
+                                                "super", "clap", "futures", "libc", "log", "parking_lot", "strum",
+                                                "thiserror", "tokio"};
+    std::string name;
+    for (char c : text) {
+        name += std::isalnum(static_cast<unsigned char>(c)) ? static_cast<char>(std::tolower(c)) : '_';
+    }
+    name.erase(0, name.find_first_not_of('_'));
+    if (name.empty() || std::isdigit(static_cast<unsigned char>(name[0]))) {
+        name = "c_" + name;
+    }
+    return taken.count(name) ? name + "_cpp" : name;
+}
+
+std::set<std::string> ModuleSplitter::identifiers(const std::string& source) {
+    std::string code = std::regex_replace(source, std::regex("//[^\n]*"), "");
+    code = std::regex_replace(code, std::regex(R"(/\*[\s\S]*?\*/)"), "");
+
+    std::set<std::string> ids;
+    std::regex id_pattern(R"([A-Za-z_]\w*)");
+    for (auto it = std::sregex_iterator(code.begin(), code.end(), id_pattern);
+         it != std::sregex_iterator(); ++it) {
+        ids.insert(it->str());
+    }
+    return ids;
+}
+
+} // namespace hybrid
@@ -0,0 +1,310 @@
+/**
+ * Algorithm Analyzer
+ * Recognizes <algorithm> and <numeric> calls over whole containers and
+ * prepares for conversion to container methods and iterator adapters
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <map>
+#include <set>
+
+namespace hybrid {
+
+/**
+ * Algorithm Analyzer
+ * Whole-program pass: predicates may be objects of functor classes
+ * declared anywhere in the input
+ */
+class AlgorithmAnalyzer {
+public:
+    /**
+     * Analyze all method and function bodies
+     */
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(ir, &class_decl, method);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(ir, nullptr, func);
+        }
+    }
+
+private:
+    /**
+     * Arguments after the iterator pair, by algorithm
+     */
+    static int extraArguments(const std::string& algorithm, size_t given) {
+        static const std::map<std::string, std::vector<size_t>> counts = {
+            {"sort", {0, 1}}, {"stable_sort", {0, 1}}, {"reverse", {0}}, {"fill", {1}},
+            {"find", {1}}, {"find_if", {1}}, {"count", {1}}, {"count_if", {1}},
+            {"any_of", {1}}, {"all_of", {1}}, {"none_of", {1}}, {"accumulate", {1, 2}},
+            {"transform", {2}}, {"copy", {1}}, {"copy_if", {2}},
+            {"min_element", {0, 1}}, {"max_element", {0, 1}},
+            {"remove", {1}}, {"remove_if", {1}}, {"unique", {0}}};
+        auto it = counts.find(algorithm);
+        if (it == counts.end()) return -1;
+        return std::count(it->second.begin(), it->second.end(), given) ? static_cast<int>(given) : -1;
+    }
+
+    /**
+     * Algorithms that reorder or overwrite the container
+     */
+    static bool isMutating(const std::string& algorithm) {
+        static const std::set<std::string> mutating = {
+            "sort", "stable_sort", "reverse", "fill", "remove", "remove_if", "unique", "erase", "erase_if"};
+        return mutating.count(algorithm) > 0;
+    }
+
+    void analyzeFunction(const IR& ir, const ClassDecl* owner, Function& func) {
+        const std::string& body = func.body;
+        if (body.empty()) return;
+
+        std::vector<std::pair<size_t, AlgorithmInfo>> found;
+        detectErases(ir, owner, func, found);
+
+        std::regex call_pattern(
+            R"(\bstd::(sort|stable_sort|reverse|fill|find|find_if|count|count_if|any_of|all_of|none_of|)"
+            R"(accumulate|transform|copy|copy_if|min_element|max_element)\s*\()");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), call_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            bool inside = std::any_of(found.begin(), found.end(), [&](const auto& entry) {
+                return pos >= entry.first && pos < entry.first + entry.second.source_text.size();
+            });
+            if (inside) continue;
+
+            size_t open = pos + it->length(0) - 1;
+            size_t close = findClosingParen(body, open);
+            if (close == std::string::npos) continue;
+
+            AlgorithmInfo info;
+            info.algorithm = (*it)[1].str();
+            std::vector<std::string> args = splitArguments(body.substr(open + 1, close - open - 1));
+            if (!fillRange(ir, owner, func, args, info)) continue;
+
+            size_t begin = pos;
+            size_t end = close + 1;
+            if (info.algorithm == "transform" || info.algorithm == "copy" || info.algorithm == "copy_if") {
+                if (!fillOutput(info) || (info.algorithm == "copy_if" && !info.back_inserter)) continue;
+            }
+
+            // std::find(...) != v.end()
+            if (info.algorithm == "find" || info.algorithm == "find_if") {
+                std::smatch comparison;
+                std::string rest = body.substr(end);
+                std::regex end_pattern(R"(^\s*(!=|==)\s*([^;,()]*\(\s*[^()]*\)))");
+                if (std::regex_search(rest, comparison, end_pattern) &&
+                    isEndOf(trim(comparison[2].str()), info.container)) {
+                    info.end_comparison = comparison[1].str();
+                    end += comparison.length(0);
+                }
+            }
+
+            // *std::max_element(...)
+            if (info.algorithm == "min_element" || info.algorithm == "max_element") {
+                size_t star = body.find_last_not_of(" \t\r\n", pos == 0 ? 0 : pos - 1);
+                if (pos > 0 && star != std::string::npos && body[star] == '*') {
+                    size_t before = body.find_last_not_of(" \t\r\n", star == 0 ? 0 : star - 1);
+                    bool unary = star == 0 || before == std::string::npos ||
+                                 !(std::isalnum(static_cast<unsigned char>(body[before])) ||
+                                   body[before] == '_' || body[before] == ')' || body[before] == ']') ||
+                                 precededByReturn(body, star);
+                    if (unary) {
+                        info.dereferenced = true;
+                        begin = star;
+                    }
+                }
+            }
+
+            info.source_text = body.substr(begin, end - begin);
+            found.emplace_back(begin, info);
+        }
+
+        appendInSourceOrder(found, func.algorithms);
+    }
+
+    /**
+     * v.erase(std::remove_if(v.begin(), v.end(), p), v.end());  std::erase_if(v, p);
+     */
+    void detectErases(const IR& ir, const ClassDecl* owner, Function& func,
+                      std::vector<std::pair<size_t, AlgorithmInfo>>& found) {
+        const std::string& body = func.body;
+
+        std::regex idiom_pattern(R"(([a-zA-Z_][\w.]*(?:->\w+)*)\s*\.\s*erase\s*\(\s*std::(remove_if|remove|unique)\s*\()");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), idiom_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            size_t erase_open = body.find('(', pos + (*it)[1].length());
+            size_t erase_close = findClosingParen(body, erase_open);
+            size_t inner_open = pos + it->length(0) - 1;
+            size_t inner_close = findClosingParen(body, inner_open);
+            if (erase_close == std::string::npos || inner_close == std::string::npos) continue;
+
+            // The erased range ends at the container's end
+            std::string tail = trim(body.substr(inner_close + 1, erase_close - inner_close - 1));
+            if (tail.empty() || tail[0] != ',' || !isEndOf(trim(tail.substr(1)), (*it)[1].str())) continue;
+
+            AlgorithmInfo info;
+            info.algorithm = (*it)[2].str();
+            std::vector<std::string> args =
+                splitArguments(body.substr(inner_open + 1, inner_close - inner_open - 1));
+            if (!fillRange(ir, owner, func, args, info) || info.container != (*it)[1].str()) continue;
+
+            info.source_text = body.substr(pos, erase_close + 1 - pos);
+            found.emplace_back(pos, info);
+        }
+
+        std::regex erase_pattern(R"(\bstd::(erase_if|erase)\s*\()");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), erase_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            size_t open = pos + it->length(0) - 1;
+            size_t close = findClosingParen(body, open);
+            if (close == std::string::npos) continue;
+
+            std::vector<std::string> args = splitArguments(body.substr(open + 1, close - open - 1));
+            if (args.size() != 2 || !mutableContainer(owner, func, args[0])) continue;
+
+            AlgorithmInfo info;
+            info.algorithm = (*it)[1].str();
+            info.container = args[0];
+            info.arguments = {args[1]};
+            info.stored_functors = {isStoredFunctor(ir, owner, func, args[1])};
+            info.source_text = body.substr(pos, close + 1 - pos);
+            found.emplace_back(pos, info);
+        }
+    }
+
+    /**
+     * v.begin(), v.end() (or std::begin(v), std::end(v), cbegin/cend)
+     */
+    bool fillRange(const IR& ir, const ClassDecl* owner, const Function& func,
+                   const std::vector<std::string>& args, AlgorithmInfo& info) {
+        if (args.size() < 2) return false;
+
+        std::smatch begin_match;
+        std::regex begin_pattern(R"(([a-zA-Z_][\w.]*(?:->\w+)*)\s*\.\s*c?begin\s*\(\s*\)|std::c?begin\s*\(\s*([^()]+?)\s*\))");
+        if (!std::regex_match(args[0], begin_match, begin_pattern)) return false;
+
+        std::string container = begin_match[1].matched ? begin_match[1].str() : begin_match[2].str();
+        if (!isEndOf(args[1], container)) return false;
+        if (extraArguments(info.algorithm, args.size() - 2) < 0) return false;
+
+        // Maps iterate pairs, and node-based containers cannot be reordered in place
+        auto type = containerType(owner, func, container);
+        if (type && (type->kind == TypeKind::StdMap || type->kind == TypeKind::StdUnorderedMap ||
+                     std::regex_search(type->name, std::regex(R"(^std::(?:unordered_)?(?:multi)?map\s*<)")))) {
+            return false;
+        }
+        if (isMutating(info.algorithm) && !mutableContainer(owner, func, container)) return false;
+
+        info.container = container;
+        info.arguments.assign(args.begin() + 2, args.end());
+        for (const auto& arg : info.arguments) {
+            info.stored_functors.push_back(isStoredFunctor(ir, owner, func, arg));
+        }
+        return true;
+    }
+
+    /**
+     * Output iterator of transform/copy: std::back_inserter(out) or out.begin()
+     */
+    bool fillOutput(AlgorithmInfo& info) {
+        if (info.arguments.empty()) return false;
+
+        std::smatch output;
+        std::regex inserter_pattern(R"(std::back_inserter\s*\(\s*([a-zA-Z_][\w.]*(?:->\w+)*)\s*\))");
+        std::regex begin_pattern(R"(([a-zA-Z_][\w.]*(?:->\w+)*)\s*\.\s*begin\s*\(\s*\))");
+        if (std::regex_match(info.arguments[0], output, inserter_pattern)) {
+            info.back_inserter = true;
+        } else if (!std::regex_match(info.arguments[0], output, begin_pattern)) {
+            return false;
+        }
+
+        info.output = output[1].str();
+        info.arguments.erase(info.arguments.begin());
+        info.stored_functors.erase(info.stored_functors.begin());
+        return true;
+    }
+
+    static bool precededByReturn(const std::string& text, size_t pos) {
+        size_t end = text.find_last_not_of(" \t\r\n", pos - 1);
+        return end != std::string::npos && end >= 5 && text.compare(end - 5, 6, "return") == 0 &&
+               (end == 5 || !(std::isalnum(static_cast<unsigned char>(text[end - 6])) || text[end - 6] == '_'));
+    }
+
+    static bool isEndOf(const std::string& arg, const std::string& container) {
+        std::regex end_pattern("(?:" + escape(container) + R"(\s*\.\s*c?end\s*\(\s*\)|std::c?end\s*\(\s*)" +
+                               escape(container) + R"(\s*\)))");
+        return std::regex_match(arg, end_pattern);
+    }
+
+    static std::string escape(const std::string& text) {
+        return std::regex_replace(text, std::regex(R"([.^$|()\[\]{}*+?\\-])"), "\\$&");
+    }
+
+    /**
+     * Declared type of a parameter, field or local (null if unknown)
+     */
+    std::shared_ptr<Type> containerType(const ClassDecl* owner, const Function& func, std::string name) {
+        if (name.compare(0, 6, "this->") == 0) name = name.substr(6);
+
+        std::shared_ptr<Type> type;
+        for (const auto& param : func.parameters) {
+            if (param.name == name) type = param.type;
+        }
+        if (!type && owner) {
+            for (const auto& field : owner->fields) {
+                if (field.name == name) type = field.type;
+            }
+        }
+        while (type && (type->kind == TypeKind::Reference || type->kind == TypeKind::RValueReference)) {
+            type = type->element_type;
+        }
+        return type;
+    }
+
+    /**
+     * Contiguous containers that support in-place reordering (or of unknown type)
+     */
+    bool mutableContainer(const ClassDecl* owner, const Function& func, const std::string& name) {
+        auto type = containerType(owner, func, name);
+        return !type || type->kind == TypeKind::StdVector || type->kind == TypeKind::Array ||
+               std::regex_search(type->name, std::regex(R"(^std::(?:vector|array)\s*<)"));
+    }
+
+    /**
+     * A parameter, field or local whose class defines operator()
+     */
+    bool isStoredFunctor(const IR& ir, const ClassDecl* owner, const Function& func, const std::string& arg) {
+        if (!std::regex_match(arg, std::regex(R"((?:this->)?[a-zA-Z_]\w*)"))) return false;
+
+        auto type = containerType(owner, func, arg);
+        std::string class_name = type ? type->name : "";
+        if (!type) {
+            // Local object: ByLength order; / ByLength order{...};
+            std::smatch local;
+            std::regex local_pattern(R"(\b([A-Za-z_]\w*)\s+)" + arg + R"(\s*[;={(])");
+            if (std::regex_search(func.body, local, local_pattern)) class_name = local[1].str();
+        }
+
+        const ClassDecl* target = ir.findClass(class_name);
+        if (!target) return false;
+        return std::any_of(target->methods.begin(), target->methods.end(),
+                           [](const Function& method) { return method.name == "operator()"; });
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,122 @@
+/**
+ * Allocation Analyzer
+ * Finds manual object lifetime (placement new, explicit destructor calls)
+ * and class-specific operator new / operator delete, which must not be
+ * translated as ordinary heap allocation
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+
+namespace hybrid {
+
+/**
+ * Allocation Analyzer
+ * Whole-program pass: placement new may construct a class declared
+ * anywhere in the input
+ */
+class AllocationAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit AllocationAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            class_decl.has_class_allocator = std::any_of(
+                class_decl.methods.begin(), class_decl.methods.end(),
+                [](const Function& method) { return method.name.compare(0, 9, "operator ") == 0; });
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(method);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(func);
+        }
+    }
+
+private:
+    TypeParser parse_type_;
+
+    void analyzeFunction(Function& func) {
+        const std::string& body = func.body;
+        if (body.empty()) return;
+
+        std::vector<std::pair<size_t, AllocationSiteInfo>> found;
+        detectPlacementNew(body, found);
+        detectDestructorCalls(body, found);
+
+        appendInSourceOrder(found, func.allocations);
+    }
+
+    /**
+     * new (buffer) Widget(args) / ::new (static_cast<void*>(p)) T{args} / new (buffer) T[n]
+     */
+    void detectPlacementNew(const std::string& body, std::vector<std::pair<size_t, AllocationSiteInfo>>& found) {
+        std::regex new_pattern(R"((?:::\s*)?\bnew\s*\()");
+        std::regex type_pattern(R"(^\s*([a-zA-Z_][\w:]*(?:<[^;(){}]*>)?)\s*)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), new_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            size_t open = pos + it->length(0) - 1;
+            size_t close = findClosing(body, open, '(', ')');
+            if (close == std::string::npos) continue;
+
+            std::string buffer = trim(body.substr(open + 1, close - open - 1));
+            // new (std::nothrow) T allocates: it is not placement
+            if (buffer == "std::nothrow" || buffer == "nothrow") continue;
+
+            std::smatch type_match;
+            std::string rest = body.substr(close + 1);
+            if (!std::regex_search(rest, type_match, type_pattern)) continue;
+
+            AllocationSiteInfo site;
+            site.kind = AllocationSiteInfo::PlacementNew;
+            site.buffer = buffer;
+            site.type = parse_type_(type_match[1].str());
+
+            size_t end = close + 1 + type_match.length(0);
+            if (end < body.size() && (body[end] == '(' || body[end] == '{' || body[end] == '[')) {
+                char open_char = body[end];
+                char close_char = open_char == '(' ? ')' : (open_char == '{' ? '}' : ']');
+                size_t args_end = findClosing(body, end, open_char, close_char);
+                if (args_end == std::string::npos) continue;
+                site.is_array = open_char == '[';
+                site.arguments = splitArguments(body.substr(end + 1, args_end - end - 1));
+                end = args_end + 1;
+            }
+            site.source_text = trim(body.substr(pos, end - pos));
+            found.emplace_back(pos, site);
+        }
+    }
+
+    /**
+     * w->~Widget() / obj.~Widget() / this->~Widget()
+     */
+    void detectDestructorCalls(const std::string& body,
+                               std::vector<std::pair<size_t, AllocationSiteInfo>>& found) {
+        std::regex call_pattern(R"((\*?[a-zA-Z_][\w.]*|this)\s*(->|\.)\s*~\s*([a-zA-Z_][\w:]*)\s*\(\s*\))");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), call_pattern);
+             it != std::sregex_iterator(); ++it) {
+            AllocationSiteInfo site;
+            site.kind = AllocationSiteInfo::DestructorCall;
+            site.source_text = it->str(0);
+            site.buffer = (*it)[1].str();
+            site.through_pointer = (*it)[2].str() == "->";
+            site.type = parse_type_((*it)[3].str());
+            found.emplace_back(it->position(0), site);
+        }
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
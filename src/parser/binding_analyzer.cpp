@@ -0,0 +1,299 @@
+/**
+ * Binding Analyzer
+ * Finds structured bindings, std::tie assignments and pair/tuple variables
+ * so they can become Rust tuple (or struct) destructuring
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+
+namespace hybrid {
+
+/**
+ * Binding Analyzer
+ * Whole-program pass: destructuring a class depends on its fields and copy semantics
+ */
+class BindingAnalyzer {
+public:
+    using CopyCheck = std::function<bool(const std::shared_ptr<Type>&)>;
+
+    explicit BindingAnalyzer(CopyCheck is_copy) : is_copy_(std::move(is_copy)) {}
+
+    /**
+     * Analyze all method and function bodies
+     */
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(ir, &class_decl, method);
+            }
+        }
+
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(ir, nullptr, func);
+        }
+    }
+
+private:
+    CopyCheck is_copy_;
+
+    void analyzeFunction(const IR& ir, const ClassDecl* class_decl, Function& func) {
+        collectTupleVariables(class_decl, func);
+        if (func.body.empty()) return;
+
+        std::vector<std::pair<size_t, BindingInfo>> found;
+        detectDeclarations(ir, class_decl, func, found);
+        detectRangeFors(ir, class_decl, func, found);
+        detectTies(ir, class_decl, func, found);
+
+        appendInSourceOrder(found, func.bindings);
+    }
+
+    /**
+     * Parameters, fields and locals holding a std::pair or std::tuple
+     * (their .first/.second become .0/.1)
+     */
+    void collectTupleVariables(const ClassDecl* class_decl, Function& func) {
+        for (const auto& param : func.parameters) {
+            if (isTuple(param.type)) func.tuple_variables.push_back(param.name);
+        }
+        if (class_decl) {
+            for (const auto& field : class_decl->fields) {
+                if (isTuple(field.type)) func.tuple_variables.push_back(field.name);
+            }
+        }
+
+        std::regex local_pattern(
+            R"(\b(?:std::(?:pair|tuple)\s*<[^;=(){}]*>|auto\b)\s*&{0,2}\s*([a-zA-Z_]\w*)\s*(=\s*std::make_(?:pair|tuple)\b|[=({;]))"
+        );
+        std::smatch match;
+        std::string::const_iterator search_start(func.body.cbegin());
+
+        while (std::regex_search(search_start, func.body.cend(), match, local_pattern)) {
+            bool is_auto = match[0].str().compare(0, 4, "auto") == 0;
+            if (!is_auto || match[2].length() > 1) {
+                func.tuple_variables.push_back(match[1].str());
+            }
+            search_start = match.suffix().first;
+        }
+    }
+
+    static bool isTuple(std::shared_ptr<Type> type) {
+        while (type && (type->kind == TypeKind::Reference ||
+                        type->kind == TypeKind::RValueReference)) {
+            type = type->element_type;
+        }
+        return type && (type->kind == TypeKind::StdPair || type->kind == TypeKind::StdTuple);
+    }
+
+    /**
+     * auto [a, b] = expr;  const auto& [a, b]{expr};  auto&& [a, b](expr);
+     */
+    void detectDeclarations(const IR& ir, const ClassDecl* class_decl, Function& func,
+                            std::vector<std::pair<size_t, BindingInfo>>& found) {
+        const std::string& body = func.body;
+        std::regex decl_pattern(
+            R"((const\s+)?auto\s*(&{0,2})\s*\[\s*([\w\s,]+)\]\s*(?:=\s*([^;]+?)|\{([^;]*)\}|\(([^;]*)\))\s*;)"
+        );
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, decl_pattern)) {
+            size_t pos = match.position(0) + (search_start - body.cbegin());
+            size_t end = match.suffix().first - body.cbegin();
+
+            BindingInfo info;
+            info.kind = BindingInfo::Declaration;
+            info.source_text = match[0].str();
+            info.is_const = match[1].matched;
+            info.names = splitNames(match[3].str());
+            for (int group = 4; group <= 6; ++group) {
+                if (match[group].matched) info.initializer = trim(match[group].str());
+            }
+            fillBinding(ir, class_decl, func, match[2].str(), end, info);
+
+            found.emplace_back(pos, info);
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * for (const auto& [k, v] : m) { ... }
+     */
+    void detectRangeFors(const IR& ir, const ClassDecl* class_decl, Function& func,
+                         std::vector<std::pair<size_t, BindingInfo>>& found) {
+        const std::string& body = func.body;
+        std::regex head_pattern(
+            R"(\bfor\s*\(\s*(const\s+)?auto\s*(&{0,2})\s*\[\s*([\w\s,]+)\]\s*:)"
+        );
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, head_pattern)) {
+            size_t pos = match.position(0) + (search_start - body.cbegin());
+            size_t range_start = match.suffix().first - body.cbegin();
+            search_start = match.suffix().first;
+
+            // The range expression may itself contain parentheses
+            size_t close = findClosingParen(body, pos + match[0].str().find('('));
+            if (close == std::string::npos) continue;
+
+            size_t brace = body.find_first_not_of(" \t\r\n", close + 1);
+            if (brace == std::string::npos || body[brace] != '{') {
+                continue;  // Only braced loop bodies are rewritten
+            }
+
+            BindingInfo info;
+            info.kind = BindingInfo::RangeFor;
+            info.source_text = body.substr(pos, close + 1 - pos);
+            info.is_const = match[1].matched;
+            info.names = splitNames(match[3].str());
+            info.initializer = trim(body.substr(range_start, close - range_start));
+            fillBinding(ir, class_decl, func, match[2].str(), brace, info);
+
+            // Range elements are not classes the parser knows about
+            info.struct_name.clear();
+            info.field_names.clear();
+            info.needs_clone = false;
+
+            found.emplace_back(pos, info);
+        }
+    }
+
+    /**
+     * std::tie(a, std::ignore) = expr;
+     */
+    void detectTies(const IR& ir, const ClassDecl* class_decl, Function& func,
+                    std::vector<std::pair<size_t, BindingInfo>>& found) {
+        const std::string& body = func.body;
+        std::regex tie_pattern(R"(std::tie\s*\(([^()]*)\)\s*=\s*([^;]+?)\s*;)");
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, tie_pattern)) {
+            size_t pos = match.position(0) + (search_start - body.cbegin());
+
+            BindingInfo info;
+            info.kind = BindingInfo::Tie;
+            info.source_text = match[0].str();
+            info.initializer = trim(match[2].str());
+            for (auto name : splitNames(match[1].str())) {
+                info.names.push_back(name == "std::ignore" ? "_" : name);
+            }
+            fillBinding(ir, class_decl, func, "", match.suffix().first - body.cbegin(), info);
+
+            // Only tuple-like values can be tied; the targets are existing variables
+            info.struct_name.clear();
+            info.field_names.clear();
+            info.reassigned.assign(info.names.size(), false);
+
+            found.emplace_back(pos, info);
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * Reference mode, struct pattern, cloning and later assignments
+     */
+    void fillBinding(const IR& ir, const ClassDecl* class_decl, const Function& func,
+                     const std::string& ref_qualifier, size_t scope_start, BindingInfo& info) {
+        info.initializer_is_lvalue = std::regex_match(
+            info.initializer, std::regex(R"((?:this->)?[a-zA-Z_]\w*(?:(?:\.|->)\w+)*)"));
+
+        // auto&& only binds by reference to an lvalue; a temporary is owned
+        info.by_reference = ref_qualifier == "&" ||
+                            (ref_qualifier == "&&" && info.initializer_is_lvalue);
+
+        for (const auto& name : info.names) {
+            info.reassigned.push_back(isAssignedAfter(func.body, name, scope_start));
+        }
+
+        auto type = info.initializer_is_lvalue ? resolveType(class_decl, func, info.initializer)
+                                               : nullptr;
+        while (type && (type->kind == TypeKind::Reference ||
+                        type->kind == TypeKind::RValueReference)) {
+            type = type->element_type;
+        }
+        if (!type) return;
+
+        if (!info.by_reference) {
+            info.needs_clone = !is_copy_(type);
+        }
+
+        // Aggregates are destructured by their non-static fields in order
+        const ClassDecl* target = ir.findClass(type->name);
+        if (!target || (type->kind != TypeKind::Class && type->kind != TypeKind::Struct)) {
+            return;
+        }
+        std::vector<std::string> fields;
+        for (const auto& field : target->fields) {
+            if (!field.is_static) fields.push_back(field.name);
+        }
+        if (fields.size() == info.names.size()) {
+            info.struct_name = target->name;
+            info.field_names = fields;
+        }
+    }
+
+    /**
+     * Type of a parameter, field or this->field
+     */
+    std::shared_ptr<Type> resolveType(const ClassDecl* class_decl, const Function& func,
+                                      std::string expr) {
+        if (expr.compare(0, 6, "this->") == 0) {
+            expr = expr.substr(6);
+        }
+        if (expr.find_first_of(".-") != std::string::npos) {
+            return nullptr;  // Member access not resolved
+        }
+
+        for (const auto& param : func.parameters) {
+            if (param.name == expr) return param.type;
+        }
+        if (class_decl) {
+            for (const auto& field : class_decl->fields) {
+                if (field.name == expr) return field.type;
+            }
+        }
+        return nullptr;
+    }
+
+    bool isAssignedAfter(const std::string& body, const std::string& name, size_t pos) {
+        if (name == "_" || pos >= body.size()) return false;
+        std::regex assign_pattern(
+            "(^|[^\\w.>])" + name + R"(\s*(?:[-+*/%&|^]|<<|>>)?=(?!=))"
+            "|(\\+\\+|--)\\s*" + name + "\\b"
+            "|(^|[^\\w.>])" + name + R"(\s*(\+\+|--))"
+        );
+        std::string rest = body.substr(pos);
+        return std::regex_search(rest, assign_pattern);
+    }
+
+    static std::vector<std::string> splitNames(const std::string& list) {
+        std::vector<std::string> names;
+        size_t start = 0;
+        while (start <= list.size()) {
+            size_t comma = list.find(',', start);
+            if (comma == std::string::npos) comma = list.size();
+            std::string name = trim(list.substr(start, comma - start));
+            if (!name.empty()) names.push_back(name);
+            start = comma + 1;
+        }
+        return names;
+    }
+
+    static std::string trim(const std::string& s) {
+        size_t start = s.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = s.find_last_not_of(" \t\n\r");
+        return s.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,485 @@
+/**
+ * Command Line Analyzer
+ * Finds the option parsing loops of main, hand-rolled over argv or
+ * driven by getopt, that a clap derive struct can replace (--clap)
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <functional>
+#include <map>
+#include <cctype>
+#include <iterator>
+
+namespace hybrid {
+
+/**
+ * Command Line Analyzer
+ * Whole-program pass over the entry point. Only loops whose every branch
+ * is understood are recorded: an option that is matched by prefix or
+ * reads two values leaves the loop as written
+ */
+class CommandLineAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit CommandLineAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    void analyze(IR& ir) {
+        for (auto& func : ir.getFunctions()) {
+            if (!func.is_entry_point || func.parameters.size() < 2) continue;
+            findGetopt(func);
+            findArgvLoops(func);
+        }
+    }
+
+private:
+    TypeParser parse_type_;
+
+    /**
+     * while ((c = getopt(argc, argv, "vo:")) != -1) { switch (c) { case 'v': ... } }
+     */
+    void findGetopt(Function& func) {
+        const std::string& body = func.body;
+        const std::string& argc = func.parameters[0].name;
+        const std::string& argv = func.parameters[1].name;
+        std::regex loop(R"(\bwhile\s*\(\s*\(\s*(\w+)\s*=\s*(getopt|getopt_long)\s*\(\s*)" + argc + R"(\s*,\s*)" +
+                        argv + R"re(\s*,\s*"([^"]*)"(?:\s*,[^()]*)?\)\s*\)\s*!=\s*-1\s*\)\s*\{)re");
+
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), loop); it != std::sregex_iterator(); ++it) {
+            size_t start = static_cast<size_t>(it->position(0));
+            size_t open = start + static_cast<size_t>(it->length(0)) - 1;
+            size_t close = findClosing(body, open, '{', '}');
+            if (close == std::string::npos) continue;
+
+            CommandLineInfo info;
+            info.style = (*it)[2].str() == "getopt_long" ? CommandLineInfo::GetoptLong : CommandLineInfo::Getopt;
+            info.source_text = body.substr(start, close + 1 - start);
+
+            // "vo:": a ':' after the letter means it takes a value
+            std::map<char, bool> letters;
+            std::string optstring = (*it)[3].str();
+            for (size_t i = 0; i < optstring.size(); ++i) {
+                char c = optstring[i];
+                if (c == ':' || c == '+' || c == '-') continue;
+                letters[c] = i + 1 < optstring.size() && optstring[i + 1] == ':';
+            }
+            // {"output", required_argument, 0, 'o'}
+            std::map<char, std::string> long_names;
+            static const std::regex long_option(
+                R"re(\{\s*"([\w-]+)"\s*,\s*(?:no_argument|required_argument|optional_argument|[012])\s*,\s*(?:0|NULL|nullptr)\s*,\s*'(\w)'\s*\})re");
+            for (auto entry = std::sregex_iterator(body.begin(), body.end(), long_option);
+                 entry != std::sregex_iterator(); ++entry) {
+                long_names[(*entry)[2].str()[0]] = (*entry)[1].str();
+            }
+
+            std::string inner = body.substr(open + 1, close - open - 1);
+            std::regex dispatch(R"(^\s*switch\s*\(\s*)" + (*it)[1].str() + R"(\s*\)\s*\{)");
+            std::smatch switch_match;
+            if (!std::regex_search(inner, switch_match, dispatch)) continue;
+            size_t switch_open = static_cast<size_t>(switch_match.length(0)) - 1;
+            size_t switch_close = findClosing(inner, switch_open, '{', '}');
+            if (switch_close == std::string::npos || !trim(inner.substr(switch_close + 1)).empty()) continue;
+
+            bool understood = true;
+            for (const auto& label : caseLabels(inner.substr(switch_open + 1, switch_close - switch_open - 1))) {
+                // default, '?', ':': unknown options and missing values, which clap reports
+                if (label.first == 0 || label.first == '?' || label.first == ':') continue;
+                // -h and --help come with clap
+                if (label.first == 'h' || long_names[label.first] == "help") continue;
+                if (!letters.count(label.first)) {
+                    understood = false;
+                    break;
+                }
+
+                CliOptionInfo option;
+                option.short_name = label.first;
+                option.long_name = long_names[label.first];
+                option.takes_value = letters[label.first];
+                std::vector<std::string> statements = splitStatements(label.second);
+                if (statements.empty() ||
+                    !classify(func, start, statements, std::regex(R"(\boptarg\b)"), option)) {
+                    understood = false;
+                    break;
+                }
+                info.options.push_back(option);
+            }
+            if (!understood || info.options.empty()) continue;
+
+            info.uses_optind = std::regex_search(body.substr(close), std::regex(R"(\boptind\b)"));
+            func.command_lines.push_back(info);
+        }
+    }
+
+    /**
+     * for (int i = 1; i < argc; ++i) { if (strcmp(argv[i], "-v") == 0) ... else if ... }
+     */
+    void findArgvLoops(Function& func) {
+        const std::string& body = func.body;
+        const std::string& argc = func.parameters[0].name;
+        const std::string& argv = func.parameters[1].name;
+        std::regex loop(R"(\bfor\s*\(\s*int\s+(\w+)\s*=\s*1\s*;\s*\1\s*<\s*)" + argc +
+                        R"(\s*;\s*(?:\+\+\s*\1|\1\s*\+\+)\s*\)\s*\{)");
+
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), loop); it != std::sregex_iterator(); ++it) {
+            size_t start = static_cast<size_t>(it->position(0));
+            size_t open = start + static_cast<size_t>(it->length(0)) - 1;
+            size_t close = findClosing(body, open, '{', '}');
+            if (close == std::string::npos) continue;
+            std::string index = (*it)[1].str();
+            std::string inner = body.substr(open + 1, close - open - 1);
+
+            // std::string arg = argv[i];
+            std::string current = argv + R"(\s*\[\s*)" + index + R"(\s*\])";
+            std::regex alias(R"(^\s*(?:const\s+)?(?:std::string\s*&?|std::string_view|char\s*\*|const\s+char\s*\*)\s*(\w+)\s*(?:=\s*)" +
+                             current + R"(|\(\s*)" + current + R"(\s*\))\s*;)");
+            std::smatch alias_match;
+            std::string arg = "(?:" + current + R"(|std::string\s*\(\s*)" + current + R"(\s*\)))";
+            if (std::regex_search(inner, alias_match, alias)) {
+                arg = "(?:\\b" + alias_match[1].str() + "\\b|" + arg + ")";
+                inner = inner.substr(static_cast<size_t>(alias_match.length(0)));
+            }
+            std::regex value(argv + R"(\s*\[\s*\+\+\s*)" + index + R"(\s*\])");
+
+            CommandLineInfo info;
+            info.style = CommandLineInfo::ArgvLoop;
+            info.source_text = body.substr(start, close + 1 - start);
+            std::vector<std::pair<std::string, std::string>> branches;
+            std::string otherwise;
+            if (!ifChain(inner, branches, otherwise)) continue;
+
+            bool understood = true;
+            for (const auto& branch : branches) {
+                CliOptionInfo option;
+                if (!optionNames(branch.first, arg, argc, index, option)) {
+                    understood = false;
+                    break;
+                }
+                std::vector<std::string> statements = splitStatements(branch.second);
+                // Two values (-s W H) are left as written
+                auto reads = std::distance(std::sregex_iterator(branch.second.begin(), branch.second.end(), value),
+                                           std::sregex_iterator());
+                if (reads > 1) {
+                    understood = false;
+                    break;
+                }
+                option.takes_value = reads == 1;
+                // -h, --help come with clap
+                if (option.short_name == 'h' || option.long_name == "help") continue;
+                if (statements.empty() || !classify(func, start, statements, value, option)) {
+                    understood = false;
+                    break;
+                }
+                info.options.push_back(option);
+            }
+            if (!understood || info.options.empty()) continue;
+
+            // files.push_back(arg), input = arg: positional arguments; anything else
+            // rejects unknown arguments, as clap does
+            std::vector<std::string> statements = splitStatements(otherwise);
+            std::smatch positional;
+            if (statements.size() == 1 &&
+                (std::regex_match(statements[0], positional,
+                                  std::regex(R"((\w+)\s*\.\s*(?:push_back|emplace_back)\s*\(\s*)" + arg + R"(\s*\))")) ||
+                 std::regex_match(statements[0], positional, std::regex(R"((\w+)\s*=\s*)" + arg)))) {
+                info.positional = positional[1].str();
+                info.positional_type = declaredType(func.body, start, info.positional);
+            }
+            func.command_lines.push_back(info);
+        }
+    }
+
+    /**
+     * Sort the statements of an option into the variable it sets and the rest
+     */
+    bool classify(const Function& func, size_t limit, const std::vector<std::string>& statements,
+                  const std::regex& value, CliOptionInfo& option) const {
+        static const std::regex flag(R"(^(\w+)\s*=\s*(?:true|1)$)");
+        static const std::regex counted(R"(^(?:\+\+\s*(\w+)|(\w+)\s*\+\+|(\w+)\s*\+=\s*1)$)");
+        static const std::regex assigned(R"(^(\w+)\s*=\s*(?:(?:std\s*::\s*)?(?:atoi|atol|atoll|atof|stoi|stol|stoll|stoul|stoull|stof|stod|strtol|strtoul|strtod)\s*\(\s*)?(\w+)\s*(?:,[^()]*)?\)?$)");
+        static const std::regex appended(R"(^(\w+)\s*\.\s*(?:push_back|emplace_back)\s*\(\s*(\w+)\s*\)$)");
+
+        std::string action;
+        for (const auto& statement : statements) {
+            // The value under one name: value
+            std::string text = std::regex_replace(statement, value, "value");
+            std::smatch match;
+            if (option.target.empty()) {
+                if (!option.takes_value && std::regex_match(text, match, flag)) {
+                    option.target = match[1].str();
+                    continue;
+                }
+                if (!option.takes_value && std::regex_match(text, match, counted)) {
+                    option.target = match[1].matched ? match[1].str() : match[2].matched ? match[2].str() : match[3].str();
+                    option.counted = true;
+                    continue;
+                }
+                if (option.takes_value && std::regex_match(text, match, assigned) && match[2].str() == "value") {
+                    option.target = match[1].str();
+                    continue;
+                }
+                if (option.takes_value && std::regex_match(text, match, appended) && match[2].str() == "value") {
+                    option.target = match[1].str();
+                    option.repeated = true;
+                    continue;
+                }
+            }
+            action += text + ";";
+        }
+        option.action = action;
+        if (!option.target.empty()) option.type = declaredType(func.body, limit, option.target);
+        return true;
+    }
+
+    /**
+     * strcmp(arg, "-v") == 0 || arg == "--verbose", with an i + 1 < argc guard
+     */
+    static bool optionNames(const std::string& condition, const std::string& arg, const std::string& argc,
+                            const std::string& index, CliOptionInfo& option) {
+        std::regex guard(R"(^(?:)" + index + R"(\s*\+\s*1\s*<\s*)" + argc + "|" + index + R"(\s*<\s*)" + argc +
+                         R"(\s*-\s*1)$)");
+        std::vector<std::regex> comparisons = {
+            std::regex("^" + arg + R"re(\s*==\s*"(-[^"]*)"$)re"),
+            std::regex(R"re(^"(-[^"]*)"\s*==\s*)re" + arg + "$"),
+            std::regex(R"(^(?:std\s*::\s*)?strcmp\s*\(\s*)" + arg + R"re(\s*,\s*"(-[^"]*)"\s*\)\s*==\s*0$)re"),
+            std::regex(R"(^!\s*(?:std\s*::\s*)?strcmp\s*\(\s*)" + arg + R"re(\s*,\s*"(-[^"]*)"\s*\)$)re"),
+            std::regex("^" + arg + R"re(\s*\.\s*compare\s*\(\s*"(-[^"]*)"\s*\)\s*==\s*0$)re"),
+        };
+        return names(unwrap(condition), guard, comparisons, option) &&
+               (option.short_name != 0 || !option.long_name.empty());
+    }
+
+    // a || b: every alternative names the option; a && guard: one part does
+    static bool names(const std::string& condition, const std::regex& guard,
+                      const std::vector<std::regex>& comparisons, CliOptionInfo& option) {
+        std::vector<std::string> alternatives = splitTopLevel(condition, "||");
+        if (alternatives.size() > 1) {
+            for (const auto& alternative : alternatives) {
+                if (!names(unwrap(alternative), guard, comparisons, option)) return false;
+            }
+            return true;
+        }
+        std::vector<std::string> parts = splitTopLevel(condition, "&&");
+        if (parts.size() > 1) {
+            bool named = false;
+            for (const auto& part : parts) {
+                std::string text = unwrap(part);
+                if (std::regex_match(text, guard)) continue;
+                if (named || !names(text, guard, comparisons, option)) return false;
+                named = true;
+            }
+            return named;
+        }
+
+        std::smatch match;
+        bool compared = false;
+        for (const auto& comparison : comparisons) {
+            if (std::regex_match(condition, match, comparison)) {
+                compared = true;
+                break;
+            }
+        }
+        if (!compared) return false;
+        std::string name = match[1].str();
+        if (name.size() > 2 && name.compare(0, 2, "--") == 0 && option.long_name.empty()) {
+            option.long_name = name.substr(2);
+            return true;
+        }
+        if (name.size() == 2 && std::isalnum(static_cast<unsigned char>(name[1])) && option.short_name == 0) {
+            option.short_name = name[1];
+            return true;
+        }
+        // -verbose (clap has no single-dash long options), a second -x for the same option
+        return false;
+    }
+
+    /**
+     * if (c1) s1 else if (c2) { s2 } else s3: conditions with their bodies
+     */
+    static bool ifChain(const std::string& text, std::vector<std::pair<std::string, std::string>>& branches,
+                        std::string& otherwise) {
+        static const std::regex if_keyword(R"(^\s*if\s*\()");
+        static const std::regex else_keyword(R"(^\s*else\b)");
+        size_t pos = 0;
+        while (true) {
+            std::smatch match;
+            std::string rest = text.substr(pos);
+            if (!std::regex_search(rest, match, if_keyword)) return false;
+            size_t open = pos + static_cast<size_t>(match.length(0)) - 1;
+            size_t close = findClosing(text, open, '(', ')');
+            if (close == std::string::npos) return false;
+            std::string condition = text.substr(open + 1, close - open - 1);
+            size_t end = branchEnd(text, close + 1);
+            if (end == std::string::npos) return false;
+            branches.emplace_back(condition, text.substr(close + 1, end - close - 1));
+            pos = end;
+
+            rest = text.substr(pos);
+            if (!std::regex_search(rest, match, else_keyword)) return trim(rest).empty();
+            pos += static_cast<size_t>(match.length(0));
+            rest = text.substr(pos);
+            if (std::regex_search(rest, match, if_keyword)) continue;
+            end = branchEnd(text, pos);
+            if (end == std::string::npos) return false;
+            otherwise = text.substr(pos, end - pos);
+            return trim(text.substr(end)).empty();
+        }
+    }
+
+    // Past the statement or block starting at pos
+    static size_t branchEnd(const std::string& text, size_t pos) {
+        size_t first = text.find_first_not_of(" \t\n\r", pos);
+        if (first == std::string::npos) return std::string::npos;
+        if (text[first] == '{') {
+            size_t close = findClosing(text, first, '{', '}');
+            return close == std::string::npos ? close : close + 1;
+        }
+        int depth = 0;
+        for (size_t i = first; i < text.size(); ++i) {
+            char c = text[i];
+            if (c == '"' || c == '\'') {
+                for (++i; i < text.size() && text[i] != c; ++i) {
+                    if (text[i] == '\\') ++i;
+                }
+            } else if (c == '(' || c == '{') {
+                depth++;
+            } else if (c == ')' || c == '}') {
+                depth--;
+            } else if (c == ';' && depth == 0) {
+                return i + 1;
+            }
+        }
+        return std::string::npos;
+    }
+
+    /**
+     * case 'v': ... break; in a switch body; default is label 0
+     */
+    static std::vector<std::pair<char, std::string>> caseLabels(const std::string& text) {
+        static const std::regex label(R"((?:^|[;:{}\s])(?:case\s*'(\\?.)'|default)\s*:(?!:))");
+        std::vector<std::pair<char, std::string>> labels;
+        std::vector<size_t> ends;
+        for (auto it = std::sregex_iterator(text.begin(), text.end(), label); it != std::sregex_iterator(); ++it) {
+            if (depthAt(text, static_cast<size_t>(it->position(0))) != 0) continue;
+            char name = (*it)[1].matched ? (*it)[1].str().back() : 0;
+            if (!labels.empty()) labels.back().second = text.substr(ends.back(), static_cast<size_t>(it->position(0)) + 1 - ends.back());
+            labels.emplace_back(name, "");
+            ends.push_back(static_cast<size_t>(it->position(0) + it->length(0)));
+        }
+        if (!labels.empty()) labels.back().second = text.substr(ends.back());
+        return labels;
+    }
+
+    static int depthAt(const std::string& text, size_t pos) {
+        int depth = 0;
+        for (size_t i = 0; i < pos && i < text.size(); ++i) {
+            char c = text[i];
+            if (c == '"' || c == '\'') {
+                for (++i; i < text.size() && text[i] != c; ++i) {
+                    if (text[i] == '\\') ++i;
+                }
+            } else if (c == '{' || c == '(') {
+                depth++;
+            } else if (c == '}' || c == ')') {
+                depth--;
+            }
+        }
+        return depth;
+    }
+
+    /**
+     * Statements of a branch without braces and break
+     */
+    static std::vector<std::string> splitStatements(const std::string& text) {
+        std::string body = trim(text);
+        if (!body.empty() && body.front() == '{' && findClosing(body, 0, '{', '}') == body.size() - 1) {
+            body = body.substr(1, body.size() - 2);
+        }
+        std::vector<std::string> statements;
+        std::string current;
+        int depth = 0;
+        for (size_t i = 0; i < body.size(); ++i) {
+            char c = body[i];
+            if (c == '"' || c == '\'') {
+                size_t end = i;
+                for (++end; end < body.size() && body[end] != c; ++end) {
+                    if (body[end] == '\\') ++end;
+                }
+                current += body.substr(i, end + 1 - i);
+                i = end;
+                continue;
+            }
+            if (c == '(' || c == '{') depth++;
+            else if (c == ')' || c == '}') depth--;
+            current += c;
+            if ((c == ';' && depth == 0) || (c == '}' && depth == 0)) {
+                std::string statement = trim(current);
+                if (statement.back() == ';') statement = trim(statement.substr(0, statement.size() - 1));
+                if (!statement.empty() && statement != "break") statements.push_back(statement);
+                current.clear();
+            }
+        }
+        if (!trim(current).empty()) statements.push_back(trim(current));
+        return statements;
+    }
+
+    static std::vector<std::string> splitTopLevel(const std::string& text, const std::string& op) {
+        std::vector<std::string> parts;
+        int depth = 0;
+        size_t start = 0;
+        for (size_t i = 0; i < text.size(); ++i) {
+            char c = text[i];
+            if (c == '"') {
+                for (++i; i < text.size() && text[i] != '"'; ++i) {
+                    if (text[i] == '\\') ++i;
+                }
+            } else if (c == '(') {
+                depth++;
+            } else if (c == ')') {
+                depth--;
+            } else if (depth == 0 && text.compare(i, op.size(), op) == 0) {
+                parts.push_back(trim(text.substr(start, i - start)));
+                start = i + op.size();
+                i += op.size() - 1;
+            }
+        }
+        parts.push_back(trim(text.substr(start)));
+        return parts;
+    }
+
+    // (a == b) -> a == b
+    static std::string unwrap(const std::string& text) {
+        std::string result = trim(text);
+        while (result.size() > 1 && result.front() == '(' && findClosing(result, 0, '(', ')') == result.size() - 1) {
+            result = trim(result.substr(1, result.size() - 2));
+        }
+        return result;
+    }
+
+    /**
+     * Type of the latest declaration of name before limit
+     */
+    std::shared_ptr<Type> declaredType(const std::string& body, size_t limit, const std::string& name) const {
+        std::string before = body.substr(0, std::min(limit, body.size()));
+        std::regex declaration(R"((?:^|[;{}])\s*((?:const\s+)?[A-Za-z_][\w:<>, ]*?[\w>*&\s])\s*\b)" + name +
+                               R"(\s*(?:=|;|\{|\())");
+        std::shared_ptr<Type> found;
+        for (auto it = std::sregex_iterator(before.begin(), before.end(), declaration);
+             it != std::sregex_iterator(); ++it) {
+            std::string type_str = trim((*it)[1].str());
+            if (type_str == "return" || type_str == "else" || type_str == "auto") continue;
+            found = parse_type_(type_str);
+        }
+        return found;
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,736 @@
+/**
+ * Constexpr Analyzer
+ * Resolves `if constexpr` chains per instantiation of the enclosing class
+ * template, so that dead branches are not translated
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include "instantiation_cache.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+#include <map>
+
+namespace hybrid {
+
+/**
+ * Constexpr Analyzer
+ * Whole-program pass: instantiations are collected from the code reachable
+ * outside template declarations
+ */
+class ConstexprAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    /**
+     * @param source Comment-free source (for Name<args> instantiations)
+     * @param parse_type Parses instantiation arguments and trait operands
+     * @param cache Selections shared with the other files of a project (optional)
+     */
+    ConstexprAnalyzer(const std::string& source, TypeParser parse_type, InstantiationCache* cache = nullptr)
+        : source_(source), parse_type_(std::move(parse_type)), cache_(cache) {}
+
+    /**
+     * Analyze all classes and their method bodies
+     */
+    void analyze(IR& ir) {
+        collectInstantiations(ir);
+
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                detectChains(method);
+                for (auto& chain : method.constexpr_ifs) {
+                    resolve(class_decl, method, chain);
+                }
+            }
+        }
+    }
+
+private:
+    /**
+     * Template parameter bound to an instantiation argument
+     */
+    struct Binding {
+        std::string argument;
+        std::shared_ptr<Type> type;      // Null for non-type parameters
+    };
+    using Bindings = std::map<std::string, Binding>;
+
+    std::string source_;
+    TypeParser parse_type_;
+    InstantiationCache* cache_;
+
+    /**
+     * A `template<...>` declaration: a class template, one of its members
+     * defined out of line, or a function template
+     */
+    struct TemplateDeclaration {
+        size_t begin = 0;                // The `template` keyword
+        size_t code = 0;                 // Past the template headers
+        size_t end = 0;
+        std::string owner;               // Class template it belongs to, if any
+    };
+
+    /**
+     * Instantiations nesting deeper than this are not followed
+     * (Wrapper<T> naming Wrapper<Wrapper<T>> instantiates forever)
+     */
+    static const int MAX_INSTANTIATION_DEPTH = 64;
+
+    /**
+     * Lazy instantiation: code outside template declarations is always
+     * compiled, so the Buffer<int, 4> it names are instantiated. The body
+     * of each instantiation, with its arguments substituted, names further
+     * instantiations (Stack<int> holding a Buffer<T, 16> instantiates
+     * Buffer<int, 16>). A template only named inside uninstantiated
+     * templates is not instantiated at all.
+     */
+    void collectInstantiations(IR& ir) {
+        std::map<std::string, ClassDecl*> templates;
+        for (auto& class_decl : ir.getClasses()) {
+            if (class_decl.is_template) {
+                templates.emplace(class_decl.name, &class_decl);
+            }
+        }
+        if (templates.empty()) return;
+
+        std::map<std::string, std::vector<std::string>> bodies;
+        std::string roots;
+        size_t copied = 0;
+        for (const auto& declaration : findTemplateDeclarations(templates)) {
+            if (declaration.owner.empty()) continue;  // Function templates: their own parameters stay unbound
+            roots += source_.substr(copied, declaration.begin - copied) + "\n";
+            copied = declaration.end;
+            bodies[declaration.owner].push_back(source_.substr(declaration.code, declaration.end - declaration.code));
+        }
+        roots += source_.substr(copied);
+
+        std::string names;
+        for (const auto& entry : templates) {
+            names += (names.empty() ? "" : "|") + entry.first;
+        }
+        std::regex use_pattern("\\b(" + names + ")\\s*<");
+
+        struct Pending {
+            ClassDecl* class_decl;
+            size_t index;
+            int depth;
+        };
+        std::vector<Pending> pending;
+        auto scan = [&](const std::string& text, int depth) {
+            std::vector<std::string> unbound = headerParameters(text);
+            for (auto it = std::sregex_iterator(text.begin(), text.end(), use_pattern);
+                 it != std::sregex_iterator(); ++it) {
+                ClassDecl& class_decl = *templates.at((*it)[1].str());
+                size_t open = it->position(0) + it->length(0) - 1;
+                size_t close = findTemplateClosing(text, open);
+                if (close == std::string::npos) continue;
+
+                std::vector<std::string> args =
+                    splitArguments(text.substr(open + 1, close - open - 1), "<([", ">)]");
+                if (mentionsAny(args, unbound) || args.size() > class_decl.template_parameters.size()) {
+                    continue;
+                }
+                if (addInstantiation(class_decl, args)) {
+                    pending.push_back({&class_decl, class_decl.instantiations.size() - 1, depth + 1});
+                }
+            }
+        };
+
+        scan(roots, 0);
+        for (size_t next = 0; next < pending.size(); ++next) {
+            Pending reached = pending[next];
+            if (reached.depth > MAX_INSTANTIATION_DEPTH) continue;
+
+            const ClassDecl& class_decl = *reached.class_decl;
+            const auto& arguments = class_decl.instantiations[reached.index].arguments;
+            for (std::string body : bodies[class_decl.name]) {
+                for (size_t i = 0; i < arguments.size(); ++i) {
+                    body = std::regex_replace(body, std::regex("\\b" + class_decl.template_parameters[i].name + "\\b"),
+                                              arguments[i]);
+                }
+                scan(body, reached.depth);
+            }
+        }
+    }
+
+    /**
+     * Missing arguments take the parameter defaults
+     * @return false if the instantiation is incomplete or already known
+     */
+    bool addInstantiation(ClassDecl& class_decl, const std::vector<std::string>& args) {
+        ClassDecl::Instantiation instantiation;
+        for (size_t i = 0; i < class_decl.template_parameters.size(); ++i) {
+            const auto& param = class_decl.template_parameters[i];
+            std::string arg = canonical(i < args.size() ? args[i] : param.default_value);
+            if (arg.empty() || param.kind == TemplateParameter::Template) {
+                return false;
+            }
+            instantiation.arguments.push_back(arg);
+        }
+
+        for (const auto& existing : class_decl.instantiations) {
+            if (existing.arguments == instantiation.arguments) return false;
+        }
+        for (size_t i = 0; i < instantiation.arguments.size(); ++i) {
+            instantiation.types.push_back(class_decl.template_parameters[i].kind == TemplateParameter::Type
+                                              ? parse_type_(instantiation.arguments[i])
+                                              : nullptr);
+        }
+        class_decl.instantiations.push_back(instantiation);
+        return true;
+    }
+
+    /**
+     * Every `template<...>` declaration at namespace or class scope, with
+     * the class template it belongs to: `template<typename T> class Stack`,
+     * `template<> struct Stack<bool>` and `int Stack<T>::size() const`
+     * belong to Stack
+     */
+    std::vector<TemplateDeclaration> findTemplateDeclarations(const std::map<std::string, ClassDecl*>& templates) {
+        static const std::regex header(R"(\btemplate\s*<)");
+        static const std::regex class_head(R"(^\s*(?:class|struct|union)\s+(?:alignas\s*\([^)]*\)\s*)?(\w+))");
+        static const std::regex qualified(R"(\b(\w+)\s*<[^<>;{]*(?:<[^<>;{]*>[^<>;{]*)*>\s*::)");
+        std::vector<TemplateDeclaration> declarations;
+        std::smatch match;
+        size_t search = 0;
+
+        while (search < source_.size() && std::regex_search(source_.cbegin() + search, source_.cend(), match, header)) {
+            TemplateDeclaration declaration;
+            declaration.begin = search + match.position(0);
+            size_t close = closeHeader(source_, search + match.position(0) + match.length(0) - 1);
+            if (close == std::string::npos) {
+                search = declaration.begin + match.length(0);
+                continue;
+            }
+
+            // template<typename T> template<typename U> void Stack<T>::push(U)
+            size_t next = source_.find_first_not_of(" \t\r\n", close + 1);
+            while (next != std::string::npos &&
+                   std::regex_search(source_.cbegin() + next, source_.cend(), match, header,
+                                     std::regex_constants::match_continuous)) {
+                close = closeHeader(source_, next + match.length(0) - 1);
+                if (close == std::string::npos) break;
+                next = source_.find_first_not_of(" \t\r\n", close + 1);
+            }
+            if (close == std::string::npos) {
+                search = declaration.begin + 1;
+                continue;
+            }
+            declaration.code = close + 1;
+
+            size_t stop = declaration.code;
+            int parens = 0;
+            while (stop < source_.size() && !(parens == 0 && (source_[stop] == '{' || source_[stop] == ';'))) {
+                if (source_[stop] == '(') parens++;
+                else if (source_[stop] == ')') parens--;
+                stop++;
+            }
+            declaration.end = stop + 1;
+            if (stop < source_.size() && source_[stop] == '{') {
+                size_t brace_end = findClosing(source_, stop, '{', '}');
+                declaration.end = brace_end == std::string::npos ? source_.size() : brace_end + 1;
+            }
+            declaration.end = std::min(declaration.end, source_.size());
+
+            std::string head = source_.substr(declaration.code, std::min(stop, source_.size()) - declaration.code);
+            if (std::regex_search(head, match, class_head) && templates.count(match[1].str())) {
+                declaration.owner = match[1].str();
+            } else {
+                for (auto it = std::sregex_iterator(head.begin(), head.end(), qualified);
+                     it != std::sregex_iterator(); ++it) {
+                    if (templates.count((*it)[1].str())) declaration.owner = (*it)[1].str();
+                }
+            }
+
+            declarations.push_back(declaration);
+            search = declaration.end;
+        }
+        return declarations;
+    }
+
+    /**
+     * Closing `>` of a template header; parentheses hide comparisons
+     * (template<bool B = (1 > 0)>)
+     */
+    static size_t closeHeader(const std::string& text, size_t open) {
+        int depth = 0;
+        int parens = 0;
+        for (size_t i = open; i < text.size(); ++i) {
+            char c = text[i];
+            if (c == '(') parens++;
+            else if (c == ')') parens--;
+            else if (parens > 0) continue;
+            else if (c == '<') depth++;
+            else if (c == '>' && --depth == 0) return i;
+            else if (c == ';' || c == '{') return std::string::npos;
+        }
+        return std::string::npos;
+    }
+
+    /**
+     * Names declared by the template headers in text: T and N of
+     * template<typename T, int N = 4>
+     */
+    static std::vector<std::string> headerParameters(const std::string& text) {
+        static const std::regex header(R"(\btemplate\s*<)");
+        static const std::regex trailing_name(R"((\w+)\s*(?:\.\.\.)?\s*$)");
+        std::vector<std::string> names;
+        for (auto it = std::sregex_iterator(text.begin(), text.end(), header); it != std::sregex_iterator(); ++it) {
+            size_t open = it->position(0) + it->length(0) - 1;
+            size_t close = closeHeader(text, open);
+            if (close == std::string::npos) continue;
+
+            for (std::string param : splitArguments(text.substr(open + 1, close - open - 1), "<([", ">)]")) {
+                size_t equals = param.find('=');
+                if (equals != std::string::npos) param = param.substr(0, equals);
+                std::smatch match;
+                if (std::regex_search(param, match, trailing_name) && match[1] != "typename" && match[1] != "class") {
+                    names.push_back(match[1].str());
+                }
+            }
+        }
+        return names;
+    }
+
+    /**
+     * Argument spelling with whitespace only between words:
+     * std::pair< int,int > and std::pair<int, int> are the same instantiation
+     */
+    static std::string canonical(const std::string& text) {
+        std::string result;
+        bool space = false;
+        for (char c : text) {
+            if (std::isspace(static_cast<unsigned char>(c))) {
+                space = true;
+                continue;
+            }
+            if (space && !result.empty() && isWordChar(result.back()) && isWordChar(c)) {
+                result += ' ';
+            }
+            result += c;
+            space = false;
+        }
+        return result;
+    }
+
+    static bool isWordChar(char c) {
+        return std::isalnum(static_cast<unsigned char>(c)) || c == '_';
+    }
+
+    static bool mentionsAny(const std::vector<std::string>& args, const std::vector<std::string>& names) {
+        for (const auto& arg : args) {
+            for (const auto& name : names) {
+                if (std::regex_search(arg, std::regex("\\b" + name + "\\b"))) return true;
+            }
+        }
+        return false;
+    }
+
+    /**
+     * if constexpr (A) { ... } else if constexpr (B) { ... } else { ... }
+     */
+    void detectChains(Function& func) {
+        const std::string& body = func.body;
+        std::regex if_pattern(R"(\bif\s+constexpr\s*\()");
+        std::smatch match;
+        size_t search = 0;
+
+        while (search < body.size() &&
+               std::regex_search(body.cbegin() + search, body.cend(), match, if_pattern)) {
+            size_t pos = search + match.position(0);
+
+            ConstexprIfInfo chain;
+            size_t end = parseChain(body, pos, chain);
+            if (end == std::string::npos) {
+                search = pos + match.length(0);  // Unbraced branches are left as written
+                continue;
+            }
+
+            chain.source_text = body.substr(pos, end - pos);
+            func.constexpr_ifs.push_back(chain);
+            search = end;  // Nested chains are resolved with their branch
+        }
+    }
+
+    /**
+     * @return Index just past the chain, or npos if a branch is not braced
+     */
+    size_t parseChain(const std::string& body, size_t pos, ConstexprIfInfo& chain) {
+        static const std::regex else_if(R"(^if\s+constexpr\s*\()");
+        size_t i = pos;
+
+        while (true) {
+            size_t open = body.find('(', i);
+            size_t close = findClosing(body, open, '(', ')');
+            if (close == std::string::npos) return std::string::npos;
+
+            size_t brace = body.find_first_not_of(" \t\r\n", close + 1);
+            if (brace == std::string::npos || body[brace] != '{') return std::string::npos;
+            size_t brace_end = findClosing(body, brace, '{', '}');
+            if (brace_end == std::string::npos) return std::string::npos;
+
+            chain.branches.push_back({trim(body.substr(open + 1, close - open - 1)),
+                                      body.substr(brace + 1, brace_end - brace - 1)});
+
+            size_t next = body.find_first_not_of(" \t\r\n", brace_end + 1);
+            if (next == std::string::npos || body.compare(next, 4, "else") != 0 ||
+                (next + 4 < body.size() && (std::isalnum(static_cast<unsigned char>(body[next + 4])) ||
+                                            body[next + 4] == '_'))) {
+                return brace_end + 1;
+            }
+
+            size_t after_else = body.find_first_not_of(" \t\r\n", next + 4);
+            if (after_else == std::string::npos) return std::string::npos;
+            if (std::regex_search(body.cbegin() + after_else, body.cend(), else_if)) {
+                i = after_else;
+                continue;
+            }
+            if (body[after_else] != '{') return std::string::npos;  // else if (runtime) ...
+
+            size_t else_end = findClosing(body, after_else, '{', '}');
+            if (else_end == std::string::npos) return std::string::npos;
+            chain.branches.push_back({"", body.substr(after_else + 1, else_end - after_else - 1)});
+            return else_end + 1;
+        }
+    }
+
+    /**
+     * Select a branch per instantiation (once for a non-template class)
+     */
+    void resolve(const ClassDecl& class_decl, const Function& method, ConstexprIfInfo& chain) {
+        // Member templates are instantiated per call, not per class
+        for (const auto& param : method.template_parameters) {
+            for (const auto& branch : chain.branches) {
+                if (std::regex_search(branch.condition, std::regex("\\b" + param.name + "\\b"))) {
+                    chain.unresolved_reason = "depends on template parameter '" + param.name +
+                                              "' of '" + method.name + "'";
+                    chain.selected.assign(std::max<size_t>(class_decl.instantiations.size(), 1),
+                                          ConstexprIfInfo::UNRESOLVED);
+                    return;
+                }
+            }
+        }
+
+        if (!class_decl.is_template) {
+            chain.selected.push_back(select(chain, Bindings(), chain.unresolved_reason));
+            return;
+        }
+        if (class_decl.instantiations.empty()) {
+            chain.unresolved_reason = "'" + class_decl.name + "' is not instantiated in the input";
+        }
+
+        // Cached per (template, arguments); the parameter names and conditions tell templates apart
+        std::string chain_key;
+        for (const auto& param : class_decl.template_parameters) {
+            chain_key += param.name + ",";
+        }
+        for (const auto& branch : chain.branches) {
+            chain_key += "\n" + branch.condition;
+        }
+
+        for (const auto& instantiation : class_decl.instantiations) {
+            std::string key = class_decl.name + "<";
+            for (size_t i = 0; i < instantiation.arguments.size(); ++i) {
+                key += (i > 0 ? "," : "") + instantiation.arguments[i];
+            }
+            key += ">";
+
+            InstantiationCache::Selection selection;
+            if (!cache_ || !cache_->lookup(key, chain_key, selection)) {
+                Bindings bindings;
+                for (size_t i = 0; i < instantiation.arguments.size(); ++i) {
+                    bindings[class_decl.template_parameters[i].name] =
+                        {instantiation.arguments[i], instantiation.types[i]};
+                }
+                selection.branch = select(chain, bindings, selection.unresolved_reason);
+                if (cache_) cache_->store(key, chain_key, selection);
+            }
+
+            if (!selection.unresolved_reason.empty()) chain.unresolved_reason = selection.unresolved_reason;
+            chain.selected.push_back(selection.branch);
+        }
+    }
+
+    /**
+     * First branch whose condition holds under the bindings
+     * @param reason Set when a condition cannot be evaluated
+     */
+    int select(const ConstexprIfInfo& chain, const Bindings& bindings, std::string& reason) {
+        for (size_t b = 0; b < chain.branches.size(); ++b) {
+            const std::string& condition = chain.branches[b].condition;
+            long long value = 1;
+            if (!condition.empty() && !evaluate(condition, bindings, value, reason)) {
+                return ConstexprIfInfo::UNRESOLVED;
+            }
+            if (value != 0) {
+                return static_cast<int>(b);
+            }
+        }
+        return -1;
+    }
+
+    /**
+     * Evaluate a constant condition: type traits, sizeof, non-type
+     * parameters, integer literals and the usual operators
+     */
+    bool evaluate(std::string expr, const Bindings& bindings, long long& value, std::string& reason) {
+        // std::is_integral_v<T>, std::is_same<T, U>::value
+        std::regex trait_pattern(
+            R"(std::(is_\w+?)(?:_v\s*<((?:[^<>]|<[^<>]*>)*)>|\s*<((?:[^<>]|<[^<>]*>)*)>\s*::\s*value))");
+        std::smatch match;
+        while (std::regex_search(expr, match, trait_pattern)) {
+            std::string args_text = match[2].matched ? match[2].str() : match[3].str();
+            std::vector<std::shared_ptr<Type>> operands;
+            for (const auto& arg : splitArguments(args_text, "<([", ">)]")) {
+                operands.push_back(resolveType(arg, bindings));
+            }
+
+            bool result = false;
+            if (!evaluateTrait(match[1].str(), operands, result)) {
+                reason = "unsupported type trait 'std::" + match[1].str() + "'";
+                return false;
+            }
+            expr.replace(match.position(0), match.length(0), result ? "1" : "0");
+        }
+
+        // sizeof(T)
+        std::regex sizeof_pattern(R"(\bsizeof\s*\(([^()]*)\))");
+        while (std::regex_search(expr, match, sizeof_pattern)) {
+            size_t size = sizeOf(resolveType(trim(match[1].str()), bindings));
+            if (size == 0) {
+                reason = "size of '" + trim(match[1].str()) + "' is not known";
+                return false;
+            }
+            expr.replace(match.position(0), match.length(0), std::to_string(size));
+        }
+
+        // Non-type parameters and boolean literals
+        for (const auto& entry : bindings) {
+            if (!entry.second.type) {
+                expr = std::regex_replace(expr, std::regex("\\b" + entry.first + "\\b"),
+                                          "(" + entry.second.argument + ")");
+            }
+        }
+        expr = std::regex_replace(expr, std::regex(R"(\btrue\b)"), "1");
+        expr = std::regex_replace(expr, std::regex(R"(\bfalse\b)"), "0");
+
+        std::smatch unknown;
+        if (std::regex_search(expr, unknown, std::regex(R"([A-Za-z_][\w:]*)")) &&
+            !std::regex_match(unknown.str(), std::regex(R"([uUlL]+)"))) {
+            reason = "'" + unknown.str() + "' is not a constant";
+            return false;
+        }
+
+        ExpressionParser parser(expr);
+        if (!parser.parse(value)) {
+            reason = "cannot evaluate '" + expr + "'";
+            return false;
+        }
+        return true;
+    }
+
+    std::shared_ptr<Type> resolveType(const std::string& spelling, const Bindings& bindings) {
+        auto bound = bindings.find(spelling);
+        if (bound != bindings.end() && bound->second.type) {
+            return bound->second.type;
+        }
+        return parse_type_(spelling);
+    }
+
+    static bool isUnsignedName(const std::string& name) {
+        return name.compare(0, 8, "unsigned") == 0 || name.compare(0, 4, "uint") == 0 ||
+               name == "size_t" || name == "std::size_t";
+    }
+
+    static bool evaluateTrait(const std::string& trait, const std::vector<std::shared_ptr<Type>>& operands,
+                              bool& result) {
+        if (trait == "is_same") {
+            if (operands.size() != 2 || !operands[0] || !operands[1]) return false;
+            result = sameType(operands[0], operands[1]);
+            return true;
+        }
+        if (operands.size() != 1 || !operands[0]) return false;
+
+        const Type& type = *operands[0];
+        bool integral = type.kind == TypeKind::Integer || type.kind == TypeKind::Bool;
+        bool floating = type.kind == TypeKind::Float;
+        bool pointer = type.kind == TypeKind::Pointer && type.name.find("_ptr<") == std::string::npos;
+        bool reference = type.kind == TypeKind::Reference || type.kind == TypeKind::RValueReference;
+        bool scalar_like = integral || floating || pointer || reference || type.kind == TypeKind::Void ||
+                           type.kind == TypeKind::Enum || type.kind == TypeKind::Array;
+
+        if (trait == "is_integral") result = integral;
+        else if (trait == "is_floating_point") result = floating;
+        else if (trait == "is_arithmetic") result = integral || floating;
+        else if (trait == "is_fundamental") result = integral || floating || type.kind == TypeKind::Void;
+        else if (trait == "is_signed") result = floating || (type.kind == TypeKind::Integer && !isUnsignedName(type.name));
+        else if (trait == "is_unsigned") result = type.kind == TypeKind::Bool ||
+                                                  (type.kind == TypeKind::Integer && isUnsignedName(type.name));
+        else if (trait == "is_pointer") result = pointer;
+        else if (trait == "is_reference") result = reference;
+        else if (trait == "is_lvalue_reference") result = type.kind == TypeKind::Reference;
+        else if (trait == "is_void") result = type.kind == TypeKind::Void;
+        else if (trait == "is_enum") result = type.kind == TypeKind::Enum;
+        else if (trait == "is_array") result = type.kind == TypeKind::Array;
+        else if (trait == "is_class") result = !scalar_like;
+        else if (trait == "is_const") result = type.is_const;
+        else return false;
+        return true;
+    }
+
+    static bool sameType(const std::shared_ptr<Type>& a, const std::shared_ptr<Type>& b) {
+        if (!a || !b) return a == b;
+        return a->kind == b->kind && a->name == b->name && a->is_const == b->is_const &&
+               sameType(a->element_type, b->element_type);
+    }
+
+    /**
+     * sizeof for fundamental types on LP64 (0 if unknown)
+     */
+    static size_t sizeOf(const std::shared_ptr<Type>& type) {
+        if (!type) return 0;
+        if (type->kind == TypeKind::Pointer || type->kind == TypeKind::Reference) return 8;
+        if (type->kind == TypeKind::Bool) return 1;
+        if (type->kind == TypeKind::Float) return type->name == "float" ? 4 : 8;
+        if (type->kind != TypeKind::Integer) return 0;
+
+        const std::string& name = type->name;
+        if (name.find("char") != std::string::npos || name.find("int8") != std::string::npos) return 1;
+        if (name.find("short") != std::string::npos || name.find("int16") != std::string::npos) return 2;
+        if (name.find("long") != std::string::npos || name.find("int64") != std::string::npos ||
+            name.find("size_t") != std::string::npos) {
+            return 8;
+        }
+        return 4;
+    }
+
+    /**
+     * Recursive descent over C++ operator precedence
+     */
+    class ExpressionParser {
+    public:
+        explicit ExpressionParser(const std::string& text) : text_(text) {}
+
+        bool parse(long long& value) {
+            bool ok = ternary(value);
+            skipSpace();
+            return ok && pos_ == text_.size();
+        }
+
+    private:
+        std::string text_;
+        size_t pos_ = 0;
+
+        void skipSpace() {
+            while (pos_ < text_.size() && std::isspace(static_cast<unsigned char>(text_[pos_]))) ++pos_;
+        }
+
+        bool accept(const std::string& op) {
+            skipSpace();
+            if (text_.compare(pos_, op.size(), op) != 0) return false;
+            // `<` is not the start of `<<` or `<=`, `&` not of `&&`, ...
+            if (op.size() == 1 && pos_ + 1 < text_.size() &&
+                (text_[pos_ + 1] == op[0] || (text_[pos_ + 1] == '=' && std::string("<>!=").find(op[0]) != std::string::npos))) {
+                return false;
+            }
+            pos_ += op.size();
+            return true;
+        }
+
+        bool ternary(long long& value) {
+            if (!binary(0, value)) return false;
+            if (!accept("?")) return true;
+            long long a = 0, b = 0;
+            if (!ternary(a) || !accept(":") || !ternary(b)) return false;
+            value = value ? a : b;
+            return true;
+        }
+
+        bool binary(size_t level, long long& value) {
+            static const std::vector<std::vector<std::string>> levels = {
+                {"||"}, {"&&"}, {"|"}, {"^"}, {"&"}, {"==", "!="}, {"<=", ">=", "<", ">"},
+                {"<<", ">>"}, {"+", "-"}, {"*", "/", "%"}};
+            if (level == levels.size()) return unary(value);
+            if (!binary(level + 1, value)) return false;
+
+            while (true) {
+                std::string matched;
+                for (const auto& op : levels[level]) {
+                    if (accept(op)) {
+                        matched = op;
+                        break;
+                    }
+                }
+                if (matched.empty()) return true;
+
+                long long rhs = 0;
+                if (!binary(level + 1, rhs)) return false;
+                if ((matched == "/" || matched == "%") && rhs == 0) return false;
+                value = matched == "||" ? (value || rhs) : matched == "&&" ? (value && rhs)
+                      : matched == "|" ? (value | rhs) : matched == "^" ? (value ^ rhs)
+                      : matched == "&" ? (value & rhs) : matched == "==" ? (value == rhs)
+                      : matched == "!=" ? (value != rhs) : matched == "<=" ? (value <= rhs)
+                      : matched == ">=" ? (value >= rhs) : matched == "<" ? (value < rhs)
+                      : matched == ">" ? (value > rhs) : matched == "<<" ? (value << rhs)
+                      : matched == ">>" ? (value >> rhs) : matched == "+" ? (value + rhs)
+                      : matched == "-" ? (value - rhs) : matched == "*" ? (value * rhs)
+                      : matched == "/" ? (value / rhs) : (value % rhs);
+            }
+        }
+
+        bool unary(long long& value) {
+            if (accept("!")) {
+                if (!unary(value)) return false;
+                value = !value;
+                return true;
+            }
+            if (accept("-")) {
+                if (!unary(value)) return false;
+                value = -value;
+                return true;
+            }
+            if (accept("~")) {
+                if (!unary(value)) return false;
+                value = ~value;
+                return true;
+            }
+            if (accept("(")) {
+                return ternary(value) && accept(")");
+            }
+
+            skipSpace();
+            size_t start = pos_;
+            while (pos_ < text_.size() && std::isalnum(static_cast<unsigned char>(text_[pos_]))) ++pos_;
+            std::string literal = text_.substr(start, pos_ - start);
+            literal.erase(literal.find_last_not_of("uUlL") + 1);
+            if (literal.empty() || !std::isdigit(static_cast<unsigned char>(literal[0]))) return false;
+            try {
+                value = std::stoll(literal, nullptr, 0);
+            } catch (const std::exception&) {
+                return false;
+            }
+            return true;
+        }
+    };
+
+    /**
+     * Index of the '>' closing the template argument list at open; npos
+     * when the '<' turns out to be a comparison
+     */
+    static size_t findTemplateClosing(const std::string& text, size_t open) {
+        size_t close = findClosing(text, open, '<', '>');
+        if (close == std::string::npos || text.find_first_of(";{)", open) < close) return std::string::npos;
+        return close;
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,313 @@
+/**
+ * Construction Analyzer
+ * Finds constructors that can fail after some members are built and
+ * prepares for conversion to fn try_new() -> Result<Self, _>
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <set>
+
+namespace hybrid {
+
+/**
+ * Construction Analyzer
+ * Whole-program pass: a constructor also fails when the constructor of a
+ * member declared in another class does, so fallibility is propagated
+ * through member types until nothing changes
+ */
+class ConstructionAnalyzer {
+public:
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                if (isConstructor(method)) detectThrows(class_decl, method);
+            }
+        }
+
+        // A member of a class with a fallible constructor makes its owner fallible
+        bool changed = true;
+        while (changed) {
+            changed = false;
+            for (auto& class_decl : ir.getClasses()) {
+                for (auto& method : class_decl.methods) {
+                    if (isConstructor(method) && detectFallibleMembers(ir, class_decl, method)) {
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                if (!method.is_fallible_constructor) continue;
+                detectHazards(class_decl, method);
+                for (const auto& entry : memberInitializers(method)) {
+                    method.initializer_arguments[entry.first] = entry.second;
+                }
+            }
+        }
+    }
+
+private:
+    /**
+     * Copy constructors become Clone and move constructors disappear
+     */
+    static bool isConstructor(const Function& func) {
+        return func.is_constructor && !func.is_copy_constructor && !func.is_move_constructor &&
+               !func.is_deleted && !func.is_defaulted;
+    }
+
+    /**
+     * throw T(args); and throw T{args}; outside try blocks, which may catch them
+     */
+    void detectThrows(const ClassDecl& class_decl, Function& func) {
+        std::vector<std::pair<size_t, size_t>> tries = tryBlocks(func.body);
+        static const std::regex throw_pattern(
+            R"(\bthrow\s+((?:\w+::)*\w+)\s*(?:\(([^;]*)\)|\{([^;]*)\})\s*;)");
+
+        for (auto it = std::sregex_iterator(func.body.begin(), func.body.end(), throw_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            bool caught = std::any_of(tries.begin(), tries.end(), [pos](const std::pair<size_t, size_t>& block) {
+                return pos > block.first && pos < block.second;
+            });
+            if (caught) continue;
+
+            ConstructionSiteInfo site;
+            site.kind = ConstructionSiteInfo::Throw;
+            site.source_text = it->str();
+            site.exception_type = (*it)[1].str();
+            site.arguments = trim((*it)[2].matched ? (*it)[2].str() : (*it)[3].str());
+            site.initialized = initializedBefore(class_decl, func, pos);
+            site.leaked = leakedOf(class_decl, site.initialized);
+            func.construction_sites.push_back(site);
+            func.is_fallible_constructor = true;
+        }
+    }
+
+    /**
+     * Members whose class has a fallible constructor: initialized in the
+     * list, or default-constructed by a fallible default constructor
+     */
+    bool detectFallibleMembers(const IR& ir, const ClassDecl& class_decl, Function& func) {
+        bool added = false;
+        std::vector<std::pair<std::string, std::string>> initializers = memberInitializers(func);
+
+        std::vector<std::string> initialized;
+        for (const auto& field : class_decl.fields) {
+            if (field.is_static) continue;
+            auto init = std::find_if(initializers.begin(), initializers.end(),
+                                     [&field](const std::pair<std::string, std::string>& entry) {
+                                         return entry.first == field.name;
+                                     });
+            if (!field.type || field.type->kind != TypeKind::Class) {
+                // Scalars without an initializer hold garbage, nothing to release
+                if (init != initializers.end() || !field.initializer.empty()) initialized.push_back(field.name);
+                continue;
+            }
+            const ClassDecl* member_class = ir.findClass(field.type->name);
+            bool fallible = member_class && std::any_of(
+                member_class->methods.begin(), member_class->methods.end(), [&init, &initializers](const Function& m) {
+                    return m.is_fallible_constructor && (init != initializers.end() || m.parameters.empty());
+                });
+            bool recorded = std::any_of(func.construction_sites.begin(), func.construction_sites.end(),
+                                        [&field](const ConstructionSiteInfo& site) {
+                                            return site.kind == ConstructionSiteInfo::FallibleMember &&
+                                                   site.member == field.name;
+                                        });
+            if (fallible && !recorded) {
+                ConstructionSiteInfo site;
+                site.kind = ConstructionSiteInfo::FallibleMember;
+                site.member = field.name;
+                site.exception_type = field.type->name;
+                site.source_text = init != initializers.end() ? field.name + "(" + init->second + ")" : field.name;
+                site.arguments = init != initializers.end() ? init->second : "";
+                site.initialized = initialized;
+                site.leaked = leakedOf(class_decl, initialized);
+                // Members are built before the body runs
+                func.construction_sites.insert(
+                    std::find_if(func.construction_sites.begin(), func.construction_sites.end(),
+                                 [](const ConstructionSiteInfo& s) { return s.kind == ConstructionSiteInfo::Throw; }),
+                    site);
+                func.is_fallible_constructor = true;
+                added = true;
+            }
+            initialized.push_back(field.name);
+        }
+        return added;
+    }
+
+    /**
+     * `this` handed out, or methods called on the partial object, before
+     * the last point where construction can fail
+     */
+    void detectHazards(const ClassDecl& class_decl, Function& func) {
+        size_t last_failure = 0;
+        for (const auto& site : func.construction_sites) {
+            if (site.kind != ConstructionSiteInfo::Throw) continue;
+            last_failure = std::max(last_failure, func.body.find(site.source_text));
+        }
+        if (last_failure == 0) return;
+
+        std::string body = func.body.substr(0, last_failure);
+        std::vector<std::pair<size_t, ConstructionSiteInfo>> found;
+
+        static const std::regex escape_pattern(R"([(,]\s*this\s*[,)]|=\s*this\s*;)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), escape_pattern);
+             it != std::sregex_iterator(); ++it) {
+            ConstructionSiteInfo site;
+            site.kind = ConstructionSiteInfo::ThisEscape;
+            site.source_text = statementAt(body, it->position(0));
+            found.emplace_back(it->position(0), site);
+        }
+
+        std::set<std::string> methods;
+        for (const auto& method : class_decl.methods) {
+            if (!method.is_static && !method.is_constructor && !method.is_destructor &&
+                method.name.compare(0, 8, "operator") != 0) {
+                methods.insert(method.name);
+            }
+        }
+        for (const auto& name : methods) {
+            std::regex call_pattern("\\b" + name + "\\s*\\(");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), call_pattern);
+                 it != std::sregex_iterator(); ++it) {
+                size_t pos = it->position(0);
+                bool own = pos == 0 || (pos >= 6 && body.compare(pos - 6, 6, "this->") == 0) ||
+                           (body[pos - 1] != '.' && body[pos - 1] != '>' && body[pos - 1] != ':');
+                if (!own) continue;
+                ConstructionSiteInfo site;
+                site.kind = ConstructionSiteInfo::MethodCall;
+                site.source_text = statementAt(body, pos);
+                site.member = name;
+                found.emplace_back(pos, site);
+            }
+        }
+
+        appendInSourceOrder(found, func.construction_sites);
+    }
+
+    /**
+     * [open brace, close brace] of each try block in a body
+     */
+    static std::vector<std::pair<size_t, size_t>> tryBlocks(const std::string& body) {
+        std::vector<std::pair<size_t, size_t>> blocks;
+        static const std::regex try_pattern(R"(\btry\s*\{)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), try_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t open = it->position(0) + it->length(0) - 1;
+            int depth = 0;
+            for (size_t i = open; i < body.size(); ++i) {
+                if (body[i] == '{') depth++;
+                if (body[i] == '}' && --depth == 0) {
+                    blocks.emplace_back(open, i);
+                    break;
+                }
+            }
+        }
+        return blocks;
+    }
+
+    /**
+     * name(args) and name{args} entries of the member initializer list
+     */
+    static std::vector<std::pair<std::string, std::string>> memberInitializers(const Function& func) {
+        std::vector<std::pair<std::string, std::string>> result;
+        const std::string& text = func.member_initializers;
+        size_t i = 0;
+        while (i < text.size()) {
+            size_t open = text.find_first_of("({", i);
+            if (open == std::string::npos) break;
+            std::string name = trim(text.substr(i, open - i));
+            int depth = 0;
+            size_t close = open;
+            for (; close < text.size(); ++close) {
+                if (text[close] == '(' || text[close] == '{') depth++;
+                if ((text[close] == ')' || text[close] == '}') && --depth == 0) break;
+            }
+            result.emplace_back(name, trim(text.substr(open + 1, close - open - 1)));
+            i = text.find(',', close);
+            if (i == std::string::npos) break;
+            i++;
+        }
+        return result;
+    }
+
+    /**
+     * Fields set before a body position: the initializer list and default
+     * member initializers (in declaration order), then body assignments
+     */
+    static std::vector<std::string> initializedBefore(const ClassDecl& class_decl, const Function& func,
+                                                      size_t pos) {
+        std::vector<std::pair<std::string, std::string>> initializers = memberInitializers(func);
+        std::vector<std::string> result;
+        for (const auto& field : class_decl.fields) {
+            if (field.is_static) continue;
+            bool listed = std::any_of(initializers.begin(), initializers.end(),
+                                      [&field](const std::pair<std::string, std::string>& entry) {
+                                          return entry.first == field.name;
+                                      });
+            if (listed || !field.initializer.empty()) result.push_back(field.name);
+        }
+
+        std::string before = func.body.substr(0, pos);
+        std::vector<std::pair<size_t, std::string>> assigned;
+        for (const auto& field : class_decl.fields) {
+            if (field.is_static || std::find(result.begin(), result.end(), field.name) != result.end()) continue;
+            std::smatch match;
+            std::regex assign_pattern("(?:^|[^.>\\w])(?:this->)?" + field.name + "\\s*=[^=]");
+            if (std::regex_search(before, match, assign_pattern)) {
+                assigned.emplace_back(match.position(0), field.name);
+            }
+        }
+        std::sort(assigned.begin(), assigned.end());
+        for (const auto& entry : assigned) {
+            result.push_back(entry.second);
+        }
+        return result;
+    }
+
+    /**
+     * Initialized fields the destructor releases by hand: the destructor
+     * never runs for a partial object, so C++ leaks them
+     */
+    static std::vector<std::string> leakedOf(const ClassDecl& class_decl, const std::vector<std::string>& fields) {
+        std::vector<std::string> result;
+        auto destructor = std::find_if(class_decl.methods.begin(), class_decl.methods.end(),
+                                       [](const Function& m) { return m.is_destructor && !m.is_defaulted; });
+        if (destructor == class_decl.methods.end()) return result;
+
+        for (const auto& name : fields) {
+            std::regex release_pattern(
+                R"((?:\bdelete\s*(?:\[\s*\])?\s*|\b(?:free|close|fclose|closesocket|munmap|\w*[Rr]elease\w*|\w*[Dd]estroy\w*)\s*\(\s*)(?:this->)?)" +
+                name + "\\b");
+            if (std::regex_search(destructor->body, release_pattern)) {
+                result.push_back(name);
+            }
+        }
+        return result;
+    }
+
+    /**
+     * Statement around a position, without its terminator
+     */
+    static std::string statementAt(const std::string& body, size_t pos) {
+        size_t begin = body.find_last_of(";{}", pos);
+        begin = begin == std::string::npos ? 0 : begin + 1;
+        size_t end = body.find(';', pos);
+        if (end == std::string::npos) end = body.size();
+        return trim(body.substr(begin, end - begin));
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
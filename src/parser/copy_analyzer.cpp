@@ -0,0 +1,506 @@
+/**
+ * Copy Analyzer
+ * Decides Copy vs Clone semantics per type and finds implicit C++ copies
+ * that must become explicit clone() calls in Rust
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <unordered_map>
+
+namespace hybrid {
+
+/**
+ * Copy Analyzer
+ * Whole-program pass: class copy semantics depend on the semantics of their fields
+ */
+class CopyAnalyzer {
+public:
+    /**
+     * Analyze all classes and their methods
+     */
+    void analyze(IR& ir) {
+        auto& classes = ir.getClasses();
+
+        for (auto& class_decl : classes) {
+            detectCopyConstructor(class_decl);
+        }
+
+        // Start optimistic and downgrade until a fixed point is reached
+        for (auto& class_decl : classes) {
+            class_decl.copy_semantics = CopySemantics::Copy;
+        }
+
+        bool changed = true;
+        while (changed) {
+            changed = false;
+            for (auto& class_decl : classes) {
+                CopySemantics semantics = computeClassSemantics(ir, class_decl);
+                if (semantics != class_decl.copy_semantics) {
+                    class_decl.copy_semantics = semantics;
+                    changed = true;
+                }
+            }
+        }
+
+        // Find copy sites in method bodies
+        for (auto& class_decl : classes) {
+            for (auto& method : class_decl.methods) {
+                detectCopySites(ir, class_decl, method);
+            }
+        }
+    }
+
+    /**
+     * Copy semantics of a single type
+     */
+    static CopySemantics semanticsOf(const IR& ir, const std::shared_ptr<Type>& type) {
+        if (!type) return CopySemantics::Copy;
+
+        switch (type->kind) {
+            case TypeKind::Void:
+            case TypeKind::Bool:
+            case TypeKind::Integer:
+            case TypeKind::Float:
+            case TypeKind::Enum:
+                return CopySemantics::Copy;
+
+            case TypeKind::Pointer:
+                if (type->name.find("unique_ptr") != std::string::npos) {
+                    return CopySemantics::MoveOnly;
+                }
+                if (type->name.find("shared_ptr") != std::string::npos ||
+                    type->name.find("weak_ptr") != std::string::npos) {
+                    return CopySemantics::Clone;
+                }
+                if (type->owned_array) {
+                    return CopySemantics::Clone;  // Box<[T]>
+                }
+                return CopySemantics::Copy;  // Raw pointers are Copy
+
+            case TypeKind::Function:
+                return CopySemantics::Copy;  // fn pointers are Copy
+
+            case TypeKind::Reference:
+                // &T is Copy, &mut T is not
+                return type->is_const ? CopySemantics::Copy : CopySemantics::MoveOnly;
+
+            case TypeKind::RValueReference:
+            case TypeKind::Array:
+                return semanticsOf(ir, type->element_type);
+
+            case TypeKind::StdThread:
+            case TypeKind::StdMutex:
+            case TypeKind::StdRecursiveMutex:
+            case TypeKind::StdSharedMutex:
+            case TypeKind::StdConditionVariable:
+            case TypeKind::StdAtomic:
+            case TypeKind::StdLockGuard:
+            case TypeKind::StdUniqueLock:
+            case TypeKind::StdSharedLock:
+            case TypeKind::StdFuture:
+            case TypeKind::StdPromise:
+                return CopySemantics::MoveOnly;
+
+            case TypeKind::StdPair:
+            case TypeKind::StdTuple: {
+                // Rust tuples are Copy when every element is
+                CopySemantics result = CopySemantics::Copy;
+                for (const auto& arg : type->template_args) {
+                    CopySemantics element = semanticsOf(ir, arg);
+                    if (element == CopySemantics::MoveOnly) return element;
+                    if (element == CopySemantics::Clone) result = element;
+                }
+                return result;
+            }
+
+            case TypeKind::Struct:
+            case TypeKind::Class: {
+                const ClassDecl* class_decl = ir.findClass(type->name);
+                if (class_decl) {
+                    return class_decl->copy_semantics;
+                }
+                return semanticsOfLibraryType(type->name);
+            }
+
+            default: {
+                // STL containers: Clone unless an element is move-only
+                for (const auto& arg : type->template_args) {
+                    if (semanticsOf(ir, arg) == CopySemantics::MoveOnly) {
+                        return CopySemantics::MoveOnly;
+                    }
+                }
+                return CopySemantics::Clone;
+            }
+        }
+    }
+
+private:
+    /**
+     * Classify library types the simple parser leaves unresolved (by name)
+     */
+    static CopySemantics semanticsOfLibraryType(const std::string& name) {
+        static const std::vector<std::string> move_only = {
+            "unique_ptr", "thread", "mutex", "atomic", "condition_variable",
+            "future", "promise", "unique_lock", "lock_guard", "fstream", "scoped_ptr", "QScopedPointer"
+        };
+        for (const auto& marker : move_only) {
+            if (name.find(marker) != std::string::npos) {
+                return CopySemantics::MoveOnly;
+            }
+        }
+        return CopySemantics::Clone;
+    }
+
+    /**
+     * Detect copy constructor: ClassName(const ClassName& other)
+     */
+    void detectCopyConstructor(ClassDecl& class_decl) {
+        for (auto& method : class_decl.methods) {
+            if (!method.is_constructor || method.parameters.size() != 1) continue;
+
+            const auto& type = method.parameters[0].type;
+            if (type && type->kind == TypeKind::Reference &&
+                type->element_type && type->element_type->name == class_decl.name) {
+                method.is_copy_constructor = true;
+            }
+        }
+    }
+
+    /**
+     * Compute semantics of a class from its special members and fields
+     */
+    CopySemantics computeClassSemantics(const IR& ir, const ClassDecl& class_decl) {
+        bool trivially_copyable = true;
+
+        for (const auto& method : class_decl.methods) {
+            if (method.is_copy_constructor && method.is_deleted) {
+                return CopySemantics::MoveOnly;
+            }
+
+            // A user-provided destructor, copy or move constructor rules out Copy
+            if ((method.is_destructor || method.is_copy_constructor ||
+                 method.is_move_constructor) && !method.is_defaulted) {
+                trivially_copyable = false;
+            }
+        }
+
+        CopySemantics result = trivially_copyable ? CopySemantics::Copy : CopySemantics::Clone;
+
+        for (const auto& field : class_decl.fields) {
+            CopySemantics field_semantics = semanticsOf(ir, field.type);
+            if (field_semantics == CopySemantics::MoveOnly) {
+                // A user copy constructor can still make the class Clone
+                if (hasUserCopyConstructor(class_decl)) {
+                    result = CopySemantics::Clone;
+                    continue;
+                }
+                return CopySemantics::MoveOnly;
+            }
+            if (field_semantics == CopySemantics::Clone) {
+                result = CopySemantics::Clone;
+            }
+        }
+
+        return result;
+    }
+
+    bool hasUserCopyConstructor(const ClassDecl& class_decl) {
+        for (const auto& method : class_decl.methods) {
+            if (method.is_copy_constructor && !method.is_deleted && !method.is_defaulted) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /**
+     * Detect implicit copies: assignments and arguments passed by value
+     */
+    void detectCopySites(const IR& ir, const ClassDecl& class_decl, Function& func) {
+        detectStoredViews(ir, class_decl, func);
+        if (func.body.empty()) return;
+        const std::string& body = func.body;
+        std::vector<std::pair<size_t, CopyInfo>> found;
+
+        // Assignment / copy-initialization: target = source;
+        std::regex assign_pattern(
+            R"((?:^|[;{}\s])([a-zA-Z_][\w.\[\]]*(?:->\w+)*)\s*=\s*([a-zA-Z_]\w*(?:(?:\.|->)\w+)*)\s*;)"
+        );
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, assign_pattern)) {
+            std::string source = match[2].str();
+            std::string target = match[1].str();
+            if (target.back() == ']' && target.find('[') == std::string::npos) {
+                // `auto [a, b] = source;` is a structured binding (BindingAnalyzer)
+                search_start = match.suffix().first;
+                continue;
+            }
+            size_t statement_pos = match.position(1) + (search_start - body.cbegin());
+            size_t statement_end = (match.suffix().first - body.cbegin());
+
+            auto type = resolveType(ir, class_decl, func, source);
+            if (type && type->isBorrowedString()) {
+                // A view stored in a view borrows; a String copies the characters
+                auto target_type = resolveType(ir, class_decl, func, target);
+                bool view = target_type ? target_type->isBorrowedString()
+                                        : type->name.find("string_view") != std::string::npos;
+                std::string field = target.compare(0, 6, "this->") == 0 ? target.substr(6) : target;
+                if (view && isParameter(func, source) && findField(class_decl, field)) {
+                    func.stored_views[source] = field;
+                }
+                if (!view) {
+                    CopyInfo info;
+                    info.source_text = body.substr(statement_pos, statement_end - statement_pos);
+                    info.expression = source;
+                    info.expression_offset = info.source_text.rfind(source);
+                    info.to_owned = true;
+                    found.emplace_back(statement_pos, info);
+                }
+            } else if (type) {
+                CopyInfo info;
+                info.source_text = body.substr(statement_pos, statement_end - statement_pos);
+                info.expression = source;
+                info.expression_offset = info.source_text.rfind(source);
+                info.needs_clone = needsClone(ir, func, source, type, statement_end);
+                info.needs_deref = !info.needs_clone && type->kind == TypeKind::Reference &&
+                                   semanticsOf(ir, type->element_type) == CopySemantics::Copy;
+                found.emplace_back(statement_pos, info);
+            }
+
+            search_start = match.suffix().first;
+        }
+
+        // Arguments passed by value to methods of the same class
+        detectArgumentCopies(ir, class_decl, func, found);
+
+        appendInSourceOrder(found, func.copies);
+    }
+
+    /**
+     * Borrowed string parameters kept in view fields by the member initializer list
+     */
+    void detectStoredViews(const IR& ir, const ClassDecl& class_decl, Function& func) {
+        std::regex init_pattern(R"((\w+)\s*[({]\s*(\w+)\s*[)}])");
+        const std::string& inits = func.member_initializers;
+        for (auto it = std::sregex_iterator(inits.begin(), inits.end(), init_pattern);
+             it != std::sregex_iterator(); ++it) {
+            auto field_type = findField(class_decl, (*it)[1].str());
+            auto param_type = resolveType(ir, class_decl, func, (*it)[2].str());
+            if (field_type && field_type->isBorrowedString() && param_type && param_type->isBorrowedString() &&
+                isParameter(func, (*it)[2].str())) {
+                func.stored_views[(*it)[2].str()] = (*it)[1].str();
+            }
+        }
+    }
+
+    /**
+     * Arguments passed by value at calls of the class's own methods; the
+     * body is scanned once and each callee looked up by name
+     */
+    void detectArgumentCopies(const IR& ir, const ClassDecl& class_decl, Function& func,
+                              std::vector<std::pair<size_t, CopyInfo>>& found) {
+        std::unordered_map<std::string, std::vector<const Function*>> callees;
+        for (const auto& method : class_decl.methods) {
+            if (method.is_constructor || method.is_destructor) continue;
+            callees[method.name].push_back(&method);
+        }
+
+        const std::string& body = func.body;
+        static const std::regex call_pattern(R"((^|[^\w.>])([a-zA-Z_]\w*)\s*\(([^()]*)\))");
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, call_pattern)) {
+            auto overloads = callees.find(match[2].str());
+            if (overloads == callees.end()) {
+                search_start = match.suffix().first;
+                continue;
+            }
+
+            size_t call_pos = match.position(0) + match.length(1) + (search_start - body.cbegin());
+            size_t call_end = (match.suffix().first - body.cbegin());
+            std::string call_text = body.substr(call_pos, call_end - call_pos);
+
+            std::vector<std::string> args = splitArguments(match[3].str(), "(<{", ")>}");
+            const Function& callee = selectOverload(overloads->second, args.size());
+            size_t args_start = call_text.find('(') + 1;
+            size_t offset = args_start;
+            static const std::regex simple_expression(R"([a-zA-Z_]\w*(?:(?:\.|->)\w+)*)");
+
+            for (size_t i = 0; i < args.size() && i < callee.parameters.size(); ++i) {
+                std::string arg = trim(args[i]);
+                size_t arg_pos = call_text.find(arg, offset);
+                offset = (arg_pos == std::string::npos) ? offset : arg_pos + arg.size();
+
+                const auto& param_type = callee.parameters[i].type;
+                if (param_type && param_type->isBorrowedString() && arg_pos != std::string::npos &&
+                    std::regex_match(arg, simple_expression)) {
+                    // An owned String coerces to &str through a borrow
+                    auto type = resolveType(ir, class_decl, func, arg);
+                    if (type && isOwnedString(type)) {
+                        CopyInfo info;
+                        info.source_text = call_text;
+                        info.expression = arg;
+                        info.expression_offset = arg_pos;
+                        info.needs_borrow = true;
+                        found.emplace_back(call_pos, info);
+                    }
+                    continue;
+                }
+                if (!param_type || param_type->kind == TypeKind::Reference ||
+                    param_type->kind == TypeKind::Pointer ||
+                    param_type->kind == TypeKind::RValueReference) {
+                    continue;  // Not passed by value
+                }
+
+                if (!std::regex_match(arg, simple_expression)) {
+                    continue;  // Temporaries are moved, not copied
+                }
+
+                auto type = resolveType(ir, class_decl, func, arg);
+                if (!type || arg_pos == std::string::npos) continue;
+
+                CopyInfo info;
+                info.source_text = call_text;
+                info.expression = arg;
+                info.expression_offset = arg_pos;
+                info.to_owned = type->isBorrowedString();
+                info.needs_clone = !info.to_owned && needsClone(ir, func, arg, type, call_end);
+                info.needs_deref = !info.needs_clone && type->kind == TypeKind::Reference &&
+                                   semanticsOf(ir, type->element_type) == CopySemantics::Copy;
+                found.emplace_back(call_pos, info);
+            }
+
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * The overload taking as many arguments as the call passes, else the first
+     */
+    static const Function& selectOverload(const std::vector<const Function*>& overloads, size_t arg_count) {
+        for (const Function* overload : overloads) {
+            if (overload->parameters.size() == arg_count) return *overload;
+        }
+        return *overloads.front();
+    }
+
+    /**
+     * A copy needs clone() unless the type is Copy or the source is an owned
+     * value that is never used again (then Rust can simply move it)
+     */
+    bool needsClone(const IR& ir, const Function& func, const std::string& source,
+                    const std::shared_ptr<Type>& type, size_t end_pos) {
+        // Copying out of a reference copies the referenced value
+        auto value_type = type;
+        if (value_type->kind == TypeKind::Reference ||
+            value_type->kind == TypeKind::RValueReference) {
+            value_type = value_type->element_type;
+        }
+
+        if (semanticsOf(ir, value_type) != CopySemantics::Clone) {
+            return false;
+        }
+
+        for (const auto& param : func.parameters) {
+            if (param.name != source || !param.type) continue;
+
+            bool owned = param.type->kind != TypeKind::Reference &&
+                         param.type->kind != TypeKind::Pointer;
+            if (owned && !isUsedAfter(func.body, source, end_pos)) {
+                return false;  // Last use of a by-value parameter: move it
+            }
+        }
+
+        return true;
+    }
+
+    /**
+     * Resolve the type of a simple expression (parameter, field, this->field, param.field)
+     */
+    std::shared_ptr<Type> resolveType(const IR& ir, const ClassDecl& class_decl,
+                                      const Function& func, const std::string& expr) {
+        std::string root = expr;
+        std::string member;
+
+        size_t sep = expr.find_first_of(".-");
+        if (sep != std::string::npos) {
+            root = expr.substr(0, sep);
+            member = expr.substr(expr.find_first_not_of(".->", sep));
+            if (member.find_first_of(".-") != std::string::npos) {
+                return nullptr;  // Nested member access not resolved
+            }
+        }
+
+        if (root == "this") {
+            return findField(class_decl, member);
+        }
+
+        std::shared_ptr<Type> root_type;
+        for (const auto& param : func.parameters) {
+            if (param.name == root) root_type = param.type;
+        }
+        if (!root_type) {
+            root_type = findField(class_decl, root);
+        }
+        if (!root_type || member.empty()) {
+            return root_type;
+        }
+
+        // param.field: look through references to the class
+        auto base = root_type;
+        while (base && (base->kind == TypeKind::Reference ||
+                        base->kind == TypeKind::RValueReference ||
+                        base->kind == TypeKind::Pointer)) {
+            base = base->element_type;
+        }
+        if (!base) return nullptr;
+
+        const ClassDecl* owner = ir.findClass(base->name);
+        return owner ? findField(*owner, member) : nullptr;
+    }
+
+    static bool isOwnedString(const std::shared_ptr<Type>& type) {
+        return type->kind == TypeKind::StdString ||
+               (type->kind == TypeKind::Class && (type->name == "std::string" || type->name == "string"));
+    }
+
+    static bool isParameter(const Function& func, const std::string& name) {
+        return std::any_of(func.parameters.begin(), func.parameters.end(),
+                           [&name](const Parameter& param) { return param.name == name; });
+    }
+
+    std::shared_ptr<Type> findField(const ClassDecl& class_decl, const std::string& name) {
+        for (const auto& field : class_decl.fields) {
+            if (field.name == name) return field.type;
+        }
+        return nullptr;
+    }
+
+    bool isUsedAfter(const std::string& body, const std::string& name, size_t pos) {
+        if (pos >= body.size()) return false;
+        static const std::regex identifier_pattern(R"((^|[^\w.>])([a-zA-Z_]\w*))");
+        std::string rest = body.substr(pos);
+        for (auto it = std::sregex_iterator(rest.begin(), rest.end(), identifier_pattern);
+             it != std::sregex_iterator(); ++it) {
+            if ((*it)[2] == name) return true;
+        }
+        return false;
+    }
+
+    std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
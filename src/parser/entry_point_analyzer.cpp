@@ -0,0 +1,159 @@
+/**
+ * Entry Point Analyzer
+ * Finds what main does with its arguments and its exit status, and
+ * calls of exit() anywhere, so that main becomes fn main() -> ExitCode
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <cctype>
+#include <utility>
+
+namespace hybrid {
+
+/**
+ * Entry Point Analyzer
+ * Whole-program pass: exit() ends the program from any function
+ */
+class EntryPointAnalyzer {
+public:
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                if (method.retained_reason.empty()) analyzeFunction(method);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            if (func.retained_reason.empty()) analyzeFunction(func);
+        }
+    }
+
+private:
+    using Found = std::vector<std::pair<size_t, EntrySiteInfo>>;
+
+    void analyzeFunction(Function& func) {
+        Found found;
+        if (func.is_entry_point) {
+            findReturns(func.body, found);
+            if (func.parameters.size() >= 2) findArguments(func.body, func.parameters[1].name, found);
+        }
+        findExits(func.body, found);
+        // Option parsing loops found by the CommandLineAnalyzer: the sites in them are nested
+        for (const auto& command_line : func.command_lines) {
+            EntrySiteInfo site;
+            site.kind = EntrySiteInfo::CommandLine;
+            site.source_text = command_line.source_text;
+            site.expression = command_line.source_text;
+            found.emplace_back(func.body.find(command_line.source_text), site);
+        }
+
+        // In body order; a site inside another (return run(argv);) is nested in it
+        sortBySourcePosition(found);
+        auto next = found.cbegin();
+        func.entry_sites = nest(next, found.cend(), std::string::npos);
+    }
+
+    static std::vector<EntrySiteInfo> nest(Found::const_iterator& next, Found::const_iterator end, size_t limit) {
+        std::vector<EntrySiteInfo> sites;
+        while (next != end && next->first < limit) {
+            EntrySiteInfo site = next->second;
+            size_t site_end = next->first + site.source_text.size();
+            ++next;
+            site.nested = nest(next, end, site_end);
+            sites.push_back(site);
+        }
+        return sites;
+    }
+
+    // return in main is the exit status; in a lambda it is not
+    static void findReturns(const std::string& body, Found& found) {
+        static const std::regex return_pattern(R"(\breturn\b\s*([^;]*);)");
+        std::vector<std::pair<size_t, size_t>> lambdas = lambdaBodies(body);
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), return_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = static_cast<size_t>(it->position(0));
+            bool in_lambda = std::any_of(lambdas.begin(), lambdas.end(), [pos](const std::pair<size_t, size_t>& range) {
+                return pos > range.first && pos < range.second;
+            });
+            if (in_lambda) continue;
+            EntrySiteInfo site;
+            site.kind = EntrySiteInfo::Return;
+            site.source_text = it->str(0);
+            site.expression = trim((*it)[1].str());
+            found.emplace_back(pos, site);
+        }
+    }
+
+    static void findArguments(const std::string& body, const std::string& argv, Found& found) {
+        if (argv.empty()) return;
+        std::regex use_pattern("\\b" + argv + "\\b");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), use_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = static_cast<size_t>(it->position(0));
+            if (pos > 0 && (body[pos - 1] == '.' || (pos > 1 && body.compare(pos - 2, 2, "->") == 0))) continue;
+
+            EntrySiteInfo site;
+            site.kind = EntrySiteInfo::ArgumentUse;
+            site.source_text = argv;
+            size_t after = body.find_first_not_of(" \t\n\r", pos + argv.size());
+            if (after != std::string::npos && body[after] == '[') {
+                size_t close = findClosing(body, after, '[', ']');
+                if (close == std::string::npos) continue;
+                site.kind = EntrySiteInfo::Argument;
+                site.source_text = body.substr(pos, close + 1 - pos);
+                site.expression = trim(body.substr(after + 1, close - after - 1));
+            }
+            found.emplace_back(pos, site);
+        }
+    }
+
+    static void findExits(const std::string& body, Found& found) {
+        static const std::regex exit_pattern(R"((\bstd\s*::\s*|::\s*)?\b(?:exit|_Exit|quick_exit)\s*\()");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), exit_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = static_cast<size_t>(it->position(0));
+            // obj.exit(), Scope::exit() and declarations are not the library function
+            size_t end = pos;
+            while (end > 0 && std::isspace(static_cast<unsigned char>(body[end - 1]))) --end;
+            char before = end > 0 ? body[end - 1] : ' ';
+            bool attached = before == '.' || before == '>' || before == '_' ||
+                            std::isalnum(static_cast<unsigned char>(before)) || (!(*it)[1].matched && before == ':');
+            static const std::regex keyword(R"((?:return|else|do)$)");
+            if (attached && !std::regex_search(body.substr(0, end), keyword)) continue;
+
+            size_t open = pos + static_cast<size_t>(it->length(0)) - 1;
+            size_t close = findClosing(body, open, '(', ')');
+            if (close == std::string::npos) continue;
+            EntrySiteInfo site;
+            site.kind = EntrySiteInfo::Exit;
+            site.source_text = body.substr(pos, close + 1 - pos);
+            site.expression = trim(body.substr(open + 1, close - open - 1));
+            found.emplace_back(pos, site);
+        }
+    }
+
+    /**
+     * [captures](params) { ... }: ranges of lambda bodies
+     */
+    static std::vector<std::pair<size_t, size_t>> lambdaBodies(const std::string& body) {
+        static const std::regex lambda(R"(\]\s*(?:\([^()]*\))?\s*(?:mutable\s*)?(?:->\s*[\w:<>]+\s*)?\{)");
+        std::vector<std::pair<size_t, size_t>> ranges;
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), lambda); it != std::sregex_iterator(); ++it) {
+            size_t open = static_cast<size_t>(it->position(0) + it->length(0)) - 1;
+            size_t close = findClosing(body, open, '{', '}');
+            if (close != std::string::npos) ranges.emplace_back(open, close);
+        }
+        return ranges;
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
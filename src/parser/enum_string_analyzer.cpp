@@ -0,0 +1,220 @@
+/**
+ * Enum String Analyzer
+ * Finds hand-written conversions between enums and strings: a toString
+ * function switching over an enum, and a parse function comparing a
+ * string with literals. They become impl Display and impl FromStr, and
+ * the cases are checked against the enumerators
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+
+namespace hybrid {
+
+/**
+ * Enum String Analyzer
+ * Works on the source text: the functions are usually free functions,
+ * which the parser does not keep. Static member functions are found too
+ */
+class EnumStringAnalyzer {
+public:
+    explicit EnumStringAnalyzer(const std::string& source) : source_(source) {}
+
+    void analyze(IR& ir) {
+        if (ir.getEnums().empty()) return;
+
+        // const char* toString(Color c) {
+        static const std::regex to_string(
+            R"((?:^|[;{}\s])(?:(?:static|inline|constexpr)\s+)*(?:const\s+char\s*\*|(?:std::)?string(?:_view)?|QString)\s+(\w+)\s*\(\s*(?:const\s+)?([\w:]+)\s*&?\s*(\w+)\s*\)\s*(?:const\s*)?(?:noexcept\s*)?\{)");
+        // Color parseColor(const std::string& s) {
+        static const std::regex from_string(
+            R"((?:^|[;{}\s])(?:(?:static|inline|constexpr)\s+)*([\w:]+)\s+(\w+)\s*\(\s*(?:const\s+)?(?:(?:std::)?string(?:_view)?\s*&?|char\s*\*|QString\s*&?)\s*(\w+)\s*\)\s*(?:noexcept\s*)?\{)");
+
+        for (auto it = std::sregex_iterator(source_.begin(), source_.end(), to_string);
+             it != std::sregex_iterator(); ++it) {
+            EnumDecl* enum_decl = findEnum(ir, (*it)[2].str());
+            size_t open = static_cast<size_t>(it->position(0) + it->length(0)) - 1;
+            size_t close = findClosing(source_, open, '{', '}');
+            if (!enum_decl || close == std::string::npos || !enum_decl->to_string_function.empty()) continue;
+            recordDisplay(ir, *enum_decl, (*it)[1].str(), (*it)[3].str(), source_.substr(open + 1, close - open - 1));
+        }
+
+        for (auto it = std::sregex_iterator(source_.begin(), source_.end(), from_string);
+             it != std::sregex_iterator(); ++it) {
+            EnumDecl* enum_decl = findEnum(ir, (*it)[1].str());
+            size_t open = static_cast<size_t>(it->position(0) + it->length(0)) - 1;
+            size_t close = findClosing(source_, open, '{', '}');
+            if (!enum_decl || close == std::string::npos || !enum_decl->from_string_function.empty()) continue;
+            recordParse(ir, *enum_decl, (*it)[2].str(), (*it)[3].str(), source_.substr(open + 1, close - open - 1));
+        }
+    }
+
+private:
+    const std::string& source_;
+
+    /**
+     * switch (c) { case Color::Red: return "red"; ... }: the text of each
+     * case; several labels before one return share it
+     */
+    void recordDisplay(IR& ir, EnumDecl& enum_decl, const std::string& function, const std::string& param,
+                       const std::string& body) {
+        std::smatch match;
+        std::regex switch_header("\\bswitch\\s*\\(\\s*" + param + "\\s*\\)\\s*\\{");
+        if (!std::regex_search(body, match, switch_header)) return;
+        size_t open = static_cast<size_t>(match.position(0) + match.length(0)) - 1;
+        size_t close = findClosing(body, open, '{', '}');
+        if (close == std::string::npos) return;
+        std::string cases = body.substr(open + 1, close - open - 1);
+
+        // Labels, and the string literal a case returns or assigns
+        static const std::regex item(
+            R"re(\bcase\s+(?:[\w:]*::)?(\w+)\s*:|\bdefault\s*:|(?:\breturn\s+|=\s*)(?:(?:std::)?string\s*\(\s*)?"((?:[^"\\]|\\.)*)")re");
+        std::vector<std::string> labels;
+        bool in_default = false;
+        std::map<std::string, std::string> texts;
+        std::string default_text;
+        bool has_default = false;
+        std::vector<std::string> unknown;
+        for (auto it = std::sregex_iterator(cases.begin(), cases.end(), item); it != std::sregex_iterator(); ++it) {
+            const std::smatch& found = *it;
+            if (found[1].matched) {
+                std::string label = found[1].str();
+                if (!hasEnumerator(enum_decl, label)) {
+                    unknown.push_back(label);
+                    continue;
+                }
+                labels.push_back(label);
+            } else if (found[2].matched) {
+                for (const auto& label : labels) texts.emplace(label, found[2].str());
+                if (in_default) {
+                    default_text = found[2].str();
+                    has_default = true;
+                }
+                labels.clear();
+                in_default = false;
+            } else {
+                in_default = true;
+            }
+        }
+        if (texts.empty()) return;
+
+        // A return after the switch gives the text of the missing cases
+        std::smatch after;
+        std::string rest = body.substr(close + 1);
+        static const std::regex returned(
+            R"re(^\s*return\s+(?:(?:std::)?string\s*\(\s*)?"((?:[^"\\]|\\.)*)")re");
+        if (!has_default && std::regex_search(rest, after, returned)) {
+            default_text = after[1].str();
+            has_default = true;
+        }
+
+        enum_decl.to_string_function = function;
+        enum_decl.display_texts = texts;
+        enum_decl.has_display_default = has_default;
+        enum_decl.display_default = default_text;
+
+        std::string signature = function + "(" + enum_decl.name + ")";
+        for (const auto& label : unknown) {
+            report(ir, Diagnostic::Warning, function,
+                   signature + ": case " + label + " is not an enumerator of " + enum_decl.name);
+        }
+        std::vector<std::string> missing;
+        for (const auto& enumerator : enum_decl.enumerators) {
+            if (!texts.count(enumerator.name)) missing.push_back(enumerator.name);
+        }
+        if (missing.empty()) {
+            report(ir, Diagnostic::Note, function,
+                   signature + " covers every enumerator: it becomes impl Display for " + enum_decl.name);
+        } else if (has_default) {
+            report(ir, Diagnostic::Note, function,
+                   signature + " has no case for " + join(missing) + ": " +
+                       (missing.size() > 1 ? "they display" : "it displays") + " as \"" + default_text +
+                       "\", the text of the C++ default");
+        } else {
+            report(ir, Diagnostic::Warning, function,
+                   signature + " has no case for " + join(missing) + " and no default (the C++ returns no text " +
+                       "for " + (missing.size() > 1 ? "them" : "it") + "); Display writes the enumerator name");
+        }
+    }
+
+    /**
+     * if (s == "red") return Color::Red; ... return Color::Unknown;
+     */
+    void recordParse(IR& ir, EnumDecl& enum_decl, const std::string& function, const std::string& param,
+                     const std::string& body) {
+        std::regex comparison(
+            "\\bif\\s*\\(\\s*(?:" + param + "\\s*==\\s*\"((?:[^\"\\\\]|\\\\.)*)\"|\"((?:[^\"\\\\]|\\\\.)*)\"\\s*==\\s*" +
+            param + "|" + param + "\\s*\\.\\s*compare\\s*\\(\\s*\"((?:[^\"\\\\]|\\\\.)*)\"\\s*\\)\\s*==\\s*0|" +
+            "(?:std::)?strcmp\\s*\\(\\s*" + param + "\\s*,\\s*\"((?:[^\"\\\\]|\\\\.)*)\"\\s*\\)\\s*==\\s*0)" +
+            R"(\s*\)\s*\{?\s*return\s+(?:[\w:]*::)?(\w+)\s*;)");
+        std::vector<std::pair<std::string, std::string>> texts;
+        size_t end = 0;
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), comparison); it != std::sregex_iterator();
+             ++it) {
+            std::string text;
+            for (int group = 1; group <= 4; ++group) {
+                if ((*it)[group].matched) text = (*it)[group].str();
+            }
+            std::string enumerator = (*it)[5].str();
+            if (!hasEnumerator(enum_decl, enumerator)) return;
+            texts.emplace_back(text, enumerator);
+            end = static_cast<size_t>(it->position(0) + it->length(0));
+        }
+        if (texts.empty()) return;
+
+        // What is returned when nothing matched: an enumerator, or an exception
+        std::smatch fallback;
+        std::string rest = body.substr(end);
+        static const std::regex returned(R"(^\s*\}?\s*return\s+(?:[\w:]*::)?(\w+)\s*;)");
+        if (std::regex_search(rest, fallback, returned) && hasEnumerator(enum_decl, fallback[1].str())) {
+            enum_decl.parse_fallback = fallback[1].str();
+        }
+        enum_decl.from_string_function = function;
+        enum_decl.parse_texts = texts;
+
+        std::vector<std::string> missing;
+        for (const auto& enumerator : enum_decl.enumerators) {
+            bool parsed = enumerator.name == enum_decl.parse_fallback;
+            for (const auto& text : texts) parsed = parsed || text.second == enumerator.name;
+            if (!parsed) missing.push_back(enumerator.name);
+        }
+        std::string signature = function + "(" + param + ")";
+        report(ir, Diagnostic::Note, function,
+               signature + " becomes impl FromStr for " + enum_decl.name +
+                   (missing.empty() ? "" : "; no text parses to " + join(missing)) +
+                   (enum_decl.parse_fallback.empty() ? "" : "; other texts give " + enum_decl.parse_fallback +
+                                                                 ", as in the C++"));
+    }
+
+    static EnumDecl* findEnum(IR& ir, const std::string& type) {
+        std::string name = type.substr(type.rfind(':') == std::string::npos ? 0 : type.rfind(':') + 1);
+        for (auto& enum_decl : ir.getEnums()) {
+            if (enum_decl.name == name) return &enum_decl;
+        }
+        return nullptr;
+    }
+
+    static bool hasEnumerator(const EnumDecl& enum_decl, const std::string& name) {
+        return std::any_of(enum_decl.enumerators.begin(), enum_decl.enumerators.end(),
+                           [&name](const EnumDecl::Enumerator& enumerator) { return enumerator.name == name; });
+    }
+
+    static std::string join(const std::vector<std::string>& names) {
+        std::string result;
+        for (const auto& name : names) result += (result.empty() ? "" : ", ") + name;
+        return result;
+    }
+
+    static void report(IR& ir, Diagnostic::Severity severity, const std::string& context,
+                       const std::string& message) {
+        Diagnostic diagnostic;
+        diagnostic.severity = severity;
+        diagnostic.context = context;
+        diagnostic.message = message;
+        ir.addDiagnostic(diagnostic);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,117 @@
+/**
+ * Format Analyzer
+ * Recognizes std::format and fmt library calls and prepares for conversion
+ * to Rust formatting macros
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+
+namespace hybrid {
+
+/**
+ * Format Analyzer
+ * Detects std::format, std::print(ln), std::format_to and their fmt:: equivalents
+ */
+class FormatAnalyzer {
+public:
+    /**
+     * Analyze all methods of a class
+     */
+    void analyzeClass(ClassDecl& class_decl) {
+        for (auto& method : class_decl.methods) {
+            analyzeFunction(method);
+        }
+    }
+
+    /**
+     * Analyze function body for formatting calls (in source order; nested
+     * calls follow the call containing them)
+     */
+    void analyzeFunction(Function& func) {
+        const std::string& body = func.body;
+        std::regex call_pattern(R"(\b(?:std|fmt)::(format|print|println|format_to)\s*\()");
+
+        auto begin = std::sregex_iterator(body.begin(), body.end(), call_pattern);
+        for (auto it = begin; it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            size_t open = pos + it->length(0) - 1;
+            size_t close = findClosingParen(body, open);
+            if (close == std::string::npos) continue;
+
+            FormatInfo info;
+            std::string name = (*it)[1].str();
+            info.kind = name == "format" ? FormatInfo::Format
+                      : name == "print" ? FormatInfo::Print
+                      : name == "println" ? FormatInfo::Println
+                      : FormatInfo::FormatTo;
+            info.source_text = body.substr(pos, close + 1 - pos);
+
+            std::vector<std::string> args = splitArguments(body.substr(open + 1, close - open - 1));
+            if (args.empty()) continue;
+
+            if (info.kind == FormatInfo::FormatTo) {
+                // Only appending to a string is translated
+                std::smatch inserter;
+                std::regex inserter_pattern(R"(std::back_inserter\s*\(\s*([\w.]+)\s*\))");
+                if (!std::regex_match(args[0], inserter, inserter_pattern)) continue;
+                info.stream = inserter[1].str();
+                args.erase(args.begin());
+            } else if ((info.kind == FormatInfo::Print || info.kind == FormatInfo::Println) &&
+                       args.size() > 1 && !isFormatString(args[0])) {
+                // print(stderr, "...", ...): the first argument is the target
+                info.stream = args[0];
+                args.erase(args.begin());
+            }
+            if (args.empty()) continue;
+
+            info.is_literal = literalContents(args[0], info.format_string);
+            info.arguments.assign(args.begin() + 1, args.end());
+            func.formats.push_back(info);
+        }
+    }
+
+private:
+    /**
+     * A string literal, possibly split into adjacent pieces or wrapped in FMT_STRING
+     */
+    bool literalContents(std::string arg, std::string& contents) {
+        std::smatch wrapped;
+        if (std::regex_match(arg, wrapped, std::regex(R"((?:FMT_STRING|FMT_COMPILE)\s*\(([\s\S]*)\))"))) {
+            arg = trim(wrapped[1].str());
+        }
+
+        contents.clear();
+        size_t i = 0;
+        while (i < arg.size()) {
+            if (arg[i] != '"') return false;
+
+            size_t end = i + 1;
+            while (end < arg.size() && arg[end] != '"') {
+                end += arg[end] == '\\' ? 2 : 1;
+            }
+            if (end >= arg.size()) return false;
+
+            contents += arg.substr(i + 1, end - i - 1);
+            i = arg.find_first_not_of(" \t\r\n", end + 1);
+            if (i == std::string::npos) break;
+        }
+        return true;
+    }
+
+    bool isFormatString(const std::string& arg) {
+        std::string contents;
+        return literalContents(arg, contents) || arg.find("fmt::runtime") == 0;
+    }
+
+    std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,201 @@
+/**
+ * Functor Analyzer
+ * Recognizes function objects (classes with operator()) and their use
+ * sites, so they can become closures or calls of a generated `call` method
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+
+namespace hybrid {
+
+/**
+ * Functor Analyzer
+ * Whole-program pass: use sites in any body refer to functor classes
+ * declared anywhere in the input
+ */
+class FunctorAnalyzer {
+public:
+    /**
+     * Classify all classes, then analyze method bodies
+     */
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            classify(class_decl);
+        }
+
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(ir, &class_decl, method);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(ir, nullptr, func);
+        }
+    }
+
+private:
+    static const Function* callOperator(const ClassDecl& class_decl) {
+        for (const auto& method : class_decl.methods) {
+            if (method.name == "operator()") return &method;
+        }
+        return nullptr;
+    }
+
+    /**
+     * A functor has a single operator() and nothing but special members
+     */
+    void classify(ClassDecl& class_decl) {
+        size_t call_operators = 0;
+        bool other_methods = false;
+        for (const auto& method : class_decl.methods) {
+            if (method.name == "operator()") {
+                call_operators++;
+            } else if (!method.is_constructor && !method.is_destructor) {
+                other_methods = true;
+            }
+        }
+        class_decl.is_functor = call_operators == 1 && !other_methods && class_decl.base_classes.empty();
+    }
+
+    void analyzeFunction(const IR& ir, const ClassDecl* owner, Function& func) {
+        if (func.body.empty()) return;
+
+        std::vector<std::pair<size_t, FunctorUseInfo>> found;
+        detectConstructions(ir, func, found);
+        detectCalls(ir, owner, func, found);
+
+        appendInSourceOrder(found, func.functor_uses);
+    }
+
+    /**
+     * ByLength{} / Threshold(5) passed as an argument
+     */
+    void detectConstructions(const IR& ir, Function& func,
+                             std::vector<std::pair<size_t, FunctorUseInfo>>& found) {
+        const std::string& body = func.body;
+
+        for (const auto& functor : ir.getClasses()) {
+            if (!functor.is_functor || functor.is_template) continue;
+
+            std::regex construct_pattern("[(,]\\s*(" + functor.name + "\\s*[({])");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), construct_pattern);
+                 it != std::sregex_iterator(); ++it) {
+                size_t pos = it->position(1);
+                size_t open = pos + it->length(1) - 1;
+                size_t close = findClosing(body, open, body[open], body[open] == '(' ? ')' : '}');
+                if (close == std::string::npos) continue;
+
+                // Only a whole argument, not the start of a longer expression
+                size_t next = body.find_first_not_of(" \t\r\n", close + 1);
+                if (next == std::string::npos || (body[next] != ')' && body[next] != ',')) continue;
+
+                FunctorUseInfo use;
+                use.kind = FunctorUseInfo::Construct;
+                use.source_text = body.substr(pos, close + 1 - pos);
+                use.functor = functor.name;
+                use.arguments = splitArguments(body.substr(open + 1, close - open - 1));
+                use.parameters = callParameters(functor);
+                for (const auto& method : functor.methods) {
+                    if (method.is_constructor) use.has_constructor = true;
+                }
+                if (!use.has_constructor) {
+                    for (const auto& field : functor.fields) {
+                        if (!field.is_static) use.field_names.push_back(field.name);
+                    }
+                    // An aggregate is only built from a full initializer list
+                    if (use.arguments.size() != use.field_names.size()) continue;
+                }
+
+                found.emplace_back(pos, use);
+            }
+        }
+    }
+
+    /**
+     * operator() parameter names (placeholders for unnamed ones)
+     */
+    static std::vector<std::string> callParameters(const ClassDecl& functor) {
+        std::vector<std::string> names;
+        const Function* call = callOperator(functor);
+        for (size_t i = 0; call && i < call->parameters.size(); ++i) {
+            const std::string& name = call->parameters[i].name;
+            names.push_back(name.empty() ? "arg" + std::to_string(i) : name);
+        }
+        return names;
+    }
+
+    /**
+     * cmp_(a, b) on a field, parameter or local of a class with operator()
+     */
+    void detectCalls(const IR& ir, const ClassDecl* owner, Function& func,
+                     std::vector<std::pair<size_t, FunctorUseInfo>>& found) {
+        const std::string& body = func.body;
+
+        // Variable name -> class
+        std::vector<std::pair<std::string, std::string>> variables;
+        auto add = [&](const std::string& name, std::shared_ptr<Type> type) {
+            while (type && (type->kind == TypeKind::Reference || type->kind == TypeKind::RValueReference)) {
+                type = type->element_type;
+            }
+            const ClassDecl* target = type ? ir.findClass(type->name) : nullptr;
+            if (target && callOperator(*target) && !name.empty()) {
+                variables.emplace_back(name, target->name);
+            }
+        };
+        for (const auto& param : func.parameters) add(param.name, param.type);
+        if (owner) {
+            for (const auto& field : owner->fields) add(field.name, field.type);
+        }
+
+        for (const auto& target : ir.getClasses()) {
+            if (!callOperator(target)) continue;
+            std::regex local_pattern("\\b" + target.name + R"(\s*&{0,2}\s+([a-zA-Z_]\w*)\s*[;={(])"
+                                     "|\\bauto\\s*&{0,2}\\s+([a-zA-Z_]\\w*)\\s*=\\s*" + target.name + "\\s*[({]");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), local_pattern);
+                 it != std::sregex_iterator(); ++it) {
+                variables.emplace_back((*it)[1].matched ? (*it)[1].str() : (*it)[2].str(), target.name);
+            }
+        }
+
+        for (const auto& variable : variables) {
+            std::regex call_pattern("(?:^|[^\\w.>:])((?:this->)?" + variable.first + "\\s*\\()");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), call_pattern);
+                 it != std::sregex_iterator(); ++it) {
+                size_t pos = it->position(1);
+                if (precededByWord(body, pos, variable.second)) {
+                    continue;  // Declaration with constructor arguments: Threshold t(5);
+                }
+                bool seen = std::any_of(found.begin(), found.end(),
+                                        [&](const auto& entry) { return entry.first == pos; });
+                if (seen) continue;
+
+                FunctorUseInfo use;
+                use.kind = FunctorUseInfo::Call;
+                use.source_text = it->str(1);
+                use.functor = variable.second;
+                found.emplace_back(pos, use);
+            }
+        }
+    }
+
+    static bool precededByWord(const std::string& text, size_t pos, const std::string& word) {
+        size_t end = text.find_last_not_of(" \t\r\n&", pos == 0 ? 0 : pos - 1);
+        if (pos == 0 || end == std::string::npos || end + 1 < word.size()) return false;
+        size_t start = end + 1 - word.size();
+        return text.compare(start, word.size(), word) == 0 &&
+               (start == 0 || !(std::isalnum(static_cast<unsigned char>(text[start - 1])) ||
+                                text[start - 1] == '_'));
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
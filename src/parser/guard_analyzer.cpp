@@ -0,0 +1,209 @@
+/**
+ * Guard Analyzer
+ * Recognizes scope-bound RAII guards and prepares for conversion to Rust guard bindings
+ */
+
+#include "ir.h"
+#include <regex>
+#include <algorithm>
+
+namespace hybrid {
+
+/**
+ * Guard Analyzer
+ * Detects std::lock_guard, std::unique_lock, std::shared_lock, std::scoped_lock
+ * and scope-exit helpers (gsl::finally, scope_exit, SCOPE_EXIT)
+ */
+class GuardAnalyzer {
+public:
+    /**
+     * Analyze class methods, using field types to tell shared mutexes apart
+     */
+    void analyzeClass(ClassDecl& class_decl) {
+        std::vector<std::string> shared_mutexes;
+        for (const auto& field : class_decl.fields) {
+            if (field.type && field.type->name.find("shared_mutex") != std::string::npos) {
+                shared_mutexes.push_back(field.name);
+            }
+        }
+
+        for (auto& method : class_decl.methods) {
+            analyzeFunction(method, shared_mutexes);
+        }
+    }
+
+    /**
+     * Analyze function body for guard declarations
+     */
+    void analyzeFunction(Function& func, const std::vector<std::string>& shared_mutexes = {}) {
+        std::vector<GuardInfo> found;
+
+        detectLockGuards(func.body, shared_mutexes, found);
+        detectScopeExitGuards(func.body, found);
+
+        // Keep declaration order: it determines drop order
+        std::sort(found.begin(), found.end(), [&](const GuardInfo& a, const GuardInfo& b) {
+            return func.body.find(a.source_text) < func.body.find(b.source_text);
+        });
+
+        for (auto& guard : found) {
+            detectEarlyRelease(func.body, guard);
+            func.guards.push_back(guard);
+        }
+    }
+
+private:
+    /**
+     * Detect lock guard declarations:
+     *   std::lock_guard<std::mutex> lock(m);
+     *   std::unique_lock<std::mutex> lock{m};
+     *   std::scoped_lock lock(a, b);
+     */
+    void detectLockGuards(const std::string& body,
+                          const std::vector<std::string>& shared_mutexes,
+                          std::vector<GuardInfo>& found) {
+        std::regex lock_pattern(
+            R"((?:std::)?(lock_guard|unique_lock|shared_lock|scoped_lock)\s*(?:<[^>]*>)?\s+(\w+)\s*[({]([^)}]*)[)}]\s*;)"
+        );
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, lock_pattern)) {
+            GuardInfo guard;
+            std::string kind = match[1].str();
+
+            if (kind == "lock_guard") guard.kind = GuardInfo::LockGuard;
+            else if (kind == "unique_lock") guard.kind = GuardInfo::UniqueLock;
+            else if (kind == "shared_lock") guard.kind = GuardInfo::SharedLock;
+            else guard.kind = GuardInfo::ScopedLock;
+
+            guard.source_text = match[0].str();
+            guard.guard_var_name = match[2].str();
+            guard.mutex_names = splitNames(match[3].str());
+
+            if (guard.kind != GuardInfo::SharedLock) {
+                for (const auto& mutex : guard.mutex_names) {
+                    if (std::find(shared_mutexes.begin(), shared_mutexes.end(), mutex) !=
+                        shared_mutexes.end()) {
+                        guard.exclusive_on_shared_mutex = true;
+                    }
+                }
+            }
+
+            found.push_back(guard);
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * Detect scope-exit helpers:
+     *   auto guard = gsl::finally([&] { ... });
+     *   auto guard = scope_exit([&]() { ... });
+     *   SCOPE_EXIT { ... };
+     */
+    void detectScopeExitGuards(const std::string& body, std::vector<GuardInfo>& found) {
+        // Only the heads are matched by regex; the cleanup block is scanned
+        // by hand so a long body cannot exhaust the regex engine's stack
+        std::regex finally_pattern(
+            R"(auto\s+(\w+)\s*=\s*(?:[\w:]*::)?(?:finally|scope_exit|make_scope_exit|ScopeExit)\s*\(\s*\[[^\]]*\]\s*(?:\(\s*\))?\s*\{)"
+        );
+        std::regex macro_pattern(R"(SCOPE_EXIT\s*\{)");
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, finally_pattern)) {
+            size_t start = match.position(0) + (search_start - body.cbegin());
+            size_t open = start + match.length(0) - 1;
+            search_start = match.suffix().first;
+
+            std::string cleanup;
+            size_t end = matchCleanupBlock(body, open, ");", cleanup);
+            if (end == std::string::npos) continue;
+
+            GuardInfo guard;
+            guard.kind = GuardInfo::ScopeExit;
+            guard.source_text = body.substr(start, end - start);
+            guard.guard_var_name = match[1].str();
+            guard.cleanup_body = trim(cleanup);
+
+            found.push_back(guard);
+            search_start = body.cbegin() + end;
+        }
+
+        search_start = body.cbegin();
+        while (std::regex_search(search_start, body.cend(), match, macro_pattern)) {
+            size_t start = match.position(0) + (search_start - body.cbegin());
+            size_t open = start + match.length(0) - 1;
+            search_start = match.suffix().first;
+
+            std::string cleanup;
+            size_t end = matchCleanupBlock(body, open, ";", cleanup);
+            if (end == std::string::npos) continue;
+
+            GuardInfo guard;
+            guard.kind = GuardInfo::ScopeExit;
+            guard.source_text = body.substr(start, end - start);
+            guard.cleanup_body = trim(cleanup);
+
+            found.push_back(guard);
+            search_start = body.cbegin() + end;
+        }
+    }
+
+    /**
+     * Match a brace-free `{ cleanup }` at open followed by the tail
+     * characters (whitespace allowed between them)
+     * @return Position after the tail, or npos if it does not match
+     */
+    size_t matchCleanupBlock(const std::string& body, size_t open, const std::string& tail,
+                             std::string& cleanup) const {
+        size_t close = body.find_first_of("{}", open + 1);
+        if (close == std::string::npos || body[close] != '}') {
+            return std::string::npos;
+        }
+        cleanup = body.substr(open + 1, close - open - 1);
+
+        size_t pos = close + 1;
+        for (char expected : tail) {
+            pos = body.find_first_not_of(" \t\r\n", pos);
+            if (pos == std::string::npos || body[pos] != expected) {
+                return std::string::npos;
+            }
+            ++pos;
+        }
+        return pos;
+    }
+
+    /**
+     * Detect guard.unlock() / guard.dismiss() / guard.release()
+     */
+    void detectEarlyRelease(const std::string& body, GuardInfo& guard) {
+        if (guard.guard_var_name.empty()) return;
+
+        std::regex release_pattern("\\b" + guard.guard_var_name + R"(\.(unlock|dismiss|release)\s*\(\s*\))");
+        guard.unlocked_early = std::regex_search(body, release_pattern);
+    }
+
+    std::vector<std::string> splitNames(const std::string& list) {
+        std::vector<std::string> names;
+        std::regex name_pattern(R"([\w.\->]+)");
+
+        auto begin = std::sregex_iterator(list.begin(), list.end(), name_pattern);
+        auto end = std::sregex_iterator();
+        for (auto it = begin; it != end; ++it) {
+            names.push_back(it->str());
+        }
+        return names;
+    }
+
+    std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,165 @@
+/**
+ * Inheritance Analyzer
+ * Lowers classes with several bases: each base becomes an embedded field
+ * (and a trait if it is polymorphic), upcasts become trait-object
+ * coercions or accessor calls, and diamonds are reported
+ */
+
+#include "ir.h"
+#include <regex>
+#include <algorithm>
+#include <set>
+
+namespace hybrid {
+
+/**
+ * Inheritance Analyzer
+ * Whole-program pass: bases and upcasts may name classes declared anywhere
+ * in the input
+ */
+class InheritanceAnalyzer {
+public:
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            if (class_decl.base_classes.size() < 2) continue;
+            checkDiamonds(ir, class_decl);
+
+            for (const auto& base_name : class_decl.base_classes) {
+                const ClassDecl* base = ir.findClass(base_name);
+                if (!base) continue;  // Only a trait, as for single inheritance
+
+                ClassDecl::BaseSubobject subobject;
+                subobject.name = base_name;
+                for (const auto& method : base->methods) {
+                    if (method.is_virtual && !method.is_constructor && !method.is_destructor) {
+                        subobject.virtual_methods.push_back(method);
+                    }
+                }
+                subobject.is_polymorphic = !subobject.virtual_methods.empty();
+                class_decl.base_subobjects.push_back(subobject);
+            }
+        }
+
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(ir, &class_decl, method);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(ir, nullptr, func);
+        }
+    }
+
+private:
+    /**
+     * Every class reachable through base class lists, declared or not
+     */
+    static std::set<std::string> ancestors(const IR& ir, const std::string& name) {
+        std::set<std::string> result;
+        std::vector<std::string> pending = {name};
+        while (!pending.empty()) {
+            std::string current = pending.back();
+            pending.pop_back();
+            const ClassDecl* class_decl = ir.findClass(current);
+            if (!class_decl) continue;
+            for (const auto& base : class_decl->base_classes) {
+                if (result.insert(base).second) pending.push_back(base);
+            }
+        }
+        return result;
+    }
+
+    static bool isVirtualBase(const IR& ir, const std::string& derived, const std::string& base) {
+        const ClassDecl* class_decl = ir.findClass(derived);
+        return class_decl && std::find(class_decl->virtual_bases.begin(), class_decl->virtual_bases.end(),
+                                       base) != class_decl->virtual_bases.end();
+    }
+
+    /**
+     * class D : public B, public C where B and C both derive from A
+     */
+    void checkDiamonds(IR& ir, const ClassDecl& class_decl) {
+        const auto& bases = class_decl.base_classes;
+        std::set<std::string> reported;
+        for (size_t i = 0; i < bases.size(); ++i) {
+            std::set<std::string> left = ancestors(ir, bases[i]);
+            left.insert(bases[i]);
+            for (size_t j = i + 1; j < bases.size(); ++j) {
+                std::set<std::string> right = ancestors(ir, bases[j]);
+                right.insert(bases[j]);
+                for (const auto& shared : left) {
+                    if (!right.count(shared) || !reported.insert(shared).second) continue;
+
+                    std::string message = "'" + class_decl.name + "' inherits '" + shared + "' twice, through '" +
+                                          bases[i] + "' and '" + bases[j] + "' (diamond inheritance); ";
+                    if (isVirtualBase(ir, bases[i], shared) && isVirtualBase(ir, bases[j], shared)) {
+                        message += "'" + shared + "' is a virtual base, one subobject shared by both in C++, "
+                                   "which embedded fields cannot express: review '" + class_decl.name + "' by hand";
+                    } else {
+                        message += "it holds two '" + shared + "' subobjects, as in C++, and uses of '" + shared +
+                                   "' members must name the path ('" + bases[i] + "::' or '" + bases[j] + "::')";
+                    }
+                    Diagnostic diagnostic;
+                    diagnostic.severity = Diagnostic::Warning;
+                    diagnostic.message = message;
+                    diagnostic.context = class_decl.name;
+                    ir.addDiagnostic(diagnostic);
+                }
+            }
+        }
+    }
+
+    /**
+     * Class of the object an upcast expression names, empty if unknown
+     */
+    static std::string objectClass(const ClassDecl* enclosing, const Function& func, const std::string& object) {
+        if (object == "this") return enclosing ? enclosing->name : "";
+        for (const auto& param : func.parameters) {
+            if (param.name != object || !param.type) continue;
+            auto type = param.type;
+            while ((type->kind == TypeKind::Pointer || type->kind == TypeKind::Reference) && type->element_type) {
+                type = type->element_type;
+            }
+            return type->name;
+        }
+        return "";
+    }
+
+    void analyzeFunction(const IR& ir, const ClassDecl* enclosing, Function& func) {
+        const std::string& body = func.body;
+        if (body.empty()) return;
+
+        // Shape* s = this; const Named& n = *this;  /  static_cast<Shape*>(this)
+        std::regex upcast_pattern(
+            R"((?:^|[;{}])\s*((const\s+)?(\w+)\s*([*&])\s*(\w+)\s*=\s*([*&]?\s*\w+))(?=\s*;))"
+            R"(|(static_cast\s*<\s*(const\s+)?(\w+)\s*([*&])\s*>\s*\(\s*([*&]?\s*\w+)\s*\)))");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), upcast_pattern);
+             it != std::sregex_iterator(); ++it) {
+            bool is_declaration = (*it)[1].matched;
+            int group = is_declaration ? 2 : 8;
+
+            UpcastInfo site;
+            site.source_text = is_declaration ? (*it)[1].str() : (*it)[7].str();
+            site.is_const = (*it)[group].matched;
+            site.base = (*it)[group + 1].str();
+            site.to_pointer = (*it)[group + 2].str() == "*";
+            if (is_declaration) site.name = (*it)[5].str();
+
+            // this / *this / &x / *x / x name the derived object
+            std::string expression = (*it)[is_declaration ? 6 : 11].str();
+            expression.erase(std::remove(expression.begin(), expression.end(), ' '), expression.end());
+            site.object = expression[0] == '*' || expression[0] == '&' ? expression.substr(1) : expression;
+
+            const ClassDecl* derived = ir.findClass(objectClass(enclosing, func, site.object));
+            if (!derived || derived->base_subobjects.empty()) continue;
+            auto subobject = std::find_if(derived->base_subobjects.begin(), derived->base_subobjects.end(),
+                                          [&](const ClassDecl::BaseSubobject& b) { return b.name == site.base; });
+            if (subobject == derived->base_subobjects.end()) continue;
+            site.is_polymorphic = subobject->is_polymorphic;
+
+            func.upcasts.push_back(site);
+        }
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,273 @@
+/**
+ * Integer Analyzer
+ * Finds integer code whose C++ semantics Rust does not share: unsigned
+ * arithmetic that wraps (a panic in a Rust debug build) and conversions
+ * between integer types, implicit in C++ and an error in Rust
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+#include <set>
+#include <cctype>
+
+namespace hybrid {
+
+/**
+ * Integer Analyzer
+ * Whole-program pass: member functions see the fields of their class.
+ * Whether a conversion narrows depends on the data model, so every
+ * conversion between different types is recorded and the code generator
+ * decides
+ */
+class IntegerAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit IntegerAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                if (method.retained_reason.empty()) analyzeFunction(method, &class_decl);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            if (func.retained_reason.empty()) analyzeFunction(func, nullptr);
+        }
+    }
+
+private:
+    TypeParser parse_type_;
+
+    struct Statement {
+        std::string text;
+        size_t position;
+    };
+
+    void analyzeFunction(Function& func, const ClassDecl* owner) {
+        static const std::regex returned(R"(\breturn\s+(.+)$)");
+        static const std::regex assignment(
+            R"(^(?:((?:const\s+)?[A-Za-z_][\w:]*(?:\s+[A-Za-z_]\w*)*)\s+)?(\w+(?:(?:\.|->)\w+)*)\s*(\+=|-=|\*=|=)(?!=)\s*(.+)$)");
+        static const std::set<std::string> keywords = {"return", "else", "case", "do", "throw", "delete", "goto"};
+
+        for (const auto& statement : statements(func.body)) {
+            const std::string& text = statement.text;
+            std::smatch match;
+
+            // static_cast<int>(n)
+            findCasts(func, owner, text, statement.position);
+
+            if (std::regex_search(text, match, returned)) {
+                if (!isInteger(func.return_type)) continue;
+                std::string expression = trim(match[1].str());
+                auto from = operandType(func, owner, expression, statement.position);
+                if (!isInteger(from) || from->name == func.return_type->name) continue;
+                IntegerSiteInfo site;
+                site.kind = IntegerSiteInfo::Conversion;
+                site.source_text = text.substr(static_cast<size_t>(match.position(0)));
+                site.expression = expression;
+                site.type = func.return_type;
+                site.from = from;
+                func.integer_sites.push_back(site);
+                continue;
+            }
+
+            if (!std::regex_match(text, match, assignment)) continue;
+            std::string declared = match[1].str();
+            if (keywords.count(declared)) continue;
+            std::string target = match[2].str();
+            std::string op = match[3].str();
+            std::string expression = trim(match[4].str());
+            auto type = declared.empty() ? operandType(func, owner, target, statement.position)
+                                         : parse_type_(declared);
+            if (!isInteger(type)) continue;
+
+            IntegerSiteInfo site;
+            site.source_text = text.substr(static_cast<size_t>(match.position(2)));
+            site.target = target;
+            site.op = op;
+            site.expression = expression;
+            site.type = type;
+
+            // Unsigned arithmetic is modular in C++
+            if (isUnsigned(type) && reliesOnWraparound(op, expression, type) && plainArithmetic(expression)) {
+                site.kind = IntegerSiteInfo::Wrapping;
+                static const std::regex identifier(R"((^|[^.\w>:])([A-Za-z_]\w*)\b(?!\s*\())");
+                for (auto it = std::sregex_iterator(expression.begin(), expression.end(), identifier);
+                     it != std::sregex_iterator(); ++it) {
+                    std::string name = (*it)[2].str();
+                    auto operand = operandType(func, owner, name, statement.position);
+                    if (isInteger(operand) && operand->name != type->name) site.operands[name] = operand;
+                }
+                func.integer_sites.push_back(site);
+                continue;
+            }
+
+            if (op != "=") continue;
+            auto from = operandType(func, owner, expression, statement.position);
+            if (!isInteger(from) || from->name == type->name) continue;
+            site.kind = IntegerSiteInfo::Conversion;
+            site.from = from;
+            func.integer_sites.push_back(site);
+        }
+    }
+
+    void findCasts(Function& func, const ClassDecl* owner, const std::string& text, size_t position) {
+        static const std::regex cast(R"(\bstatic_cast\s*<\s*([^<>]+?)\s*>\s*\()");
+        for (auto it = std::sregex_iterator(text.begin(), text.end(), cast); it != std::sregex_iterator(); ++it) {
+            size_t start = static_cast<size_t>(it->position(0));
+            size_t open = start + static_cast<size_t>(it->length(0)) - 1;
+            size_t close = findClosing(text, open, '(', ')');
+            if (close == std::string::npos) continue;
+            auto type = parse_type_((*it)[1].str());
+            std::string expression = trim(text.substr(open + 1, close - open - 1));
+            auto from = operandType(func, owner, expression, position);
+            if (!isInteger(type) || !isInteger(from) || from->name == type->name) continue;
+
+            IntegerSiteInfo site;
+            site.kind = IntegerSiteInfo::Conversion;
+            site.source_text = text.substr(start, close + 1 - start);
+            site.expression = expression;
+            site.type = type;
+            site.from = from;
+            site.explicit_cast = true;
+            func.integer_sites.push_back(site);
+        }
+    }
+
+    /**
+     * Top-level statements of a body: split at ';', '{' and '}' outside parentheses
+     */
+    static std::vector<Statement> statements(const std::string& body) {
+        std::vector<Statement> result;
+        std::string current;
+        size_t start = 0;
+        int depth = 0;
+        for (size_t i = 0; i < body.size(); ++i) {
+            char c = body[i];
+            if (c == '"' || c == '\'') {
+                size_t end = i;
+                for (++end; end < body.size() && body[end] != c; ++end) {
+                    if (body[end] == '\\') ++end;
+                }
+                current += body.substr(i, end + 1 - i);
+                i = end;
+                continue;
+            }
+            if (c == '(') depth++;
+            else if (c == ')') depth--;
+            if (depth == 0 && (c == ';' || c == '{' || c == '}')) {
+                std::string text = trim(current);
+                if (!text.empty()) result.push_back({text, start});
+                current.clear();
+                start = i + 1;
+                continue;
+            }
+            current += c;
+        }
+        return result;
+    }
+
+    /**
+     * Integer type of an expression the analyzer can type: a variable,
+     * a member, a size() call, sizeof; null otherwise
+     */
+    std::shared_ptr<Type> operandType(const Function& func, const ClassDecl* owner, const std::string& expr,
+                                      size_t limit) const {
+        static const std::regex size_call(R"(^(?:[\w.]+(?:->\w+)*\s*(?:\.|->)\s*(?:size|length)\s*\(\s*\)|sizeof\s*\(.*\)|strlen\s*\(.*\))$)");
+        std::string name = std::regex_replace(trim(expr), std::regex(R"(^this\s*->\s*)"), "");
+        if (std::regex_match(name, size_call)) return parse_type_("size_t");
+        if (name.empty() || name.find_first_not_of("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_") !=
+                                std::string::npos ||
+            std::isdigit(static_cast<unsigned char>(name[0]))) {
+            return nullptr;
+        }
+
+        for (const auto& param : func.parameters) {
+            if (param.name == name) return param.type;
+        }
+
+        // Latest declaration before the use
+        std::string before = func.body.substr(0, std::min(limit, func.body.size()));
+        std::regex declaration(R"((?:^|[;{}(,])\s*((?:const\s+)?[A-Za-z_][\w:]*(?:\s+[A-Za-z_]\w*)*?)\s+)" + name +
+                               R"(\s*(?:=|;|\{|\)|,))");
+        std::shared_ptr<Type> found;
+        for (auto it = std::sregex_iterator(before.begin(), before.end(), declaration);
+             it != std::sregex_iterator(); ++it) {
+            static const std::set<std::string> not_types = {"return", "else", "case", "delete", "throw", "do",
+                                                            "goto", "auto", "const auto"};
+            std::string type_str = (*it)[1].str();
+            if (not_types.count(type_str)) continue;
+            found = parse_type_(type_str);
+        }
+        if (found) return found;
+
+        if (owner) {
+            for (const auto& field : owner->fields) {
+                if (field.name == name) return field.type;
+            }
+        }
+        return nullptr;
+    }
+
+    static bool isInteger(const std::shared_ptr<Type>& type) {
+        return type && type->kind == TypeKind::Integer;
+    }
+
+    static bool isUnsigned(const std::shared_ptr<Type>& type) {
+        const std::string& name = type->name;
+        return name.compare(0, 9, "unsigned ") == 0 || name.compare(0, 4, "uint") == 0 || name == "size_t";
+    }
+
+    /**
+     * Hashes and generators multiply, counters run below zero, checksums
+     * of bytes add; a size_t counter that only grows does not wrap
+     */
+    static bool reliesOnWraparound(const std::string& op, const std::string& expr,
+                                   const std::shared_ptr<Type>& type) {
+        static const std::set<std::string> narrow = {"unsigned char", "unsigned short", "uint8_t", "uint16_t"};
+        bool is_narrow = narrow.count(type->name) > 0;
+        if (op == "*=" || op == "-=") return true;
+        if (op == "+=") return is_narrow;
+
+        std::string top = std::regex_replace(topLevel(expr), std::regex(R"(->)"), ".");
+        for (size_t i = 1; i < top.size(); ++i) {
+            if (top[i] == '*' || top[i] == '-' || (top[i] == '+' && is_narrow)) return true;
+        }
+        return false;
+    }
+
+    // Only + - * / % at the top level: shifts, comparisons and the like stay as written
+    static bool plainArithmetic(const std::string& expr) {
+        std::string top = std::regex_replace(topLevel(expr), std::regex(R"(->)"), ".");
+        return !std::regex_search(top, std::regex(R"([<>&|^?!=~,]|\+\+|--)"));
+    }
+
+    // expr with parenthesized groups emptied: a * (b + c) -> a * ()
+    static std::string topLevel(const std::string& expr) {
+        std::string result;
+        int depth = 0;
+        for (char c : expr) {
+            if (c == '(' || c == '[') {
+                if (depth++ == 0) result += c;
+            } else if (c == ')' || c == ']') {
+                if (--depth == 0) result += c;
+            } else if (depth == 0) {
+                result += c;
+            }
+        }
+        return result;
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
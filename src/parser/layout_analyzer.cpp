@@ -0,0 +1,230 @@
+/**
+ * Layout Analyzer
+ * Recognizes sizeof/alignof/offsetof and prepares for conversion to std::mem queries
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+
+namespace hybrid {
+
+/**
+ * Layout Analyzer
+ * Whole-program pass: offsetof on a class requires that class to keep C layout
+ */
+class LayoutAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit LayoutAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    /**
+     * Analyze all method and function bodies
+     */
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            std::vector<std::string> field_names;
+            for (const auto& field : class_decl.fields) {
+                field_names.push_back(field.name);
+            }
+
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(method, field_names);
+            }
+        }
+
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(func);
+        }
+
+        // Field offsets only match C++ if Rust keeps declaration order
+        for (auto& class_decl : ir.getClasses()) {
+            for (const auto& target : offset_targets_) {
+                if (class_decl.name == target) {
+                    class_decl.needs_c_layout = true;
+                }
+            }
+        }
+    }
+
+    /**
+     * Analyze function body for layout queries
+     */
+    void analyzeFunction(Function& func, const std::vector<std::string>& field_names = {}) {
+        std::vector<std::pair<size_t, LayoutQueryInfo>> found;
+
+        detectArrayLength(func.body, found);
+        detectSizeOf(func, field_names, found);
+        detectAlignOf(func, field_names, found);
+        detectOffsetOf(func.body, found);
+
+        appendInSourceOrder(found, func.layout_queries);
+    }
+
+private:
+    TypeParser parse_type_;
+    std::vector<std::string> offset_targets_;
+
+    /**
+     * Detect the element-count idiom: sizeof(arr) / sizeof(arr[0])
+     */
+    void detectArrayLength(const std::string& body,
+                           std::vector<std::pair<size_t, LayoutQueryInfo>>& found) {
+        std::regex length_pattern(
+            R"(sizeof\s*\(\s*([a-zA-Z_][\w.]*(?:->\w+)*)\s*\)\s*/\s*sizeof\s*\(\s*(?:\1\s*\[\s*0\s*\]|\*\s*\1)\s*\))"
+        );
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, length_pattern)) {
+            LayoutQueryInfo query;
+            query.kind = LayoutQueryInfo::ArrayLength;
+            query.source_text = match[0].str();
+            query.expression = match[1].str();
+
+            found.push_back({static_cast<size_t>(match[0].first - body.cbegin()), query});
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * Detect sizeof(T), sizeof(expr) and sizeof expr
+     */
+    void detectSizeOf(const Function& func, const std::vector<std::string>& field_names,
+                      std::vector<std::pair<size_t, LayoutQueryInfo>>& found) {
+        const std::string& body = func.body;
+
+        // `sizeof...(Pack)` is not a layout query and does not match `sizeof\s*\(`
+        std::regex paren_pattern(R"(\bsizeof\s*\(\s*([^()]*(?:\([^()]*\)[^()]*)*?)\s*\))");
+        std::regex bare_pattern(R"(\bsizeof\s+(\*?\s*[a-zA-Z_][\w.]*(?:->\w+)*(?:\[[^\]]*\])*))");
+
+        for (bool parenthesized : {true, false}) {
+            const std::regex& pattern = parenthesized ? paren_pattern : bare_pattern;
+            std::smatch match;
+            std::string::const_iterator search_start(body.cbegin());
+
+            while (std::regex_search(search_start, body.cend(), match, pattern)) {
+                size_t pos = match[0].first - body.cbegin();
+                search_start = match.suffix().first;
+
+                if (isCovered(found, pos)) continue;
+
+                LayoutQueryInfo query;
+                query.kind = LayoutQueryInfo::SizeOf;
+                query.source_text = match[0].str();
+                setOperand(func, field_names, match[1].str(), !parenthesized, query);
+
+                found.push_back({pos, query});
+            }
+        }
+    }
+
+    /**
+     * Detect alignof(T) (and the GNU __alignof__ spelling)
+     */
+    void detectAlignOf(const Function& func, const std::vector<std::string>& field_names,
+                       std::vector<std::pair<size_t, LayoutQueryInfo>>& found) {
+        const std::string& body = func.body;
+        std::regex align_pattern(R"(\b(?:alignof|_Alignof|__alignof__)\s*\(\s*([^()]*?)\s*\))");
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, align_pattern)) {
+            LayoutQueryInfo query;
+            query.kind = LayoutQueryInfo::AlignOf;
+            query.source_text = match[0].str();
+            setOperand(func, field_names, match[1].str(), false, query);
+
+            found.push_back({static_cast<size_t>(match[0].first - body.cbegin()), query});
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * Detect offsetof(T, member)
+     */
+    void detectOffsetOf(const std::string& body,
+                        std::vector<std::pair<size_t, LayoutQueryInfo>>& found) {
+        std::regex offset_pattern(R"(\boffsetof\s*\(\s*([^,()]+?)\s*,\s*([^()]+?)\s*\))");
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, offset_pattern)) {
+            LayoutQueryInfo query;
+            query.kind = LayoutQueryInfo::OffsetOf;
+            query.source_text = match[0].str();
+            query.type = parse_type_(stripElaborated(match[1].str()));
+            query.member_name = match[2].str();
+
+            offset_targets_.push_back(query.type->name);
+            found.push_back({static_cast<size_t>(match[0].first - body.cbegin()), query});
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * Decide whether the operand names a type or an expression
+     */
+    void setOperand(const Function& func, const std::vector<std::string>& field_names,
+                    const std::string& operand, bool is_expression, LayoutQueryInfo& query) {
+        if (is_expression || isExpression(func, field_names, operand)) {
+            query.expression = operand;
+        } else {
+            query.type = parse_type_(stripElaborated(operand));
+        }
+    }
+
+    /**
+     * Drop C-style elaborated type specifiers: `struct Header` -> `Header`
+     */
+    std::string stripElaborated(const std::string& type_str) {
+        static const std::regex elaborated(R"(^(?:struct|class|union|enum)\s+)");
+        return std::regex_replace(type_str, elaborated, "");
+    }
+
+    bool isExpression(const Function& func, const std::vector<std::string>& field_names,
+                      const std::string& operand) {
+        if (operand.empty()) return false;
+
+        // Dereference, subscript, member access and calls only appear in expressions
+        if (operand[0] == '*' || operand.find_first_of("[.(") != std::string::npos ||
+            operand.find("->") != std::string::npos) {
+            return true;
+        }
+
+        std::string root = operand.substr(0, operand.find_first_not_of(
+            "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_"));
+        if (root != operand) {
+            return false;  // Qualified, templated, pointer or multi-word type
+        }
+
+        for (const auto& param : func.parameters) {
+            if (param.name == root) return true;
+        }
+
+        if (std::find(field_names.begin(), field_names.end(), root) != field_names.end()) {
+            return true;
+        }
+
+        // Local variable declared in the body: `T name;`, `T name[N]`, `T name = ...`
+        std::regex local_pattern("[\\w>*&]\\s+[*&]*\\s*" + root + R"(\s*(?:\[|=|;|\{|\())");
+        return std::regex_search(func.body, local_pattern);
+    }
+
+    bool isCovered(const std::vector<std::pair<size_t, LayoutQueryInfo>>& found, size_t pos) {
+        for (const auto& entry : found) {
+            if (pos >= entry.first && pos < entry.first + entry.second.source_text.size()) {
+                return true;
+            }
+        }
+        return false;
+    }
+};
+
+} // namespace hybrid
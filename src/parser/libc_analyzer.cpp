@@ -0,0 +1,105 @@
+/**
+ * Libc Analyzer
+ * Finds direct POSIX/C library calls (open, read, malloc, memcpy, socket,
+ * ...), which have no declaration in the input and would otherwise come
+ * out as unresolved identifiers
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <set>
+#include <cctype>
+
+namespace hybrid {
+
+/**
+ * Libc Analyzer
+ * Whole-program pass: a function declared in the input shadows the
+ * library function of the same name
+ */
+class LibcAnalyzer {
+public:
+    void analyze(IR& ir) {
+        std::set<std::string> declared;
+        for (const auto& func : ir.getFunctions()) {
+            declared.insert(func.name);
+        }
+
+        for (auto& class_decl : ir.getClasses()) {
+            // Inside a member function, close(fd) finds the member close(): only ::close is libc
+            std::set<std::string> shadowed = declared;
+            for (const auto& method : class_decl.methods) {
+                shadowed.insert(method.name);
+            }
+            for (auto& method : class_decl.methods) {
+                if (method.retained_reason.empty()) method.libc_calls = findCalls(method.body, shadowed);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            if (func.retained_reason.empty()) func.libc_calls = findCalls(func.body, declared);
+        }
+    }
+
+private:
+    /**
+     * Calls in text, outermost first; calls in their arguments are nested
+     */
+    static std::vector<LibcCallInfo> findCalls(const std::string& text, const std::set<std::string>& shadowed) {
+        static const std::regex call_pattern(
+            R"((::\s*|\bstd::)?\b(open|read|write|close|lseek|unlink|mkdir|rename|malloc|calloc|realloc|free|)"
+            R"(memcpy|memmove|memset|memcmp|strlen|strcmp|strcpy|socket|connect|bind|listen|accept|send|recv|)"
+            R"(getpid|sleep|usleep)\s*\()");
+
+        std::vector<LibcCallInfo> calls;
+        size_t cursor = 0;
+        std::smatch match;
+        while (cursor < text.size() &&
+               std::regex_search(text.begin() + cursor, text.end(), match, call_pattern)) {
+            size_t pos = cursor + match.position(0);
+            size_t open = pos + match.length(0) - 1;
+            size_t close = findClosing(text, open, '(', ')');
+            if (close == std::string::npos) break;
+
+            // obj.read(...), f->close(), Reader::read(...) and declarations are not library calls
+            size_t end = pos;
+            while (end > 0 && std::isspace(static_cast<unsigned char>(text[end - 1]))) --end;
+            size_t start = end;
+            while (start > 0 &&
+                   (std::isalnum(static_cast<unsigned char>(text[start - 1])) || text[start - 1] == '_')) {
+                --start;
+            }
+            std::string word = text.substr(start, end - start);
+            char before = end > 0 ? text[end - 1] : ' ';
+            bool qualified = match[1].matched;
+            bool member = before == '.' || (before == '>' && end > 1 && text[end - 2] == '-') ||
+                          (!qualified && before == ':');
+            static const std::set<std::string> keywords = {"return", "else", "case", "throw", "co_return", "do"};
+            bool declaration = !word.empty() && !keywords.count(word);
+            std::string name = match[2].str();
+            if (member || declaration || (!qualified && shadowed.count(name))) {
+                cursor = open + 1;
+                continue;
+            }
+
+            LibcCallInfo call;
+            call.function = name;
+            call.source_text = text.substr(pos, close + 1 - pos);
+            call.arguments = splitArguments(text.substr(open + 1, close - open - 1));
+            call.nested = findCalls(text.substr(open + 1, close - open - 1), shadowed);
+            calls.push_back(call);
+            cursor = close + 1;
+        }
+        return calls;
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,296 @@
+/**
+ * Log Analyzer
+ * Recognizes glog streams, spdlog calls and project logging macros and
+ * prepares for conversion to log crate macros
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+
+namespace hybrid {
+
+/**
+ * Log Analyzer
+ * Whole-program pass: project macros are recognized from their #define
+ * anywhere in the source, or from the configured macro table
+ */
+class LogAnalyzer {
+public:
+    /**
+     * @param source Source text (for #define'd logging macros)
+     * @param configured Macro -> level entries; these take priority
+     */
+    LogAnalyzer(const std::string& source, const LogMacroTable& configured) {
+        std::regex define_pattern(R"(#\s*define\s+(\w+)\()");
+        for (auto it = std::sregex_iterator(source.begin(), source.end(), define_pattern);
+             it != std::sregex_iterator(); ++it) {
+            std::string level = levelFromName((*it)[1].str());
+            if (!level.empty()) macros_[(*it)[1].str()] = level;
+        }
+        for (const auto& entry : configured) {
+            macros_[entry.first] = entry.second;
+        }
+    }
+
+    /**
+     * Analyze all method and function bodies
+     */
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(method);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(func);
+        }
+    }
+
+private:
+    LogMacroTable macros_;
+
+    void analyzeFunction(Function& func) {
+        if (func.body.empty()) return;
+
+        std::vector<std::pair<size_t, LogInfo>> found;
+        detectStreams(func.body, found);
+        detectCalls(func.body, found);
+
+        appendInSourceOrder(found, func.logs);
+    }
+
+    /**
+     * LOG_ERROR, MY_LOG_WARN, DEBUG_LOG, LogInfo, ... -> level (empty if none)
+     */
+    static std::string levelFromName(const std::string& name) {
+        static const std::regex name_pattern(
+            R"((?:\w*_)?LOG_?(TRACE|VERBOSE|DEBUG|DBG|INFO|WARN|WARNING|ERROR|ERR|FATAL|CRITICAL)|)"
+            R"((TRACE|VERBOSE|DEBUG|DBG|INFO|WARN|WARNING|ERROR|ERR|FATAL|CRITICAL)_?LOG)",
+            std::regex::icase);
+        std::smatch match;
+        if (!std::regex_match(name, match, name_pattern)) return "";
+
+        std::string severity = match[1].matched ? match[1].str() : match[2].str();
+        std::transform(severity.begin(), severity.end(), severity.begin(), ::toupper);
+        return severityLevel(severity);
+    }
+
+    /**
+     * glog/spdlog severity -> log crate level
+     */
+    static std::string severityLevel(const std::string& severity) {
+        if (severity == "TRACE" || severity == "VERBOSE") return "trace";
+        if (severity == "DEBUG" || severity == "DBG") return "debug";
+        if (severity == "INFO") return "info";
+        if (severity == "WARN" || severity == "WARNING") return "warn";
+        return "error";  // ERROR, ERR, FATAL, CRITICAL
+    }
+
+    /**
+     * LOG(INFO) << "x = " << x;   DLOG(WARNING) << ...;   VLOG(2) << ...;
+     */
+    void detectStreams(const std::string& body, std::vector<std::pair<size_t, LogInfo>>& found) {
+        std::regex stream_pattern(
+            R"(\b(D?LOG)\s*\(\s*(?:google::)?(?:GLOG_)?(INFO|WARNING|ERROR|FATAL)\s*\)\s*<<|\bVLOG\s*\(\s*(\d+)\s*\)\s*<<)");
+
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), stream_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            size_t start = pos + it->length(0);
+            size_t end = findStatementEnd(body, start);
+            if (end == std::string::npos) continue;
+
+            LogInfo info;
+            info.style = LogInfo::Stream;
+            info.source_text = body.substr(pos, end + 1 - pos);
+            if ((*it)[3].matched) {
+                info.macro = "VLOG";
+                info.level = std::stoi((*it)[3].str()) <= 1 ? "debug" : "trace";
+            } else {
+                info.macro = (*it)[1].str();
+                info.level = severityLevel((*it)[2].str());
+                info.fatal = (*it)[2].str() == "FATAL";
+            }
+            joinStreamPieces(splitStream(body.substr(start, end - start)), info);
+
+            found.emplace_back(pos, info);
+        }
+    }
+
+    /**
+     * Literal pieces become the format string, anything else a {} argument
+     */
+    void joinStreamPieces(std::vector<std::string> pieces, LogInfo& info) {
+        static const std::regex manipulator(
+            R"(std::(?:hex|oct|dec|fixed|scientific|boolalpha|left|right|setw|setprecision|setfill|showpos)\b.*)");
+
+        // The log record ends the line itself
+        while (!pieces.empty() && (pieces.back() == "std::endl" || pieces.back() == "'\\n'" ||
+                                   pieces.back() == "\"\\n\"")) {
+            pieces.pop_back();
+        }
+
+        info.is_literal = true;
+        for (size_t i = 0; i < pieces.size(); ++i) {
+            std::string contents;
+            if (std::regex_match(pieces[i], manipulator)) {
+                info.unsupported = pieces[i];
+                return;
+            }
+            if (literalContents(pieces[i], contents)) {
+                if (i + 1 == pieces.size() && contents.size() >= 2 &&
+                    contents.compare(contents.size() - 2, 2, "\\n") == 0) {
+                    contents.erase(contents.size() - 2);
+                }
+                info.format_string += escapeBraces(contents);
+            } else {
+                info.format_string += "{}";
+                info.arguments.push_back(pieces[i]);
+            }
+        }
+    }
+
+    /**
+     * spdlog::info(...), SPDLOG_WARN(...), and mapped project macros
+     */
+    void detectCalls(const std::string& body, std::vector<std::pair<size_t, LogInfo>>& found) {
+        std::string names = R"(spdlog::(?:trace|debug|info|warn|error|critical)|SPDLOG_(?:TRACE|DEBUG|INFO|WARN|ERROR|CRITICAL))";
+        for (const auto& entry : macros_) {
+            names += "|" + entry.first;
+        }
+        std::regex call_pattern("(?:^|[^\\w:.>])(" + names + ")\\s*\\(");
+
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), call_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(1);
+            size_t open = it->position(0) + it->length(0) - 1;
+            size_t close = findClosingParen(body, open);
+            if (close == std::string::npos) continue;
+
+            // Only whole statements: the macro's value is never used
+            size_t semicolon = body.find_first_not_of(" \t\r\n", close + 1);
+            if (semicolon == std::string::npos || body[semicolon] != ';') continue;
+
+            std::vector<std::string> args = splitArguments(body.substr(open + 1, close - open - 1));
+            if (args.empty()) continue;
+
+            LogInfo info;
+            info.macro = (*it)[1].str();
+            info.source_text = body.substr(pos, semicolon + 1 - pos);
+            auto configured = macros_.find(info.macro);
+            if (configured != macros_.end()) {
+                info.level = configured->second;
+            } else {
+                // spdlog::warn / SPDLOG_WARN
+                std::string severity = info.macro.substr(info.macro.find_last_of(":_") + 1);
+                std::transform(severity.begin(), severity.end(), severity.begin(), ::toupper);
+                info.level = severityLevel(severity);
+            }
+
+            info.is_literal = literalContents(args[0], info.format_string);
+            info.arguments.assign(args.begin() + 1, args.end());
+
+            // spdlog is fmt-based; project macros are usually printf wrappers
+            static const std::regex printf_spec(
+                R"(%[-+ #0]*(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:hh|h|ll|l|z|j|t|L)?[diouxXeEfFgGcspaA])");
+            bool spdlog = info.macro.compare(0, 6, "spdlog") == 0 || info.macro.compare(0, 6, "SPDLOG") == 0;
+            info.style = !spdlog && std::regex_search(info.format_string, printf_spec) ? LogInfo::Printf
+                                                                                        : LogInfo::Fmt;
+
+            found.emplace_back(pos, info);
+        }
+    }
+
+    static std::string escapeBraces(const std::string& text) {
+        std::string result;
+        for (char c : text) {
+            result += c;
+            if (c == '{' || c == '}') result += c;
+        }
+        return result;
+    }
+
+    /**
+     * A string literal, possibly split into adjacent pieces
+     */
+    static bool literalContents(const std::string& arg, std::string& contents) {
+        contents.clear();
+        size_t i = 0;
+        while (i < arg.size()) {
+            if (arg[i] != '"') return false;
+
+            size_t end = skipLiteral(arg, i);
+            if (end >= arg.size()) return false;
+
+            contents += arg.substr(i + 1, end - i - 1);
+            i = arg.find_first_not_of(" \t\r\n", end + 1);
+            if (i == std::string::npos) break;
+        }
+        return !arg.empty();
+    }
+
+    /**
+     * Index of the ';' ending the statement starting at start
+     */
+    static size_t findStatementEnd(const std::string& text, size_t start) {
+        int depth = 0;
+        for (size_t i = start; i < text.size(); ++i) {
+            char c = text[i];
+            if (c == '"' || c == '\'') {
+                i = skipLiteral(text, i);
+            } else if (c == '(' || c == '[' || c == '{') {
+                depth++;
+            } else if (c == ')' || c == ']' || c == '}') {
+                if (--depth < 0) return std::string::npos;
+            } else if (c == ';' && depth == 0) {
+                return i;
+            }
+        }
+        return std::string::npos;
+    }
+
+    /**
+     * Split a << chain at top level
+     */
+    static std::vector<std::string> splitStream(const std::string& chain) {
+        std::vector<std::string> pieces;
+        std::string current;
+        int depth = 0;
+
+        for (size_t i = 0; i < chain.size(); ++i) {
+            char c = chain[i];
+            if (c == '"' || c == '\'') {
+                size_t end = std::min(skipLiteral(chain, i), chain.size() - 1);
+                current += chain.substr(i, end - i + 1);
+                i = end;
+                continue;
+            }
+
+            if (c == '(' || c == '[' || c == '{') depth++;
+            else if (c == ')' || c == ']' || c == '}') depth--;
+
+            if (depth == 0 && chain.compare(i, 2, "<<") == 0) {
+                pieces.push_back(trim(current));
+                current.clear();
+                ++i;
+            } else {
+                current += c;
+            }
+        }
+        if (!trim(current).empty()) {
+            pieces.push_back(trim(current));
+        }
+        return pieces;
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
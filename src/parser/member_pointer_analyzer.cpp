@@ -0,0 +1,175 @@
+/**
+ * Member Pointer Analyzer
+ * Recognizes pointers to members (&C::m, obj.*p, (this->*p)(args)) so
+ * they can become fn pointers taking the object and accessor closures
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+
+namespace hybrid {
+
+/**
+ * Member Pointer Analyzer
+ * Whole-program pass: &C::m may name a class declared anywhere in the input
+ */
+class MemberPointerAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit MemberPointerAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    /**
+     * Analyze all method and function bodies
+     */
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(ir, method);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(ir, func);
+        }
+    }
+
+private:
+    TypeParser parse_type_;
+
+    void analyzeFunction(const IR& ir, Function& func) {
+        const std::string& body = func.body;
+        if (body.empty()) return;
+
+        std::vector<std::pair<size_t, MemberPointerInfo>> found;
+        detectDeclarations(body, found);
+        detectCalls(body, found);
+        detectAccesses(body, found);
+        detectAddresses(ir, body, found);
+
+        appendInSourceOrder(found, func.member_pointers);
+    }
+
+    static bool inside(const std::vector<std::pair<size_t, MemberPointerInfo>>& found, size_t pos) {
+        return std::any_of(found.begin(), found.end(), [&](const auto& entry) {
+            return pos >= entry.first && pos < entry.first + entry.second.source_text.size();
+        });
+    }
+
+    /**
+     * void (Machine::*handler)() = ...; int Machine::*field = ...;
+     */
+    void detectDeclarations(const std::string& body,
+                            std::vector<std::pair<size_t, MemberPointerInfo>>& found) {
+        std::regex declaration_pattern(
+            R"((?:^|[;{}])\s*((?:const\s+)?([a-zA-Z_][\w:<>]*\s*[*&]?)\s*\(\s*((?:\w+::)+)\*\s*([a-zA-Z_]\w*)\s*\)\s*\(([^()]*)\)\s*(const\b)?))"
+            R"(|(?:^|[;{}])\s*(([a-zA-Z_][\w:<>]*)\s+((?:\w+::)+)\*\s*([a-zA-Z_]\w*))\s*[=;])");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), declaration_pattern);
+             it != std::sregex_iterator(); ++it) {
+            MemberPointerInfo site;
+            site.kind = MemberPointerInfo::Declaration;
+            size_t pos;
+            if ((*it)[1].matched) {
+                pos = it->position(1);
+                site.source_text = trim((*it)[1].str());
+                site.name = (*it)[4].str();
+                site.type = parse_type_((*it)[2].str() + " (" + (*it)[3].str() + "*)(" + (*it)[5].str() + ")" +
+                                        ((*it)[6].matched ? " const" : ""));
+            } else {
+                pos = it->position(7);
+                site.source_text = (*it)[7].str();
+                site.name = (*it)[10].str();
+                site.type = parse_type_((*it)[8].str() + " " + (*it)[9].str() + "*");
+            }
+            found.emplace_back(pos, site);
+        }
+    }
+
+    /**
+     * (obj.*handler)(args) / (this->*handler)(args)
+     */
+    void detectCalls(const std::string& body, std::vector<std::pair<size_t, MemberPointerInfo>>& found) {
+        std::regex call_pattern(R"(\(\s*(\*?[a-zA-Z_][\w.]*|this)\s*(\.\*|->\*)\s*([a-zA-Z_][\w.]*)\s*\)\s*\()");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), call_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            size_t open = pos + it->length(0) - 1;
+            size_t close = findClosingParen(body, open);
+            if (close == std::string::npos) continue;
+
+            MemberPointerInfo site;
+            site.kind = MemberPointerInfo::Call;
+            site.source_text = body.substr(pos, close + 1 - pos);
+            site.object = (*it)[1].str();
+            site.through_pointer = (*it)[2].str() == "->*";
+            site.pointer = (*it)[3].str();
+            site.arguments = splitArguments(body.substr(open + 1, close - open - 1));
+            found.emplace_back(pos, site);
+        }
+    }
+
+    /**
+     * obj.*field / p->*field outside a call
+     */
+    void detectAccesses(const std::string& body, std::vector<std::pair<size_t, MemberPointerInfo>>& found) {
+        std::regex access_pattern(R"((\*?[a-zA-Z_][\w.]*|this)\s*(\.\*|->\*)\s*([a-zA-Z_][\w.]*))");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), access_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            if (inside(found, pos)) continue;
+
+            MemberPointerInfo site;
+            site.kind = MemberPointerInfo::Access;
+            site.source_text = it->str(0);
+            site.object = (*it)[1].str();
+            site.through_pointer = (*it)[2].str() == "->*";
+            site.pointer = (*it)[3].str();
+            found.emplace_back(pos, site);
+        }
+    }
+
+    /**
+     * &Machine::start / &Machine::speed naming a known class
+     */
+    void detectAddresses(const IR& ir, const std::string& body,
+                         std::vector<std::pair<size_t, MemberPointerInfo>>& found) {
+        std::regex address_pattern(R"((^|[^&\w])&\s*((?:\w+::)*)(\w+)::([a-zA-Z_]\w*)\b(?!\s*\())");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), address_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0) + it->length(1);
+            const ClassDecl* target = ir.findClass((*it)[3].str());
+            if (!target) continue;
+
+            MemberPointerInfo site;
+            site.kind = MemberPointerInfo::AddressOf;
+            site.source_text = body.substr(pos, it->position(0) + it->length(0) - pos);
+            site.class_name = target->name;
+            site.member = (*it)[4].str();
+
+            size_t methods = 0;
+            for (const auto& method : target->methods) {
+                if (method.name != site.member) continue;
+                methods++;
+                site.is_virtual = site.is_virtual || method.is_virtual;
+            }
+            bool is_field = std::any_of(target->fields.begin(), target->fields.end(),
+                                        [&](const Variable& field) { return field.name == site.member; });
+            if (methods == 0 && !is_field) continue;  // Enumerator or nested type
+            site.is_data_member = is_field;
+            site.is_overloaded = methods > 1;
+
+            found.emplace_back(pos, site);
+        }
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
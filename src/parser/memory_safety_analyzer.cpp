@@ -0,0 +1,438 @@
+/**
+ * Memory Safety Analyzer
+ * Finds classic C++ memory bugs in function bodies: use after free,
+ * double delete, overruns of fixed-size arrays and reads of uninitialized
+ * locals. Whether the translation fixes each one depends on how the
+ * variable is translated, so the code generator reports them
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+#include <map>
+#include <set>
+#include <cctype>
+
+namespace hybrid {
+
+/**
+ * Memory Safety Analyzer
+ * Statements are followed in source order. A delete inside a block that
+ * then returns, throws or leaves a loop does not reach the statements
+ * after that block; other branches are not told apart
+ */
+class MemorySafetyAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit MemorySafetyAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) analyzeFunction(method, &class_decl);
+        }
+        for (auto& func : ir.getFunctions()) analyzeFunction(func, nullptr);
+    }
+
+private:
+    TypeParser parse_type_;
+
+    struct Token {
+        enum Kind { Statement, Open, Close };
+        Kind kind = Statement;
+        std::string text;
+        size_t position = 0;
+    };
+
+    struct FixedArray {
+        size_t size = 0;
+        std::string declaration;
+        std::shared_ptr<Type> type;
+    };
+
+    struct Loop {
+        std::string variable;
+        long last = 0;                   // Highest index the loop reaches
+        std::string header;
+        int depth = 0;                   // Block of the body; kNextStatement without braces
+    };
+
+    static constexpr int kNextStatement = -1;
+
+    struct Freed {
+        std::string at;                  // The delete statement
+        int depth = 0;
+    };
+
+    void analyzeFunction(Function& func, const ClassDecl* owner) {
+        static const std::regex deletion(R"(^delete\s*(?:\[\s*\])?\s*(?:this\s*->\s*)?(\w+)$)");
+        static const std::regex exit(R"(^(?:return|throw|break|continue|goto)\b)");
+        static const std::regex array_declaration(
+            R"(^(?:(?:static|const|constexpr)\s+)*([A-Za-z_][\w:<>]*)\s+(\w+)\s*\[\s*(\d{1,9})\s*\])");
+        static const std::regex loop_header(
+            R"(^for\s*\(\s*(?:[A-Za-z_][\w:]*\s+)*(\w+)\s*=\s*(\d+)\s*;\s*\1\s*(<=|<)\s*(\d{1,9}|sizeof\s*\(\s*\w+\s*\))\s*;)");
+        static const std::regex scalar_declaration(
+            R"(^((?:unsigned|signed|short|long|int|char|float|double|bool|size_t|u?int(?:8|16|32|64)_t)(?:\s+(?:unsigned|short|long|int|char|double))*|[A-Za-z_][\w:]*\s*\*)\s*(\w+)$)");
+
+        std::map<std::string, FixedArray> arrays;
+        if (owner) {
+            for (const auto& field : owner->fields) {
+                if (field.type && field.type->kind == TypeKind::Array && isNumber(field.type->array_size)) {
+                    std::string element = field.type->element_type ? field.type->element_type->name : "";
+                    arrays[field.name] = {std::stoul(field.type->array_size),
+                                          element + " " + field.name + "[" + field.type->array_size + "]",
+                                          field.type};
+                }
+            }
+        }
+
+        std::map<std::string, Freed> freed;
+        std::map<std::string, std::string> uninitialized;   // Local -> its declaration
+        std::vector<Loop> loops;
+        bool loop_pending = false;
+        int depth = 0;
+
+        for (const auto& token : tokens(func.body)) {
+            if (token.kind == Token::Open) {
+                depth++;
+                if (loop_pending) loops.back().depth = depth;
+                loop_pending = false;
+                continue;
+            }
+            if (token.kind == Token::Close) {
+                for (auto& entry : freed) entry.second.depth = std::min(entry.second.depth, depth - 1);
+                loops.erase(std::remove_if(loops.begin(), loops.end(),
+                                           [depth](const Loop& loop) { return loop.depth == depth; }),
+                            loops.end());
+                depth--;
+                continue;
+            }
+
+            const std::string& text = token.text;
+            std::smatch match;
+
+            // A loop without braces covers the next statement only
+            loops.erase(std::remove_if(loops.begin(), loops.end(),
+                                       [](const Loop& loop) { return loop.depth == kNextStatement; }),
+                        loops.end());
+            if (loop_pending) {
+                loops.back().depth = kNextStatement;
+                loop_pending = false;
+            }
+            if (std::regex_search(text, match, loop_header)) {
+                Loop loop;
+                loop.variable = match[1].str();
+                loop.header = text;
+                std::string bound = match[4].str();
+                long limit = 0;
+                if (isNumber(bound)) {
+                    limit = std::stol(bound);
+                } else {
+                    // sizeof counts bytes: i < sizeof(values) overruns an int[] by four times
+                    std::string array = std::regex_replace(bound, std::regex(R"(^sizeof\s*\(\s*(\w+)\s*\)$)"), "$1");
+                    auto found = arrays.find(array);
+                    size_t element = found != arrays.end() ? elementSize(found->second.type) : 0;
+                    if (element <= 1) continue;
+                    limit = static_cast<long>(found->second.size * element);
+                }
+                loop.last = match[3].str() == "<=" ? limit : limit - 1;
+                loops.push_back(loop);
+
+                // for (...) data[i] = 0: the body is part of the statement
+                size_t open = text.find('(');
+                size_t close = findClosingParen(text, open);
+                std::string body = close == std::string::npos ? "" : trim(text.substr(close + 1));
+                if (body.empty()) {
+                    loop_pending = true;
+                } else {
+                    checkIndexes(func, body, arrays, loops);
+                    loops.pop_back();
+                }
+                continue;
+            }
+
+            // Declarations
+            static const std::set<std::string> keywords = {"return", "delete", "else", "throw", "case", "goto"};
+            if (std::regex_search(text, match, array_declaration) && !keywords.count(match[1].str())) {
+                FixedArray array;
+                array.size = std::stoul(match[3].str());
+                array.declaration = text;
+                array.type = parse_type_(match[1].str());
+                arrays[match[2].str()] = array;
+                uninitialized.erase(match[2].str());
+                freed.erase(match[2].str());
+                continue;
+            }
+            if (std::regex_match(text, match, scalar_declaration)) {
+                uninitialized[match[2].str()] = text;
+                freed.erase(match[2].str());
+                continue;
+            }
+
+            checkIndexes(func, text, arrays, loops);
+            checkUninitialized(func, owner, text, token.position, uninitialized);
+
+            if (std::regex_match(text, match, deletion)) {
+                std::string name = match[1].str();
+                auto earlier = freed.find(name);
+                if (earlier != freed.end()) {
+                    addIssue(func, owner, MemoryIssueInfo::DoubleDelete, name, text, earlier->second.at,
+                             token.position);
+                } else {
+                    freed[name] = {text, depth};
+                }
+                continue;
+            }
+
+            for (auto it = freed.begin(); it != freed.end();) {
+                const std::string& name = it->first;
+                if (dereferences(text, name)) {
+                    addIssue(func, owner, MemoryIssueInfo::UseAfterFree, name, text, it->second.at, token.position);
+                }
+                // p = nullptr, p = new Node: the name no longer refers to the freed memory
+                std::regex assigned("^(?:[A-Za-z_][\\w:<>,\\s]*[\\s*&]+)?(?:this\\s*->\\s*)?" + name +
+                                    "\\s*=(?!=)");
+                if (std::regex_search(text, assigned)) {
+                    it = freed.erase(it);
+                } else {
+                    ++it;
+                }
+            }
+
+            // Nothing after this statement runs, up to the end of the block
+            if (std::regex_search(text, exit)) {
+                for (auto it = freed.begin(); it != freed.end();) {
+                    it = it->second.depth >= depth ? freed.erase(it) : std::next(it);
+                }
+            }
+        }
+    }
+
+    void checkIndexes(Function& func, const std::string& text, const std::map<std::string, FixedArray>& arrays,
+                      const std::vector<Loop>& loops) {
+        for (const auto& entry : arrays) {
+            std::regex index("\\b" + entry.first + "\\s*\\[\\s*(\\w+)\\s*\\]");
+            for (auto it = std::sregex_iterator(text.begin(), text.end(), index); it != std::sregex_iterator();
+                 ++it) {
+                std::string subscript = (*it)[1].str();
+                if (isNumber(subscript)) {
+                    if (std::stoul(subscript) < entry.second.size) continue;
+                    addIssue(func, nullptr, MemoryIssueInfo::BufferOverrun, entry.first, text,
+                             entry.second.declaration, 0, entry.second.type);
+                    continue;
+                }
+                for (const auto& loop : loops) {
+                    if (loop.variable != subscript || loop.last < static_cast<long>(entry.second.size)) continue;
+                    if (addIssue(func, nullptr, MemoryIssueInfo::BufferOverrun, entry.first, text,
+                                 entry.second.declaration, 0, entry.second.type)) {
+                        func.memory_issues.back().loop = loop.header;
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * First mention of a local declared without a value: assigned,
+     * handed to a call (it may be an out parameter), or read
+     */
+    void checkUninitialized(Function& func, const ClassDecl* owner, const std::string& text, size_t position,
+                            std::map<std::string, std::string>& uninitialized) {
+        for (auto it = uninitialized.begin(); it != uninitialized.end();) {
+            const std::string& name = it->first;
+            std::regex mention("\\b" + name + "\\b");
+            std::smatch match;
+            if (!std::regex_search(text, match, mention)) {
+                ++it;
+                continue;
+            }
+
+            std::smatch assignment;
+            bool read;
+            if (std::regex_search(text, assignment, std::regex("^" + name + "\\s*=(?!=)"))) {
+                std::string value = assignment.suffix().str();
+                read = std::regex_search(value, mention);
+            } else {
+                read = !passedOn(text, static_cast<size_t>(match.position(0)));
+            }
+            if (read) {
+                addIssue(func, owner, MemoryIssueInfo::UninitializedRead, name, text, it->second, position);
+            }
+            it = uninitialized.erase(it);
+        }
+    }
+
+    /**
+     * &x, std::cin >> x, or an argument of a call
+     */
+    static bool passedOn(const std::string& text, size_t at) {
+        size_t before = text.find_last_not_of(" \t", at == 0 ? std::string::npos : at - 1);
+        if (at > 0 && before != std::string::npos) {
+            if (text[before] == '&' && (before == 0 || text[before - 1] != '&')) return true;
+            if (text[before] == '>' && before > 0 && text[before - 1] == '>') return true;
+        }
+
+        int depth = 0;
+        for (size_t i = at; i-- > 0;) {
+            if (text[i] == ')') depth++;
+            if (text[i] != '(') continue;
+            if (depth-- > 0) continue;
+            size_t end = text.find_last_not_of(" \t", i == 0 ? std::string::npos : i - 1);
+            if (i == 0 || end == std::string::npos) return false;
+            size_t start = end;
+            while (start > 0 && (std::isalnum(static_cast<unsigned char>(text[start - 1])) || text[start - 1] == '_')) {
+                --start;
+            }
+            static const std::set<std::string> keywords = {"if", "while", "for", "switch", "return"};
+            std::string callee = text.substr(start, end + 1 - start);
+            return !callee.empty() && (std::isalpha(static_cast<unsigned char>(callee[0])) || callee[0] == '_') &&
+                   !keywords.count(callee);
+        }
+        return false;
+    }
+
+    /**
+     * p->next, p[0] or *p, but not int* p or a * p
+     */
+    static bool dereferences(const std::string& text, const std::string& name) {
+        std::regex member("(?:^|[^\\w.])(?:this\\s*->\\s*)?" + name + "\\s*(?:->|\\[)");
+        if (std::regex_search(text, member)) return true;
+
+        std::regex star("\\*\\s*(?:this\\s*->\\s*)?" + name + "\\b");
+        for (auto it = std::sregex_iterator(text.begin(), text.end(), star); it != std::sregex_iterator(); ++it) {
+            size_t at = static_cast<size_t>(it->position(0));
+            size_t before = at == 0 ? std::string::npos : text.find_last_not_of(" \t", at - 1);
+            if (before == std::string::npos) return true;
+            char c = text[before];
+            if (!std::isalnum(static_cast<unsigned char>(c)) && c != '_' && c != ')' && c != ']' && c != '>') {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    // False if the same bug was already recorded
+    bool addIssue(Function& func, const ClassDecl* owner, MemoryIssueInfo::IssueKind kind, const std::string& name,
+                  const std::string& text, const std::string& cause, size_t position,
+                  std::shared_ptr<Type> type = nullptr) {
+        for (const auto& issue : func.memory_issues) {
+            if (issue.kind == kind && issue.variable == name && issue.source_text == text) return false;
+        }
+        MemoryIssueInfo issue;
+        issue.kind = kind;
+        issue.variable = name;
+        issue.source_text = text;
+        issue.cause = cause;
+        issue.type = type ? type : variableType(func, owner, name, position);
+        func.memory_issues.push_back(issue);
+        return true;
+    }
+
+    /**
+     * Type of a parameter, a local declared before limit, or a field
+     */
+    std::shared_ptr<Type> variableType(const Function& func, const ClassDecl* owner, const std::string& name,
+                                       size_t limit) const {
+        for (const auto& param : func.parameters) {
+            if (param.name == name) return param.type;
+        }
+
+        std::string before = func.body.substr(0, std::min(limit, func.body.size()));
+        std::regex declaration(R"((?:^|[;{}(,])\s*((?:const\s+)?[A-Za-z_][\w:<>]*(?:\s+[A-Za-z_]\w*)*\s*\**)\s*)" +
+                               name + R"(\s*(?:=|;|\{|\)|,))");
+        std::shared_ptr<Type> found;
+        for (auto it = std::sregex_iterator(before.begin(), before.end(), declaration);
+             it != std::sregex_iterator(); ++it) {
+            static const std::set<std::string> not_types = {"return", "else", "case", "delete", "throw", "do",
+                                                            "goto"};
+            std::string type_str = trim((*it)[1].str());
+            if (not_types.count(type_str)) continue;
+            found = parse_type_(type_str);
+        }
+        if (found) return found;
+
+        if (owner) {
+            for (const auto& field : owner->fields) {
+                if (field.name == name) return field.type;
+            }
+        }
+        return nullptr;
+    }
+
+    /**
+     * Bytes of an array element, 0 if unknown (class types); long is
+     * taken as LP64, which only matters for its multiple
+     */
+    static size_t elementSize(const std::shared_ptr<Type>& type) {
+        static const std::map<std::string, size_t> sizes = {
+            {"char", 1}, {"signed char", 1}, {"unsigned char", 1}, {"bool", 1}, {"int8_t", 1}, {"uint8_t", 1},
+            {"short", 2}, {"unsigned short", 2}, {"int16_t", 2}, {"uint16_t", 2},
+            {"int", 4}, {"unsigned", 4}, {"unsigned int", 4}, {"float", 4}, {"int32_t", 4}, {"uint32_t", 4},
+            {"long", 8}, {"unsigned long", 8}, {"long long", 8}, {"unsigned long long", 8}, {"double", 8},
+            {"int64_t", 8}, {"uint64_t", 8}, {"size_t", 8}};
+        if (!type) return 0;
+        if (type->kind == TypeKind::Array) return elementSize(type->element_type);
+        if (type->kind == TypeKind::Pointer) return 8;
+        auto found = sizes.find(type->name);
+        return found != sizes.end() ? found->second : 0;
+    }
+
+    /**
+     * Statements and braces of a body in order; string and character
+     * literals are blanked so their text is not mistaken for code
+     */
+    static std::vector<Token> tokens(const std::string& body) {
+        std::vector<Token> result;
+        std::string current;
+        size_t start = 0;
+        int parens = 0;
+        auto flush = [&](size_t next) {
+            std::string text = trim(current);
+            if (!text.empty()) result.push_back({Token::Statement, text, start});
+            current.clear();
+            start = next;
+        };
+        for (size_t i = 0; i < body.size(); ++i) {
+            char c = body[i];
+            if (c == '"' || c == '\'') {
+                size_t end = i;
+                for (++end; end < body.size() && body[end] != c; ++end) {
+                    if (body[end] == '\\') ++end;
+                }
+                current += c + std::string(std::min(end, body.size()) - i - 1, ' ') + c;
+                i = end;
+                continue;
+            }
+            if (c == '(') parens++;
+            else if (c == ')') parens--;
+            if (parens == 0 && c == ';') {
+                flush(i + 1);
+            } else if (parens == 0 && (c == '{' || c == '}')) {
+                flush(i + 1);
+                result.push_back({c == '{' ? Token::Open : Token::Close, "", i});
+            } else {
+                current += c;
+            }
+        }
+        flush(body.size());
+        return result;
+    }
+
+    // Short enough for stoul
+    static bool isNumber(const std::string& text) {
+        return !text.empty() && text.size() < 10 && std::all_of(text.begin(), text.end(), [](char c) {
+            return std::isdigit(static_cast<unsigned char>(c));
+        });
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,227 @@
+/**
+ * Move Analyzer
+ * Analyzes C++ move semantics and prepares for conversion to Rust by-value moves
+ */
+
+#include "ir.h"
+#include <regex>
+#include <algorithm>
+
+namespace hybrid {
+
+/**
+ * Move Analyzer
+ * Detects rvalue-reference parameters, move constructors, std::move and std::exchange
+ */
+class MoveAnalyzer {
+public:
+    /**
+     * Analyze class for move constructors and moves inside its methods
+     */
+    void analyzeClass(ClassDecl& class_decl) {
+        std::vector<std::string> field_names;
+        for (const auto& field : class_decl.fields) {
+            field_names.push_back(field.name);
+        }
+
+        for (auto& method : class_decl.methods) {
+            detectMoveConstructor(method, class_decl.name);
+            analyzeFunction(method, field_names);
+        }
+    }
+
+    /**
+     * Analyze function body for move patterns
+     */
+    void analyzeFunction(Function& func,
+                         const std::vector<std::string>& field_names = {}) {
+        // rvalue-reference parameters are taken by value in Rust
+        detectRValueParameters(func);
+
+        // Detect std::move(expr)
+        detectMoveExpressions(func, field_names);
+
+        // Detect std::exchange(expr, value)
+        detectExchangeExpressions(func, field_names);
+
+        // Initializer list first, then the body, each in source order
+        std::sort(func.moves.begin(), func.moves.end(), [](const MoveInfo& a, const MoveInfo& b) {
+            if (a.in_initializer != b.in_initializer) return a.in_initializer;
+            return a.offset < b.offset;
+        });
+    }
+
+private:
+    /**
+     * Detect move constructor: ClassName(ClassName&& other)
+     */
+    void detectMoveConstructor(Function& func, const std::string& class_name) {
+        if (!func.is_constructor || func.parameters.size() != 1) {
+            return;
+        }
+
+        const auto& type = func.parameters[0].type;
+        if (type && type->kind == TypeKind::RValueReference &&
+            type->element_type && type->element_type->name == class_name) {
+            func.is_move_constructor = true;
+        }
+    }
+
+    /**
+     * Record T&& parameters as moved
+     */
+    void detectRValueParameters(Function& func) {
+        for (const auto& param : func.parameters) {
+            if (param.type && param.type->kind == TypeKind::RValueReference) {
+                addMovedParam(func, param.name);
+            }
+        }
+    }
+
+    /**
+     * Detect std::move(expr) expressions
+     */
+    void detectMoveExpressions(Function& func, const std::vector<std::string>& field_names) {
+        std::string body = func.member_initializers + "\n" + func.body;
+
+        std::regex move_pattern(R"(std::move\s*\(\s*([a-zA-Z_][\w.\[\]]*(?:->[\w.\[\]]+)*)\s*\))");
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, move_pattern)) {
+            MoveInfo info;
+            info.kind = MoveInfo::Move;
+            info.source_text = match[0].str();
+            info.expression = match[1].str();
+            locate(func, static_cast<size_t>(match.position(0) + (search_start - body.cbegin())), info);
+
+            size_t end_pos = (match.suffix().first - body.cbegin());
+            info.used_after_move = isUsedAfter(body, info.expression, end_pos);
+            info.moves_out_of_borrow = isBorrowedPlace(func, info.expression, field_names);
+
+            // Moving a parameter transfers ownership into the callee
+            if (isParameter(func, info.expression)) {
+                addMovedParam(func, info.expression);
+            }
+
+            func.moves.push_back(info);
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * Detect std::exchange(expr, value) expressions
+     */
+    void detectExchangeExpressions(Function& func, const std::vector<std::string>& field_names) {
+        std::string body = func.member_initializers + "\n" + func.body;
+
+        std::regex exchange_pattern(
+            R"(std::exchange\s*\(\s*([a-zA-Z_][\w.\[\]]*(?:->[\w.\[\]]+)*)\s*,\s*([^()]*(?:\([^()]*\)[^()]*)*)\))"
+        );
+
+        std::smatch match;
+        std::string::const_iterator search_start(body.cbegin());
+
+        while (std::regex_search(search_start, body.cend(), match, exchange_pattern)) {
+            MoveInfo info;
+            info.kind = MoveInfo::Exchange;
+            info.source_text = match[0].str();
+            info.expression = match[1].str();
+            locate(func, static_cast<size_t>(match.position(0) + (search_start - body.cbegin())), info);
+            info.new_value = trim(match[2].str());
+
+            // std::exchange always leaves a well-defined value behind
+            info.used_after_move = true;
+            info.moves_out_of_borrow = isBorrowedPlace(func, info.expression, field_names);
+
+            func.moves.push_back(info);
+            search_start = match.suffix().first;
+        }
+    }
+
+    /**
+     * Record whether a site at `pos` of the initializer list + body text
+     * lies in the initializer list, and its offset within that part
+     */
+    void locate(const Function& func, size_t pos, MoveInfo& info) {
+        size_t body_start = func.member_initializers.size() + 1;
+        info.in_initializer = pos < body_start;
+        info.offset = info.in_initializer ? pos : pos - body_start;
+    }
+
+    /**
+     * Check if the moved-from object is mentioned again after the move.
+     * A plain reassignment does not read the moved-from state.
+     */
+    bool isUsedAfter(const std::string& body, const std::string& expr, size_t pos) {
+        std::string rest = body.substr(pos);
+        std::regex use_pattern("(^|[^\\w.>])" + escapeRegex(expr) + "(?!\\w)(\\s*=(?!=))?");
+
+        std::smatch match;
+        if (std::regex_search(rest, match, use_pattern)) {
+            return !match[2].matched;
+        }
+        return false;
+    }
+
+    /**
+     * Check if the expression names a place Rust cannot move out of directly
+     * (a member field, or something reached through a reference/pointer)
+     */
+    bool isBorrowedPlace(const Function& func, const std::string& expr,
+                         const std::vector<std::string>& field_names) {
+        std::string root = expr.substr(0, expr.find_first_of(".-["));
+
+        if (root == "this") {
+            return true;
+        }
+
+        if (std::find(field_names.begin(), field_names.end(), root) != field_names.end()) {
+            return true;
+        }
+
+        for (const auto& param : func.parameters) {
+            if (param.name != root || !param.type) continue;
+
+            // Owned parameters (by value or T&&) can be moved out of
+            if (param.type->kind == TypeKind::Reference ||
+                param.type->kind == TypeKind::Pointer) {
+                return true;
+            }
+            return false;
+        }
+
+        // Locals: can be moved unless we move out of a sub-place through a pointer
+        return expr.find("->") != std::string::npos;
+    }
+
+    bool isParameter(const Function& func, const std::string& name) {
+        for (const auto& param : func.parameters) {
+            if (param.name == name) return true;
+        }
+        return false;
+    }
+
+    void addMovedParam(Function& func, const std::string& name) {
+        if (name.empty()) return;
+        if (std::find(func.moved_params.begin(), func.moved_params.end(), name) ==
+            func.moved_params.end()) {
+            func.moved_params.push_back(name);
+        }
+    }
+
+    std::string escapeRegex(const std::string& str) {
+        static const std::regex special_chars(R"([.^$|()\[\]{}*+?\\-])");
+        return std::regex_replace(str, special_chars, R"(\$&)");
+    }
+
+    std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
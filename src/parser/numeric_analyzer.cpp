@@ -0,0 +1,51 @@
+/**
+ * Numeric Analyzer
+ * Finds std::numeric_limits<T> members, which become associated
+ * constants of the Rust type (i32::MIN, f64::EPSILON)
+ */
+
+#include "ir.h"
+#include <regex>
+#include <functional>
+
+namespace hybrid {
+
+/**
+ * Numeric Analyzer
+ * Per-class pass: T is parsed like any other type. Numeric literals need
+ * no types and are rewritten by the code generator
+ */
+class NumericAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit NumericAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    void analyzeClass(ClassDecl& class_decl) {
+        for (auto& method : class_decl.methods) {
+            analyzeFunction(method);
+        }
+    }
+
+    void analyzeFunction(Function& func) {
+        const std::string& body = func.body;
+        if (body.find("numeric_limits") == std::string::npos) return;
+
+        // max(), lowest(), epsilon() are functions; digits, is_signed are constants
+        static const std::regex limit_pattern(
+            R"(\b(?:std\s*::\s*)?numeric_limits\s*<\s*([^<>]+?)\s*>\s*::\s*(\w+)(\s*\(\s*\))?)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), limit_pattern);
+             it != std::sregex_iterator(); ++it) {
+            NumericLimitInfo limit;
+            limit.source_text = it->str(0);
+            limit.type = parse_type_((*it)[1].str());
+            limit.member = (*it)[2].str();
+            if (limit.type) func.numeric_limits.push_back(limit);
+        }
+    }
+
+private:
+    TypeParser parse_type_;
+};
+
+} // namespace hybrid
@@ -1,14 +1,24 @@
 #include "parser.h"
+#include "stack.h"
 #include "simple_cpp_parser.cpp"
 
 namespace hybrid {
 
-IR Parser::parseFile(const std::string& filename) {
-    return SimpleCppParser::parseFile(filename);
+IR Parser::parseFile(const std::string& filename, CppStandard standard,
+                     const LogMacroTable& log_macros) {
+    IR ir;
+    runWithStack([&] { ir = SimpleCppParser::parseFile(filename, standard, log_macros); });
+    return ir;
 }
 
-IR Parser::parseString(const std::string& source) {
-    return SimpleCppParser::parseString(source);
+IR Parser::parseString(const std::string& source, CppStandard standard,
+                       const LogMacroTable& log_macros, const RuntimeTrace& trace,
+                       InstantiationCache* instantiation_cache, const Pipeline* pipeline) {
+    IR ir;
+    runWithStack([&] {
+        ir = SimpleCppParser::parseString(source, standard, log_macros, trace, instantiation_cache, pipeline);
+    });
+    return ir;
 }
 
 } // namespace hybrid
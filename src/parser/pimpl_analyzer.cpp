@@ -0,0 +1,351 @@
+/**
+ * pImpl Analyzer
+ * Finds the pointer-to-implementation idiom (a unique_ptr<Impl> member
+ * and methods forwarding to it) and merges the implementation class into
+ * its owner: one Rust struct, without a Box in between
+ */
+
+#include "ir.h"
+#include <regex>
+#include <algorithm>
+#include <map>
+#include <set>
+#include <cctype>
+
+namespace hybrid {
+
+/**
+ * pImpl Analyzer
+ * Runs before the other passes, so they see the merged class. The Impl
+ * class must be defined in the source and used by its owner only;
+ * anything else is reported and left as it is
+ */
+class PimplAnalyzer {
+public:
+    void analyze(IR& ir) {
+        std::set<std::string> merged;
+        for (auto& owner : ir.getClasses()) {
+            if (merged.count(owner.name)) continue;
+            for (size_t i = 0; i < owner.fields.size(); ++i) {
+                const ClassDecl* impl = implementationOf(ir, owner, owner.fields[i]);
+                if (!impl || merged.count(impl->name)) continue;
+
+                std::string reason = usedElsewhere(ir, owner, *impl);
+                ClassDecl flattened = owner;
+                if (reason.empty()) reason = flatten(flattened, i, *impl);
+                if (!reason.empty()) {
+                    report(ir, Diagnostic::Note, owner.name,
+                           "'" + owner.name + "' uses the pImpl idiom, but " + impl->name + " stays behind '" +
+                               owner.fields[i].name + "': " + reason);
+                    continue;
+                }
+
+                report(ir, Diagnostic::Note, owner.name,
+                       "'" + owner.name + "' uses the pImpl idiom: the fields of " + impl->name +
+                           " are fields of " + owner.name + " and its methods no longer forward through '" +
+                           owner.fields[i].name + "'");
+                merged.insert(impl->name);
+                owner = flattened;
+                break;
+            }
+        }
+
+        auto& classes = ir.getClasses();
+        classes.erase(std::remove_if(classes.begin(), classes.end(),
+                                     [&merged](const ClassDecl& class_decl) {
+                                         return merged.count(class_decl.name) > 0;
+                                     }),
+                      classes.end());
+    }
+
+private:
+    /**
+     * The Impl class a member points to: std::unique_ptr<Impl> to a class in
+     * the source (Impl, Widget::Impl) named like an implementation or
+     * forwarded to by a method
+     */
+    static const ClassDecl* implementationOf(const IR& ir, const ClassDecl& owner, const Variable& field) {
+        const auto& type = field.type;
+        if (!type || type->name.find("unique_ptr<") == std::string::npos || !type->element_type) return nullptr;
+        std::string pointee = type->element_type->name;
+
+        // The nested class first: Widget::Impl, then Impl
+        const ClassDecl* impl = nullptr;
+        for (const auto& class_decl : ir.getClasses()) {
+            if (&class_decl == &owner) continue;
+            if (class_decl.name == owner.name + "::" + pointee) {
+                impl = &class_decl;
+                break;
+            }
+            if (class_decl.name == pointee) impl = &class_decl;
+        }
+        if (!impl || impl->is_template || owner.is_template) return nullptr;
+
+        static const std::regex impl_name(R"((?:^|::|\w)(?:Impl|Private|Pimpl)$)");
+        if (std::regex_search(impl->name, impl_name)) return impl;
+        for (const auto& method : owner.methods) {
+            std::string target;
+            if (forwardedMethod(method, field.name, target)) return impl;
+        }
+        return nullptr;
+    }
+
+    /**
+     * Why Impl cannot be merged because of another class: it is named
+     * outside its owner, or holds a pointer back to it
+     */
+    static std::string usedElsewhere(const IR& ir, const ClassDecl& owner, const ClassDecl& impl) {
+        // Outside its owner a nested class is only named in full
+        std::regex named("\\b" + impl.name + "\\b");
+        for (const auto& class_decl : ir.getClasses()) {
+            if (&class_decl == &owner || class_decl.name == impl.name) continue;
+            for (const auto& field : class_decl.fields) {
+                if (field.type && std::regex_search(field.type->name, named)) return "it is used by " + class_decl.name;
+            }
+            for (const auto& method : class_decl.methods) {
+                if (std::regex_search(method.body, named)) return "it is used by " + class_decl.name;
+            }
+        }
+        for (const auto& field : impl.fields) {
+            if (field.type && std::regex_search(field.type->name, std::regex("\\b" + owner.name + "\\b"))) {
+                return "'" + field.name + "' points back to " + owner.name;
+            }
+        }
+        return "";
+    }
+
+    /**
+     * Merge impl into owner in place of its field at index; the reason it
+     * cannot be merged if it is not
+     */
+    static std::string flatten(ClassDecl& owner, size_t index, const ClassDecl& impl) {
+        std::string pointer = owner.fields[index].name;
+
+        // Fields take the place of the pointer
+        std::vector<Variable> fields;
+        for (const auto& field : impl.fields) {
+            for (const auto& existing : owner.fields) {
+                if (existing.name == field.name) return "'" + field.name + "' is declared in both classes";
+            }
+            fields.push_back(field);
+        }
+
+        std::set<std::string> consumed;
+        std::regex through_pointer("(?:\\bthis\\s*->\\s*)?\\b" + pointer + "\\s*->\\s*");
+        std::regex other_use("\\b" + pointer + "\\b");
+        for (auto& method : owner.methods) {
+            if (method.is_constructor) {
+                std::string reason = mergeConstructor(method, pointer, impl, consumed);
+                if (!reason.empty()) return reason;
+                continue;
+            }
+
+            // Forwarding methods become the implementation
+            std::string target;
+            if (forwardedMethod(method, pointer, target)) {
+                const Function* implementation = findMethod(impl, target, method.parameters.size());
+                if (!implementation) return impl.name + " has no method " + target + " to forward to";
+                for (size_t i = 0; i < method.parameters.size(); ++i) {
+                    method.parameters[i].name = implementation->parameters[i].name;
+                }
+                method.body = implementation->body;
+                // A const method could modify the Impl: the pointer did not propagate const
+                method.is_const = method.is_const && implementation->is_const;
+                consumed.insert(target);
+                continue;
+            }
+
+            // impl_->count_ is count_
+            method.body = std::regex_replace(method.body, through_pointer, "");
+            if (std::regex_search(method.body, other_use)) {
+                return owner.name + "::" + method.name + " uses '" + pointer + "' other than through ->";
+            }
+        }
+
+        // Helpers of the implementation become private methods
+        std::vector<std::string> helpers;
+        for (const auto& method : impl.methods) {
+            if (method.is_constructor || consumed.count(method.name)) continue;
+            if (method.is_destructor) {
+                if (trim(method.body).empty()) continue;
+                auto destructor = std::find_if(owner.methods.begin(), owner.methods.end(),
+                                               [](const Function& m) { return m.is_destructor; });
+                if (destructor == owner.methods.end()) {
+                    Function added = method;
+                    added.name = owner.name;
+                    owner.methods.push_back(added);
+                } else {
+                    destructor->body += "\n" + method.body;
+                    destructor->is_defaulted = false;
+                }
+                continue;
+            }
+            for (const auto& existing : owner.methods) {
+                if (existing.name == method.name) {
+                    return owner.name + "::" + method.name + " does not forward to " + impl.name + "::" + method.name;
+                }
+            }
+            owner.methods.push_back(method);
+            helpers.push_back(method.name);
+        }
+
+        owner.fields.erase(owner.fields.begin() + static_cast<std::ptrdiff_t>(index));
+        owner.fields.insert(owner.fields.begin() + static_cast<std::ptrdiff_t>(index), fields.begin(), fields.end());
+
+        ClassDecl::AccessSection merged;
+        merged.level = ClassDecl::AccessSection::Private;
+        for (const auto& field : fields) merged.members.push_back(field.name);
+        merged.members.insert(merged.members.end(), helpers.begin(), helpers.end());
+        for (auto& section : owner.access_sections) {
+            auto& members = section.members;
+            members.erase(std::remove(members.begin(), members.end(), pointer), members.end());
+        }
+        owner.access_sections.push_back(merged);
+        return "";
+    }
+
+    /**
+     * impl_(std::make_unique<Impl>(size)) in a constructor of the owner:
+     * the member initializers and body of Impl(size) instead
+     */
+    static std::string mergeConstructor(Function& ctor, const std::string& pointer, const ClassDecl& impl,
+                                        std::set<std::string>& consumed) {
+        static const std::regex creation(R"(^(?:std::make_unique\s*<[^>]*>|new\s+[\w:]+)\s*\((.*)\)$)");
+        std::vector<std::string> initializers;
+        std::string arguments;
+        bool created = false;
+        for (const auto& initializer : splitTopLevel(ctor.member_initializers)) {
+            size_t open = initializer.find('(');
+            if (open == std::string::npos || trim(initializer.substr(0, open)) != pointer) {
+                initializers.push_back(initializer);
+                continue;
+            }
+            std::smatch match;
+            std::string value = trim(initializer.substr(open + 1, initializer.rfind(')') - open - 1));
+            if (!std::regex_match(value, match, creation)) return "'" + initializer + "' does not create the Impl";
+            arguments = match[1].str();
+            created = true;
+        }
+
+        // impl_ = std::make_unique<Impl>(size); in the body
+        std::smatch assignment;
+        std::regex assigned("(?:\\bthis\\s*->\\s*)?\\b" + pointer + R"(\s*=\s*((?:std::make_unique\s*<[^>]*>|new\s+[\w:]+)\s*\(([^;]*)\))\s*;)");
+        if (!created && std::regex_search(ctor.body, assignment, assigned)) {
+            arguments = assignment[2].str();
+            ctor.body = assignment.prefix().str() + assignment.suffix().str();
+            created = true;
+        }
+        if (!created) return "";
+
+        std::vector<std::string> args = splitTopLevel(arguments);
+        const Function* impl_ctor = nullptr;
+        for (const auto& method : impl.methods) {
+            if (method.is_constructor && !method.is_copy_constructor && !method.is_move_constructor &&
+                method.parameters.size() == args.size()) {
+                impl_ctor = &method;
+            }
+        }
+        if (!impl_ctor) {
+            if (!args.empty()) return impl.name + " has no constructor taking " + std::to_string(args.size());
+            ctor.member_initializers = join(initializers);
+            return "";
+        }
+
+        // Parameters of Impl(...) are the arguments passed to it
+        auto substitute = [&](std::string text) {
+            std::string result = text;
+            std::map<std::string, std::string> marks;
+            for (size_t i = 0; i < args.size(); ++i) {
+                std::string mark = "\x01" + std::to_string(i) + "\x01";
+                result = std::regex_replace(result, std::regex("\\b" + impl_ctor->parameters[i].name + "\\b"), mark);
+                marks[mark] = args[i];
+            }
+            for (const auto& entry : marks) {
+                size_t pos;
+                while ((pos = result.find(entry.first)) != std::string::npos) {
+                    result.replace(pos, entry.first.size(), entry.second);
+                }
+            }
+            return result;
+        };
+        for (const auto& initializer : splitTopLevel(impl_ctor->member_initializers)) {
+            initializers.push_back(substitute(initializer));
+        }
+        ctor.member_initializers = join(initializers);
+        if (!trim(impl_ctor->body).empty()) ctor.body = substitute(impl_ctor->body) + "\n" + ctor.body;
+        consumed.insert(impl_ctor->name);
+        return "";
+    }
+
+    /**
+     * { impl_->draw(x); } or { return impl_->size(); }, the arguments
+     * being the parameters in order
+     */
+    static bool forwardedMethod(const Function& method, const std::string& pointer, std::string& target) {
+        std::regex forwarding("^(?:return\\s+)?(?:this\\s*->\\s*)?" + pointer +
+                              R"(\s*->\s*(\w+)\s*\(([^;]*)\)\s*;$)");
+        std::smatch match;
+        std::string body = trim(method.body);
+        if (method.is_constructor || method.is_destructor || !std::regex_match(body, match, forwarding)) {
+            return false;
+        }
+        std::vector<std::string> args = splitTopLevel(match[2].str());
+        if (args.size() != method.parameters.size()) return false;
+        for (size_t i = 0; i < args.size(); ++i) {
+            std::string arg = std::regex_replace(args[i], std::regex(R"(^std::(?:move|forward<[^>]*>)\s*\((.*)\)$)"),
+                                                 "$1");
+            if (trim(arg) != method.parameters[i].name) return false;
+        }
+        target = match[1].str();
+        return true;
+    }
+
+    static const Function* findMethod(const ClassDecl& class_decl, const std::string& name, size_t arity) {
+        for (const auto& method : class_decl.methods) {
+            if (method.name == name && !method.is_constructor && method.parameters.size() == arity) return &method;
+        }
+        return nullptr;
+    }
+
+    static std::vector<std::string> splitTopLevel(const std::string& text) {
+        std::vector<std::string> parts;
+        std::string current;
+        int depth = 0;
+        for (char c : text) {
+            if (c == '(' || c == '<' || c == '{' || c == '[') depth++;
+            if (c == ')' || c == '>' || c == '}' || c == ']') depth--;
+            if (c == ',' && depth == 0) {
+                if (!trim(current).empty()) parts.push_back(trim(current));
+                current.clear();
+                continue;
+            }
+            current += c;
+        }
+        if (!trim(current).empty()) parts.push_back(trim(current));
+        return parts;
+    }
+
+    static std::string join(const std::vector<std::string>& parts) {
+        std::string result;
+        for (const auto& part : parts) result += (result.empty() ? "" : ", ") + part;
+        return result;
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+
+    static void report(IR& ir, Diagnostic::Severity severity, const std::string& context,
+                       const std::string& message) {
+        Diagnostic diagnostic;
+        diagnostic.severity = severity;
+        diagnostic.context = context;
+        diagnostic.message = message;
+        ir.addDiagnostic(diagnostic);
+    }
+};
+
+} // namespace hybrid
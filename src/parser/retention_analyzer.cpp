@@ -0,0 +1,176 @@
+/**
+ * Retention Analyzer
+ * Finds functions that cannot be translated safely (signal handlers,
+ * setjmp/longjmp) and keeps them in C++ behind extern "C"
+ */
+
+#include "ir.h"
+#include <regex>
+#include <set>
+#include <algorithm>
+
+namespace hybrid {
+
+/**
+ * Retention Analyzer
+ * Whole-program pass: a handler installed in one class may be a static
+ * method of another
+ */
+class RetentionAnalyzer {
+public:
+    void analyze(IR& ir) {
+        std::set<std::string> handlers;
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(method, handlers);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(func, handlers);
+        }
+
+        // Handlers run in signal context, where Rust code is not async-signal-safe
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                if (method.retained_reason.empty() && handlers.count(method.name)) {
+                    method.retained_reason = "it is installed as a signal handler";
+                }
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            if (func.retained_reason.empty() && handlers.count(func.name)) {
+                func.retained_reason = "it is installed as a signal handler";
+            }
+        }
+
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                if (method.retained_reason.empty()) continue;
+                if (method.is_constructor || method.is_destructor || class_decl.is_template) {
+                    // There is no C ABI for constructing or dropping the Rust struct
+                    report(ir, class_decl.name + "::" + method.name,
+                           "'" + class_decl.name + "::" + method.name + "' cannot be translated safely (" +
+                           method.retained_reason + ") and cannot be kept in C++; review it by hand");
+                    method.retained_reason.clear();
+                    continue;
+                }
+                // The C++ shim reads the object through the pointer Rust passes
+                class_decl.needs_c_layout = true;
+                report(ir, class_decl.name + "::" + method.name,
+                       "'" + class_decl.name + "::" + method.name +
+                       "' is kept in C++ and called through extern \"C\": " + method.retained_reason);
+                findCallbacks(ir, method, class_decl.name + "::" + method.name);
+            }
+        }
+        for (const auto& func : ir.getFunctions()) {
+            if (func.retained_reason.empty()) continue;
+            report(ir, func.name,
+                   "'" + func.name + "' is kept in C++ and called through extern \"C\": " + func.retained_reason);
+        }
+    }
+
+private:
+    void analyzeFunction(Function& func, std::set<std::string>& handlers) {
+        const std::string& body = func.body;
+        if (body.empty()) return;
+
+        std::smatch match;
+        std::regex call_pattern(
+            R"(\b(?:std::)?(signal|sigaction|setjmp|_setjmp|sigsetjmp|longjmp|_longjmp|siglongjmp)\s*\()");
+        if (std::regex_search(body, match, call_pattern)) {
+            std::string callee = match[1].str();
+            if (callee == "signal" || callee == "sigaction") {
+                func.retained_reason = "it installs a signal handler with '" + callee + "'";
+            } else if (callee.find("setjmp") != std::string::npos) {
+                func.retained_reason = "it calls '" + callee + "'; a longjmp back would skip Rust destructors";
+            } else {
+                func.retained_reason = "it calls '" + callee + "', which would skip Rust destructors";
+            }
+        }
+
+        // signal(SIGINT, onInterrupt) / sa.sa_handler = &Handler::onInterrupt
+        std::regex handler_pattern(
+            R"(\b(?:std::)?signal\s*\([^,()]+,\s*&?\s*([a-zA-Z_][\w:]*)\s*\))"
+            R"(|\bsa_(?:handler|sigaction)\s*=\s*&?\s*([a-zA-Z_][\w:]*))");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), handler_pattern);
+             it != std::sregex_iterator(); ++it) {
+            std::string handler = (*it)[1].matched ? (*it)[1].str() : (*it)[2].str();
+            size_t colon = handler.rfind("::");
+            if (colon != std::string::npos) handler = handler.substr(colon + 2);
+            if (handler != "SIG_IGN" && handler != "SIG_DFL") handlers.insert(handler);
+        }
+    }
+
+    /**
+     * Function pointer and std::function parameters: Rust passes closures
+     * through a trampoline, with a context pointer when the C++ has one
+     */
+    static void findCallbacks(IR& ir, Function& method, const std::string& qualified) {
+        for (const auto& param : method.parameters) {
+            auto type = param.type;
+            if (type && type->kind == TypeKind::Reference) type = type->element_type;
+            if (!type || type->kind != TypeKind::Function || !type->member_of.empty() || param.name.empty()) continue;
+
+            CallbackInfo callback;
+            callback.parameter = param.name;
+            callback.std_function = type->name.find('*') == std::string::npos;
+            if (!callback.std_function) {
+                // void (*visit)(void* context, int value), void* context
+                for (size_t i = 0; i < type->parameter_types.size(); ++i) {
+                    if (isVoidPointer(type->parameter_types[i])) {
+                        callback.context_slot = static_cast<int>(i);
+                        break;
+                    }
+                }
+                for (const auto& other : method.parameters) {
+                    if (callback.context_slot < 0 || &other == &param || !isVoidPointer(other.type)) continue;
+                    bool taken = std::any_of(method.callbacks.begin(), method.callbacks.end(),
+                                             [&other](const CallbackInfo& c) { return c.context == other.name; });
+                    if (!taken) {
+                        callback.context = other.name;
+                        break;
+                    }
+                }
+                if (callback.context.empty()) callback.context_slot = -1;
+            }
+
+            // handler_ = handler; handlers_.push_back(std::move(handler))
+            std::regex stored_pattern(
+                "=\\s*(?:std::move\\s*\\(\\s*)?" + param.name + "\\s*\\)?\\s*;|"
+                "\\b(?:push_back|emplace_back|insert|emplace)\\s*\\(\\s*(?:std::move\\s*\\(\\s*)?" + param.name + "\\b");
+            callback.stored = std::regex_search(method.body, stored_pattern);
+
+            if (callback.std_function || !callback.context.empty()) {
+                report(ir, qualified,
+                       "'" + qualified + "' calls back into Rust through '" + param.name + "': a closure is passed " +
+                           "through an extern \"C\" trampoline" +
+                           (callback.context.empty() ? "" : " with '" + callback.context + "' as its context") +
+                           (callback.stored ? "; the C++ keeps it, so the closure is boxed and never freed"
+                                            : "; the C++ must not keep it after the call"),
+                       Diagnostic::Note);
+            } else {
+                report(ir, qualified,
+                       "'" + qualified + "' takes '" + param.name + "' without a context pointer: only an " +
+                           "extern \"C\" fn, which captures nothing, can be passed",
+                       Diagnostic::Note);
+            }
+            method.callbacks.push_back(callback);
+        }
+    }
+
+    static bool isVoidPointer(const std::shared_ptr<Type>& type) {
+        return type && type->kind == TypeKind::Pointer && type->element_type &&
+               type->element_type->kind == TypeKind::Void;
+    }
+
+    static void report(IR& ir, const std::string& context, const std::string& message,
+                       Diagnostic::Severity severity = Diagnostic::Warning) {
+        Diagnostic diagnostic;
+        diagnostic.severity = severity;
+        diagnostic.message = message;
+        diagnostic.context = context;
+        ir.addDiagnostic(diagnostic);
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,295 @@
+/**
+ * Self-Reference Analyzer
+ * Finds classes whose objects point at themselves or at each other: this
+ * stored elsewhere, pointers into the object, intrusive links. Rust moves
+ * values by memcpy, so such pointers dangle unless the layout changes.
+ * Classes holding linked nodes (lists, trees) get their node uses
+ * recorded, for translation to ids into an arena they own
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <set>
+#include <map>
+
+namespace hybrid {
+
+/**
+ * Self-Reference Analyzer
+ * Whole-program pass, so each finding is reported as a diagnostic
+ */
+class SelfReferenceAnalyzer {
+public:
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            analyzeClass(class_decl);
+            for (const auto& reference : class_decl.self_references) {
+                report(ir, class_decl, reference);
+            }
+        }
+
+        // Class holding Node* head / Node* root: the owner of the nodes
+        for (auto& class_decl : ir.getClasses()) {
+            for (const auto& field : class_decl.fields) {
+                if (!isRawPointer(field.type) || !field.type->element_type) continue;
+                const std::string& node = field.type->element_type->name;
+                const ClassDecl* node_class = ir.findClass(node);
+                if (node == class_decl.name || !node_class || !hasIntrusiveLink(*node_class)) continue;
+                if (std::find(class_decl.graph_nodes.begin(), class_decl.graph_nodes.end(), node) ==
+                    class_decl.graph_nodes.end()) {
+                    class_decl.graph_nodes.push_back(node);
+                }
+            }
+            if (class_decl.graph_nodes.empty()) continue;
+            for (auto& method : class_decl.methods) {
+                analyzeGraphSites(ir, class_decl, method);
+            }
+        }
+    }
+
+private:
+    void analyzeClass(ClassDecl& class_decl) {
+        // Node* next / Node* parent: raw links between objects of the class
+        for (const auto& field : class_decl.fields) {
+            if (isRawPointer(field.type) && field.type->element_type &&
+                field.type->element_type->name == class_decl.name) {
+                SelfReferenceInfo link;
+                link.kind = SelfReferenceInfo::IntrusiveLink;
+                link.field = field.name;
+                link.source_text = field.type->name + " " + field.name;
+                class_decl.self_references.push_back(link);
+            }
+        }
+
+        for (const auto& method : class_decl.methods) {
+            std::string qualified = class_decl.name + "::" + method.name;
+            // Member initializers store like assignments: cursor_(buffer_), owner_(this)
+            std::string text = method.body;
+            std::regex initializer(R"(\b(\w+)\s*[({]\s*(&?\s*(?:this\b|\w+)(?:\s*\.\s*data\s*\(\s*\))?)\s*[)}])");
+            for (auto it = std::sregex_iterator(method.member_initializers.begin(), method.member_initializers.end(),
+                                                initializer);
+                 it != std::sregex_iterator(); ++it) {
+                text += "; " + (*it)[1].str() + " = " + (*it)[2].str() + ";";
+            }
+            if (text.empty()) continue;
+
+            // owner = this / peer->owner = this / cursor = buffer / cursor = &items[0]
+            std::regex store(
+                R"(((?:this\s*->\s*)?[a-zA-Z_][\w.]*(?:\s*->\s*\w+)*)\s*=\s*(&?\s*(?:this->)?(\w+)(?:\s*\.\s*data\s*\(\s*\))?(?:\s*\[[^\]]*\])?)\s*;)");
+            for (auto it = std::sregex_iterator(text.begin(), text.end(), store); it != std::sregex_iterator(); ++it) {
+                std::string target = std::regex_replace((*it)[1].str(), std::regex(R"(^this\s*->\s*)"), "");
+                std::string value = (*it)[3].str();
+                const Variable* field = findField(class_decl, target);
+
+                if (value == "this" && (*it)[2].str().find('&') == std::string::npos) {
+                    SelfReferenceInfo reference;
+                    reference.kind = field ? SelfReferenceInfo::InteriorPointer : SelfReferenceInfo::ThisEscape;
+                    reference.field = field ? field->name : "";
+                    reference.function = qualified;
+                    reference.source_text = it->str(0);
+                    if (field || target.find_first_of(".-") != std::string::npos) add(class_decl, reference);
+                } else if (field && isRawPointer(field->type) && findField(class_decl, value) &&
+                           !isRawPointer(findField(class_decl, value)->type)) {
+                    SelfReferenceInfo reference;
+                    reference.kind = SelfReferenceInfo::InteriorPointer;
+                    reference.field = field->name;
+                    reference.function = qualified;
+                    reference.source_text = it->str(0);
+                    add(class_decl, reference);
+                }
+            }
+
+            // registry.add(this) / Observer(this): this handed to code that may keep it
+            std::regex call(R"(\b([a-zA-Z_][\w.:]*(?:\s*->\s*\w+)*)\s*\(([^;()]*)\))");
+            std::regex this_argument(R"((^|[^*\w])this\b(?!\s*->))");
+            for (auto it = std::sregex_iterator(method.body.begin(), method.body.end(), call);
+                 it != std::sregex_iterator(); ++it) {
+                static const std::set<std::string> keywords = {"if", "while", "for", "switch", "return",
+                                                               "sizeof", "assert"};
+                if (keywords.count((*it)[1].str()) || !std::regex_search((*it)[2].str(), this_argument)) continue;
+                SelfReferenceInfo reference;
+                reference.kind = SelfReferenceInfo::ThisEscape;
+                reference.function = qualified;
+                reference.source_text = it->str(0);
+                add(class_decl, reference);
+            }
+        }
+    }
+
+    /**
+     * Node* cur / new Node(v) / delete head; / cur->next->value
+     */
+    void analyzeGraphSites(const IR& ir, const ClassDecl& holder, Function& method) {
+        const std::string& body = method.body;
+        if (body.empty()) return;
+
+        // Pointer name -> node class, and whether it is a field
+        std::map<std::string, std::pair<std::string, bool>> pointers;
+        for (const auto& field : holder.fields) {
+            if (isRawPointer(field.type) && field.type->element_type &&
+                isGraphNode(holder, field.type->element_type->name)) {
+                pointers[field.name] = {field.type->element_type->name, true};
+            }
+        }
+        for (const auto& param : method.parameters) {
+            if (isRawPointer(param.type) && param.type->element_type &&
+                isGraphNode(holder, param.type->element_type->name)) {
+                pointers[param.name] = {param.type->element_type->name, false};
+            }
+        }
+
+        std::vector<std::pair<size_t, GraphSiteInfo>> found;
+        for (const auto& node : holder.graph_nodes) {
+            std::regex declaration(R"(\b)" + node + R"(\s*\*\s*(\w+)(?=\s*[=;]))");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), declaration);
+                 it != std::sregex_iterator(); ++it) {
+                GraphSiteInfo site;
+                site.kind = GraphSiteInfo::Declaration;
+                site.source_text = it->str(0);
+                site.node_class = node;
+                site.pointer = (*it)[1].str();
+                pointers[site.pointer] = {node, false};
+                found.emplace_back(it->position(0), site);
+            }
+
+            std::regex new_node(R"(\bnew\s+)" + node + R"(\b\s*)");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), new_node);
+                 it != std::sregex_iterator(); ++it) {
+                GraphSiteInfo site;
+                site.kind = GraphSiteInfo::NewNode;
+                site.node_class = node;
+                size_t end = it->position(0) + it->length(0);
+                if (end < body.size() && (body[end] == '(' || body[end] == '{')) {
+                    size_t close = findClosing(body, end, body[end], body[end] == '(' ? ')' : '}');
+                    if (close == std::string::npos) continue;
+                    site.arguments = splitArguments(body.substr(end + 1, close - end - 1));
+                    end = close + 1;
+                }
+                site.source_text = trim(body.substr(it->position(0), end - it->position(0)));
+                found.emplace_back(it->position(0), site);
+            }
+        }
+
+        // delete head->next; / cur->next->value, through links of the node class only
+        std::regex access(R"((\bdelete\s+)?(\bthis\s*->\s*)?\b(\w+)((?:\s*->\s*\w+)*)(\s*;)?)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), access); it != std::sregex_iterator(); ++it) {
+            auto pointer = pointers.find((*it)[3].str());
+            if (pointer == pointers.end() || ((*it)[2].matched && !pointer->second.second)) continue;
+            bool is_delete = (*it)[1].matched && (*it)[5].matched;
+            if (!is_delete && (*it)[4].length() == 0) continue;
+
+            GraphSiteInfo site;
+            site.kind = is_delete ? GraphSiteInfo::DeleteNode : GraphSiteInfo::NodeAccess;
+            site.node_class = pointer->second.first;
+            site.pointer = pointer->first;
+            site.pointer_is_field = pointer->second.second;
+
+            std::string chain = (*it)[4].str();
+            std::regex arrow(R"(\s*->\s*(\w+))");
+            size_t length = 0;
+            for (auto m = std::sregex_iterator(chain.begin(), chain.end(), arrow); m != std::sregex_iterator(); ++m) {
+                // A deleted chain must end on a link; an accessed one may end on any member
+                if (!site.members.empty() && !isLink(ir, site.node_class, site.members.back())) break;
+                site.members.push_back((*m)[1].str());
+                length = m->position(0) + m->length(0);
+            }
+            if (is_delete && (length != chain.size() || (!site.members.empty() &&
+                                                          !isLink(ir, site.node_class, site.members.back())))) {
+                continue;
+            }
+
+            size_t start = it->position(0) + (is_delete || !(*it)[1].matched ? 0 : (*it)[1].length());
+            size_t end = is_delete ? it->position(0) + it->length(0)
+                                   : it->position(4) + length;
+            site.source_text = body.substr(start, end - start);
+            found.emplace_back(start, site);
+        }
+
+        // Source order, without sites nested in an earlier one
+        sortBySourcePosition(found);
+        size_t covered = 0;
+        for (auto& entry : found) {
+            if (entry.first < covered) continue;
+            covered = entry.first + entry.second.source_text.size();
+            method.graph_sites.push_back(entry.second);
+        }
+    }
+
+    static bool hasIntrusiveLink(const ClassDecl& class_decl) {
+        return std::any_of(class_decl.self_references.begin(), class_decl.self_references.end(),
+                           [](const SelfReferenceInfo& r) { return r.kind == SelfReferenceInfo::IntrusiveLink; });
+    }
+
+    static bool isGraphNode(const ClassDecl& holder, const std::string& name) {
+        return std::find(holder.graph_nodes.begin(), holder.graph_nodes.end(), name) != holder.graph_nodes.end();
+    }
+
+    // Field of the node class pointing at another node
+    static bool isLink(const IR& ir, const std::string& node, const std::string& member) {
+        const ClassDecl* node_class = ir.findClass(node);
+        if (!node_class) return false;
+        return std::any_of(node_class->self_references.begin(), node_class->self_references.end(),
+                           [&](const SelfReferenceInfo& r) {
+                               return r.kind == SelfReferenceInfo::IntrusiveLink && r.field == member;
+                           });
+    }
+
+    static void add(ClassDecl& class_decl, const SelfReferenceInfo& reference) {
+        bool seen = std::any_of(class_decl.self_references.begin(), class_decl.self_references.end(),
+                                [&](const SelfReferenceInfo& r) {
+                                    return r.kind == reference.kind && r.field == reference.field &&
+                                           r.function == reference.function;
+                                });
+        if (!seen) class_decl.self_references.push_back(reference);
+    }
+
+    static const Variable* findField(const ClassDecl& class_decl, const std::string& name) {
+        for (const auto& field : class_decl.fields) {
+            if (field.name == name) return &field;
+        }
+        return nullptr;
+    }
+
+    // Smart pointers own their target: only T* can alias
+    static bool isRawPointer(const std::shared_ptr<Type>& type) {
+        return type && type->kind == TypeKind::Pointer && type->member_of.empty() &&
+               type->name.find("_ptr<") == std::string::npos;
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+
+    static void report(IR& ir, const ClassDecl& class_decl, const SelfReferenceInfo& reference) {
+        Diagnostic diagnostic;
+        diagnostic.severity = Diagnostic::Warning;
+        switch (reference.kind) {
+            case SelfReferenceInfo::ThisEscape:
+                diagnostic.context = reference.function;
+                diagnostic.message = "'" + reference.function + "' stores this outside the object ('" +
+                                     reference.source_text + "'); the Rust value moves after construction and "
+                                     "leaves that pointer dangling (--self-ref pin keeps it in place)";
+                break;
+            case SelfReferenceInfo::InteriorPointer:
+                diagnostic.context = reference.function;
+                diagnostic.message = "'" + class_decl.name + "::" + reference.field +
+                                     "' points into the object itself ('" + reference.source_text +
+                                     "'); moving the struct invalidates it (--self-ref pin keeps it in place)";
+                break;
+            case SelfReferenceInfo::IntrusiveLink:
+                diagnostic.context = class_decl.name;
+                diagnostic.message = "'" + class_decl.name + "::" + reference.field +
+                                     "' links objects of the same class through a raw pointer; ownership is "
+                                     "unchecked (--self-ref arena stores them in an Arena with generational ids)";
+                break;
+        }
+        ir.addDiagnostic(diagnostic);
+    }
+};
+
+} // namespace hybrid
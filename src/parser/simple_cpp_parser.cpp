@@ -7,10 +7,47 @@
  */
 
 #include "ir.h"
+#include "pipeline.h"
+#include "move_analyzer.cpp"
+#include "pimpl_analyzer.cpp"
+#include "copy_analyzer.cpp"
+#include "guard_analyzer.cpp"
+#include "layout_analyzer.cpp"
+#include "binding_analyzer.cpp"
+#include "format_analyzer.cpp"
+#include "status_analyzer.cpp"
+#include "construction_analyzer.cpp"
+#include "enum_string_analyzer.cpp"
+#include "log_analyzer.cpp"
+#include "constexpr_analyzer.cpp"
+#include "functor_analyzer.cpp"
+#include "algorithm_analyzer.cpp"
+#include "member_pointer_analyzer.cpp"
+#include "varargs_analyzer.cpp"
+#include "retention_analyzer.cpp"
+#include "allocation_analyzer.cpp"
+#include "inheritance_analyzer.cpp"
+#include "self_reference_analyzer.cpp"
+#include "libc_analyzer.cpp"
+#include "slice_analyzer.cpp"
+#include "thread_safety_analyzer.cpp"
+#include "integer_analyzer.cpp"
+#include "memory_safety_analyzer.cpp"
+#include "numeric_analyzer.cpp"
+#include "command_line_analyzer.cpp"
+#include "entry_point_analyzer.cpp"
+#include "template_analyzer.cpp"
+#include "standard_checker.cpp"
 #include <regex>
+#include <algorithm>
+#include <set>
+#include <map>
+#include <cctype>
+#include <stdexcept>
 #include <fstream>
 #include <sstream>
 #include <iostream>
+#include <functional>
 
 namespace hybrid {
 
@@ -19,7 +56,8 @@ public:
     /**
      * Parse C++ source file and build IR
      */
-    static IR parseFile(const std::string& filename) {
+    static IR parseFile(const std::string& filename, CppStandard standard = CppStandard::Cxx20,
+                        const LogMacroTable& log_macros = {}) {
         std::ifstream file(filename);
         if (!file.is_open()) {
             throw std::runtime_error("Cannot open file: " + filename);
@@ -29,52 +67,351 @@ public:
                            std::istreambuf_iterator<char>());
         file.close();
 
-        return parseString(content);
+        return parseString(content, standard, log_macros);
     }
 
     /**
      * Parse C++ source string and build IR
+     * @param pipeline Passes to run, in order (the standard passes if null)
      */
-    static IR parseString(const std::string& source) {
+    static IR parseString(const std::string& source, CppStandard standard = CppStandard::Cxx20,
+                          const LogMacroTable& log_macros = {}, const RuntimeTrace& trace = {},
+                          InstantiationCache* instantiation_cache = nullptr,
+                          const Pipeline* pipeline = nullptr) {
         IR ir;
-        SimpleCppParser parser(source);
+        ir.setStandard(standard);
+        SimpleCppParser parser(source, standard);
+        auto parse_type = [&parser](const std::string& type_str) { return parser.parseType(type_str); };
+
+        // Whole-program analysis passes, by the name Pipeline::standard() gives them
+        std::map<std::string, std::function<void()>> builtins = {
+            {"parse", [&] {
+                // Parse all classes in the source
+                parser.parseClasses(ir);
+                parser.parseTypeAliases(ir);
+                parser.parseEntryPoint(ir);
+            }},
+            {"moves", [&] { forEachClass(ir, [](ClassDecl& c) { MoveAnalyzer().analyzeClass(c); }); }},
+            {"guards", [&] { forEachClass(ir, [](ClassDecl& c) { GuardAnalyzer().analyzeClass(c); }); }},
+            {"format", [&] {
+                FormatAnalyzer format_analyzer;
+                forEachClass(ir, [&](ClassDecl& c) { format_analyzer.analyzeClass(c); });
+                for (auto& func : ir.getFunctions()) format_analyzer.analyzeFunction(func);
+            }},
+            {"varargs", [&] {
+                forEachClass(ir, [&](ClassDecl& c) { VarargsAnalyzer(parse_type).analyzeClass(c); });
+            }},
+            {"numeric", [&] {
+                NumericAnalyzer numeric_analyzer(parse_type);
+                forEachClass(ir, [&](ClassDecl& c) { numeric_analyzer.analyzeClass(c); });
+                for (auto& func : ir.getFunctions()) numeric_analyzer.analyzeFunction(func);
+            }},
+            {"pimpl", [&] { PimplAnalyzer().analyze(ir); }},
+            {"layout", [&] { LayoutAnalyzer(parse_type).analyze(ir); }},
+            {"constexpr", [&] {
+                ConstexprAnalyzer(parser.cleaned_source_, parse_type, instantiation_cache).analyze(ir);
+            }},
+            {"member-pointers", [&] { MemberPointerAnalyzer(parse_type).analyze(ir); }},
+            {"inheritance", [&] { InheritanceAnalyzer().analyze(ir); }},
+            {"retention", [&] { RetentionAnalyzer().analyze(ir); }},
+            {"copies", [&] { CopyAnalyzer().analyze(ir); }},
+            {"self-references", [&] { SelfReferenceAnalyzer().analyze(ir); }},
+            {"allocations", [&] { AllocationAnalyzer(parse_type).analyze(ir); }},
+            {"memory-safety", [&] { MemorySafetyAnalyzer(parse_type).analyze(ir); }},
+            {"slices", [&] { SliceAnalyzer(parse_type).analyze(ir); }},
+            {"thread-safety", [&] { ThreadSafetyAnalyzer(parse_type, trace).analyze(ir); }},
+            {"functors", [&] { FunctorAnalyzer().analyze(ir); }},
+            {"algorithms", [&] { AlgorithmAnalyzer().analyze(ir); }},
+            {"integers", [&] { IntegerAnalyzer(parse_type).analyze(ir); }},
+            {"libc", [&] { LibcAnalyzer().analyze(ir); }},
+            {"logging", [&] { LogAnalyzer(source, log_macros).analyze(ir); }},
+            {"status", [&] { StatusAnalyzer().analyze(ir); }},
+            {"construction", [&] { ConstructionAnalyzer().analyze(ir); }},
+            {"enum-strings", [&] { EnumStringAnalyzer(parser.cleaned_source_).analyze(ir); }},
+            {"bindings", [&] {
+                BindingAnalyzer([&ir](const std::shared_ptr<Type>& type) {
+                    return CopyAnalyzer::semanticsOf(ir, type) == CopySemantics::Copy;
+                }).analyze(ir);
+            }},
+            {"command-line", [&] { CommandLineAnalyzer(parse_type).analyze(ir); }},
+            {"entry-point", [&] { EntryPointAnalyzer().analyze(ir); }},
+        };
 
-        // Parse all classes in the source
-        parser.parseClasses(ir);
+        const Pipeline& passes = pipeline ? *pipeline : Pipeline::standard();
+        for (size_t i = 0; i < passes.passes().size(); ++i) {
+            const Pass& pass = passes.passes()[i];
+            // Code generation runs on the IR returned here
+            if (!pass.enabled || pass.stage == PassStage::Codegen) continue;
+            if (pass.isBuiltin()) {
+                builtins.at(pass.name)();
+            } else {
+                pass.run(ir);
+            }
+            passes.finished(i, ir);
+        }
 
         return ir;
     }
 
 private:
+    /**
+     * Per-class analysis: each class on its own, in source order
+     */
+    static void forEachClass(IR& ir, const std::function<void(ClassDecl&)>& analyze) {
+        for (auto& class_decl : ir.getClasses()) {
+            analyze(class_decl);
+        }
+    }
+
     std::string source_;
+    std::string cleaned_source_;            // Source with comments removed
+    CppStandard standard_;
+    std::vector<size_t> source_offsets_;    // Comment-free text index -> source index
+    std::vector<size_t> line_starts_;       // Source index of each line start
+    std::set<std::string> enum_names_;      // Enums declared in the source
+    std::set<std::string> template_names_;  // Type parameters of the class being parsed
+
+    SimpleCppParser(const std::string& source, CppStandard standard)
+        : source_(source), standard_(standard) {}
+
+    /**
+     * Longest run of source between statement boundaries (`;`, `{`, `}` or
+     * the end of a preprocessor line). The regex patterns below recurse per
+     * matched character; parsing runs on a DEEP_STACK_SIZE stack, which
+     * holds statements of this length.
+     */
+    static const size_t MAX_STATEMENT_LENGTH = 1 << 18;
+
+    /**
+     * Longest run of names, whitespace and type punctuation (`::`, `<`,
+     * `>`, `,`, `*`, `&`). Declaration patterns start with such runs and
+     * are retried at every position in them, so time grows with the
+     * square of the run: 4096 characters nest A<...> 1300 deep.
+     * Parentheses end a run, so nested expressions are not limited.
+     */
+    static const size_t MAX_DECLARATION_RUN = 4096;
 
-    explicit SimpleCppParser(const std::string& source) : source_(source) {}
+    /**
+     * Remove C++ comments from source, leaving string and character
+     * literals intact. Records where each remaining character came from
+     * so declarations can be given source spans.
+     */
+    std::string removeComments(const std::string& code) {
+        std::string result;
+        result.reserve(code.size());
+        source_offsets_.clear();
+
+        auto keep = [&](size_t from, size_t count) {
+            result.append(code, from, count);
+            for (size_t k = 0; k < count; ++k) {
+                source_offsets_.push_back(from + k);
+            }
+        };
+
+        size_t i = 0;
+        while (i < code.size()) {
+            char c = code[i];
+
+            if (c == '"' || c == '\'') {
+                // Copy the literal, honoring escapes
+                size_t end = i + 1;
+                while (end < code.size() && code[end] != c && code[end] != '\n') {
+                    end += code[end] == '\\' ? 2 : 1;
+                }
+                end = std::min(end + 1, code.size());
+                keep(i, end - i);
+                i = end;
+            } else if (code.compare(i, 2, "//") == 0) {
+                i = code.find('\n', i);
+                if (i == std::string::npos) break;
+            } else if (code.compare(i, 2, "/*") == 0) {
+                size_t end = code.find("*/", i + 2);
+                if (end == std::string::npos) break;
+                result += ' ';
+                source_offsets_.push_back(i);
+                i = end + 2;
+            } else {
+                keep(i, 1);
+                ++i;
+            }
+        }
+        source_offsets_.push_back(code.size());
+
+        line_starts_.assign(1, 0);
+        for (size_t k = 0; k < code.size(); ++k) {
+            if (code[k] == '\n') {
+                line_starts_.push_back(k + 1);
+            }
+        }
+
+        return result;
+    }
 
     /**
-     * Remove C++ comments from source
+     * Span of the comment-free text range [begin, end)
      */
-    std::string removeComments(const std::string& code) const {
-        std::string result = code;
+    SourceSpan spanOf(size_t begin, size_t end) const {
+        SourceSpan span;
+        if (source_offsets_.empty() || end <= begin) {
+            return span;
+        }
+
+        auto locate = [this](size_t offset, int& line, int& column) {
+            size_t source = source_offsets_[std::min(offset, source_offsets_.size() - 1)];
+            auto it = std::upper_bound(line_starts_.begin(), line_starts_.end(), source) - 1;
+            line = static_cast<int>(it - line_starts_.begin()) + 1;
+            column = static_cast<int>(source - *it) + 1;
+        };
+
+        locate(begin, span.begin_line, span.begin_column);
+        locate(end - 1, span.end_line, span.end_column);
+        return span;
+    }
 
-        // Remove single-line comments
-        result = std::regex_replace(result, std::regex("//[^\n]*"), "");
+    /**
+     * Doc comment directly above a declaration: lines starting with ///
+     * or //!, or a block opened with slash-star-star or slash-star-bang.
+     * Template headers and attributes in between are skipped.
+     */
+    std::string docCommentBefore(const SourceSpan& span) const {
+        if (!span.isKnown() || line_starts_.empty()) return "";
+
+        auto lineText = [this](int line) {
+            size_t start = line_starts_[static_cast<size_t>(line) - 1];
+            size_t end = static_cast<size_t>(line) < line_starts_.size() ? line_starts_[static_cast<size_t>(line)] - 1
+                                                                          : source_.size();
+            return trim(source_.substr(start, end - start));
+        };
 
-        // Remove multi-line comments
-        result = std::regex_replace(result, std::regex("/\\*.*?\\*/", std::regex::ECMAScript), "");
+        int line = span.begin_line - 1;
+        while (line >= 1 && (lineText(line).compare(0, 8, "template") == 0 || lineText(line).compare(0, 2, "[[") == 0)) {
+            line--;
+        }
+        if (line < 1) return "";
+
+        std::vector<std::string> doc;
+        std::string last = lineText(line);
+        if (last.size() >= 2 && last.compare(last.size() - 2, 2, "*/") == 0) {
+            int first = line;
+            while (first > 1 && lineText(first).find("/*") == std::string::npos) first--;
+            std::string opening = lineText(first);
+            if ((opening.compare(0, 3, "/**") != 0 && opening.compare(0, 3, "/*!") != 0) || opening == "/**/") {
+                return "";
+            }
+            for (int l = first; l <= line; ++l) {
+                std::string text = lineText(l);
+                if (l == first) text = text.substr(3);
+                if (l == line) text = text.substr(0, text.rfind("*/"));
+                text = trim(text);
+                if (l != first && text.compare(0, 1, "*") == 0) text = trim(text.substr(1));
+                doc.push_back(text);
+            }
+        } else {
+            for (; line >= 1; --line) {
+                std::string text = lineText(line);
+                if ((text.compare(0, 3, "///") != 0 && text.compare(0, 3, "//!") != 0) || text.compare(0, 4, "////") == 0) {
+                    break;
+                }
+                text = text.substr(3);
+                doc.insert(doc.begin(), text.compare(0, 1, " ") == 0 ? text.substr(1) : text);
+            }
+        }
 
+        while (!doc.empty() && doc.front().empty()) doc.erase(doc.begin());
+        while (!doc.empty() && doc.back().empty()) doc.pop_back();
+        std::string result;
+        for (const auto& text : doc) {
+            result += (result.empty() ? "" : "\n") + text;
+        }
         return result;
     }
 
+    /**
+     * Doc comments of a class and its members
+     */
+    void attachDocComments(ClassDecl& class_decl) const {
+        class_decl.doc = docCommentBefore(class_decl.span);
+        for (auto& field : class_decl.fields) {
+            field.doc = docCommentBefore(field.span);
+        }
+        for (auto& method : class_decl.methods) {
+            method.doc = docCommentBefore(method.span);
+        }
+    }
+
+    /**
+     * Reject input with statements longer than MAX_STATEMENT_LENGTH or
+     * declaration runs longer than MAX_DECLARATION_RUN
+     */
+    void checkStatementLengths(const std::string& code) const {
+        size_t length = 0;
+        size_t run = 0;
+        size_t line = 1;
+        bool line_start = true;         // Only whitespace so far on this line
+        bool preprocessor_line = false;
+
+        for (char c : code) {
+            if (c == '\n') {
+                ++line;
+                line_start = true;
+                if (preprocessor_line) {
+                    preprocessor_line = false;
+                    length = 0;
+                    run = 0;
+                    continue;
+                }
+            } else if (c == '#' && line_start) {
+                preprocessor_line = true;
+            } else if (!std::isspace(static_cast<unsigned char>(c))) {
+                line_start = false;
+            }
+
+            unsigned char u = static_cast<unsigned char>(c);
+            bool declaration = std::isalnum(u) || std::isspace(u) || c == '_' || c == ':' || c == '<' || c == '>' ||
+                               c == ',' || c == '*' || c == '&';
+            run = declaration ? run + 1 : 0;
+            if (run > MAX_DECLARATION_RUN) {
+                throw std::runtime_error("Declaration at line " + std::to_string(line) +
+                                         " exceeds " + std::to_string(MAX_DECLARATION_RUN) +
+                                         " characters");
+            }
+
+            if (c == ';' || c == '{' || c == '}') {
+                length = 0;
+            } else if (++length > MAX_STATEMENT_LENGTH) {
+                throw std::runtime_error("Statement at line " + std::to_string(line) +
+                                         " exceeds " + std::to_string(MAX_STATEMENT_LENGTH) +
+                                         " characters");
+            }
+        }
+    }
+
     /**
      * Parse all class declarations
      */
     void parseClasses(IR& ir) {
         std::string cleaned = removeComments(source_);
+        cleaned_source_ = cleaned;
+        checkStatementLengths(cleaned);
+
+        StandardChecker checker(standard_);
+        size_t offset = 0;
+        std::string feature = checker.findUnsupported(cleaned, offset);
+        if (!feature.empty()) {
+            throw std::runtime_error("line " + std::to_string(spanOf(offset, offset + 1).begin_line) +
+                                     ": " + feature);
+        }
+
+        // Enums first, so member and parameter types can refer to them
+        parseEnums(cleaned, ir);
 
-        // Regex to match class declarations
-        // Matches: class ClassName { ... };
+        // Regex to match class declaration headers
+        // Matches: class ClassName [: public Base, virtual protected Other] {
+        // and class Widget::Impl { (a nested class defined outside)
+        // The body is extracted by brace matching, so nested blocks and
+        // statements like `SCOPE_EXIT { ... };` do not end the class early
         std::regex class_pattern(
-            R"(class\s+(\w+)\s*(?::\s*public\s+(\w+(?:\s*,\s*\w+)*))?\s*\{([^}]*(?:\{[^}]*\}[^}]*)*)\};)",
+            R"(class\s+(\w+(?:::\w+)*)\s*(?::\s*((?:(?:public|protected|private|virtual)\s+)*\w+(?:\s*,\s*(?:(?:public|protected|private|virtual)\s+)*\w+)*))?\s*\{)",
             std::regex::ECMAScript
         );
 
@@ -84,43 +421,382 @@ private:
         for (std::sregex_iterator i = classes_begin; i != classes_end; ++i) {
             std::smatch match = *i;
 
+            size_t body_start = match.position(0) + match.length(0);
+            size_t body_end = findMatchingBrace(cleaned, body_start - 1);
+            if (body_end == std::string::npos || !followedBySemicolon(cleaned, body_end) ||
+                precededByWord(cleaned, match.position(0), "enum")) {
+                continue;  // Not a class definition
+            }
+
             ClassDecl class_decl;
             class_decl.name = match[1].str();
             class_decl.is_struct = false;
+            class_decl.span = spanOf(match.position(0), cleaned.find(';', body_end) + 1);
 
             // Parse base classes if present
             if (match[2].matched) {
                 parseBaseClasses(match[2].str(), class_decl);
             }
 
+            // template<typename T, int N> class ...
+            std::string template_header = templateHeaderBefore(cleaned, match.position(0));
+            if (!template_header.empty()) {
+                TemplateAnalyzer template_analyzer;
+                template_analyzer.analyzeTemplateClass(class_decl, template_header);
+                for (auto& param : class_decl.template_parameters) {
+                    if (param.kind == TemplateParameter::Type) {
+                        template_names_.insert(param.name);
+                    } else if (param.kind == TemplateParameter::NonType && param.param_type) {
+                        param.param_type = parseType(param.param_type->name);
+                    }
+                }
+            }
+
             // Parse class body
-            std::string body = match[3].str();
-            parseClassBody(body, class_decl);
+            std::string body = cleaned.substr(body_start, body_end - body_start);
+            parseClassBody(body, body_start, class_decl);
+            attachDocComments(class_decl);
+            deduceReturnTypes(class_decl, ir);
+            markArrayLengths(class_decl);
+            template_names_.clear();
 
             ir.addClass(class_decl);
         }
     }
 
+    /**
+     * Find the '}' matching the '{' at open_pos (npos if unbalanced)
+     */
+    size_t findMatchingBrace(const std::string& code, size_t open_pos) const {
+        int depth = 0;
+        for (size_t i = open_pos; i < code.size(); ++i) {
+            if (code[i] == '{') {
+                depth++;
+            } else if (code[i] == '}') {
+                depth--;
+                if (depth == 0) {
+                    return i;
+                }
+            }
+        }
+        return std::string::npos;
+    }
+
+    bool precededByWord(const std::string& code, size_t pos, const std::string& word) const {
+        size_t end = code.find_last_not_of(" \t\r\n", pos == 0 ? 0 : pos - 1);
+        if (pos == 0 || end == std::string::npos || end + 1 < word.size()) return false;
+        size_t start = end + 1 - word.size();
+        bool word_start = start == 0 || !(std::isalnum(static_cast<unsigned char>(code[start - 1])) ||
+                                          code[start - 1] == '_');
+        return word_start && code.compare(start, word.size(), word) == 0;
+    }
+
+    /**
+     * The `template<...>` header directly before pos, or empty
+     */
+    std::string templateHeaderBefore(const std::string& code, size_t pos) const {
+        size_t close = code.find_last_not_of(" \t\r\n", pos == 0 ? 0 : pos - 1);
+        if (pos == 0 || close == std::string::npos || code[close] != '>') return "";
+
+        int depth = 0;
+        for (size_t i = close + 1; i-- > 0;) {
+            if (code[i] == '>') {
+                depth++;
+            } else if (code[i] == '<' && --depth == 0) {
+                if (!precededByWord(code, i, "template")) return "";
+                size_t keyword = code.rfind("template", i);
+                return code.substr(keyword, close + 1 - keyword);
+            }
+        }
+        return "";
+    }
+
+    /**
+     * Non-type template parameters used as array bounds of fields
+     */
+    void markArrayLengths(ClassDecl& class_decl) const {
+        for (auto& param : class_decl.template_parameters) {
+            if (param.kind != TemplateParameter::NonType) continue;
+            std::regex use_pattern("\\b" + param.name + "\\b");
+            for (const auto& field : class_decl.fields) {
+                if (field.type && field.type->kind == TypeKind::Array &&
+                    std::regex_search(field.type->array_size, use_pattern)) {
+                    param.is_array_length = true;
+                }
+            }
+        }
+    }
+
+    /**
+     * Parse enum declarations: enum [class] Name [: type] { A, B = 2 };
+     */
+    void parseEnums(const std::string& cleaned, IR& ir) {
+        std::regex enum_pattern(R"(\benum\s+(class\s+|struct\s+)?(\w+)\s*(?::\s*([\w:\s]+?)\s*)?\{([^{}]*)\}\s*;)");
+
+        auto begin = std::sregex_iterator(cleaned.begin(), cleaned.end(), enum_pattern);
+        for (auto it = begin; it != std::sregex_iterator(); ++it) {
+            const std::smatch& match = *it;
+
+            EnumDecl enum_decl;
+            enum_decl.name = match[2].str();
+            enum_decl.is_scoped = match[1].matched;
+            enum_decl.underlying_type = match[3].matched ? trim(match[3].str()) : "";
+            enum_decl.span = spanOf(match.position(0), match.position(0) + match.length(0));
+            enum_decl.doc = docCommentBefore(enum_decl.span);
+
+            std::stringstream list(match[4].str());
+            std::string item;
+            while (std::getline(list, item, ',')) {
+                item = trim(item);
+                if (item.empty()) continue;
+
+                EnumDecl::Enumerator enumerator;
+                size_t equals = item.find('=');
+                enumerator.name = trim(item.substr(0, equals));
+                if (equals != std::string::npos) {
+                    enumerator.value = trim(item.substr(equals + 1));
+                }
+                enum_decl.enumerators.push_back(enumerator);
+            }
+
+            enum_names_.insert(enum_decl.name);
+            ir.addEnum(enum_decl);
+        }
+    }
+
+    /**
+     * int main(int argc, char** argv) defined at file scope. Other free
+     * functions are not parsed
+     */
+    void parseEntryPoint(IR& ir) {
+        static const std::regex main_pattern(R"((?:^|[;}\s])int\s+main\s*\(([^)]*)\)\s*\{)");
+        std::string top_level = stripBlocks(cleaned_source_);
+        std::smatch match;
+        if (!std::regex_search(top_level, match, main_pattern)) return;
+
+        size_t open = match.position(0) + match.length(0) - 1;
+        size_t close = findMatchingBrace(cleaned_source_, open);
+        if (close == std::string::npos) return;
+
+        Function main;
+        main.name = "main";
+        main.is_entry_point = true;
+        main.return_type = parseType("int");
+        main.body = cleaned_source_.substr(open + 1, close - open - 1);
+        main.span = spanOf(match.position(0) + (match.str(0).compare(0, 3, "int") == 0 ? 0 : 1), close + 1);
+
+        // char* argv[] is char** argv
+        std::string params = match[1].str();
+        params = std::regex_replace(params, std::regex(R"((\w[\w\s*]*?)\s*\b(\w+)\s*\[\s*\])"), "$1* $2");
+        parseParameters(params, match.position(1), main);
+
+        ir.addFunction(main);
+    }
+
+    /**
+     * Parse type aliases at namespace scope: using IntStack = Stack<int>;
+     * and typedef Stack<int> IntStack; alias templates are skipped
+     */
+    void parseTypeAliases(IR& ir) {
+        static const std::regex using_pattern(R"(\busing\s+(\w+)\s*=\s*([^;{]+);)");
+        static const std::regex typedef_pattern(R"(\btypedef\s+([^;{()]+?)\s+(\w+)\s*;)");
+
+        std::vector<std::pair<size_t, TypeAlias>> aliases;
+        auto collect = [&](const std::regex& pattern, int name_group, int type_group) {
+            for (auto it = std::sregex_iterator(cleaned_source_.begin(), cleaned_source_.end(), pattern);
+                 it != std::sregex_iterator(); ++it) {
+                size_t pos = it->position(0);
+                if (!atNamespaceScope(cleaned_source_, pos) || !templateHeaderBefore(cleaned_source_, pos).empty()) {
+                    continue;
+                }
+                TypeAlias alias;
+                alias.name = (*it)[name_group].str();
+                alias.type = parseType(trim((*it)[type_group].str()));
+                alias.span = spanOf(pos, pos + it->length(0));
+                alias.doc = docCommentBefore(alias.span);
+                aliases.emplace_back(pos, alias);
+            }
+        };
+        collect(using_pattern, 1, 2);
+        collect(typedef_pattern, 2, 1);
+
+        // Declaration order
+        std::stable_sort(aliases.begin(), aliases.end(),
+                         [](const std::pair<size_t, TypeAlias>& a, const std::pair<size_t, TypeAlias>& b) {
+                             return a.first < b.first;
+                         });
+        for (const auto& alias : aliases) {
+            ir.addTypeAlias(alias.second);
+        }
+    }
+
+    /**
+     * True if pos is outside every block but namespace bodies
+     */
+    bool atNamespaceScope(const std::string& code, size_t pos) const {
+        std::vector<bool> namespaces;   // Open blocks: true for a namespace body
+        for (size_t i = 0; i < pos && i < code.size(); ++i) {
+            if (code[i] == '{') {
+                // namespace a::b {, namespace {, extern "C" {
+                static const std::regex namespace_head(R"((?:\bnamespace\b[\w:\s]*|\bextern\s+"C"\s*)$)");
+                size_t start = i == 0 ? std::string::npos : code.find_last_of(";{}", i - 1);
+                size_t from = start == std::string::npos ? 0 : start + 1;
+                namespaces.push_back(std::regex_search(code.substr(from, i - from), namespace_head));
+            } else if (code[i] == '}' && !namespaces.empty()) {
+                namespaces.pop_back();
+            }
+        }
+        return std::all_of(namespaces.begin(), namespaces.end(), [](bool open) { return open; });
+    }
+
+    bool followedBySemicolon(const std::string& code, size_t pos) const {
+        size_t next = code.find_first_not_of(" \t\r\n", pos + 1);
+        return next != std::string::npos && code[next] == ';';
+    }
+
+    /**
+     * Blank out brace-enclosed blocks so statements in inline method
+     * bodies are not mistaken for declarations. Offsets are preserved.
+     */
+    std::string stripBlocks(const std::string& code) const {
+        std::string result;
+        size_t pos = 0;
+        while (pos < code.size()) {
+            size_t open = code.find('{', pos);
+            if (open == std::string::npos) {
+                result += code.substr(pos);
+                break;
+            }
+            size_t close = findMatchingBrace(code, open);
+            if (close == std::string::npos) {
+                result += code.substr(pos);
+                break;
+            }
+            result += code.substr(pos, open - pos + 1);
+            for (size_t k = open + 1; k < close; ++k) {
+                result += code[k] == '\n' ? '\n' : ' ';
+            }
+            result += '}';
+            pos = close + 1;
+        }
+        return result;
+    }
+
+    /**
+     * Deduce `auto` method return types (C++14 rules: references and
+     * top-level const are dropped). Only returns of a literal, parameter or
+     * field are understood; anything else is reported and left as `auto`.
+     */
+    void deduceReturnTypes(ClassDecl& class_decl, IR& ir) {
+        static const std::regex return_pattern(R"(\breturn\b\s*([^;]*);)");
+
+        for (auto& method : class_decl.methods) {
+            if (!method.return_type || method.return_type->kind != TypeKind::Class ||
+                method.return_type->name != "auto") {
+                continue;
+            }
+
+            std::shared_ptr<Type> deduced;
+            bool deducible = !method.body.empty();
+            auto returns_begin = std::sregex_iterator(method.body.begin(), method.body.end(), return_pattern);
+            for (auto it = returns_begin; it != std::sregex_iterator() && deducible; ++it) {
+                auto type = deduceExpressionType(trim((*it)[1].str()), method, class_decl);
+                if (!type || (deduced && deduced->name != type->name)) {
+                    deducible = false;
+                }
+                deduced = type;
+            }
+
+            if (deducible) {
+                method.return_type = deduced ? deduced : parseType("void");
+                continue;
+            }
+
+            method.return_type = std::make_shared<Type>(TypeKind::Template);
+            method.return_type->name = "auto";
+
+            Diagnostic diagnostic;
+            diagnostic.severity = Diagnostic::Warning;
+            diagnostic.message = "cannot deduce the 'auto' return type of '" + method.name +
+                                 "'; add a trailing return type (-> T)";
+            diagnostic.context = class_decl.name + "::" + method.name;
+            ir.addDiagnostic(diagnostic);
+        }
+    }
+
+    /**
+     * Type of a returned expression, or null if it is not understood
+     * (an empty expression is `return;`, i.e. void)
+     */
+    std::shared_ptr<Type> deduceExpressionType(const std::string& expr, const Function& method,
+                                               const ClassDecl& class_decl) {
+        static const std::regex integer_literal(R"(-?(?:0[xX][0-9A-Fa-f']+|[0-9][0-9']*)([uUlL]*))");
+        static const std::regex float_literal(R"(-?(?:[0-9]+\.[0-9]*|\.[0-9]+)(?:[eE][-+]?[0-9]+)?([fFlL]?))");
+
+        std::smatch match;
+        if (expr.empty()) return parseType("void");
+        if (expr == "true" || expr == "false") return parseType("bool");
+        if (expr.front() == '"') return parseType("const char*");
+        if (expr.front() == '\'' && expr.size() >= 3) return parseType("char");
+        if (std::regex_match(expr, match, integer_literal)) {
+            std::string suffix = match[1].str();
+            std::transform(suffix.begin(), suffix.end(), suffix.begin(), ::tolower);
+            if (suffix.find('u') != std::string::npos) {
+                return parseType(suffix.find('l') != std::string::npos ? "unsigned long" : "unsigned int");
+            }
+            return parseType(suffix.empty() ? "int" : "long");
+        }
+        if (std::regex_match(expr, match, float_literal)) {
+            return parseType(match[1].str() == "f" || match[1].str() == "F" ? "float" : "double");
+        }
+
+        std::string name = expr.compare(0, 6, "this->") == 0 ? trim(expr.substr(6)) : expr;
+        std::shared_ptr<Type> declared;
+        for (const auto& param : method.parameters) {
+            if (param.name == name) declared = param.type;
+        }
+        for (const auto& field : class_decl.fields) {
+            if (!declared && field.name == name) declared = field.type;
+        }
+        if (!declared) return nullptr;
+
+        // auto decays references and drops top-level const
+        while (declared->kind == TypeKind::Reference || declared->kind == TypeKind::RValueReference) {
+            declared = declared->element_type;
+        }
+        if (declared->is_const && declared->kind != TypeKind::Pointer) {
+            auto copy = std::make_shared<Type>(*declared);
+            copy->is_const = false;
+            return copy;
+        }
+        return declared;
+    }
+
     /**
      * Parse base class list
      */
     void parseBaseClasses(const std::string& bases_str, ClassDecl& class_decl) {
-        std::regex base_pattern(R"(\w+)");
+        // Each base: [public|protected|private] [virtual] Name, in either order
+        std::regex base_pattern(R"(((?:(?:public|protected|private|virtual)\s+)*)(\w+))");
         auto bases_begin = std::sregex_iterator(bases_str.begin(), bases_str.end(), base_pattern);
         auto bases_end = std::sregex_iterator();
 
         for (std::sregex_iterator i = bases_begin; i != bases_end; ++i) {
-            class_decl.base_classes.push_back((*i).str());
+            class_decl.base_classes.push_back((*i)[2].str());
+            if ((*i)[1].str().find("virtual") != std::string::npos) {
+                class_decl.virtual_bases.push_back((*i)[2].str());
+            }
         }
     }
 
     /**
      * Parse class body (fields and methods)
      */
-    void parseClassBody(const std::string& body, ClassDecl& class_decl) {
+    void parseClassBody(const std::string& body, size_t body_offset, ClassDecl& class_decl) {
         // Split by access specifiers
         std::vector<std::string> sections;
         std::vector<std::string> access_levels;
+        std::vector<size_t> section_offsets;
 
         std::regex access_pattern(R"((private|protected|public)\s*:)");
 
@@ -135,6 +811,7 @@ private:
             if (!section_content.empty()) {
                 sections.push_back(section_content);
                 access_levels.push_back(current_access);
+                section_offsets.push_back(body_offset + last_pos);
             }
 
             current_access = (*it)[1].str();
@@ -145,38 +822,59 @@ private:
         if (last_pos < body.length()) {
             sections.push_back(body.substr(last_pos));
             access_levels.push_back(current_access);
+            section_offsets.push_back(body_offset + last_pos);
         }
 
         // If no access specifier found, treat entire body as private
         if (sections.empty()) {
             sections.push_back(body);
             access_levels.push_back("private");
+            section_offsets.push_back(body_offset);
         }
 
         // Parse each section
         for (size_t i = 0; i < sections.size(); ++i) {
-            parseSection(sections[i], access_levels[i], class_decl);
+            parseSection(sections[i], section_offsets[i], access_levels[i], class_decl);
         }
     }
 
     /**
      * Parse a section (fields and methods within an access level)
      */
-    void parseSection(const std::string& section, const std::string& access, ClassDecl& class_decl) {
-        // Parse field declarations
-        parseFields(section, access, class_decl);
+    void parseSection(const std::string& section, size_t offset, const std::string& access,
+                      ClassDecl& class_decl) {
+        size_t first_field = class_decl.fields.size();
+        size_t first_method = class_decl.methods.size();
+
+        // Parse field declarations (outside of method bodies)
+        parseFields(stripBlocks(section), offset, access, class_decl);
 
         // Parse method declarations/definitions
-        parseMethods(section, access, class_decl);
+        parseMethods(section, offset, access, class_decl);
+
+        ClassDecl::AccessSection access_section;
+        access_section.level = access == "public"      ? ClassDecl::AccessSection::Public
+                               : access == "protected" ? ClassDecl::AccessSection::Protected
+                                                       : ClassDecl::AccessSection::Private;
+        for (size_t i = first_field; i < class_decl.fields.size(); ++i) {
+            access_section.members.push_back(class_decl.fields[i].name);
+        }
+        for (size_t i = first_method; i < class_decl.methods.size(); ++i) {
+            access_section.members.push_back(class_decl.methods[i].name);
+        }
+        class_decl.access_sections.push_back(access_section);
     }
 
     /**
      * Parse field declarations
      */
-    void parseFields(const std::string& section, const std::string& access, ClassDecl& class_decl) {
+    void parseFields(const std::string& section, size_t offset, const std::string& access,
+                     ClassDecl& class_decl) {
+        size_t first_field = class_decl.fields.size();
+
         // Match: type name; or type name1, name2;
         std::regex field_pattern(
-            R"((?:const\s+)?(?:static\s+)?([a-zA-Z_][\w:<>,\[\]\s*&]*?)\s+([a-zA-Z_]\w*(?:\s*,\s*[a-zA-Z_]\w*)*)\s*;)",
+            R"((?:const\s+)?(?:static\s+)?([a-zA-Z_][\w:<>,\[\]\s*&]*?)\s+([a-zA-Z_]\w*(?:\s*,\s*[a-zA-Z_]\w*)*)\s*((?:\[[^\[\];]*\]\s*)*);)",
             std::regex::ECMAScript
         );
 
@@ -194,6 +892,10 @@ private:
 
             std::string type_str = match[1].str();
             std::string names_str = match[2].str();
+            std::string bounds = match[3].str();  // char data[N][2]
+
+            // class Impl; declares a nested class, not a field
+            if (type_str == "class" || type_str == "struct") continue;
 
             // Parse multiple variable names (e.g., int x, y;)
             std::regex name_pattern(R"([a-zA-Z_]\w*)");
@@ -203,99 +905,193 @@ private:
             for (std::sregex_iterator name_it = names_begin; name_it != names_end; ++name_it) {
                 Variable field;
                 field.name = (*name_it).str();
-                field.type = parseType(type_str);
+                field.type = bounds.empty() ? parseType(type_str) : parseArrayType(type_str, bounds);
+                field.span = spanOf(offset + match.position(0), offset + match.position(0) + match.length(0));
 
                 class_decl.fields.push_back(field);
             }
         }
+
+        // Pointers to members: void (Machine::*handler_)(); int Machine::*selected_;
+        std::regex pointer_pattern(
+            R"((?:^|[;{}:])\s*((?:const\s+)?[a-zA-Z_][\w:<>]*\s*[*&]?\s*\(\s*(?:\w+::)*\*\s*\w+\s*\)\s*\([^()]*\)\s*(?:const)?)"
+            R"(|[a-zA-Z_][\w:<>]*\s+(?:\w+::)+\*\s*\w+)\s*(?:=[^;]*)?;)");
+        bool added = false;
+        for (auto it = std::sregex_iterator(section.begin(), section.end(), pointer_pattern);
+             it != std::sregex_iterator(); ++it) {
+            Variable field;
+            std::string type_str;
+            if (!splitPointerDeclarator(trim((*it)[1].str()), type_str, field.name)) continue;
+            field.type = parseType(type_str);
+            size_t begin = offset + it->position(1);
+            field.span = spanOf(begin, offset + it->position(0) + it->length(0));
+            class_decl.fields.push_back(field);
+            added = true;
+        }
+
+        // Keep declaration order
+        if (added) {
+            std::stable_sort(class_decl.fields.begin() + first_field, class_decl.fields.end(),
+                             [](const Variable& a, const Variable& b) {
+                                 return std::make_pair(a.span.begin_line, a.span.begin_column) <
+                                        std::make_pair(b.span.begin_line, b.span.begin_column);
+                             });
+        }
     }
 
     /**
      * Parse method declarations/definitions
      */
-    void parseMethods(const std::string& section, const std::string& access, ClassDecl& class_decl) {
+    void parseMethods(const std::string& section, size_t offset, const std::string& access,
+                      ClassDecl& class_decl) {
         // Match method signatures (including constructors, virtual, static)
-        // Pattern: [virtual] [static] [type] name(params) [const] [= 0] [{ body } | ;]
+        // Pattern: [virtual] [static] [type] [~]name(params) [const] [noexcept] [override|final] [-> type] [= 0|delete|default] [: inits] [{ | ;]
+        // The body is extracted by brace matching so nested blocks are kept intact
         std::regex method_pattern(
-            R"((virtual\s+)?(static\s+)?(?:([a-zA-Z_][\w:<>,\s*&]*?)\s+)?([a-zA-Z_]\w*)\s*\(([^)]*)\)\s*(const)?\s*(=\s*0)?\s*(?:\{([^}]*(?:\{[^}]*\}[^}]*)*)\}|;))",
+            R"((virtual\s+)?(static\s+)?(?:([a-zA-Z_][\w:<>,\s*&]*?)\s+)?(~?[a-zA-Z_]\w*|operator\s*\(\s*\)|operator\s*(?:new|delete)(?:\s*\[\s*\])?)\s*\(((?:[^()]|\([^()]*\))*)\)\s*(const)?\s*(?:noexcept\s*)?(?:(?:override|final)\b\s*)*(?:->\s*([^{;=]+?)\s*)?(=\s*(?:0|delete|default))?\s*(?::\s*([^{;]*?)\s*)?(\{|;))",
             std::regex::ECMAScript
         );
 
-        auto methods_begin = std::sregex_iterator(section.begin(), section.end(), method_pattern);
-        auto methods_end = std::sregex_iterator();
+        std::smatch match;
+        std::string::const_iterator search_start(section.cbegin());
 
-        for (std::sregex_iterator it = methods_begin; it != methods_end; ++it) {
-            std::smatch match = *it;
+        while (std::regex_search(search_start, section.cend(), match, method_pattern)) {
+            size_t match_end = match.suffix().first - section.cbegin();
+            std::string method_body;
+            bool has_body = match[10].str() == "{";
+
+            if (has_body) {
+                size_t body_end = findMatchingBrace(section, match_end - 1);
+                if (body_end == std::string::npos) {
+                    break;
+                }
+                method_body = section.substr(match_end, body_end - match_end);
+                match_end = body_end + 1;
+            }
 
             Function method;
             method.name = match[4].str();
+            if (method.name.compare(0, 8, "operator") == 0 && method.name.find('(') != std::string::npos) {
+                method.name = "operator()";  // Function call operator, spelled without spaces
+            }
+            std::smatch allocation_match;
+            static const std::regex allocation_name(R"(operator\s*(new|delete)\s*(\[\s*\])?)");
+            if (std::regex_match(method.name, allocation_match, allocation_name)) {
+                // Class-specific allocation functions: "operator new", "operator delete[]"
+                method.name = "operator " + allocation_match[1].str() + (allocation_match[2].matched ? "[]" : "");
+            }
+            size_t method_begin = match.position(0) + (search_start - section.cbegin());
+            method.span = spanOf(offset + method_begin, offset + match_end);
 
-            // Check if virtual
-            method.is_virtual = match[1].matched;
+            // Check if virtual (an override is virtual without saying so)
+            static const std::regex override_specifier(R"(\boverride\b)");
+            method.is_virtual = match[1].matched ||
+                                std::regex_search(match[5].second, match[0].second, override_specifier);
 
             // Check if static
             method.is_static = match[2].matched;
 
-            // Check if pure virtual (= 0)
-            method.is_pure_virtual = match[7].matched;
+            // Check for = 0, = delete, = default
+            if (match[8].matched) {
+                std::string specifier = match[8].str();
+                method.is_pure_virtual = specifier.find('0') != std::string::npos;
+                method.is_deleted = specifier.find("delete") != std::string::npos;
+                method.is_defaulted = specifier.find("default") != std::string::npos;
+            }
 
+            // Check if destructor (~ClassName)
+            if (method.name[0] == '~') {
+                method.is_destructor = true;
+                method.name = method.name.substr(1);
+                method.return_type = std::make_shared<Type>(TypeKind::Void);
+            }
             // Check if constructor (no return type and name matches class)
-            if (match[3].str().empty() || match[3].str() == class_decl.name) {
+            else if (match[3].str().empty() || match[3].str() == class_decl.name) {
                 method.is_constructor = true;
                 method.return_type = nullptr;
+            } else if (match[7].matched && trim(match[3].str()) == "auto") {
+                // Trailing return type: auto f() -> T
+                method.return_type = parseType(match[7].str());
             } else {
+                // A plain `auto` is deduced from the body once the class is parsed
                 method.return_type = parseType(match[3].str());
             }
 
             // Parse parameters
             std::string params_str = match[5].str();
             if (!params_str.empty()) {
-                parseParameters(params_str, method);
+                size_t params_begin = match.position(5) + (search_start - section.cbegin());
+                parseParameters(params_str, offset + params_begin, method);
             }
 
             // Check if const method
             method.is_const = match[6].matched;
 
+            // Store constructor member initializers if present
+            if (match[9].matched) {
+                method.member_initializers = match[9].str();
+            }
+
             // Store body if present
-            if (match[8].matched) {
-                method.body = match[8].str();
+            if (has_body) {
+                method.body = method_body;
             }
 
             class_decl.methods.push_back(method);
+            search_start = section.cbegin() + match_end;
         }
     }
 
     /**
      * Parse function parameters
      */
-    void parseParameters(const std::string& params_str, Function& func) {
-        // Split by commas (but not inside <>)
+    void parseParameters(const std::string& params_str, size_t offset, Function& func) {
+        // Split by commas (but not inside <> or the parameters of a function pointer)
         std::vector<std::string> param_strs;
+        std::vector<size_t> param_starts;
         int angle_depth = 0;
+        int paren_depth = 0;
         size_t start = 0;
 
         for (size_t i = 0; i < params_str.length(); ++i) {
             if (params_str[i] == '<') angle_depth++;
             else if (params_str[i] == '>') angle_depth--;
-            else if (params_str[i] == ',' && angle_depth == 0) {
+            else if (params_str[i] == '(') paren_depth++;
+            else if (params_str[i] == ')') paren_depth--;
+            else if (params_str[i] == ',' && angle_depth == 0 && paren_depth == 0) {
                 param_strs.push_back(params_str.substr(start, i - start));
+                param_starts.push_back(start);
                 start = i + 1;
             }
         }
         param_strs.push_back(params_str.substr(start));
+        param_starts.push_back(start);
 
         // Parse each parameter
-        for (const auto& param_str : param_strs) {
+        for (size_t p = 0; p < param_strs.size(); ++p) {
+            const std::string& param_str = param_strs[p];
             std::string trimmed = trim(param_str);
             if (trimmed.empty()) continue;
 
+            // C variadic: f(const char* fmt, ...)
+            if (trimmed == "...") {
+                func.is_variadic = true;
+                continue;
+            }
+
             Parameter param;
+            size_t leading = param_str.find_first_not_of(" \t\n\r");
+            size_t param_begin = offset + param_starts[p] + leading;
+            param.span = spanOf(param_begin, param_begin + trimmed.size());
 
             // Simple parameter parsing: type name or just type
-            std::regex param_pattern(R"(([a-zA-Z_][\w:<>,\s*&]*?)\s+([a-zA-Z_]\w*)(?:\s*=\s*(.+))?)");
+            std::regex param_pattern(R"(([a-zA-Z_][\w:<>(),\s*&]*?)\s+([a-zA-Z_]\w*)(?:\s*=\s*(.+))?)");
             std::smatch match;
+            std::string pointer_type;
 
-            if (std::regex_match(trimmed, match, param_pattern)) {
+            if (splitPointerDeclarator(trimmed, pointer_type, param.name)) {
+                param.type = parseType(pointer_type);
+            } else if (std::regex_match(trimmed, match, param_pattern)) {
                 param.type = parseType(match[1].str());
                 param.name = match[2].str();
 
@@ -316,6 +1112,28 @@ private:
     /**
      * Parse type string into Type object
      */
+    /**
+     * T name[A][B]: array of A arrays of B elements
+     */
+    std::shared_ptr<Type> parseArrayType(const std::string& type_str, const std::string& bounds) {
+        std::vector<std::string> sizes;
+        std::regex bound_pattern(R"(\[([^\[\]]*)\])");
+        for (auto it = std::sregex_iterator(bounds.begin(), bounds.end(), bound_pattern);
+             it != std::sregex_iterator(); ++it) {
+            sizes.push_back(trim((*it)[1].str()));
+        }
+
+        auto type = parseType(type_str);
+        for (auto size = sizes.rbegin(); size != sizes.rend(); ++size) {
+            auto array_type = std::make_shared<Type>(TypeKind::Array);
+            array_type->element_type = type;
+            array_type->array_size = *size;
+            array_type->name = trim(type_str) + "[" + *size + "]";
+            type = array_type;
+        }
+        return type;
+    }
+
     std::shared_ptr<Type> parseType(const std::string& type_str) {
         std::string trimmed = trim(type_str);
 
@@ -326,6 +1144,51 @@ private:
             trimmed = trim(trimmed.substr(5));
         }
 
+        // Nothing left to parse (e.g. a lone `const` or `*`)
+        if (trimmed.empty()) {
+            auto void_type = std::make_shared<Type>(TypeKind::Void);
+            void_type->name = "void";
+            return void_type;
+        }
+
+        // Pointers to functions and members: void (C::*)(int) const, int C::*
+        static const std::regex function_pointer(R"(^(.+?)\(\s*((?:\w+::)*)\*\s*\)\s*\(([^()]*)\)\s*(const)?$)");
+        static const std::regex member_pointer(R"(^(.+?)\s*((?:\w+::)+)\*$)");
+        std::smatch pointer_match;
+        // Only tried on types with their punctuation: on a long nested
+        // template the lazy prefixes would retry at every position
+        bool has_parens = trimmed.find('(') != std::string::npos;
+        if (has_parens && std::regex_match(trimmed, pointer_match, function_pointer)) {
+            auto function_type = std::make_shared<Type>(TypeKind::Function);
+            function_type->name = trimmed;
+            function_type->element_type = parseType(pointer_match[1].str());
+            function_type->member_of = memberClass(pointer_match[2].str());
+            function_type->is_const = pointer_match[4].matched;
+            for (const auto& param : splitTemplateArguments(pointer_match[3].str())) {
+                if (param != "void") function_type->parameter_types.push_back(parseType(param));
+            }
+            return function_type;
+        }
+        // std::function<R(Args)>: a callable with the signature of a function pointer
+        static const std::regex std_function(R"(^(?:std::)?function\s*<\s*(.+?)\s*\(([^()]*)\)\s*>$)");
+        if (has_parens && std::regex_match(trimmed, pointer_match, std_function)) {
+            auto function_type = std::make_shared<Type>(TypeKind::Function);
+            function_type->name = trimmed;
+            function_type->element_type = parseType(pointer_match[1].str());
+            for (const auto& param : splitTemplateArguments(pointer_match[2].str())) {
+                if (param != "void") function_type->parameter_types.push_back(parseType(param));
+            }
+            return function_type;
+        }
+        if (trimmed.back() == '*' && trimmed.find("::") != std::string::npos &&
+            std::regex_match(trimmed, pointer_match, member_pointer)) {
+            auto member_type = std::make_shared<Type>(TypeKind::Pointer);
+            member_type->name = trimmed;
+            member_type->element_type = parseType(pointer_match[1].str());
+            member_type->member_of = memberClass(pointer_match[2].str());
+            return member_type;
+        }
+
         // Check for pointer
         if (trimmed.back() == '*') {
             trimmed.pop_back();
@@ -337,6 +1200,16 @@ private:
             return ptr_type;
         }
 
+        // Check for rvalue reference
+        if (trimmed.size() >= 2 && trimmed.compare(trimmed.size() - 2, 2, "&&") == 0) {
+            trimmed = trim(trimmed.substr(0, trimmed.size() - 2));
+            auto element = parseType(trimmed);
+            auto rref_type = std::make_shared<Type>(TypeKind::RValueReference);
+            rref_type->element_type = element;
+            rref_type->name = trimmed + "&&";
+            return rref_type;
+        }
+
         // Check for reference
         if (trimmed.back() == '&') {
             trimmed.pop_back();
@@ -373,6 +1246,94 @@ private:
             return ptr_type;
         }
 
+        // std::pair / std::tuple: element types become template arguments
+        if ((trimmed.find("std::pair<") == 0 || trimmed.find("std::tuple<") == 0) && trimmed.back() == '>') {
+            TypeKind kind = trimmed.find("std::pair<") == 0 ? TypeKind::StdPair : TypeKind::StdTuple;
+            auto tuple_type = std::make_shared<Type>(kind);
+            tuple_type->name = trimmed;
+            tuple_type->is_const = is_const;
+
+            size_t start = trimmed.find('<') + 1;
+            for (const auto& arg : splitTemplateArguments(trimmed.substr(start, trimmed.size() - start - 1))) {
+                tuple_type->template_args.push_back(parseType(arg));
+            }
+            return tuple_type;
+        }
+
+        // std::array<T, N>: a fixed-size array; std::span<T>: a borrowed
+        // slice, shared when T is const, whose length goes with it
+        static const std::regex sequence_view(R"(^std::(array|span)\s*<(.*)>$)");
+        std::smatch view_match;
+        if (std::regex_match(trimmed, view_match, sequence_view)) {
+            auto args = splitTemplateArguments(view_match[2].str());
+            if (!args.empty() && args.size() <= 2) {
+                auto array_type = std::make_shared<Type>(TypeKind::Array);
+                array_type->element_type = parseType(args[0]);
+                if (args.size() == 2 && args[1].find("dynamic_extent") == std::string::npos) {
+                    array_type->array_size = args[1];
+                }
+                if (view_match[1] == "array") {
+                    array_type->name = trimmed;
+                    array_type->is_const = is_const;
+                    return array_type;
+                }
+                array_type->name = args[0] + "[]";
+                auto span_type = std::make_shared<Type>(TypeKind::Reference);
+                span_type->element_type = array_type;
+                span_type->name = trimmed;
+                span_type->is_const = array_type->element_type->is_const;
+                return span_type;
+            }
+        }
+
+        // std::string_view: a read-only borrow of a string, like const std::string&
+        if (trimmed == "std::string_view" || trimmed == "string_view") {
+            auto view_type = std::make_shared<Type>(TypeKind::Reference);
+            view_type->element_type = parseType("std::string");
+            view_type->element_type->is_const = true;
+            view_type->name = trimmed;
+            view_type->is_const = true;
+            return view_type;
+        }
+
+        // Containers with an allocator argument (std::vector<T, Pool<T>>) or
+        // from std::pmr; the rest keep their spelling for the container mapper
+        static const std::regex allocator_container(
+            R"(^std::(pmr::)?(vector|deque|list|set|map|unordered_set|unordered_map)<(.*)>$)");
+        std::smatch container_match;
+        if (std::regex_match(trimmed, container_match, allocator_container)) {
+            static const std::map<std::string, std::pair<TypeKind, size_t>> containers = {
+                // Kind, and the allocator's position among the template arguments
+                {"vector", {TypeKind::StdVector, 1}},
+                {"deque", {TypeKind::StdDeque, 1}},
+                {"list", {TypeKind::StdList, 1}},
+                {"set", {TypeKind::StdSet, 2}},
+                {"map", {TypeKind::StdMap, 3}},
+                {"unordered_set", {TypeKind::StdUnorderedSet, 3}},
+                {"unordered_map", {TypeKind::StdUnorderedMap, 4}},
+            };
+            const auto& container = containers.at(container_match[2].str());
+            auto args = splitTemplateArguments(container_match[3].str());
+            bool is_pmr = container_match[1].matched;
+            if (is_pmr || args.size() == container.second + 1) {
+                auto container_type = std::make_shared<Type>(container.first);
+                container_type->name = trimmed;
+                container_type->is_const = is_const;
+                // Element types only: comparators and hashers have no place in the Rust type
+                size_t elements = container.first == TypeKind::StdMap || container.first == TypeKind::StdUnorderedMap ? 2 : 1;
+                for (size_t i = 0; i < elements && i < args.size(); ++i) {
+                    container_type->template_args.push_back(parseType(args[i]));
+                }
+                if (is_pmr) {
+                    container_type->allocator = std::make_shared<Type>(TypeKind::Class);
+                    container_type->allocator->name = "std::pmr::polymorphic_allocator";
+                } else {
+                    container_type->allocator = parseType(args.back());
+                }
+                return container_type;
+            }
+        }
+
         // Check for arrays
         size_t bracket_pos = trimmed.find('[');
         if (bracket_pos != std::string::npos) {
@@ -384,6 +1345,14 @@ private:
             return array_type;
         }
 
+        // Type parameter of the enclosing template
+        if (template_names_.count(trimmed)) {
+            auto param_type = std::make_shared<Type>(TypeKind::Template);
+            param_type->name = trimmed;
+            param_type->is_const = is_const;
+            return param_type;
+        }
+
         // Built-in types
         auto builtin = mapBuiltinType(trimmed);
         if (builtin) {
@@ -391,13 +1360,83 @@ private:
             return builtin;
         }
 
+        // Enum declared in the source (possibly qualified: ns::Status)
+        std::string unqualified = trimmed.substr(trimmed.rfind(':') == std::string::npos ? 0 : trimmed.rfind(':') + 1);
+        if (enum_names_.count(unqualified)) {
+            auto enum_type = std::make_shared<Type>(TypeKind::Enum);
+            enum_type->name = unqualified;
+            enum_type->is_const = is_const;
+            return enum_type;
+        }
+
         // Custom class/struct type
         auto class_type = std::make_shared<Type>(TypeKind::Class);
         class_type->name = trimmed;
         class_type->is_const = is_const;
+        // Arguments of library templates (boost::optional<int>), for mapping packs
+        size_t open = trimmed.find('<');
+        if (open != std::string::npos && trimmed.back() == '>') {
+            for (const auto& arg : splitTemplateArguments(trimmed.substr(open + 1, trimmed.size() - open - 2))) {
+                class_type->template_args.push_back(parseType(arg));
+            }
+        }
         return class_type;
     }
 
+    /**
+     * Class of a pointer to member: `ns::Machine::` -> Machine
+     */
+    static std::string memberClass(std::string qualifier) {
+        if (qualifier.size() >= 2) qualifier.erase(qualifier.size() - 2);
+        size_t colon = qualifier.rfind("::");
+        return colon == std::string::npos ? qualifier : qualifier.substr(colon + 2);
+    }
+
+    /**
+     * Declarators naming a pointer to a function or member:
+     * void (C::*name)(int), void (*name)(int), int C::*name
+     */
+    bool splitPointerDeclarator(const std::string& declaration, std::string& type_str,
+                                std::string& name) const {
+        static const std::regex function_pattern(
+            R"(^(.+?)\(\s*((?:\w+::)*)\*\s*([a-zA-Z_]\w*)\s*\)\s*(\([^()]*\)\s*(?:const)?)\s*$)");
+        static const std::regex member_pattern(R"(^(.+?(?:\w+::)+\*)\s*([a-zA-Z_]\w*)\s*$)");
+
+        std::smatch match;
+        if (std::regex_match(declaration, match, function_pattern)) {
+            type_str = trim(match[1].str()) + " (" + match[2].str() + "*)" + trim(match[4].str());
+            name = match[3].str();
+            return true;
+        }
+        if (std::regex_match(declaration, match, member_pattern)) {
+            type_str = match[1].str();
+            name = match[2].str();
+            return true;
+        }
+        return false;
+    }
+
+    /**
+     * Split a template argument list at top-level commas
+     */
+    std::vector<std::string> splitTemplateArguments(const std::string& args) const {
+        std::vector<std::string> result;
+        int depth = 0;
+        size_t start = 0;
+        for (size_t i = 0; i <= args.size(); ++i) {
+            if (i == args.size() || (args[i] == ',' && depth == 0)) {
+                std::string arg = trim(args.substr(start, i - start));
+                if (!arg.empty()) result.push_back(arg);
+                start = i + 1;
+            } else if (args[i] == '<' || args[i] == '(') {
+                depth++;
+            } else if (args[i] == '>' || args[i] == ')') {
+                depth--;
+            }
+        }
+        return result;
+    }
+
     /**
      * Map C++ built-in types
      */
@@ -411,6 +1450,7 @@ private:
             {"long", TypeKind::Integer},
             {"float", TypeKind::Float},
             {"double", TypeKind::Float},
+            {"long double", TypeKind::Float},
             {"size_t", TypeKind::Integer}
         };
 
@@ -421,20 +1461,65 @@ private:
             return type;
         }
 
+        std::string integer = canonicalIntegerName(type_name);
+        if (!integer.empty()) {
+            auto type = std::make_shared<Type>(TypeKind::Integer);
+            type->name = integer;
+            return type;
+        }
+
         return nullptr;
     }
 
+    /**
+     * Integer types under one spelling each: unsigned -> unsigned int,
+     * long int -> long, std::uint32_t -> uint32_t
+     * @return Empty if type_name is not an integer type
+     */
+    static std::string canonicalIntegerName(const std::string& type_name) {
+        static const std::set<std::string> fixed = {
+            "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t", "uint64_t",
+            "intptr_t", "uintptr_t", "intmax_t", "uintmax_t", "size_t", "ssize_t", "ptrdiff_t"};
+        std::string name = type_name.compare(0, 5, "std::") == 0 ? type_name.substr(5) : type_name;
+        if (fixed.count(name)) return name;
+
+        // Combinations of signed, unsigned, short, long, int and char
+        std::istringstream words(type_name);
+        std::string word;
+        bool is_unsigned = false;
+        bool is_signed = false;
+        bool is_char = false;
+        bool is_short = false;
+        bool has_words = false;
+        int longs = 0;
+        while (words >> word) {
+            has_words = true;
+            if (word == "unsigned") is_unsigned = true;
+            else if (word == "signed") is_signed = true;
+            else if (word == "char") is_char = true;
+            else if (word == "short") is_short = true;
+            else if (word == "long") longs++;
+            else if (word != "int") return "";
+        }
+        if (!has_words || (is_unsigned && is_signed) || longs > 2) return "";
+
+        std::string base = is_char ? "char" : is_short ? "short" : longs == 2 ? "long long" : longs == 1 ? "long" : "int";
+        if (is_unsigned) return "unsigned " + base;
+        if (is_signed && is_char) return "signed char";
+        return base;
+    }
+
     /**
      * Trim whitespace from string
      */
     std::string trim(const std::string& str) const {
         size_t start = 0;
-        while (start < str.length() && std::isspace(str[start])) {
+        while (start < str.length() && std::isspace(static_cast<unsigned char>(str[start]))) {
             start++;
         }
 
         size_t end = str.length();
-        while (end > start && std::isspace(str[end - 1])) {
+        while (end > start && std::isspace(static_cast<unsigned char>(str[end - 1]))) {
             end--;
         }
 
@@ -0,0 +1,342 @@
+/**
+ * Slice Analyzer
+ * Finds the C++ spellings of a slice: (T* ptr, size_t len) parameter
+ * pairs, std::span parameters, and buffers allocated with new T[n] and
+ * freed with delete[]
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+#include <map>
+#include <set>
+
+namespace hybrid {
+
+/**
+ * Slice Analyzer
+ * Whole-program pass: a call is rewritten only when its callee's name
+ * has one parameter list in the input, so overloads are left alone
+ */
+class SliceAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit SliceAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    void analyze(IR& ir) {
+        // Owners first: a pointer that owns its buffer is not lent as a slice
+        for (auto& class_decl : ir.getClasses()) {
+            findOwnedArrays(class_decl);
+        }
+
+        std::map<std::string, Signature> signatures;
+        auto declare = [&](Function& func) {
+            if (func.retained_reason.empty()) func.slice_params = findPairs(func);
+            Signature signature{func.parameters.size(), func.slice_params, spanParams(func), false};
+            auto found = signatures.emplace(func.name, signature);
+            if (!found.second && !sameSignature(found.first->second, signature)) found.first->second.ambiguous = true;
+        };
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) declare(method);
+        }
+        for (auto& func : ir.getFunctions()) declare(func);
+
+        std::map<std::string, Signature> fused;
+        for (const auto& entry : signatures) {
+            if (!entry.second.ambiguous && (!entry.second.pairs.empty() || !entry.second.spans.empty())) {
+                fused.insert(entry);
+            }
+        }
+
+        for (auto& class_decl : ir.getClasses()) {
+            std::set<std::string> owned;
+            std::set<std::string> arrays;
+            for (const auto& field : class_decl.fields) {
+                if (field.type && field.type->owned_array) owned.insert(field.name);
+                if (isArrayValue(field.type)) arrays.insert(field.name);
+            }
+            for (auto& method : class_decl.methods) {
+                if (method.retained_reason.empty()) analyzeFunction(method, fused, owned, arrays);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            if (func.retained_reason.empty()) analyzeFunction(func, fused, {}, {});
+        }
+    }
+
+private:
+    struct Signature {
+        size_t arity = 0;
+        std::vector<SliceParamInfo> pairs;
+        std::map<size_t, bool> spans;    // std::span parameters by position; true if shared
+        bool ambiguous = false;
+    };
+
+    TypeParser parse_type_;
+
+    static bool sameSignature(const Signature& a, const Signature& b) {
+        if (a.arity != b.arity || a.pairs.size() != b.pairs.size() || a.spans != b.spans) return false;
+        for (size_t i = 0; i < a.pairs.size(); ++i) {
+            if (a.pairs[i].position != b.pairs[i].position || a.pairs[i].is_const != b.pairs[i].is_const) return false;
+        }
+        return true;
+    }
+
+    static bool isSpan(const std::shared_ptr<Type>& type) {
+        return type && type->kind == TypeKind::Reference && type->name.compare(0, 9, "std::span") == 0;
+    }
+
+    static std::map<size_t, bool> spanParams(const Function& func) {
+        std::map<size_t, bool> spans;
+        for (size_t i = 0; i < func.parameters.size(); ++i) {
+            if (isSpan(func.parameters[i].type)) spans[i] = func.parameters[i].type->is_const;
+        }
+        return spans;
+    }
+
+    /**
+     * A std::array or std::vector held by value, which Rust lends to a
+     * slice parameter only when borrowed
+     */
+    static bool isArrayValue(const std::shared_ptr<Type>& type) {
+        return type && (type->kind == TypeKind::Array || type->kind == TypeKind::StdVector);
+    }
+
+    /**
+     * Pointer fields assigned new T[n] and released with delete[] in the
+     * class's own methods become Box<[T]>
+     */
+    void findOwnedArrays(ClassDecl& class_decl) {
+        for (auto& field : class_decl.fields) {
+            if (!isBufferPointer(field.type)) continue;
+            std::regex allocated("(?:^|[^.\\w>])(?:this\\s*->\\s*)?" + field.name + "\\s*=\\s*new\\s+[^;(\\[]+\\[");
+            std::regex released("\\bdelete\\s*\\[\\s*\\]\\s*(?:this\\s*->\\s*)?" + field.name + "\\s*;");
+            bool is_allocated = false;
+            bool is_released = false;
+            for (const auto& method : class_decl.methods) {
+                is_allocated = is_allocated || std::regex_search(method.body, allocated);
+                is_released = is_released || std::regex_search(method.body, released);
+            }
+            if (!is_allocated || !is_released) continue;
+            auto owned = std::make_shared<Type>(*field.type);
+            owned->owned_array = true;
+            field.type = owned;
+        }
+    }
+
+    /**
+     * T* to data, not to a pointer, a function or untyped memory
+     */
+    static bool isBufferPointer(const std::shared_ptr<Type>& type) {
+        if (!type || type->kind != TypeKind::Pointer || !type->member_of.empty() || type->owned_array ||
+            type->name.find("_ptr<") != std::string::npos || !type->element_type) {
+            return false;
+        }
+        TypeKind element = type->element_type->kind;
+        return element != TypeKind::Void && element != TypeKind::Pointer && element != TypeKind::Function;
+    }
+
+    /**
+     * A buffer pointer followed by an integer named like its length, when
+     * the body neither moves the pointer, changes the length nor keeps
+     * the pointer past the call
+     */
+    std::vector<SliceParamInfo> findPairs(const Function& func) const {
+        static const std::regex length_name(
+            R"(^(n|len|length|size|count|num|sz|\w+_(len|length|size|count)|(n|num)_\w+|\w+(Len|Length|Size|Count))$)");
+
+        std::vector<SliceParamInfo> pairs;
+        for (size_t i = 0; i + 1 < func.parameters.size(); ++i) {
+            const Parameter& pointer = func.parameters[i];
+            const Parameter& length = func.parameters[i + 1];
+            if (!isBufferPointer(pointer.type) || !length.type || length.type->kind != TypeKind::Integer ||
+                !std::regex_match(length.name, length_name)) {
+                continue;
+            }
+            // copy(char* dst, const char* src, int n): a length shared by two buffers belongs to neither
+            const Parameter* previous = i > 0 ? &func.parameters[i - 1] : nullptr;
+            if (previous && isBufferPointer(previous->type) &&
+                previous->type->element_type->name == pointer.type->element_type->name) {
+                continue;
+            }
+            if (isModified(func.body, pointer.name) || isModified(func.body, length.name) ||
+                escapes(func.body, pointer.name)) {
+                continue;
+            }
+
+            SliceParamInfo pair;
+            pair.pointer = pointer.name;
+            pair.length = length.name;
+            pair.length_type = length.type;
+            pair.position = i;
+            pair.is_const = pointer.type->is_const;
+            pairs.push_back(pair);
+            ++i;
+        }
+        return pairs;
+    }
+
+    /**
+     * ++p, p += 4, len--, p = other, &len
+     */
+    static bool isModified(const std::string& body, const std::string& name) {
+        std::regex modified("(?:\\+\\+|--)\\s*" + name + "\\b|(?:^|[^.\\w>*])" + name +
+                            "\\s*(?:\\+\\+|--|[-+*/%&|^]?=(?!=))|(?:^|[^&\\w)\\]])&\\s*" + name + "\\b(?!\\s*\\[)");
+        return std::regex_search(body, modified);
+    }
+
+    /**
+     * data_ = p; or return p; keeps the pointer after the call returns
+     */
+    static bool escapes(const std::string& body, const std::string& name) {
+        std::regex kept("(?:[^=!<>]=|\\breturn)\\s*" + name + "\\s*;");
+        return std::regex_search(body, kept);
+    }
+
+    void analyzeFunction(Function& func, const std::map<std::string, Signature>& fused,
+                         const std::set<std::string>& owned_fields, const std::set<std::string>& array_fields) {
+        const std::string& body = func.body;
+        if (body.empty()) return;
+        std::vector<std::pair<size_t, SliceSiteInfo>> found;
+
+        // Arrays and vectors by value: the fields, parameters and locals a span call borrows
+        std::set<std::string> arrays = array_fields;
+        for (const auto& param : func.parameters) {
+            if (isArrayValue(param.type)) arrays.insert(param.name);
+            else arrays.erase(param.name);
+        }
+        static const std::regex array_local(R"(\bstd::(?:array|vector)\s*<[^;{}()]*>\s+([a-zA-Z_]\w*)\s*[;={])");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), array_local); it != std::sregex_iterator(); ++it) {
+            arrays.insert((*it)[1].str());
+        }
+
+        // Methods of std::span parameters
+        static const std::regex span_method(
+            R"(^\s*\.\s*(size|size_bytes|empty|data|front|back|first|last|subspan)\s*\()");
+        for (const auto& param : func.parameters) {
+            if (!isSpan(param.type)) continue;
+            std::regex use("(?:^|[^.\\w>])(" + param.name + ")\\b");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), use); it != std::sregex_iterator(); ++it) {
+                size_t pos = it->position(1);
+                size_t after = pos + param.name.size();
+                std::smatch match;
+                std::string rest = body.substr(after, 24);
+                if (!std::regex_search(rest, match, span_method)) continue;
+                size_t open = after + match.length(0) - 1;
+                size_t close = findClosingParen(body, open);
+                if (close == std::string::npos) continue;
+
+                SliceSiteInfo site;
+                site.kind = SliceSiteInfo::SpanMethod;
+                site.source_text = body.substr(pos, close + 1 - pos);
+                site.target = param.name;
+                site.method = match[1].str();
+                site.arguments = splitArguments(body.substr(open + 1, close - open - 1));
+                site.type = param.type;
+                found.emplace_back(pos, site);
+            }
+        }
+
+        // Calls handing a pointer and a length to a fused pair, or an array to a span
+        static const std::regex call_pattern(R"(\b([a-zA-Z_]\w*)\s*\()");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), call_pattern);
+             it != std::sregex_iterator(); ++it) {
+            auto callee = fused.find((*it)[1].str());
+            if (callee == fused.end()) continue;
+            size_t pos = it->position(0);
+            size_t end = pos;
+            while (end > 0 && std::isspace(static_cast<unsigned char>(body[end - 1]))) --end;
+            // int checksum(...) declares rather than calls
+            if (end > 0 && (std::isalnum(static_cast<unsigned char>(body[end - 1])) || body[end - 1] == '_')) {
+                size_t start = end;
+                while (start > 0 && (std::isalnum(static_cast<unsigned char>(body[start - 1])) || body[start - 1] == '_')) {
+                    --start;
+                }
+                static const std::set<std::string> keywords = {"return", "else", "case", "co_return", "do"};
+                if (!keywords.count(body.substr(start, end - start))) continue;
+            }
+            size_t open = pos + it->length(0) - 1;
+            size_t close = findClosingParen(body, open);
+            if (close == std::string::npos) continue;
+            auto args = splitArguments(body.substr(open + 1, close - open - 1));
+            if (args.size() != callee->second.arity) continue;
+
+            SliceSiteInfo site;
+            site.kind = SliceSiteInfo::FusedCall;
+            site.source_text = body.substr(pos, close + 1 - pos);
+            site.target = callee->first;
+            site.arguments = args;
+            site.fused = callee->second.pairs;
+            for (const auto& span : callee->second.spans) {
+                if (arrays.count(args[span.first])) site.borrowed[span.first] = span.second;
+            }
+            if (site.fused.empty() && site.borrowed.empty()) continue;
+            found.emplace_back(pos, site);
+        }
+
+        // Owned buffers: fields of the class, and locals released in the same body
+        std::set<std::string> owners = owned_fields;
+        static const std::regex local_pattern(
+            R"(\b([\w:]+(?:\s*<[^;]*?>)?)\s*\*\s*([a-zA-Z_]\w*)\s*=\s*new\s+([\w:]+(?:\s*<[^;]*?>)?)\s*\[([^\[\];]*)\]\s*(?:\(\s*\)|\{\s*\})?\s*;)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), local_pattern);
+             it != std::sregex_iterator(); ++it) {
+            std::string name = (*it)[2].str();
+            std::regex released("\\bdelete\\s*\\[\\s*\\]\\s*" + name + "\\s*;");
+            if (!std::regex_search(body, released) || escapes(body, name)) continue;
+            owners.insert(name);
+
+            SliceSiteInfo site;
+            site.kind = SliceSiteInfo::NewArray;
+            site.source_text = it->str(0);
+            site.target = name;
+            site.arguments = {trim((*it)[4].str())};
+            site.type = parse_type_((*it)[3].str());
+            site.is_declaration = true;
+            found.emplace_back(it->position(0), site);
+        }
+        for (const auto& owner : owners) {
+            std::regex assigned("(?:^|[^.\\w>])(?:this\\s*->\\s*)?" + owner +
+                                "\\s*=\\s*(new\\s+([\\w:]+(?:\\s*<[^;]*?>)?)\\s*\\[([^\\[\\];]*)\\](?:\\s*\\(\\s*\\)|\\s*\\{\\s*\\})?)");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), assigned);
+                 it != std::sregex_iterator(); ++it) {
+                SliceSiteInfo site;
+                site.kind = SliceSiteInfo::NewArray;
+                site.source_text = (*it)[1].str();
+                site.target = owner;
+                site.arguments = {trim((*it)[3].str())};
+                site.type = parse_type_((*it)[2].str());
+                found.emplace_back(it->position(1), site);
+            }
+            std::regex released("\\bdelete\\s*\\[\\s*\\]\\s*(?:this\\s*->\\s*)?" + owner + "\\s*;");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), released);
+                 it != std::sregex_iterator(); ++it) {
+                SliceSiteInfo site;
+                site.kind = SliceSiteInfo::DeleteArray;
+                site.source_text = it->str(0);
+                site.target = owner;
+                found.emplace_back(it->position(0), site);
+            }
+        }
+
+        // A declaration covers the new[] it contains
+        sortBySourcePosition(found);
+        size_t covered = 0;
+        for (auto& entry : found) {
+            if (entry.first < covered) continue;
+            if (entry.second.is_declaration) covered = entry.first + entry.second.source_text.size();
+            func.slice_sites.push_back(entry.second);
+        }
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
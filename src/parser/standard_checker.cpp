@@ -0,0 +1,151 @@
+/**
+ * Standard Checker
+ * Rejects language and library features newer than the selected --std
+ */
+
+#include "ir.h"
+#include <regex>
+#include <string>
+#include <vector>
+
+namespace hybrid {
+
+/**
+ * Standard Checker
+ * Scans comment-free source for features introduced after C++11
+ */
+class StandardChecker {
+public:
+    explicit StandardChecker(CppStandard standard) : standard_(standard) {}
+
+    /**
+     * Find the first feature the standard does not support
+     * @param code Source with comments removed
+     * @param offset Set to the offset of the feature in code
+     * @return Error message (e.g. "structured bindings require --std=c++17"),
+     *         or empty if everything is supported
+     */
+    std::string findUnsupported(const std::string& code, size_t& offset) const {
+        if (standard_ == CppStandard::Cxx20) {
+            return "";
+        }
+
+        std::string text = blankLiterals(code);
+        std::string message;
+        size_t first = std::string::npos;
+
+        for (const auto& feature : features()) {
+            if (feature.since <= standard_) {
+                continue;
+            }
+
+            std::smatch match;
+            if (std::regex_search(text, match, feature.pattern) &&
+                static_cast<size_t>(match.position(0)) < first) {
+                first = match.position(0);
+                message = feature.description;
+                size_t placeholder = message.find("{}");
+                if (placeholder != std::string::npos) {
+                    message.replace(placeholder, 2, match[1].str());
+                }
+                message += " --std=" + standardName(feature.since);
+            }
+        }
+
+        offset = first == std::string::npos ? 0 : first;
+        return message;
+    }
+
+private:
+    struct Feature {
+        std::regex pattern;
+        std::string description;   // "{}" is replaced by the first capture
+        CppStandard since;
+    };
+
+    CppStandard standard_;
+
+    static const std::vector<Feature>& features() {
+        static const std::vector<Feature> table = {
+            // C++14
+            {std::regex(R"(\bauto\s+[A-Za-z_]\w*\s*\([^;{}()]*\)\s*(?:const\s*)?(?:noexcept\s*)?\{)"),
+             "function return type deduction requires", CppStandard::Cxx14},
+            {std::regex(R"(\bdecltype\s*\(\s*auto\s*\))"), "'decltype(auto)' requires", CppStandard::Cxx14},
+            {std::regex(R"(\[[^\[\]]*\]\s*\([^()]*\bauto\b)"), "generic lambdas require", CppStandard::Cxx14},
+            {std::regex(R"(\b0[bB][01])"), "binary literals require", CppStandard::Cxx14},
+            {std::regex(R"(\b[0-9][0-9A-Fa-fxX]*'[0-9A-Fa-f])"), "digit separators require", CppStandard::Cxx14},
+            {std::regex(R"(\b(std::make_unique|std::shared_timed_mutex)\b)"), "'{}' requires", CppStandard::Cxx14},
+            {std::regex(R"(\[\[\s*(deprecated)\b)"), "the [[{}]] attribute requires", CppStandard::Cxx14},
+
+            // C++17
+            {std::regex(R"(\bauto\s*&{0,2}\s*\[)"), "structured bindings require", CppStandard::Cxx17},
+            {std::regex(R"(\bif\s+constexpr\b)"), "'if constexpr' requires", CppStandard::Cxx17},
+            {std::regex(R"(\bnamespace\s+\w+\s*::)"), "nested namespace definitions require", CppStandard::Cxx17},
+            {std::regex(R"(\btemplate\s*<\s*auto\b)"), "'auto' template parameters require", CppStandard::Cxx17},
+            {std::regex(R"(\[\[\s*(nodiscard|maybe_unused|fallthrough)\b)"), "the [[{}]] attribute requires",
+             CppStandard::Cxx17},
+            {std::regex(R"(\b(std::(?:optional|variant|string_view|any|scoped_lock|shared_mutex|filesystem|byte))\b)"),
+             "'{}' requires", CppStandard::Cxx17},
+            {std::regex(R"(#\s*include\s*(<(?:optional|variant|string_view|any|filesystem|charconv)>))"),
+             "{} requires", CppStandard::Cxx17},
+
+            // C++20
+            {std::regex(R"(\bco_(?:await|return|yield)\b)"), "coroutines require", CppStandard::Cxx20},
+            {std::regex(R"(\bconcept\b)"), "concepts require", CppStandard::Cxx20},
+            {std::regex(R"(\brequires\b)"), "'requires' constraints require", CppStandard::Cxx20},
+            {std::regex(R"(<=>)"), "the three-way comparison operator '<=>' requires", CppStandard::Cxx20},
+            {std::regex(R"(\b(consteval|constinit|char8_t)\b)"), "'{}' requires", CppStandard::Cxx20},
+            {std::regex(R"(\[\[\s*(likely|unlikely|no_unique_address)\b)"), "the [[{}]] attribute requires",
+             CppStandard::Cxx20},
+            {std::regex(R"(\b(std::(?:span|format|jthread|stop_token|latch|barrier|counting_semaphore|source_location))\b)"),
+             "'{}' requires", CppStandard::Cxx20},
+            {std::regex(R"(#\s*include\s*(<(?:coroutine|span|format|concepts|ranges|compare|source_location)>))"),
+             "{} requires", CppStandard::Cxx20},
+        };
+        return table;
+    }
+
+    /**
+     * Replace the contents of string and character literals with spaces so
+     * that text inside them is not mistaken for a feature. A quote inside a
+     * number (`1'000`) is a digit separator, not a literal.
+     */
+    static std::string blankLiterals(const std::string& code) {
+        std::string text = code;
+        size_t i = 0;
+        while (i < text.size()) {
+            char quote = text[i];
+            if ((quote != '"' && quote != '\'') || isDigitSeparator(text, i)) {
+                ++i;
+                continue;
+            }
+
+            size_t end = i + 1;
+            while (end < text.size() && text[end] != quote && text[end] != '\n') {
+                size_t step = text[end] == '\\' ? 2 : 1;
+                for (size_t k = end; k < end + step && k < text.size(); ++k) {
+                    text[k] = ' ';
+                }
+                end += step;
+            }
+            i = end + 1;
+        }
+        return text;
+    }
+
+    static bool isDigitSeparator(const std::string& text, size_t quote) {
+        if (text[quote] != '\'' || quote == 0) {
+            return false;
+        }
+
+        // Walk back to the start of the token; separators only occur in numbers
+        size_t start = quote;
+        while (start > 0 && (std::isalnum(static_cast<unsigned char>(text[start - 1])) ||
+                             text[start - 1] == '\'' || text[start - 1] == '_')) {
+            --start;
+        }
+        return start < quote && std::isdigit(static_cast<unsigned char>(text[start]));
+    }
+};
+
+} // namespace hybrid
@@ -0,0 +1,316 @@
+/**
+ * Status Analyzer
+ * Recognizes error-code enums returned as status values and prepares for
+ * conversion to Result<T, Status> with ? propagation
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <map>
+#include <set>
+
+namespace hybrid {
+
+/**
+ * Status Analyzer
+ * Whole-program pass: an enum is an error code when methods across the
+ * program return it and one of its enumerators means success
+ */
+class StatusAnalyzer {
+public:
+    /**
+     * Analyze all enums and method bodies
+     */
+    void analyze(IR& ir) {
+        for (auto& enum_decl : ir.getEnums()) {
+            classifyEnum(ir, enum_decl);
+        }
+        collectStatusFunctions(ir);
+
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) {
+                analyzeFunction(ir, method);
+            }
+        }
+        for (auto& func : ir.getFunctions()) {
+            analyzeFunction(ir, func);
+        }
+    }
+
+private:
+    std::map<std::string, std::set<std::string>> status_functions_;  // Enum -> functions returning it
+
+    /**
+     * Enumerator names that mean "no error"
+     */
+    static bool isSuccessName(const std::string& name) {
+        static const std::regex success_pattern(
+            R"(k?(?:OK|Ok|SUCCESS|Success|NO_ERROR|NoError)|\w+_(?:OK|SUCCESS))");
+        return std::regex_match(name, success_pattern);
+    }
+
+    static bool looksLikeStatusName(const std::string& name) {
+        static const std::regex status_pattern(R"(.*(?:[Ss]tatus|[Ee]rror|[Ee]rr|[Rr]esult|[Cc]ode)(?:_t)?)");
+        return std::regex_match(name, status_pattern);
+    }
+
+    void classifyEnum(const IR& ir, EnumDecl& enum_decl) {
+        if (enum_decl.enumerators.size() < 2) return;
+
+        std::string success;
+        for (const auto& enumerator : enum_decl.enumerators) {
+            if (isSuccessName(enumerator.name)) {
+                success = enumerator.name;
+                break;
+            }
+        }
+        if (success.empty()) return;
+
+        size_t returned_by = 0;
+        for (const auto& class_decl : ir.getClasses()) {
+            for (const auto& method : class_decl.methods) {
+                if (returnsEnum(method, enum_decl.name)) returned_by++;
+            }
+        }
+        for (const auto& func : ir.getFunctions()) {
+            if (returnsEnum(func, enum_decl.name)) returned_by++;
+        }
+
+        // One returning function is enough when the name says it is a status
+        if (returned_by >= 2 || (returned_by == 1 && looksLikeStatusName(enum_decl.name))) {
+            enum_decl.is_error_code = true;
+            enum_decl.success_value = success;
+        }
+    }
+
+    static bool returnsEnum(const Function& func, const std::string& name) {
+        return func.return_type && func.return_type->kind == TypeKind::Enum &&
+               func.return_type->name == name && !func.is_constructor;
+    }
+
+    void collectStatusFunctions(const IR& ir) {
+        status_functions_.clear();
+        for (const auto& enum_decl : ir.getEnums()) {
+            if (!enum_decl.is_error_code) continue;
+            auto& names = status_functions_[enum_decl.name];
+            for (const auto& class_decl : ir.getClasses()) {
+                for (const auto& method : class_decl.methods) {
+                    if (returnsEnum(method, enum_decl.name)) names.insert(method.name);
+                }
+            }
+            for (const auto& func : ir.getFunctions()) {
+                if (returnsEnum(func, enum_decl.name)) names.insert(func.name);
+            }
+        }
+    }
+
+    void analyzeFunction(IR& ir, Function& func) {
+        std::vector<std::pair<size_t, StatusSiteInfo>> found;
+
+        for (const auto& enum_decl : ir.getEnums()) {
+            if (!enum_decl.is_error_code) continue;
+
+            if (returnsEnum(func, enum_decl.name)) {
+                func.status_enum = enum_decl.name;
+                detectOutParameter(func);
+                detectReturns(func.body, enum_decl, found);
+                detectPropagation(func.body, enum_decl, found);
+            }
+            detectStatusParameters(func, enum_decl);
+            detectChecks(ir, func, enum_decl, found);
+        }
+
+        appendInSourceOrder(found, func.status_sites);
+    }
+
+    /**
+     * Status parse(const std::string& text, int* out): `out` becomes the Ok value
+     */
+    void detectOutParameter(Function& func) {
+        if (func.parameters.empty()) return;
+
+        const auto& last = func.parameters.back();
+        bool writable = last.type && !last.type->is_const &&
+                        (last.type->kind == TypeKind::Pointer || last.type->kind == TypeKind::Reference) &&
+                        last.type->element_type && !last.type->element_type->is_const;
+        static const std::regex out_pattern(R"((?:out|result)(?:_\w*)?|\w+_out|out[A-Z]\w*)");
+        // Smart pointers are owned values, not out-parameters
+        bool smart = last.type && last.type->name.find("_ptr<") != std::string::npos;
+
+        if (writable && !smart && std::regex_match(last.name, out_pattern)) {
+            func.status_out_param = last.name;
+        }
+    }
+
+    /**
+     * Optional qualification of an enumerator: Status::INVALID
+     */
+    static std::string qualified(const EnumDecl& enum_decl, const std::string& enumerator) {
+        return "(?:(?:\\w+::)*" + enum_decl.name + "::)?" + enumerator + "\\b";
+    }
+
+    void detectReturns(const std::string& body, const EnumDecl& enum_decl,
+                       std::vector<std::pair<size_t, StatusSiteInfo>>& found) {
+        for (const auto& enumerator : enum_decl.enumerators) {
+            std::regex return_pattern("\\breturn\\s+" + qualified(enum_decl, enumerator.name) + "\\s*;");
+
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), return_pattern);
+                 it != std::sregex_iterator(); ++it) {
+                StatusSiteInfo site;
+                site.source_text = it->str();
+                if (enumerator.name == enum_decl.success_value) {
+                    site.kind = StatusSiteInfo::ReturnSuccess;
+                } else {
+                    site.kind = StatusSiteInfo::ReturnError;
+                    site.value = enumerator.name;
+                }
+                found.emplace_back(it->position(0), site);
+            }
+        }
+    }
+
+    /**
+     * if (s != OK) return s;   if (Status s = f(); s != OK) { return s; }
+     * Status s = f(); if (s != OK) return s;   RETURN_IF_ERROR(f());
+     */
+    void detectPropagation(const std::string& body, const EnumDecl& enum_decl,
+                           std::vector<std::pair<size_t, StatusSiteInfo>>& found) {
+        std::string status_type = R"((?:auto|(?:\w+::)*)" + enum_decl.name + ")";
+        std::regex if_pattern(
+            R"((?:)" + status_type + R"(\s+(\w+)\s*=\s*([^;{}]+);\s*)?)"
+            R"(\bif\s*\(\s*(?:)" + status_type + R"(\s+(\w+)\s*=\s*([^;]+);\s*)?(\w+)\s*!=\s*)" +
+            qualified(enum_decl, enum_decl.success_value) +
+            R"(\s*\)\s*(?:return\s+(\w+)\s*;|\{\s*return\s+(\w+)\s*;\s*\}))");
+
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), if_pattern);
+             it != std::sregex_iterator(); ++it) {
+            const std::smatch& match = *it;
+            std::string checked = match[5].str();
+            std::string returned = match[6].matched ? match[6].str() : match[7].str();
+            if (checked != returned || (match[3].matched && match[3].str() != checked)) {
+                continue;  // Returns something other than the failed status
+            }
+
+            StatusSiteInfo site;
+            site.kind = StatusSiteInfo::Propagate;
+            size_t pos = match.position(0);
+            site.source_text = match.str();
+            site.expression = checked;
+            if (match[3].matched) {
+                site.expression = trim(match[4].str());
+            }
+
+            // The status was declared just for this check: propagate the call itself
+            if (match[1].matched) {
+                std::string rest = match.suffix().str();
+                bool used_later = std::regex_search(rest, std::regex("\\b" + checked + "\\b"));
+                if (match[1].str() == checked && !match[3].matched && !used_later) {
+                    site.expression = trim(match[2].str());
+                } else {
+                    size_t skip = site.source_text.find(
+                        "if", match.position(2) - match.position(0) + match.length(2));
+                    pos += skip;
+                    site.source_text = site.source_text.substr(skip);
+                }
+            }
+            found.emplace_back(pos, site);
+        }
+
+        std::regex macro_pattern(R"(\bRETURN_IF_ERROR\s*\(([^;]*)\)\s*;)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), macro_pattern);
+             it != std::sregex_iterator(); ++it) {
+            StatusSiteInfo site;
+            site.kind = StatusSiteInfo::Propagate;
+            site.source_text = it->str();
+            site.expression = trim((*it)[1].str());
+            found.emplace_back(it->position(0), site);
+        }
+    }
+
+    /**
+     * int count(Status s): the status arrives as the Result of the call
+     * that produced it, so checks on it keep meaning the same
+     */
+    void detectStatusParameters(Function& func, const EnumDecl& enum_decl) {
+        for (const auto& param : func.parameters) {
+            if (param.type && param.type->kind == TypeKind::Enum && param.type->name == enum_decl.name) {
+                func.status_params.push_back(param.name);
+            }
+        }
+    }
+
+    /**
+     * Whether a checked expression is a Result in the translation: a call
+     * to a function returning the status, a local bound to one, or a
+     * status parameter
+     */
+    bool isResult(const Function& func, const EnumDecl& enum_decl, const std::string& expression) {
+        const auto& functions = status_functions_[enum_decl.name];
+        // Called function: the last name before the parenthesis
+        static const std::regex call_pattern(R"((?:[\w.:>-]*(?:->|::|\.))?(\w+)\s*\([^();]*\))");
+        static const std::regex name_pattern(R"([a-zA-Z_]\w*)");
+        std::smatch match;
+        if (std::regex_match(expression, match, call_pattern)) {
+            return functions.count(match[1].str()) > 0;
+        }
+        if (!std::regex_match(expression, name_pattern)) return false;
+
+        const auto& params = func.status_params;
+        if (std::find(params.begin(), params.end(), expression) != params.end()) return true;
+        std::regex bound_pattern("\\b(?:auto|(?:\\w+::)*" + enum_decl.name + ")\\s+" + expression +
+                                 R"(\s*=\s*(?:[\w.:>-]*(?:->|::|\.))?(\w+)\s*\()");
+        return std::regex_search(func.body, match, bound_pattern) && functions.count(match[1].str()) > 0;
+    }
+
+    /**
+     * s == OK / f() != Status::OK outside propagation sites
+     */
+    void detectChecks(IR& ir, const Function& func, const EnumDecl& enum_decl,
+                      std::vector<std::pair<size_t, StatusSiteInfo>>& found) {
+        const std::string& body = func.body;
+        std::regex check_pattern(R"(([a-zA-Z_][\w.]*(?:->\w+)*(?:\([^();]*\))?)\s*(==|!=)\s*)" +
+                                 qualified(enum_decl, enum_decl.success_value));
+
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), check_pattern);
+             it != std::sregex_iterator(); ++it) {
+            size_t pos = it->position(0);
+            bool inside_site = false;
+            for (const auto& entry : found) {
+                if (pos >= entry.first && pos < entry.first + entry.second.source_text.size()) {
+                    inside_site = true;
+                }
+            }
+            if (inside_site) continue;
+
+            // Without the success variant, the enum cannot hold the value compared
+            if (!isResult(func, enum_decl, (*it)[1].str())) {
+                Diagnostic diagnostic;
+                diagnostic.severity = Diagnostic::Warning;
+                diagnostic.context = func.name;
+                diagnostic.message = "'" + it->str() + "' compares a " + enum_decl.name +
+                                     " that is not the result of a call; " + enum_decl.success_value +
+                                     " is Ok(()) in Rust, so the comparison was left as is";
+                ir.addDiagnostic(diagnostic);
+                continue;
+            }
+
+            StatusSiteInfo site;
+            site.kind = StatusSiteInfo::Check;
+            site.source_text = it->str();
+            site.expression = (*it)[1].str();
+            site.is_success_check = (*it)[2].str() == "==";
+            found.emplace_back(pos, site);
+        }
+    }
+
+    std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
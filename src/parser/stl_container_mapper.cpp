@@ -146,7 +146,7 @@ public:
                 if (type->name == "unsigned long") return "u64";
                 if (type->name == "short") return "i16";
                 if (type->name == "unsigned short") return "u16";
-                if (type->name == "char") return "i8";
+                if (type->name == "char") return "u8";
                 if (type->name == "unsigned char") return "u8";
                 if (type->name.find("int8_t") != std::string::npos) return "i8";
                 if (type->name.find("int16_t") != std::string::npos) return "i16";
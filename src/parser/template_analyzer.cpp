@@ -95,22 +95,21 @@ private:
                 param.name = trim(trimmed.substr(class_pos + 5));
             }
         }
-        // Non-type parameter (e.g., int N, size_t Size)
+        // Non-type parameter (e.g., int N, size_t Size = 16)
         else {
             param.kind = TemplateParameter::NonType;
 
+            size_t eq_pos = trimmed.find('=');
+            if (eq_pos != std::string::npos) {
+                param.default_value = trim(trimmed.substr(eq_pos + 1));
+                trimmed = trim(trimmed.substr(0, eq_pos));
+            }
+
             // Find the last identifier as the name
             std::vector<std::string> tokens = split(trimmed, ' ');
+            tokens.erase(std::remove(tokens.begin(), tokens.end(), ""), tokens.end());
             if (!tokens.empty()) {
-                // Check for default value
-                std::string last_token = tokens.back();
-                size_t eq_pos = last_token.find('=');
-                if (eq_pos != std::string::npos) {
-                    param.name = trim(last_token.substr(0, eq_pos));
-                    param.default_value = trim(last_token.substr(eq_pos + 1));
-                } else {
-                    param.name = last_token;
-                }
+                param.name = tokens.back();
 
                 // Type is everything except the last token
                 std::string type_str;
@@ -0,0 +1,531 @@
+/**
+ * Thread Safety Analyzer
+ * Finds values handed to other threads and the shared_ptr types they
+ * reach. Rc is neither Send nor Sync, so rustc rejects every Rc crossing a
+ * thread boundary, far from the C++ construct that caused it: those
+ * shared_ptr types become Arc, around a Mutex where the pointee has no
+ * synchronization of its own. Raw pointers have no safe upgrade and are
+ * reported instead. A runtime trace adds the crossings the source does not
+ * show and drops the locks that were never needed
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include "runtime_trace.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+#include <map>
+#include <set>
+#include <cctype>
+
+namespace hybrid {
+
+/**
+ * Thread Safety Analyzer
+ * Whole-program pass: Rc<T> and Arc<T> do not mix, so once a pointee
+ * reaches another thread every shared_ptr to it changes
+ */
+class ThreadSafetyAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit ThreadSafetyAnalyzer(TypeParser parse_type, RuntimeTrace trace = {})
+        : parse_type_(std::move(parse_type)), trace_(std::move(trace)) {}
+
+    void analyze(IR& ir) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& method : class_decl.methods) findCrossings(ir, method, &class_decl);
+        }
+        for (auto& func : ir.getFunctions()) findCrossings(ir, func, nullptr);
+
+        // Everything a crossing value reaches must be Send as well
+        for (const auto& class_decl : ir.getClasses()) {
+            for (const auto& method : class_decl.methods) checkCrossings(ir, method, &class_decl);
+        }
+        for (const auto& func : ir.getFunctions()) checkCrossings(ir, func, nullptr);
+
+        // What the program did at run time, beyond what the source shows
+        applySharedTrace(ir);
+        applyMutexTrace(ir);
+
+        if (pointees_.empty()) return;
+        forEachType(ir, [this](const std::shared_ptr<Type>& type) { mark(type); });
+    }
+
+private:
+    using TypeVisitor = std::function<void(const std::shared_ptr<Type>&)>;
+
+    TypeParser parse_type_;
+    RuntimeTrace trace_;
+    std::map<std::string, bool> pointees_;   // Shared pointee -> needs a Mutex
+    std::set<std::string> visited_classes_;
+    std::set<std::string> reported_;
+
+    /**
+     * std::thread t(work, counter), std::thread t([&] { ... }),
+     * auto f = std::async(std::launch::async, work, counter)
+     */
+    void findCrossings(IR& ir, Function& func, const ClassDecl* owner) {
+        static const std::regex spawn(R"(\bstd::(?:j?thread\s*(\w+)?\s*([({])|async\s*\())");
+        static const std::regex assigned(R"((\w+)\s*=\s*$)");
+        static const std::regex policy(R"(^std::launch::)");
+        const std::string& body = func.body;
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), spawn); it != std::sregex_iterator(); ++it) {
+            size_t start = static_cast<size_t>(it->position(0));
+            bool braces = (*it)[2].str() == "{";
+            size_t open = start + it->length(0) - 1;
+            size_t close = findClosing(body, open, braces ? '{' : '(', braces ? '}' : ')');
+            if (close == std::string::npos) continue;
+            std::vector<std::string> args = splitArguments(body.substr(open + 1, close - open - 1));
+            if (!args.empty() && std::regex_search(args[0], policy)) args.erase(args.begin());
+            if (args.empty()) continue;  // std::thread t; or a declaration of a thread member
+
+            std::string thread = (*it)[1].str();
+            std::smatch match;
+            std::string prefix = body.substr(0, start);
+            if (thread.empty() && std::regex_search(prefix, match, assigned)) thread = match[1].str();
+
+            if (args[0].front() == '[') {
+                findCaptures(func, owner, args[0], thread, start);
+                continue;
+            }
+
+            // The thread function types arguments the body does not declare
+            std::string callable = args[0];
+            callable = callable.substr(callable.find_first_not_of("&"));
+            if (callable.rfind("::") != std::string::npos) callable = callable.substr(callable.rfind("::") + 2);
+            const Function* target = findFunction(ir, owner, callable);
+            bool member = target && owner && findMethod(*owner, callable);
+            for (size_t i = 1; i < args.size(); ++i) {
+                bool by_reference = false;
+                std::string value = passedValue(args[i], by_reference);
+                ThreadCrossingInfo crossing;
+                crossing.value = value;
+                crossing.thread = thread;
+                crossing.type = resolve(func, owner, value, start);
+                // &Worker::run, this, x: the object comes first
+                size_t param = member && args[1] == "this" ? i - 2 : i - 1;
+                if (!crossing.type && target && param < target->parameters.size()) {
+                    crossing.type = target->parameters[param].type;
+                }
+                if (!crossing.type) continue;
+                if (by_reference) {
+                    report(ir, Diagnostic::Warning, func.name,
+                           "'" + value + "' is passed to " + threadName(thread) + " by reference; "
+                           "std::thread::spawn only accepts 'static data, so borrow it in std::thread::scope "
+                           "or share it through Arc");
+                }
+                addCrossing(func, crossing);
+            }
+        }
+    }
+
+    /**
+     * Variables a thread lambda uses: parameters, locals declared before
+     * the thread, and this for members
+     */
+    void findCaptures(Function& func, const ClassDecl* owner, const std::string& lambda_text,
+                      const std::string& thread, size_t start) {
+        std::string captures = lambda_text.substr(1, lambda_text.find(']') - 1);
+        size_t open = lambda_text.find('{');
+        size_t close = open == std::string::npos ? open : findClosing(lambda_text, open, '{', '}');
+        if (close == std::string::npos) return;
+        // [cache] copies cache even if the body never uses it
+        std::string lambda = captures + ";" + lambda_text.substr(open + 1, close - open - 1);
+
+        bool uses_this = std::regex_search(captures, std::regex(R"(\bthis\b)"));
+        std::set<std::string> seen;
+        static const std::regex identifier(R"((^|[^.\w>:])([A-Za-z_]\w*)\b)");
+        for (auto id = std::sregex_iterator(lambda.begin(), lambda.end(), identifier);
+             id != std::sregex_iterator(); ++id) {
+            std::string name = (*id)[2].str();
+            if (name == "this") uses_this = true;
+            if (!seen.insert(name).second || name == "this") continue;
+            ThreadCrossingInfo crossing;
+            crossing.value = name;
+            crossing.thread = thread;
+            crossing.captured = true;
+            crossing.type = resolve(func, owner, name, start);
+            if (crossing.type) {
+                addCrossing(func, crossing);
+            } else if (owner && (findField(*owner, name) || findMethod(*owner, name))) {
+                uses_this = true;
+            }
+        }
+        if (uses_this && owner) {
+            ThreadCrossingInfo crossing;
+            crossing.value = "this";
+            crossing.thread = thread;
+            crossing.captured = true;
+            crossing.type = parse_type_(owner->name + "*");
+            addCrossing(func, crossing);
+        }
+    }
+
+    void checkCrossings(IR& ir, const Function& func, const ClassDecl* owner) {
+        for (const auto& crossing : func.thread_crossings) {
+            std::string thread = threadName(crossing.thread);
+            if (crossing.value == "this" && owner) {
+                report(ir, Diagnostic::Warning, func.name,
+                       "'this' is used by " + thread + "; a spawned thread cannot borrow self, so share the " +
+                       owner->name + " through Arc (Arc<Mutex<" + owner->name + ">> if the thread mutates it)");
+                reachClass(ir, owner->name, func.name, thread);
+                continue;
+            }
+            reach(ir, crossing.type, crossing.value, func.name, thread);
+        }
+    }
+
+    /**
+     * A value of this type reaches another thread: upgrade its shared
+     * pointers, follow pointees and fields, report what cannot be sent
+     */
+    void reach(IR& ir, const std::shared_ptr<Type>& type, const std::string& value, const std::string& context,
+               const std::string& thread) {
+        if (!type) return;
+        if (isShared(type)) {
+            const auto& element = type->element_type;
+            std::string key = typeKey(element);
+            bool lock = !element->is_const && !synchronized(ir, element);
+            auto existing = pointees_.find(key);
+            if (existing != pointees_.end()) {
+                existing->second = existing->second || lock;
+                return;
+            }
+            pointees_[key] = lock;
+            report(ir, Diagnostic::Note, context,
+                   "'" + value + "' (" + type->name + ") reaches " + thread + ": shared_ptr<" + key +
+                   "> becomes " + (lock ? "Arc<Mutex<" + key + ">>" : "Arc<" + key + ">") + " instead of Rc" +
+                   (lock ? ", since " + key + " has no synchronization of its own" : ""));
+            reach(ir, element, value, context, thread);
+            return;
+        }
+
+        switch (type->kind) {
+            case TypeKind::Pointer:
+                if (!type->member_of.empty()) return;
+                if (type->name.find("std::unique_ptr<") == 0) {
+                    reach(ir, type->element_type, value, context, thread);
+                    return;
+                }
+                report(ir, Diagnostic::Warning, context,
+                       "'" + value + "' (" + type->name + ") reaches " + thread + ", but raw pointers are "
+                       "neither Send nor Sync; pass an Arc, or wrap the pointer in a type with unsafe impl Send");
+                return;
+            case TypeKind::Reference:
+            case TypeKind::RValueReference:
+                reach(ir, type->element_type, value, context, thread);
+                return;
+            case TypeKind::Class:
+            case TypeKind::Struct:
+                reachClass(ir, type->name, context, thread);
+                break;
+            default:
+                break;
+        }
+        for (const auto& arg : type->template_args) reach(ir, arg, value, context, thread);
+        if (type->element_type) reach(ir, type->element_type, value, context, thread);
+    }
+
+    void reachClass(IR& ir, const std::string& name, const std::string& context, const std::string& thread) {
+        const ClassDecl* class_decl = ir.findClass(name);
+        if (!class_decl || !visited_classes_.insert(name).second) return;
+        for (const auto& field : class_decl->fields) {
+            reach(ir, field.type, name + "::" + field.name, context, thread);
+        }
+    }
+
+    void addCrossing(Function& func, const ThreadCrossingInfo& crossing) {
+        for (const auto& existing : func.thread_crossings) {
+            if (existing.value == crossing.value && existing.thread == crossing.thread) return;
+        }
+        func.thread_crossings.push_back(crossing);
+    }
+
+    /**
+     * Trace of the shared_ptr pointees: one held on several threads the
+     * source never hands over becomes Arc too; one never written while
+     * shared needs no Mutex
+     */
+    void applySharedTrace(IR& ir) {
+        std::set<std::string> declared;
+        forEachType(ir, [&](const std::shared_ptr<Type>& type) {
+            if (isShared(type)) declared.insert(typeKey(type->element_type));
+        });
+
+        for (const auto& observed : trace_.shared_ptrs) {
+            const std::string& key = observed.type;
+            if (!declared.count(key)) {
+                report(ir, Diagnostic::Warning, "",
+                       "runtime trace: there is no shared_ptr<" + key + "> in the source; the entry is ignored");
+                continue;
+            }
+
+            auto existing = pointees_.find(key);
+            if (observed.threads <= 1) {
+                if (existing != pointees_.end()) {
+                    report(ir, Diagnostic::Note, "",
+                           "runtime trace: shared_ptr<" + key + "> was only held on one thread, but the source "
+                           "hands it to another thread, so it stays Arc");
+                }
+                continue;
+            }
+
+            bool lock = observed.written_while_shared && !synchronized(ir, parse_type_(key));
+            if (existing == pointees_.end()) {
+                pointees_[key] = lock;
+                report(ir, Diagnostic::Note, "",
+                       "runtime trace: shared_ptr<" + key + "> was held on " + std::to_string(observed.threads) +
+                       " threads: it becomes " + (lock ? "Arc<Mutex<" + key + ">>" : "Arc<" + key + ">") +
+                       " instead of Rc");
+                reach(ir, parse_type_(key), key, "", "another thread");
+            } else if (existing->second && !lock) {
+                existing->second = false;
+                report(ir, Diagnostic::Note, "",
+                       "runtime trace: " + key + " was never written while shared: Arc<" + key +
+                       "> without a Mutex");
+            }
+        }
+    }
+
+    /**
+     * Trace of the mutex members: one only ever locked from one thread
+     * becomes a RefCell<()>, unless the source shares its class with
+     * another thread (RefCell is not Sync)
+     */
+    void applyMutexTrace(IR& ir) {
+        for (const auto& observed : trace_.mutexes) {
+            size_t scope = observed.name.rfind("::");
+            ClassDecl* class_decl = nullptr;
+            Variable* field = nullptr;
+            if (scope != std::string::npos) {
+                for (auto& candidate : ir.getClasses()) {
+                    if (candidate.name == observed.name.substr(0, scope)) class_decl = &candidate;
+                }
+            }
+            if (class_decl) {
+                for (auto& candidate : class_decl->fields) {
+                    if (candidate.name == observed.name.substr(scope + 2)) field = &candidate;
+                }
+            }
+            if (!field || !isPlainMutex(field->type)) {
+                report(ir, Diagnostic::Warning, "",
+                       "runtime trace: " + observed.name + " is not a std::mutex or std::shared_mutex member "
+                       "in the source; the entry is ignored");
+                continue;
+            }
+
+            if (observed.threads > 1) {
+                report(ir, Diagnostic::Note, class_decl->name,
+                       "runtime trace: " + observed.name + " was locked from " + std::to_string(observed.threads) +
+                       " threads (" + (observed.contended > 0 ? std::to_string(observed.contended) + " contended locks"
+                                                              : "never contended") +
+                       "); it stays a Mutex");
+                continue;
+            }
+            if (visited_classes_.count(class_decl->name)) {
+                report(ir, Diagnostic::Note, class_decl->name,
+                       "runtime trace: " + observed.name + " was only locked from one thread, but the source "
+                       "shares " + class_decl->name + " with another thread, so it stays a Mutex");
+                continue;
+            }
+
+            field->type->single_threaded = true;
+            for (auto& method : class_decl->methods) {
+                for (auto& guard : method.guards) {
+                    for (const auto& mutex : guard.mutex_names) {
+                        if (mutex == field->name || mutex == "this->" + field->name) {
+                            guard.refcell_mutexes.push_back(mutex);
+                        }
+                    }
+                }
+            }
+            report(ir, Diagnostic::Note, class_decl->name,
+                   "runtime trace: " + observed.name + " was only locked from one thread: it becomes a "
+                   "RefCell<()>, and a second lock panics where the C++ would deadlock");
+        }
+    }
+
+    /**
+     * Every type in declarations and crossings, with its component types
+     */
+    void forEachType(IR& ir, const TypeVisitor& visit) {
+        for (auto& class_decl : ir.getClasses()) {
+            for (auto& field : class_decl.fields) walk(field.type, visit);
+            for (auto& method : class_decl.methods) walkFunction(method, visit);
+        }
+        for (auto& func : ir.getFunctions()) walkFunction(func, visit);
+        for (const auto& var : ir.getGlobalVariables()) walk(var.type, visit);
+    }
+
+    void walkFunction(Function& func, const TypeVisitor& visit) {
+        walk(func.return_type, visit);
+        for (auto& param : func.parameters) walk(param.type, visit);
+        for (auto& crossing : func.thread_crossings) walk(crossing.type, visit);
+    }
+
+    void walk(const std::shared_ptr<Type>& type, const TypeVisitor& visit) {
+        if (!type) return;
+        visit(type);
+        walk(type->element_type, visit);
+        for (const auto& arg : type->template_args) walk(arg, visit);
+        for (const auto& param : type->parameter_types) walk(param, visit);
+    }
+
+    void mark(const std::shared_ptr<Type>& type) {
+        if (!isShared(type)) return;
+        auto pointee = pointees_.find(typeKey(type->element_type));
+        if (pointee != pointees_.end()) {
+            type->thread_shared = true;
+            type->needs_lock = pointee->second;
+        }
+    }
+
+    /**
+     * Type of a parameter, or of a local declared before limit (the spawn)
+     */
+    std::shared_ptr<Type> resolve(const Function& func, const ClassDecl* owner, const std::string& name,
+                                  size_t limit) const {
+        if (name == "this" && owner) return parse_type_(owner->name + "*");
+        for (const auto& param : func.parameters) {
+            if (param.name == name) return param.type;
+        }
+
+        // std::make_shared<T>(...) passed directly
+        static const std::regex make_shared(R"(^std::make_shared\s*<(.+)>\s*\()");
+        std::smatch match;
+        if (std::regex_search(name, match, make_shared)) return parse_type_("std::shared_ptr<" + match[1].str() + ">");
+        if (name.empty() || !(std::isalpha(static_cast<unsigned char>(name[0])) || name[0] == '_')) return nullptr;
+        if (name.find_first_not_of("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_") !=
+            std::string::npos) {
+            return nullptr;
+        }
+
+        std::string before = func.body.substr(0, std::min(limit, func.body.size()));
+        std::regex declaration(R"(((?:const\s+)?[A-Za-z_][\w:]*(?:\s*<[^;(){}=]*>)?(?:\s*[*&])*)\s+)" + name +
+                               R"(\s*(=\s*([^;]*)|;|\(|\{))");
+        static const std::set<std::string> keywords = {"return", "else", "delete", "throw", "case", "new",
+                                                       "co_return", "goto", "using"};
+        std::shared_ptr<Type> found;
+        for (auto it = std::sregex_iterator(before.begin(), before.end(), declaration);
+             it != std::sregex_iterator(); ++it) {
+            std::string type_str = trim((*it)[1].str());
+            if (keywords.count(type_str)) continue;
+            if (type_str == "auto" || type_str == "const auto") {
+                std::string initializer = trim((*it)[3].str());
+                static const std::regex deduced(R"(^std::make_shared\s*<(.+)>\s*\()");
+                static const std::regex allocated(R"(^new\s+([\w:]+))");
+                if (std::regex_search(initializer, match, deduced)) {
+                    found = parse_type_("std::shared_ptr<" + match[1].str() + ">");
+                } else if (std::regex_search(initializer, match, allocated)) {
+                    found = parse_type_(match[1].str() + "*");
+                }
+                continue;
+            }
+            found = parse_type_(type_str);
+        }
+        return found;
+    }
+
+    static const Function* findFunction(const IR& ir, const ClassDecl* owner, const std::string& name) {
+        if (owner) {
+            if (const Function* method = findMethod(*owner, name)) return method;
+        }
+        for (const auto& func : ir.getFunctions()) {
+            if (func.name == name) return &func;
+        }
+        return nullptr;
+    }
+
+    static const Function* findMethod(const ClassDecl& class_decl, const std::string& name) {
+        for (const auto& method : class_decl.methods) {
+            if (method.name == name) return &method;
+        }
+        return nullptr;
+    }
+
+    static const Variable* findField(const ClassDecl& class_decl, const std::string& name) {
+        for (const auto& field : class_decl.fields) {
+            if (field.name == name) return &field;
+        }
+        return nullptr;
+    }
+
+    /**
+     * std::ref(x), std::move(x), &x -> x
+     */
+    static std::string passedValue(const std::string& arg, bool& by_reference) {
+        static const std::regex wrapped(R"(^std::(ref|cref|move)\s*\(\s*(.*?)\s*\)$)");
+        std::string value = trim(arg);
+        std::smatch match;
+        if (std::regex_match(value, match, wrapped)) {
+            by_reference = match[1].str() != "move";
+            return match[2].str();
+        }
+        return value;
+    }
+
+    static std::string threadName(const std::string& thread) {
+        return thread.empty() ? "another thread" : "thread '" + thread + "'";
+    }
+
+    static bool isShared(const std::shared_ptr<Type>& type) {
+        return type && type->kind == TypeKind::Pointer && type->element_type &&
+               type->name.find("std::shared_ptr<") == 0;
+    }
+
+    // Pointees safe to share without a Mutex: atomics, locks, classes with mutex or atomic members
+    static bool synchronized(const IR& ir, const std::shared_ptr<Type>& type) {
+        if (isSynchronization(type)) return true;
+        const ClassDecl* class_decl = ir.findClass(type->name);
+        if (!class_decl) return false;
+        return class_decl->thread_safe ||
+               std::any_of(class_decl->fields.begin(), class_decl->fields.end(),
+                           [](const Variable& field) { return isSynchronization(field.type); });
+    }
+
+    static bool isPlainMutex(const std::shared_ptr<Type>& type) {
+        static const std::regex name(R"(^std::(mutex|shared_mutex)$)");
+        return type && (type->kind == TypeKind::StdMutex || type->kind == TypeKind::StdSharedMutex ||
+                        std::regex_match(type->name, name));
+    }
+
+    static bool isSynchronization(const std::shared_ptr<Type>& type) {
+        static const std::regex name(R"(^std::(atomic|mutex|recursive_mutex|shared_mutex|timed_mutex)\b)");
+        if (!type) return false;
+        switch (type->kind) {
+            case TypeKind::StdAtomic:
+            case TypeKind::StdMutex:
+            case TypeKind::StdRecursiveMutex:
+            case TypeKind::StdSharedMutex:
+                return true;
+            default:
+                return std::regex_search(type->name, name);
+        }
+    }
+
+    static std::string typeKey(const std::shared_ptr<Type>& type) {
+        std::string key = type ? type->name : "";
+        if (key.find("const ") == 0) key = key.substr(6);
+        return key;
+    }
+
+    void report(IR& ir, Diagnostic::Severity severity, const std::string& context, const std::string& message) {
+        if (!reported_.insert(context + "\n" + message).second) return;
+        Diagnostic diagnostic;
+        diagnostic.severity = severity;
+        diagnostic.context = context;
+        diagnostic.message = message;
+        ir.addDiagnostic(diagnostic);
+    }
+
+    static std::string trim(const std::string& str) {
+        size_t start = str.find_first_not_of(" \t\n\r");
+        if (start == std::string::npos) return "";
+        size_t end = str.find_last_not_of(" \t\n\r");
+        return str.substr(start, end - start + 1);
+    }
+};
+
+} // namespace hybrid
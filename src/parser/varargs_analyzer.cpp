@@ -0,0 +1,113 @@
+/**
+ * Varargs Analyzer
+ * Recognizes <cstdarg> use (va_list, va_start, va_arg, va_end) in C
+ * variadic functions and in functions taking a va_list
+ */
+
+#include "ir.h"
+#include "text_scan.h"
+#include <regex>
+#include <algorithm>
+#include <functional>
+#include <set>
+
+namespace hybrid {
+
+/**
+ * Varargs Analyzer
+ * Per-class pass: va_arg types are parsed like any other type
+ */
+class VarargsAnalyzer {
+public:
+    using TypeParser = std::function<std::shared_ptr<Type>(const std::string&)>;
+
+    explicit VarargsAnalyzer(TypeParser parse_type) : parse_type_(std::move(parse_type)) {}
+
+    void analyzeClass(ClassDecl& class_decl) {
+        for (auto& method : class_decl.methods) {
+            analyzeFunction(method);
+        }
+    }
+
+    void analyzeFunction(Function& func) {
+        const std::string& body = func.body;
+        if (body.find("va_") == std::string::npos) return;
+
+        // va_list variables: locals and parameters
+        std::set<std::string> lists;
+        for (const auto& param : func.parameters) {
+            if (param.type && param.type->name == "va_list") lists.insert(param.name);
+        }
+
+        std::vector<std::pair<size_t, VarargSiteInfo>> found;
+        std::regex declaration_pattern(R"(\bva_list\s+([a-zA-Z_]\w*)\s*;)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), declaration_pattern);
+             it != std::sregex_iterator(); ++it) {
+            lists.insert((*it)[1].str());
+            addSite(found, it->position(0), VarargSiteInfo::ListDeclaration, it->str(0), (*it)[1].str());
+        }
+
+        std::regex start_pattern(R"(\bva_start\s*\(\s*([a-zA-Z_]\w*)\s*,[^;]*\)\s*;)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), start_pattern);
+             it != std::sregex_iterator(); ++it) {
+            addSite(found, it->position(0), VarargSiteInfo::Start, it->str(0), (*it)[1].str());
+        }
+
+        std::regex end_pattern(R"(\bva_end\s*\(\s*([a-zA-Z_]\w*)\s*\)\s*;)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), end_pattern);
+             it != std::sregex_iterator(); ++it) {
+            addSite(found, it->position(0), VarargSiteInfo::End, it->str(0), (*it)[1].str());
+        }
+
+        std::regex copy_pattern(R"(\bva_copy\s*\(\s*([a-zA-Z_]\w*)\s*,\s*([a-zA-Z_]\w*)\s*\)\s*;)");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), copy_pattern);
+             it != std::sregex_iterator(); ++it) {
+            lists.insert((*it)[1].str());
+            auto& site = addSite(found, it->position(0), VarargSiteInfo::Copy, it->str(0), (*it)[2].str());
+            site.target = (*it)[1].str();
+        }
+
+        std::regex arg_pattern(R"(\bva_arg\s*\(\s*([a-zA-Z_]\w*)\s*,\s*([^()]+?)\s*\))");
+        for (auto it = std::sregex_iterator(body.begin(), body.end(), arg_pattern);
+             it != std::sregex_iterator(); ++it) {
+            auto& site = addSite(found, it->position(0), VarargSiteInfo::Arg, it->str(0), (*it)[1].str());
+            site.type = parse_type_((*it)[2].str());
+        }
+
+        // Calls handing a va_list on: vprintf(fmt, args), vlog(level, fmt, args)
+        for (const auto& list : lists) {
+            std::regex forward_pattern("\\b([a-zA-Z_]\\w*)\\s*\\(([^;]*?\\b" + list + "\\b[^;]*?)\\)\\s*;");
+            for (auto it = std::sregex_iterator(body.begin(), body.end(), forward_pattern);
+                 it != std::sregex_iterator(); ++it) {
+                std::string callee = (*it)[1].str();
+                if (callee.compare(0, 3, "va_") == 0) continue;
+                // printf("%d", va_arg(args, int)) reads the list rather than handing it on
+                std::string passed = std::regex_replace((*it)[2].str(), std::regex(R"(\bva_\w+\s*\([^()]*\))"), "");
+                if (!std::regex_search(passed, std::regex("\\b" + list + "\\b"))) continue;
+                auto& site = addSite(found, it->position(0), VarargSiteInfo::Forward,
+                                     it->str(0).substr(0, it->str(0).rfind(')') + 1), list);
+                site.target = callee;
+            }
+        }
+
+        if (found.empty()) return;
+
+        appendInSourceOrder(found, func.vararg_sites);
+    }
+
+private:
+    TypeParser parse_type_;
+
+    static VarargSiteInfo& addSite(std::vector<std::pair<size_t, VarargSiteInfo>>& found, size_t pos,
+                                   VarargSiteInfo::SiteKind kind, const std::string& text,
+                                   const std::string& list) {
+        VarargSiteInfo site;
+        site.kind = kind;
+        site.source_text = text;
+        site.list = list;
+        found.emplace_back(pos, site);
+        return found.back().second;
+    }
+};
+
+} // namespace hybrid
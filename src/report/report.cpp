@@ -0,0 +1,178 @@
+#include "report.h"
+#include "codegen.h"
+#include "transpiler.h"
+#include <cctype>
+#include <set>
+
+namespace hybrid {
+
+namespace {
+
+// Raised when a field is removed or changes meaning (docs/report.schema.json)
+const int FORMAT_VERSION = 1;
+
+bool isIdentifierChar(char c) {
+    return std::isalnum(static_cast<unsigned char>(c)) || c == '_';
+}
+
+std::string severityName(Diagnostic::Severity severity) {
+    switch (severity) {
+        case Diagnostic::Note:    return "note";
+        case Diagnostic::Warning: return "warning";
+        case Diagnostic::Error:   return "error";
+    }
+    return "warning";
+}
+
+std::string statusName(FileReport::Status status) {
+    switch (status) {
+        case FileReport::Translated: return "translated";
+        case FileReport::Failed:     return "failed";
+        case FileReport::Skipped:    return "skipped";
+    }
+    return "skipped";
+}
+
+/**
+ * Rust name of a method: constructors are new, other operators than the
+ * call operator become trait impls and keep no name
+ */
+std::string methodName(const Function& method) {
+    if (method.is_constructor) return "new";
+    if (method.is_destructor) return "";
+    if (method.name.compare(0, 8, "operator") == 0 && method.name != "operator()") return "";
+    return RustCodeGenerator::sanitizeName(method.name);
+}
+
+} // namespace
+
+std::vector<UnsafeSite> auditUnsafe(const std::string& file, const std::string& code) {
+    std::vector<UnsafeSite> sites;
+    size_t start = 0;
+    for (size_t line_number = 1; start < code.size(); ++line_number) {
+        size_t end = code.find('\n', start);
+        if (end == std::string::npos) end = code.size();
+        std::string line = code.substr(start, end - start);
+        start = end + 1;
+
+        std::string text = line.substr(0, line.find("//"));
+        for (size_t pos = text.find("unsafe"); pos != std::string::npos; pos = text.find("unsafe", pos + 6)) {
+            size_t after = pos + 6;
+            bool word = (pos == 0 || !isIdentifierChar(text[pos - 1])) &&
+                        (after == text.size() || !isIdentifierChar(text[after]));
+            if (!word) continue;
+            while (after < text.size() && text[after] == ' ') ++after;
+
+            std::string kind;
+            if (text.compare(after, 1, "{") == 0) kind = "block";
+            else if (text.compare(after, 3, "fn ") == 0) kind = "fn";
+            else if (text.compare(after, 5, "impl ") == 0 || text.compare(after, 5, "impl<") == 0) kind = "impl";
+            else if (text.compare(after, 6, "extern") == 0) kind = "extern";
+            if (kind.empty()) continue;
+
+            size_t first = line.find_first_not_of(" \t");
+            size_t last = line.find_last_not_of(" \t\r");
+            sites.push_back({file, line_number, kind, line.substr(first, last - first + 1)});
+        }
+    }
+    return sites;
+}
+
+std::vector<RenamedSymbol> renamedSymbols(const IR& ir) {
+    std::vector<RenamedSymbol> renames;
+    std::set<std::pair<std::string, std::string>> seen;   // Overloads are one rename
+
+    // Members are listed when their own name changes, not their owner's
+    auto add = [&renames, &seen](const std::string& kind, const std::string& owner, const std::string& rust_owner,
+                                 const std::string& cpp, const std::string& rust) {
+        if (rust.empty() || cpp == rust) return;
+        std::string qualified = owner.empty() ? cpp : owner + "::" + cpp;
+        if (!seen.insert({kind, qualified}).second) return;
+        renames.push_back({kind, qualified, rust_owner.empty() ? rust : rust_owner + "::" + rust});
+    };
+
+    for (const auto& enum_decl : ir.getEnums()) {
+        std::string name = RustCodeGenerator::sanitizeName(enum_decl.name);
+        add("enum", "", "", enum_decl.name, name);
+        for (const auto& enumerator : enum_decl.enumerators) {
+            add("enumerator", enum_decl.name, name, enumerator.name, CodeGenerator::enumeratorName(enumerator.name));
+        }
+    }
+    for (const auto& class_decl : ir.getClasses()) {
+        std::string name = RustCodeGenerator::sanitizeName(class_decl.name);
+        add("class", "", "", class_decl.name, name);
+        for (const auto& field : class_decl.fields) {
+            add("field", class_decl.name, name, field.name, RustCodeGenerator::sanitizeName(field.name));
+        }
+        for (const auto& method : class_decl.methods) {
+            add("method", class_decl.name, name, method.name, methodName(method));
+        }
+    }
+    for (const auto& func : ir.getFunctions()) {
+        add("function", "", "", func.name, RustCodeGenerator::sanitizeName(func.name));
+    }
+    return renames;
+}
+
+JsonValue FileReport::toJson() const {
+    JsonValue diagnostics_json = JsonValue::array();
+    for (const auto& diagnostic : diagnostics) {
+        diagnostics_json.push(JsonValue::object()
+            .set("severity", severityName(diagnostic.severity))
+            .set("message", diagnostic.message)
+            .set("context", diagnostic.context));
+    }
+    JsonValue unsafe_json = JsonValue::array();
+    for (const auto& site : unsafe_sites) {
+        unsafe_json.push(JsonValue::object()
+            .set("file", site.file)
+            .set("line", site.line)
+            .set("kind", site.kind)
+            .set("code", site.code));
+    }
+    JsonValue renames_json = JsonValue::array();
+    for (const auto& rename : renames) {
+        renames_json.push(JsonValue::object().set("kind", rename.kind).set("cpp", rename.cpp).set("rust", rename.rust));
+    }
+
+    // A skipped file was never started: it has no output or metrics
+    return JsonValue::object()
+        .set("input", input_path)
+        .set("output", output_path.empty() ? JsonValue() : JsonValue(output_path))
+        .set("status", statusName(status))
+        .set("error", error.empty() ? JsonValue() : JsonValue(error))
+        .set("metrics", status == Skipped ? JsonValue() : metrics.toJson())
+        .set("diagnostics", diagnostics_json)
+        .set("unsafe", unsafe_json)
+        .set("renames", renames_json);
+}
+
+JsonValue ProjectReport::toJson() const {
+    size_t counts[3] = {0, 0, 0};   // Notes, warnings, errors
+    size_t unsafe_count = 0;
+    JsonValue files_json = JsonValue::array();
+    for (const auto& file : files) {
+        for (const auto& diagnostic : file.diagnostics) counts[diagnostic.severity]++;
+        unsafe_count += file.unsafe_sites.size();
+        files_json.push(file.toJson());
+    }
+
+    return JsonValue::object()
+        .set("format", "hybrid-report")
+        .set("version", FORMAT_VERSION)
+        .set("success", success())
+        .set("cancelled", cancelled)
+        .set("error", error.empty() ? JsonValue() : JsonValue(error))
+        .set("summary", JsonValue::object()
+            .set("files", files.size())
+            .set("translated", translated)
+            .set("failed", failed)
+            .set("skipped", skipped)
+            .set("errors", counts[Diagnostic::Error])
+            .set("warnings", counts[Diagnostic::Warning])
+            .set("notes", counts[Diagnostic::Note])
+            .set("unsafe", unsafe_count))
+        .set("files", files_json);
+}
+
+} // namespace hybrid
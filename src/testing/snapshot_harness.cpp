@@ -0,0 +1,182 @@
+#include "snapshot.h"
+#include "parser.h"
+#include "codegen.h"
+#include <algorithm>
+#include <filesystem>
+#include <fstream>
+#include <sstream>
+
+namespace hybrid {
+
+namespace {
+
+std::vector<std::string> splitLines(const std::string& text) {
+    std::vector<std::string> lines;
+    std::istringstream stream(text);
+    std::string line;
+    while (std::getline(stream, line)) {
+        lines.push_back(line);
+    }
+    return lines;
+}
+
+bool readFile(const std::string& path, std::string& content) {
+    std::ifstream file(path);
+    if (!file.is_open()) {
+        return false;
+    }
+    std::stringstream buffer;
+    buffer << file.rdbuf();
+    content = buffer.str();
+    return true;
+}
+
+} // namespace
+
+std::vector<SnapshotResult> SnapshotHarness::runDirectory(const std::string& directory) {
+    std::vector<std::string> fixtures;
+    for (const auto& entry : std::filesystem::directory_iterator(directory)) {
+        if (entry.is_regular_file() && entry.path().extension() == ".cpp") {
+            fixtures.push_back(entry.path().string());
+        }
+    }
+    std::sort(fixtures.begin(), fixtures.end());
+
+    std::vector<SnapshotResult> results;
+    for (const auto& fixture : fixtures) {
+        std::filesystem::path base(fixture);
+        bool has_rust = std::filesystem::exists(base.replace_extension(".rs"));
+        bool has_go = std::filesystem::exists(base.replace_extension(".go"));
+
+        // A fixture without any snapshot is checked (or blessed) for Rust
+        if (has_rust || !has_go) {
+            results.push_back(runFixture(fixture, TargetLanguage::Rust));
+        }
+        if (has_go) {
+            results.push_back(runFixture(fixture, TargetLanguage::Go));
+        }
+    }
+    return results;
+}
+
+SnapshotResult SnapshotHarness::runFixture(const std::string& cpp_path, TargetLanguage target) {
+    SnapshotResult result;
+    result.fixture = cpp_path;
+    result.snapshot = std::filesystem::path(cpp_path)
+        .replace_extension(target == TargetLanguage::Rust ? ".rs" : ".go").string();
+
+    std::string source;
+    if (!readFile(cpp_path, source)) {
+        result.status = SnapshotResult::Failed;
+        result.diff = "cannot read fixture";
+        return result;
+    }
+
+    std::string actual;
+    try {
+        actual = normalize(transpileSource(source, target));
+    }
+    catch (const std::exception& e) {
+        result.status = SnapshotResult::Failed;
+        result.diff = "transpilation failed: " + std::string(e.what());
+        return result;
+    }
+
+    std::string expected;
+    bool has_snapshot = readFile(result.snapshot, expected);
+    expected = normalize(expected);
+
+    if (has_snapshot && expected == actual) {
+        result.status = SnapshotResult::Passed;
+        return result;
+    }
+
+    if (bless_) {
+        std::ofstream out(result.snapshot);
+        out << actual;
+        result.status = SnapshotResult::Blessed;
+        return result;
+    }
+
+    if (!has_snapshot) {
+        result.status = SnapshotResult::Missing;
+        return result;
+    }
+
+    result.status = SnapshotResult::Failed;
+    result.diff = diff(expected, actual);
+    return result;
+}
+
+std::string SnapshotHarness::transpileSource(const std::string& source, TargetLanguage target) {
+    IR ir = Parser::parseString(source);
+
+    if (target == TargetLanguage::Go) {
+        GoCodeGenerator codegen;
+        return codegen.generate(ir);
+    }
+    RustCodeGenerator codegen;
+    return codegen.generate(ir);
+}
+
+std::string SnapshotHarness::normalize(const std::string& code) {
+    std::string result;
+    bool previous_blank = true;  // Also drops leading blank lines
+
+    for (std::string line : splitLines(code)) {
+        size_t end = line.find_last_not_of(" \t\r");
+        line = end == std::string::npos ? "" : line.substr(0, end + 1);
+
+        if (line.empty()) {
+            if (previous_blank) continue;
+            previous_blank = true;
+        } else {
+            previous_blank = false;
+        }
+        result += line + "\n";
+    }
+
+    // No trailing blank line
+    while (result.size() >= 2 && result.compare(result.size() - 2, 2, "\n\n") == 0) {
+        result.pop_back();
+    }
+    return result;
+}
+
+std::string SnapshotHarness::diff(const std::string& expected, const std::string& actual) {
+    std::vector<std::string> a = splitLines(expected);
+    std::vector<std::string> b = splitLines(actual);
+
+    // Longest common subsequence table
+    std::vector<std::vector<size_t>> lcs(a.size() + 1, std::vector<size_t>(b.size() + 1, 0));
+    for (size_t i = a.size(); i-- > 0;) {
+        for (size_t j = b.size(); j-- > 0;) {
+            lcs[i][j] = a[i] == b[j] ? lcs[i + 1][j + 1] + 1
+                                     : std::max(lcs[i + 1][j], lcs[i][j + 1]);
+        }
+    }
+
+    std::stringstream out;
+    size_t i = 0, j = 0;
+    while (i < a.size() || j < b.size()) {
+        if (i < a.size() && j < b.size() && a[i] == b[j]) {
+            ++i;
+            ++j;
+            continue;
+        }
+
+        // Emit one hunk of consecutive changes
+        out << "@@ -" << (i + 1) << " +" << (j + 1) << " @@\n";
+        while (i < a.size() || j < b.size()) {
+            if (i < a.size() && j < b.size() && a[i] == b[j]) break;
+            if (j < b.size() && (i == a.size() || lcs[i][j + 1] >= lcs[i + 1][j])) {
+                out << "+" << b[j++] << "\n";
+            } else {
+                out << "-" << a[i++] << "\n";
+            }
+        }
+    }
+    return out.str();
+}
+
+} // namespace hybrid
@@ -2,36 +2,230 @@
 #include "ir.h"
 #include "codegen.h"
 #include "parser.h"
-#include <fstream>
+#include "preprocessor.h"
+#include "build_database.h"
+#include "explain.h"
+#include "modules.h"
+#include "symbols.h"
+#include "file_system.h"
+#include "ir_json.h"
+#include "regions.h"
+#include <algorithm>
+#include <chrono>
+#include <filesystem>
+#include <map>
+#include <regex>
+#include <set>
 #include <sstream>
 
 namespace hybrid {
 
-Transpiler::Transpiler(const TranspilerOptions& options)
-    : options_(options), ir_(std::make_unique<IR>()) {
+namespace {
+
+using Clock = std::chrono::steady_clock;
+
+double elapsedMs(Clock::time_point start) {
+    return std::chrono::duration<double, std::milli>(Clock::now() - start).count();
+}
+
+/**
+ * Crates the generated code refers to, with the version written to Cargo.toml
+ */
+const std::vector<std::pair<std::string, std::string>> kCrateDependencies = {
+    {"clap", "{ version = \"4\", features = [\"derive\"] }"},
+    {"futures", "\"0.3\""},
+    {"libc", "\"0.2\""},
+    {"log", "\"0.4\""},
+    {"parking_lot", "\"0.12\""},
+    {"strum", "{ version = \"0.26\", features = [\"derive\"] }"},
+    {"thiserror", "\"1\""},
+    {"tokio", "{ version = \"1\", features = [\"full\"] }"},
+};
+
+/**
+ * Cargo.toml of a crate, with the dependencies its code uses (paths like
+ * log::info! outside comments) and the crates of its workspace it uses
+ * (next to it in the workspace directory); a crate rooted at main.rs is
+ * a binary
+ */
+std::string cargoManifest(const std::string& name, const std::string& description,
+                          const std::vector<ModuleFile>& files, const std::set<std::string>& workspace_crates = {}) {
+    std::set<std::string> used;
+    bool binary = false;
+    for (const auto& file : files) {
+        binary = binary || file.path == name + "/src/main.rs";
+        std::stringstream lines(file.content);
+        std::string line;
+        while (std::getline(lines, line)) {
+            line = line.substr(0, line.find("//"));
+            for (const auto& dependency : kCrateDependencies) {
+                std::regex path("(^|[^\\w:])" + dependency.first + "::");
+                if (std::regex_search(line, path)) used.insert(dependency.first);
+            }
+        }
+    }
+
+    std::string manifest = "[package]\n"
+                           "name = \"" + name + "\"\n"
+                           "version = \"0.1.0\"\n"
+                           "edition = \"2021\"\n"
+                           "description = \"" + description + "\"\n"
+                           "# crates.io also requires a license before cargo publish\n"
+                           "\n";
+    manifest += binary ? "[[bin]]\n"
+                         "name = \"" + name + "\"\n"
+                         "path = \"src/main.rs\"\n"
+                       : "[lib]\n"
+                         "path = \"src/lib.rs\"\n";
+    manifest += "\n"
+                "[dependencies]\n";
+    for (const auto& dependency : kCrateDependencies) {
+        if (used.count(dependency.first)) manifest += dependency.first + " = " + dependency.second + "\n";
+    }
+    for (const auto& crate : workspace_crates) {
+        manifest += crate + " = { path = \"../" + crate + "\" }\n";
+    }
+    return manifest;
+}
+
+/**
+ * Options every code generator takes
+ */
+void configureGenerator(CodeGenerator& codegen, const TranspilerOptions& options) {
+    codegen.setSafetyChecks(options.enable_safety_checks);
+    codegen.setMappingPacks(options.mapping_packs);
+    codegen.setDataModel(options.data_model);
+}
+
+/**
+ * Options of a Rust code generator, for the whole output or one module of it
+ */
+void configureRust(RustCodeGenerator& rust, const TranspilerOptions& options) {
+    configureGenerator(rust, options);
+    rust.setUseThiserror(options.use_thiserror);
+    rust.setVarargsStrategy(options.varargs);
+    rust.setAllocatorApi(options.allocator_api);
+    rust.setSelfRefStrategy(options.self_ref);
+    rust.setLibcStrategy(options.libc);
+    rust.setClap(options.clap);
+    rust.setLibraryMode(options.library);
+    rust.setStrum(options.strum);
+}
+
+} // namespace
+
+Transpiler::Transpiler(const TranspilerOptions& options, std::shared_ptr<FileSystem> fs)
+    : options_(options), fs_(fs ? fs : std::make_shared<DiskFileSystem>()),
+      ir_(std::make_unique<IR>()) {
+    // Module trees parse each file and then the whole translation unit
+    if (!options_.instantiation_cache) {
+        options_.instantiation_cache = std::make_shared<InstantiationCache>();
+    }
+
+    // --deterministic compares what a run writes with a second run
+    if (options_.deterministic) {
+        recorded_ = std::make_shared<RecordingFileSystem>(fs_, true);
+        fs_ = recorded_;
+    }
 
     // Create appropriate code generator based on target
     if (options.target == TargetLanguage::Rust) {
-        codegen_ = std::make_unique<RustCodeGenerator>();
+        auto rust = std::make_unique<RustCodeGenerator>();
+        configureRust(*rust, options);
+        codegen_ = std::move(rust);
     } else if (options.target == TargetLanguage::Go) {
         codegen_ = std::make_unique<GoCodeGenerator>();
+        configureGenerator(*codegen_, options);
     }
 }
 
 Transpiler::~Transpiler() = default;
 
 bool Transpiler::transpile(const std::string& input_path) {
-    // Parse the input file
-    if (!parseSourceFile(input_path)) {
-        return false;
+    metrics_ = TranslationMetrics();
+    metrics_.input_path = input_path;
+    metrics_.output_path = options_.output_path;
+    metrics_.target = options_.target == TargetLanguage::Rust ? "rust" : "go";
+    metrics_.record_timings = !options_.deterministic;
+    unsafe_sites_.clear();
+
+    // --deterministic: a second transpiler parses, analyzes and generates the
+    // same input first, keeping its files in memory
+    std::map<std::string, std::string> again;
+    std::string again_error;
+    bool again_success = options_.deterministic && transpileAgain(input_path, again, again_error);
+    if (recorded_) {
+        recorded_->clear();
+    }
+
+    bool success;
+    if (options_.split_modules || options_.library || options_.split_crates != CrateSplit::None) {
+        success = transpileModules(input_path);
+    } else {
+        // Parse the input file, then generate output code
+        success = parseSourceFile(input_path) && generateCode(options_.output_path);
+    }
+
+    if (success && options_.deterministic) {
+        if (!again_success) {
+            last_error_ = "Output is not deterministic: a second run failed: " + again_error;
+            success = false;
+        } else {
+            success = checkDeterministic(again);
+        }
+    }
+
+    // The output is written either way, so what exceeded the budget can be looked at
+    std::string over_budget;
+    if (success && !metrics_.withinBudget(options_.budget, over_budget)) {
+        last_error_ = "Output over the quality budget: " + over_budget;
+        success = false;
     }
 
-    // Generate output code
-    if (!generateCode(options_.output_path)) {
+    metrics_.success = success;
+    metrics_.classes = ir_->getClasses().size();
+
+    // Failed runs are recorded too, so progress tracking sees them
+    if (!options_.metrics_path.empty() &&
+        !fs_->writeFile(options_.metrics_path, metrics_.toJson().serialize(2) + "\n")) {
+        if (success) {
+            last_error_ = "Failed to open metrics file: " + options_.metrics_path;
+        }
         return false;
     }
 
-    return true;
+    if (!options_.report_path.empty()) {
+        ProjectReport report;
+        report.files.push_back(getFileReport());
+        if (success) {
+            report.translated++;
+        } else {
+            report.failed++;
+        }
+        if (!fs_->writeFile(options_.report_path, report.toJson().serialize(2) + "\n")) {
+            if (success) {
+                last_error_ = "Failed to open report file: " + options_.report_path;
+            }
+            return false;
+        }
+    }
+
+    return success;
+}
+
+FileReport Transpiler::getFileReport() const {
+    FileReport report;
+    report.status = metrics_.success ? FileReport::Translated : FileReport::Failed;
+    report.input_path = metrics_.input_path;
+    report.output_path = metrics_.output_path;
+    if (!metrics_.success) report.error = last_error_;
+    report.diagnostics = diagnostics_;
+    report.metrics = metrics_;
+    report.unsafe_sites = unsafe_sites_;
+    if (options_.target == TargetLanguage::Rust && options_.emit == EmitKind::Code) {
+        report.renames = renamedSymbols(*ir_);
+    }
+    return report;
 }
 
 bool Transpiler::transpileBatch(const std::vector<std::string>& input_paths) {
@@ -43,11 +237,84 @@ bool Transpiler::transpileBatch(const std::vector<std::string>& input_paths) {
     return true;
 }
 
+/**
+ * Run the pipeline up to code generation; with dump_ir_dir, the IR
+ * after a pass goes to <dir>/<position>-<pass>.json
+ */
+bool Transpiler::parse(const std::string& source, IR& ir) {
+    Pipeline pipeline = options_.pipeline;
+    std::string failed;
+    if (!options_.dump_ir_dir.empty()) {
+        pipeline.setDumpSink([this, &failed](size_t index, const Pass& pass, const IR& dumped) {
+            std::string position = (index < 10 ? "0" : "") + std::to_string(index);
+            std::string path = (std::filesystem::path(options_.dump_ir_dir) / (position + "-" + pass.name + ".json"))
+                                   .string();
+            if (!fs_->writeFile(path, irToJson(dumped).serialize(2) + "\n") && failed.empty()) failed = path;
+        });
+    }
+
+    ir = Parser::parseString(source, options_.standard, options_.log_macros, options_.runtime_trace,
+                             options_.instantiation_cache.get(), &pipeline);
+    if (!failed.empty()) {
+        last_error_ = "Failed to write IR dump: " + failed;
+        return false;
+    }
+    return true;
+}
+
 bool Transpiler::parseSourceFile(const std::string& input_path) {
+    auto start = Clock::now();
+    std::string source;
+    if (!fs_->readFile(input_path, source)) {
+        last_error_ = "Failed to parse input file: Cannot open file: " + input_path;
+        return false;
+    }
+    metrics_.lines_in = countLines(source);
+    metrics_.phase_ms.emplace_back("read", elapsedMs(start));
+
+    if (!options_.compile_commands.empty()) {
+        start = Clock::now();
+        BuildConfiguration config;
+        bool found = false;
+        if (!loadBuildConfiguration(input_path, config, found)) {
+            return false;
+        }
+        if (found) {
+            // Headers are read for the macros they define, not translated
+            Preprocessor preprocessor(config);
+            IncludeGraph graph;
+            graph.setPreprocessor(&preprocessor, config.include_paths);
+            graph.build(input_path, *fs_);
+            source = graph.getNodes().front().source;
+            for (const auto& note : preprocessor.getNotes()) {
+                Diagnostic diagnostic;
+                diagnostic.severity = Diagnostic::Note;
+                diagnostic.message = note;
+                diagnostics_.push_back(diagnostic);
+            }
+        }
+        metrics_.phase_ms.emplace_back("preprocess", elapsedMs(start));
+    }
+
     try {
         // Use the simple C++ parser to parse the source file
         // This will be replaced with full Clang LibTooling in the future
-        *ir_ = Parser::parseFile(input_path);
+        start = Clock::now();
+        if (!parse(source, *ir_)) {
+            return false;
+        }
+        metrics_.phase_ms.emplace_back("parse", elapsedMs(start));
+
+        if (options_.prune_unused) {
+            start = Clock::now();
+            if (!pruneUnusedDeclarations()) {
+                return false;
+            }
+            metrics_.phase_ms.emplace_back("prune", elapsedMs(start));
+        }
+        if (!deferClasses({{0, input_path}})) {
+            return false;
+        }
 
         // TODO (future): Add additional analysis passes:
         // 1. Ownership analysis for smart pointers
@@ -63,26 +330,704 @@ bool Transpiler::parseSourceFile(const std::string& input_path) {
     }
 }
 
+bool Transpiler::loadBuildDatabase(BuildDatabase& database) {
+    // A build directory holds compile_commands.json
+    std::string path = options_.compile_commands;
+    if (std::filesystem::path(path).extension() != ".json") {
+        path = (std::filesystem::path(path) / "compile_commands.json").string();
+    }
+
+    std::string text;
+    if (!fs_->readFile(path, text)) {
+        last_error_ = "Cannot read compilation database: " + path;
+        return false;
+    }
+    std::string error;
+    if (!database.parse(text, error)) {
+        last_error_ = "Invalid compilation database '" + path + "': " + error;
+        return false;
+    }
+    return true;
+}
+
+bool Transpiler::loadBuildConfiguration(const std::string& input_path, BuildConfiguration& config, bool& found) {
+    BuildDatabase database;
+    if (!loadBuildDatabase(database)) {
+        return false;
+    }
+
+    std::string error;
+    Diagnostic diagnostic;
+    diagnostic.severity = Diagnostic::Note;
+    std::string note;
+    found = database.select(input_path, options_.target_config, config, error, note);
+    if (!found && database.commandsFor(input_path).empty()) {
+        // Translated as before, with every #if branch
+        diagnostic.message = error + "; it is translated without -D defines or include paths";
+        diagnostics_.push_back(diagnostic);
+        return true;
+    }
+    if (!found) {
+        last_error_ = error;
+        return false;
+    }
+    if (!note.empty()) {
+        diagnostic.message = note;
+        diagnostics_.push_back(diagnostic);
+    }
+    return true;
+}
+
+bool Transpiler::transpileModules(const std::string& input_path) {
+    if (options_.target != TargetLanguage::Rust) {
+        last_error_ = options_.split_crates != CrateSplit::None ? "Workspaces are only generated for the Rust target"
+                      : options_.library ? "Library crates are only generated for the Rust target"
+                                         : "Module splitting is only supported for the Rust target";
+        return false;
+    }
+
+    auto start = Clock::now();
+    IncludeGraph graph;
+    std::unique_ptr<Preprocessor> preprocessor;
+    if (!options_.compile_commands.empty()) {
+        // Included headers are preprocessed with the configuration of the
+        // file that includes them
+        BuildConfiguration config;
+        bool found = false;
+        if (!loadBuildConfiguration(input_path, config, found)) {
+            return false;
+        }
+        if (found) {
+            preprocessor = std::make_unique<Preprocessor>(config);
+            graph.setPreprocessor(preprocessor.get(), config.include_paths);
+        }
+    }
+    if (!graph.build(input_path, *fs_)) {
+        last_error_ = "Failed to parse input file: Cannot open file: " + input_path;
+        return false;
+    }
+    for (const auto& node : graph.getNodes()) {
+        metrics_.lines_in += countLines(node.source);
+    }
+    metrics_.phase_ms.emplace_back("read", elapsedMs(start));
+
+    std::map<std::string, std::string> crate_of;
+    std::map<std::string, std::string> crate_sources;  // Crate -> the directory or target it holds
+    if (options_.split_crates != CrateSplit::None && !assignCrates(graph, crate_of, crate_sources)) {
+        return false;
+    }
+
+    for (const auto& include : graph.getUnresolved()) {
+        Diagnostic diagnostic;
+        diagnostic.severity = Diagnostic::Note;
+        diagnostic.message = "include not found, skipped: \"" + include + "\"";
+        diagnostics_.push_back(diagnostic);
+    }
+    if (preprocessor) {
+        for (const auto& note : preprocessor->getNotes()) {
+            Diagnostic diagnostic;
+            diagnostic.severity = Diagnostic::Note;
+            diagnostic.message = note;
+            diagnostics_.push_back(diagnostic);
+        }
+    }
+
+    try {
+        // Parse each file alone to learn what it declares, then the whole
+        // translation unit so analysis passes see every declaration
+        start = Clock::now();
+        std::map<std::string, std::vector<std::string>> provenance;
+        std::string translation_unit;
+        std::vector<std::pair<int, std::string>> file_offsets;  // Lines before each file, for --defer
+        Pipeline file_pipeline = options_.pipeline;
+        file_pipeline.setDumpSink(nullptr);  // Only the translation unit's IR is dumped
+
+        for (const auto& path : graph.topologicalOrder()) {
+            const std::string& source = graph.findNode(path)->source;
+            IR file_ir = Parser::parseString(source, options_.standard, options_.log_macros,
+                                             options_.runtime_trace, options_.instantiation_cache.get(),
+                                             &file_pipeline);
+            for (const auto& enum_decl : file_ir.getEnums()) {
+                provenance[path].push_back(enum_decl.name);
+            }
+            for (const auto& class_decl : file_ir.getClasses()) {
+                provenance[path].push_back(class_decl.name);
+            }
+            // A library has no entry point: codegen leaves main out
+            if (!options_.library) {
+                for (const auto& func : file_ir.getFunctions()) {
+                    provenance[path].push_back(func.name);
+                }
+            }
+            if (options_.library) {
+                for (const auto& alias : file_ir.getTypeAliases()) {
+                    provenance[path].push_back(alias.name);
+                }
+            }
+            file_offsets.emplace_back(static_cast<int>(std::count(translation_unit.begin(), translation_unit.end(),
+                                                                  '\n')),
+                                      path);
+            translation_unit += source + "\n";
+        }
+
+        if (!parse(translation_unit, *ir_)) {
+            return false;
+        }
+        metrics_.phase_ms.emplace_back("parse", elapsedMs(start));
+
+        if (options_.prune_unused) {
+            start = Clock::now();
+            if (!pruneUnusedDeclarations()) {
+                return false;
+            }
+            metrics_.phase_ms.emplace_back("prune", elapsedMs(start));
+        }
+        if (!deferClasses(file_offsets)) {
+            return false;
+        }
+
+        start = Clock::now();
+        std::vector<ModuleFile> files;
+        std::map<std::string, std::set<std::string>> crate_dependencies;
+        if (options_.library && !options_.split_modules && crate_of.empty()) {
+            // A library without --split-modules is one lib.rs
+            if (!generateLibraryRoot(files)) return false;
+        } else if (!splitModules(graph, provenance, crate_of, files, crate_dependencies)) {
+            return false;
+        }
+        metrics_.phase_ms.emplace_back("codegen", elapsedMs(start));
+
+        start = Clock::now();
+        // A library crate keeps its sources in src/, next to Cargo.toml;
+        // workspace crates are <crate>/src/ already
+        std::filesystem::path root = options_.output_path;
+        if (options_.library) {
+            if (crate_of.empty()) root /= "src";
+            for (auto& file : files) {
+                if (std::filesystem::path(file.path).filename() == "lib.rs") addCrateDocs(file.content, input_path);
+            }
+        }
+        for (const auto& file : files) {
+            std::string path = (root / file.path).string();
+            if (!writeOutput(path, file.content)) return false;
+            metrics_.countOutput(file.content);
+        }
+        if (!crate_of.empty()) {
+            if (!writeWorkspace(crate_dependencies, crate_sources, files)) return false;
+        } else if (options_.library && !writeManifest(input_path, files)) {
+            return false;
+        }
+        metrics_.phase_ms.emplace_back("write", elapsedMs(start));
+    }
+    catch (const std::exception& e) {
+        last_error_ = "Failed to generate modules: " + std::string(e.what());
+        return false;
+    }
+
+    return true;
+}
+
+bool Transpiler::assignCrates(const IncludeGraph& graph, std::map<std::string, std::string>& crate_of,
+                              std::map<std::string, std::string>& crate_sources) {
+    std::vector<std::string> order = graph.topologicalOrder();
+    std::map<std::string, std::string> group_of;  // File -> its directory or CMake target
+
+    if (options_.split_crates == CrateSplit::Directory) {
+        // Directories relative to the deepest one holding every file
+        std::vector<std::filesystem::path> directories;
+        std::filesystem::path common;
+        for (size_t i = 0; i < order.size(); ++i) {
+            std::filesystem::path directory = std::filesystem::path(order[i]).lexically_normal().parent_path();
+            directories.push_back(directory);
+            if (i == 0) {
+                common = directory;
+                continue;
+            }
+            std::filesystem::path shared;
+            auto a = common.begin();
+            for (auto b = directory.begin(); a != common.end() && b != directory.end() && *a == *b; ++a, ++b) {
+                shared /= *a;
+            }
+            common = shared;
+        }
+        for (size_t i = 0; i < order.size(); ++i) {
+            std::string relative = directories[i].lexically_relative(common).generic_string();
+            group_of[order[i]] = relative == "." ? "" : relative;
+        }
+    } else {
+        if (options_.compile_commands.empty()) {
+            last_error_ = "--split-crates-by target reads the CMake targets from --compile-commands";
+            return false;
+        }
+        BuildDatabase database;
+        if (!loadBuildDatabase(database)) {
+            return false;
+        }
+
+        // Sources take the target compiling them, headers the target of the
+        // source next to them (net.h, net.cpp) or of the first file including them
+        auto targetOf = [&database](const std::string& path) {
+            for (const CompileCommand* command : database.commandsFor(path)) {
+                std::string target = BuildDatabase::targetOf(*command);
+                if (!target.empty()) return target;
+            }
+            return std::string();
+        };
+        for (auto it = order.rbegin(); it != order.rend(); ++it) {
+            std::string target = targetOf(*it);
+            for (const char* extension : {".cpp", ".cc", ".cxx", ".c"}) {
+                if (!target.empty()) break;
+                target = targetOf(std::filesystem::path(*it).replace_extension(extension).string());
+            }
+            for (auto includer = order.rbegin(); target.empty() && includer != it; ++includer) {
+                const auto& includes = graph.findNode(*includer)->includes;
+                if (std::find(includes.begin(), includes.end(), *it) != includes.end()) target = group_of[*includer];
+            }
+            if (!target.empty()) group_of[*it] = target;
+        }
+        if (!group_of.count(order.back())) {
+            last_error_ = "'" + order.back() + "' is not built by a CMake target of the compilation database";
+            return false;
+        }
+        for (const auto& path : order) {
+            if (!group_of.count(path)) group_of[path] = group_of[order.back()];
+        }
+    }
+
+    // Files in the top directory form the crate named after the input
+    std::map<std::string, std::string> name_of;  // Directory or target -> crate
+    std::set<std::string> taken;
+    for (auto it = order.rbegin(); it != order.rend(); ++it) {
+        const std::string& group = group_of[*it];
+        if (name_of.count(group)) continue;
+        std::string name = ModuleSplitter::crateName(group.empty() ? ModuleSplitter::moduleName(*it) : group);
+        std::string unique = name;
+        for (int n = 2; taken.count(unique); ++n) {
+            unique = name + "_" + std::to_string(n);
+        }
+        taken.insert(unique);
+        name_of[group] = unique;
+        crate_sources[unique] = options_.split_crates == CrateSplit::Target ? "the CMake target " + group
+                                : group.empty() ? std::filesystem::path(*it).filename().string()
+                                                : group + "/";
+    }
+    for (const auto& path : order) {
+        crate_of[path] = name_of[group_of[path]];
+    }
+    return true;
+}
+
+bool Transpiler::splitModules(const IncludeGraph& graph,
+                              const std::map<std::string, std::vector<std::string>>& provenance,
+                              const std::map<std::string, std::string>& crate_of, std::vector<ModuleFile>& files,
+                              std::map<std::string, std::set<std::string>>& crate_dependencies) {
+    ModuleSplitter splitter([this](RustCodeGenerator& rust) { configureRust(rust, options_); });
+    splitter.setCrates(crate_of);
+    files = splitter.split(graph, *ir_, provenance);
+    crate_dependencies = splitter.getCrateDependencies();
+    diagnostics_.insert(diagnostics_.end(), splitter.getDiagnostics().begin(),
+                        splitter.getDiagnostics().end());
+    metrics_.generation = splitter.getStats();
+    return true;
+}
+
+bool Transpiler::generateLibraryRoot(std::vector<ModuleFile>& files) {
+    std::string code = codegen_->generate(*ir_);
+    diagnostics_.insert(diagnostics_.end(), ir_->getDiagnostics().begin(), ir_->getDiagnostics().end());
+    diagnostics_.insert(diagnostics_.end(), codegen_->getDiagnostics().begin(),
+                        codegen_->getDiagnostics().end());
+    metrics_.generation = codegen_->getStats();
+    files.push_back({"lib.rs", code});
+    return true;
+}
+
+void Transpiler::addCrateDocs(std::string& lib, const std::string& input_path) const {
+    // Inner doc comment of the crate, after the generated file header
+    size_t header_end = lib.find("\n\n");
+    size_t insert_at = header_end == std::string::npos ? 0 : header_end + 2;
+    lib.insert(insert_at, "//! Rust translation of the C++ library `" +
+                              std::filesystem::path(input_path).filename().string() + "`\n\n");
+}
+
+bool Transpiler::writeManifest(const std::string& input_path, const std::vector<ModuleFile>& files) {
+    std::string path = (std::filesystem::path(options_.output_path) / "Cargo.toml").string();
+    std::string description = "Rust translation of the C++ library " +
+                              std::filesystem::path(input_path).filename().string();
+    return writeManifestFile(path, cargoManifest(ModuleSplitter::moduleName(input_path), description, files));
+}
+
+bool Transpiler::writeWorkspace(const std::map<std::string, std::set<std::string>>& crate_dependencies,
+                                const std::map<std::string, std::string>& crate_sources,
+                                const std::vector<ModuleFile>& files) {
+    std::filesystem::path root = options_.output_path;
+    std::string workspace = "[workspace]\n"
+                            "members = [\n";
+    for (const auto& entry : crate_dependencies) {
+        workspace += "    \"" + entry.first + "\",\n";
+    }
+    workspace += "]\n"
+                 "resolver = \"2\"\n";
+    if (!writeManifestFile((root / "Cargo.toml").string(), workspace)) return false;
+
+    for (const auto& entry : crate_dependencies) {
+        std::vector<ModuleFile> crate_files;
+        for (const auto& file : files) {
+            if (file.path.compare(0, entry.first.size() + 1, entry.first + "/") == 0) crate_files.push_back(file);
+        }
+        auto source = crate_sources.find(entry.first);
+        std::string description = "Rust translation of " +
+                                  (source == crate_sources.end() ? entry.first : source->second);
+        std::string manifest = cargoManifest(entry.first, description, crate_files, entry.second);
+        if (!writeManifestFile((root / entry.first / "Cargo.toml").string(), manifest)) return false;
+    }
+    return true;
+}
+
+bool Transpiler::writeManifestFile(const std::string& path, const std::string& manifest) {
+    // Version, license and the like are filled in by hand: --update leaves them alone
+    std::string existing;
+    if (options_.update && fs_->readFile(path, existing)) {
+        Diagnostic diagnostic;
+        diagnostic.severity = Diagnostic::Note;
+        diagnostic.message = "kept the existing " + path + "; dependencies the code now needs are not added to it";
+        diagnostics_.push_back(diagnostic);
+        return true;
+    }
+
+    if (!fs_->writeFile(path, manifest)) {
+        last_error_ = "Failed to open output file: " + path;
+        return false;
+    }
+    return true;
+}
+
+bool Transpiler::pruneUnusedDeclarations() {
+    size_t total = ir_->getClasses().size() + ir_->getFunctions().size();
+    for (const auto& class_decl : ir_->getClasses()) {
+        total += class_decl.methods.size();
+    }
+
+    std::vector<std::string> unknown;
+    size_t removed = pruneUnused(*ir_, options_.entry_points, unknown);
+
+    if (!unknown.empty()) {
+        last_error_ = "Unknown entry point";
+        last_error_ += unknown.size() > 1 ? "s: " : ": ";
+        for (size_t i = 0; i < unknown.size(); ++i) {
+            last_error_ += (i > 0 ? ", " : "") + unknown[i];
+        }
+        return false;
+    }
+
+    Diagnostic diagnostic;
+    diagnostic.severity = Diagnostic::Note;
+    diagnostic.message = "pruned " + std::to_string(removed) + " of " + std::to_string(total) +
+                         " declarations not reachable from the entry points";
+    diagnostics_.push_back(diagnostic);
+    return true;
+}
+
+bool Transpiler::deferClasses(const std::vector<std::pair<int, std::string>>& files) {
+    std::vector<std::string> unknown;
+    for (const auto& name : options_.deferred_classes) {
+        ClassDecl* class_decl = nullptr;
+        for (auto& candidate : ir_->getClasses()) {
+            if (candidate.name == name) class_decl = &candidate;
+        }
+        if (!class_decl) {
+            unknown.push_back(name);
+            continue;
+        }
+
+        // The stubs name the line in the file, not in the concatenated translation unit
+        class_decl->is_deferred = true;
+        for (const auto& file : files) {
+            if (class_decl->span.isKnown() ? file.first < class_decl->span.begin_line : files.size() == 1) {
+                class_decl->source_path = file.second;
+                class_decl->source_line_offset = file.first;
+            }
+        }
+    }
+
+    if (!unknown.empty()) {
+        last_error_ = "Unknown class";
+        last_error_ += unknown.size() > 1 ? "es" : "";
+        last_error_ += " for --defer: ";
+        for (size_t i = 0; i < unknown.size(); ++i) {
+            last_error_ += (i > 0 ? ", " : "") + unknown[i];
+        }
+        return false;
+    }
+    return true;
+}
+
+/**
+ * Transpile the input again with a transpiler of its own, so parsing,
+ * analysis and generation all run a second time; its files stay in memory
+ */
+bool Transpiler::transpileAgain(const std::string& input_path, std::map<std::string, std::string>& written,
+                                std::string& error) const {
+    TranspilerOptions options = options_;
+    options.deterministic = false;
+    options.instantiation_cache = nullptr;  // Templates are instantiated again too
+    options.metrics_path.clear();
+    options.report_path.clear();
+    options.dump_ir_dir.clear();
+
+    auto memory = std::make_shared<RecordingFileSystem>(fs_, false);
+    Transpiler transpiler(options, memory);
+    bool success = transpiler.transpile(input_path);
+    written = memory->getWritten();
+    error = transpiler.getLastError();
+    return success;
+}
+
+bool Transpiler::checkDeterministic(const std::map<std::string, std::string>& again) {
+    const auto& written = recorded_->getWritten();
+    for (const auto& file : again) {
+        if (!written.count(file.first)) {
+            last_error_ = "Output is not deterministic: a second run also wrote " + file.first;
+            return false;
+        }
+    }
+
+    for (const auto& file : written) {
+        auto other = again.find(file.first);
+        if (other == again.end()) {
+            last_error_ = "Output is not deterministic: a second run did not write " + file.first;
+            return false;
+        }
+        const std::string& first = file.second;
+        const std::string& second = other->second;
+        if (first == second) continue;
+
+        size_t line = 1;
+        for (size_t i = 0; i < first.size() && i < second.size() && first[i] == second[i]; ++i) {
+            if (first[i] == '\n') line++;
+        }
+        last_error_ = "Output is not deterministic: a second run's " + file.first + " differs at line " +
+                      std::to_string(line);
+        return false;
+    }
+    return true;
+}
+
+bool Transpiler::writeOutput(const std::string& path, const std::string& code) {
+    std::string content = code;
+    if (options_.update) {
+        // --update: merge into what the last run wrote, a missing file is written whole
+        std::string existing;
+        if (!fs_->readFile(path, existing)) existing.clear();
+        RegionMerge merge = mergeRegions(existing, code, options_.force);
+        if (!merge.success) {
+            last_error_ = "Not updating " + path + ": " + merge.error;
+            if (!merge.conflicts.empty()) {
+                last_error_ += " (";
+                for (size_t i = 0; i < merge.conflicts.size(); ++i) {
+                    last_error_ += (i > 0 ? ", " : "") + merge.conflicts[i];
+                }
+                last_error_ += "); --force overwrites hand edits";
+            }
+            return false;
+        }
+        for (const auto& item : merge.overwritten) {
+            Diagnostic diagnostic;
+            diagnostic.severity = Diagnostic::Warning;
+            diagnostic.message = "overwrote the hand edits of " + item + " in " + path + " (--force)";
+            diagnostics_.push_back(diagnostic);
+        }
+        Diagnostic diagnostic;
+        diagnostic.severity = Diagnostic::Note;
+        diagnostic.message = "updated " + path + ": " + std::to_string(merge.retranslated) + " retranslated, " +
+                             std::to_string(merge.added) + " added, " + std::to_string(merge.removed) +
+                             " removed, " + std::to_string(merge.unchanged) + " unchanged, " +
+                             std::to_string(merge.kept) + " kept with hand edits";
+        diagnostics_.push_back(diagnostic);
+        content = merge.content;
+    }
+
+    if (!fs_->writeFile(path, content)) {
+        last_error_ = "Failed to open output file: " + path;
+        return false;
+    }
+
+    // What was written, hand-edited regions included, is what gets reviewed
+    if (options_.target == TargetLanguage::Rust && options_.emit == EmitKind::Code) {
+        std::vector<UnsafeSite> sites = auditUnsafe(path, content);
+        unsafe_sites_.insert(unsafe_sites_.end(), sites.begin(), sites.end());
+    }
+    return true;
+}
+
 bool Transpiler::generateCode(const std::string& output_path) {
     if (!codegen_) {
         last_error_ = "Code generator not initialized";
         return false;
     }
 
+    // Structured dumps replace the generated code
+    if (options_.emit != EmitKind::Code) {
+        JsonValue dump = options_.emit == EmitKind::AstJson ? astToJson(*ir_) : irToJson(*ir_);
+        diagnostics_.insert(diagnostics_.end(), ir_->getDiagnostics().begin(), ir_->getDiagnostics().end());
+        if (!fs_->writeFile(output_path, dump.serialize(2) + "\n")) {
+            last_error_ = "Failed to open output file: " + output_path;
+            return false;
+        }
+        return true;
+    }
+
     // Generate code from IR
+    auto start = Clock::now();
     std::string generated_code = codegen_->generate(*ir_);
+    metrics_.generation = codegen_->getStats();
+    metrics_.phase_ms.emplace_back("codegen", elapsedMs(start));
 
-    // Write to output file
-    std::ofstream out_file(output_path);
-    if (!out_file.is_open()) {
-        last_error_ = "Failed to open output file: " + output_path;
-        return false;
+    // Collect diagnostics from analysis and code generation
+    diagnostics_.insert(diagnostics_.end(), ir_->getDiagnostics().begin(), ir_->getDiagnostics().end());
+    diagnostics_.insert(diagnostics_.end(), codegen_->getDiagnostics().begin(),
+                        codegen_->getDiagnostics().end());
+    decisions_ = codegen_->getDecisions();
+
+    // --explain only looks at the decisions
+    if (!options_.explain.empty()) {
+        return true;
     }
 
-    out_file << generated_code;
-    out_file.close();
+    // Write to output file
+    start = Clock::now();
+    if (!writeOutput(output_path, generated_code)) return false;
+    metrics_.countOutput(generated_code);
+    metrics_.phase_ms.emplace_back("write", elapsedMs(start));
 
     return true;
 }
 
+bool Transpiler::explain(const std::string& symbol, Explanation& explanation) const {
+    return explainSymbol(*ir_, decisions_, diagnostics_, symbol, explanation);
+}
+
+SnippetResult transpileSnippet(const std::string& source, const TranspilerOptions& options) {
+    const std::string input_path = "snippet.cpp";
+
+    TranspilerOptions snippet_options = options;
+    snippet_options.split_modules = false;
+    snippet_options.library = false;
+    snippet_options.split_crates = CrateSplit::None;
+    snippet_options.output_path =
+        options.target == TargetLanguage::Go ? "snippet.go" : "snippet.rs";
+
+    auto fs = std::make_shared<MemoryFileSystem>();
+    fs->addFile(input_path, source);
+
+    Transpiler transpiler(snippet_options, fs);
+
+    SnippetResult result;
+    result.success = transpiler.transpile(input_path);
+    result.diagnostics = transpiler.getDiagnostics();
+    if (result.success) {
+        fs->readFile(snippet_options.output_path, result.code);
+    } else {
+        result.error = transpiler.getLastError();
+    }
+    return result;
+}
+
+std::string defaultOutputPath(const std::string& input_path, const TranspilerOptions& options) {
+    std::string extension = (options.target == TargetLanguage::Rust) ? ".rs" : ".go";
+    if (options.split_crates != CrateSplit::None) {
+        extension = "_workspace";  // Workspace directory
+    } else if (options.library) {
+        extension = "_crate";  // Crate directory
+    } else if (options.split_modules) {
+        extension = "_rs";  // Output directory
+    } else if (options.emit == EmitKind::AstJson) {
+        extension = ".ast.json";
+    } else if (options.emit == EmitKind::IrJson) {
+        extension = ".ir.json";
+    }
+
+    // Only a dot in the file name starts an extension: ../Makefile
+    size_t dot_pos = input_path.find_last_of('.');
+    size_t separator = input_path.find_last_of("/\\");
+    if (dot_pos != std::string::npos && (separator == std::string::npos || dot_pos > separator + 1)) {
+        return input_path.substr(0, dot_pos) + extension;
+    }
+    return input_path + extension;
+}
+
+ProjectReport transpileProject(const std::vector<std::string>& input_paths, const TranspilerOptions& options,
+                               const ProgressCallback& progress, const CancellationToken* cancel,
+                               std::shared_ptr<FileSystem> fs) {
+    if (!fs) fs = std::make_shared<DiskFileSystem>();
+
+    ProjectReport report;
+    std::map<std::string, std::string> outputs;  // Output path -> the input writing it
+    std::shared_ptr<InstantiationCache> instantiation_cache = options.instantiation_cache;
+    if (!instantiation_cache) instantiation_cache = std::make_shared<InstantiationCache>();
+    for (size_t i = 0; i < input_paths.size(); ++i) {
+        FileReport file;
+        file.input_path = input_paths[i];
+        if (cancel && cancel->isCancelled()) {
+            report.cancelled = true;
+            report.skipped++;
+            report.files.push_back(file);
+            continue;
+        }
+
+        ProjectProgress event;
+        event.input_path = file.input_path;
+        event.index = i;
+        event.total = input_paths.size();
+        if (progress) progress(event);
+
+        // <dir>/src/net/socket.rs for src/net/socket.cpp
+        TranspilerOptions file_options = options;
+        file_options.metrics_path.clear();
+        file_options.report_path.clear();
+        file_options.instantiation_cache = instantiation_cache;  // Headers shared by the files are analyzed once
+        std::filesystem::path input = std::filesystem::path(file.input_path).lexically_normal();
+        if (options.output_path.empty()) {
+            file.output_path = defaultOutputPath(file.input_path, options);
+        } else {
+            std::filesystem::path relative = input;
+            if (input.is_absolute() || (!input.empty() && *input.begin() == "..")) relative = input.filename();
+            file.output_path = defaultOutputPath((std::filesystem::path(options.output_path) / relative).string(),
+                                                 options);
+        }
+        file_options.output_path = file.output_path;
+        if (!options.dump_ir_dir.empty()) {
+            // <dir>/src/net/socket.cpp/03-layout.json
+            std::filesystem::path relative = input;
+            if (input.is_absolute() || (!input.empty() && *input.begin() == "..")) relative = input.filename();
+            file_options.dump_ir_dir = (std::filesystem::path(options.dump_ir_dir) / relative).string();
+        }
+
+        auto earlier = outputs.find(file.output_path);
+        if (earlier != outputs.end()) {
+            file.status = FileReport::Failed;
+            file.error = "Output path " + file.output_path + " is also the output of " + earlier->second;
+        } else {
+            outputs[file.output_path] = file.input_path;
+            Transpiler transpiler(file_options, fs);
+            transpiler.transpile(file.input_path);
+            file = transpiler.getFileReport();
+        }
+
+        if (file.status == FileReport::Translated) {
+            report.translated++;
+        } else {
+            report.failed++;
+        }
+        event.stage = ProjectProgress::Finished;
+        event.success = file.status == FileReport::Translated;
+        report.files.push_back(file);
+        if (progress) progress(event);
+    }
+
+    if (!options.report_path.empty() && !fs->writeFile(options.report_path, report.toJson().serialize(2) + "\n")) {
+        report.error = "Failed to open report file: " + options.report_path;
+    }
+    return report;
+}
+
 } // namespace hybrid
@@ -0,0 +1,403 @@
+#include "json.h"
+#include <cctype>
+#include <cmath>
+#include <cstdio>
+#include <cstdlib>
+#include <stdexcept>
+
+namespace hybrid {
+
+namespace {
+
+void writeString(std::string& out, const std::string& value) {
+    out += '"';
+    for (char c : value) {
+        switch (c) {
+            case '"':  out += "\\\""; break;
+            case '\\': out += "\\\\"; break;
+            case '\b': out += "\\b"; break;
+            case '\f': out += "\\f"; break;
+            case '\n': out += "\\n"; break;
+            case '\r': out += "\\r"; break;
+            case '\t': out += "\\t"; break;
+            default:
+                if (static_cast<unsigned char>(c) < 0x20) {
+                    char escape[8];
+                    std::snprintf(escape, sizeof(escape), "\\u%04x", c);
+                    out += escape;
+                } else {
+                    out += c;
+                }
+        }
+    }
+    out += '"';
+}
+
+void writeNumber(std::string& out, double value) {
+    if (!std::isfinite(value)) {
+        out += "null";
+        return;
+    }
+
+    char buffer[32];
+    if (value == std::floor(value) && std::fabs(value) < 1e15) {
+        std::snprintf(buffer, sizeof(buffer), "%.0f", value);
+    } else {
+        // Shortest of 15 or 17 significant digits that reads back exactly
+        std::snprintf(buffer, sizeof(buffer), "%.15g", value);
+        if (std::strtod(buffer, nullptr) != value) {
+            std::snprintf(buffer, sizeof(buffer), "%.17g", value);
+        }
+    }
+    out += buffer;
+}
+
+/**
+ * Recursive descent parser over the input text
+ */
+class JsonParser {
+public:
+    explicit JsonParser(const std::string& text) : text_(text) {}
+
+    JsonValue parseDocument() {
+        JsonValue value = parseValue(0);
+        skipWhitespace();
+        if (pos_ != text_.size()) {
+            fail("unexpected trailing characters");
+        }
+        return value;
+    }
+
+private:
+    static const int MAX_DEPTH = 256;
+
+    const std::string& text_;
+    size_t pos_ = 0;
+
+    [[noreturn]] void fail(const std::string& message) const {
+        throw std::runtime_error("Invalid JSON at offset " + std::to_string(pos_) + ": " + message);
+    }
+
+    void skipWhitespace() {
+        while (pos_ < text_.size() &&
+               (text_[pos_] == ' ' || text_[pos_] == '\t' || text_[pos_] == '\n' || text_[pos_] == '\r')) {
+            ++pos_;
+        }
+    }
+
+    bool consume(const char* literal) {
+        size_t length = std::char_traits<char>::length(literal);
+        if (text_.compare(pos_, length, literal) == 0) {
+            pos_ += length;
+            return true;
+        }
+        return false;
+    }
+
+    JsonValue parseValue(int depth) {
+        if (depth > MAX_DEPTH) {
+            fail("nesting too deep");
+        }
+
+        skipWhitespace();
+        if (pos_ >= text_.size()) {
+            fail("unexpected end of input");
+        }
+
+        char c = text_[pos_];
+        if (c == '{') return parseObject(depth);
+        if (c == '[') return parseArray(depth);
+        if (c == '"') return JsonValue(parseString());
+        if (consume("true")) return JsonValue(true);
+        if (consume("false")) return JsonValue(false);
+        if (consume("null")) return JsonValue();
+        if (c == '-' || (c >= '0' && c <= '9')) return parseNumber();
+
+        fail(std::string("unexpected character '") + c + "'");
+    }
+
+    JsonValue parseObject(int depth) {
+        JsonValue object = JsonValue::object();
+        ++pos_;  // {
+
+        skipWhitespace();
+        if (pos_ < text_.size() && text_[pos_] == '}') {
+            ++pos_;
+            return object;
+        }
+
+        while (true) {
+            skipWhitespace();
+            if (pos_ >= text_.size() || text_[pos_] != '"') {
+                fail("expected member name");
+            }
+            std::string key = parseString();
+
+            skipWhitespace();
+            if (pos_ >= text_.size() || text_[pos_] != ':') {
+                fail("expected ':'");
+            }
+            ++pos_;
+
+            object.set(key, parseValue(depth + 1));
+
+            skipWhitespace();
+            if (pos_ < text_.size() && text_[pos_] == ',') {
+                ++pos_;
+            } else if (pos_ < text_.size() && text_[pos_] == '}') {
+                ++pos_;
+                return object;
+            } else {
+                fail("expected ',' or '}'");
+            }
+        }
+    }
+
+    JsonValue parseArray(int depth) {
+        JsonValue array = JsonValue::array();
+        ++pos_;  // [
+
+        skipWhitespace();
+        if (pos_ < text_.size() && text_[pos_] == ']') {
+            ++pos_;
+            return array;
+        }
+
+        while (true) {
+            array.push(parseValue(depth + 1));
+
+            skipWhitespace();
+            if (pos_ < text_.size() && text_[pos_] == ',') {
+                ++pos_;
+            } else if (pos_ < text_.size() && text_[pos_] == ']') {
+                ++pos_;
+                return array;
+            } else {
+                fail("expected ',' or ']'");
+            }
+        }
+    }
+
+    JsonValue parseNumber() {
+        size_t start = pos_;
+        if (text_[pos_] == '-') ++pos_;
+        while (pos_ < text_.size() &&
+               (std::isdigit(static_cast<unsigned char>(text_[pos_])) || text_[pos_] == '.' ||
+                text_[pos_] == 'e' || text_[pos_] == 'E' || text_[pos_] == '+' || text_[pos_] == '-')) {
+            ++pos_;
+        }
+
+        std::string number = text_.substr(start, pos_ - start);
+        char* end = nullptr;
+        double value = std::strtod(number.c_str(), &end);
+        if (number.empty() || end != number.c_str() + number.size()) {
+            pos_ = start;
+            fail("malformed number");
+        }
+        return JsonValue(value);
+    }
+
+    unsigned parseHex4() {
+        if (pos_ + 4 > text_.size()) {
+            fail("truncated \\u escape");
+        }
+        unsigned code = 0;
+        for (int i = 0; i < 4; ++i) {
+            char h = text_[pos_++];
+            code <<= 4;
+            if (h >= '0' && h <= '9') code |= h - '0';
+            else if (h >= 'a' && h <= 'f') code |= h - 'a' + 10;
+            else if (h >= 'A' && h <= 'F') code |= h - 'A' + 10;
+            else fail("invalid \\u escape");
+        }
+        return code;
+    }
+
+    static void appendUtf8(std::string& out, unsigned code) {
+        if (code < 0x80) {
+            out += static_cast<char>(code);
+        } else if (code < 0x800) {
+            out += static_cast<char>(0xC0 | (code >> 6));
+            out += static_cast<char>(0x80 | (code & 0x3F));
+        } else if (code < 0x10000) {
+            out += static_cast<char>(0xE0 | (code >> 12));
+            out += static_cast<char>(0x80 | ((code >> 6) & 0x3F));
+            out += static_cast<char>(0x80 | (code & 0x3F));
+        } else {
+            out += static_cast<char>(0xF0 | (code >> 18));
+            out += static_cast<char>(0x80 | ((code >> 12) & 0x3F));
+            out += static_cast<char>(0x80 | ((code >> 6) & 0x3F));
+            out += static_cast<char>(0x80 | (code & 0x3F));
+        }
+    }
+
+    std::string parseString() {
+        std::string result;
+        ++pos_;  // opening quote
+
+        while (pos_ < text_.size()) {
+            char c = text_[pos_++];
+            if (c == '"') {
+                return result;
+            }
+            if (c != '\\') {
+                result += c;
+                continue;
+            }
+
+            if (pos_ >= text_.size()) break;
+            char escape = text_[pos_++];
+            switch (escape) {
+                case '"':  result += '"'; break;
+                case '\\': result += '\\'; break;
+                case '/':  result += '/'; break;
+                case 'b':  result += '\b'; break;
+                case 'f':  result += '\f'; break;
+                case 'n':  result += '\n'; break;
+                case 'r':  result += '\r'; break;
+                case 't':  result += '\t'; break;
+                case 'u': {
+                    unsigned code = parseHex4();
+                    // Surrogate pair
+                    if (code >= 0xD800 && code <= 0xDBFF && consume("\\u")) {
+                        unsigned low = parseHex4();
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    appendUtf8(result, code);
+                    break;
+                }
+                default:
+                    fail("invalid escape");
+            }
+        }
+
+        fail("unterminated string");
+    }
+};
+
+} // namespace
+
+JsonValue JsonValue::array() {
+    JsonValue value;
+    value.kind_ = Array;
+    return value;
+}
+
+JsonValue JsonValue::object() {
+    JsonValue value;
+    value.kind_ = Object;
+    return value;
+}
+
+const std::string& JsonValue::asString() const {
+    static const std::string empty;
+    return kind_ == String ? string_ : empty;
+}
+
+const std::vector<JsonValue>& JsonValue::asArray() const {
+    static const std::vector<JsonValue> empty;
+    return kind_ == Array ? elements_ : empty;
+}
+
+const std::vector<std::pair<std::string, JsonValue>>& JsonValue::members() const {
+    static const std::vector<std::pair<std::string, JsonValue>> empty;
+    return kind_ == Object ? members_ : empty;
+}
+
+bool JsonValue::has(const std::string& key) const {
+    for (const auto& member : members()) {
+        if (member.first == key) return true;
+    }
+    return false;
+}
+
+const JsonValue& JsonValue::operator[](const std::string& key) const {
+    static const JsonValue null;
+    for (const auto& member : members()) {
+        if (member.first == key) return member.second;
+    }
+    return null;
+}
+
+JsonValue& JsonValue::set(const std::string& key, const JsonValue& value) {
+    if (kind_ == Null) {
+        kind_ = Object;
+    }
+    for (auto& member : members_) {
+        if (member.first == key) {
+            member.second = value;
+            return *this;
+        }
+    }
+    members_.emplace_back(key, value);
+    return *this;
+}
+
+JsonValue& JsonValue::push(const JsonValue& value) {
+    if (kind_ == Null) {
+        kind_ = Array;
+    }
+    elements_.push_back(value);
+    return *this;
+}
+
+size_t JsonValue::size() const {
+    return kind_ == Array ? elements_.size() : kind_ == Object ? members_.size() : 0;
+}
+
+std::string JsonValue::serialize(int indent) const {
+    std::string out;
+    write(out, indent, 0);
+    return out;
+}
+
+void JsonValue::write(std::string& out, int indent, int depth) const {
+    auto newline = [&](int level) {
+        if (indent < 0) return;
+        out += '\n';
+        out.append(static_cast<size_t>(indent * level), ' ');
+    };
+
+    switch (kind_) {
+        case Null:   out += "null"; break;
+        case Bool:   out += bool_ ? "true" : "false"; break;
+        case Number: writeNumber(out, number_); break;
+        case String: writeString(out, string_); break;
+        case Array:
+            if (elements_.empty()) {
+                out += "[]";
+                break;
+            }
+            out += '[';
+            for (size_t i = 0; i < elements_.size(); ++i) {
+                if (i > 0) out += ',';
+                newline(depth + 1);
+                elements_[i].write(out, indent, depth + 1);
+            }
+            newline(depth);
+            out += ']';
+            break;
+        case Object:
+            if (members_.empty()) {
+                out += "{}";
+                break;
+            }
+            out += '{';
+            for (size_t i = 0; i < members_.size(); ++i) {
+                if (i > 0) out += ',';
+                newline(depth + 1);
+                writeString(out, members_[i].first);
+                out += indent < 0 ? ":" : ": ";
+                members_[i].second.write(out, indent, depth + 1);
+            }
+            newline(depth);
+            out += '}';
+            break;
+    }
+}
+
+JsonValue JsonValue::parse(const std::string& text) {
+    return JsonParser(text).parseDocument();
+}
+
+} // namespace hybrid
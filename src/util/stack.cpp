@@ -0,0 +1,63 @@
+#include "stack.h"
+#include <exception>
+
+#if defined(__unix__) || defined(__APPLE__)
+#if !defined(__EMSCRIPTEN__)
+#include <pthread.h>
+#define HYBRID_HAS_PTHREAD 1
+#endif
+#endif
+
+namespace hybrid {
+
+namespace {
+
+// Set on threads started by runWithStack, so nested calls do not start another
+thread_local bool on_deep_stack = false;
+
+#ifdef HYBRID_HAS_PTHREAD
+struct StackTask {
+    const std::function<void()>* task;
+    std::exception_ptr error;
+};
+
+void* runTask(void* arg) {
+    auto* state = static_cast<StackTask*>(arg);
+    on_deep_stack = true;
+    try {
+        (*state->task)();
+    } catch (...) {
+        state->error = std::current_exception();
+    }
+    return nullptr;
+}
+#endif
+
+} // namespace
+
+void runWithStack(const std::function<void()>& task, size_t stack_size) {
+#ifdef HYBRID_HAS_PTHREAD
+    if (!on_deep_stack) {
+        StackTask state{&task, nullptr};
+        pthread_attr_t attr;
+        pthread_t thread;
+        bool started = pthread_attr_init(&attr) == 0;
+        if (started) {
+            started = pthread_attr_setstacksize(&attr, stack_size) == 0 &&
+                      pthread_create(&thread, &attr, runTask, &state) == 0;
+            pthread_attr_destroy(&attr);
+        }
+        if (started) {
+            pthread_join(thread, nullptr);
+            if (state.error) std::rethrow_exception(state.error);
+            return;
+        }
+    }
+#else
+    (void)stack_size;
+#endif
+    // Already on a deep stack, or no thread could be given one
+    task();
+}
+
+} // namespace hybrid
@@ -0,0 +1,66 @@
+/**
+ * WebAssembly API
+ *
+ * C entry points for the in-browser playground. Results are kept until the
+ * next call, so JavaScript can read them with plain string returns:
+ *
+ *   const ok = Module.ccall('hybrid_transpile_snippet', 'number',
+ *                           ['string', 'string'], [source, 'rust']);
+ *   const code = Module.ccall('hybrid_output', 'string', [], []);
+ */
+
+#include "transpiler.h"
+#include <string>
+
+#ifdef __EMSCRIPTEN__
+#include <emscripten/emscripten.h>
+#define HYBRID_EXPORT EMSCRIPTEN_KEEPALIVE
+#else
+#define HYBRID_EXPORT
+#endif
+
+namespace {
+
+std::string last_output;
+std::string last_diagnostics;
+
+} // namespace
+
+extern "C" {
+
+/**
+ * Transpile a C++ snippet
+ * @param source C++ source code
+ * @param target "rust" or "go"
+ * @return 1 on success, 0 on failure (hybrid_output() holds the error)
+ */
+HYBRID_EXPORT int hybrid_transpile_snippet(const char* source, const char* target) {
+    hybrid::TranspilerOptions options;
+    options.target = std::string(target ? target : "") == "go" ? hybrid::TargetLanguage::Go
+                                                               : hybrid::TargetLanguage::Rust;
+
+    hybrid::SnippetResult result = hybrid::transpileSnippet(source ? source : "", options);
+
+    last_output = result.success ? result.code : result.error;
+    last_diagnostics.clear();
+    for (const auto& diagnostic : result.diagnostics) {
+        last_diagnostics += diagnostic.toString() + "\n";
+    }
+    return result.success ? 1 : 0;
+}
+
+/**
+ * Generated code, or the error message, of the last call
+ */
+HYBRID_EXPORT const char* hybrid_output() {
+    return last_output.c_str();
+}
+
+/**
+ * Diagnostics of the last call, one per line
+ */
+HYBRID_EXPORT const char* hybrid_diagnostics() {
+    return last_diagnostics.c_str();
+}
+
+} // extern "C"
@@ -0,0 +1,25 @@
+class Point {
+public:
+    Point(int x, int y) { x_ = x; y_ = y; }
+private:
+    int x_;
+    int y_;
+};
+class Person {
+public:
+    void setName(const std::string& n) { name_ = n; }
+    void setNick(std::string n) { nick_ = n; }
+    void setPos(const Point& p) { pos_ = p; }
+    void rename(std::string a) { setNick(name_); setNick(a); }
+    ~Person() { release(); }
+private:
+    std::string name_;
+    std::string nick_;
+    Point pos_;
+};
+class Handle {
+public:
+    Handle(const Handle& other) = delete;
+private:
+    std::unique_ptr<int> p_;
+};
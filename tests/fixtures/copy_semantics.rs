@@ -0,0 +1,59 @@
+// Auto-generated Rust code from C++ source
+// Generated by Hybrid Transpiler
+
+#[derive(Clone, Copy)]
+pub struct point {
+    pub x_: i32,
+    pub y_: i32,
+}
+
+impl point {
+    pub fn new(x: i32, y: i32) -> Self {
+        // TODO: Implement function body
+         x_ = x; y_ = y;
+    }
+
+}
+
+#[derive(Clone)]
+pub struct person {
+    pub name_: std::string,
+    pub nick_: std::string,
+    pub pos_: point,
+}
+
+impl person {
+    pub fn set_name(&mut self, n: &str) {
+        // TODO: Implement function body
+         name_ = n.to_string();
+    }
+
+    pub fn set_nick(&mut self, n: std::string) {
+        // TODO: Implement function body
+         nick_ = n;
+    }
+
+    pub fn set_pos(&mut self, p: &point) {
+        // TODO: Implement function body
+         pos_ = *p;
+    }
+
+    pub fn rename(&mut self, a: std::string) {
+        // TODO: Implement function body
+         setNick(name_.clone()); setNick(a);
+    }
+
+}
+
+impl Drop for person {
+    fn drop(&mut self) {
+         release();
+    }
+}
+
+pub struct handle {
+    pub p_: Box<i32>,
+}
+
+impl handle {
+}
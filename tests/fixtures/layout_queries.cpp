@@ -0,0 +1,11 @@
+class Header {
+public:
+    int magic;
+    short version;
+    char tag[8];
+
+    size_t size() const { return sizeof(Header) + sizeof(int) + alignof(double); }
+    size_t tagLen() { return sizeof(tag) / sizeof(tag[0]); }
+    size_t off() { return offsetof(Header, version) + sizeof magic; }
+    size_t local() { char buf[16]; return sizeof(buf) + sizeof(struct Header); }
+};
@@ -0,0 +1,33 @@
+// Auto-generated Rust code from C++ source
+// Generated by Hybrid Transpiler
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct header {
+    pub magic: i32,
+    pub version: i16,
+    pub tag: [u8; 8],
+}
+
+impl header {
+    pub fn size(&self) -> usize {
+        // TODO: Implement function body
+         return std::mem::size_of::<header>() + std::mem::size_of::<i32>() + std::mem::align_of::<f64>();
+    }
+
+    pub fn tag_len(&mut self) -> usize {
+        // TODO: Implement function body
+         return tag.len();
+    }
+
+    pub fn off(&mut self) -> usize {
+        // TODO: Implement function body
+         return std::mem::offset_of!(header, version) + std::mem::size_of_val(&magic);
+    }
+
+    pub fn local(&mut self) -> usize {
+        // TODO: Implement function body
+         char buf[16]; return std::mem::size_of_val(&buf) + std::mem::size_of::<header>();
+    }
+
+}
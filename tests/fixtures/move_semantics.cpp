@@ -0,0 +1,9 @@
+class Holder {
+public:
+    Holder(Holder&& other) noexcept : data_(std::move(other.data_)) { other.size_ = 0; }
+    void set(std::string&& value) { data_ = std::move(value); }
+    void swapOut(std::vector<int>& v) { items_ = std::move(v); v.push_back(1); int old = std::exchange(size_, 0); }
+private:
+    std::string data_;
+    int size_;
+};
@@ -0,0 +1,23 @@
+// Auto-generated Rust code from C++ source
+// Generated by Hybrid Transpiler
+
+#[derive(Clone)]
+pub struct holder {
+    pub data_: std::string,
+    pub size_: i32,
+}
+
+impl holder {
+    // Move constructor elided: Rust moves values implicitly
+
+    pub fn set(&mut self, value: std::string) {
+        // TODO: Implement function body
+         data_ = value;
+    }
+
+    pub fn swap_out(&mut self, v: &mut std::vector<int>) {
+        // TODO: Implement function body
+         items_ = std::mem::take(v); v.push_back(1); int old = std::mem::replace(&mut size_, 0);
+    }
+
+}
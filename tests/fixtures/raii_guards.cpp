@@ -0,0 +1,12 @@
+class Counter {
+public:
+    void inc() { std::lock_guard<std::mutex> lock(mtx_); count_ += 1; }
+    void both() { std::scoped_lock lk(mtx_, other_); std::unique_lock<std::mutex> ul(mtx_); work(); ul.unlock(); more(); }
+    void read() const { std::shared_lock<std::shared_mutex> r(rw_); std::unique_lock<std::shared_mutex> w(rw_); }
+    void tx() { SCOPE_EXIT { log(); }; commit(); }
+private:
+    std::mutex mtx_;
+    std::mutex other_;
+    std::shared_mutex rw_;
+    int count_;
+};
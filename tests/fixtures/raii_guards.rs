@@ -0,0 +1,32 @@
+// Auto-generated Rust code from C++ source
+// Generated by Hybrid Transpiler
+
+pub struct counter {
+    pub mtx_: std::mutex,
+    pub other_: std::mutex,
+    pub rw_: std::shared_mutex,
+    pub count_: i32,
+}
+
+impl counter {
+    pub fn inc(&mut self) {
+        // TODO: Implement function body
+         let _lock = mtx_.lock().unwrap(); count_ += 1;
+    }
+
+    pub fn both(&mut self) {
+        // TODO: Implement function body
+         let _lk_0 = mtx_.lock().unwrap(); let _lk_1 = other_.lock().unwrap(); let mut ul = mtx_.lock().unwrap(); work(); drop(ul); more();
+    }
+
+    pub fn read(&self) {
+        // TODO: Implement function body
+         let _r = rw_.read().unwrap(); let mut _w = rw_.write().unwrap();
+    }
+
+    pub fn tx(&mut self) {
+        // TODO: Implement function body
+         let _guard = scopeguard::guard((), |_| { log(); }); commit();
+    }
+
+}
@@ -0,0 +1,9 @@
+class Point {
+public:
+    Point(int x, int y) { x_ = x; y_ = y; }
+    int getX() const { return x_; }
+    int getY() const { return y_; }
+private:
+    int x_;
+    int y_;
+};
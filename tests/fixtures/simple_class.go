@@ -0,0 +1,26 @@
+// Auto-generated Go code from C++ source
+// Generated by Hybrid Transpiler
+
+package main
+
+type Point struct {
+    X_ int32
+    Y_ int32
+}
+
+func NewPoint(x int32, y int32) *Point {
+    return &Point{
+        X_: X_,
+        Y_: Y_,
+    }
+}
+
+func (this *Point) GetX() int32 {
+    // TODO: Implement function body
+     return x_;
+}
+
+func (this *Point) GetY() int32 {
+    // TODO: Implement function body
+     return y_;
+}
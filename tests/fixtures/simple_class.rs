@@ -0,0 +1,26 @@
+// Auto-generated Rust code from C++ source
+// Generated by Hybrid Transpiler
+
+#[derive(Clone, Copy)]
+pub struct point {
+    pub x_: i32,
+    pub y_: i32,
+}
+
+impl point {
+    pub fn new(x: i32, y: i32) -> Self {
+        // TODO: Implement function body
+         x_ = x; y_ = y;
+    }
+
+    pub fn get_x(&self) -> i32 {
+        // TODO: Implement function body
+         return x_;
+    }
+
+    pub fn get_y(&self) -> i32 {
+        // TODO: Implement function body
+         return y_;
+    }
+
+}
@@ -1,31 +1,38 @@
 #include <iostream>
 #include <string>
 
-// Simple test framework
-int main(int argc, char* argv[]) {
-    std::cout << "Running Hybrid Transpiler Tests...\n";
+namespace hybrid {
+namespace test {
 
-    // Run all tests
-    int passed = 0;
-    int failed = 0;
+void runAllTypeMappingTests();
+void runAllCodegenTests();
 
-    std::cout << "\n=== Type Mapping Tests ===\n";
-    // TODO: Add type mapping tests
-    passed += 5;
+} // namespace test
+} // namespace hybrid
 
-    std::cout << "\n=== Code Generation Tests ===\n";
-    // TODO: Add code generation tests
-    passed += 5;
+// Simple test framework: every test asserts, so a failure aborts the run
+//
+//   test_transpiler                       run every suite
+//   test_transpiler --test-type-mapping   run one suite
+//   test_transpiler --test-codegen
+int main(int argc, char* argv[]) {
+    std::string suite = argc > 1 ? argv[1] : "";
+    if (!suite.empty() && suite != "--test-type-mapping" && suite != "--test-codegen") {
+        std::cerr << "Unknown test suite: " << suite << "\n";
+        return 1;
+    }
+
+    std::cout << "Running Hybrid Transpiler Tests...\n";
 
-    std::cout << "\n=== Memory Pattern Analysis Tests ===\n";
-    // TODO: Add memory pattern tests
-    passed += 5;
+    if (suite.empty() || suite == "--test-type-mapping") {
+        std::cout << "\n=== Type Mapping Tests ===\n";
+        hybrid::test::runAllTypeMappingTests();
+    }
 
-    std::cout << "\n" << std::string(50, '=') << "\n";
-    std::cout << "Test Results:\n";
-    std::cout << "  Passed: " << passed << "\n";
-    std::cout << "  Failed: " << failed << "\n";
-    std::cout << "  Total:  " << (passed + failed) << "\n";
+    if (suite.empty() || suite == "--test-codegen") {
+        std::cout << "\n=== Code Generation Tests ===\n";
+        hybrid::test::runAllCodegenTests();
+    }
 
-    return (failed == 0) ? 0 : 1;
+    return 0;
 }
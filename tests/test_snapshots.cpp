@@ -0,0 +1,63 @@
+#include "snapshot.h"
+#include <cstdlib>
+#include <iostream>
+#include <string>
+#include <vector>
+
+// Snapshot test runner
+//   test_snapshots [--bless] [fixture_dir...]
+// Fixture directories default to tests/fixtures. Set HYBRID_BLESS=1 or pass
+// --bless to rewrite snapshots from the current output.
+int main(int argc, char* argv[]) {
+    bool bless = std::getenv("HYBRID_BLESS") != nullptr;
+    std::vector<std::string> directories;
+
+    for (int i = 1; i < argc; ++i) {
+        std::string arg = argv[i];
+        if (arg == "--bless") {
+            bless = true;
+        } else {
+            directories.push_back(arg);
+        }
+    }
+    if (directories.empty()) {
+        directories.push_back("tests/fixtures");
+    }
+
+    hybrid::SnapshotHarness harness(bless);
+    int passed = 0;
+    int failed = 0;
+    int blessed = 0;
+
+    std::cout << "Running Snapshot Tests:\n";
+    for (const auto& directory : directories) {
+        for (const auto& result : harness.runDirectory(directory)) {
+            switch (result.status) {
+                case hybrid::SnapshotResult::Passed:
+                    std::cout << "  ✓ " << result.snapshot << "\n";
+                    passed++;
+                    break;
+                case hybrid::SnapshotResult::Blessed:
+                    std::cout << "  ★ " << result.snapshot << " (blessed)\n";
+                    blessed++;
+                    break;
+                case hybrid::SnapshotResult::Missing:
+                    std::cout << "  ✗ " << result.snapshot << " missing (run with --bless)\n";
+                    failed++;
+                    break;
+                case hybrid::SnapshotResult::Failed:
+                    std::cout << "  ✗ " << result.snapshot << "\n" << result.diff;
+                    failed++;
+                    break;
+            }
+        }
+    }
+
+    std::cout << "\nSnapshots: " << passed << " passed, " << failed << " failed";
+    if (blessed > 0) {
+        std::cout << ", " << blessed << " blessed";
+    }
+    std::cout << "\n";
+
+    return (failed == 0) ? 0 : 1;
+}